@@ -0,0 +1,314 @@
+//! zip and tar member listing and extraction, without touching the filesystem
+
+use std::io;
+
+/// Archive member
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    /// path inside the archive
+    pub name: String,
+    /// uncompressed size in bytes
+    pub size: u64,
+    /// where the member's data lives
+    location: Location,
+}
+
+/// Position and encoding of member data inside the archive
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Location {
+    /// zip member: local header offset, compression method and compressed size
+    Zip {
+        header: usize,
+        method: u16,
+        compressed: usize,
+    },
+    /// tar member: data offset
+    Tar { data: usize },
+}
+
+/// Supported archive layouts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// zip archive, stored or deflated members
+    Zip,
+    /// ustar, gnu or pax tar archive
+    Tar,
+    /// gzip compressed tar archive
+    TarGz,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn u16_le(data: &[u8], at: usize) -> io::Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("truncated zip header"))
+}
+
+fn u32_le(data: &[u8], at: usize) -> io::Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated zip header"))
+}
+
+/// Detect the archive layout from its leading bytes.
+pub fn detect(data: &[u8]) -> Option<ArchiveKind> {
+    if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        Some(ArchiveKind::Zip)
+    } else if data.starts_with(&[0x1f, 0x8b]) {
+        Some(ArchiveKind::TarGz)
+    } else if data.get(257..262) == Some(b"ustar") {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+/// An archive loaded into memory
+#[derive(Clone, Debug)]
+pub struct Archive {
+    /// archive bytes, decompressed for tar.gz
+    data: Vec<u8>,
+    /// the archive is a tar.gz, its bytes decompressed
+    gzipped: bool,
+    /// members in archive order
+    pub members: Vec<Member>,
+}
+
+/// Archive implementation
+impl Archive {
+    /// Parse an archive, detecting zip, tar and tar.gz layouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - whole archive contents.
+    pub fn parse(data: Vec<u8>) -> io::Result<Archive> {
+        match detect(&data) {
+            Some(ArchiveKind::Zip) => Ok(Archive {
+                members: zip_members(&data)?,
+                data,
+                gzipped: false,
+            }),
+            Some(ArchiveKind::TarGz) => {
+                let data = gunzip(&data)?;
+                Ok(Archive {
+                    members: tar_members(&data)?,
+                    data,
+                    gzipped: true,
+                })
+            }
+            Some(ArchiveKind::Tar) => Ok(Archive {
+                members: tar_members(&data)?,
+                data,
+                gzipped: false,
+            }),
+            None => Err(invalid("input is not a zip, tar or tar.gz archive")),
+        }
+    }
+
+    /// The named member.
+    fn member(&self, name: &str) -> io::Result<&Member> {
+        self.members.iter().find(|m| m.name == name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no member {name:?} in archive"),
+            )
+        })
+    }
+
+    /// Offset of the data of a zip member, after its local header.
+    fn zip_data(&self, header: usize) -> io::Result<usize> {
+        if u32_le(&self.data, header)? != 0x0403_4b50 {
+            return Err(invalid("bad zip local header signature"));
+        }
+        Ok(header
+            + 30
+            + u16_le(&self.data, header + 26)? as usize
+            + u16_le(&self.data, header + 28)? as usize)
+    }
+
+    /// Archive offset of the data of the named member, and whether it's
+    /// compressed there; a tar.gz member is in the gzip stream at 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - path inside the archive.
+    pub fn source(&self, name: &str) -> io::Result<(u64, bool)> {
+        let member = self.member(name)?;
+        Ok(match member.location {
+            Location::Tar { .. } if self.gzipped => (0, true),
+            Location::Tar { data } => (data as u64, false),
+            Location::Zip { header, method, .. } => (self.zip_data(header)? as u64, method != 0),
+        })
+    }
+
+    /// Uncompressed contents of the named member.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - path inside the archive.
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let member = self.member(name)?;
+        match member.location {
+            Location::Tar { data } => Ok(self.data[data..data + member.size as usize].to_vec()),
+            Location::Zip {
+                header,
+                method,
+                compressed,
+            } => {
+                let start = self.zip_data(header)?;
+                let raw = self
+                    .data
+                    .get(start..start + compressed)
+                    .ok_or_else(|| invalid("truncated zip member"))?;
+                match method {
+                    0 => Ok(raw.to_vec()),
+                    8 => miniz_oxide::inflate::decompress_to_vec(raw)
+                        .map_err(|e| invalid(format!("deflate error: {e:?}"))),
+                    m => Err(invalid(format!("unsupported zip compression method {m}"))),
+                }
+            }
+        }
+    }
+}
+
+/// Members listed in the zip central directory.
+fn zip_members(data: &[u8]) -> io::Result<Vec<Member>> {
+    // the end of central directory record sits in the last 64 KiB + 22 bytes
+    let search_from = data.len().saturating_sub(0xffff + 22);
+    let eocd = (search_from..data.len().saturating_sub(21))
+        .rev()
+        .find(|&i| data[i..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(|| invalid("zip end of central directory not found"))?;
+    let entries = u16_le(data, eocd + 10)? as usize;
+    let mut at = u32_le(data, eocd + 16)? as usize;
+
+    let mut members = Vec::with_capacity(entries);
+    for _ in 0..entries {
+        if u32_le(data, at)? != 0x0201_4b50 {
+            return Err(invalid("bad zip central directory signature"));
+        }
+        let method = u16_le(data, at + 10)?;
+        let compressed = u32_le(data, at + 20)?;
+        let size = u32_le(data, at + 24)?;
+        let name_len = u16_le(data, at + 28)? as usize;
+        let extra_len = u16_le(data, at + 30)? as usize;
+        let comment_len = u16_le(data, at + 32)? as usize;
+        let header = u32_le(data, at + 42)?;
+        if compressed == u32::MAX || size == u32::MAX || header == u32::MAX {
+            return Err(invalid("zip64 archives are not supported"));
+        }
+        let name = data
+            .get(at + 46..at + 46 + name_len)
+            .ok_or_else(|| invalid("truncated zip member name"))?;
+        members.push(Member {
+            name: String::from_utf8_lossy(name).into_owned(),
+            size: size as u64,
+            location: Location::Zip {
+                header: header as usize,
+                method,
+                compressed: compressed as usize,
+            },
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(members)
+}
+
+/// Parse a tar numeric field, octal text or gnu base-256.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|b| b & 0x80 != 0) {
+        return Ok(field[1..]
+            .iter()
+            .fold(0u64, |n, b| (n << 8) | u64::from(*b)));
+    }
+    let text = String::from_utf8_lossy(field);
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    match text.is_empty() {
+        true => Ok(0),
+        false => u64::from_str_radix(text, 8).map_err(|_| invalid("bad tar numeric field")),
+    }
+}
+
+/// NUL terminated tar string field.
+fn tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Regular file members of a tar archive.
+fn tar_members(data: &[u8]) -> io::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut at = 0;
+    let mut long_name: Option<String> = None;
+    while at + 512 <= data.len() {
+        let header = &data[at..at + 512];
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let size = tar_number(&header[124..136])?;
+        let body = at + 512;
+        let end = body + size as usize;
+        if end > data.len() {
+            return Err(invalid("truncated tar member"));
+        }
+        let mut name = tar_string(&header[0..100]);
+        if &header[257..262] == b"ustar" {
+            let prefix = tar_string(&header[345..500]);
+            if !prefix.is_empty() {
+                name = format!("{prefix}/{name}");
+            }
+        }
+        match header[156] {
+            // gnu long name for the next member
+            b'L' => long_name = Some(tar_string(&data[body..end])),
+            // pax extended header, only the path record matters here
+            b'x' => {
+                let records = String::from_utf8_lossy(&data[body..end]);
+                long_name = records
+                    .lines()
+                    .filter_map(|record| record.split_once(' ').map(|(_, kv)| kv))
+                    .find_map(|kv| kv.strip_prefix("path="))
+                    .map(str::to_string);
+            }
+            b'0' | b'\0' | b'7' => members.push(Member {
+                name: long_name.take().unwrap_or(name),
+                size,
+                location: Location::Tar { data: body },
+            }),
+            _ => long_name = None,
+        }
+        at = body + (size as usize).div_ceil(512) * 512;
+    }
+    Ok(members)
+}
+
+/// Decompress a gzip stream.
+fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 18 || data[2] != 8 {
+        return Err(invalid("unsupported gzip stream"));
+    }
+    let flags = data[3];
+    let mut at = 10;
+    if flags & 0x04 != 0 {
+        at += 2 + u16_le(data, at)? as usize;
+    }
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            at += data[at..]
+                .iter()
+                .position(|b| *b == 0)
+                .ok_or_else(|| invalid("truncated gzip header"))?
+                + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        at += 2;
+    }
+    let body = data
+        .get(at..data.len() - 8)
+        .ok_or_else(|| invalid("truncated gzip stream"))?;
+    miniz_oxide::inflate::decompress_to_vec(body).map_err(|e| invalid(format!("gzip error: {e:?}")))
+}
@@ -0,0 +1,4525 @@
+//! command line definition and panic-free argument parsing
+
+use crate::bank::Banks;
+use crate::bookmarks::{self, Bookmark};
+use crate::charset::{ByteClass, Charset};
+use crate::conv;
+use crate::decode::Decoder;
+use crate::dirs::{self, Dirs};
+use crate::dwarf::LineTable;
+use crate::examples::{self, TOPICS};
+use crate::expr;
+use crate::format::Format;
+use crate::formats::Registry;
+use crate::frame::{self, Framing};
+use crate::generate;
+use crate::hash;
+use crate::i18n::{self, format_size, tr, Msg};
+use crate::input::{InputSource, DIRECT_BUFFER};
+use crate::literal;
+use crate::log::{Level, LogFormat};
+use crate::pager::Paging;
+use crate::panel::{parse_panels, Panel, DEFAULT_PANELS};
+use crate::patch::{self, Hunk, WriteGuard};
+use crate::records::{Conflict, Fill, RecordFormat};
+use crate::scalar::{
+    parse_layout, parse_scalar, BitField, Endian, ScalarSpec, ScalarType, SCALAR_TYPES,
+};
+use crate::session::{self, Session};
+use crate::spool::Spool;
+use crate::state;
+use crate::symbols::{self, Symbol};
+use crate::temp;
+use crate::transform::Step;
+use crate::{
+    function_bytes, Border, FloatSearch, FloatWord, Group, IntSearch, PointerScan, SourceMap,
+    Timestamps, ARG_ABS, ARG_ALN, ARG_ALS, ARG_ARR, ARG_AT, ARG_ATM, ARG_BAK, ARG_BAS, ARG_BIT,
+    ARG_BKS, ARG_BKT, ARG_BLK, ARG_BMK, ARG_BND, ARG_BRD, ARG_CAP, ARG_CAT, ARG_CAU, ARG_CHS,
+    ARG_CKS, ARG_CLE, ARG_CLR, ARG_CMT, ARG_CNT, ARG_COL, ARG_CRV, ARG_CTO, ARG_DAL, ARG_DDS,
+    ARG_DEC, ARG_DET, ARG_DIL, ARG_DIO, ARG_DRP, ARG_END, ARG_EXC, ARG_EXP, ARG_EXT, ARG_FD,
+    ARG_FFR, ARG_FIL, ARG_FIN, ARG_FLT, ARG_FMT, ARG_FNC, ARG_FND, ARG_FNF, ARG_FNI, ARG_FNO,
+    ARG_FRA, ARG_FRG, ARG_FRM, ARG_GAP, ARG_GEN, ARG_GLB, ARG_GRP, ARG_HDR, ARG_HHS, ARG_HLT,
+    ARG_ILV, ARG_INP, ARG_IRA, ARG_JRN, ARG_LAN, ARG_LAY, ARG_LBF, ARG_LBL, ARG_LEN, ARG_LGF,
+    ARG_LIT, ARG_LSC, ARG_LSZ, ARG_MBR, ARG_MEM, ARG_MJS, ARG_MSK, ARG_MTR, ARG_NCL, ARG_NCO,
+    ARG_NEA, ARG_NEW, ARG_NUM, ARG_NWR, ARG_OCF, ARG_OFM, ARG_OLD, ARG_ONL, ARG_OOF, ARG_OUT,
+    ARG_OVL, ARG_PAT, ARG_PCP, ARG_PFX, ARG_PGR, ARG_PKT, ARG_PLC, ARG_PNL, ARG_PRF, ARG_PSC,
+    ARG_PTT, ARG_QTY, ARG_QUI, ARG_RAW, ARG_RDF, ARG_RDO, ARG_REC, ARG_RED, ARG_RES, ARG_REV,
+    ARG_RGN, ARG_RLE, ARG_RNG, ARG_ROT, ARG_RPR, ARG_RVB, ARG_RVP, ARG_RWB, ARG_SED, ARG_SEP,
+    ARG_SES, ARG_SIZ, ARG_SKP, ARG_SMP, ARG_SPC, ARG_SPL, ARG_SQL, ARG_SRO, ARG_STK, ARG_STP,
+    ARG_STR, ARG_SYM, ARG_TIM, ARG_TMD, ARG_TO, ARG_TOL, ARG_TPC, ARG_TRN, ARG_TSP, ARG_TSZ,
+    ARG_TWP, ARG_TXR, ARG_TYP, ARG_UAL, ARG_UNQ, ARG_VAL, ARG_VRB, ARG_WRD, ARG_XOR, ARG_YES,
+    ARG_ZST, CMD_BITS, CMD_BOOKMARK, CMD_CHECK, CMD_COLORS, CMD_CONV, CMD_DIFF, CMD_ECHO,
+    CMD_EXAMPLES, CMD_EXTRACT, CMD_FILL, CMD_MATCH, CMD_MOVE, CMD_PATHS, CMD_POKE, CMD_RESIZE,
+    CMD_SELFTEST, CMD_SUM, CMD_TABLE, CMD_UNDO, READ_ONLY_ENV,
+};
+use clap::builder::{PossibleValue, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Errors produced while parsing arguments or running hx
+#[derive(Debug)]
+pub enum HexError {
+    /// the command line was rejected by clap, including help and version requests
+    Usage(clap::Error),
+    /// an option value could not be interpreted
+    InvalidValue {
+        /// usage of the offending option, e.g. `-c, --cols <integer>`
+        usage: &'static str,
+        /// reason the value was rejected
+        reason: String,
+    },
+    /// reading input or writing output failed
+    Io(io::Error),
+    /// a subcommand that changes files was run in read-only mode
+    ReadOnly(&'static str),
+    /// a verify mode found the input doesn't hold what's expected
+    Mismatch(String),
+    /// Ctrl-C ended a dump or search early, what was read was shown
+    Interrupted(String),
+    /// an option was given with another option that ignores it
+    Incompatible {
+        /// long name of the option that would be ignored
+        option: &'static str,
+        /// long name of the option ignoring it
+        with: &'static str,
+        /// why, and what to do instead
+        hint: &'static str,
+    },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::Usage(e) => write!(f, "{e}"),
+            HexError::InvalidValue { usage, reason } => write!(f, "{usage} expected. {reason}"),
+            HexError::Io(e) => write!(f, "{e}"),
+            HexError::Mismatch(reason) | HexError::Interrupted(reason) => write!(f, "{reason}"),
+            HexError::ReadOnly(command) => write!(
+                f,
+                "{command} changes files, refusing in read-only mode (--read-only or {READ_ONLY_ENV})"
+            ),
+            HexError::Incompatible { option, with, hint } => write!(
+                f,
+                "--{option} can't be combined with --{with}: {hint}, drop one of them"
+            ),
+        }
+    }
+}
+
+impl Error for HexError {}
+
+impl From<io::Error> for HexError {
+    fn from(e: io::Error) -> HexError {
+        HexError::Io(e)
+    }
+}
+
+impl From<clap::Error> for HexError {
+    fn from(e: clap::Error) -> HexError {
+        HexError::Usage(e)
+    }
+}
+
+/// What a single hx invocation produces
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// hex dump with offset, byte and ascii columns
+    Dump,
+    /// source code array in the given language, rust (r), C (c), golang (g), ...
+    Array(String),
+    /// function wave of the given length and decimal places
+    Func {
+        /// wave length
+        len: u64,
+        /// decimal places for function wave floats
+        places: usize,
+    },
+    /// built-in golden output checks
+    Selftest,
+    /// what this build supports, as JSON
+    Capabilities,
+    /// every byte value in the dump colors, with its class
+    Colors,
+    /// the ascii table with control character names
+    Table {
+        /// add 0x80-0xff as latin-1
+        extended: bool,
+    },
+    /// convert a dump back into bytes
+    Reverse {
+        /// byte order of cells wider than a byte, when given
+        words: Option<Endian>,
+        /// write the bytes at their offsets in --outfile, keeping the rest
+        absolute: bool,
+    },
+    /// curated example invocations
+    Examples {
+        /// topic of the examples, None for all
+        topic: Option<String>,
+    },
+    /// print the directories hx keeps its files in
+    Paths,
+    /// print an integer in every radix, byte order and two's complement width
+    Conv {
+        /// integer to print
+        value: u64,
+        /// whether it was written with a leading `-`
+        negative: bool,
+    },
+    /// read a typed scalar from a file and print it in every radix
+    Extract {
+        /// file to read
+        path: PathBuf,
+        /// scalar to read
+        spec: ScalarSpec,
+        /// inclusive bit range to extract, the whole value when None
+        bits: Option<(u32, u32)>,
+    },
+    /// add up the typed values in byte ranges of a file
+    Sum {
+        /// file to read
+        path: PathBuf,
+        /// `start`, `end` byte ranges, empty for the whole file
+        ranges: Vec<(u64, u64)>,
+        /// type of the values
+        kind: ScalarType,
+        /// byte order of the values
+        endian: Endian,
+    },
+    /// read a typed scalar from a file and draw its bit fields
+    Bits {
+        /// file to read
+        path: PathBuf,
+        /// scalar to read
+        spec: ScalarSpec,
+        /// fields, highest bits first
+        layout: Vec<BitField>,
+    },
+    /// check a file against a pattern under a mask
+    Match {
+        /// file to check
+        path: PathBuf,
+        /// file of expected bytes
+        pattern: PathBuf,
+        /// file of bits to compare, every bit when None
+        mask: Option<PathBuf>,
+    },
+    /// check a file against a layout spec
+    Check {
+        /// file to check
+        path: PathBuf,
+        /// layout spec file
+        spec: PathBuf,
+    },
+    /// compare two files, reporting changed and moved regions
+    Diff {
+        /// file compared against
+        old: PathBuf,
+        /// file compared with it
+        new: PathBuf,
+        /// shortest move, in bytes, 0 to look for none
+        block: usize,
+        /// align the files across insertions and deletions, `--diff-algo
+        /// aligned`, rather than compare the bytes at the same offset
+        aligned: bool,
+        /// add the CRC-32 of every region
+        checksums: bool,
+    },
+    /// write a typed scalar into a file
+    Poke {
+        /// file to patch
+        path: PathBuf,
+        /// scalar to write
+        spec: ScalarSpec,
+        /// value bits, zero extended
+        value: u64,
+        /// backup and replacement behavior
+        guard: WriteGuard,
+    },
+    /// copy or move a region within a file
+    Move {
+        /// file to change
+        path: PathBuf,
+        /// offset of the region
+        from: u64,
+        /// length of the region
+        len: u64,
+        /// offset to copy the region to
+        to: u64,
+        /// byte to clear the source bytes the copy doesn't cover with
+        clear: Option<u8>,
+        /// backup and replacement behavior
+        guard: WriteGuard,
+    },
+    /// truncate or extend a file
+    Resize {
+        /// file to resize
+        path: PathBuf,
+        /// new length
+        size: u64,
+        /// pattern to fill what the file grows by with
+        fill: Vec<u8>,
+        /// backup and replacement behavior
+        guard: WriteGuard,
+    },
+    /// apply a reverse patch written by an earlier edit
+    Undo {
+        /// patch file
+        patch: PathBuf,
+        /// backup and replacement behavior
+        guard: WriteGuard,
+    },
+    /// add, remove or list bookmarks
+    Bookmark {
+        /// bookmarks file
+        path: PathBuf,
+        /// what to do with it
+        action: BookmarkAction,
+    },
+}
+
+/// A change to, or listing of, a bookmarks file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookmarkAction {
+    /// add a bookmark, replacing one at the same offset
+    Add(Bookmark),
+    /// remove the bookmark at an offset
+    Remove(u64),
+    /// print every bookmark
+    List,
+}
+
+/// Mode implementation
+impl Mode {
+    /// Subcommand name when the mode changes files, None for read-only modes.
+    pub fn mutating_command(&self) -> Option<&'static str> {
+        match self {
+            Mode::Poke { .. } => Some(CMD_POKE),
+            Mode::Move { .. } => Some(CMD_MOVE),
+            Mode::Resize { .. } => Some(CMD_RESIZE),
+            Mode::Undo { .. } => Some(CMD_UNDO),
+            _ => None,
+        }
+    }
+}
+
+/// Inclusive range of 1 based indexes, e.g. `3`, `3-10` or `3-`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IndexRange {
+    /// first index
+    pub start: u64,
+    /// last index, None for everything from `start`
+    pub end: Option<u64>,
+}
+
+/// IndexRange implementation
+impl IndexRange {
+    /// Whether `index` falls inside the range.
+    pub fn contains(&self, index: u64) -> bool {
+        index >= self.start && self.end.is_none_or(|end| index <= end)
+    }
+
+    /// Whether every index after `index` falls outside the range.
+    pub fn is_past(&self, index: u64) -> bool {
+        self.end.is_some_and(|end| index >= end)
+    }
+}
+
+/// Fully parsed command line, independent of clap
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// output mode
+    pub mode: Mode,
+    /// where the input comes from
+    pub input: InputSource,
+    /// file with a list of input paths, `-` for stdin
+    pub files_from: Option<PathBuf>,
+    /// directory to walk for input files
+    pub recursive: Option<PathBuf>,
+    /// glob selecting files in the recursive walk
+    pub glob: String,
+    /// archive member to dump, with the `archives` feature
+    pub member: Option<String>,
+    /// list archive members instead of dumping, with the `archives` feature
+    pub list_members: bool,
+    /// memory region of a core file or minidump to dump, with the `cores` feature
+    pub region: Option<String>,
+    /// list the memory regions instead of dumping, with the `cores` feature
+    pub list_regions: bool,
+    /// read the input as a seekable zstd archive, with the `zstd` feature
+    pub zst: bool,
+    /// sqlite database page to annotate and dump
+    pub sqlite_page: Option<u32>,
+    /// dump each packet of a pcap or pcapng capture as its own block
+    pub pcap: bool,
+    /// packets to dump in pcap mode, all when None
+    pub packets: Option<IndexRange>,
+    /// write output to this file instead of stdout
+    pub outfile: Option<PathBuf>,
+    /// more outputs written from the same read, `--also`: format name
+    /// and path
+    pub also: Vec<(String, String)>,
+    /// compare the output with this file instead of writing it
+    pub expect: Option<String>,
+    /// continue an interrupted output file instead of overwriting it
+    pub resume: bool,
+    /// number of random windows to dump instead of the whole input
+    pub sample: Option<u64>,
+    /// random seed for sampling
+    pub seed: u64,
+    /// bytes to skip before dumping
+    pub skip: u64,
+    /// state file recording where `--continue` left off in the input
+    pub continue_state: Option<PathBuf>,
+    /// pad the first line so offsets stay aligned to the column width
+    pub align: bool,
+    /// byte ranges to dump, start and exclusive end, in increasing order
+    pub ranges: Vec<(u64, u64)>,
+    /// mark lines where a multiple of this many bytes falls, 0 for none
+    pub boundary: u64,
+    /// bookmarks to annotate in the margin, sorted by offset
+    pub bookmarks: Vec<Bookmark>,
+    /// symbols to annotate line offsets with, sorted by address
+    pub symbols: Vec<Symbol>,
+    /// source lines of code addresses, from the DWARF info of the symbol file
+    pub lines: LineTable,
+    /// print each distinct line once with its number of occurrences
+    pub unique_lines: bool,
+    /// report ranges repeating elsewhere, in blocks of this many bytes
+    pub dedupe_scan: Option<u64>,
+    /// list runs of at least this many equal bytes and collapse them in the dump
+    pub rle: Option<u64>,
+    /// directory `--carve-auto` extracts embedded files to
+    pub carve_auto: Option<PathBuf>,
+    /// structure to walk the input as
+    pub decode: Option<Decoder>,
+    /// delimiter to dump the fields between one at a time
+    pub split_on: Option<Vec<u8>>,
+    /// pattern `--find` dumps the lines around
+    pub find: Option<Vec<u8>>,
+    /// print only the number of `--find` matches
+    pub count_matches: bool,
+    /// list the offsets of `--find`, `--find-int`, `--find-float` or
+    /// `--pointer-scan` hits instead of dumping their lines
+    pub only_offsets: bool,
+    /// list the hits as JSON lines instead of dumping their lines
+    pub matches_json: bool,
+    /// report the byte order mark, line endings, longest line and NULs
+    /// instead of dumping
+    pub text_report: bool,
+    /// report the likely text encodings before the dump
+    pub detect_encoding: bool,
+    /// set the panel charset to the best encoding guess, when `--charset`
+    /// isn't given
+    pub detect_charset: bool,
+    /// region `--pointer-scan` finds the pointers into
+    pub pointer_scan: Option<PointerScan>,
+    /// typed integer `--find-int` finds the encodings of
+    pub find_int: Option<IntSearch>,
+    /// float `--find-float` finds the encodings of
+    pub find_float: Option<FloatSearch>,
+    /// how to cut the input into frames dumped one at a time
+    pub frame: Option<Framing>,
+    /// record size of a column-major dump, a row per record byte
+    pub transpose: Option<u64>,
+    /// bytes per output line, 0 for the whole input on one line
+    pub columns: u64,
+    /// text after every byte of a dump line
+    pub separator: String,
+    /// bytes shown as one integer word per cell, and their order
+    pub group: Group,
+    /// bytes to read, 0 reads everything
+    pub truncate_len: u64,
+    /// byte format
+    pub format: Format,
+    /// explicit color choice, None defers to NO_COLOR and terminal detection
+    pub color: Option<bool>,
+    /// include the format prefix with every byte
+    pub prefix: bool,
+    /// dim every other group of this many byte columns, 0 for no stripes
+    pub stripe: u64,
+    /// cells whose byte or float value lies within these bounds stand out
+    pub highlight: Option<(f64, f64)>,
+    /// character set of the panel after the byte columns
+    pub charset: Charset,
+    /// bytes shown, the others blank or dimmed when colored, None for all
+    pub only: Option<ByteClass>,
+    /// panels of every dump line, in order
+    pub panels: Vec<Panel>,
+    /// lines drawn around the panels of the dump
+    pub border: Border,
+    /// write only basic colors and ascii, for chats and limited
+    /// terminals, `--profile safe`
+    pub safe: bool,
+    /// transforms applied to the input, in order
+    pub transforms: Vec<Step>,
+    /// number of byte lanes and the lane to keep, `--deinterleave` and `--lane`
+    pub lane: Option<(usize, usize)>,
+    /// decode the input from records into the image they describe
+    pub from: Option<RecordFormat>,
+    /// fail on malformed records instead of skipping them
+    pub strict: bool,
+    /// what fills the gaps between records
+    pub fill: Fill,
+    /// which record wins where records overlap
+    pub on_conflict: Conflict,
+    /// bank layout translating offsets to addresses
+    pub banks: Banks,
+    /// map printing the input file offset of every line, `--source-offsets`
+    pub source_map: Option<SourceMap>,
+    /// print a metadata block before the dump of each input
+    pub header: bool,
+    /// include the sha-256 of the input in the metadata block
+    pub header_hash: bool,
+    /// name of the output format dumps are written in
+    pub output: String,
+    /// output formats by name
+    pub formats: Registry,
+    /// when to pipe output through a pager
+    pub paging: Paging,
+    /// flush after every output line
+    pub line_buffered: bool,
+    /// bytes per block read ahead from input files, 0 to read as needed
+    pub readahead: usize,
+    /// bytes the features holding input in memory may take, None for no
+    /// limit
+    pub memory_limit: Option<u64>,
+    /// read input files around the page cache
+    pub direct_io: bool,
+    /// print a timing report to stderr
+    pub timing: bool,
+    /// how much is reported on stderr, errors only also silences the
+    /// reports of verify modes
+    pub log_level: Level,
+    /// whether stderr records are text or JSON lines
+    pub log_format: LogFormat,
+    /// time column before every dump line
+    pub timestamps: Option<Timestamps>,
+    /// print plain byte counts instead of grouped digits and IEC units
+    pub raw_sizes: bool,
+    /// `--len` is one budget for all the files of a list, not one per file
+    pub len_total: bool,
+    /// offsets of a file list count on from the end of the file before
+    pub continuous_offsets: bool,
+    /// list the groups of identical files of a file list instead of dumps
+    pub dedupe_report: bool,
+    /// say so when `--len` cut the dump short
+    pub mark_truncated: bool,
+    /// leave out what opens and closes the output, so outputs concatenate
+    pub fragment: bool,
+    /// wrap concatenated array fragments read from the input
+    pub finalize: bool,
+    /// replacements shown in place of the input bytes, the file untouched
+    pub overlay: Vec<Hunk>,
+    /// files `--carve` writes, one for each of the ranges
+    pub carve: Vec<PathBuf>,
+    /// session file to save the input and dump options to after the run
+    pub session: Option<Session>,
+    /// language of summary text and sizes, None for the user's locale
+    pub lang: Option<String>,
+    /// output the same on every machine and run: no color, times or
+    /// locale, plain sizes and 80 columns for `-c auto`
+    pub reproducible: bool,
+    /// what `load` reads when the run starts
+    pub pending: Pending,
+}
+
+/// What the command line names for `Config::load` to read when the run
+/// starts, so parsing it has no side effects.
+#[derive(Clone, Debug, Default)]
+pub struct Pending {
+    /// directory `--two-pass` copies a streamed input to
+    pub spool_dir: Option<PathBuf>,
+    /// `--continue`: the state file is named after the start of the input
+    pub continued: bool,
+    /// `--bookmarks` file
+    pub bookmarks: Option<PathBuf>,
+    /// `--overlay` patch file
+    pub overlay: Option<PathBuf>,
+    /// `--symbols` file
+    pub symbols: Option<PathBuf>,
+    /// offset options as given, for `end` in the `--two-pass` copy
+    offsets: OffsetArgs,
+}
+
+/// Offset options as given on the command line, evaluated once the length
+/// `end` stands for is known.
+#[derive(Clone, Debug, Default)]
+struct OffsetArgs {
+    /// `--skip`
+    skip: Option<String>,
+    /// `--range`
+    ranges: Option<String>,
+    /// `--carve`
+    carve: Option<OsString>,
+    /// `--redact`
+    redact: Option<String>,
+    /// `--redact-fill`
+    redact_fill: u8,
+    /// `--at` of `--decode leb128`
+    at: Option<String>,
+    /// `--base` of `--pointer-scan`
+    base: Option<String>,
+    /// `--size` of `--pointer-scan`
+    size: Option<String>,
+}
+
+/// Config implementation
+impl Config {
+    /// Interpret clap matches, returning an error for any value hx can't use.
+    ///
+    /// # Arguments
+    ///
+    /// * `matches` - argument matches from `command()`.
+    pub fn from_matches(matches: &ArgMatches) -> Result<Config, HexError> {
+        if let Some(path) = matches.get_one::<String>(ARG_SES) {
+            return Config::from_session(matches, path);
+        }
+        validate(matches)?;
+        let generated = match matches.subcommand() {
+            Some((CMD_ECHO, echo)) => Some((CMD_ECHO, "echo <literal>", "literal", echo)),
+            Some((CMD_FILL, fill)) => Some((CMD_FILL, "fill --pattern <bytes>", "pattern", fill)),
+            _ => None,
+        };
+        let generated = match generated {
+            Some((command, usage, what, sub)) => {
+                let given = [ARG_INP, ARG_CAT, ARG_ILV]
+                    .into_iter()
+                    .find_map(|arg| matches.get_many::<PathBuf>(arg)?.next());
+                if let Some(path) = given {
+                    return Err(HexError::InvalidValue {
+                        usage,
+                        reason: format!("the {what} is the input, drop {path:?}"),
+                    });
+                }
+                Some(generated_source(command, sub, usage)?)
+            }
+            None => None,
+        };
+        let generated = match (matches.get_one::<String>(ARG_GEN), generated) {
+            (Some(_), Some(_)) => {
+                return Err(HexError::InvalidValue {
+                    usage: "--generate <expression>",
+                    reason: "echo and fill already give the input".to_string(),
+                })
+            }
+            (Some(expr), None) => {
+                Some(
+                    generate::parse(expr).map_err(|reason| HexError::InvalidValue {
+                        usage: "--generate <expression>",
+                        reason,
+                    })?,
+                )
+            }
+            (None, generated) => generated,
+        };
+        // --func-output bytes dumps the wave like any input
+        let func_bytes = matches
+            .get_one::<String>(ARG_FNO)
+            .is_some_and(|output| output == "bytes");
+        let generated = match (matches.get_one::<String>(ARG_FNC), func_bytes) {
+            (Some(len), true) => Some(InputSource::Literal(function_bytes(parse_value(
+                len,
+                "-u, --func <integer>",
+            )?))),
+            _ => generated,
+        };
+        let mode = if matches.get_flag(ARG_CAP) {
+            Mode::Capabilities
+        } else if matches.subcommand_matches(CMD_SELFTEST).is_some() {
+            Mode::Selftest
+        } else if matches.subcommand_matches(CMD_COLORS).is_some() {
+            Mode::Colors
+        } else if let Some(table) = matches.subcommand_matches(CMD_TABLE) {
+            Mode::Table {
+                extended: table.get_flag(ARG_EXT),
+            }
+        } else if let Some(examples) = matches.subcommand_matches(CMD_EXAMPLES) {
+            Mode::Examples {
+                topic: examples.get_one::<String>(ARG_TPC).cloned(),
+            }
+        } else if matches.subcommand_matches(CMD_PATHS).is_some() {
+            Mode::Paths
+        } else if let Some(conv) = matches.subcommand_matches(CMD_CONV) {
+            let number = required(conv, ARG_NUM, "conv <number>")?;
+            Mode::Conv {
+                value: conv::parse(number).map_err(|reason| HexError::InvalidValue {
+                    usage: "conv <number>",
+                    reason,
+                })?,
+                negative: number.starts_with('-'),
+            }
+        } else if let Some(extract) = matches.subcommand_matches(CMD_EXTRACT) {
+            let spec = scalar_spec(extract)?;
+            let bits = match extract.get_one::<String>(ARG_BIT) {
+                Some(bits) => Some(parse_bits(bits, spec.kind)?),
+                None => None,
+            };
+            Mode::Extract {
+                path: extract
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                spec,
+                bits,
+            }
+        } else if let Some(sum) = matches.subcommand_matches(CMD_SUM) {
+            let path = sum.get_one::<PathBuf>(ARG_FIL).cloned().unwrap_or_default();
+            let name = sum.get_one::<String>(ARG_TYP).map_or("u8", String::as_str);
+            let Some((kind, order)) = ScalarType::parse_ordered(name) else {
+                return Err(HexError::InvalidValue {
+                    usage: "--type <type>",
+                    reason: format!(
+                        "{name} is not one of {} with an optional le or be suffix",
+                        SCALAR_TYPES.join(", ")
+                    ),
+                });
+            };
+            Mode::Sum {
+                ranges: match sum.get_one::<String>(ARG_RNG) {
+                    Some(ranges) => parse_byte_ranges(
+                        ranges,
+                        "--range <start:end|start+len,...>",
+                        file_len(Some(&path)),
+                    )?,
+                    None => Vec::new(),
+                },
+                path,
+                kind,
+                endian: match (order, sum.get_one::<String>(ARG_END).map(String::as_str)) {
+                    (Some(order), _) => order,
+                    (None, Some("big")) => Endian::Big,
+                    (None, _) => Endian::Little,
+                },
+            }
+        } else if let Some(bits) = matches.subcommand_matches(CMD_BITS) {
+            let spec = scalar_spec(bits)?;
+            let layout = required(bits, ARG_LAY, "--layout <fields>")?;
+            Mode::Bits {
+                path: bits
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                layout: parse_layout(layout, spec.kind).map_err(|reason| {
+                    HexError::InvalidValue {
+                        usage: "--layout <fields>",
+                        reason,
+                    }
+                })?,
+                spec,
+            }
+        } else if let Some(check) = matches.subcommand_matches(CMD_MATCH) {
+            Mode::Match {
+                path: check
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                pattern: check
+                    .get_one::<PathBuf>(ARG_PAT)
+                    .cloned()
+                    .unwrap_or_default(),
+                mask: check.get_one::<PathBuf>(ARG_MSK).cloned(),
+            }
+        } else if let Some(check) = matches.subcommand_matches(CMD_CHECK) {
+            Mode::Check {
+                path: check
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                spec: check
+                    .get_one::<PathBuf>(ARG_SPC)
+                    .cloned()
+                    .unwrap_or_default(),
+            }
+        } else if let Some(diff) = matches.subcommand_matches(CMD_DIFF) {
+            Mode::Diff {
+                old: diff
+                    .get_one::<PathBuf>(ARG_OLD)
+                    .cloned()
+                    .unwrap_or_default(),
+                new: diff
+                    .get_one::<PathBuf>(ARG_NEW)
+                    .cloned()
+                    .unwrap_or_default(),
+                block: match diff.get_one::<String>(ARG_BLK) {
+                    Some(block) => parse_value(block, "--block <bytes>")?,
+                    None => 64,
+                },
+                aligned: diff.get_one::<String>(ARG_DAL).map(String::as_str) != Some("naive"),
+                checksums: diff.get_flag(ARG_CKS),
+            }
+        } else if let Some(poke) = matches.subcommand_matches(CMD_POKE) {
+            let spec = scalar_spec(poke)?;
+            let value = poke.get_one::<String>(ARG_VAL).cloned().unwrap_or_default();
+            Mode::Poke {
+                path: poke
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                value: parse_scalar(&value, spec.kind).map_err(|reason| {
+                    HexError::InvalidValue {
+                        usage: "--value <value>",
+                        reason,
+                    }
+                })?,
+                spec,
+                guard: write_guard(poke),
+            }
+        } else if let Some(region) = matches.subcommand_matches(CMD_MOVE) {
+            let path = region
+                .get_one::<PathBuf>(ARG_FIL)
+                .cloned()
+                .unwrap_or_default();
+            let end = file_len(Some(&path));
+            let offset = |arg: &str, usage| match region.get_one::<String>(arg) {
+                Some(value) => parse_offset(value, usage, end),
+                None => Ok(0),
+            };
+            let len = offset(ARG_LEN, "--len <bytes>")?;
+            if len == 0 {
+                return Err(HexError::InvalidValue {
+                    usage: "--len <bytes>",
+                    reason: "the region is empty".to_string(),
+                });
+            }
+            Mode::Move {
+                from: offset(ARG_FRM, "--from <offset>")?,
+                len,
+                to: offset(ARG_TO, "--to <offset>")?,
+                clear: match region.get_one::<String>(ARG_CLE) {
+                    Some(byte) => Some(parse_byte(byte, "--clear <byte>")?),
+                    None => None,
+                },
+                guard: write_guard(region),
+                path,
+            }
+        } else if let Some(resize) = matches.subcommand_matches(CMD_RESIZE) {
+            let path = resize
+                .get_one::<PathBuf>(ARG_FIL)
+                .cloned()
+                .unwrap_or_default();
+            let size = resize.get_one::<String>(ARG_TO).map_or("", String::as_str);
+            let fill = resize
+                .get_one::<String>(ARG_GAP)
+                .map_or("00", String::as_str);
+            let usage = "--fill <bytes>";
+            Mode::Resize {
+                size: parse_offset(size, "--to <size>", file_len(Some(&path)))?,
+                fill: match literal::parse(fill) {
+                    Ok(fill) if fill.is_empty() => {
+                        return Err(HexError::InvalidValue {
+                            usage,
+                            reason: "the pattern is empty".to_string(),
+                        })
+                    }
+                    Ok(fill) => fill,
+                    Err(reason) => return Err(HexError::InvalidValue { usage, reason }),
+                },
+                guard: write_guard(resize),
+                path,
+            }
+        } else if let Some(undo) = matches.subcommand_matches(CMD_UNDO) {
+            Mode::Undo {
+                patch: undo
+                    .get_one::<PathBuf>(ARG_FIL)
+                    .cloned()
+                    .unwrap_or_default(),
+                guard: write_guard(undo),
+            }
+        } else if let Some(bookmark) = matches.subcommand_matches(CMD_BOOKMARK) {
+            let (action, sub) = match bookmark.subcommand() {
+                Some(("add", sub)) => (
+                    BookmarkAction::Add(Bookmark {
+                        offset: parse_offset(
+                            sub.get_one::<String>(ARG_AT).map_or("", String::as_str),
+                            "<offset>",
+                            None,
+                        )?,
+                        label: sub.get_one::<String>(ARG_LBL).cloned().unwrap_or_default(),
+                    }),
+                    sub,
+                ),
+                Some(("remove", sub)) => (
+                    BookmarkAction::Remove(parse_offset(
+                        sub.get_one::<String>(ARG_AT).map_or("", String::as_str),
+                        "<offset>",
+                        None,
+                    )?),
+                    sub,
+                ),
+                Some((_, sub)) => (BookmarkAction::List, sub),
+                None => (BookmarkAction::List, bookmark),
+            };
+            Mode::Bookmark {
+                path: bookmarks_path(sub.get_one::<PathBuf>(ARG_FIL), "<file>")?,
+                action,
+            }
+        } else if let (Some(len), false) = (matches.get_one::<String>(ARG_FNC), func_bytes) {
+            Mode::Func {
+                len: parse_value(len, "-u, --func <integer>")?,
+                places: match matches.get_one::<String>(ARG_PLC) {
+                    Some(places) => parse_value(places, "-p, --places <integer>")?,
+                    None => 4,
+                },
+            }
+        } else if matches.get_flag(ARG_REV) {
+            Mode::Reverse {
+                words: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+                    Some("big") => Some(Endian::Big),
+                    Some(_) => Some(Endian::Little),
+                    None => None,
+                },
+                absolute: matches.get_flag(ARG_ABS),
+            }
+        } else if let Some(array) = matches.get_one::<String>(ARG_ARR) {
+            Mode::Array(array.to_string())
+        } else {
+            Mode::Dump
+        };
+        let read_only = matches.get_flag(ARG_RDO) || read_only_env();
+        if let Some(command) = mode.mutating_command() {
+            if read_only {
+                return Err(HexError::ReadOnly(command));
+            }
+        }
+
+        let format = match matches.get_one::<String>(ARG_FMT).map(String::as_str) {
+            // o, x, X, p, b, e, E
+            None | Some("x") => Format::LowerHex,
+            Some("o") => Format::Octal,
+            Some("X") => Format::UpperHex,
+            Some("p") => Format::Pointer,
+            Some("b") => Format::Binary,
+            Some("e") => Format::LowerExp(float_word(matches)?),
+            Some("E") => Format::UpperExp(float_word(matches)?),
+            Some(other) => {
+                return Err(HexError::InvalidValue {
+                    usage: "-f, --format <format>",
+                    reason: format!("{other:?} is not one of {}", FORMATS.listed()),
+                })
+            }
+        };
+        let group = Group {
+            bytes: match matches.get_one::<String>(ARG_GRP) {
+                Some(bytes) => parse_value(bytes, "--group <bytes>")?,
+                None => 1,
+            },
+            endian: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+                Some("big") => Endian::Big,
+                _ => Endian::Little,
+            },
+        };
+        let listed = [ARG_OOF, ARG_MJS]
+            .into_iter()
+            .find(|arg| matches.get_flag(arg));
+        if let (Some(arg), false) = (
+            listed,
+            matches.contains_id(ARG_FND)
+                || matches.contains_id(ARG_FNI)
+                || matches.contains_id(ARG_FNF)
+                || matches.get_flag(ARG_PSC),
+        ) {
+            return Err(HexError::InvalidValue {
+                usage: match arg {
+                    ARG_OOF => "--only-offsets",
+                    _ => "--matches-json",
+                },
+                reason:
+                    "it lists the hits of --find, --find-int, --find-float or --pointer-scan, give one of them"
+                        .to_string(),
+            });
+        }
+        let typed_search = matches.contains_id(ARG_FNI) || matches.contains_id(ARG_FNF);
+        if let (Some("any"), false) = (
+            matches.get_one::<String>(ARG_END).map(String::as_str),
+            typed_search,
+        ) {
+            return Err(HexError::InvalidValue {
+                usage: "--endian <endian>",
+                reason: "any only applies to --find-int and --find-float, use little or big"
+                    .to_string(),
+            });
+        }
+        if matches.contains_id(ARG_TYP) && !typed_search {
+            return Err(HexError::InvalidValue {
+                usage: "--type <type>",
+                reason: "it's the type of --find-int or --find-float, give one of them".to_string(),
+            });
+        }
+        if matches.get_flag(ARG_DRP)
+            && !matches.contains_id(ARG_FFR)
+            && !matches.contains_id(ARG_REC)
+        {
+            return Err(HexError::InvalidValue {
+                usage: "--dedupe-report",
+                reason: "it compares the files of --files-from or --recursive, give one of them"
+                    .to_string(),
+            });
+        }
+        if group.bytes > 1 && format.word() > 1 {
+            return Err(HexError::InvalidValue {
+                usage: "--group <bytes>",
+                reason: "the e and E formats already print whole float words".to_string(),
+            });
+        }
+        let output = match (
+            matches.get_flag(ARG_STK),
+            matches.get_flag(ARG_EXP),
+            matches.get_flag(ARG_RWB),
+            matches.contains_id(ARG_TRN),
+        ) {
+            (true, _, _, _) => "stacked".to_string(),
+            (_, true, _, _) => "explain".to_string(),
+            (_, _, true, _) => "raw".to_string(),
+            (_, _, _, true) => "transposed".to_string(),
+            _ => matches
+                .get_one::<String>(ARG_OFM)
+                .cloned()
+                .unwrap_or_else(|| "dump".to_string()),
+        };
+        if output == "zst" && matches.get_one::<String>(ARG_PRF).map(String::as_str) == Some("safe")
+        {
+            return Err(HexError::InvalidValue {
+                usage: "--profile <profile>",
+                reason: "safe only applies to text, --output zst writes an archive".to_string(),
+            });
+        }
+        // scripts read the machine formats, escape codes would only get in the way
+        let machine = output == "json" || output == "plain";
+        let reproducible = matches.get_flag(ARG_RPR);
+        let memory_limit = memory_limit(matches)?;
+        let input = input_source(matches, generated)?;
+        // a stream is read to the end first, so the dump knows its length
+        let streamed = matches!(
+            input,
+            InputSource::Stdin | InputSource::Fd(_) | InputSource::File(_)
+        ) && input.capabilities().len.is_none();
+        let spool_dir =
+            match matches.get_flag(ARG_TWP) && matches!(mode, Mode::Dump | Mode::Array(_)) {
+                true if streamed => Some(temp::dir(
+                    matches.get_one::<PathBuf>(ARG_TMD).map(PathBuf::as_path),
+                )),
+                _ => None,
+            };
+        let input_len = input_file_len(&input);
+        let offsets = OffsetArgs {
+            skip: matches.get_one::<String>(ARG_SKP).cloned(),
+            ranges: matches.get_one::<String>(ARG_RNG).cloned(),
+            carve: matches.get_one::<OsString>(ARG_CRV).cloned(),
+            redact: matches.get_one::<String>(ARG_RED).cloned(),
+            redact_fill: redact_fill(matches)?,
+            at: matches.get_one::<String>(ARG_AT).cloned(),
+            base: matches.get_one::<String>(ARG_BAS).cloned(),
+            size: matches.get_one::<String>(ARG_SIZ).cloned(),
+        };
+        let overlay = matches.get_one::<String>(ARG_OVL).map(PathBuf::from);
+        let bookmarks = match matches.get_one::<PathBuf>(ARG_BMK) {
+            Some(path) => Some(bookmarks_path(Some(path), "--bookmarks <file>")?),
+            None => None,
+        };
+
+        let mut config = Config {
+            mode,
+            input,
+            files_from: matches.get_one::<PathBuf>(ARG_FFR).cloned(),
+            recursive: matches.get_one::<PathBuf>(ARG_REC).cloned(),
+            glob: matches
+                .get_one::<String>(ARG_GLB)
+                .cloned()
+                .unwrap_or_else(|| "*".to_string()),
+            member: matches
+                .try_get_one::<String>(ARG_MBR)
+                .ok()
+                .flatten()
+                .filter(|member| !member.is_empty())
+                .cloned(),
+            list_members: matches
+                .try_get_one::<String>(ARG_MBR)
+                .ok()
+                .flatten()
+                .is_some_and(|member| member.is_empty()),
+            region: matches
+                .try_get_one::<String>(ARG_RGN)
+                .ok()
+                .flatten()
+                .filter(|region| !region.is_empty())
+                .cloned(),
+            list_regions: matches
+                .try_get_one::<String>(ARG_RGN)
+                .ok()
+                .flatten()
+                .is_some_and(|region| region.is_empty()),
+            zst: matches.try_get_one::<bool>(ARG_ZST).ok().flatten() == Some(&true),
+            sqlite_page: match matches.get_one::<String>(ARG_SQL) {
+                Some(page) => Some(parse_value(page, "--sqlite-page <integer>")?),
+                None => None,
+            },
+            pcap: matches.get_flag(ARG_PCP),
+            packets: match matches.get_one::<String>(ARG_PKT) {
+                Some(packets) => Some(parse_range(packets, "--packets <first[-last]>")?),
+                None => None,
+            },
+            outfile: matches.get_one::<PathBuf>(ARG_OUT).cloned(),
+            also: also_outputs(matches)?,
+            expect: matches.get_one::<String>(ARG_EXC).cloned(),
+            resume: matches.get_flag(ARG_RES),
+            sample: matches.get_one::<u64>(ARG_SMP).copied(),
+            seed: match matches.get_one::<String>(ARG_SED) {
+                Some(seed) => parse_value(seed, "--seed <integer>")?,
+                None => 0,
+            },
+            skip: 0,
+            continue_state: None,
+            align: matches.get_flag(ARG_ALN),
+            ranges: Vec::new(),
+            bookmarks: Vec::new(),
+            overlay: Vec::new(),
+            carve: Vec::new(),
+            len_total: matches.get_one::<String>(ARG_LSC).map(String::as_str) == Some("total"),
+            continuous_offsets: matches.get_flag(ARG_CTO),
+            dedupe_report: matches.get_flag(ARG_DRP),
+            mark_truncated: matches.get_flag(ARG_MTR),
+            fragment: matches.get_flag(ARG_FRG),
+            finalize: matches.get_flag(ARG_FIN),
+            symbols: Vec::new(),
+            lines: LineTable::default(),
+            boundary: match matches.get_one::<String>(ARG_BND).map(String::as_str) {
+                Some("page") => 4096,
+                Some("sector") => 512,
+                Some(boundary) => match parse_offset(boundary, "--boundary <size>", None)? {
+                    0 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--boundary <size>",
+                            reason: "boundary must be at least 1 byte".to_string(),
+                        })
+                    }
+                    boundary => boundary,
+                },
+                None => 0,
+            },
+            unique_lines: matches.get_flag(ARG_UNQ),
+            carve_auto: matches.get_one::<PathBuf>(ARG_CAU).cloned(),
+            dedupe_scan: match matches.get_one::<String>(ARG_DDS) {
+                Some(block) => match parse_value(block, "--dedupe-scan <integer>")? {
+                    0 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--dedupe-scan <integer>",
+                            reason: "block size must be at least 1".to_string(),
+                        })
+                    }
+                    block => Some(block),
+                },
+                None => None,
+            },
+            rle: match matches.get_one::<String>(ARG_RLE) {
+                Some(min) => match parse_offset(min, "--rle <min_run>", None)? {
+                    0 | 1 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--rle <min_run>",
+                            reason: "runs are at least 2 bytes".to_string(),
+                        })
+                    }
+                    min => Some(min),
+                },
+                None => None,
+            },
+            decode: decoder(matches)?,
+            count_matches: matches.get_flag(ARG_CMT),
+            only_offsets: matches.get_flag(ARG_OOF),
+            matches_json: matches.get_flag(ARG_MJS),
+            text_report: matches.get_flag(ARG_TXR),
+            detect_encoding: matches.get_flag(ARG_DET),
+            detect_charset: matches.get_flag(ARG_DET) && !matches.contains_id(ARG_CHS),
+            find: match matches.get_one::<String>(ARG_FND) {
+                Some(pattern) => {
+                    Some(
+                        parse_pattern(pattern).map_err(|reason| HexError::InvalidValue {
+                            usage: "--find <pattern>",
+                            reason,
+                        })?,
+                    )
+                }
+                None => None,
+            },
+            pointer_scan: match matches.get_flag(ARG_PSC) {
+                true => {
+                    let kind = matches
+                        .get_one::<String>(ARG_PTT)
+                        .map_or("u64", String::as_str);
+                    let (width, endian) =
+                        PointerScan::parse_type(kind).map_err(|reason| HexError::InvalidValue {
+                            usage: "--pointer-type <type>",
+                            reason,
+                        })?;
+                    // the region is set with the other offsets
+                    Some(PointerScan {
+                        base: 0,
+                        size: 0,
+                        width,
+                        endian,
+                    })
+                }
+                false => None,
+            },
+            find_int: match matches.get_one::<String>(ARG_FNI) {
+                Some(value) => {
+                    let kind = match matches.get_one::<String>(ARG_TYP).map(String::as_str) {
+                        None => ScalarType::U32,
+                        Some(name) => match ScalarType::parse(name) {
+                            Some(ScalarType::F32 | ScalarType::F64) | None => {
+                                return Err(HexError::InvalidValue {
+                                    usage: "--type <type>",
+                                    reason: format!(
+                                        "{name} is not an integer type, use --find-float for floats"
+                                    ),
+                                })
+                            }
+                            Some(kind) => kind,
+                        },
+                    };
+                    Some(IntSearch {
+                        kind,
+                        value: parse_scalar(value, kind).map_err(|reason| {
+                            HexError::InvalidValue {
+                                usage: "--find-int <value>",
+                                reason,
+                            }
+                        })?,
+                        near: match matches.get_one::<String>(ARG_NEA) {
+                            Some(near) => parse_value(near, "--near <integer>")?,
+                            None => 0,
+                        },
+                        endian: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+                            Some("little") => Some(Endian::Little),
+                            Some("big") => Some(Endian::Big),
+                            _ => None,
+                        },
+                    })
+                }
+                None => None,
+            },
+            find_float: match matches.get_one::<String>(ARG_FNF) {
+                Some(value) => Some(FloatSearch {
+                    kind: match matches.get_one::<String>(ARG_TYP).map(String::as_str) {
+                        None | Some("f32") => ScalarType::F32,
+                        Some("f64") => ScalarType::F64,
+                        Some(name) => {
+                            return Err(HexError::InvalidValue {
+                                usage: "--type <type>",
+                                reason: format!(
+                                    "{name} is not a float type, use --find-int for integers"
+                                ),
+                            })
+                        }
+                    },
+                    value: parse_value(value, "--find-float <value>")?,
+                    tolerance: match matches.get_one::<String>(ARG_TOL) {
+                        Some(tolerance) => parse_value(tolerance, "--tolerance <difference>")?,
+                        None => 0.0,
+                    },
+                    endian: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+                        Some("little") => Some(Endian::Little),
+                        Some("big") => Some(Endian::Big),
+                        _ => None,
+                    },
+                    unaligned: matches.get_flag(ARG_UAL),
+                }),
+                None => None,
+            },
+            split_on: match matches.get_one::<String>(ARG_SPL) {
+                Some(delimiter) => match literal::parse(delimiter) {
+                    Ok(bytes) if !bytes.is_empty() => Some(bytes),
+                    result => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--split-on <bytes>",
+                            reason: result
+                                .err()
+                                .unwrap_or_else(|| "the delimiter is empty".to_string()),
+                        })
+                    }
+                },
+                None => None,
+            },
+            frame: match matches.get_one::<String>(ARG_FRA) {
+                Some(spec) => {
+                    Some(frame::parse(spec).map_err(|reason| HexError::InvalidValue {
+                        usage: "--frame <framing>",
+                        reason,
+                    })?)
+                }
+                None => None,
+            },
+            transpose: match matches.get_one::<String>(ARG_TRN) {
+                Some(record) => match parse_offset(record, "--transpose <record_size>", None)? {
+                    0 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--transpose <record_size>",
+                            reason: "records are at least a byte".to_string(),
+                        })
+                    }
+                    record => Some(record),
+                },
+                None => None,
+            },
+            columns: match matches.get_one::<String>(ARG_COL) {
+                _ if matches.get_flag(ARG_NWR) => 0,
+                Some(columns) if columns == "auto" => 0,
+                Some(columns) => parse_value(columns, "-c, --cols <integer>")?,
+                None if group.word(format) > 1 => 16,
+                // the line length of xxd -p
+                None if output == "plain" => 30,
+                None => 10,
+            },
+            separator: matches
+                .get_one::<String>(ARG_SEP)
+                .cloned()
+                .unwrap_or_else(|| " ".to_string()),
+            group,
+            truncate_len: match matches.get_one::<String>(ARG_LEN) {
+                Some(length) => parse_value(length, "-l, --len <integer>")?,
+                None => 0x0,
+            },
+            format,
+            color: match matches.get_one::<String>(ARG_CLR) {
+                _ if matches.get_flag(ARG_NCO) || machine || reproducible => Some(false),
+                Some(color) if color == "auto" => None,
+                Some(color) => Some(parse_switch(color, "-t, --color <always|never|auto>")?),
+                None => None,
+            },
+            prefix: match matches.get_one::<String>(ARG_PFX) {
+                Some(prefix) => parse_switch(prefix, "-r, --prefix <on|off>")?,
+                None => true,
+            },
+            stripe: match matches.get_one::<String>(ARG_STP) {
+                Some(stripe) => match parse_value(stripe, "--stripe <integer>")? {
+                    0 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--stripe <integer>",
+                            reason: "group size must be at least 1".to_string(),
+                        })
+                    }
+                    stripe => stripe,
+                },
+                None => 0,
+            },
+            highlight: match matches.get_one::<String>(ARG_HLT) {
+                Some(bounds) => {
+                    Some(
+                        parse_bounds(bounds).map_err(|reason| HexError::InvalidValue {
+                            usage: "--highlight <min:max>",
+                            reason,
+                        })?,
+                    )
+                }
+                None => None,
+            },
+            charset: match matches.get_one::<String>(ARG_CHS) {
+                Some(name) => Charset::parse(name).unwrap_or_default(),
+                None => Charset::Ascii,
+            },
+            only: matches
+                .get_one::<String>(ARG_ONL)
+                .and_then(|name| ByteClass::parse(name)),
+            panels: match matches.get_one::<String>(ARG_PNL) {
+                Some(list) => parse_panels(list).map_err(|reason| HexError::InvalidValue {
+                    usage: "--panels <list>",
+                    reason,
+                })?,
+                None => DEFAULT_PANELS.to_vec(),
+            },
+            border: matches
+                .get_one::<String>(ARG_BRD)
+                .and_then(|name| Border::parse(name))
+                .unwrap_or_default(),
+            safe: matches.get_one::<String>(ARG_PRF).map(String::as_str) == Some("safe"),
+            transforms: transform_steps(matches)?,
+            lane: lane(matches)?,
+            from: matches
+                .get_one::<String>(ARG_FRM)
+                .and_then(|name| RecordFormat::parse(name)),
+            strict: matches.get_flag(ARG_STR),
+            fill: gap_fill(matches)?,
+            on_conflict: matches
+                .get_one::<String>(ARG_OCF)
+                .and_then(|name| Conflict::parse(name))
+                .unwrap_or_default(),
+            banks: banks(matches)?,
+            source_map: match matches.get_flag(ARG_SRO) {
+                true => Some(SourceMap::new(lane(matches)?)),
+                false => None,
+            },
+            header: matches.get_flag(ARG_HDR),
+            header_hash: matches.get_flag(ARG_HHS),
+            output: output.clone(),
+            formats: Registry::default(),
+            paging: match matches.get_one::<String>(ARG_PGR) {
+                Some(name) => Paging::parse(name).unwrap_or_default(),
+                None => Paging::Never,
+            },
+            line_buffered: matches.get_flag(ARG_LBF),
+            readahead: match (matches.get_one::<String>(ARG_IRA), memory_limit) {
+                (Some(size), _) => parse_offset(size, "--io-readahead <size>", None)? as usize,
+                // a block being read, one waiting and one being dumped
+                (None, Some(limit)) => (limit / READAHEAD_BLOCKS).clamp(1, 1 << 20) as usize,
+                (None, None) => 1 << 20,
+            },
+            memory_limit,
+            direct_io: matches.get_flag(ARG_DIO),
+            timing: matches.get_flag(ARG_TIM),
+            log_format: LogFormat::from_name(
+                matches
+                    .get_one::<String>(ARG_LGF)
+                    .map_or("text", String::as_str),
+            ),
+            log_level: Level::from_counts(matches.get_count(ARG_QUI), matches.get_count(ARG_VRB)),
+            timestamps: match matches.get_one::<String>(ARG_TSP).map(String::as_str) {
+                Some("abs") => Some(Timestamps::Absolute),
+                Some("delta") => Some(Timestamps::Delta),
+                _ => None,
+            },
+            raw_sizes: matches.get_flag(ARG_RAW) || reproducible,
+            session: None,
+            lang: reproducible.then(|| "en".to_string()),
+            reproducible,
+            pending: Pending {
+                spool_dir,
+                continued: matches.get_flag(ARG_CNT),
+                bookmarks,
+                overlay,
+                symbols: matches.get_one::<PathBuf>(ARG_SYM).cloned(),
+                offsets,
+            },
+        };
+        // the length of a --two-pass copy is known once it's made
+        if config.pending.spool_dir.is_none() {
+            let offsets = config.pending.offsets.clone();
+            config.set_offsets(&offsets, input_len)?;
+        }
+        if matches.get_one::<String>(ARG_COL).map(String::as_str) == Some("auto") {
+            let width = match reproducible {
+                true => 80,
+                false => terminal_width(),
+            };
+            config.columns = config.fit_columns(width);
+        }
+        let word = config.group.word(config.format) as u64;
+        if word > 1 && (!config.columns.is_multiple_of(word) || config.align) {
+            let words = match config.group.bytes {
+                1 => format!("the e and E formats print whole {word} byte floats"),
+                _ => format!("--group prints whole {word} byte words"),
+            };
+            return Err(HexError::InvalidValue {
+                usage: "-c, --cols <integer>",
+                reason: format!("{words}, use multiples of {word} without --align"),
+            });
+        }
+        // writing over a file changes it as much as a subcommand does, be it
+        // truncated or patched in place with --absolute or --resume
+        if read_only {
+            if config.outfile.as_deref().is_some_and(Path::is_file) {
+                return Err(HexError::ReadOnly("--outfile onto an existing file"));
+            }
+            if config
+                .also
+                .iter()
+                .any(|(_, path)| Path::new(path).is_file())
+            {
+                return Err(HexError::ReadOnly("--also onto an existing file"));
+            }
+        }
+        check_memory_limit(&config)?;
+        Ok(config)
+    }
+
+    /// Evaluate the offset options, with `end` for the input length.
+    ///
+    /// # Arguments
+    ///
+    /// * `offsets` - offset options as given.
+    /// * `end` - length of the input, None when unknown.
+    fn set_offsets(&mut self, offsets: &OffsetArgs, end: Option<u64>) -> Result<(), HexError> {
+        let (carved, paths) = match &offsets.carve {
+            Some(list) => {
+                let (ranges, paths) = split_carve(list)?;
+                (parse_byte_ranges(&ranges, CARVE_USAGE, end)?, paths)
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        let redact = match &offsets.redact {
+            Some(ranges) => parse_byte_ranges(ranges, "--redact <start:end|start+len,...>", end)?,
+            None => Vec::new(),
+        };
+        if let Some(skip) = &offsets.skip {
+            self.skip = parse_offset(skip, "-s, --skip <offset>", end)?;
+        }
+        self.ranges = match &offsets.ranges {
+            _ if !carved.is_empty() => carved,
+            Some(ranges) => parse_byte_ranges(ranges, "--range <start:end|start+len,...>", end)?,
+            None => Vec::new(),
+        };
+        self.carve = paths;
+        if let Some(Decoder::Leb128 { at, .. }) = &mut self.decode {
+            *at = match &offsets.at {
+                Some(value) => Some(parse_offset(value, "--at <offset>", end)?),
+                None => None,
+            };
+        }
+        if let Some(scan) = &mut self.pointer_scan {
+            let bound = |value: &Option<String>, usage| match value {
+                Some(value) => parse_offset(value, usage, end),
+                None => Ok(0),
+            };
+            scan.base = bound(&offsets.base, "--base <address>")?;
+            scan.size = bound(&offsets.size, "--size <bytes>")?;
+        }
+        // redacted ranges are marked where they start
+        self.bookmarks
+            .extend(redact.iter().map(|(start, end)| Bookmark {
+                offset: *start,
+                label: format!("redacted {} bytes", end - start),
+            }));
+        self.bookmarks.sort_by_key(|mark| mark.offset);
+        // redaction goes last, so no transform brings the bytes back
+        if !redact.is_empty() {
+            self.transforms.push(Step::Redact {
+                ranges: redact,
+                fill: offsets.redact_fill,
+            });
+        }
+        Ok(())
+    }
+
+    /// Read what the command line names when the run starts: the
+    /// `--two-pass` copy of a streamed input, where `--continue` left off,
+    /// and the `--bookmarks`, `--overlay` and `--symbols` files.
+    pub fn load(&self) -> Result<Config, HexError> {
+        let mut config = self.clone();
+        let pending = std::mem::take(&mut config.pending);
+        if let Some(dir) = &pending.spool_dir {
+            let spool = Spool::create(config.input.clone(), dir)?;
+            config.input = InputSource::Spooled(Arc::new(spool));
+            let end = input_file_len(&config.input);
+            config.set_offsets(&pending.offsets, end)?;
+        }
+        if pending.continued {
+            // the input as given, not its --two-pass copy
+            let state = continue_state(self.input.path())?;
+            if pending.offsets.skip.is_none() {
+                config.skip = state::load(&state)?.unwrap_or(0);
+            }
+            config.continue_state = Some(state);
+        }
+        if let Some(path) = &pending.bookmarks {
+            config
+                .bookmarks
+                .extend(bookmarks::parse(&std::fs::read_to_string(path)?)?);
+        }
+        if let Some(path) = &pending.overlay {
+            let (_, hunks) = patch::parse_patch(&std::fs::read_to_string(path)?)?;
+            if hunks.iter().any(Hunk::resizes) {
+                return Err(HexError::InvalidValue {
+                    usage: "--overlay <patch>",
+                    reason: "the patch resizes the file, which an overlay can't show".to_string(),
+                });
+            }
+            if let Some(input) = self.input.path() {
+                patch::verify(input, &hunks)?;
+            }
+            // overlaid bytes are marked in the margin with what they replace
+            config.bookmarks.extend(hunks.iter().map(|hunk| Bookmark {
+                offset: hunk.at,
+                label: format!(
+                    "overlay -{} +{}",
+                    hash::to_hex(&hunk.old),
+                    hash::to_hex(&hunk.new)
+                ),
+            }));
+            config.overlay = hunks;
+        }
+        config.bookmarks.sort_by_key(|mark| mark.offset);
+        if let Some(path) = &pending.symbols {
+            let data = std::fs::read(path)?;
+            config.symbols = symbols::parse(&data)?;
+            config.lines = source_lines(&data)?;
+        }
+        Ok(config)
+    }
+
+    /// Interpret clap matches with a `--session` file: the input and options
+    /// saved in it fill in what the command line doesn't give, and the
+    /// result is what gets saved back after the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `matches` - argument matches from `command()`.
+    /// * `path` - session file, created on the first run.
+    fn from_session(matches: &ArgMatches, path: &str) -> Result<Config, HexError> {
+        let usage = "--session <file>";
+        if let Some((name, _)) = matches.subcommand() {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: format!("sessions keep dump options, drop the {name} subcommand"),
+            });
+        }
+        let path = dirs::named_file(path, Dirs::current().sessions(), "hxs", dirs::STATE_DIR_ENV)
+            .map_err(|reason| HexError::InvalidValue { usage, reason })?;
+        let argv = |args: Vec<String>| std::iter::once("hx".to_string()).chain(args);
+        let saved = command()
+            .try_get_matches_from(argv(session::load(&path)?))
+            .map_err(|e| HexError::InvalidValue {
+                usage,
+                reason: format!("{} has bad options: {}", path.display(), e.kind()),
+            })?;
+        let given = session::given(&command(), matches, &[ARG_SES]);
+        let mut args: Vec<(String, Vec<String>)> = session::given(&command(), &saved, &[ARG_SES])
+            .into_iter()
+            .filter(|(id, _)| !given.iter().any(|(given, _)| given == id))
+            .collect();
+        args.extend(given);
+        // options before the input, whichever side they came from
+        args.sort_by_key(|(id, _)| id == ARG_INP);
+        let args: Vec<String> = args.into_iter().flat_map(|(_, tokens)| tokens).collect();
+        let merged = command().try_get_matches_from(argv(args.clone()))?;
+        let mut config = Config::from_matches(&merged)?;
+        config.session = Some(Session { path, args });
+        Ok(config)
+    }
+
+    /// Most bytes per line that keep dump lines, with offset, byte cells and
+    /// character panel, within a width. At least one.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - characters per output line.
+    pub fn fit_columns(&self, width: usize) -> u64 {
+        let word = self.group.word(self.format);
+        let cell = self.group.cell_width(self.format, self.prefix) + self.separator.len();
+        let gap = match self.separator.ends_with(char::is_whitespace) {
+            true => 0,
+            false => 1,
+        };
+        let line = |columns: usize| {
+            let panel = match self.charset {
+                Charset::Ascii => columns,
+                _ => columns.div_ceil(2),
+            };
+            "0x000000: ".len() + columns.div_ceil(word) * cell + gap + panel
+        };
+        (2..)
+            .map(|cells| cells * word)
+            .take_while(|columns| line(*columns) <= width)
+            .last()
+            .unwrap_or(word) as u64
+    }
+
+    /// Length of the input the dump reads, None when only known at its end
+    /// or when the dump reads something derived from it, like a decoded
+    /// image, a member or every other byte.
+    pub fn input_len(&self) -> Option<u64> {
+        let derived = self.from.is_some()
+            || self.member.is_some()
+            || self.sqlite_page.is_some()
+            || self.pcap
+            || self.lane.is_some()
+            || self.files_from.is_some()
+            || self.recursive.is_some()
+            || self.region.is_some()
+            || self.zst;
+        match derived {
+            true => None,
+            false => self.input.capabilities().len,
+        }
+    }
+
+    /// Text for a message in the configured language, else the user's locale.
+    pub fn tr(&self, msg: Msg) -> &'static str {
+        match &self.lang {
+            Some(lang) => i18n::translate(msg, lang),
+            None => tr(msg),
+        }
+    }
+
+    /// Byte count as configured: plain with `--raw-sizes`, else grouped
+    /// for the configured language or the user's numeric locale.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - number of bytes.
+    pub fn format_size(&self, n: u64) -> String {
+        match (&self.lang, self.raw_sizes) {
+            (Some(lang), false) => i18n::human_size(n, lang),
+            _ => format_size(n, self.raw_sizes),
+        }
+    }
+}
+
+/// Dump options arrays would ignore, with the reason
+const DUMP_ONLY: [(&str, &str); 15] = [
+    (ARG_FMT, "arrays are always written as hex literals"),
+    (ARG_GRP, "arrays are written a byte at a time"),
+    (ARG_STP, "arrays aren't colored"),
+    (ARG_HLT, "arrays aren't colored"),
+    (ARG_CHS, "arrays have no character panel"),
+    (ARG_BND, "arrays have no rule lines"),
+    (ARG_BMK, "arrays have no margin notes"),
+    (ARG_SYM, "arrays have no margin notes"),
+    (ARG_ALN, "arrays have no offset column"),
+    (ARG_BKS, "arrays have no offset column"),
+    (ARG_SRO, "arrays have no offset column"),
+    (ARG_SEP, "array elements are separated by commas"),
+    (ARG_PNL, "arrays have no panels"),
+    (ARG_ONL, "arrays hold every byte"),
+    (ARG_PRF, "arrays are plain ascii text already"),
+];
+
+/// Modes that don't draw the dump `--border` frames, with the reason
+const UNBORDERED: [(&str, &str); 10] = [
+    (ARG_ARR, "arrays aren't framed"),
+    (ARG_RWB, "raw bytes aren't framed"),
+    (ARG_TXR, "the text report isn't framed"),
+    (ARG_DDS, "the dedupe report isn't framed"),
+    (ARG_STK, "stacked lines have no panels to frame"),
+    (
+        ARG_EXP,
+        "explained bytes are listed one a line, not in panels",
+    ),
+    (
+        ARG_FND,
+        "hits are dumped in pieces the frame would break up",
+    ),
+    (
+        ARG_FNI,
+        "hits are dumped in pieces the frame would break up",
+    ),
+    (
+        ARG_FNF,
+        "hits are dumped in pieces the frame would break up",
+    ),
+    (
+        ARG_PSC,
+        "hits are dumped in pieces the frame would break up",
+    ),
+];
+
+/// Options that add text lines scripts reading json or plain hex would
+/// trip on, with the reason
+const TEXT_LINES: [(&str, &str); 3] = [
+    (ARG_TSP, "timestamps are written as text before every line"),
+    (ARG_HDR, "the header is written as lines of text"),
+    (ARG_MTR, "the truncation mark is a line of text"),
+];
+
+/// Options that only style the text dump, left out of json and plain hex,
+/// with the reason
+const UNSTYLED: [(&str, &str); 2] = [
+    (ARG_STP, "scripts read the bytes uncolored"),
+    (ARG_HLT, "scripts read the bytes uncolored"),
+];
+
+/// Searches `--unaligned` doesn't apply to, it's for `--find-float`, with
+/// the reason
+const OTHER_SEARCHES: [(&str, &str); 3] = [
+    (ARG_FND, "patterns are looked for at every offset"),
+    (ARG_FNI, "integers are looked for at every offset"),
+    (ARG_PSC, "pointers are aligned words"),
+];
+
+/// Options that need lines of a fixed width, with the reason
+const WRAPPED_ONLY: [(&str, &str); 4] = [
+    (ARG_RES, "resuming counts whole lines"),
+    (ARG_UNQ, "one line has nothing to compare"),
+    (ARG_SMP, "sample windows are counted in lines"),
+    (ARG_ALN, "alignment pads to the column width"),
+];
+
+/// Reject option combinations where one option would quietly be ignored,
+/// naming both and why, and values no mode can use.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches from `command()`.
+fn validate(matches: &ArgMatches) -> Result<(), HexError> {
+    let given = |arg: &str| matches.value_source(arg) == Some(ValueSource::CommandLine);
+    let func_bytes = matches
+        .get_one::<String>(ARG_FNO)
+        .is_some_and(|output| output == "bytes");
+    if given(ARG_FNC) && given(ARG_ARR) && !func_bytes {
+        return Err(HexError::Incompatible {
+            option: ARG_ARR,
+            with: ARG_FNC,
+            hint:
+                "--func prints a function wave instead of the input, --func-output bytes dumps it",
+        });
+    }
+    if given(ARG_PLC) && func_bytes {
+        return Err(HexError::Incompatible {
+            option: ARG_PLC,
+            with: ARG_FNO,
+            hint: "bytes have no decimal places",
+        });
+    }
+    if given(ARG_ARR) {
+        if let Some((option, hint)) = DUMP_ONLY.into_iter().find(|(arg, _)| given(arg)) {
+            return Err(HexError::Incompatible {
+                option,
+                with: ARG_ARR,
+                hint,
+            });
+        }
+    }
+    let no_wrap = match given(ARG_NWR) {
+        true => Some(ARG_NWR),
+        false => matches
+            .get_one::<String>(ARG_COL)
+            .filter(|columns| columns.parse::<u64>() == Ok(0))
+            .map(|_| "cols 0"),
+    };
+    if let Some(with) = no_wrap {
+        if let Some((option, hint)) = WRAPPED_ONLY.into_iter().find(|(arg, _)| given(arg)) {
+            return Err(HexError::Incompatible { option, with, hint });
+        }
+    }
+    let machine = match matches.get_one::<String>(ARG_OFM).map(String::as_str) {
+        Some("json") => Some("output json"),
+        Some("plain") => Some("output plain"),
+        _ => None,
+    };
+    if let Some(with) = machine {
+        let ignored = TEXT_LINES.into_iter().chain(UNSTYLED);
+        if let Some((option, hint)) = ignored.into_iter().find(|(arg, _)| given(arg)) {
+            return Err(HexError::Incompatible { option, with, hint });
+        }
+    }
+    let ordered = [ARG_GRP, ARG_REV, ARG_FNI, ARG_FNF].into_iter().any(given)
+        || matches.get_one::<String>(ARG_DEC).map(String::as_str) == Some("tlv");
+    if given(ARG_END) && !ordered {
+        return Err(HexError::InvalidValue {
+            usage: "--endian <endian>",
+            reason: "it orders the bytes of --group, --reverse, --decode tlv, --find-int or --find-float, give one of them".to_string(),
+        });
+    }
+    // clap drops a requirement that conflicts with what's given, as
+    // --recursive does with an input file
+    if given(ARG_UAL) && !given(ARG_FNF) {
+        if let Some((with, hint)) = OTHER_SEARCHES.into_iter().find(|(arg, _)| given(arg)) {
+            return Err(HexError::Incompatible {
+                option: ARG_UAL,
+                with,
+                hint,
+            });
+        }
+    }
+    if given(ARG_GLB) && !given(ARG_REC) {
+        return Err(HexError::InvalidValue {
+            usage: "--glob <pattern>",
+            reason: "it only filters the files of --recursive, which isn't given".to_string(),
+        });
+    }
+    if given(ARG_PNL) {
+        let unpaneled = match machine {
+            Some(with) => Some((with, "the fields are fixed")),
+            None => given(ARG_EXP).then_some((
+                ARG_EXP,
+                "explained bytes are listed one a line, not in panels",
+            )),
+        };
+        if let Some((with, hint)) = unpaneled {
+            return Err(HexError::Incompatible {
+                option: ARG_PNL,
+                with,
+                hint,
+            });
+        }
+    }
+    if matches
+        .get_one::<String>(ARG_BRD)
+        .is_some_and(|style| style != "none")
+    {
+        let unbordered = match (machine, no_wrap) {
+            (Some(with), _) => Some((with, "only the dump is framed")),
+            (None, Some(with)) => Some((with, "the frame is drawn to the column width")),
+            (None, None) => UNBORDERED.into_iter().find(|(arg, _)| given(arg)),
+        };
+        if let Some((with, hint)) = unbordered {
+            return Err(HexError::Incompatible {
+                option: ARG_BRD,
+                with,
+                hint,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `--find` pattern: bytes as for `hx echo`, e.g. `deadbeef` or
+/// `'ELF'`, or else the text itself.
+///
+/// # Arguments
+///
+/// * `text` - pattern.
+pub fn parse_pattern(text: &str) -> Result<Vec<u8>, String> {
+    let bytes = literal::parse(text).unwrap_or_else(|_| text.as_bytes().to_vec());
+    match bytes.is_empty() {
+        true => Err("the pattern is empty".to_string()),
+        false => Ok(bytes),
+    }
+}
+
+/// Parse `--highlight` bounds, `min:max` as decimal or `0x` hex integers
+/// or floats, either end included.
+///
+/// # Arguments
+///
+/// * `text` - bounds, e.g. `0x20:0x7e` or `-1.5:1e3`.
+pub fn parse_bounds(text: &str) -> Result<(f64, f64), String> {
+    let bound = |text: &str| {
+        let text = text.trim();
+        let value = match text.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok().map(|value| value as f64),
+            None => text.parse::<f64>().ok().filter(|value| !value.is_nan()),
+        };
+        value.ok_or(format!("{text:?} is not a number"))
+    };
+    let Some((min, max)) = text.split_once(':') else {
+        return Err(format!("{text:?} is not min:max"));
+    };
+    let (min, max) = (bound(min)?, bound(max)?);
+    if min > max {
+        return Err(format!("{min} is above {max}"));
+    }
+    Ok((min, max))
+}
+
+/// Float words for the exponent formats, from `--float`.
+fn float_word(matches: &ArgMatches) -> Result<FloatWord, HexError> {
+    match matches.get_one::<String>(ARG_FLT) {
+        Some(text) => FloatWord::parse(text).map_err(|reason| HexError::InvalidValue {
+            usage: "--float <f32|f64>[le|be]",
+            reason,
+        }),
+        None => Ok(FloatWord::default()),
+    }
+}
+
+/// Width of the terminal for `--cols auto`: `$COLUMNS` when it's set to a
+/// number, else 80.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|columns| *columns > 0)
+        .unwrap_or(80)
+}
+
+/// Read-only mode was switched on through the environment: any value but
+/// empty or `0`.
+fn read_only_env() -> bool {
+    std::env::var(READ_ONLY_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Parse a numeric option value.
+fn parse_value<T>(value: &str, usage: &'static str) -> Result<T, HexError>
+where
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse::<T>().map_err(|e| HexError::InvalidValue {
+        usage,
+        reason: e.to_string(),
+    })
+}
+
+/// Value of an option clap requires, an error rather than a panic should
+/// it be missing all the same.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches holding the option.
+/// * `arg` - name of the option.
+/// * `usage` - usage of the option, for the error.
+fn required<'a>(
+    matches: &'a ArgMatches,
+    arg: &str,
+    usage: &'static str,
+) -> Result<&'a str, HexError> {
+    matches
+        .get_one::<String>(arg)
+        .map(String::as_str)
+        .ok_or_else(|| HexError::InvalidValue {
+            usage,
+            reason: "it's missing".to_string(),
+        })
+}
+
+/// Bank layout from `--bank-size` and `--bank-stride`, which come together.
+fn banks(matches: &ArgMatches) -> Result<Banks, HexError> {
+    let (Some(size), Some(stride)) = (
+        matches.get_one::<String>(ARG_BKS),
+        matches.get_one::<String>(ARG_BKT),
+    ) else {
+        return Ok(Banks::default());
+    };
+    let size = parse_offset(size, "--bank-size <size>", None)?;
+    if size == 0 {
+        return Err(HexError::InvalidValue {
+            usage: "--bank-size <size>",
+            reason: "bank size must be at least 1 byte".to_string(),
+        });
+    }
+    Ok(Banks {
+        size,
+        stride: parse_offset(stride, "--bank-stride <bytes>", None)?,
+    })
+}
+
+/// usage shown for a bad `--carve` list
+const CARVE_USAGE: &str = "--carve <start:end=file,...>";
+
+/// Split a `--carve` list into its byte ranges, as for `--range`, and the
+/// file after each range's `=`.
+///
+/// # Arguments
+///
+/// * `value` - carve list, e.g. `0x400:0x800=header.bin,0x1000+0x100=table.bin`.
+fn split_carve(value: &OsStr) -> Result<(String, Vec<PathBuf>), HexError> {
+    let mut ranges = Vec::new();
+    let mut paths = Vec::new();
+    // the files may not be UTF-8, their ranges must
+    for item in value.as_encoded_bytes().split(|b| *b == b',') {
+        let split = item.iter().position(|b| *b == b'=');
+        match split.map(|at| (std::str::from_utf8(&item[..at]), &item[at + 1..])) {
+            Some((Ok(range), path)) if !path.is_empty() => {
+                ranges.push(range);
+                paths.push(crate::path_from_bytes(path));
+            }
+            _ => {
+                return Err(HexError::InvalidValue {
+                    usage: CARVE_USAGE,
+                    reason: format!(
+                        "{} has no =file to write the range to",
+                        String::from_utf8_lossy(item)
+                    ),
+                })
+            }
+        }
+    }
+    Ok((ranges.join(","), paths))
+}
+
+/// Byte written over `--redact` ranges, 0 unless `--redact-fill` gives one.
+fn redact_fill(matches: &ArgMatches) -> Result<u8, HexError> {
+    match matches.get_one::<String>(ARG_RDF) {
+        None => Ok(0),
+        Some(byte) => parse_byte(byte, "--redact-fill <byte>"),
+    }
+}
+
+/// Parse a byte value, decimal or hex with 0x.
+///
+/// # Arguments
+///
+/// * `value` - byte, e.g. `0xff`.
+/// * `usage` - option usage for the error.
+fn parse_byte(value: &str, usage: &'static str) -> Result<u8, HexError> {
+    match parse_offset(value, usage, None)? {
+        byte @ 0..=0xff => Ok(byte as u8),
+        _ => Err(HexError::InvalidValue {
+            usage,
+            reason: format!("{value} doesn't fit in a byte"),
+        }),
+    }
+}
+
+/// Gap fill from `--fill`: a byte, decimal or hex with 0x, or skip, which
+/// only plain dumps can show.
+fn gap_fill(matches: &ArgMatches) -> Result<Fill, HexError> {
+    let usage = "--fill <byte|skip>";
+    match matches.get_one::<String>(ARG_GAP).map(String::as_str) {
+        None => Ok(Fill::default()),
+        Some("skip") => {
+            let other = [
+                ARG_ARR, ARG_SKP, ARG_LEN, ARG_RNG, ARG_CRV, ARG_UNQ, ARG_DDS, ARG_RWB,
+            ]
+            .into_iter()
+            .find(|arg| matches.value_source(arg) == Some(ValueSource::CommandLine));
+            match other {
+                Some(arg) => Err(HexError::InvalidValue {
+                    usage,
+                    reason: format!(
+                        "skip leaves gaps out of the dump, it can't be combined with --{arg}"
+                    ),
+                }),
+                None => Ok(Fill::Skip),
+            }
+        }
+        Some(byte) => match parse_offset(byte, usage, None)? {
+            byte @ 0..=0xff => Ok(Fill::Byte(byte as u8)),
+            _ => Err(HexError::InvalidValue {
+                usage,
+                reason: format!("{byte} doesn't fit in a byte"),
+            }),
+        },
+    }
+}
+
+/// Input of `hx echo` or `hx fill`: the literal, or the pattern repeated
+/// to `--len` bytes.
+///
+/// # Arguments
+///
+/// * `command` - `echo` or `fill`.
+/// * `matches` - matches of the subcommand.
+/// * `usage` - usage of the subcommand, for errors.
+fn generated_source(
+    command: &str,
+    matches: &ArgMatches,
+    usage: &'static str,
+) -> Result<InputSource, HexError> {
+    let parse = |text: &str| {
+        literal::parse(text).map_err(|reason| HexError::InvalidValue { usage, reason })
+    };
+    if command == CMD_ECHO {
+        let literal = required(matches, ARG_LIT, usage)?;
+        return Ok(InputSource::Literal(parse(literal)?));
+    }
+    let bytes = parse(
+        matches
+            .get_one::<String>(ARG_PAT)
+            .map_or("00", String::as_str),
+    )?;
+    if bytes.is_empty() {
+        return Err(HexError::InvalidValue {
+            usage,
+            reason: "the pattern is empty".to_string(),
+        });
+    }
+    let len = required(matches, ARG_LEN, "fill --len <bytes>")?;
+    Ok(InputSource::Pattern {
+        bytes,
+        len: parse_offset(len, "fill --len <bytes>", None)?,
+    })
+}
+
+/// Extra outputs of `--also FORMAT=PATH`, every format `--output` takes
+/// and `array` and `raw`.
+fn also_outputs(matches: &ArgMatches) -> Result<Vec<(String, String)>, HexError> {
+    let usage = "--also <format>=<path>";
+    let mut formats = output_formats();
+    formats.extend(["array", "raw"]);
+    let mut also = Vec::new();
+    for value in matches.get_many::<String>(ARG_ALS).into_iter().flatten() {
+        let Some((name, path)) = value.split_once('=').filter(|(_, path)| !path.is_empty()) else {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: format!("{value:?} isn't <format>=<path>"),
+            });
+        };
+        if !formats.contains(&name) {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: format!("unknown format {name:?}, use {}", formats.join(", ")),
+            });
+        }
+        also.push((name.to_string(), path.to_string()));
+    }
+    Ok(also)
+}
+
+/// Formats `--output` takes, `zst` with the `zstd` feature.
+fn output_formats() -> Vec<&'static str> {
+    let mut formats = vec!["dump", "json", "plain", "html"];
+    if cfg!(feature = "zstd") {
+        formats.push("zst");
+    }
+    formats
+}
+
+/// Input from `hx echo`, `hx fill`, `--fd`, `--concat`, `--interleave` or
+/// the input file.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches from `command()`.
+/// * `generated` - input of `hx echo` or `hx fill`, when given.
+fn input_source(
+    matches: &ArgMatches,
+    generated: Option<InputSource>,
+) -> Result<InputSource, HexError> {
+    // clap lets a requirement go when it conflicts with the input file
+    if matches.contains_id(ARG_WRD) && !matches.contains_id(ARG_ILV) {
+        return Err(HexError::InvalidValue {
+            usage: "--interleave <files>... --word <bytes>",
+            reason: "--word sets how --interleave takes its files".to_string(),
+        });
+    }
+    if let Some(source) = generated {
+        return Ok(source);
+    }
+    if let Some(fd) = matches.get_one::<String>(ARG_FD) {
+        return Ok(InputSource::Fd(parse_value(fd, "--fd <number>")?));
+    }
+    if let Some(paths) = matches.get_many::<PathBuf>(ARG_CAT) {
+        return Ok(InputSource::Concat(paths.cloned().collect()));
+    }
+    if let Some(paths) = matches.get_many::<PathBuf>(ARG_ILV) {
+        let word = match matches.get_one::<String>(ARG_WRD) {
+            Some(word) => parse_value::<usize>(word, "--word <bytes>")?,
+            None => 1,
+        };
+        if word == 0 {
+            return Err(HexError::InvalidValue {
+                usage: "--word <bytes>",
+                reason: "words are at least a byte".to_string(),
+            });
+        }
+        return Ok(InputSource::Interleave {
+            paths: paths.cloned().collect(),
+            word,
+        });
+    }
+    Ok(InputSource::new(
+        matches.get_one::<PathBuf>(ARG_INP).map(PathBuf::as_path),
+    ))
+}
+
+/// Structure from `--decode` and its layout options.
+///
+/// # Arguments
+///
+/// * `matches` - argument matches from `command()`.
+fn decoder(matches: &ArgMatches) -> Result<Option<Decoder>, HexError> {
+    let size = |arg: &str, usage: &'static str| -> Result<usize, HexError> {
+        match matches.get_one::<String>(arg) {
+            None => Ok(1),
+            Some(size) => match parse_value::<usize>(size, usage)? {
+                size @ 1..=8 => Ok(size),
+                _ => Err(HexError::InvalidValue {
+                    usage,
+                    reason: "fields are 1 to 8 bytes".to_string(),
+                }),
+            },
+        }
+    };
+    Ok(
+        match matches.get_one::<String>(ARG_DEC).map(String::as_str) {
+            Some("tlv") => Some(Decoder::Tlv {
+                type_size: size(ARG_TSZ, "--type-size <bytes>")?,
+                len_size: size(ARG_LSZ, "--len-size <bytes>")?,
+                endian: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+                    Some("little") => Endian::Little,
+                    _ => Endian::Big,
+                },
+            }),
+            // --at is set with the other offsets
+            Some("leb128") => Some(Decoder::Leb128 {
+                at: None,
+                count: match matches.get_one::<String>(ARG_QTY) {
+                    Some(count) => match parse_offset(count, "--count <varints>", None)? {
+                        0 => {
+                            return Err(HexError::InvalidValue {
+                                usage: "--count <varints>",
+                                reason: "at least one varint".to_string(),
+                            })
+                        }
+                        count => count,
+                    },
+                    None => 1,
+                },
+            }),
+            _ => None,
+        },
+    )
+}
+
+/// Lanes from `--deinterleave`, and the lane to keep from `--lane`.
+fn lane(matches: &ArgMatches) -> Result<Option<(usize, usize)>, HexError> {
+    let Some(lanes) = matches.get_one::<String>(ARG_DIL) else {
+        return Ok(None);
+    };
+    let lanes = parse_value::<usize>(lanes, "--deinterleave <lanes>")?;
+    if lanes == 0 {
+        return Err(HexError::InvalidValue {
+            usage: "--deinterleave <lanes>",
+            reason: "there is at least one lane".to_string(),
+        });
+    }
+    let lane = match matches.get_one::<String>(ARG_LAN) {
+        Some(lane) => parse_value::<usize>(lane, "--lane <lane>")?,
+        None => 0,
+    };
+    if lane >= lanes {
+        return Err(HexError::InvalidValue {
+            usage: "--lane <lane>",
+            reason: format!(
+                "lanes of --deinterleave {lanes} count from 0 to {}",
+                lanes - 1
+            ),
+        });
+    }
+    Ok(Some((lanes, lane)))
+}
+
+/// Transforms from `--xor`, `--rot` and `--reverse-bytes`, in the order
+/// they were given, each option may repeat.
+fn transform_steps(matches: &ArgMatches) -> Result<Vec<Step>, HexError> {
+    let mut steps: Vec<(usize, Step)> = Vec::new();
+    for arg in [ARG_XOR, ARG_ROT, ARG_RVB] {
+        let (Some(values), Some(indices)) =
+            (matches.get_many::<String>(arg), matches.indices_of(arg))
+        else {
+            continue;
+        };
+        for (value, index) in values.zip(indices) {
+            let step = match arg {
+                ARG_XOR => Step::Xor(parse_xor_key(value)?),
+                ARG_ROT => Step::Rot(parse_value(value, "--rot <n>")?),
+                _ => match parse_value(value, "--reverse-bytes <width>")? {
+                    0 => {
+                        return Err(HexError::InvalidValue {
+                            usage: "--reverse-bytes <width>",
+                            reason: "width must be at least 1 byte".to_string(),
+                        })
+                    }
+                    width => Step::ReverseBytes(width),
+                },
+            };
+            steps.push((index, step));
+        }
+    }
+    steps.sort_by_key(|(index, _)| *index);
+    Ok(steps.into_iter().map(|(_, step)| step).collect())
+}
+
+/// Parse an XOR key: a byte in decimal, or one or more bytes in hex with a
+/// `0x` prefix, e.g. `0x5a` or `0xdeadbeef`.
+fn parse_xor_key(value: &str) -> Result<Vec<u8>, HexError> {
+    let usage = "--xor <key>";
+    let invalid = |reason: &str| HexError::InvalidValue {
+        usage,
+        reason: reason.to_string(),
+    };
+    match value.strip_prefix("0x") {
+        Some(hex) if hex.is_empty() || !hex.len().is_multiple_of(2) => {
+            Err(invalid("hex keys need two digits per byte"))
+        }
+        Some(hex) => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| invalid(&e.to_string())),
+        None => Ok(vec![parse_value(value, usage)?]),
+    }
+}
+
+/// Parse a byte offset: decimal or hex with a `0x` prefix, or an
+/// expression of them such as `0x400+3*512` or `end-256`.
+///
+/// # Arguments
+///
+/// * `value` - offset text.
+/// * `usage` - usage of the option, for errors.
+/// * `end` - length of the input `end` stands for, None when unknown.
+fn parse_offset(value: &str, usage: &'static str, end: Option<u64>) -> Result<u64, HexError> {
+    expr::eval(value, end).map_err(|reason| HexError::InvalidValue { usage, reason })
+}
+
+/// Split `start+len` at its last `+` outside parentheses.
+fn split_relative(range: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in range.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => depth -= 1,
+            '+' if depth == 0 => return Some((&range[..i], &range[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse a comma separated list of byte ranges, each either absolute
+/// `start:end` or relative `start+len`, with offset expressions for the
+/// bounds. Ranges must be in increasing order and not overlap, so they can
+/// be read from a stream.
+///
+/// # Arguments
+///
+/// * `value` - range list, e.g. `0x200+0x40,0x400:0x600`.
+/// * `usage` - usage of the option, for errors.
+/// * `end` - length of the input `end` stands for, None when unknown.
+fn parse_byte_ranges(
+    value: &str,
+    usage: &'static str,
+    end: Option<u64>,
+) -> Result<Vec<(u64, u64)>, HexError> {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for range in value.split(',') {
+        let (start, stop) = match (range.split_once(':'), split_relative(range)) {
+            (Some((start, stop)), _) => (
+                parse_offset(start, usage, end)?,
+                parse_offset(stop, usage, end)?,
+            ),
+            (None, Some((start, len))) => {
+                let start = parse_offset(start, usage, end)?;
+                let len = parse_offset(len, usage, end)?;
+                (start, start.saturating_add(len))
+            }
+            (None, None) => {
+                return Err(HexError::InvalidValue {
+                    usage,
+                    reason: format!("{range} is neither start:end nor start+len"),
+                })
+            }
+        };
+        if stop <= start {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: format!("{range} is empty"),
+            });
+        }
+        if ranges.last().is_some_and(|(_, last)| start < *last) {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: "ranges must be in increasing order and not overlap".to_string(),
+            });
+        }
+        ranges.push((start, stop));
+    }
+    Ok(ranges)
+}
+
+/// DWARF line table of an ELF symbol file, empty for other symbol files
+/// or without the `dwarf` feature.
+fn source_lines(data: &[u8]) -> io::Result<LineTable> {
+    #[cfg(feature = "dwarf")]
+    if data.starts_with(b"\x7fELF") {
+        return crate::dwarf::line_table(data);
+    }
+    let _ = data;
+    Ok(LineTable::default())
+}
+
+/// Blocks of the input read ahead held at once: one being read, one
+/// waiting and one being dumped
+const READAHEAD_BLOCKS: u64 = 3;
+
+/// Parse `--memory-limit`, a size of at least one byte.
+fn memory_limit(matches: &ArgMatches) -> Result<Option<u64>, HexError> {
+    let usage = "--memory-limit <size>";
+    match matches.get_one::<String>(ARG_MEM) {
+        Some(size) => match parse_offset(size, usage, None)? {
+            0 => Err(HexError::InvalidValue {
+                usage,
+                reason: "the limit must be at least one byte".to_string(),
+            }),
+            limit => Ok(Some(limit)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Fail when the buffers the dump itself needs take more than the
+/// `--memory-limit`, before anything is read: the read-ahead blocks, the
+/// direct I/O buffer or a single line of the whole input.
+fn check_memory_limit(config: &Config) -> Result<(), HexError> {
+    let usage = "--memory-limit <size>";
+    let Some(limit) = config.memory_limit else {
+        return Ok(());
+    };
+    let reason = if config.columns == 0 {
+        "-c 0 holds the whole input on one line, give a line width".to_string()
+    } else if config.direct_io && DIRECT_BUFFER as u64 > limit {
+        format!("--direct-io reads through a {DIRECT_BUFFER} byte buffer, more than the limit of {limit}")
+    } else if config.readahead as u64 * READAHEAD_BLOCKS > limit {
+        format!(
+            "--io-readahead holds {READAHEAD_BLOCKS} blocks of {} bytes, more than the limit of {limit}",
+            config.readahead
+        )
+    } else {
+        return Ok(());
+    };
+    Err(HexError::InvalidValue { usage, reason })
+}
+
+/// Length of a file input, for `end` in offset expressions.
+fn file_len(path: Option<impl AsRef<Path>>) -> Option<u64> {
+    InputSource::new(path.as_ref().map(AsRef::as_ref))
+        .capabilities()
+        .len
+}
+
+/// Length of the input file, `--fd` descriptor or `--two-pass` copy, for
+/// `end` in offset expressions.
+fn input_file_len(input: &InputSource) -> Option<u64> {
+    match input {
+        InputSource::File(_) | InputSource::Fd(_) | InputSource::Spooled(_) => {
+            input.capabilities().len
+        }
+        _ => None,
+    }
+}
+
+/// State file of the input whose position `--continue` keeps.
+///
+/// # Arguments
+///
+/// * `input` - input file, stdin has no position to come back to.
+fn continue_state(input: Option<&Path>) -> Result<PathBuf, HexError> {
+    let usage = "--continue";
+    let path = match input {
+        Some(path) => path,
+        None => {
+            return Err(HexError::InvalidValue {
+                usage,
+                reason: "it needs an input file, stdin can't be continued".to_string(),
+            })
+        }
+    };
+    let dir = Dirs::current()
+        .state
+        .ok_or_else(|| HexError::InvalidValue {
+            usage,
+            reason: format!("no state directory, set {}", dirs::STATE_DIR_ENV),
+        })?;
+    Ok(dir.join(state::fingerprint(path)?))
+}
+
+/// Bookmarks file given on the command line, `@name` for the one of that
+/// name in the data directory.
+///
+/// # Arguments
+///
+/// * `value` - path or `@name`.
+/// * `usage` - option or argument, for errors.
+fn bookmarks_path(value: Option<&PathBuf>, usage: &'static str) -> Result<PathBuf, HexError> {
+    let Some(name) = value.map_or(Some(""), |path| path.to_str()) else {
+        // a path that isn't UTF-8 names no @name file
+        return Ok(value.cloned().unwrap_or_default());
+    };
+    dirs::named_file(
+        name,
+        Dirs::current().bookmarks(),
+        "marks",
+        dirs::DATA_DIR_ENV,
+    )
+    .map_err(|reason| HexError::InvalidValue { usage, reason })
+}
+
+/// Scalar offset, type and byte order from subcommand matches.
+fn scalar_spec(matches: &ArgMatches) -> Result<ScalarSpec, HexError> {
+    Ok(ScalarSpec {
+        at: match matches.get_one::<String>(ARG_AT) {
+            Some(at) => parse_offset(
+                at,
+                "--at <offset>",
+                file_len(matches.get_one::<PathBuf>(ARG_FIL)),
+            )?,
+            None => 0,
+        },
+        kind: matches
+            .get_one::<String>(ARG_TYP)
+            .and_then(|kind| ScalarType::parse(kind))
+            .unwrap_or(ScalarType::U8),
+        endian: match matches.get_one::<String>(ARG_END).map(String::as_str) {
+            Some("big") => Endian::Big,
+            _ => Endian::Little,
+        },
+    })
+}
+
+/// Backup, replacement, journal, undo and confirmation options from the
+/// matches of a mutating subcommand.
+fn write_guard(matches: &ArgMatches) -> WriteGuard {
+    WriteGuard {
+        backup: matches.get_one::<String>(ARG_BAK).cloned(),
+        no_clobber: matches.get_flag(ARG_NCL),
+        atomic: matches.get_flag(ARG_ATM),
+        journal: matches.get_one::<PathBuf>(ARG_JRN).cloned(),
+        reverse_patch: matches.get_one::<PathBuf>(ARG_RVP).cloned(),
+        yes: matches.get_flag(ARG_YES),
+    }
+}
+
+/// Parse an inclusive bit range `low:high`, or a single bit.
+fn parse_bits(value: &str, kind: ScalarType) -> Result<(u32, u32), HexError> {
+    let usage = "--bits <low:high>";
+    let (low, high) = match value.split_once(':') {
+        Some((low, high)) => (parse_value(low, usage)?, parse_value(high, usage)?),
+        None => {
+            let bit = parse_value(value, usage)?;
+            (bit, bit)
+        }
+    };
+    let width = kind.width() as u32 * 8;
+    if low > high || high >= width {
+        return Err(HexError::InvalidValue {
+            usage,
+            reason: format!("bits {value} don't fit in a {width} bit {kind}"),
+        });
+    }
+    Ok((low, high))
+}
+
+/// Values of `--format`, with the names they can also be given by
+pub(crate) const FORMATS: Choices = Choices {
+    values: &[
+        ("o", "octal"),
+        ("x", "hex"),
+        ("X", "upperhex"),
+        ("b", "binary"),
+        ("e", "exp"),
+        ("E", "upperexp"),
+    ],
+    aliases: &[
+        ("oct", "o"),
+        ("lowerhex", "x"),
+        ("bin", "b"),
+        ("lowerexp", "e"),
+    ],
+};
+
+/// Values of `--array`, with the names they can also be given by
+pub(crate) const ARRAY_FORMATS: Choices = Choices {
+    values: &[
+        ("r", "rust"),
+        ("c", "c"),
+        ("g", "golang"),
+        ("p", "python"),
+        ("k", "kotlin"),
+        ("j", "java"),
+        ("s", "swift"),
+        ("f", "fsharp"),
+    ],
+    aliases: &[
+        ("rs", "r"),
+        ("go", "g"),
+        ("py", "p"),
+        ("kt", "k"),
+        ("fs", "f"),
+    ],
+};
+
+/// Values of `--color`, auto leaving it to `NO_COLOR` and the terminal
+const COLORS: Choices = Choices {
+    values: &[("1", "always"), ("0", "never"), ("auto", "auto")],
+    aliases: &[("on", "1"), ("off", "0")],
+};
+
+/// Values of `--prefix`
+const PREFIXES: Choices = Choices {
+    values: &[("1", "on"), ("0", "off")],
+    aliases: &[],
+};
+
+/// Value parser for a short list of one letter values that also takes
+/// their names and aliases, rejecting anything else with the choices and
+/// the closest one
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Choices {
+    /// values with their names
+    pub(crate) values: &'static [(&'static str, &'static str)],
+    /// more names, with the value they stand for
+    aliases: &'static [(&'static str, &'static str)],
+}
+
+/// Choices implementation
+impl Choices {
+    /// Every value with its name, e.g. `r (rust), c, g (golang)`.
+    fn listed(&self) -> String {
+        self.values
+            .iter()
+            .map(|(value, name)| match value == name {
+                true => value.to_string(),
+                false => format!("{value} ({name})"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Value given as itself, its name or an alias, ignoring the case of
+    /// names.
+    pub(crate) fn resolve(&self, text: &str) -> Option<&'static str> {
+        let named = |name: &str| name.eq_ignore_ascii_case(text);
+        self.values
+            .iter()
+            .find(|(value, name)| *value == text || named(name))
+            .map(|(value, _)| *value)
+            .or_else(|| {
+                self.aliases
+                    .iter()
+                    .find(|(alias, _)| named(alias))
+                    .map(|(_, value)| *value)
+            })
+    }
+
+    /// Value a mistyped one was most likely meant to be: the closest value
+    /// or name within an edit for every two characters typed, ignoring
+    /// case.
+    fn closest(&self, text: &str) -> Option<(&'static str, &'static str)> {
+        let text = text.to_lowercase();
+        let limit = text.chars().count() / 2;
+        self.values
+            .iter()
+            .map(|&(value, name)| {
+                let distance =
+                    edit_distance(&text, &value.to_lowercase()).min(edit_distance(&text, name));
+                (distance, (value, name))
+            })
+            .filter(|(distance, _)| *distance <= limit)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, choice)| choice)
+    }
+}
+
+impl TypedValueParser for Choices {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<String, clap::Error> {
+        let text = value.to_string_lossy();
+        if let Some(value) = self.resolve(&text) {
+            return Ok(value.to_string());
+        }
+        let arg = arg.map_or("...".to_string(), ToString::to_string);
+        let mut message = format!(
+            "invalid value '{text}' for '{arg}'\n  [possible values: {}]\n",
+            self.listed()
+        );
+        if let Some((value, name)) = self.closest(&text) {
+            message.push_str(&format!("\n  tip: did you mean '{value}' ({name})?\n"));
+        }
+        Err(clap::Error::raw(ErrorKind::InvalidValue, message).with_cmd(cmd))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(self.values.iter().map(|(value, name)| {
+            let aliases = self.aliases.iter().filter(|(_, of)| of == value);
+            PossibleValue::new(value)
+                .help(name)
+                .aliases(aliases.map(|(alias, _)| alias))
+        })))
+    }
+}
+
+/// Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match ca == *cb {
+                true => diagonal,
+                false => 1 + diagonal.min(above).min(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Parse a 0/1 switch value.
+fn parse_switch(value: &str, usage: &'static str) -> Result<bool, HexError> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(HexError::InvalidValue {
+            usage,
+            reason: format!("invalid switch value {value:?}"),
+        }),
+    }
+}
+
+/// Parse a 1 based index range: `N`, `N-M` or `N-`.
+fn parse_range(value: &str, usage: &'static str) -> Result<IndexRange, HexError> {
+    let range = match value.split_once('-') {
+        None => {
+            let index = parse_value(value, usage)?;
+            IndexRange {
+                start: index,
+                end: Some(index),
+            }
+        }
+        Some((start, "")) => IndexRange {
+            start: parse_value(start, usage)?,
+            end: None,
+        },
+        Some((start, end)) => IndexRange {
+            start: parse_value(start, usage)?,
+            end: Some(parse_value(end, usage)?),
+        },
+    };
+    if range.start == 0 || range.end.is_some_and(|end| end < range.start) {
+        return Err(HexError::InvalidValue {
+            usage,
+            reason: format!("invalid range {value:?}, indexes start at 1"),
+        });
+    }
+    Ok(range)
+}
+
+/// Parse command line arguments without exiting the process, so fuzzers
+/// and tests can drive the parser directly. Neither the input nor the files
+/// the options name are read, `Config::load` does that when the run starts.
+///
+/// # Arguments
+///
+/// * `args` - arguments following the program name.
+pub fn parse_args(args: &[&str]) -> Result<Config, HexError> {
+    let argv = std::iter::once("hx").chain(args.iter().copied());
+    let matches = command().try_get_matches_from(argv)?;
+    Config::from_matches(&matches)
+}
+
+/// Offset, type and byte order options shared by the scalar subcommands.
+fn scalar_args() -> [Arg; 3] {
+    [
+        Arg::new(ARG_AT)
+            .action(clap::ArgAction::Set)
+            .long(ARG_AT)
+            .value_name("offset")
+            .help("Offset of the scalar, decimal or hex with 0x")
+            .required(true)
+            .num_args(1),
+        Arg::new(ARG_TYP)
+            .action(clap::ArgAction::Set)
+            .long(ARG_TYP)
+            .value_name("type")
+            .help("Scalar type")
+            .value_parser(SCALAR_TYPES)
+            .default_value("u8")
+            .num_args(1),
+        Arg::new(ARG_END)
+            .action(clap::ArgAction::Set)
+            .long(ARG_END)
+            .help("Byte order")
+            .value_parser(["little", "big"])
+            .default_value("little")
+            .num_args(1),
+    ]
+}
+
+/// Backup, replacement, journal, undo and confirmation options shared by
+/// the subcommands that change files.
+fn guard_args() -> [Arg; 6] {
+    [
+        Arg::new(ARG_BAK)
+            .action(clap::ArgAction::Set)
+            .long(ARG_BAK)
+            .value_name("suffix")
+            .help("Copy the original to <file><suffix> before changing it")
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value(".bak"),
+        Arg::new(ARG_NCL)
+            .action(clap::ArgAction::SetTrue)
+            .long(ARG_NCL)
+            .help("Fail instead of overwriting an existing backup")
+            .requires(ARG_BAK),
+        Arg::new(ARG_ATM)
+            .action(clap::ArgAction::SetTrue)
+            .long(ARG_ATM)
+            .help("Write a patched copy and rename it over the original"),
+        Arg::new(ARG_JRN)
+            .value_parser(clap::value_parser!(PathBuf))
+            .action(clap::ArgAction::Set)
+            .long(ARG_JRN)
+            .value_name("file")
+            .help("Append a JSON line per edit to <file>")
+            .long_help("Append a JSON line per edit: time, file, offset, old and new bytes, sha-256 before and after")
+            .num_args(1),
+        Arg::new(ARG_RVP)
+            .value_parser(clap::value_parser!(PathBuf))
+            .action(clap::ArgAction::Set)
+            .long(ARG_RVP)
+            .value_name("file")
+            .help("Write a patch file that undoes the edit, for hx undo")
+            .num_args(1),
+        Arg::new(ARG_YES)
+            .action(clap::ArgAction::SetTrue)
+            .long(ARG_YES)
+            .help("Change the file without asking first")
+            .long_help("Change the file without asking first; required when stdin or stdout isn't a terminal"),
+    ]
+}
+
+/// Bookmarks file argument of the bookmark subcommands.
+fn bookmarks_file() -> Arg {
+    Arg::new(ARG_FIL)
+        .value_parser(clap::value_parser!(PathBuf))
+        .help("Bookmarks file")
+        .required(true)
+        .index(1)
+}
+
+/// The hx command line definition.
+pub fn command() -> Command {
+    let desc = format!(
+        "{}\n{}",
+        env!("CARGO_PKG_DESCRIPTION"),
+        env!("CARGO_PKG_HOMEPAGE")
+    );
+    let app = Command::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(desc)
+        .after_long_help(examples::help_section())
+        .arg(
+            Arg::new(ARG_COL)
+                .action(clap::ArgAction::Set)
+                .short('c')
+                .long(ARG_COL)
+                .value_name("columns")
+                .help("Set column length, 0 for no wrapping, or auto")
+                .long_help("Set column length, 0 for no wrapping, or auto for as many as fit in $COLUMNS (80 when unset)")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_STK)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_STK)
+                .help("Print the characters of every line under its bytes, like od -bc")
+                .long_help("Print the characters of every line on a second row, each under its byte, like od -bc")
+                .conflicts_with_all([ARG_ARR, ARG_CHS, ARG_UNQ]),
+        )
+        .arg(
+            Arg::new(ARG_EXP)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_EXP)
+                .help("Print every byte on its own line in every base")
+                .long_help("Print every byte on its own line in binary, octal, decimal and hex with its ascii name, for short inputs or --range")
+                .conflicts_with_all([ARG_ARR, ARG_STK, ARG_UNQ, ARG_DDS]),
+        )
+        .arg(
+            Arg::new(ARG_TRN)
+                .action(clap::ArgAction::Set)
+                .long(ARG_TRN)
+                .value_name("record_size")
+                .help("Dump fixed size records column-major")
+                .long_help("Dump fixed size records column-major: a row per record byte, holding that byte of --cols records")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_STK, ARG_EXP, ARG_RWB, ARG_UNQ, ARG_DDS, ARG_RNG, ARG_CRV, ARG_ALN, ARG_BND,
+                    ARG_BMK, ARG_SYM, ARG_SMP, ARG_RES,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RWB)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_RWB)
+                .help("Write the input bytes as they are instead of dumping them")
+                .long_help("Write the input bytes as they are, after --skip, --len, transforms, --from decoding, --concat or --interleave, instead of dumping them")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_STK, ARG_EXP, ARG_UNQ, ARG_DDS, ARG_RNG, ARG_CRV, ARG_HDR, ARG_FMT, ARG_STP,
+                    ARG_CHS, ARG_BND, ARG_BMK, ARG_SYM, ARG_ALN, ARG_BKS, ARG_SEP, ARG_SMP, ARG_PCP,
+                    ARG_PNL, ARG_ONL,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_NWR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_NWR)
+                .help("Dump the whole input on one line, the same as --cols 0")
+                .conflicts_with(ARG_COL),
+        )
+        .arg(
+            Arg::new(ARG_SEP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SEP)
+                .value_name("text")
+                .help("Write <text> after every byte instead of a space, e.g. '' or ','")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_GRP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_GRP)
+                .value_name("bytes")
+                .help("Print every <bytes> bytes as one integer word")
+                .long_help("Print every <bytes> bytes as one integer word in the --endian byte order, 16 columns by default")
+                .value_parser(["1", "2", "4", "8"])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_LEN)
+                .action(clap::ArgAction::Set)
+                .short('l')
+                .long(ARG_LEN)
+                .value_name(ARG_LEN)
+                .help("Set <len> bytes to read")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FMT)
+                .action(clap::ArgAction::Set)
+                .short('f')
+                .long(ARG_FMT)
+                .help("Set format of octet: o, x, X, b, or e, E for --float words")
+                .long_help("Set format of octet: Octal (o), LowerHex (x), UpperHex (X), Binary (b), or --float words in scientific notation (e, E)")
+                .value_parser(FORMATS)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FLT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FLT)
+                .value_name("type")
+                .help("Float words the e and E formats read, f32 by default")
+                .long_help("Float words the e and E formats read: f32 (default) or f64, little endian or with a be suffix for big endian")
+                .requires(ARG_FMT)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_INP)
+                .help("Pass file path as an argument, or input data may be passed via stdin")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false)
+                .index(1),
+        )
+        .arg(
+            Arg::new(ARG_CLR)
+                .action(clap::ArgAction::Set)
+                .short('t')
+                .long(ARG_CLR)
+                .value_name("when")
+                .help("Color tint terminal output: always (1), never (0) or auto")
+                .long_help("Color tint terminal output: always (1), never (0), or auto to color only a terminal without NO_COLOR set (default)")
+                .value_parser(COLORS)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_NCO)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_NCO)
+                .help("Don't color output, the same as --color never")
+                .conflicts_with(ARG_CLR),
+        )
+        .arg(
+            Arg::new(ARG_ARR)
+                .action(clap::ArgAction::Set)
+                .short('a')
+                .long(ARG_ARR)
+                .value_name("array_format")
+                .help("Set source code format output: r, c, g, p, k, j, s or f")
+                .long_help("Set source code format output: rust (r), C (c), golang (g), python (p), kotlin (k), java (j), swift (s), fsharp (f)")
+                .value_parser(ARRAY_FORMATS)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_REV)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_REV)
+                .help("Convert a dump back into bytes, like xxd -r")
+                .long_help("Convert a dump back into bytes, like xxd -r: lines are placed by their offsets and may come from any hx layout, xxd or hexdump; --endian sets the byte order of word cells")
+                .conflicts_with_all([ARG_ARR, ARG_FNC, ARG_SKP, ARG_LEN, ARG_PNL]),
+        )
+        .arg(
+            Arg::new(ARG_ABS)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_ABS)
+                .help("Write the --reverse bytes in place at their offsets")
+                .long_help("Write the --reverse bytes at the offsets their lines state in --outfile, without truncating it or filling the gaps, so a partial dump patches an image in place")
+                .requires_all([ARG_REV, ARG_OUT]),
+        )
+        .arg(
+            Arg::new(ARG_FNC)
+                .short('u')
+                .long(ARG_FNC)
+                .value_name("func_length")
+                .help("Set function wave length")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PLC)
+                .short('p')
+                .long(ARG_PLC)
+                .value_name("func_places")
+                .help("Set function wave output decimal places")
+                .requires(ARG_FNC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FNO)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FNO)
+                .value_name("output")
+                .help("Print the function wave as text, bytes or an array")
+                .long_help("Print the function wave as comma separated text, or scale it to bytes from 0 to 255 and dump them, or write them as an array with -a (default text)")
+                .value_parser(["text", "bytes"])
+                .requires(ARG_FNC)
+                .conflicts_with_all([ARG_INP, ARG_CAT, ARG_ILV, ARG_FD, ARG_REV])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PFX)
+                .action(clap::ArgAction::Set)
+                .short('r')
+                .long(ARG_PFX)
+                .value_name("on|off")
+                .help("Include prefix in output (e.g. 0x/0b/0o): on (1, default) or off (0)")
+                .value_parser(PREFIXES)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_STP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_STP)
+                .value_name("bytes")
+                .help("Dim every other group of <bytes> columns, when colored")
+                .long_help("Dim every other group of <bytes> columns to guide the eye across wide lines, when colored")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_HLT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_HLT)
+                .value_name("min:max")
+                .help("Highlight the byte cells whose value lies within <min:max>")
+                .long_help("Highlight the byte cells, or --float words, whose value lies within <min:max>, e.g. 0x20:0x7e or 1e-3:1, when colored; float words are colored by magnitude")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_CHS)
+                .action(clap::ArgAction::Set)
+                .long(ARG_CHS)
+                .value_name(ARG_CHS)
+                .help("Decode the character panel as ascii or UTF-16")
+                .long_help("Decode the character panel as ascii bytes, or UTF-16 code units in either byte order")
+                .value_parser(["ascii", "utf16le", "utf16be"])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_ONL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_ONL)
+                .value_name("class")
+                .help("Show only the printable, nonzero or high bytes")
+                .long_help("Show only the printable, nonzero or high (0x80 and above) bytes, leaving the others blank, or dimmed when colored, in their place")
+                .value_parser(["printable", "nonzero", "high"])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PNL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_PNL)
+                .value_name("list")
+                .help("Panels of every dump line, in order")
+                .long_help("Panels of every dump line in order, each as often as wanted: offset, hex (the --format), decimal, ascii (the --charset) and annotations [default: offset,hex,ascii,annotations]")
+                .conflicts_with(ARG_RES)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_BRD)
+                .action(clap::ArgAction::Set)
+                .long(ARG_BRD)
+                .value_name("style")
+                .help("Draw lines around the panels")
+                .long_help("Draw lines around the offset, byte and character panels, with box-drawing or ascii characters")
+                .value_parser(["none", "ascii", "unicode"])
+                .default_value("none")
+                .conflicts_with_all([ARG_RES, ARG_REV])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PRF)
+                .action(clap::ArgAction::Set)
+                .long(ARG_PRF)
+                .value_name("profile")
+                .help("What the output may use: full (default) or safe")
+                .long_help("What the output may use: full (default), or safe for chats, IRC and limited terminals, the 16 basic colors alone and ascii, box-drawing as | - + and other characters as dots")
+                .value_parser(["full", "safe"])
+                .conflicts_with_all([ARG_RWB, ARG_REV])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_XOR)
+                .action(clap::ArgAction::Append)
+                .long(ARG_XOR)
+                .value_name("key")
+                .help("XOR the input with a repeating key")
+                .long_help("XOR the input with a repeating key, a decimal byte or hex bytes with 0x; transforms apply in the order given")
+                .conflicts_with_all([ARG_SMP, ARG_RES])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_ROT)
+                .action(clap::ArgAction::Append)
+                .long(ARG_ROT)
+                .value_name("n")
+                .help("Rotate ascii letters of the input <n> places through the alphabet, e.g. 13")
+                .conflicts_with_all([ARG_SMP, ARG_RES])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RVB)
+                .action(clap::ArgAction::Append)
+                .long(ARG_RVB)
+                .value_name("width")
+                .help("Reverse the byte order of every <width> byte group")
+                .long_help("Reverse the byte order of every <width> byte group of the input, e.g. 4 to swap 32-bit endianness")
+                .conflicts_with_all([ARG_SMP, ARG_RES])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_DIL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_DIL)
+                .value_name("lanes")
+                .help("Keep every <lanes>th byte of the input, starting at --lane")
+                .long_help("Keep every <lanes>th byte of the input, starting at --lane, e.g. 2 for one EPROM of a 16-bit pair; offsets count kept bytes")
+                .conflicts_with_all([ARG_SMP, ARG_RES])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_LAN)
+                .action(clap::ArgAction::Set)
+                .long(ARG_LAN)
+                .value_name("lane")
+                .help("Lane --deinterleave keeps, counting from 0 (default 0)")
+                .requires(ARG_DIL)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SRO)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_SRO)
+                .help("Print the offset in the input file after every line offset")
+                .long_help("Print after every line offset the offset of its first byte in the input file, through --deinterleave, archive members and --zst frames; ~ marks the start of the compressed data holding it")
+                .conflicts_with_all([ARG_ILV, ARG_CAT, ARG_FRM]),
+        )
+        .arg(
+            Arg::new(ARG_FRM)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FRM)
+                .value_name("format")
+                .help("Decode the input from Intel HEX, S-records or hex digit text")
+                .long_help("Decode the input from Intel HEX (ihex), S-records (srec) or hex digit text (hex) and dump the bytes at their addresses")
+                .value_parser(["ihex", "srec", "hex"])
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_PCP, ARG_SQL, ARG_SMP, ARG_RES, ARG_CNT])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_STR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_STR)
+                .help("Fail on the first malformed record of --from input")
+                .long_help("Fail on the first malformed record or character of --from input, instead of skipping its line with a warning")
+                .requires(ARG_FRM),
+        )
+        .arg(
+            Arg::new(ARG_GAP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_GAP)
+                .value_name("byte|skip")
+                .help("Fill gaps between --from records with a byte, or skip them")
+                .long_help("Fill gaps between --from records with a byte, e.g. 0xff or 0x00 (default), or skip them and mark them with <gap> lines")
+                .requires(ARG_FRM)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_OCF)
+                .action(clap::ArgAction::Set)
+                .long(ARG_OCF)
+                .value_name("policy")
+                .help("What to do where --from records overlap")
+                .long_help("Where --from records overlap, fail listing the ranges (error, default), or keep the first or last record written")
+                .value_parser(["error", "first", "last"])
+                .requires(ARG_FRM)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_BKS)
+                .action(clap::ArgAction::Set)
+                .long(ARG_BKS)
+                .value_name("size")
+                .help("Size of the banks of a banked ROM")
+                .long_help("Size of the banks of a banked ROM; offsets and symbol lookups use bank * --bank-stride + offset in bank")
+                .requires(ARG_BKT)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_BKT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_BKT)
+                .value_name("bytes")
+                .help("Address distance between consecutive banks")
+                .long_help("Address distance between consecutive banks, e.g. 0x10000 to show the bank number above 16-bit addresses")
+                .requires(ARG_BKS)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_HDR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_HDR)
+                .help("Print file name, size, type and options before the dump")
+                .long_help("Print file name, size, modification time, detected type and options before the dump")
+                .conflicts_with_all([ARG_ARR, ARG_RES]),
+        )
+        .arg(
+            Arg::new(ARG_HHS)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_HHS)
+                .help("Include the sha-256 of the file in the --header block")
+                .requires(ARG_HDR),
+        )
+        .arg(
+            Arg::new(ARG_PGR)
+                .action(clap::ArgAction::Set)
+                .long(ARG_PGR)
+                .value_name("when")
+                .help("Pipe output through $PAGER: auto, always or never (default)")
+                .long_help("Pipe output through $PAGER, or less -RFX, when stdout is a terminal (auto), always, or never (default)")
+                .value_parser(["auto", "always", "never"])
+                .conflicts_with(ARG_OUT)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_LBF)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_LBF)
+                .help("Flush output after every line, e.g. when tailing a live pipe or socket"),
+        )
+        .arg(
+            Arg::new(ARG_IRA)
+                .action(clap::ArgAction::Set)
+                .long(ARG_IRA)
+                .value_name("size")
+                .help("Read input files ahead in blocks of this many bytes")
+                .long_help("Read input files ahead on a background thread in blocks of this many bytes, 1 MiB by default, 0 to read as the dump goes")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_MEM)
+                .action(clap::ArgAction::Set)
+                .long(ARG_MEM)
+                .value_name("size")
+                .help("Fail instead of holding more than <size> bytes in memory")
+                .long_help("Fail with an error instead of running out of memory when a feature would hold more than <size> bytes, optionally in KiB, MiB or GiB: whole inputs read by --rle, --dedupe-scan, --carve and the like, files compared by match and check, the --expect diff and the read-ahead blocks, which shrink to fit unless --io-readahead is given")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_DIO)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_DIO)
+                .help("Read input files with O_DIRECT, around the page cache")
+                .long_help("Read input files with O_DIRECT, around the page cache, to see what's on the disk and measure its real throughput (Linux only)")
+                .conflicts_with_all([ARG_IRA, ARG_SMP, ARG_CAT, ARG_ILV]),
+        )
+        .arg(
+            Arg::new(ARG_QUI)
+                .action(clap::ArgAction::Count)
+                .short('q')
+                .long(ARG_QUI)
+                .global(true)
+                .help("Print errors only")
+                .long_help("Print errors only: no warnings, nothing for match and check, the exit status alone tells: 0 when the file passes, 1 when it doesn't or can't be checked, and nothing for the subcommands changing files"),
+        )
+        .arg(
+            Arg::new(ARG_VRB)
+                .action(clap::ArgAction::Count)
+                .short('v')
+                .long(ARG_VRB)
+                .global(true)
+                .conflicts_with(ARG_QUI)
+                .help("Report how options resolved and the timing to stderr")
+                .long_help("Report how options resolved and the timing to stderr, -vv also every I/O decision"),
+        )
+        .arg(
+            Arg::new(ARG_LGF)
+                .action(clap::ArgAction::Set)
+                .long(ARG_LGF)
+                .global(true)
+                .value_name("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("Write reports and the summary to stderr as text or json")
+                .long_help("Write warnings, errors, match and check reports and the byte count summary to stderr as text, or as JSON lines with json, keeping stdout to the dump alone"),
+        )
+        .arg(
+            Arg::new(ARG_RDO)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_RDO)
+                .global(true)
+                .help("Refuse to change files")
+                .long_help(format!(
+                    "Refuse to change files, by subcommands or by writing over them, also set by {READ_ONLY_ENV}=1"
+                )),
+        )
+        .arg(
+            Arg::new(ARG_CAP)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_CAP)
+                .help("Print the features of this build as JSON")
+                .long_help("Print the formats, array languages, output formats, subcommands, options and optional features of this build as JSON")
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new(ARG_TIM)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_TIM)
+                .help("Print elapsed time, bytes processed and throughput to stderr"),
+        )
+        .arg(
+            Arg::new(ARG_TSP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_TSP)
+                .value_name("clock")
+                .help("Print the time each line was read before its offset")
+                .long_help("Print the time each line was read before its offset: UTC time of day (abs, the default) or seconds since the previous line (=delta), for timing live streams")
+                .value_parser(["abs", "delta"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("abs")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_RWB, ARG_STK, ARG_EXP, ARG_TRN, ARG_UNQ, ARG_DDS, ARG_CAU,
+                    ARG_RLE, ARG_SPL, ARG_DEC,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_LSC)
+                .action(clap::ArgAction::Set)
+                .long(ARG_LSC)
+                .value_name("scope")
+                .help("Whether --len applies to each file or to all of them")
+                .long_help("Whether --len applies to each of --files-from or --recursive files (file), or is one budget for all of them together (total)")
+                .value_parser(["file", "total"])
+                .default_value("file")
+                .requires(ARG_LEN),
+        )
+        .arg(
+            Arg::new(ARG_CTO)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_CTO)
+                .help("Number the offsets of the files on from one to the next")
+                .long_help("Number the offsets of --files-from or --recursive files on from one file to the next, as if they were cat'ed together, with each file's offset in its header"),
+        )
+        .arg(
+            Arg::new(ARG_DRP)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_DRP)
+                .help("List the groups of identical files instead of dumping them")
+                .long_help("Instead of dumping the --files-from or --recursive files, list the groups of identical ones by sha-256, with the bytes the duplicates take")
+                .conflicts_with_all([ARG_CTO, ARG_CMT, ARG_ARR]),
+        )
+        .arg(
+            Arg::new(ARG_MTR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_MTR)
+                .help("When --len cuts the dump short, say how much was left out")
+                .long_help("When --len cuts the dump short, end it with how many bytes were left out, as a comment for arrays")
+                .requires(ARG_LEN),
+        )
+        .arg(
+            Arg::new(ARG_FRG)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_FRG)
+                .help("Leave out the array declaration or the dump summary")
+                .long_help("Leave out the array declaration and closing, or the dump summary, so outputs of separate runs concatenate; wrap array fragments with --finalize")
+                .conflicts_with_all([
+                    ARG_STK, ARG_EXP, ARG_TRN, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RLE, ARG_SPL, ARG_FRA,
+                    ARG_DEC, ARG_HDR, ARG_RES,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_FIN)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_FIN)
+                .help("Wrap concatenated --fragment arrays in one declaration")
+                .long_help("Read concatenated --fragment arrays from the input and wrap them in one array declaration")
+                .requires(ARG_ARR)
+                .conflicts_with(ARG_FRG),
+        )
+        .arg(
+            Arg::new(ARG_OVL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_OVL)
+                .value_name("patch")
+                .help("Show the input with an hx patch file applied")
+                .long_help("Show the input with an hx patch file applied, e.g. from --reverse-patch, marking the changed bytes in the margin, without changing the file")
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_SMP, ARG_RES, ARG_FRM, ARG_PCP, ARG_SQL])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RED)
+                .action(clap::ArgAction::Set)
+                .long(ARG_RED)
+                .value_name("ranges")
+                .help("Overwrite byte ranges with --redact-fill in every output")
+                .long_help("Overwrite byte ranges with --redact-fill in every output, marking each in the margin, e.g. 0x100:0x140,0x200+16 to share a dump without keys or personal data")
+                .conflicts_with(ARG_SMP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RDF)
+                .action(clap::ArgAction::Set)
+                .long(ARG_RDF)
+                .value_name("byte")
+                .help("Byte written over --redact ranges, 0 by default")
+                .requires(ARG_RED)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SES)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SES)
+                .value_name("file")
+                .help("Restore and save the input and dump options in <file>")
+                .long_help("Restore the input and dump options saved in <file>, options on the command line taking precedence, and save them back after the run")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RAW)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_RAW)
+                .help("Print plain byte counts, without digit grouping or IEC units"),
+        )
+        .arg(
+            Arg::new(ARG_RPR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_RPR)
+                .help("Make the output the same on every machine and run")
+                .long_help("Make the output the same on every machine and run, for committing dumps and arrays: no color even with --color, English summary with plain sizes, no modification time in --header, and 80 columns for -c auto")
+                .conflicts_with_all([ARG_TSP, ARG_TIM]),
+        )
+        .arg(
+            Arg::new(ARG_CAT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_CAT)
+                .value_name("files")
+                .help("Dump the files one after another as a single input")
+                .long_help("Dump the files one after another as a single input, e.g. the parts of a split ROM")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([ARG_INP, ARG_ILV, ARG_FFR, ARG_REC])
+                .num_args(2..),
+        )
+        .arg(
+            Arg::new(ARG_ILV)
+                .action(clap::ArgAction::Set)
+                .long(ARG_ILV)
+                .value_name("files")
+                .help("Dump the files interleaved a --word at a time")
+                .long_help("Dump the files interleaved a --word at a time as a single input, e.g. the hi and lo EPROMs of a 16-bit ROM, ending with the shortest")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([ARG_INP, ARG_FFR, ARG_REC])
+                .num_args(2..),
+        )
+        .arg(
+            Arg::new(ARG_FD)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FD)
+                .value_name("number")
+                .help("Read the input from an inherited file descriptor")
+                .long_help("Read the input from a file descriptor inherited from the parent, e.g. 3 after exec 3< file, or a handle number on Windows")
+                .conflicts_with_all([ARG_INP, ARG_CAT, ARG_ILV, ARG_FFR, ARG_REC])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_GEN)
+                .action(clap::ArgAction::Set)
+                .long(ARG_GEN)
+                .value_name("expression")
+                .help("Dump generated test data instead of an input")
+                .long_help("Dump generated test data instead of an input: count:0..255 for every byte from one value to another, repeat:deadbeef*64 for bytes as hx echo takes them, or lfsr:poly=0x1d,seed=0xff,len=1024 for the bit stream of a Galois LFSR, with width=<bits> for registers other than 8 bits")
+                .conflicts_with_all([ARG_INP, ARG_CAT, ARG_ILV, ARG_FD, ARG_FNC, ARG_REV, ARG_FFR, ARG_REC])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_TWP)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_TWP)
+                .help("Read a streamed input to a temporary file first")
+                .long_help("Read a streamed input, such as stdin, to the end into a temporary file first, so its length is known: end in offsets, --sample seeking and --mark-truncated counts then work as on a file")
+                .conflicts_with_all([ARG_CAT, ARG_ILV, ARG_FFR, ARG_REC]),
+        )
+        .arg(
+            Arg::new(ARG_TMD)
+                .long(ARG_TMD)
+                .value_name("dir")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Directory of the --two-pass temporary file")
+                .long_help("Directory of the --two-pass temporary file, instead of $HX_TEMP_DIR or the system's; it's removed when hx ends or is interrupted")
+                .requires(ARG_TWP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_WRD)
+                .action(clap::ArgAction::Set)
+                .long(ARG_WRD)
+                .value_name("bytes")
+                .help("Bytes taken from each --interleave file in turn (default 1)")
+                .requires(ARG_ILV)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FFR)
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(clap::ArgAction::Set)
+                .long(ARG_FFR)
+                .value_name("list")
+                .help("Dump every file named in <list>, - for stdin")
+                .long_help("Dump every file named in <list>, one per line or NUL delimited; - reads the list from stdin")
+                .conflicts_with(ARG_INP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_REC)
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(clap::ArgAction::Set)
+                .long(ARG_REC)
+                .value_name("dir")
+                .help("Dump every file found walking <dir> recursively")
+                .conflicts_with_all([ARG_INP, ARG_FFR])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_GLB)
+                .action(clap::ArgAction::Set)
+                .long(ARG_GLB)
+                .value_name("pattern")
+                .help("Only dump files whose name matches <pattern>, e.g. '*.bin', in --recursive mode")
+                .requires(ARG_REC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SQL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SQL)
+                .value_name("page")
+                .help("Annotate the b-tree header of sqlite database page <page> and dump the page")
+                .conflicts_with_all([ARG_FFR, ARG_REC])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PCP)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_PCP)
+                .help("Dump every packet of a pcap or pcapng capture with its index, time and length")
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_SQL]),
+        )
+        .arg(
+            Arg::new(ARG_PKT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_PKT)
+                .value_name("range")
+                .help("Only dump packets in <range>, e.g. 5, 5-10 or 5-, counting from 1")
+                .requires(ARG_PCP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_OFM)
+                .action(clap::ArgAction::Set)
+                .long(ARG_OFM)
+                .value_name("format")
+                .help("Write the dump as text, JSON lines, plain hex, HTML or zst")
+                .long_help("Write the dump as a human readable dump, JSON lines of offset, bytes, hex and ascii, plain hex digits like xxd -p, 30 bytes a line, the dump as HTML spans in the dump colors, or, with the zstd feature, the bytes as a seekable zstd archive --zst reopens; json and plain are never colored")
+                .value_parser(output_formats())
+                .conflicts_with_all([ARG_ARR, ARG_STK, ARG_EXP, ARG_RWB, ARG_TRN])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_EXC)
+                .action(clap::ArgAction::Set)
+                .long(ARG_EXC)
+                .value_name("path")
+                .help("Compare the output with the golden dump in <path>")
+                .long_help("Compare the output with the golden dump in <path> instead of writing it, and fail with a unified diff when they differ; uncolored unless --color asks, in English unless --lang is set")
+                .conflicts_with_all([ARG_OUT, ARG_PGR, ARG_CNT])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_OUT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_OUT)
+                .value_name("path")
+                .help("Write output to <path> instead of stdout")
+                .value_parser(clap::value_parser!(PathBuf))
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_ALS)
+                .action(clap::ArgAction::Append)
+                .long(ARG_ALS)
+                .value_name("format=path")
+                .help("Also write the dumped bytes in <format> to <path>")
+                .long_help("Also write the dumped bytes in <format> to <path>, from the same read, e.g. dump=/dev/stderr next to an array; takes the --output formats, array in the -a language or rust, and raw; repeatable")
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_FRM])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RES)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_RES)
+                .help("Continue an interrupted dump or array in --outfile from its last complete line")
+                .requires(ARG_OUT)
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_PCP, ARG_SQL]),
+        )
+        .arg(
+            Arg::new(ARG_SMP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SMP)
+                .value_name("count")
+                .help("Dump <count> randomly chosen windows of --len bytes")
+                .long_help("Dump <count> randomly chosen windows of --len bytes (default 4 lines) with their offsets")
+                .value_parser(clap::builder::RangedU64ValueParser::<u64>::new().range(1..))
+                .conflicts_with_all([ARG_ARR, ARG_FFR, ARG_REC, ARG_PCP, ARG_SQL, ARG_RES])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SED)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SED)
+                .value_name("seed")
+                .help("Seed for --sample, the same seed picks the same windows (default 0)")
+                .requires(ARG_SMP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SKP)
+                .action(clap::ArgAction::Set)
+                .short('s')
+                .long(ARG_SKP)
+                .visible_alias("seek")
+                .value_name("offset")
+                .help("Skip <offset> bytes of input")
+                .long_help("Skip <offset> bytes of input, decimal or hex with 0x, offsets count from the start")
+                .conflicts_with(ARG_SMP)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_CNT)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_CNT)
+                .help("Start where the last --continue of this input left off")
+                .long_help("Start where the last --continue of this input left off and remember where this one ends, --skip starts over")
+                .conflicts_with_all([ARG_FFR, ARG_REC, ARG_PCP, ARG_SQL, ARG_RES, ARG_SMP, ARG_RNG, ARG_CRV]),
+        )
+        .arg(
+            Arg::new(ARG_ALN)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_ALN)
+                .help("Pad the first line after --skip so offsets stay aligned to the column width")
+                .conflicts_with(ARG_RES),
+        )
+        .arg(
+            Arg::new(ARG_RNG)
+                .action(clap::ArgAction::Set)
+                .long(ARG_RNG)
+                .value_name("ranges")
+                .help("Dump only these byte ranges, comma separated, each start:end or start+len")
+                .conflicts_with_all([ARG_SKP, ARG_LEN, ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_CRV)
+                .value_parser(clap::value_parser!(OsString))
+                .action(clap::ArgAction::Set)
+                .long(ARG_CRV)
+                .value_name("ranges")
+                .help("Dump byte ranges and write each to a file")
+                .long_help("Dump byte ranges like --range and write each to a file, comma separated, each start:end=file or start+len=file")
+                .conflicts_with_all([ARG_RNG, ARG_SKP, ARG_LEN, ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_BND)
+                .action(clap::ArgAction::Set)
+                .long(ARG_BND)
+                .value_name("size")
+                .help("Put a rule line before every multiple of <size> bytes")
+                .long_help("Put a rule line before every multiple of <size> bytes and highlight its offset: page (4096), sector (512), or a size in bytes")
+                .conflicts_with(ARG_RES)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_BMK)
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(clap::ArgAction::Set)
+                .long(ARG_BMK)
+                .value_name("file")
+                .help("Label lines holding an offset from a bookmarks file, as written by hx bookmark")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SYM)
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(clap::ArgAction::Set)
+                .long(ARG_SYM)
+                .value_name("file")
+                .help("Label line offsets with symbols from <file>")
+                .long_help("Label line offsets with name+0x1c from a linker map, nm listing or ELF symbol table")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_UNQ)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_UNQ)
+                .help("Print each distinct line once with its occurrence count, most frequent first")
+                .conflicts_with_all([ARG_ARR, ARG_SMP, ARG_RES, ARG_ALN]),
+        )
+        .arg(
+            Arg::new(ARG_DDS)
+                .action(clap::ArgAction::Set)
+                .long(ARG_DDS)
+                .value_name("block_size")
+                .help("Report ranges whose content repeats earlier in the input")
+                .long_help("Report ranges of at least <block_size> bytes whose content repeats earlier in the input")
+                .conflicts_with_all([ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_CAU)
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(clap::ArgAction::Set)
+                .long(ARG_CAU)
+                .value_name("dir")
+                .help("Extract embedded files into <dir> and list them")
+                .long_help("Scan the input for embedded PNG, JPEG, ZIP and gzip files, extract each into <dir> named after its offset and list them")
+                .conflicts_with_all([ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_RNG, ARG_CRV])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_RLE)
+                .action(clap::ArgAction::Set)
+                .long(ARG_RLE)
+                .value_name("min_run")
+                .help("List runs of at least <min_run> equal bytes")
+                .long_help("List runs of at least <min_run> equal bytes, e.g. 0xff erased flash or padding, and dump the input with each run on one line")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_CMT)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_CMT)
+                .help("Print only the number of --find matches, like grep -c")
+                .long_help("Print only the number of --find matches, overlapping ones included, like grep -c, as <file>:<count> for each of --files-from or --recursive files")
+                .requires(ARG_FND)
+                .conflicts_with_all([ARG_OOF, ARG_MJS, ARG_HDR]),
+        )
+        .arg(
+            Arg::new(ARG_OOF)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_OOF)
+                .help("List the offset of every match instead of dumping lines")
+                .long_help("List the offset of every --find, --find-int or --find-float match or --pointer-scan pointer, one a line, instead of dumping their lines"),
+        )
+        .arg(
+            Arg::new(ARG_MJS)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_MJS)
+                .help("List every match as a JSON line instead of dumping lines")
+                .long_help("List every --find, --find-int or --find-float match or --pointer-scan pointer as a JSON line of its offset, length, hex bytes and number or pointer value, instead of dumping their lines")
+                .conflicts_with(ARG_OOF),
+        )
+        .arg(
+            Arg::new(ARG_TXR)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_TXR)
+                .help("Report on the input as text instead of dumping it")
+                .long_help("Report on the input as text instead of dumping it: byte order mark, LF, CRLF and CR line endings, line count, longest line and NUL bytes")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV, ARG_FND, ARG_FNI, ARG_FNF, ARG_PSC,
+                    ARG_DET, ARG_OFM,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_DET)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_DET)
+                .help("Report the likely encodings of the input")
+                .long_help("Report the likely encodings of the first 64 KiB, utf-8, utf-16le/be, latin-1 or shift-jis, with their confidence, and dump the input with the character panel in the best one unless --charset is given")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV, ARG_FND, ARG_FNI, ARG_FNF,
+                    ARG_PSC,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_FND)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FND)
+                .value_name("pattern")
+                .help("Dump only the lines holding <pattern>")
+                .long_help("Dump only the lines holding <pattern>, its bytes highlighted and the offset of every match in the margin, even across lines: bytes as for hx echo, e.g. deadbeef or 'ELF', or else plain text")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PSC)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_PSC)
+                .help("Dump only the lines holding likely pointers")
+                .long_help("Dump only the lines holding aligned words whose value falls within --base and --size, likely pointers into that region, highlighted with their target in the margin")
+                .requires_all([ARG_BAS, ARG_SIZ])
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV, ARG_FND, ARG_FNI,
+                    ARG_FNF,
+                ]),
+        )
+        .arg(
+            Arg::new(ARG_BAS)
+                .action(clap::ArgAction::Set)
+                .long(ARG_BAS)
+                .value_name("address")
+                .help("First address of the --pointer-scan region, decimal or hex with 0x")
+                .requires(ARG_PSC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_SIZ)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SIZ)
+                .value_name("bytes")
+                .help("Bytes the --pointer-scan region spans, decimal or hex with 0x")
+                .requires(ARG_PSC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_PTT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_PTT)
+                .value_name("type")
+                .help("Pointers --pointer-scan reads, u64 by default")
+                .long_help("Pointers --pointer-scan reads: u64 (default) or u32, little endian or with a be suffix for big endian")
+                .requires(ARG_PSC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FNI)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FNI)
+                .value_name("value")
+                .help("Dump only the lines holding an integer")
+                .long_help("Dump only the lines holding the bytes of an integer of --type, in the --endian byte order or both, highlighted with the type, order and value in the margin: decimal, hex with 0x or negative, e.g. 305419896 or 0x12345678")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV, ARG_FND, ARG_PSC,
+                ])
+                .allow_hyphen_values(true)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FNF)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FNF)
+                .value_name("value")
+                .help("Dump only the lines holding a float near <value>")
+                .long_help("Dump only the lines holding a float of --type within --tolerance of <value>, at offsets a multiple of its width unless --unaligned, in the --endian byte order or both, highlighted with the type, order and value in the margin")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_FRA, ARG_DEC, ARG_REV, ARG_FND, ARG_FNI, ARG_PSC,
+                ])
+                .allow_hyphen_values(true)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_TYP)
+                .action(clap::ArgAction::Set)
+                .long(ARG_TYP)
+                .value_name("type")
+                .help("Type of the --find-int or --find-float value")
+                .long_help("Type of the --find-int value, an integer type (default u32), or of the --find-float value, f32 (default) or f64")
+                .value_parser(SCALAR_TYPES)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_NEA)
+                .action(clap::ArgAction::Set)
+                .long(ARG_NEA)
+                .value_name("integer")
+                .help("Also find integers up to <integer> off the --find-int value")
+                .long_help("Also find the integers up to <integer> above or below the --find-int value, e.g. 16 for a value that drifts")
+                .requires(ARG_FNI)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_TOL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_TOL)
+                .value_name("difference")
+                .help("Also find floats up to <difference> off the --find-float value")
+                .long_help("Also find the floats up to <difference> above or below the --find-float value, e.g. 1e-4 (default 0, the value exactly)")
+                .requires(ARG_FNF)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_UAL)
+                .action(clap::ArgAction::SetTrue)
+                .long(ARG_UAL)
+                .help("Look for --find-float values at every offset")
+                .long_help("Look for --find-float values at every offset, not only at multiples of their width")
+                .requires(ARG_FNF),
+        )
+        .arg(
+            Arg::new(ARG_SPL)
+                .action(clap::ArgAction::Set)
+                .long(ARG_SPL)
+                .value_name("bytes")
+                .help("Dump every field between <bytes> delimiters on its own")
+                .long_help("Dump every field between <bytes> delimiters on its own with its index and offset, e.g. 0x00 for string tables or \"0d 0a\"; bytes as for hx echo")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_FRA)
+                .action(clap::ArgAction::Set)
+                .long(ARG_FRA)
+                .value_name("framing")
+                .help("Dump every protocol frame on its own")
+                .long_help("Dump every protocol frame on its own with its index, offset and length, as frames arrive: len-prefixed:<u8|u16|u32|u64><be|le> for a length before each frame, or delim:<bytes> for a delimiter after it")
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL, ARG_DEC,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_DEC)
+                .action(clap::ArgAction::Set)
+                .long(ARG_DEC)
+                .value_name("structure")
+                .help("Walk the input as tlv entries or decode leb128 varints")
+                .long_help("Walk the input as type-length-value entries (tlv), printing each entry's offset, type and length over a dump of its value, or decode LEB128 varints (leb128) from --at")
+                .value_parser(["tlv", "leb128"])
+                .conflicts_with_all([
+                    ARG_ARR, ARG_SMP, ARG_RES, ARG_UNQ, ARG_DDS, ARG_CAU, ARG_RNG, ARG_CRV, ARG_STK, ARG_EXP,
+                    ARG_RWB,
+                    ARG_TRN, ARG_RLE, ARG_SPL,
+                ])
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_TSZ)
+                .action(clap::ArgAction::Set)
+                .long(ARG_TSZ)
+                .value_name("bytes")
+                .help("Bytes of the type field of --decode tlv (default 1)")
+                .requires(ARG_DEC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_LSZ)
+                .action(clap::ArgAction::Set)
+                .long(ARG_LSZ)
+                .value_name("bytes")
+                .help("Bytes of the length field of --decode tlv (default 1)")
+                .requires(ARG_DEC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_AT)
+                .action(clap::ArgAction::Set)
+                .long(ARG_AT)
+                .value_name("offset")
+                .help("Offset of the first --decode leb128 varint (default the first byte dumped)")
+                .requires(ARG_DEC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_QTY)
+                .action(clap::ArgAction::Set)
+                .long(ARG_QTY)
+                .value_name("varints")
+                .help("Number of --decode leb128 varints, one after another (default 1)")
+                .requires(ARG_DEC)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_END)
+                .action(clap::ArgAction::Set)
+                .long(ARG_END)
+                .help("Byte order of words, --decode fields and searched values")
+                .long_help("Byte order of the --group words and the --reverse word cells (default little), the --decode tlv type and length fields (default big) and the --find-int and --find-float values (default any, both)")
+                .value_parser(["little", "big", "any"])
+                .num_args(1),
+        )
+        .subcommand(Command::new(CMD_SELFTEST).about(
+            "Check every format, layout and array language against built-in golden output",
+        ))
+        .subcommand(
+            Command::new(CMD_COLORS)
+                .about("Print every byte value in the dump colors, with its class")
+                .long_about("Print every byte value in the dump colors and format, with its class: null, control, whitespace, printable or high"),
+        )
+        .subcommand(
+            Command::new(CMD_TABLE)
+                .about("Print the ascii table in the dump format and colors")
+                .long_about("Print the ascii table in the dump format and colors, with control character names")
+                .arg(
+                    Arg::new(ARG_EXT)
+                        .action(clap::ArgAction::SetTrue)
+                        .long(ARG_EXT)
+                        .help("Include 0x80-0xff as latin-1"),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_EXAMPLES)
+                .about("Print runnable example invocations for a topic, or for every topic")
+                .arg(
+                    Arg::new(ARG_TPC)
+                        .help("Topic of the examples")
+                        .value_parser(TOPICS.map(|(name, _)| name))
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_PATHS)
+                .about("Print the directories hx keeps its files in")
+                .long_about("Print the directories hx keeps config, bookmarks, sessions and --continue positions in, and the variables overriding them"),
+        )
+        .subcommand(
+            Command::new(CMD_ECHO)
+                .about("Dump the bytes of a literal, or write them out with --raw")
+                .long_about("Dump the bytes of a literal like \"de ad 0x10 #65 'A' 00*16\", or write them out with --raw")
+                .arg(
+                    Arg::new(ARG_LIT)
+                        .help("Bytes to write, as hex digits, numbers or strings")
+                        .long_help("Bytes: hex digits, 0x or 0o bytes, # for decimal, negative bytes, quoted strings with escapes, each repeatable with *<count>")
+                        .allow_hyphen_values(true)
+                        .required(true)
+                        .index(1),
+                )
+        )
+        .subcommand(
+            Command::new(CMD_FILL)
+                .about("Dump a byte or pattern repeated to a length")
+                .long_about("Dump a byte or pattern repeated to a length, or write it out with --raw, e.g. for padding and erased flash images")
+                .arg(
+                    Arg::new(ARG_LEN)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_LEN)
+                        .value_name("bytes")
+                        .help("Bytes to generate, decimal or hex with 0x, optionally in KiB, MiB or GiB")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_PAT)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_PAT)
+                        .value_name("bytes")
+                        .help("Byte or pattern to repeat, as for hx echo, 0 by default")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_CONV)
+                .about("Print an integer in every radix and byte order")
+                .long_about("Print an integer in every radix, as little and big endian bytes, and as 8, 16, 32 and 64 bit two's complement")
+                .arg(
+                    Arg::new(ARG_NUM)
+                        .help("Integer: hex with 0x, octal with 0o, binary with 0b, or decimal, negative with -")
+                        .allow_hyphen_values(true)
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_EXTRACT)
+                .about("Read a typed scalar and print it in every radix")
+                .long_about("Read a typed scalar, optionally a bit range of it, and print it in every radix")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to read")
+                        .required(true)
+                        .index(1),
+                )
+                .args(scalar_args())
+                .arg(
+                    Arg::new(ARG_BIT)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_BIT)
+                        .value_name("low:high")
+                        .help("Only print bits <low> through <high>, bit 0 being the least significant")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_SUM)
+                .about("Add up the typed values in byte ranges of a file")
+                .long_about("Add up the typed values in byte ranges of a file, printing their count, sum, min, max and mean")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to read")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_RNG)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_RNG)
+                        .value_name("ranges")
+                        .help("Byte ranges to read [default: the whole file]")
+                        .long_help("Byte ranges to read, comma separated, each start:end or start+len [default: the whole file]")
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_TYP)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_TYP)
+                        .value_name("type")
+                        .help("Value type, u8 to u64, i8 to i64, f32 or f64")
+                        .long_help("Value type, u8 to u64, i8 to i64, f32 or f64, with an optional le or be byte order suffix, e.g. u16le")
+                        .default_value("u8")
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_END)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_END)
+                        .help("Byte order of types without a suffix")
+                        .value_parser(["little", "big"])
+                        .default_value("little")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_BITS)
+                .about("Read a typed scalar and draw a register diagram of its bit fields")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to read")
+                        .required(true)
+                        .index(1),
+                )
+                .args(scalar_args())
+                .arg(
+                    Arg::new(ARG_LAY)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_LAY)
+                        .value_name("fields")
+                        .help("Comma separated bit fields, each <high>:<low> and a name")
+                        .long_help("Comma separated fields, each a bit range <high>:<low> or a single bit and a name, e.g. \"31:24 flags,23:12 addr,11:0 len\"")
+                        .required(true)
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_MATCH)
+                .about("Check that a file matches a pattern file under a mask")
+                .long_about("Check that a file matches a pattern file in the bits set in a mask file, showing the first mismatch")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to check")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_PAT)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_PAT)
+                        .value_name("file")
+                        .help("Expected bytes, compared from the start of the file")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_MSK)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_MSK)
+                        .value_name("file")
+                        .help("Bits of every pattern byte to compare")
+                        .long_help("Bits of every pattern byte to compare, e.g. 0x00 for device specific bytes; bytes past its end are compared in full")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_DIFF)
+                .about("Compare two files byte by byte")
+                .long_about("Compare two files byte by byte, listing the regions that changed, moved, were inserted or deleted")
+                .arg(
+                    Arg::new(ARG_OLD)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File compared against")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_NEW)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File compared with it")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::new(ARG_BLK)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_BLK)
+                        .value_name("bytes")
+                        .help("Shortest run reported as moved, 0 for none (default 64)")
+                        .long_help("Report identical runs of at least <bytes> found at another offset as moved, 0 compares every byte in place (default 64)")
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_CKS)
+                        .action(clap::ArgAction::SetTrue)
+                        .long(ARG_CKS)
+                        .help("Add the CRC-32 of the old and new bytes of every region"),
+                )
+                .arg(
+                    Arg::new(ARG_DAL)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_DAL)
+                        .value_name("algo")
+                        .value_parser(["naive", "aligned"])
+                        .help("How the files line up: aligned (default) or naive")
+                        .long_help("How the files line up: aligned follows insertions and deletions, naive compares the bytes at the same offset and is faster (default aligned)")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_CHECK)
+                .about("Check a file against a layout spec")
+                .long_about("Check a file's magic bytes, field values and checksums against a layout spec, printing a pass or fail report")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to check")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_SPC)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_SPC)
+                        .value_name("toml")
+                        .help("Layout spec of fields and checksums")
+                        .long_help("Layout spec: [[field]] tables with name, at and bytes, equals or min and max, [[checksum]] tables with name, algorithm, range and at")
+                        .required(true)
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_POKE)
+                .about("Write a typed scalar into a file")
+                .long_about("Write a typed scalar into a file, printing the patched line before and after")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to patch")
+                        .required(true)
+                        .index(1),
+                )
+                .args(scalar_args())
+                .args(guard_args())
+                .arg(
+                    Arg::new(ARG_VAL)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_VAL)
+                        .value_name("value")
+                        .help("Value to write: decimal, hex with 0x, negative for signed types, or a float")
+                        .allow_hyphen_values(true)
+                        .required(true)
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_MOVE)
+                .about("Copy a region of a file to another offset in it")
+                .long_about("Copy a region of a file to another offset in it, overlapping or not, printing the changed lines before and after")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to change")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_FRM)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_FRM)
+                        .value_name("offset")
+                        .help("Offset of the region, decimal or hex with 0x, offsets count from the start")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_LEN)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_LEN)
+                        .value_name("bytes")
+                        .help("Length of the region")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_TO)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_TO)
+                        .value_name("offset")
+                        .help("Offset to copy the region to")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_CLE)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_CLE)
+                        .value_name("byte")
+                        .help("Overwrite the source bytes the copy doesn't land on")
+                        .long_help("Overwrite the source bytes the copy doesn't land on with <byte>, moving the region rather than copying it")
+                        .num_args(1),
+                )
+                .args(guard_args()),
+        )
+        .subcommand(
+            Command::new(CMD_RESIZE)
+                .about("Truncate a file, or extend it with a fill byte or pattern")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File to resize")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new(ARG_TO)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_TO)
+                        .value_name("size")
+                        .help("New size, or relative to end")
+                        .long_help("New size, decimal or hex with 0x, optionally in KiB, MiB or GiB, or relative to end")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new(ARG_GAP)
+                        .action(clap::ArgAction::Set)
+                        .long(ARG_GAP)
+                        .value_name("bytes")
+                        .help("Byte or pattern to extend the file with, 0 by default")
+                        .long_help("Byte or pattern to extend the file with, as for hx echo, 0 by default; patterns repeat from offset 0")
+                        .num_args(1),
+                )
+                .args(guard_args()),
+        )
+        .subcommand(
+            Command::new(CMD_BOOKMARK)
+                .about("Manage a bookmarks file of labeled offsets, used by --bookmarks")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a bookmark, replacing one at the same offset")
+                        .arg(bookmarks_file())
+                        .arg(
+                            Arg::new(ARG_AT)
+                                .help("Offset, decimal or hex with 0x")
+                                .required(true)
+                                .index(2),
+                        )
+                        .arg(
+                            Arg::new(ARG_LBL)
+                                .help("Label")
+                                .required(true)
+                                .index(3),
+                        ),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove the bookmark at an offset")
+                        .arg(bookmarks_file())
+                        .arg(
+                            Arg::new(ARG_AT)
+                                .help("Offset, decimal or hex with 0x")
+                                .required(true)
+                                .index(2),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("Print every bookmark")
+                        .arg(bookmarks_file()),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_UNDO)
+                .about("Apply a reverse patch written with --reverse-patch")
+                .long_about("Apply a reverse patch written with --reverse-patch, checking the file hasn't changed since")
+                .arg(
+                    Arg::new(ARG_FIL)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Patch file")
+                        .required(true)
+                        .index(1),
+                )
+                .args(guard_args()),
+        );
+
+    #[cfg(feature = "archives")]
+    let app = app.arg(
+        Arg::new(ARG_MBR)
+            .action(clap::ArgAction::Set)
+            .long(ARG_MBR)
+            .value_name("path")
+            .help("Dump <path> from a zip or tar input, or list the members")
+            .long_help("Dump <path> from a zip, tar or tar.gz input, or list the members when <path> is omitted")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .conflicts_with(ARG_OVL),
+    );
+
+    #[cfg(feature = "zstd")]
+    let app = app.arg(
+        Arg::new(ARG_ZST)
+            .action(clap::ArgAction::SetTrue)
+            .long(ARG_ZST)
+            .help("Read the input as a seekable zstd archive")
+            .long_help("Read the input as a seekable zstd archive written with --output zst, decoding only the frames the dump reaches, so --skip into a large archive is instant")
+            .requires(ARG_INP)
+            .conflicts_with_all([ARG_CAT, ARG_ILV, ARG_FD, ARG_GEN, ARG_FNC, ARG_REV, ARG_TWP, ARG_HDR, ARG_CNT, ARG_RES, ARG_SMP, ARG_FRM, ARG_SQL, ARG_PCP]),
+    );
+
+    #[cfg(feature = "cores")]
+    let app = app.arg(
+        Arg::new(ARG_RGN)
+            .action(clap::ArgAction::Set)
+            .long(ARG_RGN)
+            .value_name("region")
+            .help("Dump a memory region of a core file, or list the regions")
+            .long_help("Dump a memory region of an ELF core file or minidump by name, index or an 0x address inside it, offsets from its virtual address, or list the regions when <region> is omitted")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .conflicts_with_all([ARG_OVL, ARG_SQL, ARG_PCP]),
+    );
+
+    app
+}
@@ -0,0 +1,117 @@
+//! names of the ascii control characters, for explanations and tables
+
+use crate::{print_byte, DumpOptions};
+use std::io::{self, Write};
+
+/// Abbreviation and name of every control character, by value
+const CONTROL: [(&str, &str); 32] = [
+    ("NUL", "null"),
+    ("SOH", "start of heading"),
+    ("STX", "start of text"),
+    ("ETX", "end of text"),
+    ("EOT", "end of transmission"),
+    ("ENQ", "enquiry"),
+    ("ACK", "acknowledge"),
+    ("BEL", "bell"),
+    ("BS", "backspace"),
+    ("HT", "horizontal tab"),
+    ("LF", "line feed"),
+    ("VT", "vertical tab"),
+    ("FF", "form feed"),
+    ("CR", "carriage return"),
+    ("SO", "shift out"),
+    ("SI", "shift in"),
+    ("DLE", "data link escape"),
+    ("DC1", "device control 1"),
+    ("DC2", "device control 2"),
+    ("DC3", "device control 3"),
+    ("DC4", "device control 4"),
+    ("NAK", "negative acknowledge"),
+    ("SYN", "synchronous idle"),
+    ("ETB", "end of transmission block"),
+    ("CAN", "cancel"),
+    ("EM", "end of medium"),
+    ("SUB", "substitute"),
+    ("ESC", "escape"),
+    ("FS", "file separator"),
+    ("GS", "group separator"),
+    ("RS", "record separator"),
+    ("US", "unit separator"),
+];
+
+/// Abbreviation and name of a byte without a glyph: control characters,
+/// space and delete, e.g. `("LF", "line feed")`.
+///
+/// # Arguments
+///
+/// * `b` - byte value.
+pub fn name(b: u8) -> Option<(&'static str, &'static str)> {
+    match b {
+        0x00..=0x1f => Some(CONTROL[b as usize]),
+        0x20 => Some(("SP", "space")),
+        0x7f => Some(("DEL", "delete")),
+        _ => None,
+    }
+}
+
+/// What a byte is in ascii: its name, e.g. `LF (line feed)`, the
+/// character itself, or that it's outside ascii.
+///
+/// # Arguments
+///
+/// * `b` - byte value.
+pub fn describe(b: u8) -> String {
+    match (name(b), b) {
+        (Some((abbreviation, name)), _) => format!("{abbreviation} ({name})"),
+        (None, 0x21..=0x7e) => (b as char).to_string(),
+        _ => "not ascii".to_string(),
+    }
+}
+
+/// Short form of a byte for tables: its abbreviation, the character, the
+/// latin-1 character for extended bytes, or a dot for C1 controls.
+///
+/// # Arguments
+///
+/// * `b` - byte value.
+fn glyph(b: u8) -> String {
+    match (name(b), b) {
+        (Some((abbreviation, _)), _) => abbreviation.to_string(),
+        (None, 0x80..=0x9f) => ".".to_string(),
+        (None, 0xa0) => "NBSP".to_string(),
+        (None, 0xad) => "SHY".to_string(),
+        (None, _) => char::from(b).to_string(),
+    }
+}
+
+/// Write the ascii table, 32 rows of byte values in the dump format and
+/// colors with their characters, then the names of the control characters.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `opts` - byte format, prefix and colors.
+/// * `extended` - add 0x80-0xff as latin-1.
+pub fn write_table(out: &mut impl Write, opts: &DumpOptions, extended: bool) -> io::Result<()> {
+    let columns: u8 = match extended {
+        true => 8,
+        false => 4,
+    };
+    for row in 0..32u8 {
+        for column in 0..columns {
+            let b = column * 32 + row;
+            print_byte(out, b, opts.format, opts.colorize, opts.prefix, " ")?;
+            match column + 1 == columns {
+                true => writeln!(out, "{}", glyph(b))?,
+                false => write!(out, "{:<6}", glyph(b))?,
+            }
+        }
+    }
+    writeln!(out)?;
+    for b in (0x00..=0x20).chain([0x7f]) {
+        if let Some((abbreviation, name)) = name(b) {
+            writeln!(out, "{abbreviation:<4}{name}")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! address translation for banked and segmented memory images
+//!
+//! A ROM dumped from a banked cartridge holds its banks back to back, while
+//! the CPU sees every bank at its own address. With a bank size and stride,
+//! file offset `f` is shown as `f / size * stride + f % size`.
+
+/// Bank layout of an image, the identity unless both sizes are set
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Banks {
+    /// bytes per bank in the file, 0 for no banking
+    pub size: u64,
+    /// address distance between the starts of consecutive banks
+    pub stride: u64,
+}
+
+/// Banks implementation
+impl Banks {
+    /// Address a file offset is seen at.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset into the input.
+    pub fn translate(&self, offset: u64) -> u64 {
+        match self.size {
+            0 => offset,
+            size => offset / size * self.stride + offset % size,
+        }
+    }
+}
@@ -0,0 +1,512 @@
+//! byte level diff of two inputs for `hx diff`: regions that changed,
+//! aligned across insertions and deletions, and blocks that moved, found
+//! with a rolling hash so a relocated region isn't reported as a delete
+//! and an insert
+
+use std::collections::HashMap;
+
+/// A region where two inputs differ, offsets into them
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// different bytes at the same offset of both inputs
+    Changed { at: u64, len: u64 },
+    /// bytes of the new input past the end of the old one
+    Added { at: u64, len: u64 },
+    /// bytes of the old input past the end of the new one
+    Removed { at: u64, len: u64 },
+    /// bytes of the old input found again at another offset of the new one
+    Moved { from: u64, to: u64, len: u64 },
+    /// bytes of the new input the old one doesn't have, `old_at` where
+    /// they would go in the old input
+    Inserted { at: u64, old_at: u64, len: u64 },
+    /// bytes of the old input the new one doesn't have, `new_at` where
+    /// they were in the new input
+    Deleted { at: u64, new_at: u64, len: u64 },
+    /// bytes of the old input replaced by bytes of another length or at
+    /// another offset of the new one
+    Replaced {
+        from: u64,
+        old_len: u64,
+        to: u64,
+        len: u64,
+    },
+}
+
+/// Change implementation
+impl Change {
+    /// Offset of the region in the new input; removed bytes have their
+    /// offset in the old input, past the end of the new one.
+    pub fn new_at(&self) -> u64 {
+        match self {
+            Change::Changed { at, .. } | Change::Added { at, .. } | Change::Removed { at, .. } => {
+                *at
+            }
+            Change::Moved { to, .. } | Change::Replaced { to, .. } => *to,
+            Change::Inserted { at, .. } => *at,
+            Change::Deleted { new_at, .. } => *new_at,
+        }
+    }
+
+    /// Bytes in the region, those of the new input when it was replaced.
+    pub fn len(&self) -> u64 {
+        match self {
+            Change::Changed { len, .. }
+            | Change::Added { len, .. }
+            | Change::Removed { len, .. }
+            | Change::Moved { len, .. }
+            | Change::Inserted { len, .. }
+            | Change::Deleted { len, .. }
+            | Change::Replaced { len, .. } => *len,
+        }
+    }
+}
+
+/// Multiplier of the rolling hash
+const BASE: u32 = 257;
+
+/// Polynomial hash of a window, the one `Rolling` keeps up to date.
+fn hash(window: &[u8]) -> u32 {
+    window.iter().fold(0u32, |h, b| {
+        h.wrapping_mul(BASE).wrapping_add(u32::from(*b))
+    })
+}
+
+/// Hash of a window sliding over bytes a byte at a time
+struct Rolling {
+    hash: u32,
+    /// BASE to the power of the window length, to take the first byte out
+    out: u32,
+}
+
+/// Rolling implementation
+impl Rolling {
+    /// Rolling constructor
+    fn new(window: &[u8]) -> Rolling {
+        let out = (0..window.len()).fold(1u32, |p, _| p.wrapping_mul(BASE));
+        Rolling {
+            hash: hash(window),
+            out,
+        }
+    }
+
+    /// Slide the window a byte: `first` leaves it, `next` enters it.
+    fn roll(&mut self, first: u8, next: u8) {
+        self.hash = self
+            .hash
+            .wrapping_mul(BASE)
+            .wrapping_add(u32::from(next))
+            .wrapping_sub(self.out.wrapping_mul(u32::from(first)));
+    }
+}
+
+/// Blocks of the old input found at other offsets of the new one, in
+/// order of their new offset. Every block of `block` bytes the old input
+/// starts at a multiple of is indexed by hash; a window of the new input
+/// matching one, where the old input doesn't already hold the same bytes
+/// at the same offset, is a move, grown forward as far as the bytes agree.
+///
+/// # Arguments
+///
+/// * `old` - old input.
+/// * `new` - new input.
+/// * `block` - shortest move, in bytes.
+pub fn moves(old: &[u8], new: &[u8], block: usize) -> Vec<Change> {
+    let mut found = Vec::new();
+    if block == 0 || old.len() < block || new.len() < block {
+        return found;
+    }
+    let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+    for at in (0..=old.len() - block).step_by(block) {
+        index
+            .entry(hash(&old[at..at + block]))
+            .or_default()
+            .push(at);
+    }
+    let mut at = 0;
+    let mut rolling = Rolling::new(&new[..block]);
+    while at + block <= new.len() {
+        let window = &new[at..at + block];
+        let in_place = old.get(at..at + block) == Some(window);
+        let from = match in_place {
+            true => None,
+            false => index.get(&rolling.hash).and_then(|starts| {
+                starts
+                    .iter()
+                    .copied()
+                    .find(|from| &old[*from..*from + block] == window)
+            }),
+        };
+        let Some(from) = from else {
+            if at + block < new.len() {
+                rolling.roll(new[at], new[at + block]);
+            }
+            at += 1;
+            continue;
+        };
+        let len = old[from..]
+            .iter()
+            .zip(&new[at..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        found.push(Change::Moved {
+            from: from as u64,
+            to: at as u64,
+            len: len as u64,
+        });
+        at += len;
+        if at + block <= new.len() {
+            rolling = Rolling::new(&new[at..at + block]);
+        }
+    }
+    found
+}
+
+/// Equal bytes between two changed runs that still make them one region,
+/// so bytes equal by chance don't split a rewritten region up
+const MERGE_GAP: usize = 8;
+
+/// Runs of offsets in `start..end` where `differs` holds, runs less than
+/// `MERGE_GAP` apart counted as one.
+fn runs(start: usize, end: usize, differs: impl Fn(usize) -> bool) -> Vec<(usize, usize)> {
+    let mut found: Vec<(usize, usize)> = Vec::new();
+    let mut at = start;
+    while at < end {
+        if !differs(at) {
+            at += 1;
+            continue;
+        }
+        let first = at;
+        while at < end && differs(at) {
+            at += 1;
+        }
+        match found.last_mut() {
+            Some((last, len)) if first - (*last + *len) < MERGE_GAP => *len = at - *last,
+            _ => found.push((first, at - first)),
+        }
+    }
+    found
+}
+
+/// Regions where two inputs differ, in order of their offset in the new
+/// input: moved blocks first, then the bytes outside them compared with
+/// the old input at the same offset. Fast, but every byte after an
+/// insertion or deletion shifts and differs; `diff_aligned` follows them.
+///
+/// # Arguments
+///
+/// * `old` - old input.
+/// * `new` - new input.
+/// * `block` - shortest move, in bytes, 0 to look for none.
+pub fn diff_naive(old: &[u8], new: &[u8], block: usize) -> Vec<Change> {
+    let moved = moves(old, new, block);
+    let mut changes = Vec::new();
+    // new bytes outside the moves, the gaps before, between and after them
+    let mut gaps = Vec::new();
+    let mut at = 0;
+    for change in &moved {
+        gaps.push((at, change.new_at() as usize));
+        at = (change.new_at() + change.len()) as usize;
+    }
+    gaps.push((at, new.len()));
+    for (start, end) in gaps {
+        let shared = end.min(old.len()).max(start);
+        for (at, len) in runs(start, shared, |at| old[at] != new[at]) {
+            changes.push(Change::Changed {
+                at: at as u64,
+                len: len as u64,
+            });
+        }
+        if shared < end {
+            changes.push(Change::Added {
+                at: shared as u64,
+                len: (end - shared) as u64,
+            });
+        }
+    }
+    // old bytes past the end of the new input, unless they moved
+    let moved_from = |at: usize| {
+        moved.iter().any(|change| match change {
+            Change::Moved { from, len, .. } => (*from..from + len).contains(&(at as u64)),
+            _ => false,
+        })
+    };
+    for (at, len) in runs(new.len(), old.len(), |at| !moved_from(at)) {
+        changes.push(Change::Removed {
+            at: at as u64,
+            len: len as u64,
+        });
+    }
+    changes.extend(moved);
+    changes.sort_by_key(|change| (change.new_at(), matches!(change, Change::Removed { .. })));
+    changes
+}
+
+/// Edit of one element of a sequence, from a Myers edit script
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Most edits `myers` looks for before giving up, which bounds its
+/// memory to a few MiB
+const MAX_EDITS: usize = 1000;
+
+/// Shortest edit script from `a` to `b`, an edit per element, by Myers'
+/// O(ND) algorithm; None when it takes more than `max` edits.
+fn myers<T: PartialEq>(a: &[T], b: &[T], max: usize) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let limit = (a.len() + b.len()).min(max) as isize;
+    let offset = limit + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // v[k] for the diagonals -d..=d, before each step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=limit {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = (offset + k) as usize;
+            let mut x = match k == -d || (k != d && v[at - 1] < v[at + 1]) {
+                true => v[at + 1],
+                false => v[at - 1] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+/// Edits of the path `myers` found, walking its steps back from the end.
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let v = &trace[d as usize];
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev = match k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let prev_x = at(prev);
+        let prev_y = prev_x - prev;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(match x == prev_x {
+            true => Edit::Insert,
+            false => Edit::Delete,
+        });
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.extend((0..x).map(|_| Edit::Keep));
+    edits.reverse();
+    edits
+}
+
+/// Bytes of the rolling window picking chunk boundaries
+const CHUNK_WINDOW: usize = 16;
+/// Chunk boundaries fall where the window hash has these bits clear, one
+/// in 64 offsets
+const CHUNK_MASK: u32 = 63;
+/// Longest chunk, cut even without a boundary
+const CHUNK_MAX: usize = 1024;
+
+/// Content defined chunks: cut where the hash of the bytes just before
+/// has its low bits clear, so an insertion only changes the chunks around
+/// it and both inputs fall back in step after it.
+fn chunks(data: &[u8]) -> Vec<&[u8]> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    if data.len() > CHUNK_WINDOW {
+        let mut rolling = Rolling::new(&data[..CHUNK_WINDOW]);
+        for end in CHUNK_WINDOW..data.len() {
+            let len = end - start;
+            if len >= CHUNK_WINDOW && (rolling.hash & CHUNK_MASK == 0 || len >= CHUNK_MAX) {
+                found.push(&data[start..end]);
+                start = end;
+            }
+            rolling.roll(data[end - CHUNK_WINDOW], data[end]);
+        }
+    }
+    if start < data.len() {
+        found.push(&data[start..]);
+    }
+    found
+}
+
+/// Hunks of an edit script: old and new start and length of every run of
+/// deletes and inserts, runs less than `MERGE_GAP` kept elements apart
+/// counted as one.
+///
+/// # Arguments
+///
+/// * `edits` - edit script.
+/// * `old_len` - length of each old element.
+/// * `new_len` - length of each new element.
+fn hunks(
+    edits: &[Edit],
+    old_len: impl Fn(usize) -> usize,
+    new_len: impl Fn(usize) -> usize,
+) -> Vec<(usize, usize, usize, usize)> {
+    let mut found: Vec<(usize, usize, usize, usize)> = Vec::new();
+    let (mut i, mut j, mut x, mut y) = (0, 0, 0, 0);
+    // old and new offsets where the last hunk ended, the kept elements
+    // since it as long in both
+    let mut last_end = None;
+    for edit in edits {
+        let (dx, dy) = match edit {
+            Edit::Keep => (old_len(i), new_len(j)),
+            Edit::Delete => (old_len(i), 0),
+            Edit::Insert => (0, new_len(j)),
+        };
+        if *edit != Edit::Keep {
+            match (found.last_mut(), last_end) {
+                (Some(hunk), Some((end, _))) if x - end < MERGE_GAP => {
+                    hunk.1 = x + dx - hunk.0;
+                    hunk.3 = y + dy - hunk.2;
+                }
+                _ => found.push((x, dx, y, dy)),
+            }
+            last_end = Some((x + dx, y + dy));
+        }
+        if *edit != Edit::Insert {
+            i += 1;
+        }
+        if *edit != Edit::Delete {
+            j += 1;
+        }
+        (x, y) = (x + dx, y + dy);
+    }
+    found
+}
+
+/// The change a hunk is: bytes inserted, deleted, changed in place, or
+/// replaced by others of another length or offset.
+fn hunk_change(old_at: usize, old_len: usize, new_at: usize, new_len: usize) -> Change {
+    let (from, to) = (old_at as u64, new_at as u64);
+    match (old_len, new_len) {
+        (0, len) => Change::Inserted {
+            at: to,
+            old_at: from,
+            len: len as u64,
+        },
+        (len, 0) => Change::Deleted {
+            at: from,
+            new_at: to,
+            len: len as u64,
+        },
+        (old_len, len) if old_len == len && from == to => Change::Changed {
+            at: to,
+            len: len as u64,
+        },
+        (old_len, len) => Change::Replaced {
+            from,
+            old_len: old_len as u64,
+            to,
+            len: len as u64,
+        },
+    }
+}
+
+/// Lengths of the common prefix of two inputs, and of the common suffix
+/// of what's left of them.
+fn common(a: &[u8], b: &[u8]) -> (usize, usize) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (prefix, suffix)
+}
+
+/// Bytes of two hunks each at most this long are aligned byte by byte
+const REFINE: usize = 1024;
+
+/// Regions where two inputs differ, aligned so inserted and deleted bytes
+/// don't shift everything after them: Myers' algorithm over content
+/// defined chunks, then over the bytes of hunks short enough. Deleted
+/// bytes inserted again elsewhere are reported as moved.
+///
+/// # Arguments
+///
+/// * `old` - old input.
+/// * `new` - new input.
+/// * `block` - shortest move, in bytes, 0 to look for none.
+pub fn diff_aligned(old: &[u8], new: &[u8], block: usize) -> Vec<Change> {
+    let (prefix, suffix) = common(old, new);
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    let (a_chunks, b_chunks) = (chunks(a), chunks(b));
+    let coarse = match myers(&a_chunks, &b_chunks, MAX_EDITS) {
+        Some(edits) => hunks(&edits, |i| a_chunks[i].len(), |j| b_chunks[j].len()),
+        None => vec![(0, a.len(), 0, b.len())],
+    };
+    let mut changes = Vec::new();
+    for (x, dx, y, dy) in coarse {
+        // chunks cut apart differently around an edit end or start the same
+        let (head, tail) = common(&a[x..x + dx], &b[y..y + dy]);
+        let (x, dx, y, dy) = (x + head, dx - head - tail, y + head, dy - head - tail);
+        if dx == 0 && dy == 0 {
+            continue;
+        }
+        let fine = match dx <= REFINE && dy <= REFINE && dx > 0 && dy > 0 {
+            true => myers(&a[x..x + dx], &b[y..y + dy], MAX_EDITS)
+                .map(|edits| hunks(&edits, |_| 1, |_| 1)),
+            false => None,
+        };
+        for (fx, fdx, fy, fdy) in fine.unwrap_or(vec![(0, dx, 0, dy)]) {
+            changes.push(hunk_change(prefix + x + fx, fdx, prefix + y + fy, fdy));
+        }
+    }
+    if block > 0 {
+        pair_moves(old, new, block, &mut changes);
+    }
+    changes
+}
+
+/// Turn every insertion of at least `block` bytes that some deletion took
+/// out, byte for byte, into a move.
+fn pair_moves(old: &[u8], new: &[u8], block: usize, changes: &mut Vec<Change>) {
+    for i in 0..changes.len() {
+        let Change::Inserted { at, len, .. } = changes[i] else {
+            continue;
+        };
+        if (len as usize) < block {
+            continue;
+        }
+        let inserted = &new[at as usize..(at + len) as usize];
+        let source = changes.iter().position(|change| match *change {
+            Change::Deleted {
+                at, len: deleted, ..
+            } => deleted == len && &old[at as usize..(at + len) as usize] == inserted,
+            _ => false,
+        });
+        if let Some(source) = source {
+            let Change::Deleted { at: from, .. } = changes[source] else {
+                unreachable!("a deletion was found")
+            };
+            changes[i] = Change::Moved { from, to: at, len };
+            changes[source] = Change::Deleted {
+                at: from,
+                new_at: 0,
+                len: 0,
+            };
+        }
+    }
+    changes.retain(|change| !matches!(change, Change::Deleted { len: 0, .. }));
+}
@@ -0,0 +1,88 @@
+//! bookmarks files: labeled offsets, one per line
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A labeled offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    /// offset the label refers to
+    pub offset: u64,
+    /// label, e.g. `fs superblock`
+    pub label: String,
+}
+
+/// Parse a bookmarks file: an offset, decimal or hex with `0x`, then the
+/// label, e.g. `0x000400 fs superblock`. Blank lines and lines starting
+/// with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `text` - bookmarks file contents.
+pub fn parse(text: &str) -> io::Result<Vec<Bookmark>> {
+    let mut marks = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (offset, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let offset = match offset.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => offset.parse().ok(),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} of the bookmarks has a bad offset", i + 1),
+            )
+        })?;
+        marks.push(Bookmark {
+            offset,
+            label: label.trim().to_string(),
+        });
+    }
+    marks.sort_by_key(|mark| mark.offset);
+    Ok(marks)
+}
+
+/// Text of a bookmarks file, sorted by offset.
+///
+/// # Arguments
+///
+/// * `marks` - bookmarks to write.
+pub fn format(marks: &[Bookmark]) -> String {
+    let mut sorted = marks.to_vec();
+    sorted.sort_by_key(|mark| mark.offset);
+    sorted
+        .iter()
+        .map(|mark| format!("{} {}\n", crate::offset(mark.offset), mark.label))
+        .collect()
+}
+
+/// Read a bookmarks file, a missing file has no bookmarks yet.
+///
+/// # Arguments
+///
+/// * `path` - bookmarks file.
+pub fn load(path: &Path) -> io::Result<Vec<Bookmark>> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Bookmarks falling within `len` bytes from `start`.
+///
+/// # Arguments
+///
+/// * `marks` - bookmarks, sorted by offset.
+/// * `start` - first offset.
+/// * `len` - number of bytes.
+pub fn within(marks: &[Bookmark], start: u64, len: u64) -> &[Bookmark] {
+    let from = marks.partition_point(|mark| mark.offset < start);
+    let to = marks.partition_point(|mark| mark.offset < start + len);
+    &marks[from..to]
+}
@@ -0,0 +1,147 @@
+//! block cache in front of a seekable source, so front ends paging back and
+//! forth with `render_range` don't read the same blocks again, e.g. over a
+//! network filesystem
+
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Block size of `BlockCache::new`
+pub const CACHE_BLOCK: usize = 64 * 1024;
+
+/// Reader keeping the most recently used blocks of a seekable source in
+/// memory, reading a block on a miss and dropping the least recently used
+/// one when full
+pub struct BlockCache<R: Read + Seek> {
+    inner: R,
+    /// bytes per block, every block starting at a multiple of it
+    block: usize,
+    /// blocks kept at most
+    capacity: usize,
+    /// cached blocks by index, with the tick they were last used at
+    blocks: HashMap<u64, (Vec<u8>, u64)>,
+    tick: u64,
+    /// offset of the next read
+    pos: u64,
+    /// length of the source, once a seek from the end needed it
+    len: Option<u64>,
+    /// reads served from memory and from the source
+    hits: u64,
+    misses: u64,
+}
+
+/// BlockCache implementation
+impl<R: Read + Seek> BlockCache<R> {
+    /// Cache of `capacity` blocks of 64 KiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - source to cache.
+    /// * `capacity` - blocks kept at most.
+    pub fn new(inner: R, capacity: usize) -> BlockCache<R> {
+        BlockCache::with_block(inner, CACHE_BLOCK, capacity)
+    }
+
+    /// Cache of blocks of a given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - source to cache.
+    /// * `block` - bytes per block, at least 1.
+    /// * `capacity` - blocks kept at most, at least 1.
+    pub fn with_block(inner: R, block: usize, capacity: usize) -> BlockCache<R> {
+        BlockCache {
+            inner,
+            block: block.max(1),
+            capacity: capacity.max(1),
+            blocks: HashMap::new(),
+            tick: 0,
+            pos: 0,
+            len: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Block reads served from memory and from the source so far.
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+
+    /// Forget every cached block, e.g. after the source changed.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.len = None;
+    }
+
+    /// The source, dropping the cache.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The block at an index, read from the source on a miss; short or
+    /// empty at the end of the source.
+    fn fetch(&mut self, index: u64) -> io::Result<&[u8]> {
+        self.tick += 1;
+        let tick = self.tick;
+        if self.blocks.contains_key(&index) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            self.inner
+                .seek(SeekFrom::Start(index * self.block as u64))?;
+            let mut data = Vec::with_capacity(self.block);
+            (&mut self.inner)
+                .take(self.block as u64)
+                .read_to_end(&mut data)?;
+            if self.blocks.len() >= self.capacity {
+                let oldest = self.blocks.iter().min_by_key(|(_, (_, used))| *used);
+                if let Some(oldest) = oldest.map(|(index, _)| *index) {
+                    self.blocks.remove(&oldest);
+                }
+            }
+            self.blocks.insert(index, (data, tick));
+        }
+        let (data, used) = self.blocks.get_mut(&index).expect("block just cached");
+        *used = tick;
+        Ok(data)
+    }
+}
+
+impl<R: Read + Seek> Read for BlockCache<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let block = self.block as u64;
+        let (index, at) = (self.pos / block, (self.pos % block) as usize);
+        let data = self.fetch(index)?;
+        let n = data.len().saturating_sub(at).min(out.len());
+        out[..n].copy_from_slice(&data[at..at + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for BlockCache<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(to) => {
+                self.pos = to;
+                return Ok(to);
+            }
+            SeekFrom::Current(delta) => (self.pos, delta),
+            SeekFrom::End(delta) => {
+                let len = match self.len {
+                    Some(len) => len,
+                    None => self.inner.seek(SeekFrom::End(0))?,
+                };
+                self.len = Some(len);
+                (len, delta)
+            }
+        };
+        self.pos = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to before the start of the source",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,83 @@
+//! `--capabilities`: what this hx build supports, as JSON for wrapper
+//! tools and editor plugins
+
+use crate::patch::json_string;
+use clap::Command;
+use std::io::{self, Write};
+
+/// Optional cargo features and whether this build has them
+pub const FEATURES: [(&str, bool); 5] = [
+    ("archives", cfg!(feature = "archives")),
+    ("cores", cfg!(feature = "cores")),
+    ("dwarf", cfg!(feature = "dwarf")),
+    ("i18n", cfg!(feature = "i18n")),
+    ("zstd", cfg!(feature = "zstd")),
+];
+
+/// JSON array of strings.
+fn strings<'a>(items: impl IntoIterator<Item = &'a str>) -> String {
+    let items: Vec<String> = items.into_iter().map(json_string).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// JSON array of `{"value": ..., "name": ...}` objects.
+fn choices(items: &[(&str, &str)]) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|(value, name)| {
+            format!(
+                "{{\"value\": {}, \"name\": {}}}",
+                json_string(value),
+                json_string(name)
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Write the capabilities document: version, byte formats, array
+/// languages, output formats, subcommands, long options and optional
+/// features.
+///
+/// # Arguments
+///
+/// * `w` - output.
+/// * `command` - command line definition.
+/// * `formats` - values of `--format` with their names.
+/// * `arrays` - values of `--array` with their names.
+/// * `outputs` - registered output formats.
+pub fn describe(
+    w: &mut impl Write,
+    command: &Command,
+    formats: &[(&str, &str)],
+    arrays: &[(&str, &str)],
+    outputs: &[&str],
+) -> io::Result<()> {
+    let features: Vec<String> = FEATURES
+        .iter()
+        .map(|(name, enabled)| format!("{}: {enabled}", json_string(name)))
+        .collect();
+    writeln!(w, "{{")?;
+    writeln!(w, "  \"name\": {},", json_string(command.get_name()))?;
+    writeln!(
+        w,
+        "  \"version\": {},",
+        json_string(env!("CARGO_PKG_VERSION"))
+    )?;
+    writeln!(w, "  \"formats\": {},", choices(formats))?;
+    writeln!(w, "  \"arrays\": {},", choices(arrays))?;
+    writeln!(w, "  \"outputs\": {},", strings(outputs.iter().copied()))?;
+    writeln!(
+        w,
+        "  \"subcommands\": {},",
+        strings(command.get_subcommands().map(Command::get_name))
+    )?;
+    writeln!(
+        w,
+        "  \"options\": {},",
+        strings(command.get_arguments().filter_map(|arg| arg.get_long()))
+    )?;
+    writeln!(w, "  \"features\": {{{}}}", features.join(", "))?;
+    writeln!(w, "}}")?;
+    w.flush()
+}
@@ -0,0 +1,175 @@
+//! embedded file carving for `--carve-auto`: PNG, JPEG, ZIP and gzip
+//! signatures anywhere in the input, with their extent where the format
+//! tells it
+
+use crate::magic;
+use crate::search::Finder;
+
+/// How the end of an embedded file was found
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum End {
+    /// its format says where it ends
+    Found,
+    /// the input ends before the file does
+    CutOff,
+    /// the format doesn't say, it runs to the next signature or the end
+    Unknown,
+}
+
+/// A candidate embedded file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Embedded {
+    /// offset of its first byte
+    pub offset: usize,
+    /// bytes it takes
+    pub len: usize,
+    /// file type, as `--header` names it
+    pub kind: &'static str,
+    /// file name extension
+    pub ext: &'static str,
+    /// how its end was found
+    pub end: End,
+}
+
+/// Extent of a file starting at the front of the bytes: its length and how
+/// it was found, None when the bytes only look like the signature.
+type Extent = fn(&[u8]) -> Option<(usize, End)>;
+
+/// Signatures searched for: the magic bytes, the file name extension and
+/// how far the file goes
+const SIGNATURES: &[(&[u8], &str, Extent)] = &[
+    (b"\x89PNG\r\n\x1a\n", "png", png),
+    (b"\xff\xd8\xff", "jpg", jpeg),
+    (b"PK\x03\x04", "zip", zip),
+    (b"\x1f\x8b\x08", "gz", gzip),
+];
+
+/// Big endian integer of the bytes at `at`, None past the end.
+fn be(data: &[u8], at: usize, width: usize) -> Option<usize> {
+    let bytes = data.get(at..at.checked_add(width)?)?;
+    Some(bytes.iter().fold(0, |value, b| value << 8 | *b as usize))
+}
+
+/// PNG: chunks up to and including IEND, the first one IHDR.
+fn png(data: &[u8]) -> Option<(usize, End)> {
+    let mut at = 8;
+    loop {
+        let (Some(len), Some(kind)) = (be(data, at, 4), data.get(at + 4..at + 8)) else {
+            return Some((data.len(), End::CutOff));
+        };
+        if (at == 8 && kind != b"IHDR") || !kind.iter().all(u8::is_ascii_alphabetic) {
+            return None;
+        }
+        at += 12 + len;
+        if at > data.len() {
+            return Some((data.len(), End::CutOff));
+        }
+        if kind == b"IEND" {
+            return Some((at, End::Found));
+        }
+    }
+}
+
+/// JPEG: marker segments up to the end of image marker, skipping the
+/// entropy coded data after each start of scan.
+fn jpeg(data: &[u8]) -> Option<(usize, End)> {
+    let mut at = 2;
+    loop {
+        let (Some(0xff), Some(&marker)) = (data.get(at), data.get(at + 1)) else {
+            return match at >= data.len() - 1 {
+                true => Some((data.len(), End::CutOff)),
+                false => None,
+            };
+        };
+        match marker {
+            0xd9 => return Some((at + 2, End::Found)),
+            0xff => at += 1,
+            0x01 | 0xd0..=0xd7 => at += 2,
+            0x00 => return None,
+            _ => {
+                let Some(len) = be(data, at + 2, 2) else {
+                    return Some((data.len(), End::CutOff));
+                };
+                if len < 2 {
+                    return None;
+                }
+                at += 2 + len;
+                if marker == 0xda {
+                    // scan data runs to the next marker, 0xff 0x00 is a stuffed 0xff
+                    while at + 1 < data.len()
+                        && (data[at] != 0xff || matches!(data[at + 1], 0x00 | 0xd0..=0xd7))
+                    {
+                        at += 1;
+                    }
+                }
+                if at >= data.len() {
+                    return Some((data.len(), End::CutOff));
+                }
+            }
+        }
+    }
+}
+
+/// ZIP: from the first local file header to the end of the central
+/// directory record after it, and its comment.
+fn zip(data: &[u8]) -> Option<(usize, End)> {
+    let Some(record) = Finder::new(b"PK\x05\x06").find(data, 0) else {
+        return Some((data.len(), End::Unknown));
+    };
+    let comment = data
+        .get(record + 20..record + 22)
+        .map_or(0, |len| u16::from_le_bytes([len[0], len[1]]) as usize);
+    match record + 22 + comment {
+        end if end > data.len() => Some((data.len(), End::CutOff)),
+        end => Some((end, End::Found)),
+    }
+}
+
+/// gzip: deflate with no reserved flags set. Where the stream ends takes
+/// inflating it, so it runs to the next signature.
+fn gzip(data: &[u8]) -> Option<(usize, End)> {
+    match data.get(3) {
+        Some(flags) if flags & 0xe0 == 0 => Some((data.len(), End::Unknown)),
+        _ => None,
+    }
+}
+
+/// Find the files embedded in the input. Scanning goes on after the end of
+/// each file found, so the members of a ZIP archive aren't reported apart,
+/// and a file whose end is unknown is cut off at the next one found.
+///
+/// # Arguments
+///
+/// * `data` - input to be scanned.
+pub fn scan(data: &[u8]) -> Vec<Embedded> {
+    let mut found: Vec<Embedded> = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        let rest = &data[at..];
+        let hit = SIGNATURES.iter().find_map(|(signature, ext, extent)| {
+            match rest.starts_with(signature) {
+                true => extent(rest).map(|(len, end)| (*ext, len, end)),
+                false => None,
+            }
+        });
+        let Some((ext, len, end)) = hit else {
+            at += 1;
+            continue;
+        };
+        if let Some(last) = found.last_mut().filter(|last| last.end == End::Unknown) {
+            last.len = at - last.offset;
+        }
+        found.push(Embedded {
+            offset: at,
+            len,
+            kind: magic::detect(rest).unwrap_or("data"),
+            ext,
+            end,
+        });
+        at += match end {
+            End::Unknown => 1,
+            _ => len,
+        };
+    }
+    found
+}
@@ -0,0 +1,151 @@
+//! character panel decoding: ascii bytes or UTF-16 code units
+
+use crate::byte_to_color;
+use crate::style::{Role, SpanSink};
+use ansi_term::Style;
+
+/// Character set of the panel after the byte columns
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// one glyph per byte, printable ascii only
+    #[default]
+    Ascii,
+    /// one glyph per little endian UTF-16 code unit
+    Utf16Le,
+    /// one glyph per big endian UTF-16 code unit
+    Utf16Be,
+}
+
+/// Charset implementation
+impl Charset {
+    /// Charset for a name such as `utf16le`.
+    pub fn parse(name: &str) -> Option<Charset> {
+        match name {
+            "ascii" => Some(Charset::Ascii),
+            "utf16le" => Some(Charset::Utf16Le),
+            "utf16be" => Some(Charset::Utf16Be),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes shown by `--only`, the others left blank
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteClass {
+    /// printable ascii, space included
+    Printable,
+    /// anything but zero
+    Nonzero,
+    /// 0x80 and above
+    High,
+}
+
+/// ByteClass implementation
+impl ByteClass {
+    /// ByteClass for a name such as `printable`.
+    pub fn parse(name: &str) -> Option<ByteClass> {
+        match name {
+            "printable" => Some(ByteClass::Printable),
+            "nonzero" => Some(ByteClass::Nonzero),
+            "high" => Some(ByteClass::High),
+            _ => None,
+        }
+    }
+
+    /// Whether a byte is in the class.
+    pub fn contains(&self, b: u8) -> bool {
+        match self {
+            ByteClass::Printable => (0x20..0x7f).contains(&b),
+            ByteClass::Nonzero => b != 0,
+            ByteClass::High => b >= 0x80,
+        }
+    }
+
+    /// The bytes of a line with those outside the class, or every code
+    /// unit without a byte in it, turned into spaces in the charset, so the
+    /// character panel leaves them blank.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - bytes of the line.
+    /// * `charset` - how the panel decodes them.
+    pub fn blank(&self, bytes: &[u8], charset: Charset) -> Vec<u8> {
+        let (width, space): (usize, &[u8]) = match charset {
+            Charset::Ascii => (1, b" "),
+            Charset::Utf16Le => (2, b" \0"),
+            Charset::Utf16Be => (2, b"\0 "),
+        };
+        bytes
+            .chunks(width)
+            .flat_map(|unit| match unit.iter().any(|b| self.contains(*b)) {
+                true => unit,
+                false => &space[..unit.len()],
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Add a glyph of the character panel, in the color of its byte when
+/// colored.
+fn glyph(spans: &mut impl SpanSink, chr: char, color: u8, colorize: bool) {
+    let style = match colorize {
+        true => Style::new().fg(byte_to_color(color)),
+        false => Style::new(),
+    };
+    spans.push(chr.encode_utf8(&mut [0; 4]), style, Role::Char);
+}
+
+/// Add the character panel for the bytes of a line, one span per glyph,
+/// and return the number of glyphs. Ascii prints a dot for anything but
+/// printable characters; UTF-16 surrogate pairs print as one glyph across
+/// the slots of both code units, unpaired surrogates, control characters
+/// and a trailing odd byte as a dot.
+///
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `bytes` - bytes of the line.
+/// * `charset` - how to decode the bytes.
+/// * `colorize` - color glyphs like the byte columns.
+pub fn panel_spans(
+    spans: &mut impl SpanSink,
+    bytes: &[u8],
+    charset: Charset,
+    colorize: bool,
+) -> usize {
+    let unit = |pair: &[u8]| match charset {
+        Charset::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    };
+    if charset == Charset::Ascii {
+        for b in bytes {
+            match *b > 31 && *b < 127 {
+                true => glyph(spans, *b as char, *b, colorize),
+                false => glyph(spans, '.', *b, colorize),
+            }
+        }
+        return bytes.len();
+    }
+    let mut glyphs = 0;
+    let units: Vec<u16> = bytes.chunks_exact(2).map(unit).collect();
+    let mut i = 0;
+    while i < units.len() {
+        let (chr, used) = match char::decode_utf16(units[i..].iter().copied()).next() {
+            Some(Ok(chr)) => (chr, chr.len_utf16()),
+            _ => ('.', 1),
+        };
+        let chr = match chr.is_control() {
+            true => '.',
+            false => chr,
+        };
+        glyph(spans, chr, units[i] as u8, colorize);
+        glyphs += 1;
+        i += used;
+    }
+    if !bytes.len().is_multiple_of(2) {
+        glyph(spans, '.', bytes[bytes.len() - 1], colorize);
+        glyphs += 1;
+    }
+    glyphs
+}
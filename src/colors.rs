@@ -0,0 +1,84 @@
+//! the `hx colors` legend: every byte value in the dump colors, with the
+//! class it belongs to
+
+use crate::bank::Banks;
+use crate::{byte_to_color, write_line, DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Byte classes, in legend order
+pub const CLASSES: [&str; 5] = ["null", "control", "whitespace", "printable", "high"];
+
+/// Class of a byte value: null, control, whitespace, printable ascii, or
+/// high for bytes outside ascii.
+///
+/// # Arguments
+///
+/// * `b` - byte value.
+pub fn class(b: u8) -> &'static str {
+    match b {
+        0x00 => "null",
+        0x09..=0x0d | 0x20 => "whitespace",
+        0x21..=0x7e => "printable",
+        0x80..=0xff => "high",
+        _ => "control",
+    }
+}
+
+/// Byte values of a class as ranges, e.g. `0x01-0x08, 0x0e-0x1f, 0x7f`.
+///
+/// # Arguments
+///
+/// * `name` - class name.
+pub fn ranges(name: &str) -> String {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    for b in (0..=255).filter(|b| class(*b) == name) {
+        match runs.last_mut() {
+            Some(run) if run.1 + 1 == b => run.1 = b,
+            _ => runs.push((b, b)),
+        }
+    }
+    runs.iter()
+        .map(|(first, last)| match first == last {
+            true => format!("{first:#04x}"),
+            false => format!("{first:#04x}-{last:#04x}"),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Write all 256 byte values as a 16 column dump in the dump colors, then
+/// every class with its byte values.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `opts` - dump format, prefix and colors.
+pub fn write_legend(out: &mut impl Write, opts: &DumpOptions) -> io::Result<()> {
+    let opts = DumpOptions {
+        column_width: 16,
+        start_offset: 0,
+        align: false,
+        banks: Banks::default(),
+        source: None,
+        ..*opts
+    };
+    for row in 0..16u8 {
+        let mut line = Line::new();
+        line.offset = row as u64 * 16;
+        line.bytes = 16;
+        line.hex_body = (row * 16..=row * 16 + 15).collect();
+        write_line(out, &line, 0, &opts)?;
+    }
+    writeln!(out)?;
+    for name in CLASSES {
+        // each label in the color of the first byte of its class
+        let label = format!("{name:<10}");
+        let first = (0..=255).find(|b| class(*b) == name).unwrap_or_default();
+        match opts.colorize {
+            true => write!(out, "{}", byte_to_color(first).paint(label))?,
+            false => write!(out, "{label}")?,
+        }
+        writeln!(out, " {}", ranges(name))?;
+    }
+    Ok(())
+}
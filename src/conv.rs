@@ -0,0 +1,93 @@
+//! integer radix conversion for `hx conv`
+
+use std::io::{self, Write};
+
+/// Parse an integer in hex (`0x`), octal (`0o`), binary (`0b`) or decimal,
+/// with `_` separators allowed. A leading `-` gives the 64 bit two's
+/// complement.
+///
+/// # Arguments
+///
+/// * `text` - integer literal, e.g. `0xdead_beef` or `-17`.
+pub fn parse(text: &str) -> Result<u64, String> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let digits = digits.replace('_', "");
+    let lower = digits.to_ascii_lowercase();
+    let (radix, body) = match lower.get(..2) {
+        Some("0x") => (16, &lower[2..]),
+        Some("0o") => (8, &lower[2..]),
+        Some("0b") => (2, &lower[2..]),
+        _ => (10, &lower[..]),
+    };
+    let value = u64::from_str_radix(body, radix)
+        .map_err(|_| format!("{text:?} is not a hex, decimal, octal or binary integer"))?;
+    match negative {
+        false => Ok(value),
+        true if value <= 1 << 63 => Ok(value.wrapping_neg()),
+        true => Err(format!("{text:?} doesn't fit in 64 bits")),
+    }
+}
+
+/// Sign extend the low `bits` bits of a value.
+fn signed(value: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+/// Whether the low `bits` bits of a value, zero or sign extended, give
+/// the value back.
+fn fits(value: u64, bits: u32) -> bool {
+    bits == 64 || value >> bits == 0 || signed(value, bits) as u64 == value
+}
+
+/// Print a value in every radix, its little and big endian bytes, and what
+/// its low 8, 16, 32 and 64 bits read as in two's complement. The bytes are
+/// as few as hold the value unsigned, or for a negative literal the
+/// smallest of 1, 2, 4 or 8 that holds it sign extended.
+///
+/// # Arguments
+///
+/// * `w` - output.
+/// * `value` - value to convert.
+/// * `negative` - whether the literal was written with a leading `-`.
+pub fn describe(w: &mut impl Write, value: u64, negative: bool) -> io::Result<()> {
+    writeln!(w, "{:>8}: {value:#x}", "hex")?;
+    writeln!(w, "{:>8}: {value}", "dec")?;
+    writeln!(w, "{:>8}: {value:#o}", "oct")?;
+    writeln!(w, "{:>8}: {value:#b}", "bin")?;
+    let width = match negative {
+        false => (64 - value.leading_zeros()).div_ceil(8).max(1),
+        true => {
+            [8, 16, 32, 64]
+                .into_iter()
+                .find(|bits| fits(value, *bits))
+                .unwrap_or(64)
+                / 8
+        }
+    };
+    let bytes = &value.to_le_bytes()[..width as usize];
+    let hex = |bytes: &mut dyn Iterator<Item = &u8>| {
+        bytes
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    writeln!(w, "{:>8}: {}", "le", hex(&mut bytes.iter()))?;
+    writeln!(w, "{:>8}: {}", "be", hex(&mut bytes.iter().rev()))?;
+    for bits in [8, 16, 32, 64] {
+        let note = match fits(value, bits) {
+            false => " (truncated)",
+            true => "",
+        };
+        writeln!(
+            w,
+            "{:>8}: {}{note}",
+            format!("i{bits}"),
+            signed(value, bits)
+        )?;
+    }
+    w.flush()
+}
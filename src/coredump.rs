@@ -0,0 +1,176 @@
+//! memory regions of ELF core files and Windows minidumps for `--region`,
+//! with the file offset of each region's bytes and its virtual address
+
+use std::io;
+
+/// A memory region saved in a dump
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// name to select it by, e.g. `load2`
+    pub name: String,
+    /// virtual address of its first byte
+    pub address: u64,
+    /// offset of its bytes in the dump file
+    pub offset: u64,
+    /// bytes saved in the file
+    pub size: u64,
+    /// access rights as `rwx`, dashes for the missing ones, empty when the
+    /// dump doesn't say
+    pub flags: String,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Unsigned integer of `width` bytes at `at`.
+fn uint(data: &[u8], at: usize, width: usize, big: bool) -> io::Result<u64> {
+    let bytes = at
+        .checked_add(width)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(|| invalid("truncated dump header"))?;
+    let fold = |value: u64, b: &u8| value << 8 | u64::from(*b);
+    Ok(match big {
+        true => bytes.iter().fold(0, fold),
+        false => bytes.iter().rev().fold(0, fold),
+    })
+}
+
+/// Memory regions of an ELF core file or a minidump.
+///
+/// # Arguments
+///
+/// * `data` - dump contents.
+pub fn regions(data: &[u8]) -> io::Result<Vec<Region>> {
+    if data.starts_with(b"\x7fELF") {
+        elf(data)
+    } else if data.starts_with(b"MDMP") {
+        minidump(data)
+    } else {
+        Err(invalid("not an ELF core file or a minidump"))
+    }
+}
+
+/// The `PT_LOAD` segments of an ELF file, named `load0`, `load1`, ...
+fn elf(data: &[u8]) -> io::Result<Vec<Region>> {
+    let wide = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err(invalid("unknown ELF class")),
+    };
+    let big = data.get(5) == Some(&2);
+    let word = |at: usize| match wide {
+        true => uint(data, at, 8, big),
+        false => uint(data, at, 4, big),
+    };
+    let (phoff, entsize, count) = match wide {
+        true => (
+            word(0x20)?,
+            uint(data, 0x36, 2, big)?,
+            uint(data, 0x38, 2, big)?,
+        ),
+        false => (
+            word(0x1c)?,
+            uint(data, 0x2a, 2, big)?,
+            uint(data, 0x2c, 2, big)?,
+        ),
+    };
+    let mut regions = Vec::new();
+    for i in 0..count {
+        let at = phoff.saturating_add(i * entsize) as usize;
+        if uint(data, at, 4, big)? != 1 {
+            continue;
+        }
+        // p_offset, p_vaddr, p_filesz and p_flags
+        let (offset, address, size, flags) = match wide {
+            true => (at + 8, at + 16, at + 32, at + 4),
+            false => (at + 4, at + 8, at + 16, at + 24),
+        };
+        let flags = uint(data, flags, 4, big)?;
+        regions.push(Region {
+            name: format!("load{}", regions.len()),
+            address: word(address)?,
+            offset: word(offset)?,
+            size: word(size)?,
+            flags: [(4, 'r'), (2, 'w'), (1, 'x')]
+                .iter()
+                .map(|(bit, c)| if flags & bit != 0 { *c } else { '-' })
+                .collect(),
+        });
+    }
+    Ok(regions)
+}
+
+/// The ranges of the memory list streams of a minidump, named `memory0`,
+/// `memory1`, ...
+fn minidump(data: &[u8]) -> io::Result<Vec<Region>> {
+    let count = uint(data, 8, 4, false)?;
+    let directory = uint(data, 12, 4, false)? as usize;
+    let mut regions = Vec::new();
+    let mut push = |address: u64, offset: u64, size: u64| {
+        regions.push(Region {
+            name: format!("memory{}", regions.len()),
+            address,
+            offset,
+            size,
+            flags: String::new(),
+        })
+    };
+    for i in 0..count as usize {
+        let entry = directory + i * 12;
+        let rva = uint(data, entry + 8, 4, false)? as usize;
+        match uint(data, entry, 4, false)? {
+            // MemoryListStream: descriptors with their own rva
+            5 => {
+                for j in 0..uint(data, rva, 4, false)? as usize {
+                    let at = rva + 4 + j * 16;
+                    push(
+                        uint(data, at, 8, false)?,
+                        uint(data, at + 12, 4, false)?,
+                        uint(data, at + 8, 4, false)?,
+                    );
+                }
+            }
+            // Memory64ListStream: ranges stored back to back from a base rva
+            9 => {
+                let mut offset = uint(data, rva + 8, 8, false)?;
+                for j in 0..uint(data, rva, 8, false)? as usize {
+                    let at = rva + 16 + j * 16;
+                    let size = uint(data, at + 8, 8, false)?;
+                    push(uint(data, at, 8, false)?, offset, size);
+                    offset = offset.saturating_add(size);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(regions)
+}
+
+/// Find a region by name, index or an address inside it.
+///
+/// # Arguments
+///
+/// * `regions` - regions of the dump.
+/// * `wanted` - name such as `load2`, index such as `2`, or `0x` address.
+pub fn select<'a>(regions: &'a [Region], wanted: &str) -> io::Result<&'a Region> {
+    let address = wanted
+        .strip_prefix("0x")
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok());
+    regions
+        .iter()
+        .find(|region| region.name == wanted)
+        .or_else(|| wanted.parse::<usize>().ok().and_then(|i| regions.get(i)))
+        .or_else(|| {
+            let address = address?;
+            regions
+                .iter()
+                .find(|region| region.address <= address && address - region.address < region.size)
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no region {wanted:?} in the dump"),
+            )
+        })
+}
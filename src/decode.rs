@@ -0,0 +1,158 @@
+//! structure decoders for `--decode`: generic type-length-value walks and
+//! LEB128 varints
+
+use crate::scalar::{self, Endian};
+use std::ops::Range;
+
+/// A structure `--decode` walks the input as
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Decoder {
+    /// type-length-value entries one after another
+    Tlv {
+        /// bytes of the type field
+        type_size: usize,
+        /// bytes of the length field
+        len_size: usize,
+        /// byte order of both fields
+        endian: Endian,
+    },
+    /// LEB128 varints one after another, as in WASM, DWARF and protobuf
+    Leb128 {
+        /// input offset of the first varint, the first byte dumped when None
+        at: Option<u64>,
+        /// number of varints
+        count: u64,
+    },
+}
+
+/// A type-length-value entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// offset of the type field
+    pub offset: usize,
+    /// type and the length the entry claims, None when the header is cut off
+    pub header: Option<(u64, u64)>,
+    /// value bytes present in the input
+    pub value: Range<usize>,
+    /// what's wrong with the entry, the walk stops at it
+    pub problem: Option<String>,
+}
+
+/// Walk type-length-value entries from the start of `data` to its end. An
+/// entry whose header or value runs past the end is the last one, flagged
+/// with the problem.
+///
+/// # Arguments
+///
+/// * `data` - input.
+/// * `type_size` - bytes of the type field, 1 to 8.
+/// * `len_size` - bytes of the length field, 1 to 8.
+/// * `endian` - byte order of both fields.
+pub fn tlv(data: &[u8], type_size: usize, len_size: usize, endian: Endian) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let header = type_size + len_size;
+    let mut at = 0;
+    while at < data.len() {
+        if data.len() - at < header {
+            entries.push(Entry {
+                offset: at,
+                header: None,
+                value: data.len()..data.len(),
+                problem: Some(format!(
+                    "header cut off after {} of {header} bytes",
+                    data.len() - at
+                )),
+            });
+            break;
+        }
+        let tag = scalar::decode(&data[at..at + type_size], endian);
+        let len = scalar::decode(&data[at + type_size..at + header], endian);
+        let start = at + header;
+        let left = (data.len() - start) as u64;
+        if len > left {
+            entries.push(Entry {
+                offset: at,
+                header: Some((tag, len)),
+                value: start..data.len(),
+                problem: Some(format!("length runs {} bytes past the end", len - left)),
+            });
+            break;
+        }
+        let end = start + len as usize;
+        entries.push(Entry {
+            offset: at,
+            header: Some((tag, len)),
+            value: start..end,
+            problem: None,
+        });
+        at = end;
+    }
+    entries
+}
+
+/// A LEB128 varint
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Varint {
+    /// offset of the first byte
+    pub offset: usize,
+    /// bytes of the varint
+    pub len: usize,
+    /// value read as unsigned
+    pub unsigned: u64,
+    /// value read as signed
+    pub signed: i64,
+    /// what's wrong with the varint, decoding stops at it
+    pub problem: Option<String>,
+}
+
+/// Decode up to `count` LEB128 varints one after another, from `at` in
+/// `data`. A varint cut off by the end of `data` or longer than 10 bytes
+/// is the last one, flagged with the problem.
+///
+/// # Arguments
+///
+/// * `data` - input.
+/// * `at` - offset of the first varint.
+/// * `count` - number of varints.
+pub fn leb128(data: &[u8], mut at: usize, count: u64) -> Vec<Varint> {
+    let mut varints = Vec::new();
+    while (varints.len() as u64) < count && at < data.len() {
+        let (mut unsigned, mut shift, mut len) = (0u64, 0, 0);
+        let mut problem = None;
+        let mut last = 0;
+        loop {
+            let Some(b) = data.get(at + len) else {
+                problem = Some("cut off by the end of the input".to_string());
+                break;
+            };
+            if len == 10 {
+                problem = Some("longer than 10 bytes, overflows 64 bits".to_string());
+                break;
+            }
+            len += 1;
+            last = *b;
+            unsigned |= u64::from(b & 0x7f) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        let signed = match shift < 64 && last & 0x40 != 0 {
+            true => (unsigned | (u64::MAX << shift)) as i64,
+            false => unsigned as i64,
+        };
+        let stop = problem.is_some();
+        varints.push(Varint {
+            offset: at,
+            len,
+            unsigned,
+            signed,
+            problem,
+        });
+        if stop {
+            break;
+        }
+        at += len;
+    }
+    varints
+}
@@ -0,0 +1,225 @@
+//! repeated content detection: unique line fingerprints, duplicate ranges
+//! and runs of one byte
+
+use crate::interrupt;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+/// A distinct line of input and how often it occurs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinePattern {
+    /// line bytes
+    pub bytes: Vec<u8>,
+    /// number of lines with these bytes
+    pub count: u64,
+    /// offset of the first such line
+    pub first: u64,
+}
+
+/// Split the input into lines of `columns` bytes and count each distinct
+/// line, most frequent first, ties in order of first appearance. Returns
+/// the patterns, the number of lines and the number of bytes read.
+///
+/// # Arguments
+///
+/// * `buf` - input to be read.
+/// * `columns` - bytes per line.
+/// * `truncate_len` - bytes to read, 0 reads everything.
+/// * `start_offset` - offset of the first byte.
+/// * `limit` - most bytes the distinct lines may take, failing with
+///   `OutOfMemory` past it.
+pub fn unique_lines(
+    buf: &mut dyn Read,
+    columns: u64,
+    truncate_len: u64,
+    start_offset: u64,
+    limit: Option<u64>,
+) -> io::Result<(Vec<LinePattern>, u64, u64)> {
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut patterns: Vec<LinePattern> = Vec::new();
+    let (mut lines, mut bytes) = (0, 0);
+    // bytes of the distinct lines, held twice, as key and pattern
+    let mut held: u64 = 0;
+    loop {
+        let mut want = columns.max(1);
+        if truncate_len > 0 {
+            want = want.min(truncate_len - bytes);
+        }
+        let mut line = Vec::new();
+        buf.take(want).read_to_end(&mut line)?;
+        if line.is_empty() {
+            break;
+        }
+        let offset = start_offset + bytes;
+        bytes += line.len() as u64;
+        lines += 1;
+        match seen.get(&line) {
+            Some(i) => patterns[*i].count += 1,
+            None => {
+                held += 2 * line.len() as u64;
+                if limit.is_some_and(|limit| held > limit) {
+                    return Err(io::ErrorKind::OutOfMemory.into());
+                }
+                seen.insert(line.clone(), patterns.len());
+                patterns.push(LinePattern {
+                    bytes: line,
+                    count: 1,
+                    first: offset,
+                });
+            }
+        }
+    }
+    // stable, so equally frequent lines keep their order of appearance
+    patterns.sort_by_key(|pattern| Reverse(pattern.count));
+    Ok((patterns, lines, bytes))
+}
+
+/// A range of input whose content already appeared at `source`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Duplicate {
+    /// start of the repeated range
+    pub offset: u64,
+    /// length of the range
+    pub len: u64,
+    /// start of the earlier copy
+    pub source: u64,
+}
+
+/// multiplier of the polynomial rolling hash
+const BASE: u64 = 0x100_0000_01b3;
+
+fn block_hash(block: &[u8]) -> u64 {
+    block.iter().fold(0u64, |h, b| {
+        h.wrapping_mul(BASE).wrapping_add(u64::from(*b))
+    })
+}
+
+/// Find ranges whose content repeats earlier in `data`.
+///
+/// Every block aligned to `block` bytes is indexed by hash, then a rolling
+/// hash checks every offset against the earlier blocks. Matches are verified
+/// byte for byte and grown in both directions, so any copy of at least
+/// `2 * block` bytes is found whatever its alignment.
+///
+/// # Arguments
+///
+/// * `data` - input.
+/// * `block` - block size.
+pub fn dedupe_scan(data: &[u8], block: usize) -> Vec<Duplicate> {
+    let mut found: Vec<Duplicate> = Vec::new();
+    if block == 0 || data.len() < 2 * block {
+        return found;
+    }
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    // Ctrl-C stops the scan, with what it found so far
+    for start in (0..=data.len() - block).step_by(block) {
+        if interrupt::interrupted() {
+            return found;
+        }
+        index
+            .entry(block_hash(&data[start..start + block]))
+            .or_default()
+            .push(start);
+    }
+    // BASE^(block - 1), to drop the outgoing byte from the rolling hash
+    let power = (1..block).fold(1u64, |p, _| p.wrapping_mul(BASE));
+
+    let mut offset = 0;
+    // end of the last reported range, matches don't grow back past it
+    let mut reported = 0;
+    let mut hash = block_hash(&data[..block]);
+    while offset + block <= data.len() && !interrupt::interrupted() {
+        let window = &data[offset..offset + block];
+        let source = index.get(&hash).and_then(|starts| {
+            starts
+                .iter()
+                .copied()
+                .find(|&start| start + block <= offset && &data[start..start + block] == window)
+        });
+
+        match source {
+            Some(source) => {
+                let (mut start, mut from) = (offset, source);
+                while start > reported && from > 0 && data[start - 1] == data[from - 1] {
+                    start -= 1;
+                    from -= 1;
+                }
+                let mut end = offset + block;
+                while end < data.len() && data[end] == data[source + end - offset] {
+                    end += 1;
+                }
+                found.push(Duplicate {
+                    offset: start as u64,
+                    len: (end - start) as u64,
+                    source: from as u64,
+                });
+                (offset, reported) = (end, end);
+                if offset + block <= data.len() {
+                    hash = block_hash(&data[offset..offset + block]);
+                }
+            }
+            None => {
+                if offset + block < data.len() {
+                    hash = hash
+                        .wrapping_sub(u64::from(data[offset]).wrapping_mul(power))
+                        .wrapping_mul(BASE)
+                        .wrapping_add(u64::from(data[offset + block]));
+                }
+                offset += 1;
+            }
+        }
+    }
+    found
+}
+
+/// Bytes of a run compared between checks for Ctrl-C
+const RUN_BLOCK: usize = 64 * 1024;
+
+/// A run of one byte value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Run {
+    /// start of the run
+    pub offset: u64,
+    /// length of the run
+    pub len: u64,
+    /// repeated byte
+    pub byte: u8,
+}
+
+/// Find runs of at least `min` equal bytes in `data`, in order.
+///
+/// # Arguments
+///
+/// * `data` - input.
+/// * `min` - shortest run reported.
+pub fn runs(data: &[u8], min: u64) -> Vec<Run> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    // Ctrl-C stops the scan, with what it found so far
+    while start < data.len() && !interrupt::interrupted() {
+        let byte = data[start];
+        let mut end = start + 1;
+        // a block at a time, so a long run stops on Ctrl-C too
+        while end < data.len() && !interrupt::interrupted() {
+            let block = &data[end..data.len().min(end + RUN_BLOCK)];
+            match block.iter().position(|b| *b != byte) {
+                Some(at) => {
+                    end += at;
+                    break;
+                }
+                None => end += block.len(),
+            }
+        }
+        let len = (end - start) as u64;
+        if len >= min {
+            found.push(Run {
+                offset: start as u64,
+                len,
+                byte,
+            });
+        }
+        start = end;
+    }
+    found
+}
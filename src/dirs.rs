@@ -0,0 +1,172 @@
+//! directories hx keeps its files in, where each platform expects them:
+//! the XDG base directories on Linux and other unixes, Application Support
+//! on macOS and AppData on Windows, each overridden by an `HX_` variable
+
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// environment variable overriding the config directory
+pub const CONFIG_DIR_ENV: &str = "HX_CONFIG_DIR";
+/// environment variable overriding the data directory
+pub const DATA_DIR_ENV: &str = "HX_DATA_DIR";
+/// environment variable overriding the state directory
+pub const STATE_DIR_ENV: &str = "HX_STATE_DIR";
+
+/// Platform conventions the directories follow
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Platform {
+    /// XDG base directories, Linux and the BSDs
+    Unix,
+    /// `~/Library/Application Support`
+    MacOs,
+    /// `%APPDATA%` and `%LOCALAPPDATA%`
+    Windows,
+}
+
+/// Platform implementation
+impl Platform {
+    /// Platform hx was built for.
+    pub fn current() -> Platform {
+        match () {
+            _ if cfg!(target_os = "macos") => Platform::MacOs,
+            _ if cfg!(windows) => Platform::Windows,
+            _ => Platform::Unix,
+        }
+    }
+}
+
+/// Directories hx keeps its files in, None where the environment doesn't
+/// say where home is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dirs {
+    /// settings written by hand
+    pub config: Option<PathBuf>,
+    /// files worth keeping, bookmarks
+    pub data: Option<PathBuf>,
+    /// files hx writes for itself, sessions and `--continue` positions
+    pub state: Option<PathBuf>,
+}
+
+/// Dirs implementation
+impl Dirs {
+    /// Directories of a platform.
+    ///
+    /// # Arguments
+    ///
+    /// * `platform` - conventions to follow.
+    /// * `var` - environment variable lookup, None for unset or empty.
+    pub fn resolve(platform: Platform, var: impl Fn(&str) -> Option<OsString>) -> Dirs {
+        let under = |name: &str, path: &str| var(name).map(|dir| Path::new(&dir).join(path));
+        let (config, data, state) = match platform {
+            Platform::Unix => (
+                under("XDG_CONFIG_HOME", "hx").or_else(|| under("HOME", ".config/hx")),
+                under("XDG_DATA_HOME", "hx").or_else(|| under("HOME", ".local/share/hx")),
+                under("XDG_STATE_HOME", "hx").or_else(|| under("HOME", ".local/state/hx")),
+            ),
+            Platform::MacOs => {
+                let support = under("HOME", "Library/Application Support/hx");
+                (
+                    support.clone(),
+                    support.clone(),
+                    support.map(|dir| dir.join("state")),
+                )
+            }
+            Platform::Windows => {
+                let roaming =
+                    under("APPDATA", "hx").or_else(|| under("USERPROFILE", "AppData\\Roaming\\hx"));
+                let local = under("LOCALAPPDATA", "hx")
+                    .or_else(|| under("USERPROFILE", "AppData\\Local\\hx"));
+                (roaming.clone(), roaming, local)
+            }
+        };
+        let own = |name: &str| var(name).map(PathBuf::from);
+        Dirs {
+            config: own(CONFIG_DIR_ENV).or(config),
+            data: own(DATA_DIR_ENV).or(data),
+            state: own(STATE_DIR_ENV).or(state),
+        }
+    }
+
+    /// Directories of the platform hx runs on, from its environment.
+    pub fn current() -> Dirs {
+        Dirs::resolve(Platform::current(), |name| {
+            env::var_os(name).filter(|value| !value.is_empty())
+        })
+    }
+
+    /// Directory of the session files named `@name`.
+    pub fn sessions(&self) -> Option<PathBuf> {
+        self.state.as_ref().map(|dir| dir.join("sessions"))
+    }
+
+    /// Directory of the bookmarks files named `@name`.
+    pub fn bookmarks(&self) -> Option<PathBuf> {
+        self.data.as_ref().map(|dir| dir.join("bookmarks"))
+    }
+}
+
+/// Path of a file given on the command line: `@name` is the file of that
+/// name in a directory of hx, anything else a path as it is.
+///
+/// # Arguments
+///
+/// * `value` - path, or `@` and a name without a directory.
+/// * `dir` - directory named files go in, None when there's none.
+/// * `extension` - extension of named files, e.g. `hxs`.
+/// * `env` - variable to point out when there's no directory.
+pub fn named_file(
+    value: &str,
+    dir: Option<PathBuf>,
+    extension: &str,
+    env: &str,
+) -> Result<PathBuf, String> {
+    let Some(name) = value.strip_prefix('@') else {
+        return Ok(PathBuf::from(value));
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("{value:?} isn't @ and a file name"));
+    }
+    match dir {
+        Some(dir) => Ok(dir.join(format!("{name}.{extension}"))),
+        None => Err(format!("no directory for {value}, set {env}")),
+    }
+}
+
+/// Write where hx keeps its files, a line for every directory with the
+/// variable overriding it, then a line for every kind of file.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `dirs` - directories to write.
+pub fn write(out: &mut dyn Write, dirs: &Dirs) -> io::Result<()> {
+    let shown = |dir: &Option<PathBuf>| match dir {
+        Some(dir) => dir.display().to_string(),
+        None => "none".to_string(),
+    };
+    for (name, dir, env) in [
+        ("config", &dirs.config, CONFIG_DIR_ENV),
+        ("data", &dirs.data, DATA_DIR_ENV),
+        ("state", &dirs.state, STATE_DIR_ENV),
+    ] {
+        writeln!(out, "{name:>9}: {}  (${env})", shown(dir))?;
+    }
+    let temp = crate::temp::dir(None);
+    writeln!(
+        out,
+        "{:>9}: {}  (${}, --temp-dir)",
+        "temp",
+        temp.display(),
+        crate::temp::TEMP_DIR_ENV
+    )?;
+    for (name, dir, file) in [
+        ("bookmarks", dirs.bookmarks(), "<name>.marks for @name"),
+        ("sessions", dirs.sessions(), "<name>.hxs for @name"),
+        ("continue", dirs.state.clone(), "a file per input"),
+    ] {
+        writeln!(out, "{name:>9}: {}  ({file})", shown(&dir))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,349 @@
+//! source lines for code addresses, from the DWARF line table of an ELF
+//! file. The parser is built with the `dwarf` feature.
+
+/// A row of the line table: code from `addr` on comes from `file:line`,
+/// up to the next row
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LineRow {
+    /// first address of the row
+    pub addr: u64,
+    /// index into the table's file names
+    pub file: usize,
+    /// 1 based source line
+    pub line: u64,
+    /// the row ends a sequence, its address holds no code of it
+    pub end: bool,
+}
+
+/// Line table of every compilation unit, rows sorted by address
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineTable {
+    /// source file names
+    pub files: Vec<String>,
+    /// rows
+    pub rows: Vec<LineRow>,
+}
+
+/// LineTable implementation
+impl LineTable {
+    /// `file:line` of the code at `addr`, None outside every sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - code address.
+    pub fn lookup(&self, addr: u64) -> Option<String> {
+        let index = self.rows.partition_point(|row| row.addr <= addr);
+        let row = self.rows.get(index.checked_sub(1)?)?;
+        match row.end {
+            true => None,
+            false => Some(format!("{}:{}", self.files[row.file], row.line)),
+        }
+    }
+}
+
+#[cfg(feature = "dwarf")]
+pub use parser::line_table;
+
+#[cfg(feature = "dwarf")]
+mod parser {
+    use super::{LineRow, LineTable};
+    use crate::scalar::{decode, Endian};
+    use crate::symbols::Elf;
+    use std::io;
+
+    fn invalid(msg: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("bad DWARF line table: {msg}"),
+        )
+    }
+
+    /// Reads DWARF values from a section
+    struct Reader<'a> {
+        data: &'a [u8],
+        at: usize,
+        endian: Endian,
+    }
+
+    impl<'a> Reader<'a> {
+        fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+            let bytes = self
+                .data
+                .get(self.at..self.at + len)
+                .ok_or_else(|| invalid("truncated"))?;
+            self.at += len;
+            Ok(bytes)
+        }
+
+        fn uint(&mut self, len: usize) -> io::Result<u64> {
+            let endian = self.endian;
+            Ok(decode(self.bytes(len)?, endian))
+        }
+
+        fn uleb(&mut self) -> io::Result<u64> {
+            let mut value = 0u64;
+            for shift in (0..).step_by(7) {
+                let byte = self.uint(1)?;
+                if shift < 64 {
+                    value |= (byte & 0x7f) << shift;
+                }
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            Ok(value)
+        }
+
+        fn sleb(&mut self) -> io::Result<i64> {
+            let mut value = 0i64;
+            let mut shift = 0;
+            loop {
+                let byte = self.uint(1)?;
+                if shift < 64 {
+                    value |= ((byte & 0x7f) as i64) << shift;
+                }
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    if shift < 64 && byte & 0x40 != 0 {
+                        value |= -1i64 << shift;
+                    }
+                    return Ok(value);
+                }
+            }
+        }
+
+        fn string(&mut self) -> io::Result<String> {
+            let rest = self.data.get(self.at..).unwrap_or_default();
+            let len = rest
+                .iter()
+                .position(|b| *b == 0)
+                .ok_or_else(|| invalid("unterminated string"))?;
+            self.at += len + 1;
+            Ok(String::from_utf8_lossy(&rest[..len]).to_string())
+        }
+    }
+
+    /// String at `offset` of a string section.
+    fn string_at(section: Option<&[u8]>, offset: u64) -> io::Result<String> {
+        let rest = section
+            .and_then(|section| section.get(offset as usize..))
+            .ok_or_else(|| invalid("string offset out of range"))?;
+        let len = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+        Ok(String::from_utf8_lossy(&rest[..len]).to_string())
+    }
+
+    /// Sections DWARF 5 file tables point into
+    struct Strings<'a> {
+        line_str: Option<&'a [u8]>,
+        str: Option<&'a [u8]>,
+    }
+
+    /// A DWARF 5 directory or file entry: path and directory index.
+    fn entry(
+        r: &mut Reader,
+        formats: &[(u64, u64)],
+        offset_size: usize,
+        strings: &Strings,
+    ) -> io::Result<(String, u64)> {
+        let mut path = String::new();
+        let mut dir = 0;
+        for (content, form) in formats {
+            let mut text = None;
+            let value = match form {
+                0x08 => {
+                    text = Some(r.string()?);
+                    0
+                }
+                0x1f => {
+                    let offset = r.uint(offset_size)?;
+                    text = Some(string_at(strings.line_str, offset)?);
+                    0
+                }
+                0x0e => {
+                    let offset = r.uint(offset_size)?;
+                    text = Some(string_at(strings.str, offset)?);
+                    0
+                }
+                0x0b => r.uint(1)?,
+                0x05 => r.uint(2)?,
+                0x06 => r.uint(4)?,
+                0x07 => r.uint(8)?,
+                0x0f => r.uleb()?,
+                0x1e => {
+                    r.bytes(16)?;
+                    0
+                }
+                0x09 => {
+                    let len = r.uleb()? as usize;
+                    r.bytes(len)?;
+                    0
+                }
+                _ => return Err(invalid("unsupported entry form")),
+            };
+            match content {
+                1 => path = text.unwrap_or_default(),
+                2 => dir = value,
+                _ => {}
+            }
+        }
+        Ok((path, dir))
+    }
+
+    /// File names of a unit, joined with their directory unless it is the
+    /// compilation directory.
+    fn join(dirs: &[String], name: String, dir: u64, first_dir: u64) -> String {
+        match dirs.get(dir as usize) {
+            Some(dir_name) if dir >= first_dir && !name.starts_with('/') => {
+                format!("{dir_name}/{name}")
+            }
+            _ => name,
+        }
+    }
+
+    /// Parse the `.debug_line` section of an ELF file, an empty table when
+    /// there is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - ELF file contents.
+    pub fn line_table(data: &[u8]) -> io::Result<LineTable> {
+        let elf = Elf::new(data)?;
+        let mut table = LineTable::default();
+        let Some(section) = elf.section_named(".debug_line")? else {
+            return Ok(table);
+        };
+        let strings = Strings {
+            line_str: elf.section_named(".debug_line_str")?,
+            str: elf.section_named(".debug_str")?,
+        };
+        let endian = match elf.big_endian {
+            true => Endian::Big,
+            false => Endian::Little,
+        };
+        let mut r = Reader {
+            data: section,
+            at: 0,
+            endian,
+        };
+        while r.at < section.len() {
+            let (unit_len, offset_size) = match r.uint(4)? {
+                0xffff_ffff => (r.uint(8)?, 8),
+                len => (len, 4),
+            };
+            let unit_end = r.at + unit_len as usize;
+            let version = r.uint(2)?;
+            if !(2..=5).contains(&version) {
+                return Err(invalid("unsupported version"));
+            }
+            let mut address_size = if elf.wide { 8 } else { 4 };
+            if version >= 5 {
+                address_size = r.uint(1)? as usize;
+                r.uint(1)?;
+            }
+            let header_len = r.uint(offset_size)? as usize;
+            let program = r.at + header_len;
+            let min_inst = r.uint(1)?;
+            if version >= 4 {
+                r.uint(1)?;
+            }
+            // default_is_stmt, every row counts as a statement here
+            r.uint(1)?;
+            let line_base = r.uint(1)? as u8 as i8 as i64;
+            let line_range = r.uint(1)?.max(1);
+            let opcode_base = r.uint(1)?;
+            let lengths = r.bytes(opcode_base.saturating_sub(1) as usize)?.to_vec();
+
+            // file indexes are 1 based before DWARF 5
+            let first_file = table.files.len();
+            let mut files = Vec::new();
+            if version >= 5 {
+                let formats = |r: &mut Reader| -> io::Result<Vec<(u64, u64)>> {
+                    let count = r.uint(1)?;
+                    (0..count).map(|_| Ok((r.uleb()?, r.uleb()?))).collect()
+                };
+                let dir_formats = formats(&mut r)?;
+                let mut dirs = Vec::new();
+                for _ in 0..r.uleb()? {
+                    dirs.push(entry(&mut r, &dir_formats, offset_size, &strings)?.0);
+                }
+                let file_formats = formats(&mut r)?;
+                for _ in 0..r.uleb()? {
+                    let (name, dir) = entry(&mut r, &file_formats, offset_size, &strings)?;
+                    files.push(join(&dirs, name, dir, 1));
+                }
+            } else {
+                let mut dirs = vec![String::new()];
+                loop {
+                    let dir = r.string()?;
+                    if dir.is_empty() {
+                        break;
+                    }
+                    dirs.push(dir);
+                }
+                files.push(String::from("?"));
+                loop {
+                    let name = r.string()?;
+                    if name.is_empty() {
+                        break;
+                    }
+                    let dir = r.uleb()?;
+                    r.uleb()?;
+                    r.uleb()?;
+                    files.push(join(&dirs, name, dir, 1));
+                }
+            }
+            table.files.extend(files);
+
+            r.at = program;
+            let start_file = if version >= 5 { 0 } else { 1 };
+            let (mut addr, mut file, mut line) = (0u64, start_file, 1i64);
+            while r.at < unit_end {
+                let mut emit = |end: bool, addr: u64, file: u64, line: i64| {
+                    table.rows.push(LineRow {
+                        addr,
+                        file: (first_file + file as usize).min(table.files.len().max(1) - 1),
+                        line: line.max(0) as u64,
+                        end,
+                    })
+                };
+                let opcode = r.uint(1)?;
+                match opcode {
+                    op if op >= opcode_base => {
+                        let adjusted = op - opcode_base;
+                        addr = addr.wrapping_add(adjusted / line_range * min_inst);
+                        line += line_base + (adjusted % line_range) as i64;
+                        emit(false, addr, file, line);
+                    }
+                    0 => {
+                        let len = r.uleb()? as usize;
+                        let next = r.at + len;
+                        match r.uint(1)? {
+                            1 => {
+                                emit(true, addr, file, line);
+                                (addr, file, line) = (0, start_file, 1);
+                            }
+                            2 => addr = r.uint(address_size.min(len.saturating_sub(1)))?,
+                            _ => {}
+                        }
+                        r.at = next;
+                    }
+                    1 => emit(false, addr, file, line),
+                    2 => addr = addr.wrapping_add(r.uleb()?.wrapping_mul(min_inst)),
+                    3 => line += r.sleb()?,
+                    4 => file = r.uleb()?,
+                    8 => addr = addr.wrapping_add((255 - opcode_base) / line_range * min_inst),
+                    9 => addr = addr.wrapping_add(r.uint(2)?),
+                    op => {
+                        for _ in 0..lengths.get(op as usize - 1).copied().unwrap_or(0) {
+                            r.uleb()?;
+                        }
+                    }
+                }
+            }
+            r.at = unit_end;
+        }
+        // sequences may come in any order, rows within one are increasing
+        table.rows.sort_by_key(|row| (row.addr, !row.end));
+        Ok(table)
+    }
+}
@@ -0,0 +1,286 @@
+//! text encoding guesses for `--detect-encoding`: byte order marks first,
+//! then how well a sample decodes as each encoding and how much of it
+//! reads as text; and the byte order mark, line ending and NUL tally of
+//! `--text-report`
+
+use crate::charset::Charset;
+
+/// Bytes of the input the guesses are based on
+pub const SAMPLE: usize = 64 * 1024;
+
+/// A text encoding hx can recognize
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, ascii included
+    Utf8,
+    /// little endian UTF-16
+    Utf16Le,
+    /// big endian UTF-16
+    Utf16Be,
+    /// ISO 8859-1, one character per byte
+    Latin1,
+    /// Shift-JIS, one or two bytes per character
+    ShiftJis,
+}
+
+/// Encoding implementation
+impl Encoding {
+    /// Name in the report.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+            Encoding::ShiftJis => "shift-jis",
+        }
+    }
+
+    /// Character panel charset closest to the encoding: UTF-16 has its
+    /// own, everything else shows its ascii bytes.
+    pub fn charset(&self) -> Charset {
+        match self {
+            Encoding::Utf16Le => Charset::Utf16Le,
+            Encoding::Utf16Be => Charset::Utf16Be,
+            _ => Charset::Ascii,
+        }
+    }
+}
+
+/// One likely encoding of a sample
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Guess {
+    /// the encoding
+    pub encoding: Encoding,
+    /// whether the sample starts with its byte order mark
+    pub bom: bool,
+    /// how likely it is, from 0 to 1
+    pub confidence: f64,
+}
+
+/// Share of bytes that read as text: anything but control characters
+/// other than whitespace and escape.
+fn text_share(data: &[u8]) -> f64 {
+    let text = data
+        .iter()
+        .filter(|b| !matches!(b, 0x00..=0x08 | 0x0e..=0x1a | 0x1c..=0x1f | 0x7f))
+        .count();
+    text as f64 / data.len() as f64
+}
+
+/// Confidence of UTF-8: no invalid sequences, higher when there are
+/// multibyte characters. A sequence cut short by the end of the sample
+/// doesn't count against it.
+fn utf8(data: &[u8]) -> f64 {
+    let valid = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&data[..error.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return 0.0,
+    };
+    match valid.is_ascii() {
+        true => 0.8,
+        false => 0.95,
+    }
+}
+
+/// Confidence of UTF-16 without a byte order mark: ascii range text has
+/// a zero high byte in every code unit, and the units decode without
+/// unpaired surrogates.
+fn utf16(data: &[u8], big: bool) -> f64 {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| match big {
+            true => u16::from_be_bytes([pair[0], pair[1]]),
+            false => u16::from_le_bytes([pair[0], pair[1]]),
+        })
+        .collect();
+    if units.is_empty() || char::decode_utf16(units.iter().copied()).any(|c| c.is_err()) {
+        return 0.0;
+    }
+    let high = units.iter().filter(|unit| **unit >> 8 == 0).count();
+    let low = units.iter().filter(|unit| **unit & 0xff == 0).count();
+    (high as f64 - low as f64).max(0.0) / units.len() as f64
+}
+
+/// Confidence of Latin-1: every byte decodes, so it's a fallback for
+/// text with high bytes that isn't UTF-8, less likely the more C1
+/// control bytes it has.
+fn latin1(data: &[u8]) -> f64 {
+    let high = data.iter().filter(|b| **b >= 0x80).count();
+    if high == 0 {
+        return 0.5;
+    }
+    let c1 = data.iter().filter(|b| (0x80..0xa0).contains(*b)).count();
+    0.7 * (1.0 - c1 as f64 / high as f64)
+}
+
+/// Confidence of Shift-JIS: lead bytes followed by valid trail bytes, at
+/// least one two byte character, halfwidth katakana allowed.
+fn shift_jis(data: &[u8]) -> f64 {
+    let mut pairs = 0;
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            0x00..=0x7f | 0xa1..=0xdf => i += 1,
+            0x81..=0x9f | 0xe0..=0xfc => match data.get(i + 1) {
+                Some(0x40..=0x7e | 0x80..=0xfc) => {
+                    pairs += 1;
+                    i += 2;
+                }
+                // a lead byte at the end of the sample
+                None => break,
+                Some(_) => return 0.0,
+            },
+            _ => return 0.0,
+        }
+    }
+    match pairs {
+        0 => 0.0,
+        _ => 0.85,
+    }
+}
+
+/// Byte order marks and the encodings they mark
+const BOMS: [(&[u8], Encoding); 3] = [
+    (b"\xef\xbb\xbf", Encoding::Utf8),
+    (b"\xff\xfe", Encoding::Utf16Le),
+    (b"\xfe\xff", Encoding::Utf16Be),
+];
+
+/// Encoding whose byte order mark starts the data, if any.
+///
+/// # Arguments
+///
+/// * `data` - first bytes of the input.
+pub fn bom(data: &[u8]) -> Option<Encoding> {
+    BOMS.iter()
+        .find(|(bom, _)| data.starts_with(bom))
+        .map(|(_, encoding)| *encoding)
+}
+
+/// What `--text-report` tells about a supposed text file, tallied a chunk
+/// at a time
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextReport {
+    /// encoding of the byte order mark the input starts with
+    pub bom: Option<Encoding>,
+    /// lines ended by a lone `\n`
+    pub lf: u64,
+    /// lines ended by `\r\n`
+    pub crlf: u64,
+    /// lines ended by a lone `\r`
+    pub cr: u64,
+    /// NUL bytes
+    pub nuls: u64,
+    /// offset of the first NUL byte
+    pub first_nul: Option<u64>,
+    /// lines, the last one counted with or without a line ending
+    pub lines: u64,
+    /// bytes of the longest line, without its line ending
+    pub longest: u64,
+    /// number of the longest line, counting from 1, 0 without lines
+    pub longest_line: u64,
+    /// bytes read so far
+    pub bytes: u64,
+    /// bytes of the line being read
+    current: u64,
+    /// the last byte read was a `\r`, which a `\n` makes a CRLF
+    pending_cr: bool,
+}
+
+/// TextReport implementation
+impl TextReport {
+    /// End the line being read.
+    fn end_line(&mut self) {
+        self.lines += 1;
+        if self.current > self.longest || self.longest_line == 0 {
+            self.longest = self.current;
+            self.longest_line = self.lines;
+        }
+        self.current = 0;
+    }
+
+    /// Tally the next chunk of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - bytes following the ones already fed.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if self.bytes == 0 {
+            self.bom = bom(chunk);
+        }
+        for b in chunk {
+            match *b {
+                // the \r already ended the line
+                b'\n' if self.pending_cr => {
+                    self.cr -= 1;
+                    self.crlf += 1;
+                }
+                b'\n' => {
+                    self.end_line();
+                    self.lf += 1;
+                }
+                b'\r' => {
+                    self.end_line();
+                    self.cr += 1;
+                }
+                0 => {
+                    self.first_nul.get_or_insert(self.bytes);
+                    self.nuls += 1;
+                    self.current += 1;
+                }
+                _ => self.current += 1,
+            }
+            self.pending_cr = *b == b'\r';
+            self.bytes += 1;
+        }
+    }
+
+    /// Count a last line without a line ending, after the last chunk.
+    pub fn finish(&mut self) {
+        if self.current > 0 {
+            self.end_line();
+        }
+    }
+}
+
+/// Likely encodings of a sample, the most likely first. A byte order mark
+/// settles it; without one every encoding the sample could be is listed
+/// with its confidence, scaled by the share of the sample that reads as
+/// text for the byte oriented ones.
+///
+/// # Arguments
+///
+/// * `data` - sample of the input, e.g. its first `SAMPLE` bytes.
+pub fn detect(data: &[u8]) -> Vec<Guess> {
+    if let Some(encoding) = bom(data) {
+        return vec![Guess {
+            encoding,
+            bom: true,
+            confidence: 1.0,
+        }];
+    }
+    if data.is_empty() {
+        return Vec::new();
+    }
+    let text = text_share(data);
+    let mut guesses: Vec<Guess> = [
+        (Encoding::Utf8, utf8(data) * text),
+        (Encoding::Utf16Le, utf16(data, false)),
+        (Encoding::Utf16Be, utf16(data, true)),
+        (Encoding::ShiftJis, shift_jis(data) * text),
+        (Encoding::Latin1, latin1(data) * text),
+    ]
+    .into_iter()
+    .filter(|(_, confidence)| *confidence > 0.0)
+    .map(|(encoding, confidence)| Guess {
+        encoding,
+        bom: false,
+        confidence,
+    })
+    .collect();
+    guesses.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    guesses
+}
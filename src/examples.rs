@@ -0,0 +1,138 @@
+//! curated example invocations for `hx examples` and the end of `--help`,
+//! grouped by topic; the tests run every one of them
+
+use std::io::{self, Write};
+
+/// A runnable example invocation
+pub struct Example {
+    /// topic it belongs to, e.g. `arrays`
+    pub topic: &'static str,
+    /// what it does
+    pub about: &'static str,
+    /// shell command line; the files it reads are `file.bin`, `old.bin`,
+    /// `new.bin` and `mask.bin`
+    pub command: &'static str,
+}
+
+/// Topics in the order they're printed, with what their examples show
+pub const TOPICS: [(&str, &str); 5] = [
+    ("basics", "dumping files, formats and offsets"),
+    ("piping", "stdin, pipes and raw bytes"),
+    ("arrays", "source code arrays"),
+    ("diffs", "comparing inputs"),
+    ("searches", "finding and counting bytes"),
+];
+
+/// Every example, grouped by topic
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        topic: "basics",
+        about: "Dump a file, 16 bytes per line",
+        command: "hx -c16 file.bin",
+    },
+    Example {
+        topic: "basics",
+        about: "Dump 8 bytes from offset 0x40 in binary",
+        command: "hx -fb -s 0x40 -l 8 file.bin",
+    },
+    Example {
+        topic: "basics",
+        about: "Dump the last 32 bytes",
+        command: "hx --skip end-32 file.bin",
+    },
+    Example {
+        topic: "piping",
+        about: "Dump what a command writes",
+        command: "printf 'hello, world' | hx",
+    },
+    Example {
+        topic: "piping",
+        about: "Dump bytes written as a literal, without a file",
+        command: "hx echo \"de ad be ef 'hx' 00*4\"",
+    },
+    Example {
+        topic: "piping",
+        about: "Cut 16 bytes out of a file",
+        command: "hx --raw -s 16 -l 16 file.bin > part.bin",
+    },
+    Example {
+        topic: "piping",
+        about: "Turn a dump back into the bytes",
+        command: "hx -t0 file.bin | hx --reverse > copy.bin",
+    },
+    Example {
+        topic: "arrays",
+        about: "Write a file as a rust array",
+        command: "hx -ar file.bin",
+    },
+    Example {
+        topic: "arrays",
+        about: "Write the first 16 bytes as a C array of 8 bytes per row",
+        command: "hx -ac -c8 -l16 file.bin",
+    },
+    Example {
+        topic: "arrays",
+        about: "Write a file as a python bytes array",
+        command: "hx -ap file.bin",
+    },
+    Example {
+        topic: "diffs",
+        about: "Compare a file with another, ignoring the bits a mask clears",
+        command: "hx match new.bin --pattern old.bin --mask mask.bin",
+    },
+    Example {
+        topic: "diffs",
+        about: "Check a dump hasn't changed since it was saved",
+        command: "hx file.bin > dump.txt && hx file.bin --expect dump.txt",
+    },
+    Example {
+        topic: "searches",
+        about: "Dump the lines holding the ELF magic bytes",
+        command: "hx --find \"7f 'ELF'\" file.bin",
+    },
+    Example {
+        topic: "searches",
+        about: "Count the NUL bytes",
+        command: "hx --count-matches --find 00 file.bin",
+    },
+];
+
+/// Write the examples of a topic, or of every topic, each under what it
+/// does.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `topic` - topic to write, None for all of them.
+pub fn write(out: &mut dyn Write, topic: Option<&str>) -> io::Result<()> {
+    let topics = TOPICS
+        .iter()
+        .filter(|(name, _)| topic.is_none_or(|topic| topic == *name));
+    for (i, (name, about)) in topics.enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{name}: {about}")?;
+        for example in EXAMPLES.iter().filter(|example| example.topic == *name) {
+            writeln!(out, "\n  # {}\n  {}", example.about, example.command)?;
+        }
+    }
+    Ok(())
+}
+
+/// Examples section at the end of `--help`: the first example of every
+/// topic, and where to find the rest.
+pub fn help_section() -> String {
+    let mut section = "Examples:\n".to_string();
+    for (name, _) in TOPICS {
+        if let Some(example) = EXAMPLES.iter().find(|example| example.topic == name) {
+            section.push_str(&format!("  {}\n", example.command));
+        }
+    }
+    let names: Vec<&str> = TOPICS.iter().map(|(name, _)| *name).collect();
+    section.push_str(&format!(
+        "\nRun hx examples [TOPIC] for more, topics: {}",
+        names.join(", ")
+    ));
+    section
+}
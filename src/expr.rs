@@ -0,0 +1,144 @@
+//! offset expressions: `0x400+3*512`, `end-256`, `(0x10+2)*4`, `1MiB`
+
+/// Evaluate an offset expression of decimal and `0x` hex numbers, with an
+/// optional `KiB`, `MiB`, `GiB` or `TiB` unit, `end`, `+ - * /` and
+/// parentheses, in unsigned 64 bit arithmetic.
+///
+/// # Arguments
+///
+/// * `text` - expression.
+/// * `end` - length of the input `end` stands for, None when it is unknown.
+pub fn eval(text: &str, end: Option<u64>) -> Result<u64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        at: 0,
+        end,
+    };
+    let value = parser.sum()?;
+    match parser.tokens.get(parser.at) {
+        None => Ok(value),
+        Some(token) => Err(format!("unexpected {token:?}")),
+    }
+}
+
+/// Unit suffixes and the power of two they stand for
+const UNITS: [(&str, u32); 4] = [("KiB", 10), ("MiB", 20), ("GiB", 30), ("TiB", 40)];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    End,
+    Op(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            ' ' => {}
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(Token::Op(c)),
+            c if c.is_ascii_alphanumeric() => {
+                let mut stop = start + 1;
+                while let Some((i, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() && *c != '_' {
+                        break;
+                    }
+                    stop = i + 1;
+                    chars.next();
+                }
+                let word = text[start..stop].replace('_', "");
+                if word == "end" {
+                    tokens.push(Token::End);
+                    continue;
+                }
+                let (word, shift) = UNITS
+                    .iter()
+                    .find_map(|(unit, shift)| {
+                        word.strip_suffix(unit)
+                            .filter(|digits| !digits.is_empty())
+                            .map(|digits| (digits, *shift))
+                    })
+                    .unwrap_or((&word, 0));
+                let number = match word.strip_prefix("0x").or(word.strip_prefix("0X")) {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                let number =
+                    number.map_err(|_| format!("{:?} is not a number", &text[start..stop]))?;
+                tokens.push(Token::Number(number.checked_mul(1 << shift).ok_or_else(
+                    || format!("{:?} doesn't fit in 64 bits", &text[start..stop]),
+                )?));
+            }
+            c => return Err(format!("unexpected {c:?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+    end: Option<u64>,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<char> {
+        match self.tokens.get(self.at) {
+            Some(Token::Op(op)) => Some(*op),
+            _ => None,
+        }
+    }
+
+    /// sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<u64, String> {
+        let mut value = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek_op() {
+            self.at += 1;
+            let rhs = self.product()?;
+            value = match op {
+                '+' => value.checked_add(rhs).ok_or("offset overflows")?,
+                _ => value.checked_sub(rhs).ok_or("offset is negative")?,
+            };
+        }
+        Ok(value)
+    }
+
+    /// product := atom (('*' | '/') atom)*
+    fn product(&mut self) -> Result<u64, String> {
+        let mut value = self.atom()?;
+        while let Some(op @ ('*' | '/')) = self.peek_op() {
+            self.at += 1;
+            let rhs = self.atom()?;
+            value = match op {
+                '*' => value.checked_mul(rhs).ok_or("offset overflows")?,
+                _ => value.checked_div(rhs).ok_or("division by zero")?,
+            };
+        }
+        Ok(value)
+    }
+
+    /// atom := number | 'end' | '(' sum ')'
+    fn atom(&mut self) -> Result<u64, String> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::End) => self.end.ok_or_else(|| {
+                "end is only known for file inputs, read a stream with --two-pass".to_string()
+            }),
+            Some(Token::Op('(')) => {
+                let value = self.sum()?;
+                match self.tokens.get(self.at) {
+                    Some(Token::Op(')')) => {
+                        self.at += 1;
+                        Ok(value)
+                    }
+                    _ => Err("missing )".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("expression ends early".to_string()),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! float search for `--find-float`: encodings of values within a tolerance
+//! of a wanted one, in one byte order or both, at aligned offsets or at
+//! every one
+
+use crate::interrupt;
+use crate::scalar::{self, Endian, ScalarType};
+
+/// Value, tolerance and layout of a float search
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FloatSearch {
+    /// f32 or f64
+    pub kind: ScalarType,
+    /// wanted value
+    pub value: f64,
+    /// largest difference from it still found
+    pub tolerance: f64,
+    /// byte order, None for both
+    pub endian: Option<Endian>,
+    /// look at every offset, not only those a multiple of the width
+    pub unaligned: bool,
+}
+
+/// FloatSearch implementation
+impl FloatSearch {
+    /// Raw bits read as the type.
+    fn number(&self, raw: u64) -> f64 {
+        match self.kind {
+            ScalarType::F32 => f64::from(f32::from_bits(raw as u32)),
+            _ => f64::from_bits(raw),
+        }
+    }
+
+    /// Whether raw bits are a value within the tolerance, never a NaN. The
+    /// wanted value is rounded to the type first, so an f32 is found
+    /// exactly.
+    fn matches(&self, raw: u64) -> bool {
+        let wanted = match self.kind {
+            ScalarType::F32 => f64::from(self.value as f32),
+            _ => self.value,
+        };
+        (self.number(raw) - wanted).abs() <= self.tolerance
+    }
+
+    /// Find the values in data. Returns the index of each in the data, its
+    /// value bits and its byte order; bytes that match in both orders are
+    /// reported once, as little endian.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to search.
+    /// * `start_offset` - offset of the first byte, which alignment counts
+    ///   from.
+    pub fn scan(&self, data: &[u8], start_offset: u64) -> Vec<(usize, u64, Endian)> {
+        let width = self.kind.width();
+        let orders = match self.endian {
+            Some(endian) => vec![endian],
+            None => vec![Endian::Little, Endian::Big],
+        };
+        // Ctrl-C stops the scan, with what it found so far
+        data.windows(width)
+            .enumerate()
+            .take_while(|_| !interrupt::interrupted())
+            .filter(|(at, _)| {
+                self.unaligned || (start_offset + *at as u64).is_multiple_of(width as u64)
+            })
+            .filter_map(|(at, word)| {
+                orders.iter().find_map(|endian| {
+                    let raw = scalar::decode(word, *endian);
+                    self.matches(raw).then_some((at, raw, *endian))
+                })
+            })
+            .collect()
+    }
+
+    /// Label of a value found, its type, byte order and value, e.g.
+    /// `f32le 3.14159`.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - value bits.
+    /// * `endian` - byte order it was found in.
+    pub fn label(&self, raw: u64, endian: Endian) -> String {
+        let order = match endian {
+            Endian::Little => "le",
+            Endian::Big => "be",
+        };
+        format!("{}{order} {}", self.kind, self.kind.display(raw))
+    }
+}
@@ -1,3 +1,5 @@
+use crate::scalar::{self, Endian, ScalarType};
+
 /// nothing ⇒ Display
 /// ? ⇒ Debug
 /// o ⇒ Octal
@@ -8,6 +10,9 @@
 /// e ⇒ LowerExp
 /// E ⇒ UpperExp
 /// evaluate for traits implementation
+///
+/// The exponent formats read words of `--float` bytes as floats; a lone
+/// byte, as in `--rle` runs or the byte table, prints its integer value.
 #[derive(Copy, Clone, Debug)]
 pub enum Format {
     /// octal format
@@ -21,14 +26,217 @@ pub enum Format {
     /// binary format
     Binary,
     /// lower exp format
-    LowerExp,
+    LowerExp(FloatWord),
     /// upper exp format
-    UpperExp,
+    UpperExp(FloatWord),
     /// unknown format
     Unknown,
 }
 
+/// Float words of the exponent formats
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FloatWord {
+    /// `f32` or `f64`
+    pub kind: ScalarType,
+    /// byte order of the words
+    pub endian: Endian,
+}
+
+/// FloatWord implementation
+impl FloatWord {
+    /// Parse a float word type: `f32` or `f64` with a `le` or `be` suffix,
+    /// little endian when left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - type, e.g. `f32` or `f64be`.
+    pub fn parse(text: &str) -> Result<FloatWord, String> {
+        let (name, endian) = match (text.strip_suffix("le"), text.strip_suffix("be")) {
+            (Some(name), _) => (name, Endian::Little),
+            (_, Some(name)) => (name, Endian::Big),
+            _ => (text, Endian::Little),
+        };
+        match ScalarType::parse(name) {
+            Some(kind @ (ScalarType::F32 | ScalarType::F64)) => Ok(FloatWord { kind, endian }),
+            _ => Err(format!("{text:?} is not f32 or f64 with le or be")),
+        }
+    }
+}
+
+impl Default for FloatWord {
+    fn default() -> FloatWord {
+        FloatWord {
+            kind: ScalarType::F32,
+            endian: Endian::Little,
+        }
+    }
+}
+
+/// Integer words of `--group`: bytes shown as one cell and their order
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    /// bytes of a word, 1 for single bytes
+    pub bytes: usize,
+    /// byte order of the words
+    pub endian: Endian,
+}
+
+impl Default for Group {
+    fn default() -> Group {
+        Group {
+            bytes: 1,
+            endian: Endian::Little,
+        }
+    }
+}
+
+/// Group implementation
+impl Group {
+    /// Bytes printed in one cell: the group, or the float width of the
+    /// exponent formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - format of the cells.
+    pub fn word(&self, format: Format) -> usize {
+        format.word().max(self.bytes)
+    }
+
+    /// Characters of one cell, without the separator.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - format of the cells.
+    /// * `prefix` - whether or not to add a prefix
+    pub fn cell_width(&self, format: Format, prefix: bool) -> usize {
+        self.format_word(format, &vec![0; self.word(format)], prefix)
+            .len()
+    }
+
+    /// Formats one cell: a byte, a float word for the exponent formats or
+    /// an integer word in the base of the format. A word cut short by the
+    /// end of the input prints its bytes in hex, padded to the cell width.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - format of the cells.
+    /// * `bytes` - the cell's bytes, at most `word()` of them
+    /// * `prefix` - whether or not to add a prefix
+    pub fn format_word(&self, format: Format, bytes: &[u8], prefix: bool) -> String {
+        if self.bytes == 1 {
+            return format.format_word(bytes, prefix);
+        }
+        let bits = self.bytes * 8;
+        let (digits, marker) = match format {
+            Format::Octal => (bits.div_ceil(3), "0o"),
+            Format::Binary => (bits, "0b"),
+            _ => (bits / 4, "0x"),
+        };
+        let marker = match prefix {
+            true => marker,
+            false => "",
+        };
+        if bytes.len() < self.bytes {
+            let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            return format!("{hex:>0$}", digits + marker.len());
+        }
+        let value = scalar::decode(bytes, self.endian);
+        match format {
+            Format::Octal => format!("{marker}{value:0digits$o}"),
+            Format::Binary => format!("{marker}{value:0digits$b}"),
+            Format::UpperHex => format!("{marker}{value:0digits$X}"),
+            _ => format!("{marker}{value:0digits$x}"),
+        }
+    }
+
+    /// Value of one cell: an integer word, or as `Format::word_value`
+    /// without a group. None for a word cut short.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - format of the cells.
+    /// * `bytes` - the cell's bytes, at most `word()` of them
+    pub fn word_value(&self, format: Format, bytes: &[u8]) -> Option<f64> {
+        match self.bytes {
+            1 => format.word_value(bytes),
+            width if bytes.len() < width => None,
+            _ => Some(scalar::decode(bytes, self.endian) as f64),
+        }
+    }
+}
+
 impl Format {
+    /// Bytes printed in one cell: the float width for the exponent
+    /// formats, else one.
+    pub fn word(&self) -> usize {
+        match self {
+            Self::LowerExp(float) | Self::UpperExp(float) => float.kind.width(),
+            _ => 1,
+        }
+    }
+
+    /// Characters of one cell, without the separator.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - whether or not to add a prefix
+    pub fn cell_width(&self, prefix: bool) -> usize {
+        self.format_word(&vec![0; self.word()], prefix).len()
+    }
+
+    /// Formats one cell: a byte, or a float word for the exponent formats.
+    /// A word cut short by the end of the input prints its bytes in hex,
+    /// padded to the cell width.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the cell's bytes, at most `word()` of them
+    /// * `prefix` - whether or not to add a prefix
+    pub fn format_word(&self, bytes: &[u8], prefix: bool) -> String {
+        let (float, upper) = match self {
+            Self::LowerExp(float) => (float, false),
+            Self::UpperExp(float) => (float, true),
+            _ => return self.format(bytes[0], prefix),
+        };
+        // room for a sign and the longest exponent, e-45 or e-324
+        let width = match float.kind {
+            ScalarType::F64 => 14,
+            _ => 13,
+        };
+        if bytes.len() < float.kind.width() {
+            let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            return format!("{hex:>width$}");
+        }
+        let raw = scalar::decode(bytes, float.endian);
+        match (float.kind, upper) {
+            (ScalarType::F64, false) => format!("{:>width$.6e}", f64::from_bits(raw)),
+            (ScalarType::F64, true) => format!("{:>width$.6E}", f64::from_bits(raw)),
+            (_, false) => format!("{:>width$.6e}", f32::from_bits(raw as u32)),
+            (_, true) => format!("{:>width$.6E}", f32::from_bits(raw as u32)),
+        }
+    }
+
+    /// Value of one cell: the float of a whole word for the exponent
+    /// formats, else the byte. None for a word cut short.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the cell's bytes, at most `word()` of them
+    pub fn word_value(&self, bytes: &[u8]) -> Option<f64> {
+        let float = match self {
+            Self::LowerExp(float) | Self::UpperExp(float) => float,
+            _ => return bytes.first().map(|b| f64::from(*b)),
+        };
+        if bytes.len() < float.kind.width() {
+            return None;
+        }
+        let raw = scalar::decode(bytes, float.endian);
+        Some(match float.kind {
+            ScalarType::F64 => f64::from_bits(raw),
+            _ => f64::from(f32::from_bits(raw as u32)),
+        })
+    }
+
     /// Formats a given u8 according to the base Format
     ///
     /// # Arguments
@@ -42,6 +250,8 @@ impl Format {
                 Self::LowerHex => format!("{:#04x}", data),
                 Self::UpperHex => format!("{:#04X}", data),
                 Self::Binary => format!("{:#010b}", data),
+                Self::LowerExp(_) => format!("{:e}", data),
+                Self::UpperExp(_) => format!("{:E}", data),
                 _ => panic!("format is not implemented for this Format"),
             }
         } else {
@@ -50,6 +260,8 @@ impl Format {
                 Self::LowerHex => format!("{:02x}", data),
                 Self::UpperHex => format!("{:02X}", data),
                 Self::Binary => format!("{:08b}", data),
+                Self::LowerExp(_) => format!("{:e}", data),
+                Self::UpperExp(_) => format!("{:E}", data),
                 _ => panic!("format is not implemented for this Format"),
             }
         }
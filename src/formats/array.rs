@@ -0,0 +1,141 @@
+//! source code arrays: rust, c, golang, python, kotlin, java, swift, fsharp
+
+use super::OutputFormat;
+use crate::temp::{self, TempFile};
+use crate::{write_array_footer, write_array_header, write_array_row, Line};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// A source code array declaration. Rows are written as they're read, one
+/// held back since the last element has no separator. A declaration that
+/// states the length needs it first, so without a known length the rows
+/// are spooled to a temporary file until the end.
+pub struct ArrayFormat<'a> {
+    array_format: &'a str,
+    columns: u64,
+    /// elements the declaration states, when known before the first row
+    len: Option<u64>,
+    /// elements so far
+    bytes: u64,
+    /// last row read, not written yet
+    pending: Option<Line>,
+    /// whether the last row read was full, true before the first
+    last_full: bool,
+    /// temporary file of the rows kept until the length is known
+    kept: Option<(TempFile, BufWriter<File>)>,
+    /// leave out the declaration, for `--fragment`
+    fragment: bool,
+    /// write the declaration, false when continuing after it
+    header: bool,
+}
+
+/// ArrayFormat implementation
+impl<'a> ArrayFormat<'a> {
+    /// ArrayFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `array_format` - array format, rust (r), C (c), golang (g).
+    /// * `columns` - elements per row.
+    /// * `len` - number of elements, when known before they're read.
+    pub fn new(array_format: &'a str, columns: u64, len: Option<u64>) -> ArrayFormat<'a> {
+        ArrayFormat {
+            array_format,
+            columns,
+            len,
+            bytes: 0,
+            pending: None,
+            last_full: true,
+            kept: None,
+            fragment: false,
+            header: true,
+        }
+    }
+
+    /// ArrayFormat constructor for the elements only, without the
+    /// declaration, so separate outputs concatenate into one array.
+    ///
+    /// # Arguments
+    ///
+    /// * `array_format` - array format, rust (r), C (c), golang (g).
+    /// * `columns` - elements per row.
+    pub fn fragment(array_format: &'a str, columns: u64) -> ArrayFormat<'a> {
+        ArrayFormat {
+            fragment: true,
+            ..ArrayFormat::new(array_format, columns, None)
+        }
+    }
+
+    /// ArrayFormat constructor for the rows after those an interrupted
+    /// output already holds, its declaration included.
+    ///
+    /// # Arguments
+    ///
+    /// * `array_format` - array format, rust (r), C (c), golang (g).
+    /// * `columns` - elements per row.
+    pub fn resumed(array_format: &'a str, columns: u64) -> ArrayFormat<'a> {
+        ArrayFormat {
+            header: false,
+            ..ArrayFormat::new(array_format, columns, None)
+        }
+    }
+
+    /// Write a row where it goes: the output, or the rows kept.
+    fn write_row(&mut self, out: &mut dyn Write, row: &[u8], last: bool) -> io::Result<()> {
+        match self.kept.as_mut() {
+            Some((_, kept)) => write_array_row(kept, self.array_format, row, last),
+            None => write_array_row(&mut &mut *out, self.array_format, row, last),
+        }
+    }
+}
+
+impl OutputFormat for ArrayFormat<'_> {
+    fn begin(&mut self, mut out: &mut dyn Write) -> io::Result<()> {
+        if self.fragment || !self.header {
+            return Ok(());
+        }
+        let declares_len = matches!(self.array_format, "r" | "c" | "g");
+        match (self.len, declares_len) {
+            (None, true) => {
+                let (spool, file) = TempFile::create(&temp::dir(None), "hx-spool")
+                    .map_err(|e| io::Error::new(e.kind(), format!("--array {e}")))?;
+                self.kept = Some((spool, BufWriter::new(file)));
+            }
+            (len, _) => write_array_header(&mut out, self.array_format, len.unwrap_or(0))?,
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        self.bytes += line.bytes;
+        self.last_full = line.bytes == self.columns;
+        if let Some(previous) = self.pending.replace(line.clone()) {
+            self.write_row(out, &previous.hex_body, false)?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, mut out: &mut dyn Write, _bytes: u64) -> io::Result<()> {
+        let last = self.pending.take();
+        if self.fragment {
+            if let Some(line) = last {
+                write_array_row(&mut out, self.array_format, &line.hex_body, false)?;
+            }
+            return Ok(());
+        }
+        if let Some(line) = last {
+            self.write_row(out, &line.hex_body, true)?;
+        }
+        // a full last row is followed by an empty one, as the output always
+        // ended on a short row, which `--resume` counts on
+        if self.last_full {
+            self.write_row(out, &[], true)?;
+        }
+        if let Some((spool, mut kept)) = self.kept.take() {
+            kept.flush()?;
+            write_array_header(&mut out, self.array_format, self.bytes)?;
+            io::copy(&mut File::open(&spool.path)?, &mut out)?;
+        }
+        write_array_footer(&mut out, self.array_format)
+    }
+}
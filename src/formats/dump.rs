@@ -0,0 +1,57 @@
+//! the hex dump: offset, byte cells and character panel, optionally boxed
+//! in by `--border`
+
+use super::OutputFormat;
+use crate::{offset, write_line, write_rule, Border, DumpOptions, Edge, Line};
+use std::io::{self, Write};
+
+/// Hex dump lines, with rules above marked boundaries
+pub struct DumpFormat<'a> {
+    opts: DumpOptions<'a>,
+    first: bool,
+}
+
+/// DumpFormat implementation
+impl<'a> DumpFormat<'a> {
+    /// DumpFormat constructor
+    pub fn new(opts: DumpOptions<'a>) -> DumpFormat<'a> {
+        DumpFormat { opts, first: true }
+    }
+}
+
+impl OutputFormat for DumpFormat<'_> {
+    fn begin(&mut self, mut out: &mut dyn Write) -> io::Result<()> {
+        match self.opts.border {
+            Border::None => Ok(()),
+            _ => write_rule(&mut out, &self.opts, Edge::Top),
+        }
+    }
+
+    fn line(&mut self, mut out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        // only the first line can start before the start offset, when aligned
+        let lead = match self.first {
+            true => self.opts.start_offset - line.offset,
+            false => 0,
+        };
+        // no rule above the very first line
+        if line.boundary && !self.first && self.opts.border != Border::None {
+            write_rule(&mut out, &self.opts, Edge::Middle)?;
+        } else if line.boundary && !self.first {
+            let next = line.offset.div_ceil(self.opts.boundary) * self.opts.boundary;
+            writeln!(
+                out,
+                "-------- {} --------",
+                offset(self.opts.banks.translate(next))
+            )?;
+        }
+        self.first = false;
+        write_line(&mut out, line, lead, &self.opts)
+    }
+
+    fn end(&mut self, mut out: &mut dyn Write, _bytes: u64) -> io::Result<()> {
+        match self.opts.border {
+            Border::None => Ok(()),
+            _ => write_rule(&mut out, &self.opts, Edge::Bottom),
+        }
+    }
+}
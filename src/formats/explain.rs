@@ -0,0 +1,51 @@
+//! one line per byte in every radix, with its ascii name, for teaching
+
+use super::OutputFormat;
+use crate::{ascii, byte_to_color, offset, write_summary, Config, DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Every byte on its own line: offset, binary, octal, decimal, hex and
+/// what it is in ascii
+pub struct ExplainFormat<'a> {
+    config: &'a Config,
+    opts: DumpOptions<'a>,
+}
+
+/// ExplainFormat implementation
+impl<'a> ExplainFormat<'a> {
+    /// ExplainFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parsed command line, for the summary.
+    /// * `opts` - dump colors and offsets.
+    pub fn new(config: &'a Config, opts: DumpOptions<'a>) -> ExplainFormat<'a> {
+        ExplainFormat { config, opts }
+    }
+}
+
+impl OutputFormat for ExplainFormat<'_> {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        // an aligned first line starts before its first byte
+        let first = line.offset.max(self.opts.start_offset);
+        for (i, b) in line.hex_body.iter().enumerate() {
+            let at = self.opts.banks.translate(first + i as u64);
+            let hex = format!("{b:#04x}");
+            let hex = match self.opts.colorize {
+                true => byte_to_color(*b).paint(hex).to_string(),
+                false => hex,
+            };
+            writeln!(
+                out,
+                "{}: {b:#010b} {b:#05o} {b:>3} {hex} {}",
+                offset(at),
+                ascii::describe(*b)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, mut out: &mut dyn Write, bytes: u64) -> io::Result<()> {
+        write_summary(&mut out, self.config, bytes)
+    }
+}
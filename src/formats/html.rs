@@ -0,0 +1,43 @@
+//! HTML: the dump lines as styled `<span>` elements in a `<pre>` block,
+//! for pasting into pages and reports
+
+use super::OutputFormat;
+use crate::{line_spans, Backend, DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Hex dump lines in HTML, always in the dump colors since a page has no
+/// terminal to check; every span has a class for its part of the line,
+/// e.g. `hx-offset` or `hx-cell`, for style sheets to restyle
+pub struct HtmlFormat<'a> {
+    opts: DumpOptions<'a>,
+}
+
+/// HtmlFormat implementation
+impl<'a> HtmlFormat<'a> {
+    /// HtmlFormat constructor
+    pub fn new(opts: DumpOptions<'a>) -> HtmlFormat<'a> {
+        HtmlFormat {
+            opts: DumpOptions {
+                colorize: true,
+                ..opts
+            },
+        }
+    }
+}
+
+impl OutputFormat for HtmlFormat<'_> {
+    fn begin(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "<pre class=\"hx\">")
+    }
+
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        // only the first line can start before the start offset, when aligned
+        let lead = self.opts.start_offset.saturating_sub(line.offset);
+        Backend::Html.write(out, &line_spans(line, lead, &self.opts))?;
+        writeln!(out)
+    }
+
+    fn end(&mut self, out: &mut dyn Write, _bytes: u64) -> io::Result<()> {
+        writeln!(out, "</pre>")
+    }
+}
@@ -0,0 +1,52 @@
+//! JSON lines: one object per dump line for scripts, no colors or summary
+
+use super::OutputFormat;
+use crate::patch::json_string;
+use crate::{DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Every line as a JSON object: the offset of its first byte, the number
+/// of bytes, their hex digits, the bytes as ascii with dots for the rest,
+/// and its margin notes
+pub struct JsonFormat {
+    start_offset: u64,
+}
+
+/// JsonFormat implementation
+impl JsonFormat {
+    /// JsonFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `opts` - dump layout, for the offset of the first byte.
+    pub fn new(opts: DumpOptions) -> JsonFormat {
+        JsonFormat {
+            start_offset: opts.start_offset,
+        }
+    }
+}
+
+impl OutputFormat for JsonFormat {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        // an aligned first line starts before its first byte
+        let first = line.offset.max(self.start_offset);
+        let hex: String = line.hex_body.iter().map(|b| format!("{b:02x}")).collect();
+        let ascii: String = line
+            .hex_body
+            .iter()
+            .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+                true => *b as char,
+                false => '.',
+            })
+            .collect();
+        let notes: Vec<String> = line.notes.iter().map(|note| json_string(note)).collect();
+        writeln!(
+            out,
+            "{{\"offset\": {first}, \"bytes\": {}, \"hex\": {}, \"ascii\": {}, \"notes\": [{}]}}",
+            line.bytes,
+            json_string(&hex),
+            json_string(&ascii),
+            notes.join(", ")
+        )
+    }
+}
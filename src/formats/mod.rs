@@ -0,0 +1,225 @@
+//! output formats: emitters fed the input one line at a time
+//!
+//! A format is picked by name from the `Registry` in the `Config`, so new
+//! emitters are registered there instead of being wired into `run()`.
+
+mod array;
+mod dump;
+mod explain;
+mod html;
+mod json;
+mod plain;
+mod raw;
+mod stacked;
+mod tee;
+mod transposed;
+
+pub use array::ArrayFormat;
+pub use dump::DumpFormat;
+pub use explain::ExplainFormat;
+pub use html::HtmlFormat;
+pub use json::JsonFormat;
+pub use plain::PlainFormat;
+pub use raw::RawFormat;
+pub use stacked::StackedFormat;
+pub use tee::{Also, TeeFormat};
+pub use transposed::TransposedFormat;
+
+use crate::{
+    bookmarks, offset, pcap, read_line, symbols, Config, DumpOptions, Line, Mode, OutputWriter,
+    Timestamps,
+};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// An output format, written one line of input bytes at a time
+pub trait OutputFormat {
+    /// Write what comes before the first line, e.g. a declaration.
+    fn begin(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write one line of input: its offset, bytes and margin notes.
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()>;
+
+    /// Write what comes after the last line.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - number of input bytes written in total.
+    fn end(&mut self, _out: &mut dyn Write, _bytes: u64) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Builds an output format for a parsed command line and dump layout
+pub type Constructor = for<'a> fn(&'a Config, DumpOptions<'a>) -> Box<dyn OutputFormat + 'a>;
+
+/// Output formats by name
+#[derive(Clone)]
+pub struct Registry {
+    formats: Vec<(&'static str, Constructor)>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// The built-in formats: `dump`, `stacked`, `explain`, `transposed`, `raw`,
+/// `json`, `plain`, `html` and `array`, and `zst` with the `zstd` feature
+impl Default for Registry {
+    fn default() -> Registry {
+        let mut registry = Registry {
+            formats: Vec::new(),
+        };
+        registry.register("dump", |_, opts| Box::new(DumpFormat::new(opts)));
+        registry.register("stacked", |config, opts| {
+            Box::new(StackedFormat::new(config, opts))
+        });
+        registry.register("explain", |config, opts| {
+            Box::new(ExplainFormat::new(config, opts))
+        });
+        registry.register("transposed", |config, opts| {
+            Box::new(TransposedFormat::new(config, opts))
+        });
+        registry.register("raw", |_, _| Box::new(RawFormat));
+        registry.register("json", |_, opts| Box::new(JsonFormat::new(opts)));
+        registry.register("plain", |_, _| Box::new(PlainFormat));
+        registry.register("html", |_, opts| Box::new(HtmlFormat::new(opts)));
+        registry.register("array", |config, opts| {
+            let array_format = match &config.mode {
+                Mode::Array(array) => array.as_str(),
+                _ => "r",
+            };
+            match config.fragment {
+                true => Box::new(ArrayFormat::fragment(array_format, opts.column_width)),
+                false => Box::new(ArrayFormat::new(
+                    array_format,
+                    opts.column_width,
+                    crate::array_len(config),
+                )),
+            }
+        });
+        #[cfg(feature = "zstd")]
+        registry.register("zst", |_, _| {
+            Box::new(crate::seekable::ArchiveFormat::default())
+        });
+        registry
+    }
+}
+
+/// Registry implementation
+impl Registry {
+    /// Add a format, replacing any format registered under the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name the format is selected by.
+    /// * `constructor` - builds the format.
+    pub fn register(&mut self, name: &'static str, constructor: Constructor) {
+        self.formats.retain(|(known, _)| *known != name);
+        self.formats.push((name, constructor));
+    }
+
+    /// Constructor of the format registered under `name`.
+    pub fn get(&self, name: &str) -> Option<Constructor> {
+        self.formats
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, constructor)| *constructor)
+    }
+
+    /// Names of the registered formats, in registration order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.formats.iter().map(|(name, _)| *name).collect()
+    }
+}
+
+/// Read the input a line at a time, filling in each line's offset,
+/// boundary mark and margin notes, and write it through an output format.
+/// Returns the number of bytes written.
+///
+/// # Arguments
+///
+/// * `out` - output writer, its flush policy is applied after every line.
+/// * `format` - output format.
+/// * `buf` - input to be read.
+/// * `opts` - dump layout: line width, length, start offset and notes.
+pub fn emit<W: Write>(
+    out: &mut OutputWriter<W>,
+    format: &mut dyn OutputFormat,
+    buf: &mut dyn Read,
+    opts: &DumpOptions,
+) -> io::Result<u64> {
+    // lines are read and written one at a time so live streams show
+    // up as they arrive; the flush policy decides when they hit stdout
+    let mut offset_counter: u64 = 0x0;
+    // blank cells leading the first line when aligning an unaligned start
+    let mut lead = match opts.align {
+        true => opts.start_offset % opts.column_width.max(1),
+        false => 0,
+    };
+
+    // when the previous line was read, for --timestamps delta
+    let mut previous = Instant::now();
+
+    format.begin(out)?;
+    loop {
+        let mut line = Line::new();
+        // no column width puts everything on one line
+        let mut want = match opts.column_width {
+            0 => u64::MAX,
+            width => width - lead,
+        };
+        if opts.truncate_len > 0 {
+            want = want.min(opts.truncate_len - offset_counter);
+        }
+        if want == 0 || read_line(buf, &mut line, want)? == 0 {
+            break;
+        }
+        let first = opts.start_offset + offset_counter;
+        line.offset = first - lead;
+        if opts.boundary > 0 {
+            let next = first.div_ceil(opts.boundary) * opts.boundary;
+            line.boundary = next < first + line.bytes;
+        }
+        let address = opts.banks.translate(first);
+        line.notes = symbols::lookup(opts.symbols, address)
+            .into_iter()
+            .chain(opts.lines.lookup(address))
+            .chain(
+                bookmarks::within(opts.bookmarks, first, line.bytes)
+                    .iter()
+                    .map(|mark| format!("{} {}", offset(mark.offset), mark.label)),
+            )
+            .collect();
+        match opts.timestamps {
+            Some(Timestamps::Absolute) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let stamp = pcap::format_timestamp(now.as_secs(), now.subsec_nanos(), 6);
+                // time of day only, the date rarely changes within a dump
+                write!(out, "{} ", &stamp[11..])?;
+            }
+            Some(Timestamps::Delta) => {
+                let now = Instant::now();
+                let delta = now.duration_since(previous).as_secs_f64();
+                write!(out, "{:>11} ", format!("+{delta:.6}"))?;
+                previous = now;
+            }
+            None => {}
+        }
+        format.line(out, &line)?;
+        out.line_done()?;
+
+        offset_counter += line.bytes;
+        lead = 0;
+    }
+    format.end(out, offset_counter)?;
+    out.flush()?;
+    Ok(offset_counter)
+}
@@ -0,0 +1,18 @@
+//! plain hex: the bytes as bare hex digits, like `xxd -p`
+
+use super::OutputFormat;
+use crate::Line;
+use std::io::{self, Write};
+
+/// Every line as its bytes' hex digits alone, without offsets, spaces or
+/// characters, for piping into tools that read hex
+pub struct PlainFormat;
+
+impl OutputFormat for PlainFormat {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        for b in &line.hex_body {
+            write!(out, "{b:02x}")?;
+        }
+        writeln!(out)
+    }
+}
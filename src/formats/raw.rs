@@ -0,0 +1,15 @@
+//! raw bytes: the input as it is, after transforms and decoding
+
+use super::OutputFormat;
+use crate::Line;
+use std::io::{self, Write};
+
+/// The input bytes, unformatted, e.g. to write a decoded image or joined
+/// files out
+pub struct RawFormat;
+
+impl OutputFormat for RawFormat {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        out.write_all(&line.hex_body)
+    }
+}
@@ -0,0 +1,111 @@
+//! hex on one row and the characters aligned beneath it, like `od -bc`
+
+use super::OutputFormat;
+use crate::{byte_to_color, offset, print_byte, write_summary, Config, DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Two rows per line: offset and byte cells, then every byte's character
+/// right aligned under its cell
+pub struct StackedFormat<'a> {
+    config: &'a Config,
+    opts: DumpOptions<'a>,
+    first: bool,
+}
+
+/// StackedFormat implementation
+impl<'a> StackedFormat<'a> {
+    /// StackedFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parsed command line, for the summary.
+    /// * `opts` - dump layout and styling.
+    pub fn new(config: &'a Config, opts: DumpOptions<'a>) -> StackedFormat<'a> {
+        StackedFormat {
+            config,
+            opts,
+            first: true,
+        }
+    }
+}
+
+/// Character shown under a byte: the character itself, a C escape for
+/// common control characters, or a dot.
+///
+/// # Arguments
+///
+/// * `b` - byte value.
+pub fn glyph(b: u8) -> String {
+    match b {
+        0x00 => "\\0".to_string(),
+        0x07 => "\\a".to_string(),
+        0x08 => "\\b".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\n' => "\\n".to_string(),
+        0x0b => "\\v".to_string(),
+        0x0c => "\\f".to_string(),
+        b'\r' => "\\r".to_string(),
+        0x20..=0x7e => (b as char).to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+impl OutputFormat for StackedFormat<'_> {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        let opts = &self.opts;
+        let lead = match self.first {
+            true => opts.start_offset - line.offset,
+            false => 0,
+        };
+        if line.boundary && !self.first {
+            let next = line.offset.div_ceil(opts.boundary) * opts.boundary;
+            writeln!(
+                out,
+                "-------- {} --------",
+                offset(opts.banks.translate(next))
+            )?;
+        }
+        self.first = false;
+        let width = |b: u8| opts.format.format(b, opts.prefix).len();
+        let blank = (width(0) + opts.separator.len()) * lead as usize;
+
+        write!(
+            out,
+            "{}: {:blank$}",
+            offset(opts.banks.translate(line.offset)),
+            ""
+        )?;
+        let mut cells = Vec::new();
+        for b in &line.hex_body {
+            print_byte(
+                &mut cells,
+                *b,
+                opts.format,
+                opts.colorize,
+                opts.prefix,
+                opts.separator,
+            )?;
+        }
+        out.write_all(&cells)?;
+        if !line.notes.is_empty() {
+            write!(out, " # {}", line.notes.join(", "))?;
+        }
+        writeln!(out)?;
+
+        // under the offset column, `0x000000: `
+        write!(out, "{:1$}", "", offset(0).len() + 2 + blank)?;
+        for b in &line.hex_body {
+            let glyph = format!("{:>1$}", glyph(*b), width(*b));
+            match opts.colorize {
+                true => write!(out, "{}", byte_to_color(*b).paint(glyph))?,
+                false => write!(out, "{glyph}")?,
+            }
+            write!(out, "{}", opts.separator)?;
+        }
+        writeln!(out)
+    }
+
+    fn end(&mut self, mut out: &mut dyn Write, bytes: u64) -> io::Result<()> {
+        write_summary(&mut out, self.config, bytes)
+    }
+}
@@ -0,0 +1,76 @@
+//! `--also`: more outputs written from the lines of the same read, so a
+//! huge input is read once for a dump and an array
+
+use super::OutputFormat;
+use crate::{write_summary, Config, Line};
+use std::io::{self, Write};
+
+/// An extra output of `--also`: the format it's written in and where to
+pub struct Also<'a> {
+    /// name the format was picked by
+    pub name: String,
+    /// the format
+    pub format: Box<dyn OutputFormat + 'a>,
+    /// where it's written
+    pub out: Box<dyn Write>,
+}
+
+/// Lines written through a format and through every extra output
+pub struct TeeFormat<'a> {
+    config: &'a Config,
+    primary: Box<dyn OutputFormat + 'a>,
+    also: Vec<Also<'a>>,
+}
+
+/// TeeFormat implementation
+impl<'a> TeeFormat<'a> {
+    /// TeeFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parsed command line, for the summary of extra dumps.
+    /// * `primary` - format of the output itself.
+    /// * `also` - extra outputs.
+    pub fn new(
+        config: &'a Config,
+        primary: Box<dyn OutputFormat + 'a>,
+        also: Vec<Also<'a>>,
+    ) -> TeeFormat<'a> {
+        TeeFormat {
+            config,
+            primary,
+            also,
+        }
+    }
+}
+
+impl OutputFormat for TeeFormat<'_> {
+    fn begin(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        self.primary.begin(out)?;
+        for also in self.also.iter_mut() {
+            also.format.begin(&mut also.out)?;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        self.primary.line(out, line)?;
+        for also in self.also.iter_mut() {
+            also.format.line(&mut also.out, line)?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, out: &mut dyn Write, bytes: u64) -> io::Result<()> {
+        self.primary.end(out, bytes)?;
+        for also in self.also.iter_mut() {
+            also.format.end(&mut also.out, bytes)?;
+            // a dump ends with its summary, as the output itself would
+            if also.name == "dump" && !self.config.fragment {
+                write_summary(&mut also.out, self.config, bytes)?;
+            }
+            also.out.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! column-major dump of fixed size records: one row per field
+
+use super::OutputFormat;
+use crate::{write_line, write_summary, Config, DumpOptions, Line};
+use std::io::{self, Write};
+
+/// Records side by side: every line of input holds a group of records,
+/// written as one row per byte of the record, holding that byte of every
+/// record in the group. Rows show the offset of their byte in the first
+/// record, and groups are set apart by a blank line.
+pub struct TransposedFormat<'a> {
+    config: &'a Config,
+    /// layout of a row, a cell per record
+    opts: DumpOptions<'a>,
+    record: u64,
+    first: bool,
+}
+
+/// TransposedFormat implementation
+impl<'a> TransposedFormat<'a> {
+    /// TransposedFormat constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parsed command line, for the record size, records per row and the summary.
+    /// * `opts` - dump layout and styling.
+    pub fn new(config: &'a Config, opts: DumpOptions<'a>) -> TransposedFormat<'a> {
+        TransposedFormat {
+            config,
+            opts: DumpOptions {
+                column_width: config.columns,
+                ..opts
+            },
+            record: config.transpose.unwrap_or(1),
+            first: true,
+        }
+    }
+}
+
+impl OutputFormat for TransposedFormat<'_> {
+    fn line(&mut self, mut out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        if !self.first {
+            writeln!(out)?;
+        }
+        self.first = false;
+        let fields = self.record.min(line.bytes);
+        for field in 0..fields {
+            let mut row = Line::new();
+            row.offset = line.offset + field;
+            row.hex_body = line
+                .hex_body
+                .iter()
+                .skip(field as usize)
+                .step_by(self.record as usize)
+                .copied()
+                .collect();
+            row.bytes = row.hex_body.len() as u64;
+            write_line(&mut out, &row, 0, &self.opts)?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, mut out: &mut dyn Write, bytes: u64) -> io::Result<()> {
+        write_summary(&mut out, self.config, bytes)
+    }
+}
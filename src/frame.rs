@@ -0,0 +1,147 @@
+//! protocol framing for `--frame`: length-prefixed or delimited frames
+//! read one at a time, so frames of a live stream show up as they arrive
+
+use crate::literal;
+use crate::scalar::{self, Endian};
+use std::io::{self, BufRead, Read};
+
+/// How a byte stream is cut into frames
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// each frame starts with its length, not counting the prefix itself
+    LengthPrefixed {
+        /// bytes of the length prefix, 1 to 8
+        width: usize,
+        /// byte order of the length prefix
+        endian: Endian,
+    },
+    /// each frame ends with a delimiter
+    Delimited(Vec<u8>),
+}
+
+/// A frame read from the input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// bytes before the body: the length prefix, 0 for delimited frames
+    pub head: usize,
+    /// length from the prefix, None for delimited frames
+    pub declared: Option<u64>,
+    /// frame body, without prefix or delimiter
+    pub body: Vec<u8>,
+    /// bytes after the body: the delimiter, 0 for length-prefixed frames
+    pub tail: usize,
+    /// what's wrong with the frame, only the last one can have a problem
+    pub problem: Option<String>,
+}
+
+/// Parse a framing spec: `len-prefixed:<type>` with a `u8`, `u16`, `u32`
+/// or `u64` prefix and a `be` or `le` suffix (big endian when left out),
+/// or `delim:<bytes>` with bytes as for `hx echo`.
+///
+/// # Arguments
+///
+/// * `text` - spec, e.g. `len-prefixed:u16be` or `delim:0d 0a`.
+pub fn parse(text: &str) -> Result<Framing, String> {
+    match text.split_once(':') {
+        Some(("len-prefixed", kind)) => {
+            let (bits, endian) = match (kind.strip_suffix("be"), kind.strip_suffix("le")) {
+                (Some(bits), _) => (bits, Endian::Big),
+                (_, Some(bits)) => (bits, Endian::Little),
+                _ => (kind, Endian::Big),
+            };
+            let width = match bits {
+                "u8" => 1,
+                "u16" => 2,
+                "u32" => 4,
+                "u64" => 8,
+                _ => return Err(format!("{kind:?} is not u8, u16, u32 or u64 with be or le")),
+            };
+            Ok(Framing::LengthPrefixed { width, endian })
+        }
+        Some(("delim", bytes)) => match literal::parse(bytes)? {
+            bytes if bytes.is_empty() => Err("the delimiter is empty".to_string()),
+            bytes => Ok(Framing::Delimited(bytes)),
+        },
+        _ => Err(format!(
+            "{text:?} is not len-prefixed:<type> or delim:<bytes>"
+        )),
+    }
+}
+
+/// Read bytes into `out` until it holds `len` bytes or the input ends.
+fn fill(buf: &mut dyn BufRead, out: &mut Vec<u8>, len: u64) -> io::Result<()> {
+    let want = len.saturating_sub(out.len() as u64);
+    buf.take(want).read_to_end(out)?;
+    Ok(())
+}
+
+/// Read the next frame, None at the end of the input. A frame cut off by
+/// the end of the input is returned with the problem.
+///
+/// # Arguments
+///
+/// * `buf` - input, positioned at the start of a frame.
+/// * `framing` - how frames are cut.
+pub fn next(buf: &mut dyn BufRead, framing: &Framing) -> io::Result<Option<Frame>> {
+    if buf.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(match framing {
+        Framing::LengthPrefixed { width, endian } => {
+            let mut prefix = Vec::new();
+            fill(buf, &mut prefix, *width as u64)?;
+            if prefix.len() < *width {
+                return Ok(Some(Frame {
+                    head: prefix.len(),
+                    declared: None,
+                    body: Vec::new(),
+                    tail: 0,
+                    problem: Some(format!(
+                        "length cut off after {} of {width} bytes",
+                        prefix.len()
+                    )),
+                }));
+            }
+            let declared = scalar::decode(&prefix, *endian);
+            let mut body = Vec::new();
+            fill(buf, &mut body, declared)?;
+            let problem = match (body.len() as u64) < declared {
+                true => Some(format!("cut off after {} bytes", body.len())),
+                false => None,
+            };
+            Frame {
+                head: *width,
+                declared: Some(declared),
+                body,
+                tail: 0,
+                problem,
+            }
+        }
+        Framing::Delimited(delimiter) => {
+            let last = delimiter[delimiter.len() - 1];
+            let mut body = Vec::new();
+            loop {
+                if buf.read_until(last, &mut body)? == 0 {
+                    break;
+                }
+                if body.ends_with(delimiter) {
+                    body.truncate(body.len() - delimiter.len());
+                    return Ok(Some(Frame {
+                        head: 0,
+                        declared: None,
+                        body,
+                        tail: delimiter.len(),
+                        problem: None,
+                    }));
+                }
+            }
+            Frame {
+                head: 0,
+                declared: None,
+                body,
+                tail: 0,
+                problem: Some("no delimiter before the end".to_string()),
+            }
+        }
+    }))
+}
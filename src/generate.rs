@@ -0,0 +1,145 @@
+//! generator expressions for `--generate`: counting, repeated and LFSR
+//! test patterns, dumped like any input
+
+use crate::expr;
+use crate::input::InputSource;
+use crate::literal;
+use std::io::{self, Read};
+
+/// Linear feedback shift register sequence, a PRBS bit stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lfsr {
+    /// feedback taps without the top term, e.g. 0x1d for x^8+x^4+x^3+x^2+1
+    pub poly: u32,
+    /// register state to start from, not zero
+    pub seed: u32,
+    /// register bits, 2 to 32
+    pub width: u32,
+    /// bytes generated
+    pub len: u64,
+}
+
+/// Lfsr implementation
+impl Lfsr {
+    /// Reader of the sequence, eight output bits to a byte, the first one
+    /// in the high bit.
+    pub fn reader(&self) -> impl Read {
+        LfsrReader {
+            lfsr: self.clone(),
+            state: self.seed,
+        }
+        .take(self.len)
+    }
+}
+
+/// Reader stepping a Galois LFSR, the bit shifted out of the top of the
+/// register being the output
+struct LfsrReader {
+    lfsr: Lfsr,
+    state: u32,
+}
+
+impl Read for LfsrReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let top = 1u64 << (self.lfsr.width - 1);
+        let mask = (1u64 << self.lfsr.width) - 1;
+        for b in buf.iter_mut() {
+            *b = 0;
+            for _ in 0..8 {
+                let state = u64::from(self.state);
+                let out = state & top != 0;
+                let next = match out {
+                    true => ((state << 1) ^ u64::from(self.lfsr.poly)) & mask,
+                    false => (state << 1) & mask,
+                };
+                self.state = next as u32;
+                *b = *b << 1 | u8::from(out);
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
+/// A number of a generator expression, decimal or hex, sizes with a unit.
+fn number(text: &str, what: &str) -> Result<u64, String> {
+    expr::eval(text, None).map_err(|reason| format!("{what}: {reason}"))
+}
+
+/// `count:<from>..<to>`, every byte value from one bound to the other,
+/// both included, counting down when `to` is below `from`.
+fn count(range: &str) -> Result<Vec<u8>, String> {
+    let (from, to) = range
+        .split_once("..")
+        .ok_or_else(|| format!("count takes <from>..<to>, not {range:?}"))?;
+    let byte = |text: &str| {
+        u8::try_from(number(text, "count")?).map_err(|_| format!("{text:?} doesn't fit in a byte"))
+    };
+    let (from, to) = (byte(from)?, byte(to)?);
+    Ok(match from <= to {
+        true => (from..=to).collect(),
+        false => (to..=from).rev().collect(),
+    })
+}
+
+/// `lfsr:poly=<taps>,seed=<state>,len=<bytes>[,width=<bits>]`, the seed 1
+/// and the width 8 when left out.
+fn lfsr(params: &str) -> Result<Lfsr, String> {
+    let (mut poly, mut seed, mut width, mut len) = (None, 1, 8, None);
+    for param in params.split(',') {
+        let (key, value) = param
+            .split_once('=')
+            .ok_or_else(|| format!("lfsr takes key=value parameters, not {param:?}"))?;
+        let value = number(value, key)?;
+        match key {
+            "poly" => poly = Some(value),
+            "seed" => seed = value,
+            "width" => width = value,
+            "len" => len = Some(value),
+            _ => {
+                return Err(format!(
+                    "unknown lfsr parameter {key:?}, use poly, seed, width or len"
+                ))
+            }
+        }
+    }
+    let (Some(poly), Some(len)) = (poly, len) else {
+        return Err("lfsr needs poly and len".to_string());
+    };
+    if !(2..=32).contains(&width) {
+        return Err(format!("lfsr width {width} isn't between 2 and 32 bits"));
+    }
+    if poly >> width != 0 || seed >> width != 0 {
+        return Err(format!(
+            "poly and seed must fit in the {width} bit register"
+        ));
+    }
+    if seed == 0 {
+        return Err("a zero seed never leaves zero".to_string());
+    }
+    Ok(Lfsr {
+        poly: poly as u32,
+        seed: seed as u32,
+        width: width as u32,
+        len,
+    })
+}
+
+/// Input a generator expression produces.
+///
+/// # Arguments
+///
+/// * `text` - expression: `count:0..255`, `repeat:deadbeef*64` with the
+///   bytes of `hx echo`, or `lfsr:poly=0x1d,seed=0xff,len=1024`.
+pub fn parse(text: &str) -> Result<InputSource, String> {
+    let (kind, rest) = text
+        .split_once(':')
+        .ok_or_else(|| format!("{text:?} isn't <generator>:<parameters>"))?;
+    match kind {
+        "count" => Ok(InputSource::Literal(count(rest)?)),
+        "repeat" => Ok(InputSource::Literal(literal::parse(rest)?)),
+        "lfsr" => Ok(InputSource::Lfsr(lfsr(rest)?)),
+        _ => Err(format!(
+            "unknown generator {kind:?}, use count, repeat or lfsr"
+        )),
+    }
+}
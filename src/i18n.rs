@@ -0,0 +1,168 @@
+//! message catalog for summary, error and banner text
+//!
+//! Without the `i18n` feature every message is English. With it, the
+//! language is taken from `LC_ALL`, `LC_MESSAGES` or `LANG`, falling back to
+//! English for unknown languages.
+
+use std::env;
+
+/// Translatable messages
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Msg {
+    /// label of the byte count summary line
+    Bytes,
+    /// prefix of error messages
+    Error,
+    /// prefix of warnings, for input hx skips and goes on without
+    Warning,
+    /// label of the timing report
+    Timing,
+}
+
+/// English text for a message.
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Bytes => "bytes",
+        Msg::Error => "error",
+        Msg::Warning => "warning",
+        Msg::Timing => "timing",
+    }
+}
+
+/// Text for a message in the given language, e.g. `de` or `es`.
+///
+/// # Arguments
+///
+/// * `msg` - message to translate.
+/// * `lang` - two letter language code.
+#[cfg(feature = "i18n")]
+pub fn translate(msg: Msg, lang: &str) -> &'static str {
+    match (lang, msg) {
+        ("de", Msg::Bytes) => "Bytes",
+        ("de", Msg::Error) => "Fehler",
+        ("de", Msg::Warning) => "Warnung",
+        ("de", Msg::Timing) => "Laufzeit",
+        ("es", Msg::Bytes) => "bytes",
+        ("es", Msg::Error) => "error",
+        ("es", Msg::Warning) => "aviso",
+        ("es", Msg::Timing) => "tiempo",
+        ("fr", Msg::Bytes) => "octets",
+        ("fr", Msg::Error) => "erreur",
+        ("fr", Msg::Warning) => "avertissement",
+        ("fr", Msg::Timing) => "durée",
+        ("pt", Msg::Bytes) => "bytes",
+        ("pt", Msg::Error) => "erro",
+        ("pt", Msg::Warning) => "aviso",
+        ("pt", Msg::Timing) => "tempo",
+        _ => english(msg),
+    }
+}
+
+/// Text for a message; English only as the `i18n` feature is disabled.
+#[cfg(not(feature = "i18n"))]
+pub fn translate(msg: Msg, _lang: &str) -> &'static str {
+    english(msg)
+}
+
+/// Language code of the user's locale, e.g. `de` for `de_DE.UTF-8`.
+pub fn locale_language() -> String {
+    language_of(&["LC_ALL", "LC_MESSAGES", "LANG"])
+}
+
+/// Language code of the user's numeric locale.
+pub fn numeric_language() -> String {
+    language_of(&["LC_ALL", "LC_NUMERIC", "LANG"])
+}
+
+/// Language code of the first non-empty locale variable.
+fn language_of(vars: &[&str]) -> String {
+    vars.iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['_', '.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .unwrap_or_default()
+}
+
+/// Text for a message in the user's locale.
+pub fn tr(msg: Msg) -> &'static str {
+    translate(msg, &locale_language())
+}
+
+/// Digit grouping and decimal separators for a language.
+///
+/// # Arguments
+///
+/// * `lang` - two letter language code.
+#[cfg(feature = "i18n")]
+pub fn separators(lang: &str) -> (char, char) {
+    match lang {
+        "de" | "es" | "pt" | "it" | "nl" => ('.', ','),
+        "fr" => (' ', ','),
+        _ => (',', '.'),
+    }
+}
+
+/// Digit grouping and decimal separators; English only as the `i18n`
+/// feature is disabled.
+#[cfg(not(feature = "i18n"))]
+pub fn separators(_lang: &str) -> (char, char) {
+    (',', '.')
+}
+
+/// Group the digits of `n` in thousands, e.g. `12,345,678`.
+///
+/// # Arguments
+///
+/// * `n` - number to format.
+/// * `lang` - two letter language code selecting the separator.
+pub fn group_digits(n: u64, lang: &str) -> String {
+    let (group, _) = separators(lang);
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(group);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Byte count with digit grouping, plus IEC units from 1 KiB upwards,
+/// e.g. `12,345,678 (11.8 MiB)`.
+///
+/// # Arguments
+///
+/// * `n` - number of bytes.
+/// * `lang` - two letter language code selecting the separators.
+pub fn human_size(n: u64, lang: &str) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    let grouped = group_digits(n, lang);
+    if n < 1024 {
+        return grouped;
+    }
+    let mut value = n as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let (_, decimal) = separators(lang);
+    let value = format!("{value:.1}").replace('.', &decimal.to_string());
+    format!("{grouped} ({value} {})", UNITS[unit])
+}
+
+/// Byte count for summaries: human readable in the user's numeric locale,
+/// or the plain number when `raw` is set.
+pub fn format_size(n: u64, raw: bool) -> String {
+    match raw {
+        true => n.to_string(),
+        false => human_size(n, &numeric_language()),
+    }
+}
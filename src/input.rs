@@ -0,0 +1,633 @@
+//! input sources: where the bytes to dump come from
+
+use crate::generate::Lfsr;
+use crate::spool::Spool;
+use std::borrow::Cow;
+use std::fs::{File, FileType, Metadata, OpenOptions};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Stdin};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+/// Where input bytes come from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    /// standard input, when no file or `-` is given
+    Stdin,
+    /// a file by path, which needn't be UTF-8
+    File(PathBuf),
+    /// bytes given on the command line, e.g. by `hx echo`
+    Literal(Vec<u8>),
+    /// a pattern repeated to a length, by `hx fill`
+    Pattern {
+        /// bytes repeated, at least one
+        bytes: Vec<u8>,
+        /// length of the input
+        len: u64,
+    },
+    /// a file descriptor, or a handle on Windows, inherited from the
+    /// parent process, `--fd`
+    Fd(u64),
+    /// a streamed input read to the end into a temporary file first,
+    /// `--two-pass`
+    Spooled(Arc<Spool>),
+    /// a linear feedback shift register sequence, `--generate lfsr:...`
+    Lfsr(Lfsr),
+    /// files read one after another, `--concat`
+    Concat(Vec<PathBuf>),
+    /// files read a word from each in turn, `--interleave`
+    Interleave {
+        /// files, in the order their words are taken
+        paths: Vec<PathBuf>,
+        /// bytes taken from each file in turn
+        word: usize,
+    },
+}
+
+/// What an input source supports
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// offsets can be read directly instead of reading up to them
+    pub seekable: bool,
+    /// length of the input, None when it's only known at the end
+    pub len: Option<u64>,
+    /// a named pipe or character device: bytes come as they're written,
+    /// maybe without end
+    pub live: bool,
+}
+
+/// An opened input source
+pub enum Input {
+    /// a stream, read front to back
+    Stream(BufReader<Stdin>),
+    /// a file, which can also be seeked
+    File(BufReader<File>),
+    /// bytes in memory
+    Bytes(Cursor<Vec<u8>>),
+    /// several files joined into one stream
+    Joined(BufReader<Box<dyn Read>>),
+    /// a file read ahead on a background thread
+    Ahead(ReadAhead),
+}
+
+/// Input implementation
+impl Input {
+    /// The underlying file, for seeking, None for streams. Only use it
+    /// before anything was read through the input, as the read buffer
+    /// would be skipped.
+    pub fn file(&mut self) -> Option<&mut File> {
+        match self {
+            Input::Stream(_) | Input::Bytes(_) | Input::Joined(_) | Input::Ahead(_) => None,
+            Input::File(file) => Some(file.get_mut()),
+        }
+    }
+
+    /// Read a file ahead in blocks of `size` bytes on a background thread,
+    /// so the next block is on its way while this one is dumped. Other
+    /// inputs, and a size of 0, are left as they are. Only use it before
+    /// anything was read through the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - bytes per block.
+    pub fn read_ahead(self, size: usize) -> Input {
+        match (self, size) {
+            (Input::File(file), 1..) => Input::Ahead(ReadAhead::new(file.into_inner(), size)),
+            (input, _) => input,
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Stream(stream) => stream.read(buf),
+            Input::File(file) => file.read(buf),
+            Input::Bytes(bytes) => bytes.read(buf),
+            Input::Joined(joined) => joined.read(buf),
+            Input::Ahead(ahead) => ahead.read(buf),
+        }
+    }
+}
+
+impl BufRead for Input {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Input::Stream(stream) => stream.fill_buf(),
+            Input::File(file) => file.fill_buf(),
+            Input::Bytes(bytes) => bytes.fill_buf(),
+            Input::Joined(joined) => joined.fill_buf(),
+            Input::Ahead(ahead) => ahead.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Input::Stream(stream) => stream.consume(amt),
+            Input::File(file) => file.consume(amt),
+            Input::Bytes(bytes) => bytes.consume(amt),
+            Input::Joined(joined) => joined.consume(amt),
+            Input::Ahead(ahead) => ahead.consume(amt),
+        }
+    }
+}
+
+/// Reader filling blocks on a background thread: one block is read while
+/// the one before it is consumed, and one more waits in between
+pub struct ReadAhead {
+    blocks: Receiver<io::Result<Vec<u8>>>,
+    /// block being consumed
+    block: Vec<u8>,
+    /// bytes of the block already consumed
+    pos: usize,
+}
+
+/// ReadAhead implementation
+impl ReadAhead {
+    /// ReadAhead constructor, starting the reading thread. The thread
+    /// stops at the end of the input, at an error, or when the reader is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input, read from where it is.
+    /// * `size` - bytes per block, at least one.
+    pub fn new(mut inner: impl Read + Send + 'static, size: usize) -> ReadAhead {
+        let (sender, blocks) = mpsc::sync_channel(1);
+        thread::spawn(move || loop {
+            // one read per block, so a pipe's bytes are passed on as they come
+            let mut block = vec![0; size];
+            let result = match inner.read(&mut block) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => result,
+            };
+            let last = !matches!(result, Ok(n) if n > 0);
+            let result = result.map(|n| {
+                block.truncate(n);
+                block
+            });
+            if sender.send(result).is_err() || last {
+                break;
+            }
+        });
+        ReadAhead {
+            blocks,
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ReadAhead {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.block.len() {
+            // a closed channel is the end of the input
+            if let Ok(block) = self.blocks.recv() {
+                self.block = block?;
+                self.pos = 0;
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+/// Alignment of direct reads: buffer address, length and file offset
+const DIRECT_ALIGN: usize = 4096;
+
+/// Bytes per direct read
+const DIRECT_BLOCK: usize = 1 << 20;
+
+/// Bytes of the buffer of a direct reader
+pub const DIRECT_BUFFER: usize = DIRECT_BLOCK + DIRECT_ALIGN;
+
+/// `O_DIRECT` of the target, None where it isn't known
+const O_DIRECT: Option<i32> = if cfg!(not(target_os = "linux")) {
+    None
+} else if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+    Some(0o40000)
+} else if cfg!(any(target_arch = "arm", target_arch = "aarch64")) {
+    Some(0o200000)
+} else if cfg!(any(target_arch = "powerpc", target_arch = "powerpc64")) {
+    Some(0o400000)
+} else if cfg!(any(target_arch = "mips", target_arch = "mips64")) {
+    Some(0o100000)
+} else {
+    None
+};
+
+/// Reader going around the page cache, reading whole aligned blocks of a
+/// file opened with `O_DIRECT`
+pub struct DirectReader {
+    file: File,
+    /// buffer, with room to start the block at an aligned address
+    buf: Vec<u8>,
+    /// start of the aligned block in `buf`
+    start: usize,
+    /// bytes of the block read from the file
+    len: usize,
+    /// bytes of the block already consumed
+    pos: usize,
+}
+
+/// DirectReader implementation
+impl DirectReader {
+    /// Open a file for direct reads.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - file to open.
+    pub fn open(path: &Path) -> io::Result<DirectReader> {
+        let flags = O_DIRECT.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "direct I/O is only supported on Linux",
+            )
+        })?;
+        let mut options = OpenOptions::new();
+        options.read(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, flags);
+        #[cfg(not(unix))]
+        let _ = flags;
+        let file = options.open(path).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidInput => io::Error::new(
+                e.kind(),
+                format!(
+                    "{}: the filesystem doesn't support direct I/O",
+                    path.display()
+                ),
+            ),
+            _ => e,
+        })?;
+        let buf = vec![0; DIRECT_BUFFER];
+        let start = buf.as_ptr().align_offset(DIRECT_ALIGN);
+        Ok(DirectReader {
+            file,
+            buf,
+            start,
+            len: 0,
+            pos: 0,
+        })
+    }
+}
+
+impl Read for DirectReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.len {
+            let block = &mut self.buf[self.start..self.start + DIRECT_BLOCK];
+            self.len = self.file.read(block)?;
+            self.pos = 0;
+        }
+        let n = out.len().min(self.len - self.pos);
+        let at = self.start + self.pos;
+        out[..n].copy_from_slice(&self.buf[at..at + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Capabilities of a file by its metadata: regular files can be seeked
+/// and have a length, pipes and character devices are live.
+fn file_capabilities(meta: io::Result<Metadata>) -> Capabilities {
+    match meta {
+        Ok(meta) if meta.is_file() => Capabilities {
+            seekable: true,
+            len: Some(meta.len()),
+            live: false,
+        },
+        Ok(meta) => Capabilities {
+            live: is_live(&meta.file_type()),
+            ..Capabilities::default()
+        },
+        Err(_) => Capabilities::default(),
+    }
+}
+
+/// Whether a file type is a named pipe or a character device.
+#[cfg(unix)]
+fn is_live(file_type: &FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_fifo() || file_type.is_char_device()
+}
+
+/// Whether a file type is a named pipe or a character device, which only
+/// unix tells.
+#[cfg(not(unix))]
+fn is_live(_: &FileType) -> bool {
+    false
+}
+
+/// Metadata of the file behind stdin, by fstat of descriptor 0.
+#[cfg(unix)]
+fn stdin_metadata() -> io::Result<Metadata> {
+    inherited(0)?.metadata()
+}
+
+/// Metadata of the file behind stdin, which only unix tells here.
+#[cfg(not(unix))]
+fn stdin_metadata() -> io::Result<Metadata> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Duplicate of an inherited file descriptor, or handle on Windows, so
+/// the source can be opened more than once and the descriptor itself is
+/// left open.
+///
+/// # Arguments
+///
+/// * `fd` - descriptor or handle number.
+fn inherited(fd: u64) -> io::Result<File> {
+    let closed = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("fd {fd} isn't an open descriptor"),
+        )
+    };
+    #[cfg(unix)]
+    {
+        use std::os::fd::{BorrowedFd, RawFd};
+        let fd = RawFd::try_from(fd).map_err(|_| closed())?;
+        // SAFETY: the descriptor is only borrowed to duplicate it, which
+        // fails with EBADF when nothing is open under that number
+        let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+        match borrowed.try_clone_to_owned() {
+            Ok(owned) => Ok(File::from(owned)),
+            Err(e) if e.raw_os_error() == Some(9) => Err(closed()),
+            Err(e) => Err(e),
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::{BorrowedHandle, RawHandle};
+        let handle = usize::try_from(fd).map_err(|_| closed())? as RawHandle;
+        // SAFETY: the handle is only borrowed to duplicate it, which fails
+        // when it isn't a valid handle of this process
+        let borrowed = unsafe { BorrowedHandle::borrow_raw(handle) };
+        Ok(File::from(
+            borrowed.try_clone_to_owned().map_err(|_| closed())?,
+        ))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = closed;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "inherited descriptors aren't supported on this platform",
+        ))
+    }
+}
+
+/// Reader repeating a pattern without end
+pub struct Repeat {
+    bytes: Vec<u8>,
+    /// index of the next pattern byte
+    next: usize,
+}
+
+impl Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        for b in buf.iter_mut() {
+            *b = self.bytes[self.next];
+            self.next = (self.next + 1) % self.bytes.len();
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Reader taking a word from each input in turn, ending with the
+/// shortest input
+pub struct Interleave {
+    inputs: Vec<BufReader<File>>,
+    word: usize,
+    /// input the next bytes come from
+    next: usize,
+    /// bytes of the current word still to take
+    left: usize,
+    done: bool,
+}
+
+impl Read for Interleave {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let want = buf.len().min(self.left);
+        let n = self.inputs[self.next].read(&mut buf[..want])?;
+        if n == 0 {
+            self.done = true;
+        }
+        self.left -= n;
+        if self.left == 0 {
+            self.left = self.word;
+            self.next = (self.next + 1) % self.inputs.len();
+        }
+        Ok(n)
+    }
+}
+
+/// Reader keeping one lane of bytes: every `lanes`th byte, starting at
+/// `lane`
+pub struct Deinterleave<R: Read> {
+    inner: R,
+    lanes: usize,
+    lane: usize,
+    /// lane of the next byte read from `inner`
+    position: usize,
+    scratch: Vec<u8>,
+}
+
+/// Deinterleave implementation
+impl<R: Read> Deinterleave<R> {
+    /// Deinterleave constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input, read from the start.
+    /// * `lanes` - number of lanes the input bytes are spread over.
+    /// * `lane` - lane to keep, below `lanes`.
+    pub fn new(inner: R, lanes: usize, lane: usize) -> Deinterleave<R> {
+        Deinterleave {
+            inner,
+            lanes,
+            lane,
+            position: 0,
+            scratch: Vec::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Deinterleave<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        // at most one byte per lane of scratch is kept, so it all fits in out
+        self.scratch.resize(out.len().min(8192) * self.lanes, 0);
+        loop {
+            let n = self.inner.read(&mut self.scratch)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut kept = 0;
+            for &b in &self.scratch[..n] {
+                if self.position == self.lane {
+                    out[kept] = b;
+                    kept += 1;
+                }
+                self.position = (self.position + 1) % self.lanes;
+            }
+            if kept > 0 {
+                return Ok(kept);
+            }
+        }
+    }
+}
+
+/// InputSource implementation
+impl InputSource {
+    /// Source for an input argument: stdin for None or `-`, else a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - input argument.
+    pub fn new(path: Option<&Path>) -> InputSource {
+        match path {
+            None => InputSource::Stdin,
+            Some(path) if path == Path::new("-") => InputSource::Stdin,
+            Some(path) => InputSource::File(path.to_path_buf()),
+        }
+    }
+
+    /// File path, None for stdin, descriptors, literals and joined files.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            InputSource::Stdin
+            | InputSource::Fd(_)
+            | InputSource::Spooled(_)
+            | InputSource::Literal(_)
+            | InputSource::Pattern { .. }
+            | InputSource::Lfsr(_)
+            | InputSource::Concat(_)
+            | InputSource::Interleave { .. } => None,
+            InputSource::File(path) => Some(path),
+        }
+    }
+
+    /// Name of the source in messages: the file path, `fd N` for an
+    /// inherited descriptor, else `stdin`.
+    pub fn name(&self) -> Cow<'_, str> {
+        match self {
+            InputSource::Fd(fd) => format!("fd {fd}").into(),
+            InputSource::Spooled(spool) => spool.of.name(),
+            source => source.path().map_or("stdin".into(), Path::to_string_lossy),
+        }
+    }
+
+    /// What the source supports, without opening it. A file that can't be
+    /// read reports no capabilities; opening it gives the error.
+    pub fn capabilities(&self) -> Capabilities {
+        match self {
+            // stdin is read as a stream even when it's a file, only its
+            // type tells whether it's live
+            InputSource::Stdin => Capabilities {
+                live: file_capabilities(stdin_metadata()).live,
+                ..Capabilities::default()
+            },
+            InputSource::File(path) => file_capabilities(std::fs::metadata(path)),
+            InputSource::Fd(fd) => file_capabilities(inherited(*fd).and_then(|f| f.metadata())),
+            InputSource::Spooled(spool) => file_capabilities(std::fs::metadata(&spool.temp.path)),
+            InputSource::Literal(bytes) => Capabilities {
+                seekable: false,
+                len: Some(bytes.len() as u64),
+                live: false,
+            },
+            InputSource::Pattern { len, .. } => Capabilities {
+                seekable: false,
+                len: Some(*len),
+                live: false,
+            },
+            InputSource::Lfsr(lfsr) => Capabilities {
+                seekable: false,
+                len: Some(lfsr.len),
+                live: false,
+            },
+            InputSource::Concat(paths) => Capabilities {
+                seekable: false,
+                len: paths
+                    .iter()
+                    .map(|path| InputSource::new(Some(path)).capabilities().len)
+                    .sum(),
+                live: false,
+            },
+            InputSource::Interleave { .. } => Capabilities::default(),
+        }
+    }
+
+    /// Open a file source for reads around the page cache. Only files can
+    /// be read this way.
+    pub fn open_direct(&self) -> io::Result<Input> {
+        match self {
+            InputSource::File(path) => Ok(Input::Joined(BufReader::new(Box::new(
+                DirectReader::open(path)?,
+            )))),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "direct I/O needs a file input",
+            )),
+        }
+    }
+
+    /// Open the source for reading.
+    pub fn open(&self) -> io::Result<Input> {
+        Ok(match self {
+            InputSource::Stdin => Input::Stream(BufReader::new(io::stdin())),
+            InputSource::File(path) => Input::File(BufReader::new(File::open(path)?)),
+            InputSource::Fd(fd) => Input::File(BufReader::new(inherited(*fd)?)),
+            InputSource::Spooled(spool) => {
+                Input::File(BufReader::new(File::open(&spool.temp.path)?))
+            }
+            InputSource::Literal(bytes) => Input::Bytes(Cursor::new(bytes.clone())),
+            InputSource::Pattern { bytes, len } => {
+                let repeat = Repeat {
+                    bytes: bytes.clone(),
+                    next: 0,
+                };
+                Input::Joined(BufReader::new(Box::new(repeat.take(*len))))
+            }
+            InputSource::Lfsr(lfsr) => Input::Joined(BufReader::new(Box::new(lfsr.reader()))),
+            InputSource::Concat(paths) => {
+                let mut joined: Box<dyn Read> = Box::new(io::empty());
+                for path in paths {
+                    joined = Box::new(joined.chain(File::open(path)?));
+                }
+                Input::Joined(BufReader::new(joined))
+            }
+            InputSource::Interleave { paths, word } => {
+                let inputs = paths
+                    .iter()
+                    .map(|path| Ok(BufReader::new(File::open(path)?)))
+                    .collect::<io::Result<_>>()?;
+                Input::Joined(BufReader::new(Box::new(Interleave {
+                    inputs,
+                    word: *word,
+                    next: 0,
+                    left: *word,
+                    done: false,
+                })))
+            }
+        })
+    }
+}
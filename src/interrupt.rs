@@ -0,0 +1,142 @@
+//! signals on unix: a first Ctrl-C during a dump or search ends the input
+//! there, so the line being written is finished and the summary of what was
+//! read is printed; a second one, a hang up or a terminate kill hx as they
+//! would, after removing its temporary files
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// exit status of a run stopped by Ctrl-C, 128 and SIGINT as shells report
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Whether Ctrl-C has been pressed since `catch` was called
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C stops the input rather than killing hx
+static CATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Stop the input at the next Ctrl-C rather than be killed by it.
+pub fn catch() {
+    CATCHING.store(true, Ordering::Relaxed);
+    #[cfg(unix)]
+    unix::install();
+}
+
+/// Whether Ctrl-C has been pressed, so the input has ended early.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Reader ending once Ctrl-C is pressed, as if the input ended there
+pub struct Interruptible<R: Read> {
+    inner: R,
+}
+
+/// Interruptible implementation
+impl<R: Read> Interruptible<R> {
+    /// Interruptible constructor
+    pub fn new(inner: R) -> Interruptible<R> {
+        Interruptible { inner }
+    }
+}
+
+impl<R: Read> Read for Interruptible<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if interrupted() {
+            return Ok(0);
+        }
+        match self.inner.read(buf) {
+            // a read waiting on a pipe is cut short by the signal
+            Err(e) if e.kind() == io::ErrorKind::Interrupted && interrupted() => Ok(0),
+            result => result,
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{unwatch, watch};
+
+/// Signals don't remove files elsewhere.
+#[cfg(not(unix))]
+pub fn watch(_path: &std::path::Path) {}
+
+/// Signals don't remove files elsewhere.
+#[cfg(not(unix))]
+pub fn unwatch(_path: &std::path::Path) {}
+
+/// The signal handler and the temporary files it removes
+#[cfg(unix)]
+mod unix {
+    use super::{CATCHING, INTERRUPTED};
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Mutex, Once};
+
+    const SIGHUP: c_int = 1;
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+    /// `SIG_DFL`, the default action
+    const DEFAULT: usize = 0;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+        fn siginterrupt(signum: c_int, flag: c_int) -> c_int;
+        fn raise(signum: c_int) -> c_int;
+        fn unlink(path: *const c_char) -> c_int;
+    }
+
+    static INSTALL: Once = Once::new();
+
+    /// Temporary files not removed or renamed yet, as the handler passes
+    /// them to unlink
+    static LIVE: Mutex<Vec<CString>> = Mutex::new(Vec::new());
+
+    /// Add a file to the ones removed on a signal.
+    pub fn watch(path: &Path) {
+        install();
+        if let (Ok(path), Ok(mut live)) = (CString::new(path.as_os_str().as_bytes()), LIVE.lock()) {
+            live.push(path);
+        }
+    }
+
+    /// Take a file off the ones removed on a signal.
+    pub fn unwatch(path: &Path) {
+        if let Ok(mut live) = LIVE.lock() {
+            live.retain(|live| live.as_bytes() != path.as_os_str().as_bytes());
+        }
+    }
+
+    /// Install the handler, once.
+    pub fn install() {
+        INSTALL.call_once(|| {
+            for signum in [SIGHUP, SIGINT, SIGTERM] {
+                // SAFETY: the handler has the signature signal expects
+                unsafe { signal(signum, handler as extern "C" fn(c_int) as usize) };
+            }
+            // SAFETY: plain flag change, so a blocked read returns on Ctrl-C
+            unsafe { siginterrupt(SIGINT, 1) };
+        });
+    }
+
+    extern "C" fn handler(signum: c_int) {
+        let caught = signum == SIGINT && CATCHING.load(Ordering::Relaxed);
+        if caught && !INTERRUPTED.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        // a list being changed as the signal came can't be read safely
+        if let Ok(live) = LIVE.try_lock() {
+            for path in live.iter() {
+                // SAFETY: a nul terminated path
+                unsafe { unlink(path.as_ptr()) };
+            }
+        }
+        // SAFETY: back to the default action, which raise then takes
+        unsafe {
+            signal(signum, DEFAULT);
+            raise(signum);
+        }
+    }
+}
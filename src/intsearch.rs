@@ -0,0 +1,78 @@
+//! integer search for `--find-int`: every encoding of a typed value, in one
+//! byte order or both, and optionally of the values around it
+
+use crate::interrupt;
+use crate::scalar::{self, Endian, ScalarType};
+
+/// Value and layout of an integer search
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IntSearch {
+    /// integer type, not a float
+    pub kind: ScalarType,
+    /// value bits, zero extended
+    pub value: u64,
+    /// also find values this far above or below it
+    pub near: u64,
+    /// byte order, None for both
+    pub endian: Option<Endian>,
+}
+
+/// IntSearch implementation
+impl IntSearch {
+    /// Raw bits read as the type, sign extended for signed ones.
+    fn number(&self, raw: u64) -> i128 {
+        let shift = 64 - self.kind.width() as u32 * 8;
+        match self.kind {
+            ScalarType::I8 | ScalarType::I16 | ScalarType::I32 | ScalarType::I64 => {
+                i128::from(((raw << shift) as i64) >> shift)
+            }
+            _ => i128::from(raw),
+        }
+    }
+
+    /// Whether raw bits are a value within `near` of the wanted one.
+    fn matches(&self, raw: u64) -> bool {
+        (self.number(raw) - self.number(self.value)).unsigned_abs() <= u128::from(self.near)
+    }
+
+    /// Find the values at every offset, aligned or not. Returns the index
+    /// of each in the data, its value bits and its byte order; bytes that
+    /// match in both orders are reported once, as little endian.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to search.
+    pub fn scan(&self, data: &[u8]) -> Vec<(usize, u64, Endian)> {
+        let orders = match self.endian {
+            Some(endian) => vec![endian],
+            None => vec![Endian::Little, Endian::Big],
+        };
+        // Ctrl-C stops the scan, with what it found so far
+        data.windows(self.kind.width())
+            .enumerate()
+            .take_while(|_| !interrupt::interrupted())
+            .filter_map(|(at, word)| {
+                orders.iter().find_map(|endian| {
+                    let raw = scalar::decode(word, *endian);
+                    self.matches(raw).then_some((at, raw, *endian))
+                })
+            })
+            .collect()
+    }
+
+    /// Label of a value found, its type, byte order and value, e.g.
+    /// `u32be 305419896`.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - value bits.
+    /// * `endian` - byte order it was found in.
+    pub fn label(&self, raw: u64, endian: Endian) -> String {
+        let order = match (self.kind.width(), endian) {
+            (1, _) => "",
+            (_, Endian::Little) => "le",
+            (_, Endian::Big) => "be",
+        };
+        format!("{}{order} {}", self.kind, self.kind.display(raw))
+    }
+}
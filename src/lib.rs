@@ -1,21 +1,128 @@
 //! general hex lib
 
 #[cfg(test)]
+#[allow(clippy::legacy_numeric_constants)]
 mod tests;
 
+#[cfg(feature = "archives")]
+mod archive;
+mod args;
+mod ascii;
+mod bank;
+mod bindiff;
+mod bookmarks;
+mod cache;
+mod capabilities;
+mod carve;
+mod charset;
+mod colors;
+mod conv;
+#[cfg(feature = "cores")]
+mod coredump;
+mod decode;
+mod dedupe;
+mod dirs;
+mod dwarf;
+mod encoding;
+mod examples;
+mod expr;
+mod floatsearch;
 mod format;
-use crate::format::Format;
+mod formats;
+mod frame;
+mod generate;
+mod hash;
+mod i18n;
+mod input;
+mod interrupt;
+mod intsearch;
+mod library;
+mod literal;
+mod log;
+mod magic;
+mod masked;
+mod observe;
+mod pager;
+mod panel;
+mod patch;
+mod pcap;
+mod pointers;
+mod records;
+mod resume;
+mod safe;
+mod sample;
+mod scalar;
+mod search;
+#[cfg(feature = "zstd")]
+mod seekable;
+mod selftest;
+mod session;
+mod sourcemap;
+mod spec;
+mod spool;
+mod sqlite;
+mod state;
+mod style;
+mod symbols;
+mod temp;
+mod textdiff;
+mod transform;
+mod walk;
+pub use crate::args::{command, parse_args, BookmarkAction, Config, HexError, IndexRange, Mode};
+use crate::args::{ARRAY_FORMATS, FORMATS};
+pub use crate::bank::Banks;
+pub use crate::bookmarks::Bookmark;
+pub use crate::cache::{BlockCache, CACHE_BLOCK};
+pub use crate::charset::{ByteClass, Charset};
+pub use crate::dwarf::{LineRow, LineTable};
+pub use crate::floatsearch::FloatSearch;
+pub use crate::format::{FloatWord, Format, Group};
+pub use crate::formats::{
+    emit, ArrayFormat, Constructor, DumpFormat, ExplainFormat, OutputFormat, RawFormat, Registry,
+    StackedFormat, TransposedFormat,
+};
+pub use crate::hash::{Crc32, Sha256};
+pub use crate::i18n::{format_size, tr, Msg};
+pub use crate::input::{Capabilities, Deinterleave, DirectReader, Input, InputSource, ReadAhead};
+pub use crate::interrupt::EXIT_INTERRUPTED;
+pub use crate::intsearch::IntSearch;
+pub use crate::library::{dump, dump_array, dump_observed, render_range, HexConfig, RenderedLine};
+pub use crate::log::{Level, LogFormat};
+pub use crate::observe::{ByteStats, ObservedReader, Observer};
+pub use crate::pager::Paging;
+pub use crate::panel::{Border, Edge, Panel, DEFAULT_PANELS};
+pub use crate::patch::{Hunk, Overlay, WriteGuard};
+pub use crate::pointers::PointerScan;
+pub use crate::records::{Conflict, Fill, Malformed, Record, RecordFormat};
+pub use crate::scalar::{Endian, ScalarSpec, ScalarType};
+#[cfg(feature = "zstd")]
+pub use crate::seekable::ArchiveReader;
+pub use crate::sourcemap::SourceMap;
+pub use crate::style::{Backend, Role, Span};
+pub use crate::symbols::Symbol;
+pub use crate::transform::{Step, Transform, TransformReader};
+pub use crate::walk::glob_match;
 
-use ansi_term::Color;
+use crate::bindiff::Change;
+use crate::charset::panel_spans;
+use crate::decode::Decoder;
+use crate::formats::{Also, TeeFormat};
+use crate::interrupt::Interruptible;
+use crate::log::Field;
+use crate::style::SpanSink;
+use ansi_term::{Color, Style};
 use clap::ArgMatches;
 use no_color::is_no_color;
 use std::env;
 use std::error::Error;
 use std::f64;
-use std::fs::File;
-use std::io::BufReader;
+use std::fs::{self, File};
 use std::io::IsTerminal;
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, BufRead, Read, Seek, Write};
+use std::io::{BufReader, BufWriter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// arg cols
 pub const ARG_COL: &str = "cols";
@@ -23,24 +130,340 @@ pub const ARG_COL: &str = "cols";
 pub const ARG_LEN: &str = "len";
 /// arg format
 pub const ARG_FMT: &str = "format";
+/// arg float
+pub const ARG_FLT: &str = "float";
 /// arg INPUTFILE
 pub const ARG_INP: &str = "INPUTFILE";
 /// arg color
 pub const ARG_CLR: &str = "color";
+/// arg no-color
+pub const ARG_NCO: &str = "no-color";
 /// arg array
 pub const ARG_ARR: &str = "array";
 /// arg func
 pub const ARG_FNC: &str = "func";
 /// arg places
 pub const ARG_PLC: &str = "places";
+/// arg func-output
+pub const ARG_FNO: &str = "func-output";
 /// arg prefix
 pub const ARG_PFX: &str = "prefix";
-
-const ARGS: [&str; 9] = [
-    ARG_COL, ARG_LEN, ARG_FMT, ARG_INP, ARG_CLR, ARG_ARR, ARG_FNC, ARG_PLC, ARG_PFX,
-];
-
-const DBG: bool = false;
+/// arg stripe
+pub const ARG_STP: &str = "stripe";
+/// arg highlight
+pub const ARG_HLT: &str = "highlight";
+/// arg group
+pub const ARG_GRP: &str = "group";
+/// arg count-matches
+pub const ARG_CMT: &str = "count-matches";
+/// arg only-offsets
+pub const ARG_OOF: &str = "only-offsets";
+/// arg matches-json
+pub const ARG_MJS: &str = "matches-json";
+/// arg find
+pub const ARG_FND: &str = "find";
+/// arg pointer-scan
+pub const ARG_PSC: &str = "pointer-scan";
+/// arg base
+pub const ARG_BAS: &str = "base";
+/// arg size
+pub const ARG_SIZ: &str = "size";
+/// arg pointer-type
+pub const ARG_PTT: &str = "pointer-type";
+/// arg find-int
+pub const ARG_FNI: &str = "find-int";
+/// arg near of find-int
+pub const ARG_NEA: &str = "near";
+/// arg find-float
+pub const ARG_FNF: &str = "find-float";
+/// arg tolerance of find-float
+pub const ARG_TOL: &str = "tolerance";
+/// arg unaligned of find-float
+pub const ARG_UAL: &str = "unaligned";
+/// arg xor
+pub const ARG_XOR: &str = "xor";
+/// arg rot
+pub const ARG_ROT: &str = "rot";
+/// arg reverse bytes
+pub const ARG_RVB: &str = "reverse-bytes";
+/// arg from
+pub const ARG_FRM: &str = "from";
+/// arg strict
+pub const ARG_STR: &str = "strict";
+/// arg fill
+pub const ARG_GAP: &str = "fill";
+/// arg on conflict
+pub const ARG_OCF: &str = "on-conflict";
+/// arg explain
+pub const ARG_EXP: &str = "explain";
+/// arg raw
+pub const ARG_RWB: &str = "raw";
+/// arg concat
+pub const ARG_CAT: &str = "concat";
+/// arg interleave
+pub const ARG_ILV: &str = "interleave";
+/// arg fd
+pub const ARG_FD: &str = "fd";
+/// arg two-pass
+pub const ARG_TWP: &str = "two-pass";
+/// arg temp-dir
+pub const ARG_TMD: &str = "temp-dir";
+/// arg generate
+pub const ARG_GEN: &str = "generate";
+/// arg word of interleave
+pub const ARG_WRD: &str = "word";
+/// arg deinterleave
+pub const ARG_DIL: &str = "deinterleave";
+/// arg lane of deinterleave
+pub const ARG_LAN: &str = "lane";
+/// arg source-offsets
+pub const ARG_SRO: &str = "source-offsets";
+/// arg transpose
+pub const ARG_TRN: &str = "transpose";
+/// arg text-report
+pub const ARG_TXR: &str = "text-report";
+/// arg detect-encoding
+pub const ARG_DET: &str = "detect-encoding";
+/// arg rle
+pub const ARG_RLE: &str = "rle";
+/// arg split-on
+pub const ARG_SPL: &str = "split-on";
+/// arg frame
+pub const ARG_FRA: &str = "frame";
+/// arg decode
+pub const ARG_DEC: &str = "decode";
+/// arg type-size of decode tlv
+pub const ARG_TSZ: &str = "type-size";
+/// arg len-size of decode tlv
+pub const ARG_LSZ: &str = "len-size";
+/// arg count of decode leb128
+pub const ARG_QTY: &str = "count";
+/// arg stacked
+pub const ARG_STK: &str = "stacked";
+/// arg no-wrap
+pub const ARG_NWR: &str = "no-wrap";
+/// arg separator
+pub const ARG_SEP: &str = "separator";
+/// arg bank size
+pub const ARG_BKS: &str = "bank-size";
+/// arg bank stride
+pub const ARG_BKT: &str = "bank-stride";
+/// arg header
+pub const ARG_HDR: &str = "header";
+/// arg header hash
+pub const ARG_HHS: &str = "header-hash";
+/// arg paging
+pub const ARG_PGR: &str = "paging";
+/// arg continue
+pub const ARG_CNT: &str = "continue";
+/// arg reverse
+pub const ARG_REV: &str = "reverse";
+/// arg absolute
+pub const ARG_ABS: &str = "absolute";
+/// arg panels
+pub const ARG_PNL: &str = "panels";
+/// arg border
+pub const ARG_BRD: &str = "border";
+/// arg profile
+pub const ARG_PRF: &str = "profile";
+/// arg charset
+pub const ARG_CHS: &str = "charset";
+/// arg only
+pub const ARG_ONL: &str = "only";
+/// arg line-buffered
+pub const ARG_LBF: &str = "line-buffered";
+/// arg io-readahead
+pub const ARG_IRA: &str = "io-readahead";
+/// arg memory-limit
+pub const ARG_MEM: &str = "memory-limit";
+/// arg direct-io
+pub const ARG_DIO: &str = "direct-io";
+/// arg timing
+pub const ARG_TIM: &str = "timing";
+/// arg capabilities
+pub const ARG_CAP: &str = "capabilities";
+/// arg timestamps
+pub const ARG_TSP: &str = "timestamps";
+/// arg session
+pub const ARG_SES: &str = "session";
+/// arg overlay
+pub const ARG_OVL: &str = "overlay";
+/// arg carve
+pub const ARG_CRV: &str = "carve";
+/// arg redact
+pub const ARG_RED: &str = "redact";
+/// arg redact-fill
+pub const ARG_RDF: &str = "redact-fill";
+/// arg fragment
+pub const ARG_FRG: &str = "fragment";
+/// arg finalize
+pub const ARG_FIN: &str = "finalize";
+/// arg mark-truncated
+pub const ARG_MTR: &str = "mark-truncated";
+/// arg continuous-offsets
+pub const ARG_CTO: &str = "continuous-offsets";
+/// arg len-scope
+pub const ARG_LSC: &str = "len-scope";
+/// arg raw-sizes
+pub const ARG_RAW: &str = "raw-sizes";
+/// arg reproducible
+pub const ARG_RPR: &str = "reproducible";
+/// arg files-from
+pub const ARG_FFR: &str = "files-from";
+/// arg recursive
+pub const ARG_REC: &str = "recursive";
+/// arg glob
+pub const ARG_GLB: &str = "glob";
+/// arg member
+pub const ARG_MBR: &str = "member";
+/// arg region
+pub const ARG_RGN: &str = "region";
+/// arg sqlite-page
+pub const ARG_SQL: &str = "sqlite-page";
+/// arg pcap
+pub const ARG_PCP: &str = "pcap";
+/// arg packets
+pub const ARG_PKT: &str = "packets";
+/// arg output
+pub const ARG_OFM: &str = "output";
+/// arg expect
+pub const ARG_EXC: &str = "expect";
+/// arg outfile
+pub const ARG_OUT: &str = "outfile";
+/// arg also
+pub const ARG_ALS: &str = "also";
+/// arg resume
+pub const ARG_RES: &str = "resume";
+/// arg zst
+pub const ARG_ZST: &str = "zst";
+/// arg sample
+pub const ARG_SMP: &str = "sample";
+/// arg seed
+pub const ARG_SED: &str = "seed";
+/// arg skip
+pub const ARG_SKP: &str = "skip";
+/// arg align
+pub const ARG_ALN: &str = "align";
+/// arg range
+pub const ARG_RNG: &str = "range";
+/// arg boundary
+pub const ARG_BND: &str = "boundary";
+/// arg bookmarks
+pub const ARG_BMK: &str = "bookmarks";
+/// arg label of bookmark add
+pub const ARG_LBL: &str = "label";
+/// arg symbols
+pub const ARG_SYM: &str = "symbols";
+/// arg unique-lines
+pub const ARG_UNQ: &str = "unique-lines";
+/// arg dedupe-scan
+pub const ARG_DDS: &str = "dedupe-scan";
+/// arg dedupe-report
+pub const ARG_DRP: &str = "dedupe-report";
+/// arg carve-auto
+pub const ARG_CAU: &str = "carve-auto";
+/// subcommand selftest
+pub const CMD_SELFTEST: &str = "selftest";
+/// subcommand extract
+pub const CMD_EXTRACT: &str = "extract";
+/// subcommand poke
+pub const CMD_POKE: &str = "poke";
+/// subcommand move
+pub const CMD_MOVE: &str = "move";
+/// arg destination of the move subcommand
+pub const ARG_TO: &str = "to";
+/// arg fill for the vacated bytes of the move subcommand
+pub const ARG_CLE: &str = "clear";
+/// subcommand resize
+pub const CMD_RESIZE: &str = "resize";
+/// subcommand bookmark
+pub const CMD_BOOKMARK: &str = "bookmark";
+/// subcommand undo
+pub const CMD_UNDO: &str = "undo";
+/// subcommand colors
+pub const CMD_COLORS: &str = "colors";
+/// subcommand table
+pub const CMD_TABLE: &str = "table";
+/// subcommand conv
+pub const CMD_CONV: &str = "conv";
+/// arg value of the conv subcommand
+pub const ARG_NUM: &str = "number";
+/// subcommand echo
+pub const CMD_ECHO: &str = "echo";
+/// arg literal of the echo subcommand
+pub const ARG_LIT: &str = "literal";
+/// subcommand fill
+pub const CMD_FILL: &str = "fill";
+/// subcommand match
+pub const CMD_MATCH: &str = "match";
+/// arg pattern of the match subcommand
+pub const ARG_PAT: &str = "pattern";
+/// arg mask of the match subcommand
+pub const ARG_MSK: &str = "mask";
+/// subcommand check
+pub const CMD_CHECK: &str = "check";
+/// subcommand diff
+pub const CMD_DIFF: &str = "diff";
+/// arg OLD of the diff subcommand
+pub const ARG_OLD: &str = "OLD";
+/// arg NEW of the diff subcommand
+pub const ARG_NEW: &str = "NEW";
+/// arg block of the diff subcommand
+pub const ARG_BLK: &str = "block";
+/// arg checksums of the diff subcommand
+pub const ARG_CKS: &str = "checksums";
+/// arg diff-algo of the diff subcommand
+pub const ARG_DAL: &str = "diff-algo";
+/// arg spec of the check subcommand
+pub const ARG_SPC: &str = "spec";
+/// subcommand bits
+pub const CMD_BITS: &str = "bits";
+/// subcommand sum
+pub const CMD_SUM: &str = "sum";
+/// subcommand examples
+pub const CMD_EXAMPLES: &str = "examples";
+/// arg TOPIC of the examples subcommand
+pub const ARG_TPC: &str = "TOPIC";
+/// subcommand paths
+pub const CMD_PATHS: &str = "paths";
+/// arg layout of the bits subcommand
+pub const ARG_LAY: &str = "layout";
+/// arg extended of the table subcommand
+pub const ARG_EXT: &str = "extended";
+/// arg FILE of the scalar subcommands
+pub const ARG_FIL: &str = "FILE";
+/// arg at
+pub const ARG_AT: &str = "at";
+/// arg type
+pub const ARG_TYP: &str = "type";
+/// arg endian
+pub const ARG_END: &str = "endian";
+/// arg bits
+pub const ARG_BIT: &str = "bits";
+/// arg value
+pub const ARG_VAL: &str = "value";
+/// arg quiet
+pub const ARG_QUI: &str = "quiet";
+/// arg verbose
+pub const ARG_VRB: &str = "verbose";
+/// arg log-format
+pub const ARG_LGF: &str = "log-format";
+/// arg read-only
+pub const ARG_RDO: &str = "read-only";
+/// environment variable switching on read-only mode
+pub const READ_ONLY_ENV: &str = "HX_READ_ONLY";
+/// arg backup
+pub const ARG_BAK: &str = "backup";
+/// arg no-clobber
+pub const ARG_NCL: &str = "no-clobber";
+/// arg atomic
+pub const ARG_ATM: &str = "atomic";
+/// arg journal
+pub const ARG_JRN: &str = "journal";
+/// arg reverse-patch
+pub const ARG_RVP: &str = "reverse-patch";
+/// arg yes
+pub const ARG_YES: &str = "yes";
 
 /// Line structure for hex output
 #[derive(Clone, Debug, Default)]
@@ -53,6 +476,10 @@ pub struct Line {
     pub ascii: Vec<u8>,
     /// total bytes in Line
     pub bytes: u64,
+    /// a marked boundary falls on this line, its offset is highlighted
+    pub boundary: bool,
+    /// bookmark labels for offsets on this line, printed in the margin
+    pub notes: Vec<String>,
 }
 
 /// Line implementation
@@ -64,8 +491,20 @@ impl Line {
             hex_body: Vec::new(),
             ascii: Vec::new(),
             bytes: 0x0,
+            boundary: false,
+            notes: Vec::new(),
         }
     }
+
+    /// Bytes of the line.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.hex_body
+    }
+
+    /// Offset just past the last byte of the line.
+    pub fn end(&self) -> u64 {
+        self.offset + self.hex_body.len() as u64
+    }
 }
 
 /// Page structure
@@ -89,6 +528,211 @@ impl Page {
             bytes: 0x0,
         }
     }
+
+    /// Page of lines, its offset that of the first line and its byte count
+    /// their sum.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - lines in order.
+    pub fn from_lines(body: Vec<Line>) -> Page {
+        Page {
+            offset: body.first().map_or(0, |line| line.offset),
+            bytes: body.iter().map(|line| line.hex_body.len() as u64).sum(),
+            body,
+        }
+    }
+
+    /// Every byte of the page, line after line.
+    pub fn iter_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.body
+            .iter()
+            .flat_map(|line| line.hex_body.iter().copied())
+    }
+
+    /// Pages of `n` lines each, the last one shorter when the lines don't
+    /// divide evenly, e.g. one page per record of a fixed number of rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - lines per page, panics when 0.
+    pub fn chunks(&self, n: usize) -> impl Iterator<Item = Page> + '_ {
+        self.body
+            .chunks(n)
+            .map(|lines| Page::from_lines(lines.to_vec()))
+    }
+
+    /// Bytes of a range of offsets, the lines cut to it and their offsets
+    /// moved along, so they still give the offset of their first byte.
+    /// Lines outside the range are left out, and so are the parts of the
+    /// range outside the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - offsets, as the line offsets count them.
+    pub fn slice(&self, range: Range<u64>) -> Page {
+        let body = self
+            .body
+            .iter()
+            .filter_map(|line| {
+                let start = range.start.clamp(line.offset, line.end());
+                let end = range.end.clamp(start, line.end());
+                if start == end {
+                    return None;
+                }
+                let cut = (start - line.offset) as usize..(end - line.offset) as usize;
+                Some(Line {
+                    offset: start,
+                    hex_body: line.hex_body[cut.clone()].to_vec(),
+                    ascii: line
+                        .ascii
+                        .get(cut)
+                        .map_or(Vec::new(), |ascii| ascii.to_vec()),
+                    bytes: end - start,
+                    boundary: line.boundary,
+                    notes: line.notes.clone(),
+                })
+            })
+            .collect();
+        Page {
+            offset: range.start.max(self.offset),
+            ..Page::from_lines(body)
+        }
+    }
+}
+
+/// Layout and styling of hex dump lines
+#[derive(Copy, Clone, Debug)]
+pub struct DumpOptions<'a> {
+    /// byte format
+    pub format: Format,
+    /// colorize bytes and ascii
+    pub colorize: bool,
+    /// include the format prefix with every byte
+    pub prefix: bool,
+    /// bytes to dump, 0 dumps everything
+    pub truncate_len: u64,
+    /// bytes per line, 0 for the whole input on one line
+    pub column_width: u64,
+    /// offset printed for the first byte
+    pub start_offset: u64,
+    /// pad the first line so line offsets stay multiples of the column width
+    pub align: bool,
+    /// dim every other group of this many byte columns, 0 for no stripes
+    pub stripe: u64,
+    /// cells whose value lies within these bounds stand out, when colored
+    pub highlight: Option<(f64, f64)>,
+    /// character set of the panel after the byte columns
+    pub charset: Charset,
+    /// bytes shown, the others blank or dimmed when colored, None for all
+    pub only: Option<ByteClass>,
+    /// mark lines where a multiple of this many bytes falls, 0 for none
+    pub boundary: u64,
+    /// bookmarks labeled in the margin, sorted by offset
+    pub bookmarks: &'a [Bookmark],
+    /// byte ranges of `--find` matches or likely pointers, highlighted
+    /// when colored
+    pub found: &'a [Range<u64>],
+    /// symbols labeling line offsets, sorted by address
+    pub symbols: &'a [Symbol],
+    /// source lines labeling line offsets
+    pub lines: &'a LineTable,
+    /// bank layout translating printed offsets and symbol lookups
+    pub banks: Banks,
+    /// input file offsets printed after the line offsets, `--source-offsets`
+    pub source: Option<&'a SourceMap>,
+    /// text after every byte cell
+    pub separator: &'a str,
+    /// bytes shown as one integer word per cell
+    pub group: Group,
+    /// time column before every line, None for none
+    pub timestamps: Option<Timestamps>,
+    /// panels of every line, in order
+    pub panels: &'a [Panel],
+    /// lines drawn around the panels
+    pub border: Border,
+}
+
+/// Output flushing policy
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// flush after every output line, for tailing live streams
+    Line,
+    /// flush only when the buffer fills up, the default for file dumps
+    Block,
+}
+
+/// Time column printed before the offset of every dump line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Timestamps {
+    /// UTC time of day the line was read, e.g. `12:34:56.789012`
+    Absolute,
+    /// seconds since the previous line was read, or since the start for
+    /// the first line
+    Delta,
+}
+
+/// Buffered writer applying a `FlushPolicy` at line boundaries
+pub struct OutputWriter<W: Write> {
+    inner: BufWriter<W>,
+    policy: FlushPolicy,
+    /// hits of the searches written so far, None before the first
+    hits: Option<u64>,
+}
+
+/// OutputWriter implementation
+impl<W: Write> OutputWriter<W> {
+    /// OutputWriter constructor
+    pub fn new(inner: W, policy: FlushPolicy) -> OutputWriter<W> {
+        OutputWriter {
+            inner: BufWriter::new(inner),
+            policy,
+            hits: None,
+        }
+    }
+
+    /// Add the hits of a search to the ones the exit status is taken from.
+    ///
+    /// # Arguments
+    ///
+    /// * `hits` - matches, values or pointers found.
+    pub fn record_hits(&mut self, hits: u64) {
+        self.hits = Some(self.hits.unwrap_or(0) + hits);
+    }
+
+    /// Hits of every search written so far, None when nothing was searched.
+    pub fn hits(&self) -> Option<u64> {
+        self.hits
+    }
+
+    /// Flush and return the underlying writer
+    pub fn into_inner(self) -> io::Result<W> {
+        self.inner.into_inner().map_err(|e| e.into_error())
+    }
+
+    /// Terminate the current output line, flushing if the policy asks for it
+    pub fn end_line(&mut self) -> io::Result<()> {
+        writeln!(self.inner)?;
+        self.line_done()
+    }
+
+    /// Flush if the policy asks for it, after a complete line was written
+    pub fn line_done(&mut self) -> io::Result<()> {
+        if self.policy == FlushPolicy::Line {
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for OutputWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 /// offset column
@@ -105,25 +749,42 @@ pub fn print_offset(w: &mut impl Write, b: u64) -> io::Result<()> {
     write!(w, "{}: ", offset(b))
 }
 
-/// print byte to std out
+/// print byte to std out, followed by the separator
 pub fn print_byte(
     w: &mut impl Write,
     b: u8,
     format: Format,
     colorize: bool,
     prefix: bool,
+    separator: &str,
 ) -> io::Result<()> {
     let fmt_string = format.format(b, prefix);
     if colorize {
         // note, for color testing: for (( i = 0; i < 256; i++ )); do echo "$(tput setaf $i)This is ($i) $(tput sgr0)"; done
         let color = byte_to_color(b);
         let string = ansi_term::Style::new().fg(color).paint(fmt_string);
-        write!(w, "{string} ")
+        write!(w, "{string}{separator}")
     } else {
-        write!(w, "{fmt_string} ")
+        write!(w, "{fmt_string}{separator}")
     }
 }
 
+/// print a colored byte dimmed, for the striped column groups, followed
+/// by the separator
+pub fn print_dimmed_byte(
+    w: &mut impl Write,
+    b: u8,
+    format: Format,
+    prefix: bool,
+    separator: &str,
+) -> io::Result<()> {
+    let string = ansi_term::Style::new()
+        .fg(byte_to_color(b))
+        .dimmed()
+        .paint(format.format(b, prefix));
+    write!(w, "{string}{separator}")
+}
+
 /// get the color for a specific byte
 pub fn byte_to_color(b: u8) -> Color {
     let color = match b {
@@ -134,6 +795,25 @@ pub fn byte_to_color(b: u8) -> Color {
     ansi_term::Color::Fixed(color)
 }
 
+/// get the color for the value of a float word by its magnitude: zero as
+/// the zero byte, then blue for tiny values through green and yellow to
+/// magenta for huge ones, red for infinities and NaN
+pub fn word_to_color(value: f64) -> Color {
+    if !value.is_finite() {
+        return Color::Red;
+    }
+    if value == 0.0 {
+        return byte_to_color(0);
+    }
+    match value.abs().log10().floor() as i32 {
+        ..-6 => Color::Fixed(33),
+        -6..0 => Color::Fixed(44),
+        0..6 => Color::Fixed(40),
+        6..30 => Color::Fixed(178),
+        _ => Color::Fixed(170),
+    }
+}
+
 /// append char representation of a byte to a buffer
 pub fn append_ascii(target: &mut Vec<u8>, b: u8, colorize: bool) {
     let chr = match b > 31 && b < 127 {
@@ -164,286 +844,4249 @@ pub fn append_ascii(target: &mut Vec<u8>, b: u8, colorize: bool) {
 ///
 /// * `matches` - Argument matches from command line.
 pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let mut column_width: u64 = 10;
-    let mut truncate_len: u64 = 0x0;
-    if let Some(len) = matches.get_one::<String>("func") {
-        let mut p: usize = 4;
-        if let Some(places) = matches.get_one::<String>("places") {
-            p = match places.parse::<usize>() {
-                Ok(p) => p,
-                Err(e) => {
-                    eprintln!("-p, --places <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
-                }
+    // records of errors in the options themselves are formatted too
+    if let Some(format) = matches.get_one::<String>(ARG_LGF) {
+        log::set_format(LogFormat::from_name(format));
+    }
+    let config = Config::from_matches(matches)?;
+    run_config(&config)
+}
+
+/// Report an error on stderr, prefixed with `error:`, or as a JSON record
+/// with `--log-format json`.
+///
+/// # Arguments
+///
+/// * `error` - what went wrong.
+pub fn log_error(error: impl std::fmt::Display) {
+    log::error(error);
+}
+
+/// Run hx for an already parsed `Config`.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+pub fn run_config(config: &Config) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let mut processed: u64 = 0x0;
+    let mut hits = None;
+    log::set_level(config.log_level);
+    log::set_format(config.log_format);
+    // the files the options name and the --two-pass copy are read now,
+    // parsing has no side effects
+    let config = &config.load()?;
+    log_options(config);
+    match &config.mode {
+        Mode::Selftest => {
+            let stdout = io::stdout();
+            let mismatches = selftest::selftest(&mut stdout.lock())?;
+            if mismatches > 0 {
+                return Err(format!("selftest failed with {mismatches} mismatches").into());
             }
+            return Ok(());
         }
-        output_function(len.parse::<u64>().unwrap(), p);
-    } else {
-        // cases:
-        //  $ cat Cargo.toml | target/debug/hx
-        //  $ cat Cargo.toml | target/debug/hx -a r
-        //  $ target/debug/hx Cargo.toml
-        //  $ target/debug/hx Cargo.toml -a r
-        let mut buf: Box<dyn BufRead> = match is_stdin(&matches) {
-            true => Box::new(BufReader::new(io::stdin())),
-            false => {
-                let path = matches.get_one::<String>(ARG_INP).unwrap();
-                let file = File::open(path)?;
-                Box::new(BufReader::new(file))
+        Mode::Capabilities => capabilities::describe(
+            &mut io::stdout().lock(),
+            &command(),
+            FORMATS.values,
+            ARRAY_FORMATS.values,
+            &config.formats.names(),
+        )?,
+        Mode::Colors => {
+            let mut out = OutputWriter::new(io::stdout().lock(), FlushPolicy::Block);
+            colors::write_legend(&mut out, &dump_options(config))?;
+            out.flush()?;
+        }
+        Mode::Table { extended } => {
+            let mut out = OutputWriter::new(io::stdout().lock(), FlushPolicy::Block);
+            ascii::write_table(&mut out, &dump_options(config), *extended)?;
+            out.flush()?;
+        }
+        Mode::Conv { value, negative } => {
+            conv::describe(&mut io::stdout().lock(), *value, *negative)?
+        }
+        Mode::Examples { topic } => examples::write(&mut io::stdout().lock(), topic.as_deref())?,
+        Mode::Paths => dirs::write(&mut io::stdout().lock(), &dirs::Dirs::current())?,
+        Mode::Reverse { words, absolute } => match (&config.outfile, absolute) {
+            (Some(path), true) => {
+                let mut image = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)?;
+                processed = reverse_dump_into(&mut config.input.open()?, &mut image, *words)?;
             }
-        };
-        let mut format_out = Format::LowerHex;
-        let mut colorize = true;
-        let mut prefix = true;
-
-        if let Some(columns) = matches.get_one::<String>(ARG_COL) {
-            column_width = match columns.parse::<u64>() {
-                Ok(column_width) => column_width,
-                Err(e) => {
-                    eprintln!("-c, --cols <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
-                }
+            _ => {
+                let sink: Box<dyn Write> = match &config.outfile {
+                    Some(path) => Box::new(File::create(path)?),
+                    None => Box::new(io::stdout().lock()),
+                };
+                let mut out = io::BufWriter::new(sink);
+                processed = reverse_dump_words(&mut config.input.open()?, &mut out, *words)?;
+                out.flush()?;
             }
+        },
+        Mode::Func { len, places } => {
+            let mut out = OutputWriter::new(io::stdout().lock(), FlushPolicy::Block);
+            output_function(&mut out, *len, *places, dump_options(config).colorize)?;
+            out.flush()?;
         }
-
-        if let Some(length) = matches.get_one::<String>(ARG_LEN) {
-            truncate_len = match length.parse::<u64>() {
-                Ok(truncate_len) => truncate_len,
-                Err(e) => {
-                    eprintln!("-l, --len <integer> expected. {:?}", e);
-                    return Err(Box::new(e));
+        Mode::Extract { path, spec, bits } => {
+            let raw =
+                scalar::read_scalar(&mut InputSource::new(Some(Path::new(path))).open()?, spec)?;
+            scalar::describe(&mut io::stdout().lock(), spec, raw, *bits)?;
+        }
+        Mode::Sum {
+            path,
+            ranges,
+            kind,
+            endian,
+        } => {
+            let totals = scalar::totals(
+                &mut InputSource::new(Some(Path::new(path))).open()?,
+                ranges,
+                *kind,
+                *endian,
+            )?;
+            scalar::report(&mut io::stdout().lock(), &totals)?;
+        }
+        Mode::Bits { path, spec, layout } => {
+            let raw =
+                scalar::read_scalar(&mut InputSource::new(Some(Path::new(path))).open()?, spec)?;
+            scalar::diagram(&mut io::stdout().lock(), spec, raw, layout)?;
+        }
+        Mode::Match {
+            path,
+            pattern,
+            mask,
+        } => match_pattern(config, path, pattern, mask.as_deref())?,
+        Mode::Check { path, spec } => check_spec(config, path, spec)?,
+        Mode::Diff {
+            old,
+            new,
+            block,
+            aligned,
+            checksums,
+        } => diff_files(config, old, new, *block, *aligned, *checksums)?,
+        Mode::Poke {
+            path,
+            spec,
+            value,
+            guard,
+        } => poke(config, path, spec, *value, guard)?,
+        Mode::Move {
+            path,
+            from,
+            len,
+            to,
+            clear,
+            guard,
+        } => move_region(config, path, (*from, *len), *to, *clear, guard)?,
+        Mode::Resize {
+            path,
+            size,
+            fill,
+            guard,
+        } => resize(config, path, *size, fill, guard)?,
+        Mode::Bookmark { path, action } => {
+            let mut marks = bookmarks::load(path)?;
+            match action {
+                BookmarkAction::Add(mark) => {
+                    marks.retain(|m| m.offset != mark.offset);
+                    marks.push(mark.clone());
+                    if let Some(dir) = path.parent() {
+                        std::fs::create_dir_all(dir)?;
+                    }
+                    temp::write_atomically(path, bookmarks::format(&marks).as_bytes())?;
                 }
+                BookmarkAction::Remove(at) => {
+                    let count = marks.len();
+                    marks.retain(|m| m.offset != *at);
+                    if marks.len() == count {
+                        return Err(format!("no bookmark at {}", offset(*at)).into());
+                    }
+                    temp::write_atomically(path, bookmarks::format(&marks).as_bytes())?;
+                }
+                BookmarkAction::List => print!("{}", bookmarks::format(&marks)),
             }
         }
-
-        if let Some(format) = matches.get_one::<String>(ARG_FMT) {
-            // o, x, X, p, b, e, E
-            match format.as_str() {
-                "o" => format_out = Format::Octal,
-                "x" => format_out = Format::LowerHex,
-                "X" => format_out = Format::UpperHex,
-                "p" => format_out = Format::Pointer,
-                "b" => format_out = Format::Binary,
-                "e" => format_out = Format::LowerExp,
-                "E" => format_out = Format::UpperExp,
-                _ => format_out = Format::Unknown,
+        Mode::Undo { patch, guard } => {
+            let (path, hunks) = patch::parse_patch(&std::fs::read_to_string(patch)?)?;
+            patch::verify(&path, &hunks)?;
+            let (tails, hunks): (Vec<Hunk>, Vec<Hunk>) = hunks.into_iter().partition(Hunk::resizes);
+            let edits: Vec<(u64, Vec<u8>)> = hunks.into_iter().map(|h| (h.at, h.new)).collect();
+            if !edits.is_empty() {
+                show_patch(config, &path, &edits, guard)?;
+            }
+            for tail in tails {
+                // the bytes to add back, lined up as a pattern from offset 0
+                let mut fill = tail.new;
+                let size = tail.at + fill.len() as u64;
+                if !fill.is_empty() {
+                    let shift = (tail.at % fill.len() as u64) as usize;
+                    fill.rotate_right(shift);
+                }
+                resize(config, &path, size, &fill, guard)?;
             }
         }
-
-        // check no_color here
-        // override via ARG_CLR below
-        if is_no_color() {
-            colorize = false;
+        Mode::Array(_) | Mode::Dump if config.expect.is_some() => {
+            processed = expect_output(config, config.expect.as_deref().unwrap_or_default())?;
         }
-
-        // prevent term color codes being sent to stdout
-        // test: cat Cargo.toml | target/debug/hx | more
-        // override via ARG_CLR below
-        if !io::stdout().is_terminal() {
-            colorize = false;
+        Mode::Array(_) | Mode::Dump => {
+            // a pipe or device streams, its lines are shown as they come
+            let live = config.input.capabilities().live;
+            if live && !config.line_buffered {
+                log::info(format_args!(
+                    "{} is a pipe or device, flushing every line",
+                    config.input.name()
+                ));
+            }
+            // Ctrl-C ends the input, the dump and summary are finished
+            interrupt::catch();
+            let flush_policy = match config.line_buffered || live {
+                true => FlushPolicy::Line,
+                false => FlushPolicy::Block,
+            };
+            let mut resume_at = None;
+            let mut pager = match &config.outfile {
+                Some(_) => None,
+                None => pager::spawn(config.paging)?,
+            };
+            let sink: Box<dyn Write> = match &config.outfile {
+                Some(path) if config.resume => {
+                    let array = matches!(config.mode, Mode::Array(_));
+                    let (file, point) = resume::reopen(path, array, config.columns, config.skip)?;
+                    log::trace(format_args!("resuming {} at {point:?}", path.display()));
+                    resume_at = Some(point);
+                    Box::new(file)
+                }
+                Some(path) => Box::new(File::create(path)?),
+                None => match pager.as_mut().and_then(|child| child.stdin.take()) {
+                    Some(stdin) => {
+                        log::trace("writing through the pager");
+                        Box::new(stdin)
+                    }
+                    None => Box::new(io::stdout().lock()),
+                },
+            };
+            let mut out = OutputWriter::new(safe_sink(config, sink), flush_policy);
+            let dumped = dump_inputs(config, &mut out, resume_at);
+            hits = out.hits();
+            // close the pager's input and let it finish before exiting
+            drop(out);
+            if let Some(mut child) = pager {
+                child.wait()?;
+            }
+            processed = dumped?;
         }
+    }
+    if let Some(session) = &config.session {
+        session::save(session)?;
+    }
+    // asked for with --timing it's reported like a summary
+    let level = match config.timing {
+        true => Level::Report,
+        false => Level::Info,
+    };
+    let elapsed = started.elapsed().as_secs_f64();
+    log::event(
+        level,
+        "timing",
+        &[
+            ("seconds", Field::Float(elapsed)),
+            ("bytes", Field::Number(processed)),
+        ],
+    );
+    if log::enabled(level) && !log::json() {
+        log::log(
+            level,
+            timing_report(started.elapsed(), processed, config.raw_sizes),
+        );
+    }
+    if interrupt::interrupted() {
+        return Err(HexError::Interrupted(format!(
+            "interrupted after {elapsed:.3}s, {} {} read",
+            config.format_size(processed),
+            config.tr(Msg::Bytes)
+        ))
+        .into());
+    }
+    // like grep, finding nothing is a failed run scripts can test for
+    if hits == Some(0) {
+        return Err(HexError::Mismatch("no matches found".to_string()).into());
+    }
+    Ok(())
+}
 
-        if let Some(color) = matches.get_one::<String>(ARG_CLR) {
-            colorize = color.parse::<u8>().unwrap() == 1;
-        }
+/// Report how the options resolved, shown with `-v`.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+fn log_options(config: &Config) {
+    if !log::enabled(Level::Info) {
+        return;
+    }
+    log::info(format_args!("mode: {:?}", config.mode));
+    log::info(format_args!("input: {}", config.input.name()));
+    let len = match config.truncate_len {
+        0 => "all".to_string(),
+        len => len.to_string(),
+    };
+    log::info(format_args!(
+        "layout: {} columns, skip {}, length {len}",
+        config.columns, config.skip
+    ));
+    log::info(format_args!(
+        "output: {} format to {}, paging {:?}",
+        config.output,
+        config
+            .outfile
+            .as_deref()
+            .map_or("stdout".into(), Path::to_string_lossy),
+        config.paging
+    ));
+}
 
-        if let Some(prefix_flag) = matches.get_one::<String>(ARG_PFX) {
-            prefix = prefix_flag.parse::<u8>().unwrap() == 1;
+/// Dump or print arrays of every input: the file list, the walked
+/// directory, or the single input, sampled or resumed as configured.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `out` - output writer.
+/// * `resume_at` - where an interrupted output file left off.
+fn dump_inputs<W: Write>(
+    config: &Config,
+    out: &mut OutputWriter<W>,
+    resume_at: Option<resume::ResumePoint>,
+) -> Result<u64, Box<dyn Error>> {
+    let paths = match (&config.files_from, &config.recursive) {
+        (Some(list), _) => read_file_list(list)?,
+        (None, Some(dir)) => {
+            let mut warn =
+                |path: &Path, e: io::Error| log::error(format_args!("{}: {e}", path.display()));
+            walk::walk(dir, &config.glob, &mut warn)
         }
-
-        // array output mode is mutually exclusive
-        if let Some(array) = matches.get_one::<String>(ARG_ARR) {
-            output_array(array, buf, truncate_len, column_width)?;
-        } else {
-            // Transforms this Read instance to an Iterator over its bytes.
-            // The returned type implements Iterator where the Item is
-            // Result<u8, R::Err>. The yielded item is Ok if a byte was
-            // successfully read and Err otherwise for I/O errors. EOF is
-            // mapped to returning None from this iterator.
-            // (https://doc.rust-lang.org/1.16.0/std/io/trait.Read.html#method.bytes)
-            let mut ascii_line: Line = Line::new();
-            let mut offset_counter: u64 = 0x0;
-            let mut byte_column: u64 = 0x0;
-            let page = buf_to_array(&mut buf, truncate_len, column_width)?;
-
-            let stdout = io::stdout();
-            let mut locked = stdout.lock();
-
-            for line in page.body.iter() {
-                print_offset(&mut locked, offset_counter)?;
-
-                for hex in line.hex_body.iter() {
-                    offset_counter += 1;
-                    byte_column += 1;
-                    print_byte(&mut locked, *hex, format_out, colorize, prefix)?;
-                    append_ascii(&mut ascii_line.ascii, *hex, colorize);
+        (None, None) => {
+            let sample = match config.sample {
+                Some(_) if config.input.capabilities().live => {
+                    log::warn(format_args!(
+                        "--sample needs the end of {}, a pipe or device, dumping it as it streams",
+                        config.input.name()
+                    ));
+                    None
                 }
-
-                if byte_column < column_width {
-                    write!(
-                        locked,
-                        "{:<1$}",
-                        "",
-                        5 * (column_width - byte_column) as usize
-                    )?;
+                sample => sample,
+            };
+            let processed = match (resume_at, sample) {
+                (Some(point), _) => {
+                    let mut buf = config.input.open()?;
+                    render_resumed(config, &mut buf, out, point)?
+                }
+                (None, Some(count)) => render_samples(config, count, out)?,
+                (None, None) if config.zst => render_zst(config, out)?,
+                (None, None) => {
+                    let mut buf = open_input(config, &config.input)?;
+                    if config.header {
+                        let head = buf.fill_buf()?.to_vec();
+                        write_header(out, config, &config.input, &head)?;
+                    }
+                    let bytes = render_input(config, &mut buf, out)?;
+                    if let Some(state) = &config.continue_state {
+                        state::save(state, config.skip + bytes)?;
+                    }
+                    bytes
                 }
+            };
+            return Ok(processed);
+        }
+    };
+    log::trace(format_args!("{} input files", paths.len()));
+    if paths.is_empty() {
+        return Ok(0);
+    }
+    let (processed, failures) = match config.dedupe_report {
+        true => write_dedupe_report(config, &paths, out)?,
+        false => render_files(config, &paths, out)?,
+    };
+    if failures > 0 {
+        return Err(format!("{failures} of {} inputs failed", paths.len()).into());
+    }
+    Ok(processed)
+}
+
+/// Render the inputs to memory and compare the output with the `--expect`
+/// file, failing with a unified diff on stdout when they differ. Like
+/// `render_to_string`, it's colored only when `--color` asks for it and
+/// the summary is in English unless `--lang` is set, so the expected file
+/// holds on every machine.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `path` - file holding the expected output.
+fn expect_output(config: &Config, path: &str) -> Result<u64, Box<dyn Error>> {
+    fits_in_memory(config, Path::new(path))?;
+    let expected = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let config = Config {
+        color: Some(config.color.unwrap_or(false)),
+        lang: Some(config.lang.clone().unwrap_or_else(|| "en".to_string())),
+        ..config.clone()
+    };
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Block);
+    let processed = dump_inputs(&config, &mut out, None)?;
+    let actual = String::from_utf8_lossy(&out.into_inner()?).into_owned();
+    let table = textdiff::table_bytes(&expected, &actual);
+    if config.memory_limit.is_some_and(|limit| table > limit) {
+        return Err(over_limit(&config, "diffing the output with the expected one").into());
+    }
+    if let Some(diff) = textdiff::unified(&expected, &actual, path, "hx output") {
+        print!("{diff}");
+        return Err(format!("output differs from {path}").into());
+    }
+    Ok(processed)
+}
 
-                locked.write_all(ascii_line.ascii.as_slice())?;
-                writeln!(locked)?;
+/// Error for a feature that would hold more than `--memory-limit` in
+/// memory.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, with the limit.
+/// * `what` - what needs the memory, e.g. `the input`.
+fn over_limit(config: &Config, what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::OutOfMemory,
+        format!(
+            "{what} needs more than the --memory-limit of {}",
+            config.format_size(config.memory_limit.unwrap_or_default())
+        ),
+    )
+}
 
-                byte_column = 0x0;
-                ascii_line = Line::new();
-            }
-            if true {
-                writeln!(locked, "   bytes: {}", page.bytes)?;
+/// Read the input into memory for the features that need all of it at
+/// once, failing instead of holding more than `--memory-limit` allows.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, with the limit.
+/// * `buf` - input to be read.
+/// * `len` - bytes to read, 0 for the rest of the input.
+fn read_input(config: &Config, buf: &mut dyn Read, len: u64) -> io::Result<Vec<u8>> {
+    let want = match len {
+        0 => u64::MAX,
+        len => len,
+    };
+    let mut data = Vec::new();
+    match config.memory_limit {
+        // one byte over the limit tells the input doesn't fit
+        Some(limit) if want > limit => {
+            buf.take(limit + 1).read_to_end(&mut data)?;
+            if data.len() as u64 > limit {
+                return Err(over_limit(config, "the input"));
             }
         }
+        _ => {
+            buf.take(want).read_to_end(&mut data)?;
+        }
     }
-    Ok(())
+    Ok(data)
 }
 
-/// Detect stdin, file path and/or parameters.
+/// Fail when a file is larger than `--memory-limit` allows to read whole.
+///
 /// # Arguments
 ///
-/// * `matches` - argument matches.
-#[allow(clippy::absurd_extreme_comparisons)]
-pub fn is_stdin(matches: &ArgMatches) -> bool {
-    if let Some(file) = matches.get_one::<String>(ARG_INP) {
-        if DBG {
-            dbg!(file);
+/// * `config` - parsed command line, with the limit.
+/// * `path` - file to be read.
+fn fits_in_memory(config: &Config, path: &Path) -> io::Result<()> {
+    match config.memory_limit {
+        Some(limit) if fs::metadata(path)?.len() > limit => {
+            Err(over_limit(config, &path.display().to_string()))
         }
+        _ => Ok(()),
+    }
+}
 
-        return false;
-    } else if let Some(nth1) = env::args().nth(1) {
-        if DBG {
-            dbg!(nth1);
-        }
+/// Read a whole file into memory, failing before reading it when it's
+/// larger than `--memory-limit` allows.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, with the limit.
+/// * `path` - file to read.
+fn read_file(config: &Config, path: &Path) -> io::Result<Vec<u8>> {
+    fits_in_memory(config, path)?;
+    fs::read(path)
+}
 
-        return ARGS.iter().any(|arg| matches.index_of(arg) == Some(2));
-    } else if !matches.args_present() {
-        return true;
+/// Open an input to dump: read ahead, or around the page cache with
+/// `--direct-io`.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `source` - input to open.
+fn open_input(config: &Config, source: &InputSource) -> io::Result<Input> {
+    let name = source.name();
+    match config.direct_io {
+        true => {
+            log::trace(format_args!("reading {name} with O_DIRECT"));
+            source.open_direct()
+        }
+        false => {
+            log::trace(format_args!(
+                "reading {name} with {} byte read-ahead blocks",
+                config.readahead
+            ));
+            Ok(source.open()?.read_ahead(config.readahead))
+        }
     }
-
-    false
 }
 
-/// Output source code array format.
+/// Write a scalar into a file, printing the dump lines it touches before
+/// and after the change.
+///
 /// # Arguments
 ///
-/// * `array_format` - array format, rust (r), C (c), golang (g).
-/// * `buf` - BufRead.
-/// * `truncate_len` - truncate to length.
-/// * `column_width` - column width.
-pub fn output_array(
-    array_format: &str,
-    mut buf: Box<dyn BufRead>,
-    truncate_len: u64,
-    column_width: u64,
+/// * `config` - parsed command line, for the dump layout.
+/// * `path` - file to patch.
+/// * `spec` - where and how to write.
+/// * `value` - value bits.
+/// * `guard` - backup and replacement behavior.
+fn poke(
+    config: &Config,
+    path: &Path,
+    spec: &ScalarSpec,
+    value: u64,
+    guard: &WriteGuard,
 ) -> io::Result<()> {
-    let stdout = io::stdout();
-    let mut locked = stdout.lock();
+    let bytes = scalar::encode(value, spec.kind.width(), spec.endian);
+    show_patch(config, path, &[(spec.at, bytes)], guard)
+}
 
-    let page = buf_to_array(&mut buf, truncate_len, column_width).unwrap();
-    match array_format {
-        "r" => writeln!(locked, "let ARRAY: [u8; {}] = [", page.bytes)?,
-        "c" => writeln!(locked, "unsigned char ARRAY[{}] = {{", page.bytes)?,
-        "g" => writeln!(locked, "a := [{}]byte{{", page.bytes)?,
-        "p" => writeln!(locked, "a = [")?,
-        "k" => writeln!(locked, "val a = byteArrayOf(")?,
-        "j" => writeln!(locked, "byte[] a = new byte[]{{")?,
-        "s" => writeln!(locked, "let a: [UInt8] = [")?,
-        "f" => writeln!(locked, "let a = [|")?,
-        _ => writeln!(locked, "unknown array format")?,
-    }
-    let mut i: u64 = 0x0;
-    for line in page.body.iter() {
-        write!(locked, "    ")?;
-        for hex in line.hex_body.iter() {
-            i += 1;
-            if i == page.bytes && array_format != "g" {
-                if array_format != "f" {
-                    write!(locked, "{}", Format::LowerHex.format(*hex, true))?;
-                } else {
-                    write!(locked, "{}uy", Format::LowerHex.format(*hex, true))?;
-                }
-            } else if array_format != "f" {
-                write!(locked, "{}, ", Format::LowerHex.format(*hex, true))?;
-            } else {
-                write!(locked, "{}uy; ", Format::LowerHex.format(*hex, true))?;
-            }
+/// Copy a region of a file to another offset in it, overlapping or not,
+/// printing the dump lines it touches before and after the change. The
+/// whole region is read before anything is written, so an overlapping
+/// copy moves the original bytes.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, for the dump layout.
+/// * `path` - file to change.
+/// * `region` - offset and length of the bytes to copy.
+/// * `to` - offset to copy them to.
+/// * `clear` - byte to overwrite the source bytes the copy doesn't cover
+///   with, which makes it a move, None leaves them.
+/// * `guard` - backup and replacement behavior.
+fn move_region(
+    config: &Config,
+    path: &Path,
+    (from, len): (u64, u64),
+    to: u64,
+    clear: Option<u8>,
+    guard: &WriteGuard,
+) -> io::Result<()> {
+    let file_len = std::fs::metadata(path)?.len();
+    for at in [from, to] {
+        if at.checked_add(len).is_none_or(|end| end > file_len) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "region of {len} bytes at {} runs past the end of the file ({file_len} bytes)",
+                    offset(at)
+                ),
+            ));
         }
-        writeln!(locked)?;
     }
+    let bytes = patch::read_at(&mut File::open(path)?, from, len as usize)?;
+    let edits = match clear {
+        None => vec![(to, bytes)],
+        // overlapping or touching regions are one edit, so one preview
+        Some(fill) if from.max(to) <= (from + len).min(to + len) => {
+            let start = from.min(to);
+            let mut span = vec![fill; ((from.max(to) + len) - start) as usize];
+            span[(to - start) as usize..][..len as usize].copy_from_slice(&bytes);
+            vec![(start, span)]
+        }
+        Some(fill) => vec![(from, vec![fill; len as usize]), (to, bytes)],
+    };
+    show_patch(config, path, &edits, guard)
+}
 
+/// Truncate or extend a file to a size, filling what it grows by with a
+/// pattern repeated from offset 0, so it lines up however long the file
+/// was.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, for the sizes.
+/// * `path` - file to resize.
+/// * `size` - new length.
+/// * `fill` - pattern, at least one byte when the file grows.
+/// * `guard` - backup, replacement, journal and confirmation behavior.
+fn resize(
+    config: &Config,
+    path: &Path,
+    size: u64,
+    fill: &[u8],
+    guard: &WriteGuard,
+) -> io::Result<()> {
+    let len = std::fs::metadata(path)?.len();
+    patch::resize(path, size, fill, guard)?;
+    log::event(
+        Level::Report,
+        "resize",
+        &[
+            ("file", Field::Text(&path.to_string_lossy())),
+            ("from", Field::Number(len)),
+            ("to", Field::Number(size)),
+        ],
+    );
+    let mut out = OutputWriter::new(report_sink(config), FlushPolicy::Block);
     writeln!(
-        locked,
-        "{}",
-        match array_format {
-            "r" => "];",
-            "c" | "j" => "};",
-            "g" => "}",
-            "p" => "]",
-            "k" => ")",
-            "s" => "]",
-            "f" => "|]",
-            _ => "unknown array format",
-        }
-    )
+        out,
+        "{}: {} -> {} bytes",
+        path.display(),
+        config.format_size(len),
+        config.format_size(size)
+    )?;
+    out.flush()
+}
+
+/// Check a file against a pattern under a mask, printing the lines of
+/// all three around the first mismatch and failing when there is one.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, for the dump layout.
+/// * `path` - file to check.
+/// * `pattern` - file of expected bytes.
+/// * `mask` - file of bits to compare, every bit when None.
+fn match_pattern(
+    config: &Config,
+    path: &Path,
+    pattern: &Path,
+    mask: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let data = read_file(config, path)?;
+    let expected = read_file(config, pattern)?;
+    let bits = match mask {
+        Some(mask) => read_file(config, mask)?,
+        None => Vec::new(),
+    };
+    let (path, pattern) = (path.to_string_lossy(), pattern.to_string_lossy());
+    let (path, pattern) = (path.as_ref(), pattern.as_ref());
+    let mut out = OutputWriter::new(report_sink(config), FlushPolicy::Block);
+    let Some(at) = masked::first_mismatch(&data, &expected, &bits) else {
+        let ignored = bits
+            .iter()
+            .take(expected.len())
+            .filter(|b| **b == 0)
+            .count();
+        log::event(
+            Level::Report,
+            "match",
+            &[
+                ("file", Field::Text(path)),
+                ("pattern", Field::Text(pattern)),
+                ("compared", Field::Number(expected.len() as u64)),
+                ("masked", Field::Number(ignored as u64)),
+            ],
+        );
+        writeln!(
+            out,
+            "{path} matches {pattern}: {} bytes compared, {} masked out",
+            config.format_size(expected.len() as u64),
+            config.format_size(ignored as u64)
+        )?;
+        out.flush()?;
+        return Ok(());
+    };
+    let bit = bits.get(at).copied().unwrap_or(0xff);
+    let mut fields = vec![
+        ("file", Field::Text(path)),
+        ("pattern", Field::Text(pattern)),
+        ("at", Field::Number(at as u64)),
+        ("expected", Field::Number(u64::from(expected[at]))),
+        ("mask", Field::Number(u64::from(bit))),
+    ];
+    if let Some(b) = data.get(at) {
+        fields.push(("found", Field::Number(u64::from(*b))));
+    }
+    log::event(Level::Report, "mismatch", &fields);
+    match data.get(at) {
+        Some(b) => writeln!(
+            out,
+            "mismatch at {}: {b:#04x} where {pattern} has {:#04x} under mask {bit:#04x}",
+            offset(at as u64),
+            expected[at]
+        )?,
+        None => writeln!(
+            out,
+            "mismatch at {}: {path} ends, {pattern} is {} long",
+            offset(at as u64),
+            config.format_size(expected.len() as u64)
+        )?,
+    }
+    let opts = dump_options(config);
+    let columns = opts.column_width.max(1) as usize;
+    let start = at - at % columns;
+    let mut rows = vec![("file", &data), ("pattern", &expected)];
+    if mask.is_some() {
+        rows.push(("mask", &bits));
+    }
+    for (label, bytes) in rows {
+        let chunk = bytes.get(start..).unwrap_or_default();
+        let chunk = &chunk[..chunk.len().min(columns)];
+        write!(out, "{label:>8} ")?;
+        let mut line = Line::new();
+        line.offset = start as u64;
+        line.bytes = chunk.len() as u64;
+        line.hex_body = chunk.to_vec();
+        write_line(&mut out, &line, 0, &opts)?;
+    }
+    out.flush()?;
+    Err(HexError::Mismatch(format!("{path} doesn't match {pattern}")).into())
 }
 
-/// Function wave out.
+/// Compare two files byte by byte, printing every region that changed,
+/// moved, was added or removed, and failing when there is one.
+///
 /// # Arguments
 ///
-/// * `len` - Wave length.
-/// * `places` - Number of decimal places for function wave floats.
-pub fn output_function(len: u64, places: usize) {
-    for y in 0..len {
-        let y_float = y as f64;
-        let len_float = len as f64;
-        let x = (((y_float / len_float) * f64::consts::PI) / 2.0).sin();
-        let formatted_number = format!("{:.*}", places, x);
-        print!("{}", formatted_number);
-        print!(",");
-        if (y % 10) == 9 {
-            println!();
+/// * `config` - parsed command line, for the sizes and colors.
+/// * `old` - file compared against.
+/// * `new` - file compared with it.
+/// * `block` - shortest move, in bytes, 0 to look for none.
+/// * `aligned` - align the files across insertions and deletions, rather
+///   than compare the bytes at the same offset.
+/// * `checksums` - add the CRC-32 of the bytes of every region.
+fn diff_files(
+    config: &Config,
+    old: &Path,
+    new: &Path,
+    block: usize,
+    aligned: bool,
+    checksums: bool,
+) -> Result<(), Box<dyn Error>> {
+    let old_data = read_file(config, old)?;
+    let new_data = read_file(config, new)?;
+    let (old, new) = (old.to_string_lossy(), new.to_string_lossy());
+    let (old, new) = (old.as_ref(), new.as_ref());
+    let changes = match aligned {
+        true => bindiff::diff_aligned(&old_data, &new_data, block),
+        false => bindiff::diff_naive(&old_data, &new_data, block),
+    };
+    let colorize = dump_options(config).colorize;
+    let mut out = OutputWriter::new(report_sink(config), FlushPolicy::Block);
+    // bytes changed in place, moved, added and removed
+    let mut totals = [0u64; 4];
+    for change in &changes {
+        let len = change.len();
+        let crc =
+            |data: &[u8], at: u64, len: u64| hash::crc32(&data[at as usize..(at + len) as usize]);
+        let (kind, total, text, crcs) = match *change {
+            Change::Changed { at, .. } => (
+                "changed",
+                0,
+                format!("at {}", offset(at)),
+                (Some(crc(&old_data, at, len)), Some(crc(&new_data, at, len))),
+            ),
+            Change::Replaced {
+                from, old_len, to, ..
+            } => (
+                "replaced",
+                0,
+                format!(
+                    "at {} with {} at {}",
+                    offset(from),
+                    config.format_size(len),
+                    offset(to)
+                ),
+                (
+                    Some(crc(&old_data, from, old_len)),
+                    Some(crc(&new_data, to, len)),
+                ),
+            ),
+            Change::Moved { from, to, .. } => (
+                "moved",
+                1,
+                format!("from {} to {}", offset(from), offset(to)),
+                (Some(crc(&old_data, from, len)), None),
+            ),
+            Change::Added { at, .. } => (
+                "added",
+                2,
+                format!("at {}", offset(at)),
+                (None, Some(crc(&new_data, at, len))),
+            ),
+            Change::Inserted { at, old_at, .. } => (
+                "inserted",
+                2,
+                format!("at {} (old {})", offset(at), offset(old_at)),
+                (None, Some(crc(&new_data, at, len))),
+            ),
+            Change::Removed { at, .. } => (
+                "removed",
+                3,
+                format!("at {}", offset(at)),
+                (Some(crc(&old_data, at, len)), None),
+            ),
+            Change::Deleted { at, new_at, .. } => (
+                "deleted",
+                3,
+                format!("at {} (new {})", offset(at), offset(new_at)),
+                (Some(crc(&old_data, at, len)), None),
+            ),
+        };
+        // the old length is the one written for a replacement
+        let len = match *change {
+            Change::Replaced { old_len, .. } => old_len,
+            _ => len,
+        };
+        totals[total] += change.len();
+        let mut fields = vec![("change", Field::Text(kind)), ("len", Field::Number(len))];
+        match *change {
+            Change::Moved { from, to, .. } => {
+                fields.extend([("from", Field::Number(from)), ("to", Field::Number(to))])
+            }
+            Change::Replaced {
+                from,
+                to,
+                len: new_len,
+                ..
+            } => fields.extend([
+                ("from", Field::Number(from)),
+                ("to", Field::Number(to)),
+                ("new_len", Field::Number(new_len)),
+            ]),
+            Change::Inserted { at, old_at, .. } => {
+                fields.extend([("at", Field::Number(at)), ("old_at", Field::Number(old_at))])
+            }
+            Change::Deleted { at, new_at, .. } => {
+                fields.extend([("at", Field::Number(at)), ("new_at", Field::Number(new_at))])
+            }
+            _ => fields.push(("at", Field::Number(change.new_at()))),
+        }
+        let crc_text: Vec<String> = [crcs.0, crcs.1]
+            .iter()
+            .flatten()
+            .map(|crc| format!("{crc:08x}"))
+            .collect();
+        if checksums {
+            if let Some(crc) = crcs.0 {
+                fields.push(("old_crc32", Field::Number(u64::from(crc))));
+            }
+            if let Some(crc) = crcs.1 {
+                fields.push(("new_crc32", Field::Number(u64::from(crc))));
+            }
+        }
+        log::event(Level::Report, "change", &fields);
+        let label = match (colorize, kind) {
+            (false, _) => kind.to_string(),
+            (true, "changed" | "replaced") => Color::Yellow.paint(kind).to_string(),
+            (true, "moved") => Color::Cyan.paint(kind).to_string(),
+            (true, "added" | "inserted") => Color::Green.paint(kind).to_string(),
+            (true, _) => Color::Red.paint(kind).to_string(),
+        };
+        write!(out, "{label} {} bytes {text}", config.format_size(len))?;
+        match checksums {
+            true => writeln!(out, ", crc32 {}", crc_text.join(" to "))?,
+            false => writeln!(out)?,
         }
     }
-    println!();
+    log::event(
+        Level::Report,
+        "diff",
+        &[
+            ("old", Field::Text(old)),
+            ("new", Field::Text(new)),
+            ("changes", Field::Number(changes.len() as u64)),
+            ("changed", Field::Number(totals[0])),
+            ("moved", Field::Number(totals[1])),
+            ("added", Field::Number(totals[2])),
+            ("removed", Field::Number(totals[3])),
+        ],
+    );
+    if changes.is_empty() {
+        writeln!(out, "{old} and {new} are identical")?;
+        out.flush()?;
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "{:>8}: {}, {} bytes changed, {} moved, {} added, {} removed",
+        "changes",
+        changes.len(),
+        config.format_size(totals[0]),
+        config.format_size(totals[1]),
+        config.format_size(totals[2]),
+        config.format_size(totals[3])
+    )?;
+    out.flush()?;
+    Err(HexError::Mismatch(format!("{old} and {new} differ")).into())
 }
 
-/// Buffer to array.
+/// Where a verify or patching mode writes its report: stdout, or nowhere with
+/// `--quiet`, leaving only the exit status, or JSON log records.
 ///
 /// # Arguments
 ///
-/// * `buf` - Buffer to be read.
-/// * `buf_len` - force buffer length.
-/// * `column_width` - column width for output.
-pub fn buf_to_array(
-    buf: &mut dyn Read,
-    buf_len: u64,
-    column_width: u64,
-) -> Result<Page, Box<dyn ::std::error::Error>> {
-    let mut column_count = 0u64;
-    let max_array_size = u16::MAX; // 2^16;
-    let mut page: Page = Page::new();
-    let mut line: Line = Line::new();
-    for b in buf.bytes() {
-        let b1: u8 = b?;
-        line.bytes += 1;
-        page.bytes += 1;
-        line.hex_body.push(b1);
-        column_count += 1;
-
-        if column_count >= column_width {
-            page.body.push(line);
-            line = Line::new();
-            column_count = 0;
-        }
-
-        if buf_len > 0 && (page.bytes == buf_len || u64::from(max_array_size) == buf_len) {
-            break;
+/// * `config` - parsed command line.
+fn report_sink(config: &Config) -> Box<dyn Write> {
+    match config.log_level == Level::Error || log::json() {
+        true => Box::new(io::sink()),
+        false => safe_sink(config, Box::new(io::stdout().lock())),
+    }
+}
+
+/// Output with basic colors and ascii alone under `--profile safe`, the
+/// output itself otherwise.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `sink` - output to write to.
+fn safe_sink(config: &Config, sink: Box<dyn Write>) -> Box<dyn Write> {
+    match config.safe {
+        true => Box::new(safe::SafeWriter::new(sink)),
+        false => sink,
+    }
+}
+
+/// Check a file against a layout spec, printing a pass or fail line for
+/// every check and the dump line around each failure.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, for the dump layout and colors.
+/// * `path` - file to check.
+/// * `spec` - layout spec file.
+fn check_spec(config: &Config, path: &Path, spec: &Path) -> Result<(), Box<dyn Error>> {
+    let data = read_file(config, path)?;
+    let checks = spec::parse(&std::fs::read_to_string(spec)?, data.len() as u64)
+        .map_err(|reason| format!("{}: {reason}", spec.display()))?;
+    let (path, spec) = (path.to_string_lossy(), spec.display());
+    let path = path.as_ref();
+    let opts = dump_options(config);
+    let columns = opts.column_width.max(1) as usize;
+    let mut out = OutputWriter::new(report_sink(config), FlushPolicy::Block);
+    let mut failed: u64 = 0;
+    for check in &checks {
+        let outcome = spec::evaluate(check, &data);
+        log::event(
+            Level::Report,
+            "check",
+            &[
+                ("file", Field::Text(path)),
+                ("name", Field::Text(&check.name)),
+                ("at", Field::Number(check.at)),
+                ("pass", Field::Flag(outcome.pass)),
+                ("found", Field::Text(&outcome.found)),
+                ("expected", Field::Text(&outcome.expected)),
+            ],
+        );
+        let (verdict, color) = match outcome.pass {
+            true => ("pass", Color::Green),
+            false => ("FAIL", Color::Red),
+        };
+        let verdict = match opts.colorize {
+            true => color.bold().paint(verdict).to_string(),
+            false => verdict.to_string(),
+        };
+        write!(
+            out,
+            "{verdict} {}: {} {}",
+            check.name,
+            offset(check.at),
+            outcome.found
+        )?;
+        if outcome.pass {
+            writeln!(out)?;
+            continue;
         }
+        failed += 1;
+        writeln!(out, ", expected {}", outcome.expected)?;
+        // whole dump lines around the checked bytes
+        let at = (check.at as usize).min(data.len());
+        let start = at - at % columns;
+        let end = (at + outcome.len.max(1)).div_ceil(columns) * columns;
+        let excerpt = data.get(start..end.min(data.len())).unwrap_or_default();
+        for (i, row) in excerpt.chunks(columns).enumerate() {
+            write!(out, "{:>8} ", "")?;
+            let mut line = Line::new();
+            line.offset = (start + i * columns) as u64;
+            line.bytes = row.len() as u64;
+            line.hex_body = row.to_vec();
+            write_line(&mut out, &line, 0, &opts)?;
+        }
+    }
+    log::event(
+        Level::Report,
+        "checks",
+        &[
+            ("file", Field::Text(path)),
+            ("checks", Field::Number(checks.len() as u64)),
+            ("failed", Field::Number(failed)),
+        ],
+    );
+    writeln!(out, "{:>8}: {}, {failed} failed", "checks", checks.len())?;
+    out.flush()?;
+    match failed {
+        0 => Ok(()),
+        _ => Err(HexError::Mismatch(format!("{path} doesn't match {spec}")).into()),
     }
-    page.body.push(line);
-    Ok(page)
+}
+
+/// Patch a file, printing the dump lines each edit touches before and
+/// after the change.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, for the dump layout.
+/// * `path` - file to patch.
+/// * `edits` - offsets and replacement bytes.
+/// * `guard` - backup and replacement behavior.
+fn show_patch(
+    config: &Config,
+    path: &Path,
+    edits: &[(u64, Vec<u8>)],
+    guard: &WriteGuard,
+) -> io::Result<()> {
+    patch::check_fits(path, edits)?;
+    let opts = dump_options(config);
+    let columns = opts.column_width.max(1);
+    // whole lines around the patched bytes
+    let windows: Vec<(u64, usize)> = edits
+        .iter()
+        .map(|(at, bytes)| {
+            let start = at - at % columns;
+            let end = (at + bytes.len() as u64).div_ceil(columns) * columns;
+            (start, (end - start) as usize)
+        })
+        .collect();
+    let read_windows = || -> io::Result<Vec<Vec<u8>>> {
+        let mut file = File::open(path)?;
+        windows
+            .iter()
+            .map(|(start, len)| patch::read_at(&mut file, *start, *len))
+            .collect()
+    };
+
+    let before = read_windows()?;
+    patch::apply(path, edits, guard)?;
+    let after = read_windows()?;
+
+    log::event(
+        Level::Report,
+        "patch",
+        &[
+            ("file", Field::Text(&path.to_string_lossy())),
+            ("edits", Field::Number(edits.len() as u64)),
+            (
+                "bytes",
+                Field::Number(edits.iter().map(|(_, bytes)| bytes.len() as u64).sum()),
+            ),
+        ],
+    );
+    let mut out = OutputWriter::new(report_sink(config), FlushPolicy::Block);
+    for (i, (start, _)) in windows.iter().enumerate() {
+        for (label, data) in [("before", &before[i]), ("after", &after[i])] {
+            for (row, chunk) in data.chunks(columns as usize).enumerate() {
+                write!(out, "{label:>8} ")?;
+                let mut line = Line::new();
+                line.offset = start + row as u64 * columns;
+                line.bytes = chunk.len() as u64;
+                line.hex_body = chunk.to_vec();
+                write_line(&mut out, &line, 0, &opts)?;
+            }
+        }
+    }
+    out.flush()
+}
+
+/// Render one input as configured, dump with summary or source code array.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `base` - offset of the first input byte, e.g. the address of a decoded image.
+fn render<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    base: u64,
+) -> io::Result<u64> {
+    let mut stoppable = Interruptible::new(buf);
+    let buf: &mut dyn Read = &mut stoppable;
+    let mut lane;
+    let buf: &mut dyn Read = match config.lane {
+        Some((lanes, keep)) => {
+            lane = Deinterleave::new(buf, lanes, keep);
+            &mut lane
+        }
+        None => buf,
+    };
+    let mut transformed;
+    let buf: &mut dyn Read = match config.transforms.is_empty() {
+        true => buf,
+        false => {
+            transformed = TransformReader::new(buf, &config.transforms);
+            &mut transformed
+        }
+    };
+    let skipped = base + discard(buf, config.skip)?;
+    match &config.mode {
+        // array output mode is mutually exclusive
+        Mode::Array(array) => {
+            let opts = DumpOptions {
+                align: false,
+                ..dump_options(config)
+            };
+            let bytes = emit_also(config, "array", buf, out, &opts)?;
+            if let Some(more) = truncated(config, buf, skipped + bytes)? {
+                let comment = match array.as_str() {
+                    "p" => "#",
+                    _ => "//",
+                };
+                writeln!(out, "{comment} truncated, {more}")?;
+            }
+            Ok(bytes)
+        }
+        _ if !config.ranges.is_empty() => render_ranges(config, buf, out),
+        _ if config.carve_auto.is_some() => render_carve_auto(config, buf, out, skipped),
+        _ if config.dedupe_scan.is_some() => render_dedupe_scan(config, buf, out, skipped),
+        _ if config.rle.is_some() => render_rle(config, buf, out, skipped),
+        _ if config.detect_encoding => render_encoding(config, buf, out, skipped),
+        _ if config.text_report => render_text_report(config, buf, out),
+        _ if config.count_matches => {
+            let pattern = config.find.as_deref().unwrap_or_default();
+            let finder = search::Finder::new(pattern);
+            let (matches, bytes) = match config.truncate_len {
+                0 => finder.count(buf)?,
+                len => finder.count(&mut buf.take(len))?,
+            };
+            writeln!(out, "{matches}")?;
+            out.record_hits(matches);
+            Ok(bytes)
+        }
+        _ if config.find.is_some()
+            || config.find_int.is_some()
+            || config.find_float.is_some()
+            || config.pointer_scan.is_some() =>
+        {
+            render_hits(config, buf, out, skipped)
+        }
+        _ if config.split_on.is_some() => render_fields(config, buf, out, skipped),
+        _ if config.frame.is_some() => render_frames(config, buf, out, skipped),
+        Mode::Dump if config.decode.is_some() => render_decoded(config, buf, out, skipped),
+        _ if config.unique_lines => {
+            let opts = DumpOptions {
+                start_offset: skipped,
+                align: false,
+                ..dump_options(config)
+            };
+            render_unique_lines(config, buf, out, &opts)
+        }
+        _ => {
+            let opts = DumpOptions {
+                start_offset: skipped,
+                // a line of transposed output is a row of whole records
+                column_width: config.columns * config.transpose.unwrap_or(1),
+                ..dump_options(config)
+            };
+            let bytes = emit_also(config, &config.output, buf, out, &opts)?;
+            if let Some(more) = truncated(config, buf, skipped + bytes)? {
+                writeln!(out, "... truncated, {more} ...")?;
+            }
+            // other formats end themselves, fragments are left open
+            if config.output == "dump" && !config.fragment {
+                write_summary(out, config, bytes)?;
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// What `--mark-truncated` says follows a dump cut short by `--len`: the
+/// number of bytes left when the input length is known, else that more
+/// follow. None when the dump wasn't cut short, or the marker is off.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input, positioned after the dumped bytes.
+/// * `end` - input offset after the last dumped byte.
+fn truncated(config: &Config, buf: &mut dyn Read, end: u64) -> io::Result<Option<String>> {
+    if !config.mark_truncated || config.truncate_len == 0 {
+        return Ok(None);
+    }
+    // one more byte tells whether anything was left out, even on a pipe
+    if buf.read(&mut [0])? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(match config.input_len() {
+        Some(len) if len > end => format!("{} more bytes", config.format_size(len - end)),
+        _ => "more bytes follow".to_string(),
+    }))
+}
+
+/// Write an input through the output format registered under `name`.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, holding the format registry.
+/// * `name` - name of the output format.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `opts` - dump layout and styling.
+fn emit_format<W: Write>(
+    config: &Config,
+    name: &str,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    opts: &DumpOptions,
+) -> io::Result<u64> {
+    let constructor = config
+        .formats
+        .get(name)
+        .ok_or_else(|| io::Error::other(format!("unknown output format {name}")))?;
+    emit(out, constructor(config, *opts).as_mut(), buf, opts)
+}
+
+/// Write the input in a format like `emit_format`, and in every `--also`
+/// format from the same read. Extra outputs are uncolored unless `--color`
+/// asks, and `/dev/stderr` is written without opening it.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, with the extra outputs.
+/// * `name` - name of the format of the output itself.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `opts` - dump layout.
+fn emit_also<W: Write>(
+    config: &Config,
+    name: &str,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    opts: &DumpOptions,
+) -> io::Result<u64> {
+    if config.also.is_empty() {
+        return emit_format(config, name, buf, out, opts);
+    }
+    let constructor = |name: &str| {
+        config
+            .formats
+            .get(name)
+            .ok_or_else(|| io::Error::other(format!("unknown output format {name}")))
+    };
+    let also_opts = DumpOptions {
+        colorize: config.color.unwrap_or(false),
+        ..*opts
+    };
+    let mut also = Vec::with_capacity(config.also.len());
+    for (name, path) in &config.also {
+        let writer: Box<dyn Write> = match path.as_str() {
+            "/dev/stderr" => Box::new(io::stderr()),
+            path => Box::new(BufWriter::new(File::create(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("--also can't create {path}: {e}"))
+            })?)),
+        };
+        also.push(Also {
+            name: name.clone(),
+            format: constructor(name)?(config, also_opts),
+            out: writer,
+        });
+    }
+    let primary = constructor(name)?(config, *opts);
+    emit(out, &mut TeeFormat::new(config, primary, also), buf, opts)
+}
+
+/// Dump only the configured byte ranges, reading forward from one to the
+/// next, with a single summary for all of them. With `--carve` each range
+/// is also written to its file, cut short where the input ends.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read from the start.
+/// * `out` - output writer.
+fn render_ranges<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let mut position = 0;
+    let mut bytes = 0;
+    let mut carved = 0;
+    for (i, (start, end)) in config.ranges.iter().enumerate() {
+        position += discard(buf, start - position)?;
+        if position < *start {
+            break;
+        }
+        let opts = DumpOptions {
+            start_offset: *start,
+            truncate_len: end - start,
+            align: false,
+            ..dump_options(config)
+        };
+        let dumped = match config.carve.get(i) {
+            Some(path) => {
+                let data = read_input(config, buf, end - start)?;
+                std::fs::write(path, &data)?;
+                carved += 1;
+                writeln!(
+                    out,
+                    "carved {}: {}, {} bytes",
+                    path.display(),
+                    offset(opts.banks.translate(*start)),
+                    config.format_size(data.len() as u64)
+                )?;
+                out.line_done()?;
+                output_dump(out, &mut data.as_slice(), &opts)?
+            }
+            None => output_dump(out, buf, &opts)?,
+        };
+        position += dumped;
+        bytes += dumped;
+        if dumped < end - start {
+            break;
+        }
+    }
+    if !config.fragment {
+        write_summary(out, config, bytes)?;
+        if !config.carve.is_empty() {
+            writeln!(out, "{:>8}: {carved}", "carved")?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Print every distinct line of the input once, prefixed by its number of
+/// occurrences and at the offset it first appears, most frequent first.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `opts` - dump layout and styling.
+fn render_unique_lines<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    opts: &DumpOptions,
+) -> io::Result<u64> {
+    let (patterns, lines, bytes) = dedupe::unique_lines(
+        buf,
+        opts.column_width,
+        opts.truncate_len,
+        opts.start_offset,
+        config.memory_limit,
+    )
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::OutOfMemory => over_limit(config, "--unique-lines"),
+        _ => e,
+    })?;
+    for pattern in patterns.iter() {
+        write!(out, "{:>7}x ", pattern.count)?;
+        let mut line = Line::new();
+        line.offset = pattern.first;
+        line.bytes = pattern.bytes.len() as u64;
+        line.hex_body.clone_from(&pattern.bytes);
+        write_line(out, &line, 0, opts)?;
+        out.line_done()?;
+    }
+    write_summary(out, config, bytes)?;
+    writeln!(out, "{:>8}: {} of {lines} lines", "unique", patterns.len())?;
+    out.flush()?;
+    Ok(bytes)
+}
+
+/// Report ranges of the input which repeat elsewhere in it.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read, held in memory for the scan.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_dedupe_scan<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, config.truncate_len)?;
+    let block = config.dedupe_scan.unwrap_or_default() as usize;
+    let found = dedupe::dedupe_scan(&data, block);
+    for duplicate in found.iter() {
+        let start = start_offset + duplicate.offset;
+        let source = start_offset + duplicate.source;
+        writeln!(
+            out,
+            "{}-{}: {} bytes, same as {}-{}",
+            offset(start),
+            offset(start + duplicate.len - 1),
+            config.format_size(duplicate.len),
+            offset(source),
+            offset(source + duplicate.len - 1)
+        )?;
+    }
+    let repeated = found.iter().map(|duplicate| duplicate.len).sum();
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(
+        out,
+        "{:>8}: {} ranges, {} bytes",
+        "repeats",
+        found.len(),
+        config.format_size(repeated)
+    )?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// Extract the files embedded in the input into the `--carve-auto`
+/// directory, named after their offsets, and list them.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read, held in memory for the scan.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_carve_auto<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, config.truncate_len)?;
+    let dir = config.carve_auto.as_deref().unwrap_or(Path::new(""));
+    std::fs::create_dir_all(dir)?;
+    let found = carve::scan(&data);
+    for file in found.iter() {
+        let start = start_offset + file.offset as u64;
+        let path = dir.join(format!("{start:08x}.{}", file.ext));
+        std::fs::write(&path, &data[file.offset..file.offset + file.len])?;
+        let note = match file.end {
+            carve::End::Found => "",
+            carve::End::CutOff => ", cut off",
+            carve::End::Unknown => ", end unknown",
+        };
+        writeln!(
+            out,
+            "{}: {}, {} bytes{note} -> {}",
+            offset(start),
+            file.kind,
+            config.format_size(file.len as u64),
+            path.display()
+        )?;
+    }
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(out, "{:>8}: {}", "carved", found.len())?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// List the runs of at least `--rle` equal bytes, then dump the input
+/// with every run collapsed to a single line holding its byte and length.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_rle<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, config.truncate_len)?;
+    let found = dedupe::runs(&data, config.rle.unwrap_or_default());
+    let opts = dump_options(config);
+    let byte = |out: &mut OutputWriter<W>, b| {
+        print_byte(out, b, opts.format, opts.colorize, opts.prefix, "")
+    };
+    for run in found.iter().take_while(|_| !interrupt::interrupted()) {
+        let start = start_offset + run.offset;
+        write!(
+            out,
+            "{}-{}: {} bytes of ",
+            offset(start),
+            offset(start + run.len - 1),
+            config.format_size(run.len)
+        )?;
+        byte(out, run.byte)?;
+        writeln!(out)?;
+    }
+    if !found.is_empty() {
+        writeln!(out)?;
+    }
+    let mut done = 0;
+    for run in found.iter().map(Some).chain([None]) {
+        if interrupt::interrupted() {
+            break;
+        }
+        let (until, next) = match run {
+            Some(run) => (run.offset, run.offset + run.len),
+            None => (data.len() as u64, data.len() as u64),
+        };
+        if done < until {
+            let piece = DumpOptions {
+                start_offset: start_offset + done,
+                truncate_len: 0,
+                ..opts
+            };
+            output_dump(
+                out,
+                &mut Interruptible::new(&data[done as usize..until as usize]),
+                &piece,
+            )?;
+        }
+        if let Some(run) = run {
+            print_offset(out, opts.banks.translate(start_offset + run.offset))?;
+            byte(out, run.byte)?;
+            writeln!(out, " x {}", config.format_size(run.len))?;
+        }
+        done = next;
+    }
+    let collapsed = found.iter().map(|run| run.len).sum();
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(
+        out,
+        "{:>8}: {} runs, {} bytes",
+        "runs",
+        found.len(),
+        config.format_size(collapsed)
+    )?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// Report the likely encodings of the first bytes of the input, then dump
+/// it with the character panel in the charset of the best guess, unless
+/// `--charset` says otherwise.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_encoding<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let sample_len = match config.truncate_len {
+        0 => encoding::SAMPLE as u64,
+        len => len.min(encoding::SAMPLE as u64),
+    };
+    let mut sample = Vec::new();
+    buf.take(sample_len).read_to_end(&mut sample)?;
+    let guesses = encoding::detect(&sample);
+    for guess in &guesses {
+        let bom = match guess.bom {
+            true => " with bom",
+            false => "",
+        };
+        writeln!(
+            out,
+            "{:>8}: {}{bom}, {:.0}%",
+            "encoding",
+            guess.encoding.name(),
+            guess.confidence * 100.0
+        )?;
+    }
+    if guesses.is_empty() {
+        writeln!(out, "{:>8}: unknown", "encoding")?;
+    }
+    writeln!(out)?;
+    let charset = match (config.detect_charset, guesses.first()) {
+        (true, Some(guess)) => guess.encoding.charset(),
+        _ => config.charset,
+    };
+    let opts = DumpOptions {
+        start_offset,
+        charset,
+        ..dump_options(config)
+    };
+    let bytes = output_dump(out, &mut (&sample[..]).chain(buf), &opts)?;
+    write_summary(out, config, bytes)?;
+    out.flush()?;
+    Ok(bytes)
+}
+
+/// Report on the input as text instead of dumping it: its byte order mark,
+/// how its lines end, its longest line and any NUL bytes.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+fn render_text_report<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let mut limited;
+    let buf: &mut dyn Read = match config.truncate_len {
+        0 => buf,
+        len => {
+            limited = buf.take(len);
+            &mut limited
+        }
+    };
+    let mut report = encoding::TextReport::default();
+    let mut chunk = vec![0; encoding::SAMPLE];
+    loop {
+        match buf.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => report.feed(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    report.finish();
+    let bom = report.bom.map_or("none", |encoding| encoding.name());
+    writeln!(out, "{:>8}: {bom}", "bom")?;
+    let kinds = [report.lf, report.crlf, report.cr];
+    let mixed = match kinds.iter().filter(|count| **count > 0).count() {
+        0 | 1 => "",
+        _ => ", mixed",
+    };
+    writeln!(
+        out,
+        "{:>8}: lf {}, crlf {}, cr {}{mixed}",
+        "endings", report.lf, report.crlf, report.cr
+    )?;
+    writeln!(out, "{:>8}: {}", "lines", report.lines)?;
+    match report.longest_line {
+        0 => writeln!(out, "{:>8}: none", "longest")?,
+        line => writeln!(
+            out,
+            "{:>8}: {} bytes, line {line}",
+            "longest",
+            config.format_size(report.longest)
+        )?,
+    }
+    match report.first_nul {
+        Some(first) => writeln!(
+            out,
+            "{:>8}: {}, first at {}",
+            "nuls",
+            report.nuls,
+            offset(first)
+        )?,
+        None => writeln!(out, "{:>8}: 0", "nuls")?,
+    }
+    write_summary(out, config, report.bytes)?;
+    out.flush()?;
+    Ok(report.bytes)
+}
+
+/// A search hit: index, length, value and margin label.
+type Hit = (u64, usize, Option<String>, String);
+
+/// Dump only the lines holding `--find` matches, `--find-int` or
+/// `--find-float` values or `--pointer-scan` pointers, their bytes
+/// highlighted and the offset of every hit in the
+/// margin, runs of lines apart separated by `--`. Matches may overlap, and
+/// hits may straddle lines. `--only-offsets` and `--matches-json` list the
+/// hits for scripts instead.
+///
+/// The input is searched block by block, keeping the bytes of a hit that
+/// may run over the end of a block and the lines not dumped yet, so it
+/// streams whatever its length.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_hits<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let mut limited;
+    let buf: &mut dyn Read = match config.truncate_len {
+        0 => buf,
+        len => {
+            limited = buf.take(len);
+            &mut limited
+        }
+    };
+    // longest hit, its last byte possibly in the next block
+    let (width, counted) = match (
+        &config.find,
+        &config.find_int,
+        &config.find_float,
+        &config.pointer_scan,
+    ) {
+        (Some(pattern), ..) => (pattern.len(), "matches"),
+        (None, Some(search), ..) => (search.kind.width(), "matches"),
+        (None, None, Some(search), _) => (search.kind.width(), "matches"),
+        (None, None, None, Some(scan)) => (scan.width, "pointers"),
+        (None, None, None, None) => (1, "hits"),
+    };
+    let finder = config.find.as_deref().map(search::Finder::new);
+    // hits starting in `bytes`, the first of them at offset `first`
+    let search = |bytes: &[u8], first: u64| -> Vec<(usize, usize, Option<String>, String)> {
+        let mut hits: Vec<(usize, usize, Option<String>, String)> = Vec::new();
+        if let Some(finder) = &finder {
+            // Ctrl-C stops the search, with the hits found so far
+            while let Some(at) = finder
+                .find(bytes, hits.last().map_or(0, |hit| hit.0 + 1))
+                .filter(|_| !interrupt::interrupted())
+            {
+                hits.push((at, width, None, "match".to_string()));
+            }
+        } else if let Some(search) = &config.find_int {
+            hits = search
+                .scan(bytes)
+                .into_iter()
+                .map(|(at, value, endian)| {
+                    let label = search.label(value, endian);
+                    (at, width, Some(search.kind.display(value)), label)
+                })
+                .collect();
+        } else if let Some(search) = &config.find_float {
+            hits = search
+                .scan(bytes, first)
+                .into_iter()
+                .map(|(at, value, endian)| {
+                    let label = search.label(value, endian);
+                    (at, width, Some(search.kind.display(value)), label)
+                })
+                .collect();
+        } else if let Some(scan) = &config.pointer_scan {
+            hits = scan
+                .scan(bytes, first)
+                .into_iter()
+                .map(|(at, value)| {
+                    let label = format!("-> {}", offset(value));
+                    (at, width, Some(value.to_string()), label)
+                })
+                .collect();
+        }
+        hits
+    };
+    let columns = match config.columns {
+        0 => u64::MAX,
+        columns => columns,
+    };
+    let opts = DumpOptions {
+        start_offset,
+        align: false,
+        truncate_len: 0,
+        ..dump_options(config)
+    };
+    let listed = config.only_offsets || config.matches_json;
+    let mut block = vec![0; 64 * 1024];
+    // bytes from index `base` on, and the index of the first not searched
+    let mut window = Vec::new();
+    let (mut base, mut searched) = (0, 0);
+    // hits on lines not all dumped yet, and the run of those lines
+    let mut pending: Vec<Hit> = Vec::new();
+    let mut run: Option<Range<u64>> = None;
+    // whether a `--` is due before the next line dumped
+    let (mut dumped, mut apart) = (false, false);
+    let mut total = 0;
+    loop {
+        let n = match buf.read(&mut block) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        window.extend_from_slice(&block[..n]);
+        let end = base + window.len() as u64;
+        let from = (searched - base) as usize;
+        for (at, len, value, label) in search(&window[from..], start_offset + searched) {
+            let at = searched + at as u64;
+            total += 1;
+            if listed {
+                let first = start_offset + at;
+                if config.only_offsets {
+                    writeln!(out, "{}", offset(first))?;
+                    continue;
+                }
+                let index = (at - base) as usize;
+                let hex: String = window[index..index + len]
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect();
+                let value = value
+                    .as_ref()
+                    .map_or(String::new(), |value| format!(", \"value\": {value}"));
+                writeln!(
+                    out,
+                    "{{\"offset\": {first}, \"length\": {len}, \"hex\": \"{hex}\"{value}}}"
+                )?;
+                continue;
+            }
+            let lines = at / columns..(at + len as u64 - 1) / columns + 1;
+            match &mut run {
+                Some(run) if lines.start <= run.end => run.end = run.end.max(lines.end),
+                _ => {
+                    if let Some(done) = run.take().filter(|done| done.start < done.end) {
+                        dump_lines(out, &opts, config, &window, base, done, &pending, apart)?;
+                        dumped = true;
+                    }
+                    pending.clear();
+                    apart = dumped;
+                    run = Some(lines);
+                }
+            }
+            pending.push((at, len, value, label));
+        }
+        // a hit can't start where its last byte is still to be read
+        searched = match n {
+            0 => end,
+            _ => searched.max(end.saturating_sub(width as u64 - 1)),
+        };
+        if let Some(lines) = &mut run {
+            // no later hit reaches back to a line ending before `searched`
+            let last = match n {
+                0 => lines.end,
+                _ => lines.end.min(searched / columns),
+            };
+            if lines.start < last {
+                dump_lines(
+                    out,
+                    &opts,
+                    config,
+                    &window,
+                    base,
+                    lines.start..last,
+                    &pending,
+                    apart,
+                )?;
+                (dumped, apart) = (true, false);
+                let printed = last.saturating_mul(columns);
+                pending.retain(|(at, len, ..)| at + *len as u64 > printed);
+                lines.start = last;
+            }
+        }
+        if n == 0 || interrupt::interrupted() {
+            break;
+        }
+        // keep the line a later hit may start on, and the lines not dumped
+        let line = searched / columns * columns;
+        let keep = run
+            .as_ref()
+            .filter(|lines| lines.start < lines.end)
+            .map_or(line, |lines| line.min(lines.start.saturating_mul(columns)));
+        window.drain(..(keep - base) as usize);
+        base = keep;
+        if config
+            .memory_limit
+            .is_some_and(|limit| window.len() as u64 > limit)
+        {
+            return Err(over_limit(config, "the lines holding hits"));
+        }
+    }
+    out.record_hits(total);
+    let read = base + window.len() as u64;
+    if listed {
+        out.flush()?;
+        return Ok(read);
+    }
+    write_summary(out, config, read)?;
+    writeln!(out, "{counted:>8}: {total}")?;
+    out.flush()?;
+    Ok(read)
+}
+
+/// Dump `lines` of a run holding hits, their bytes highlighted and
+/// labeled in the margin.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `opts` - dump options of the command line, from the first byte.
+/// * `config` - parsed command line, with the bookmarks.
+/// * `window` - input bytes kept, holding the lines.
+/// * `base` - index of the first byte of `window`.
+/// * `lines` - indexes of the lines to dump.
+/// * `hits` - hits on those lines.
+/// * `apart` - whether to separate the lines from the ones before with `--`.
+#[allow(clippy::too_many_arguments)]
+fn dump_lines<W: Write>(
+    out: &mut OutputWriter<W>,
+    opts: &DumpOptions,
+    config: &Config,
+    window: &[u8],
+    base: u64,
+    lines: Range<u64>,
+    hits: &[Hit],
+    apart: bool,
+) -> io::Result<()> {
+    let start_offset = opts.start_offset;
+    let found: Vec<Range<u64>> = hits
+        .iter()
+        .map(|(at, len, ..)| start_offset + at..start_offset + at + *len as u64)
+        .collect();
+    let mut marks = config.bookmarks.clone();
+    marks.extend(hits.iter().map(|(at, _, _, label)| Bookmark {
+        offset: start_offset + at,
+        label: label.clone(),
+    }));
+    marks.sort_by_key(|mark| mark.offset);
+    let columns = match config.columns {
+        0 => u64::MAX,
+        columns => columns,
+    };
+    let first = lines.start.saturating_mul(columns);
+    let last = lines.end.saturating_mul(columns);
+    let bytes =
+        &window[(first - base) as usize..(last.min(base + window.len() as u64) - base) as usize];
+    if apart {
+        writeln!(out, "--")?;
+    }
+    let piece = DumpOptions {
+        start_offset: start_offset + first,
+        bookmarks: &marks,
+        found: &found,
+        ..*opts
+    };
+    output_dump(out, &mut Interruptible::new(bytes), &piece)?;
+    Ok(())
+}
+
+/// Dump every field between `--split-on` delimiters on its own, headed by
+/// its index, offset and length. A delimiter at the very end leaves no
+/// empty field after it.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_fields<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, config.truncate_len)?;
+    let delimiter = config.split_on.as_deref().unwrap_or_default();
+    let finder = search::Finder::new(delimiter);
+    let mut fields = Vec::new();
+    let mut start = 0;
+    while let Some(at) = finder
+        .find(&data, start)
+        .filter(|_| !interrupt::interrupted())
+    {
+        fields.push(start..at);
+        start = at + delimiter.len();
+    }
+    if start < data.len() || fields.is_empty() {
+        fields.push(start..data.len());
+    }
+    let opts = dump_options(config);
+    for (index, field) in fields.iter().enumerate() {
+        if interrupt::interrupted() {
+            break;
+        }
+        let first = start_offset + field.start as u64;
+        writeln!(
+            out,
+            "field {index}: {}, {} bytes",
+            offset(opts.banks.translate(first)),
+            config.format_size(field.len() as u64)
+        )?;
+        let piece = DumpOptions {
+            start_offset: first,
+            truncate_len: 0,
+            ..opts
+        };
+        output_dump(out, &mut Interruptible::new(&data[field.clone()]), &piece)?;
+    }
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(out, "{:>8}: {}", "fields", fields.len())?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// Dump the input one `--frame` at a time, each frame's body headed by its
+/// index, offset and length. Frames are read and written as they arrive,
+/// and a frame cut off by the end of the input is flagged.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_frames<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let Some(framing) = &config.frame else {
+        return Ok(0);
+    };
+    let len = match config.truncate_len {
+        0 => u64::MAX,
+        len => len,
+    };
+    let mut input = BufReader::new(buf.take(len));
+    let opts = dump_options(config);
+    let mut position = start_offset;
+    let mut count = 0;
+    while let Some(frame) = frame::next(&mut input, framing)? {
+        write!(
+            out,
+            "frame {count}: {}, ",
+            offset(opts.banks.translate(position))
+        )?;
+        match frame.declared {
+            Some(declared) => write!(out, "length {declared}")?,
+            None => write!(out, "{} bytes", config.format_size(frame.body.len() as u64))?,
+        }
+        match &frame.problem {
+            Some(problem) => writeln!(out, "  ! {problem}")?,
+            None => writeln!(out)?,
+        }
+        out.line_done()?;
+        let piece = DumpOptions {
+            start_offset: position + frame.head as u64,
+            truncate_len: 0,
+            ..opts
+        };
+        output_dump(out, &mut frame.body.as_slice(), &piece)?;
+        position += (frame.head + frame.body.len() + frame.tail) as u64;
+        count += 1;
+    }
+    let bytes = position - start_offset;
+    write_summary(out, config, bytes)?;
+    writeln!(out, "{:>8}: {count}", "frames")?;
+    out.flush()?;
+    Ok(bytes)
+}
+
+/// Walk the input as the `--decode` structure, writing every entry's
+/// header with its offset, then a dump of its value. Entries cut off by the
+/// end of the input are flagged.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte.
+fn render_decoded<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, config.truncate_len)?;
+    if let Some(Decoder::Leb128 { at, count }) = config.decode {
+        let at = at.unwrap_or(start_offset);
+        return render_varints(config, &data, out, start_offset, at, count);
+    }
+    let opts = dump_options(config);
+    let at = |offset: usize| start_offset + offset as u64;
+    let (entries, digits) = match config.decode {
+        Some(Decoder::Tlv {
+            type_size,
+            len_size,
+            endian,
+        }) => (
+            decode::tlv(&data, type_size, len_size, endian),
+            2 * type_size,
+        ),
+        _ => (Vec::new(), 0),
+    };
+    for entry in entries.iter() {
+        write!(out, "{}:", offset(opts.banks.translate(at(entry.offset))))?;
+        if let Some((tag, len)) = entry.header {
+            write!(out, " type {tag:#0w$x}, length {len}", w = digits + 2)?;
+        }
+        match (&entry.problem, entry.header) {
+            (Some(problem), Some(_)) => writeln!(out, "  ! {problem}")?,
+            (Some(problem), None) => writeln!(out, " ! {problem}")?,
+            (None, _) => writeln!(out)?,
+        }
+        let piece = DumpOptions {
+            start_offset: at(entry.value.start),
+            truncate_len: 0,
+            ..opts
+        };
+        output_dump(out, &mut &data[entry.value.clone()], &piece)?;
+    }
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(out, "{:>8}: {}", "entries", entries.len())?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// Decode LEB128 varints from `at`, writing each one's byte range, bytes
+/// and value read as unsigned and signed, then the dump lines they span
+/// with every varint's value noted on the line it starts on.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `data` - input.
+/// * `out` - output writer.
+/// * `start_offset` - offset of the first byte of `data`.
+/// * `at` - input offset of the first varint.
+/// * `count` - number of varints.
+fn render_varints<W: Write>(
+    config: &Config,
+    data: &[u8],
+    out: &mut OutputWriter<W>,
+    start_offset: u64,
+    at: u64,
+    count: u64,
+) -> io::Result<u64> {
+    let end = start_offset + data.len() as u64;
+    if at < start_offset || at >= end {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--at {} is outside the input, {}-{}",
+                offset(at),
+                offset(start_offset),
+                offset(end)
+            ),
+        ));
+    }
+    let opts = dump_options(config);
+    let varints = decode::leb128(data, (at - start_offset) as usize, count);
+    for varint in varints.iter() {
+        let first = start_offset + varint.offset as u64;
+        write!(
+            out,
+            "{}-{}: ",
+            offset(first),
+            offset(first + varint.len.max(1) as u64 - 1)
+        )?;
+        for b in &data[varint.offset..varint.offset + varint.len] {
+            print_byte(out, *b, opts.format, opts.colorize, opts.prefix, " ")?;
+        }
+        write!(
+            out,
+            "unsigned {}, signed {}",
+            varint.unsigned, varint.signed
+        )?;
+        match &varint.problem {
+            Some(problem) => writeln!(out, "  ! {problem}")?,
+            None => writeln!(out)?,
+        }
+    }
+    // the lines the varints span, each varint noted where it starts
+    if let (Some(first), Some(last)) = (varints.first(), varints.last()) {
+        writeln!(out)?;
+        let columns = opts.column_width.max(1) as usize;
+        let from = first.offset - first.offset % columns;
+        let to = (last.offset + last.len).max(first.offset + 1);
+        for start in (from..to).step_by(columns) {
+            let mut line = Line::new();
+            line.offset = start_offset + start as u64;
+            line.hex_body = data[start..(start + columns).min(data.len())].to_vec();
+            line.bytes = line.hex_body.len() as u64;
+            line.notes = varints
+                .iter()
+                .filter(|varint| (start..start + columns).contains(&varint.offset))
+                .map(|varint| {
+                    format!(
+                        "{} {}",
+                        offset(start_offset + varint.offset as u64),
+                        varint.unsigned
+                    )
+                })
+                .collect();
+            write_line(out, &line, 0, &opts)?;
+        }
+    }
+    write_summary(out, config, data.len() as u64)?;
+    writeln!(out, "{:>8}: {}", "varints", varints.len())?;
+    out.flush()?;
+    Ok(data.len() as u64)
+}
+
+/// Number of elements a source code array of the input holds, when it's
+/// known before reading it, for the declarations that state it.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+fn array_len(config: &Config) -> Option<u64> {
+    let left = config.input_len()?.saturating_sub(config.skip);
+    Some(match config.truncate_len {
+        0 => left,
+        len => left.min(len),
+    })
+}
+
+/// Dump options for the parsed command line.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+fn dump_options(config: &Config) -> DumpOptions<'_> {
+    // check no_color and prevent term color codes being sent to stdout
+    // test: cat Cargo.toml | target/debug/hx | more
+    // override via ARG_CLR
+    let colorize = config.color.unwrap_or_else(|| {
+        config.outfile.is_none() && !is_no_color() && io::stdout().is_terminal()
+    });
+    DumpOptions {
+        format: config.format,
+        colorize,
+        prefix: config.prefix,
+        truncate_len: config.truncate_len,
+        column_width: config.columns,
+        start_offset: 0,
+        align: config.align,
+        stripe: config.stripe,
+        highlight: config.highlight,
+        charset: config.charset,
+        only: config.only,
+        boundary: config.boundary,
+        bookmarks: &config.bookmarks,
+        found: &[],
+        symbols: &config.symbols,
+        lines: &config.lines,
+        banks: config.banks,
+        source: config.source_map.as_ref(),
+        separator: &config.separator,
+        group: config.group,
+        timestamps: config.timestamps,
+        panels: &config.panels,
+        border: config.border,
+    }
+}
+
+/// Render the rest of an input whose output was interrupted, continuing
+/// after the rows already written.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer, appending to the interrupted output.
+/// * `point` - where the interrupted output left off.
+fn render_resumed<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+    point: resume::ResumePoint,
+) -> io::Result<u64> {
+    if point.complete {
+        return Ok(0);
+    }
+    let skipped = discard(buf, config.skip)?;
+    match &config.mode {
+        Mode::Array(array) => {
+            let full = point.rows * config.columns;
+            let done = discard(
+                buf,
+                match config.truncate_len {
+                    0 => full,
+                    len => full.min(len),
+                },
+            )?;
+            // every row is written, the empty one after a full row too
+            if done < full {
+                write_array_footer(out, array)?;
+                out.flush()?;
+                return Ok(0);
+            }
+            let mut format = match point.header {
+                true => ArrayFormat::resumed(array, config.columns),
+                false => ArrayFormat::new(array, config.columns, array_len(config)),
+            };
+            let opts = DumpOptions {
+                align: false,
+                truncate_len: config.truncate_len.saturating_sub(done),
+                ..dump_options(config)
+            };
+            // --len reached, only the end is missing
+            let bytes = match config.truncate_len > 0 && opts.truncate_len == 0 {
+                true => emit(out, &mut format, &mut io::empty(), &opts)?,
+                false => emit(out, &mut format, buf, &opts)?,
+            };
+            out.flush()?;
+            Ok(bytes)
+        }
+        _ => {
+            let mut opts = dump_options(config);
+            if opts.colorize {
+                return Err(io::Error::other("colored output can't be resumed"));
+            }
+            let mut want = point.rows * opts.column_width;
+            if opts.truncate_len > 0 {
+                want = want.min(opts.truncate_len);
+            }
+            let done = discard(buf, want)?;
+            opts.start_offset = skipped + done;
+            let bytes = match opts.truncate_len {
+                0 => output_dump(out, buf, &opts)?,
+                // everything was dumped already, only the summary is missing
+                len if len == done => 0,
+                len => {
+                    opts.truncate_len = len - done;
+                    output_dump(out, buf, &opts)?
+                }
+            };
+            write_summary(out, config, done + bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Dump `count` randomly chosen windows of the input, each under a header
+/// with its offset. Files are sampled by seeking, so only the chosen
+/// windows are read; stdin is read through once.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `count` - number of windows.
+/// * `out` - output writer.
+fn render_samples<W: Write>(
+    config: &Config,
+    count: u64,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let window = match config.truncate_len {
+        0 => 4 * config.columns,
+        len => len,
+    }
+    .max(1);
+    let mut input = config.input.open()?;
+    let windows = match (config.input.capabilities().seekable, input.file()) {
+        (true, Some(file)) => sample::sample_file(file, window, count, config.seed)?,
+        _ => sample::sample_stream(&mut input, window, count, config.seed)?,
+    };
+    let mut opts = dump_options(config);
+    opts.truncate_len = 0;
+    let mut processed = 0;
+    for (i, (index, data)) in windows.iter().enumerate() {
+        if i > 0 {
+            out.end_line()?;
+        }
+        opts.start_offset = index * window;
+        writeln!(
+            out,
+            "==> sample {} of {}, {} <==",
+            i + 1,
+            windows.len(),
+            offset(opts.start_offset)
+        )?;
+        let bytes = output_dump(out, &mut data.as_slice(), &opts)?;
+        write_summary(out, config, bytes)?;
+        processed += bytes;
+    }
+    Ok(processed)
+}
+
+/// Render bytes as the single input would be, without looking at the
+/// terminal or the environment: colour only when `config.color` asks for
+/// it, summary text in English unless `config.lang` is set. Meant for
+/// snapshot tests of the output.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line, its input and output file are ignored.
+/// * `bytes` - input bytes.
+pub fn render_to_string(config: &Config, bytes: &[u8]) -> io::Result<String> {
+    let config = Config {
+        color: Some(config.color.unwrap_or(false)),
+        lang: Some(config.lang.clone().unwrap_or_else(|| "en".to_string())),
+        ..config.clone()
+    };
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Block);
+    render_input(&config, &mut &bytes[..], &mut out)?;
+    String::from_utf8(out.into_inner()?).map_err(io::Error::other)
+}
+
+/// Render the single input, looking inside archives and databases first
+/// when asked to.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - input to be read.
+/// * `out` - output writer.
+fn render_input<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    if let (Mode::Array(array), true) = (&config.mode, config.finalize) {
+        finalize_array(out, array, buf)
+    } else if config.member.is_some() || config.list_members {
+        render_archive(config, buf, out)
+    } else if config.region.is_some() || config.list_regions {
+        render_region(config, buf, out)
+    } else if let Some(page_no) = config.sqlite_page {
+        let (page_size, page) = sqlite::read_page(buf, page_no)?;
+        sqlite::annotate(out, page_no, page_size, &page)?;
+        render(config, &mut page.as_slice(), out, 0)
+    } else if config.pcap {
+        render_packets(config, buf, out)
+    } else if let Some(format) = config.from {
+        render_records(config, format, buf, out)
+    } else if !config.overlay.is_empty() {
+        render(config, &mut Overlay::new(buf, &config.overlay), out, 0)
+    } else {
+        render(config, buf, out, 0)
+    }
+}
+
+/// Render the image described by Intel HEX, S-record or hex text input,
+/// offsets starting at its lowest address. Malformed lines are skipped
+/// with a warning, or fail with `--strict`. Overlapping records fail
+/// unless `--on-conflict` picks a winner, and are reported either way.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `format` - record format of the input.
+/// * `buf` - records to be read.
+/// * `out` - output writer.
+fn render_records<W: Write>(
+    config: &Config,
+    format: RecordFormat,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let text = read_input(config, buf, 0)?;
+    let mut warn = |e: &Malformed| log::warn(format_args!("{e}, skipped"));
+    let records = records::parse(&text, format, config.strict, &mut warn)?;
+    let overlaps = records::overlaps(&records);
+    if !overlaps.is_empty() {
+        let ranges: Vec<String> = overlaps
+            .iter()
+            .map(|(start, end)| format!("{}..{}", offset(*start), offset(*end)))
+            .collect();
+        let ranges = ranges.join(", ");
+        match config.on_conflict {
+            Conflict::Error => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "records overlap at {ranges}, keep the first or last with --on-conflict"
+                    ),
+                ))
+            }
+            Conflict::First => log::warn(format_args!(
+                "records overlap at {ranges}, keeping the first"
+            )),
+            Conflict::Last => log::warn(format_args!(
+                "records overlap at {ranges}, keeping the last"
+            )),
+        }
+    }
+    match config.fill {
+        Fill::Byte(fill) => {
+            let (base, image) = records::assemble(&records, fill, config.on_conflict);
+            render(config, &mut image.as_slice(), out, base)
+        }
+        Fill::Skip => {
+            let segments = records::segments(&records, config.on_conflict);
+            render_segments(config, &segments, out)
+        }
+    }
+}
+
+/// Dump runs of bytes at their addresses, with a `<gap>` line between
+/// runs and a single summary for all of them.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `segments` - address and bytes of every run.
+/// * `out` - output writer.
+fn render_segments<W: Write>(
+    config: &Config,
+    segments: &[(u64, Vec<u8>)],
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let opts = dump_options(config);
+    let mut bytes = 0;
+    let mut end = None;
+    for (start, data) in segments {
+        if let Some(end) = end {
+            writeln!(out, "<gap> {}..{}", offset(end), offset(*start))?;
+        }
+        let opts = DumpOptions {
+            start_offset: *start,
+            ..opts
+        };
+        bytes += emit_format(config, &config.output, &mut data.as_slice(), out, &opts)?;
+        end = Some(start + data.len() as u64);
+    }
+    if config.output == "dump" {
+        write_summary(out, config, bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// Render every selected packet of a capture under a header with its index,
+/// capture time and length.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - pcap or pcapng capture.
+/// * `out` - output writer.
+fn render_packets<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let mut reader = pcap::PcapReader::new(buf)?;
+    let mut processed = 0;
+    let mut first = true;
+    while let Some(packet) = reader.next_packet()? {
+        if interrupt::interrupted() {
+            break;
+        }
+        if config
+            .packets
+            .is_some_and(|range| !range.contains(packet.index))
+        {
+            if config
+                .packets
+                .is_some_and(|range| range.is_past(packet.index))
+            {
+                break;
+            }
+            continue;
+        }
+        if !first {
+            out.end_line()?;
+        }
+        first = false;
+        let time = match packet.digits {
+            0 => "no timestamp".to_string(),
+            digits => pcap::format_timestamp(packet.seconds, packet.nanos, digits),
+        };
+        let len = match packet.data.len() as u64 == u64::from(packet.orig_len) {
+            true => format!("{} bytes", packet.orig_len),
+            false => format!("{} of {} bytes", packet.data.len(), packet.orig_len),
+        };
+        writeln!(out, "==> packet {}, {time}, {len} <==", packet.index)?;
+        processed += render(config, &mut packet.data.as_slice(), out, 0)?;
+        if config
+            .packets
+            .is_some_and(|range| range.is_past(packet.index))
+        {
+            break;
+        }
+    }
+    out.flush()?;
+    Ok(processed)
+}
+
+/// List the members of an archive input, or render the selected member.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - archive to be read.
+/// * `out` - output writer.
+#[cfg(feature = "archives")]
+fn render_archive<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, 0)?;
+    let archive = archive::Archive::parse(data)?;
+    match &config.member {
+        Some(name) => {
+            let config = Config {
+                source_map: match &config.source_map {
+                    Some(map) => {
+                        let (at, compressed) = archive.source(name)?;
+                        Some(map.within(vec![(0, at)], compressed))
+                    }
+                    None => None,
+                },
+                ..config.clone()
+            };
+            render(&config, &mut archive.read(name)?.as_slice(), out, 0)
+        }
+        None => {
+            for member in archive.members.iter() {
+                writeln!(out, "{:>12}  {}", member.size, member.name)?;
+            }
+            out.flush()?;
+            Ok(0)
+        }
+    }
+}
+
+/// Dump a seekable zstd archive written by `--output zst`, decoding only
+/// the frames from `--skip` on.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `out` - output writer.
+#[cfg(feature = "zstd")]
+fn render_zst<W: Write>(config: &Config, out: &mut OutputWriter<W>) -> io::Result<u64> {
+    let path = config.input.path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "--zst reads an archive file")
+    })?;
+    let mut archive = seekable::ArchiveReader::new(File::open(path)?)?;
+    let skip = config.skip.min(archive.len());
+    archive.seek(io::SeekFrom::Start(skip))?;
+    let config = Config {
+        skip: 0,
+        source_map: config
+            .source_map
+            .as_ref()
+            .map(|map| map.within(archive.frames(), true)),
+        ..config.clone()
+    };
+    render(&config, &mut archive, out, skip)
+}
+
+/// Seekable zstd archives need the `zstd` feature; `--zst` doesn't exist
+/// without it.
+#[cfg(not(feature = "zstd"))]
+fn render_zst<W: Write>(_config: &Config, _out: &mut OutputWriter<W>) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "hx was built without the zstd feature",
+    ))
+}
+
+/// Archive members need the `archives` feature; the member options don't
+/// exist without it.
+#[cfg(not(feature = "archives"))]
+fn render_archive<W: Write>(
+    _config: &Config,
+    _buf: &mut dyn Read,
+    _out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    Err(io::Error::other(
+        "hx was built without the archives feature",
+    ))
+}
+
+/// List the memory regions of an ELF core file or minidump input, or
+/// render the selected region, offsets from its virtual address.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `buf` - dump to be read.
+/// * `out` - output writer.
+#[cfg(feature = "cores")]
+fn render_region<W: Write>(
+    config: &Config,
+    buf: &mut dyn Read,
+    out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    let data = read_input(config, buf, 0)?;
+    let regions = coredump::regions(&data)?;
+    match &config.region {
+        Some(wanted) => {
+            let region = coredump::select(&regions, wanted)?;
+            let start = region.offset as usize;
+            let Some(bytes) = start
+                .checked_add(region.size as usize)
+                .and_then(|end| data.get(start..end))
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("region {} runs past the end of the dump", region.name),
+                ));
+            };
+            render(config, &mut &bytes[..], out, region.address)
+        }
+        None => {
+            for (i, region) in regions.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{i:>3}  {:<8}  {:>12}  {:>10}  {:>10}  {}",
+                    region.name,
+                    offset(region.address),
+                    offset(region.offset),
+                    region.size,
+                    region.flags
+                )?;
+            }
+            out.flush()?;
+            Ok(0)
+        }
+    }
+}
+
+/// Memory regions need the `cores` feature; the region option doesn't
+/// exist without it.
+#[cfg(not(feature = "cores"))]
+fn render_region<W: Write>(
+    _config: &Config,
+    _buf: &mut dyn Read,
+    _out: &mut OutputWriter<W>,
+) -> io::Result<u64> {
+    Err(io::Error::other("hx was built without the cores feature"))
+}
+
+/// Render every file under a `==> name <==` header, reporting files which
+/// can't be opened to stderr and carrying on with the rest.
+///
+/// Returns the number of bytes rendered and the number of failed files.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `paths` - files to render.
+/// * `out` - output writer.
+fn render_files<W: Write>(
+    config: &Config,
+    paths: &[PathBuf],
+    out: &mut OutputWriter<W>,
+) -> io::Result<(u64, usize)> {
+    let mut processed = 0;
+    let mut failures = 0;
+    let mut budget;
+    // offset of the file in the files cat'ed together, for --continuous-offsets
+    let mut base = 0;
+    for (i, file) in paths.iter().enumerate() {
+        let path = file.display();
+        if interrupt::interrupted() {
+            break;
+        }
+        // --len-scope total shares one --len between all the files
+        let config = match config.len_total && config.truncate_len > 0 {
+            true if processed >= config.truncate_len => break,
+            true => {
+                budget = Config {
+                    truncate_len: config.truncate_len - processed,
+                    ..config.clone()
+                };
+                &budget
+            }
+            false => config,
+        };
+        // counts are listed like grep -c, one file a line
+        if i > 0 && !config.count_matches {
+            out.end_line()?;
+        }
+        match (config.count_matches, config.continuous_offsets) {
+            (true, _) => {}
+            (false, true) => writeln!(out, "==> {path} at {} <==", offset(base))?,
+            (false, false) => writeln!(out, "==> {path} <==")?,
+        }
+        let source = InputSource::new(Some(file));
+        match open_input(config, &source) {
+            Ok(mut buf) => {
+                if config.header {
+                    let head = buf.fill_buf()?.to_vec();
+                    write_header(out, config, &source, &head)?;
+                }
+                if config.count_matches {
+                    write!(out, "{path}:")?;
+                }
+                let start = match config.continuous_offsets {
+                    true => base,
+                    false => 0,
+                };
+                let bytes = render(config, &mut buf, out, start)?;
+                processed += bytes;
+                // the next file starts after all of this one, dumped or not
+                base += fs::metadata(file).map_or(bytes, |meta| meta.len());
+            }
+            Err(e) => {
+                out.flush()?;
+                log::error(format_args!("{path}: {e}"));
+                failures += 1;
+            }
+        }
+    }
+    Ok((processed, failures))
+}
+
+/// Print the groups of identical files among the inputs instead of dumping
+/// them: every group under its sha-256 and size, then the number of files,
+/// groups and copies past the first file of each, and the bytes the
+/// copies take. Only files whose size matches
+/// another file's are hashed.
+///
+/// # Arguments
+///
+/// * `config` - parsed command line.
+/// * `paths` - files to compare.
+/// * `out` - output writer.
+///
+/// Returns the bytes hashed and the number of files that couldn't be read.
+fn write_dedupe_report<W: Write>(
+    config: &Config,
+    paths: &[PathBuf],
+    out: &mut OutputWriter<W>,
+) -> io::Result<(u64, usize)> {
+    let mut failures = 0;
+    let mut sizes: Vec<(&PathBuf, u64)> = Vec::new();
+    for path in paths {
+        match fs::metadata(path) {
+            Ok(meta) => sizes.push((path, meta.len())),
+            Err(e) => {
+                log::error(format_args!("{}: {e}", path.display()));
+                failures += 1;
+            }
+        }
+    }
+    let mut processed = 0;
+    // groups of identical files by digest and size, in the order of their first file
+    let mut groups: Vec<(String, u64, Vec<&PathBuf>)> = Vec::new();
+    for (path, size) in &sizes {
+        if sizes.iter().filter(|(_, other)| other == size).count() < 2 {
+            continue;
+        }
+        let digest = match hash::sha256_file(path) {
+            Ok(digest) => digest,
+            Err(e) => {
+                log::error(format_args!("{}: {e}", path.display()));
+                failures += 1;
+                continue;
+            }
+        };
+        processed += size;
+        match groups.iter_mut().find(|(other, _, _)| *other == digest) {
+            Some((_, _, files)) => files.push(path),
+            None => groups.push((digest, *size, vec![path])),
+        }
+    }
+    groups.retain(|(_, _, files)| files.len() > 1);
+    for (digest, size, files) in &groups {
+        writeln!(
+            out,
+            "{digest}  size {}, {} files",
+            config.format_size(*size),
+            files.len()
+        )?;
+        for path in files {
+            writeln!(out, "  {}", path.display())?;
+        }
+        out.end_line()?;
+    }
+    // every file of a group but the first is a copy
+    let copies: usize = groups.iter().map(|(_, _, files)| files.len() - 1).sum();
+    let wasted: u64 = groups
+        .iter()
+        .map(|(_, size, files)| size * (files.len() as u64 - 1))
+        .sum();
+    writeln!(out, "{:>8}: {}", "files", sizes.len())?;
+    writeln!(out, "{:>8}: {}", "groups", groups.len())?;
+    writeln!(out, "{:>8}: {copies}", "copies")?;
+    writeln!(
+        out,
+        "{:>8}: {}",
+        config.tr(Msg::Bytes),
+        config.format_size(wasted)
+    )?;
+    Ok((processed, failures))
+}
+
+/// Skip `len` bytes of input by reading and discarding them, so skipping
+/// works the same on pipes as on files. Returns the number of bytes skipped,
+/// less than `len` when the input ends first.
+///
+/// # Arguments
+///
+/// * `buf` - input to be read.
+/// * `len` - number of bytes to skip.
+pub fn discard(buf: &mut dyn Read, len: u64) -> io::Result<u64> {
+    io::copy(&mut buf.take(len), &mut io::sink())
+}
+
+/// Read a list of file names, NUL delimited when the list contains a NUL
+/// byte (as from `find -print0`), newline delimited otherwise.
+///
+/// # Arguments
+///
+/// * `source` - file containing the list, `-` for stdin.
+pub fn read_file_list(source: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut list = Vec::new();
+    match source.to_str() {
+        Some("-") => io::stdin().lock().read_to_end(&mut list)?,
+        _ => File::open(source)?.read_to_end(&mut list)?,
+    };
+    Ok(split_file_list(&list))
+}
+
+/// Split a file list on NUL bytes, or on newlines when there are none.
+pub fn split_file_list(list: &[u8]) -> Vec<PathBuf> {
+    let delimiter = match list.contains(&0) {
+        true => b'\0',
+        false => b'\n',
+    };
+    list.split(|b| *b == delimiter)
+        .filter(|name| !name.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+/// Path named by raw bytes, kept as they are on unix, where a file name
+/// is any bytes, and read as UTF-8 elsewhere.
+///
+/// # Arguments
+///
+/// * `name` - bytes of the path.
+pub(crate) fn path_from_bytes(name: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(name))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(name).into_owned())
+    }
+}
+
+/// Timing summary line: elapsed time, bytes processed and throughput.
+///
+/// # Arguments
+///
+/// * `elapsed` - wall clock time of the run.
+/// * `bytes` - number of input bytes processed.
+/// * `raw_sizes` - print the plain byte count instead of grouped digits and IEC units.
+pub fn timing_report(elapsed: Duration, bytes: u64, raw_sizes: bool) -> String {
+    let secs = elapsed.as_secs_f64();
+    let throughput = match secs > 0.0 {
+        true => bytes as f64 / secs / 1_000_000.0,
+        false => 0.0,
+    };
+    format!(
+        "{:>8}: {:.3}s, {} {}, {:.2} MB/s",
+        tr(Msg::Timing),
+        secs,
+        format_size(bytes, raw_sizes),
+        tr(Msg::Bytes),
+        throughput
+    )
+}
+
+/// Detect stdin, file path and/or parameters.
+/// # Arguments
+///
+/// * `matches` - argument matches.
+pub fn is_stdin(matches: &ArgMatches) -> bool {
+    if let Some(file) = matches.get_one::<PathBuf>(ARG_INP) {
+        log::trace(format_args!("input file {}", file.display()));
+        return false;
+    } else if let Some(nth1) = env::args().nth(1) {
+        log::trace(format_args!("no input file after {nth1}, reading stdin"));
+        // options without an input file, e.g. `hx -c8` or `hx --line-buffered`
+        return true;
+    } else if !matches.args_present() {
+        return true;
+    }
+
+    false
+}
+
+/// Output hex dump lines with offset, byte and ascii columns.
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `buf` - input to be read.
+/// * `opts` - dump layout and styling.
+///
+/// Returns the number of bytes dumped.
+pub fn output_dump<W: Write>(
+    out: &mut OutputWriter<W>,
+    buf: &mut dyn Read,
+    opts: &DumpOptions,
+) -> io::Result<u64> {
+    emit(out, &mut DumpFormat::new(*opts), buf, opts)
+}
+
+/// Convert a dump back into the bytes it shows, the inverse of
+/// `output_dump`. Lines that don't start with an offset, like the summary,
+/// are skipped. The byte cells may have the `0x` prefix or not, lines may
+/// have any number of them and the character panel may be missing. Bytes
+/// are placed by offset from the first line, gaps filled with zeros.
+/// # Arguments
+///
+/// * `buf` - dump to be read.
+/// * `out` - writer of the bytes.
+///
+/// Returns the number of bytes written.
+pub fn reverse_dump(buf: &mut dyn BufRead, out: &mut impl Write) -> io::Result<u64> {
+    reverse_dump_words(buf, out, None)
+}
+
+/// Convert a dump back into bytes like `reverse_dump`, from any layout hx
+/// writes or from `xxd` and `hexdump`: cells of bytes or words, octal or
+/// binary with their prefix, borders, reordered panels and `,` or no
+/// separators, or bare hex digits without offsets, as `--output plain`
+/// writes them. Where the cells are and what follows them is taken from
+/// the first line and kept for the rest. A `*` line repeats the line
+/// before it up to the next offset, and an offset alone after the last
+/// line ends the bytes there, dropping the padding of a last word.
+///
+/// # Arguments
+///
+/// * `buf` - dump to be read.
+/// * `out` - writer of the bytes.
+/// * `words` - byte order of cells wider than a byte, when known. Without
+///   it `0x` words are little endian, as `--group` writes them, and bare
+///   ones are in byte order, as `xxd` writes them.
+///
+/// Returns the number of bytes written.
+pub fn reverse_dump_words(
+    buf: &mut dyn BufRead,
+    out: &mut impl Write,
+    words: Option<Endian>,
+) -> io::Result<u64> {
+    let mut written = 0;
+    let span = reverse_blocks(buf, words, |_, at, bytes| {
+        io::copy(&mut io::repeat(0).take(at - written), out)?;
+        out.write_all(bytes)?;
+        written = at + bytes.len() as u64;
+        Ok(())
+    })?;
+    let len = span.map_or(0, |span| span.end - span.start);
+    io::copy(&mut io::repeat(0).take(len - written), out)?;
+    Ok(len)
+}
+
+/// Convert a dump back into bytes like `reverse_dump_words`, writing them
+/// at the offsets the lines state in an image file, which is neither
+/// truncated nor filled in between, so a partial dump patches it in place.
+/// Gaps past the end of the image are left sparse.
+///
+/// # Arguments
+///
+/// * `buf` - dump to be read.
+/// * `image` - file the bytes are written into.
+/// * `words` - byte order of cells wider than a byte, when known.
+///
+/// Returns the number of bytes from the first offset to the last.
+pub fn reverse_dump_into(
+    buf: &mut dyn BufRead,
+    image: &mut File,
+    words: Option<Endian>,
+) -> io::Result<u64> {
+    let span = reverse_blocks(buf, words, |first, at, bytes| {
+        image.seek(io::SeekFrom::Start(first + at))?;
+        image.write_all(bytes)
+    })?;
+    let Some(span) = span else {
+        return Ok(0);
+    };
+    if image.metadata()?.len() < span.end {
+        image.set_len(span.end)?;
+    }
+    Ok(span.end - span.start)
+}
+
+/// Read the lines of a dump, handing every run of bytes it shows to
+/// `place` with the offset of the first line and its own from there.
+/// Gaps between the runs are zeros, except after a `*` line, which
+/// repeats the line before it and is handed over as well.
+///
+/// # Arguments
+///
+/// * `buf` - dump to be read.
+/// * `words` - byte order of cells wider than a byte, when known.
+/// * `place` - writer of a run at an offset.
+///
+/// Returns the offsets the dump spans, None without any offset.
+fn reverse_blocks(
+    buf: &mut dyn BufRead,
+    words: Option<Endian>,
+    mut place: impl FnMut(u64, u64, &[u8]) -> io::Result<()>,
+) -> io::Result<Option<Range<u64>>> {
+    let mut base = None;
+    let mut written: u64 = 0;
+    // bytes of the last line, held back since an end offset may cut them
+    let mut pending: Vec<u8> = Vec::new();
+    let mut repeat = false;
+    // whether the lines are bare hex digits, as `--output plain` and
+    // `xxd -p` write them, and where their cells are, taken from the first one
+    let mut plain = None;
+    let mut layout = None;
+    for (number, line) in buf.split(b'\n').enumerate() {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        if line.trim().is_empty() {
+            continue;
+        }
+        if *plain.get_or_insert_with(|| parse_plain_line(&line).is_some()) {
+            let bytes = parse_plain_line(&line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: not plain hex like the lines before", number + 1),
+                )
+            })?;
+            place(*base.get_or_insert(0), written, &bytes)?;
+            written += bytes.len() as u64;
+            continue;
+        }
+        if line.trim() == "*" {
+            repeat = true;
+            continue;
+        }
+        let Some((at, bytes)) = parse_dump_line(&line, words, &mut layout) else {
+            continue;
+        };
+        let first = *base.get_or_insert(at);
+        let end = written + pending.len() as u64;
+        let relative = at
+            .checked_sub(first)
+            .filter(|relative| *relative >= written);
+        let at = match relative {
+            Some(at) if bytes.is_empty() && at < end => {
+                pending.truncate((at - written) as usize);
+                continue;
+            }
+            Some(at) if at >= end => at,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "line {}: offset {} goes back before {}",
+                        number + 1,
+                        offset(at),
+                        offset(first + end)
+                    ),
+                ))
+            }
+        };
+        if !pending.is_empty() {
+            place(first, written, &pending)?;
+            if repeat && at > end {
+                let fill: Vec<u8> = pending
+                    .iter()
+                    .cycle()
+                    .take((at - end) as usize)
+                    .copied()
+                    .collect();
+                place(first, end, &fill)?;
+            }
+        }
+        written = at;
+        pending = bytes;
+        repeat = false;
+    }
+    if let Some(first) = base.filter(|_| !pending.is_empty()) {
+        place(first, written, &pending)?;
+    }
+    Ok(base.map(|first| first..first + written + pending.len() as u64))
+}
+
+/// Bytes of a line of bare hex digits, None when it isn't one.
+///
+/// # Arguments
+///
+/// * `line` - dump line.
+fn parse_plain_line(line: &str) -> Option<Vec<u8>> {
+    let digits = line.trim();
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&digits[at..at + 2], 16).ok())
+        .collect()
+}
+
+/// Offset of a dump line from its offset token: `0x` hex ending in a
+/// colon or border as hx writes it, or at least four bare hex digits, with
+/// or without a colon, as `xxd` and `hexdump` write them at the start of
+/// a line.
+///
+/// # Arguments
+///
+/// * `token` - text up to the first whitespace.
+/// * `leading` - whether the token starts the line.
+fn parse_offset_token(token: &str, leading: bool) -> Option<u64> {
+    let (token, marked) = match token.strip_suffix([':', '|', '\u{2502}']) {
+        Some(token) => (token, true),
+        None => (token, false),
+    };
+    let (digits, prefixed) = match token.strip_prefix("0x") {
+        Some(digits) => (digits, true),
+        None => (token, false),
+    };
+    let shaped = match prefixed {
+        true => marked,
+        false => leading && digits.len() >= 4,
+    };
+    match shaped && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => u64::from_str_radix(digits, 16).ok(),
+        false => None,
+    }
+}
+
+/// How the byte cells of a dump line are written, taken from the first
+/// cell
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CellStyle {
+    /// hex digits, with the `0x` prefix or not
+    Hex(bool),
+    /// `0o` and three or four octal digits
+    Octal,
+    /// `0b` and eight binary digits
+    Binary,
+}
+
+/// What follows the cells of a dump line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Trailer {
+    /// nothing, the cells end the line
+    Bare,
+    /// a character panel of a glyph per byte
+    Glyphs,
+    /// a UTF-16 character panel of a glyph per two bytes
+    Pairs,
+}
+
+/// Where the cells of the lines of a dump are, taken from the first line
+/// so later ones, like a short last line, aren't guessed anew
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct CellLayout {
+    /// whether panels come before the cells
+    lead: bool,
+    /// what follows the cells
+    trailer: Trailer,
+}
+
+/// Bytes of one cell in a style, None when the token isn't such a cell.
+///
+/// # Arguments
+///
+/// * `token` - the cell text.
+/// * `style` - style of the line's cells.
+/// * `widest` - digits of the first cell, which no word may exceed.
+/// * `words` - byte order of words, when known.
+fn parse_cell(
+    token: &str,
+    style: CellStyle,
+    widest: usize,
+    words: Option<Endian>,
+) -> Option<Vec<u8>> {
+    let prefixed = |marker: &str| {
+        token
+            .strip_prefix(marker)
+            .or(token.strip_prefix(&marker.to_uppercase()))
+    };
+    let radix = |digits: &str, radix: u32| u8::from_str_radix(digits, radix).ok().map(|b| vec![b]);
+    let (digits, order) = match style {
+        CellStyle::Octal => {
+            return prefixed("0o")
+                .filter(|d| (3..=4).contains(&d.len()))
+                .and_then(|d| radix(d, 8))
+        }
+        CellStyle::Binary => {
+            return prefixed("0b")
+                .filter(|d| d.len() == 8)
+                .and_then(|d| radix(d, 2))
+        }
+        // a word cut short by the end of the input is written bare, in byte order
+        CellStyle::Hex(true) => match prefixed("0x") {
+            Some(digits) => (digits, words.unwrap_or(Endian::Little)),
+            None if token.len() < widest => (token, Endian::Big),
+            None => return None,
+        },
+        CellStyle::Hex(false) => (token, words.unwrap_or(Endian::Big)),
+    };
+    if digits.is_empty()
+        || digits.len() % 2 != 0
+        || digits.len() > widest
+        || !digits.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    let mut bytes: Vec<u8> = (0..digits.len())
+        .step_by(2)
+        .filter_map(|at| u8::from_str_radix(&digits[at..at + 2], 16).ok())
+        .collect();
+    if order == Endian::Little {
+        bytes.reverse();
+    }
+    Some(bytes)
+}
+
+/// Bytes of the cells of a dump line body. Cells with a prefix may follow
+/// a decimal or character panel of as many bytes, otherwise they start the
+/// body. The first line with cells sets the layout of the ones after it.
+///
+/// # Arguments
+///
+/// * `body` - the line after its offset.
+/// * `words` - byte order of words, when known.
+/// * `layout` - layout of the lines before, None before the first.
+fn parse_cells(body: &str, words: Option<Endian>, layout: &mut Option<CellLayout>) -> Vec<u8> {
+    let separator = |c: char| c.is_whitespace() || c == ',';
+    let body = body.trim_start_matches(separator);
+    let prefixed = |token: &str| {
+        matches!(
+            token.get(..2),
+            Some("0x" | "0X" | "0o" | "0O" | "0b" | "0B")
+        )
+    };
+    let trailer = layout.map(|layout| layout.trailer);
+    let lead = (!prefixed(body) && layout.is_none_or(|layout| layout.lead))
+        .then(|| {
+            (1..body.len())
+                .filter(|at| {
+                    body.is_char_boundary(*at)
+                        && body[..*at].ends_with(separator)
+                        && prefixed(&body[*at..])
+                })
+                .find_map(|at| {
+                    let (bytes, style, trailer) = parse_cell_run(&body[at..], words, trailer);
+                    // a character panel, padded on the last line, and a
+                    // decimal one on either side of it. Bare cells start
+                    // the body, even where one of them reads 0b.
+                    let text = body[..at].trim_end();
+                    let fits = [
+                        Some(text),
+                        strip_decimal(text, bytes.len(), false),
+                        strip_decimal(text, bytes.len(), true),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .any(|panel| panel.chars().count() <= bytes.len());
+                    (!bytes.is_empty() && style != CellStyle::Hex(false) && fits)
+                        .then_some((bytes, trailer))
+                })
+        })
+        .flatten();
+    let (bytes, trailer, lead) = match lead {
+        Some((bytes, trailer)) => (bytes, trailer, true),
+        None => {
+            let (bytes, _, trailer) = parse_cell_run(body, words, trailer);
+            (bytes, trailer, false)
+        }
+    };
+    if layout.is_none() && !bytes.is_empty() {
+        *layout = Some(CellLayout { lead, trailer });
+    }
+    bytes
+}
+
+/// Text left after `count` decimal byte values at its start or its end,
+/// None when it doesn't have them.
+///
+/// # Arguments
+///
+/// * `text` - panels before the cells of a line.
+/// * `count` - bytes of the line.
+/// * `from_end` - whether the decimal values end the text.
+fn strip_decimal(text: &str, count: usize, from_end: bool) -> Option<&str> {
+    let mut rest = text;
+    for _ in 0..count {
+        let token = match from_end {
+            true => rest.trim_end().rsplit(' ').next()?,
+            false => rest.trim_start().split(' ').next()?,
+        };
+        if token.is_empty() || token.len() > 3 || !token.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        rest = match from_end {
+            true => rest.trim_end().strip_suffix(token)?,
+            false => rest.trim_start().strip_prefix(token)?,
+        };
+    }
+    Some(rest.trim())
+}
+
+/// Bytes of the cells at the start of a dump line body, their style and
+/// what follows them. The cells end where the rest of the body reads as
+/// the panel after them for that many bytes, or at the first token that
+/// isn't a cell in the style of the first one.
+///
+/// # Arguments
+///
+/// * `body` - the line after its offset, from its first cell.
+/// * `words` - byte order of words, when known.
+/// * `trailer` - what follows the cells of the first line, None on it.
+fn parse_cell_run(
+    body: &str,
+    words: Option<Endian>,
+    trailer: Option<Trailer>,
+) -> (Vec<u8>, CellStyle, Trailer) {
+    let separator = |c: char| c.is_whitespace() || c == ',';
+    let first = body.split(separator).next().unwrap_or_default();
+    let binary = first.len() == 10
+        && first
+            .get(2..)
+            .is_some_and(|digits| digits.chars().all(|c| c == '0' || c == '1'));
+    let style = match first.get(..2) {
+        Some("0x" | "0X") => CellStyle::Hex(true),
+        Some("0o" | "0O") => CellStyle::Octal,
+        Some("0b" | "0B") if binary => CellStyle::Binary,
+        _ => CellStyle::Hex(false),
+    };
+    let (bytes, cells, found) = parse_cell_run_in(body, style, words, trailer);
+    if style != CellStyle::Binary {
+        return (bytes, style, found);
+    }
+    // bare hex words may start with 0b too, so binary cells must be all
+    // of the run
+    let bare = CellStyle::Hex(false);
+    let (hex, hex_cells, hex_found) = parse_cell_run_in(body, bare, words, trailer);
+    match hex_cells > cells {
+        true => (hex, bare, hex_found),
+        false => (bytes, style, found),
+    }
+}
+
+/// Bytes of the cells at the start of a dump line body in one style, as
+/// `parse_cell_run` reads them, the number of cells read and what follows
+/// them.
+///
+/// # Arguments
+///
+/// * `body` - the line after its offset, from its first cell.
+/// * `style` - style of the cells.
+/// * `words` - byte order of words, when known.
+/// * `trailer` - what follows the cells of the first line, None on it.
+fn parse_cell_run_in(
+    body: &str,
+    style: CellStyle,
+    words: Option<Endian>,
+    trailer: Option<Trailer>,
+) -> (Vec<u8>, usize, Trailer) {
+    let separator = |c: char| c.is_whitespace() || c == ',';
+    let mut rest = body;
+    let first = rest.split(separator).next().unwrap_or_default();
+    let widest = match style {
+        CellStyle::Hex(true) => first.get(2..).map_or(0, |digits| {
+            digits.split("0x").next().unwrap_or_default().len()
+        }),
+        _ => first.len(),
+    };
+    // bytes, and what follows each count of them
+    let mut bytes = Vec::new();
+    let mut rests = vec![(0, rest)];
+    loop {
+        let token = rest.split(separator).next().unwrap_or_default();
+        // cells written without a separator between them
+        let cells: Vec<&str> = match style {
+            CellStyle::Hex(true) if token.len() > 2 + widest => token
+                .match_indices("0x")
+                .map(|(at, _)| at)
+                .chain([token.len()])
+                .collect::<Vec<usize>>()
+                .windows(2)
+                .map(|pair| &token[pair[0]..pair[1]])
+                .collect(),
+            _ => vec![token],
+        };
+        let parsed: Option<Vec<Vec<u8>>> = cells
+            .iter()
+            .map(|cell| parse_cell(cell, style, widest, words))
+            .collect();
+        match parsed {
+            Some(parsed) if !token.is_empty() => {
+                bytes.extend(parsed.concat());
+                rest = rest[token.len()..].trim_start_matches(separator);
+                rests.push((bytes.len(), rest));
+            }
+            _ => break,
+        }
+    }
+    let cells = rests.len() - 1;
+    let panel = |glyphs: fn(usize) -> usize| {
+        rests
+            .iter()
+            .rev()
+            .find(|(count, rest)| *count > 0 && rest.chars().count() == glyphs(*count))
+            .map(|(count, _)| *count)
+    };
+    let glyphs = || panel(|count| count);
+    let pairs = || panel(|count| count.div_ceil(2));
+    // on the first line a glyph per byte, then cells ending the line, then
+    // a glyph per two bytes of UTF-16. Other text after the cells is taken
+    // for a character panel with its trailing spaces trimmed.
+    let (count, trailer) = match trailer {
+        Some(Trailer::Bare) => (None, Trailer::Bare),
+        Some(Trailer::Glyphs) => (glyphs(), Trailer::Glyphs),
+        Some(Trailer::Pairs) => (pairs(), Trailer::Pairs),
+        None => match glyphs() {
+            Some(count) => (Some(count), Trailer::Glyphs),
+            None if rest.is_empty() => (None, Trailer::Bare),
+            None => match pairs() {
+                Some(count) => (Some(count), Trailer::Pairs),
+                None => (None, Trailer::Glyphs),
+            },
+        },
+    };
+    bytes.truncate(count.unwrap_or(bytes.len()));
+    (bytes, cells, trailer)
+}
+
+/// Offset and bytes of a dump line, None when it has no offset. The
+/// offset leads the line, after a border, or else ends in a colon after
+/// the cells, with `--panels` putting it last.
+///
+/// # Arguments
+///
+/// * `line` - dump line, possibly colored.
+/// * `words` - byte order of words, when known.
+/// * `layout` - layout of the lines before, None before the first.
+fn parse_dump_line(
+    line: &str,
+    words: Option<Endian>,
+    layout: &mut Option<CellLayout>,
+) -> Option<(u64, Vec<u8>)> {
+    let line = strip_colors(line);
+    // a panel of trailing spaces can't be told from the padding after the cells
+    let text = line.trim().trim_start_matches(['|', '\u{2502}']);
+    let lead = text.split(char::is_whitespace).next()?;
+    if let Some(at) = parse_offset_token(lead, true) {
+        return Some((at, parse_cells(&text[lead.len()..], words, layout)));
+    }
+    let (start, token) = text
+        .split(char::is_whitespace)
+        .scan(0, |at, token| {
+            let start = *at;
+            *at += token.len() + 1;
+            Some((start, token))
+        })
+        .find(|(_, token)| token.ends_with(':') && parse_offset_token(token, false).is_some())?;
+    let at = parse_offset_token(token, false)?;
+    let after = parse_cells(&text[start + token.len()..], words, layout);
+    match after.is_empty() {
+        true => Some((at, parse_cells(text[..start].trim_end(), words, layout))),
+        false => Some((at, after)),
+    }
+}
+
+/// Text without ANSI color codes.
+///
+/// # Arguments
+///
+/// * `text` - possibly colored text.
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                // up to the letter ending the sequence
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+/// Write one dump line: its panels in the configured order, by default
+/// offset, byte cells, character panel and annotations, in ANSI colors
+/// when the options ask for them.
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `line` - line to write.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+pub fn write_line(
+    out: &mut impl Write,
+    line: &Line,
+    lead: u64,
+    opts: &DumpOptions,
+) -> io::Result<()> {
+    if !opts.colorize {
+        // plain text has no styles to keep, so skip building the spans
+        let mut text = String::new();
+        add_line_spans(&mut text, line, lead, opts);
+        text.push('\n');
+        return out.write_all(text.as_bytes());
+    }
+    Backend::Ansi.write(out, &line_spans(line, lead, opts))?;
+    writeln!(out)
+}
+
+/// Styled spans of one dump line, for a backend to write: its panels in
+/// the configured order, without the line ending. Styles are only set
+/// when the options colorize.
+/// # Arguments
+///
+/// * `line` - line to render.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+pub fn line_spans(line: &Line, lead: u64, opts: &DumpOptions) -> Vec<Span> {
+    let mut spans = Vec::new();
+    add_line_spans(&mut spans, line, lead, opts);
+    spans
+}
+
+/// Add the spans of one dump line, like `line_spans`.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `line` - line to render.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+fn add_line_spans(spans: &mut impl SpanSink, line: &Line, lead: u64, opts: &DumpOptions) {
+    let bordered = opts.border != Border::None;
+    for (i, panel) in opts.panels.iter().enumerate() {
+        // annotations go past the right border
+        if bordered && *panel != Panel::Annotations {
+            spans.push(opts.border.bar(), Style::new(), Role::Border);
+        }
+        match panel {
+            Panel::Offset => {
+                let address = opts.banks.translate(line.offset);
+                let style = match line.boundary && opts.colorize {
+                    true => Style::new().bold().reverse(),
+                    false => Style::new(),
+                };
+                spans.push(&offset(address), style, Role::Offset);
+                if let Some(source) = opts.source {
+                    let (at, compressed) = source.translate(line.offset);
+                    let mark = if compressed { "~" } else { "" };
+                    let text = format!(" @{mark}{}", offset(at));
+                    spans.push(&text, Style::new(), Role::Offset);
+                }
+                if !bordered {
+                    spans.push(": ", Style::new(), Role::Space);
+                }
+            }
+            Panel::Hex | Panel::Decimal if bordered => {
+                spans.pad(1);
+                match panel {
+                    Panel::Hex => byte_cell_spans(spans, line, lead, opts),
+                    _ => decimal_cell_spans(spans, line, lead, opts),
+                }
+            }
+            Panel::Hex => byte_cell_spans(spans, line, lead, opts),
+            Panel::Decimal => decimal_cell_spans(spans, line, lead, opts),
+            Panel::Ascii if bordered => char_panel_spans(spans, line, lead, opts, true),
+            Panel::Ascii => {
+                // keep the panel apart from bytes when the separator doesn't
+                let after_cells = i > 0 && opts.panels[i - 1].is_cells();
+                if after_cells && !opts.separator.ends_with(char::is_whitespace) {
+                    spans.pad(1);
+                }
+                // annotations follow the text as they are, without padding
+                let followed = opts.panels[i + 1..]
+                    .iter()
+                    .any(|panel| *panel != Panel::Annotations);
+                char_panel_spans(spans, line, lead, opts, followed);
+                if followed {
+                    spans.pad(1);
+                }
+            }
+            Panel::Annotations if bordered => {}
+            Panel::Annotations => note_spans(spans, line),
+        }
+    }
+    if bordered {
+        spans.push(opts.border.bar(), Style::new(), Role::Border);
+        for _ in opts
+            .panels
+            .iter()
+            .filter(|panel| **panel == Panel::Annotations)
+        {
+            note_spans(spans, line);
+        }
+    }
+}
+
+/// Add the margin notes of a line, if it has any.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `line` - line to render.
+fn note_spans(spans: &mut impl SpanSink, line: &Line) {
+    if !line.notes.is_empty() {
+        let notes = format!("  # {}", line.notes.join(", "));
+        spans.push(&notes, Style::new(), Role::Note);
+    }
+}
+
+/// Write a rule of a bordered dump across its panels, annotations aside.
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `opts` - dump layout and border.
+/// * `edge` - the rule above, between or below the lines.
+pub fn write_rule(out: &mut impl Write, opts: &DumpOptions, edge: Edge) -> io::Result<()> {
+    let word = opts.group.word(opts.format) as u64;
+    let cell = opts.group.cell_width(opts.format, opts.prefix) + opts.separator.len();
+    let widths: Vec<usize> = opts
+        .panels
+        .iter()
+        .filter_map(|panel| match panel {
+            Panel::Offset => Some(offset(0).len()),
+            Panel::Hex => Some(1 + cell * opts.column_width.div_ceil(word) as usize),
+            Panel::Decimal => Some(1 + (3 + opts.separator.len()) * opts.column_width as usize),
+            Panel::Ascii => Some(char_slots(opts.charset, opts.column_width) as usize),
+            Panel::Annotations => None,
+        })
+        .collect();
+    writeln!(out, "{}", opts.border.rule(edge, &widths))
+}
+
+/// Add the byte cells of a line in the dump format, padded to the column
+/// width.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `line` - line to render.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+fn byte_cell_spans(spans: &mut impl SpanSink, line: &Line, lead: u64, opts: &DumpOptions) {
+    let cell = opts.group.cell_width(opts.format, opts.prefix) + opts.separator.len();
+    spans.pad(lead as usize * cell);
+    // a cell and its separator, or blanks across both for a hidden one
+    let mut push = |text: String, style: Option<Style>| match style {
+        Some(style) => {
+            spans.push(&text, style, Role::Cell);
+            pad_with(spans, opts.separator);
+        }
+        None => spans.pad(text.len() + opts.separator.len()),
+    };
+    let word = opts.group.word(opts.format);
+    for (i, hex) in line.hex_body.iter().enumerate() {
+        let column = lead + i as u64;
+        let hidden = opts.only.is_some_and(|only| !only.contains(*hex));
+        if word > 1 {
+            if i.is_multiple_of(word) {
+                let cell = &line.hex_body[i..line.hex_body.len().min(i + word)];
+                let text = opts.group.format_word(opts.format, cell, opts.prefix);
+                let address = line.offset + column;
+                let found = (0..cell.len() as u64).any(|i| is_found(opts, address + i));
+                let shown = opts
+                    .only
+                    .is_none_or(|only| cell.iter().any(|b| only.contains(*b)));
+                match opts.group.word_value(opts.format, cell) {
+                    _ if !shown && opts.colorize => push(text, Some(Style::new().dimmed())),
+                    _ if !shown => push(text, None),
+                    _ if opts.colorize && found => push(text, Some(found_style())),
+                    Some(value) if opts.colorize => {
+                        push(text, Some(highlighted(opts, value, word_to_color(value))))
+                    }
+                    _ => push(text, Some(Style::new())),
+                }
+            }
+            continue;
+        }
+        let text = opts.format.format(*hex, opts.prefix);
+        let color = byte_to_color(*hex);
+        let style = if hidden && opts.colorize {
+            Some(Style::new().fg(color).dimmed())
+        } else if hidden {
+            None
+        } else if opts.colorize && is_found(opts, line.offset + column) {
+            Some(found_style())
+        } else if opts.colorize && is_highlighted(opts, f64::from(*hex)) {
+            Some(highlighted(opts, f64::from(*hex), color))
+        } else if opts.colorize && opts.stripe > 0 && (column / opts.stripe) % 2 == 1 {
+            Some(Style::new().fg(color).dimmed())
+        } else if opts.colorize {
+            Some(Style::new().fg(color))
+        } else {
+            Some(Style::new())
+        };
+        push(text, style);
+    }
+    if lead + line.bytes < opts.column_width {
+        let word = word as u64;
+        let missing = opts.column_width.div_ceil(word) - (lead + line.bytes).div_ceil(word);
+        spans.pad(cell * missing as usize);
+    }
+}
+
+/// Add a separator to the spans of a line, unless it's empty.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `text` - separator text.
+fn pad_with(spans: &mut impl SpanSink, text: &str) {
+    if !text.is_empty() {
+        spans.push(text, Style::new(), Role::Space);
+    }
+}
+
+/// Style of the bytes of `--find` and `--pointer-scan` hits, apart from every byte color.
+fn found_style() -> Style {
+    Style::new().fg(Color::Black).on(Color::Yellow).bold()
+}
+
+/// Whether the byte at an offset is part of a `--find` or
+/// `--pointer-scan` hit.
+/// # Arguments
+///
+/// * `opts` - dump layout and styling.
+/// * `address` - offset of the byte in the input.
+fn is_found(opts: &DumpOptions, address: u64) -> bool {
+    let next = opts.found.partition_point(|range| range.end <= address);
+    opts.found
+        .get(next)
+        .is_some_and(|range| range.contains(&address))
+}
+
+/// Whether a cell value lies within the `--highlight` bounds.
+/// # Arguments
+///
+/// * `opts` - dump layout and styling.
+/// * `value` - byte or float word.
+fn is_highlighted(opts: &DumpOptions, value: f64) -> bool {
+    opts.highlight
+        .is_some_and(|(min, max)| min <= value && value <= max)
+}
+
+/// Style of a colored cell, bold and reversed when highlighted.
+/// # Arguments
+///
+/// * `opts` - dump layout and styling.
+/// * `value` - byte or float word.
+/// * `color` - color of the cell.
+fn highlighted(opts: &DumpOptions, value: f64, color: Color) -> Style {
+    let style = Style::new().fg(color);
+    match is_highlighted(opts, value) {
+        true => style.bold().reverse(),
+        false => style,
+    }
+}
+
+/// Add the byte cells of a line in decimal, padded to the column width.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `line` - line to render.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+fn decimal_cell_spans(spans: &mut impl SpanSink, line: &Line, lead: u64, opts: &DumpOptions) {
+    let cell = 3 + opts.separator.len();
+    spans.pad(lead as usize * cell);
+    for (i, b) in line.hex_body.iter().enumerate() {
+        let text = format!("{b:>3}");
+        let address = line.offset + lead + i as u64;
+        let hidden = opts.only.is_some_and(|only| !only.contains(*b));
+        let style = match opts.colorize {
+            true if hidden => Style::new().dimmed(),
+            false if hidden => {
+                spans.pad(cell);
+                continue;
+            }
+            true if is_found(opts, address) => found_style(),
+            true => Style::new().fg(byte_to_color(*b)),
+            false => Style::new(),
+        };
+        spans.push(&text, style, Role::Cell);
+        pad_with(spans, opts.separator);
+    }
+    let missing = opts.column_width.saturating_sub(lead + line.bytes);
+    spans.pad(cell * missing as usize);
+}
+
+/// Add the character panel of a line.
+/// # Arguments
+///
+/// * `spans` - spans of the line.
+/// * `line` - line to render.
+/// * `lead` - blank cells before the first byte.
+/// * `opts` - dump layout and styling.
+/// * `padded` - pad it to the column width.
+fn char_panel_spans(
+    spans: &mut impl SpanSink,
+    line: &Line,
+    lead: u64,
+    opts: &DumpOptions,
+    padded: bool,
+) {
+    let slots = |bytes: u64| char_slots(opts.charset, bytes);
+    spans.pad(slots(lead) as usize);
+    let bytes = match opts.only {
+        Some(only) => only.blank(&line.hex_body, opts.charset),
+        None => line.hex_body.clone(),
+    };
+    let glyphs = panel_spans(spans, &bytes, opts.charset, opts.colorize);
+    if padded {
+        let width = slots(opts.column_width).saturating_sub(slots(lead) + glyphs as u64);
+        spans.pad(width as usize);
+    }
+}
+
+/// Characters the character panel takes for a number of bytes.
+/// # Arguments
+///
+/// * `charset` - character set of the panel.
+/// * `bytes` - bytes shown.
+fn char_slots(charset: Charset, bytes: u64) -> u64 {
+    match charset {
+        Charset::Ascii => bytes,
+        _ => bytes.div_ceil(2),
+    }
+}
+
+/// Print the `--header` metadata block: file name, size, modification
+/// time, detected type, dump options and optionally the sha-256 of the
+/// file. Size, time and hash are only known for files, not stdin.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `config` - parsed command line.
+/// * `source` - input source.
+/// * `head` - leading bytes of the input, for type detection.
+fn write_header<W: Write>(
+    out: &mut OutputWriter<W>,
+    config: &Config,
+    source: &InputSource,
+    head: &[u8],
+) -> io::Result<()> {
+    let path = source.path();
+    writeln!(
+        out,
+        "{:>8}: {}",
+        "file",
+        path.map_or("-".into(), Path::to_string_lossy)
+    )?;
+    if source.capabilities().live {
+        log::warn(format_args!(
+            "{} is a pipe or device, --header leaves out its size",
+            source.name()
+        ));
+    } else if let Some(path) = path {
+        let meta = std::fs::metadata(path)?;
+        writeln!(out, "{:>8}: {}", "size", config.format_size(meta.len()))?;
+        let modified = meta.modified()?.duration_since(std::time::UNIX_EPOCH);
+        if let (Ok(since), false) = (modified, config.reproducible) {
+            let time = pcap::format_timestamp(since.as_secs(), 0, 0);
+            writeln!(out, "{:>8}: {time} UTC", "modified")?;
+        }
+    }
+    writeln!(
+        out,
+        "{:>8}: {}",
+        "type",
+        magic::detect(head).unwrap_or("data")
+    )?;
+    let format = match config.format {
+        Format::Octal => "o",
+        Format::UpperHex => "X",
+        Format::Binary => "b",
+        _ => "x",
+    };
+    let mut options = format!("-c {} -f {format}", config.columns);
+    if config.skip > 0 {
+        options.push_str(&format!(" -s {}", offset(config.skip)));
+    }
+    if config.truncate_len > 0 {
+        options.push_str(&format!(" -l {}", config.truncate_len));
+    }
+    writeln!(out, "{:>8}: {options}", "options")?;
+    if let (Some(path), true) = (path, config.header_hash) {
+        writeln!(out, "{:>8}: {}", "sha256", hash::sha256_file(path)?)?;
+    }
+    out.end_line()
+}
+
+/// print the byte count summary line
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `bytes` - number of bytes dumped.
+/// * `raw_sizes` - print the plain count instead of grouped digits and IEC units.
+pub fn print_summary(w: &mut impl Write, bytes: u64, raw_sizes: bool) -> io::Result<()> {
+    writeln!(
+        w,
+        "{:>8}: {}",
+        tr(Msg::Bytes),
+        format_size(bytes, raw_sizes)
+    )?;
+    w.flush()
+}
+
+/// print the byte count summary line in the configured language and units
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `config` - parsed command line.
+/// * `bytes` - number of bytes dumped.
+fn write_summary(w: &mut impl Write, config: &Config, bytes: u64) -> io::Result<()> {
+    if log::json() {
+        log::event(Level::Report, "summary", &[("bytes", Field::Number(bytes))]);
+        return w.flush();
+    }
+    writeln!(
+        w,
+        "{:>8}: {}",
+        config.tr(Msg::Bytes),
+        config.format_size(bytes)
+    )?;
+    w.flush()
+}
+
+/// Output source code array format.
+/// # Arguments
+///
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `buf` - BufRead.
+/// * `truncate_len` - truncate to length.
+/// * `column_width` - column width.
+///
+/// Returns the number of bytes written out as array elements.
+pub fn output_array(
+    array_format: &str,
+    mut buf: Box<dyn BufRead>,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<u64> {
+    let stdout = io::stdout();
+    let mut locked = stdout.lock();
+    write_array(
+        &mut locked,
+        array_format,
+        &mut buf,
+        truncate_len,
+        column_width,
+    )
+}
+
+/// Write source code array format to a writer.
+/// # Arguments
+///
+/// * `locked` - output writer.
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `buf` - input to be read.
+/// * `truncate_len` - truncate to length.
+/// * `column_width` - column width.
+///
+/// Returns the number of bytes written out as array elements.
+pub fn write_array(
+    locked: &mut impl Write,
+    array_format: &str,
+    buf: &mut dyn Read,
+    truncate_len: u64,
+    column_width: u64,
+) -> io::Result<u64> {
+    let opts = DumpOptions {
+        format: Format::LowerHex,
+        colorize: false,
+        prefix: true,
+        truncate_len,
+        column_width,
+        start_offset: 0,
+        align: false,
+        stripe: 0,
+        highlight: None,
+        charset: Charset::Ascii,
+        only: None,
+        boundary: 0,
+        bookmarks: &[],
+        found: &[],
+        symbols: &[],
+        lines: &LineTable::default(),
+        banks: Banks::default(),
+        source: None,
+        separator: " ",
+        group: Group::default(),
+        timestamps: None,
+        panels: DEFAULT_PANELS,
+        border: Border::None,
+    };
+    let mut out = OutputWriter::new(locked, FlushPolicy::Block);
+    emit(
+        &mut out,
+        &mut ArrayFormat::new(array_format, column_width, None),
+        buf,
+        &opts,
+    )
+}
+
+/// Write a row of a source code array, every element followed by its
+/// separator but the last one of the array.
+///
+/// # Arguments
+///
+/// * `locked` - output writer.
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `row` - elements of the row.
+/// * `last` - the row ends the array.
+pub fn write_array_row(
+    locked: &mut impl Write,
+    array_format: &str,
+    row: &[u8],
+    last: bool,
+) -> io::Result<()> {
+    write!(locked, "    ")?;
+    for (i, hex) in row.iter().enumerate() {
+        let hex = Format::LowerHex.format(*hex, true);
+        // golang wants the separator after the last element too
+        let separator = !last || i + 1 < row.len() || array_format == "g";
+        match (array_format, separator) {
+            ("f", true) => write!(locked, "{hex}uy; ")?,
+            ("f", false) => write!(locked, "{hex}uy")?,
+            (_, true) => write!(locked, "{hex}, ")?,
+            (_, false) => write!(locked, "{hex}")?,
+        }
+    }
+    writeln!(locked)
+}
+
+/// Write the declaration opening a source code array.
+///
+/// # Arguments
+///
+/// * `locked` - output writer.
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `bytes` - number of elements, for formats that declare it.
+pub fn write_array_header(
+    locked: &mut impl Write,
+    array_format: &str,
+    bytes: u64,
+) -> io::Result<()> {
+    match array_format {
+        "r" => writeln!(locked, "let ARRAY: [u8; {bytes}] = ["),
+        "c" => writeln!(locked, "unsigned char ARRAY[{bytes}] = {{"),
+        "g" => writeln!(locked, "a := [{bytes}]byte{{"),
+        "p" => writeln!(locked, "a = ["),
+        "k" => writeln!(locked, "val a = byteArrayOf("),
+        "j" => writeln!(locked, "byte[] a = new byte[]{{"),
+        "s" => writeln!(locked, "let a: [UInt8] = ["),
+        "f" => writeln!(locked, "let a = [|"),
+        _ => writeln!(locked, "unknown array format"),
+    }
+}
+
+/// Write what closes a source code array.
+///
+/// # Arguments
+///
+/// * `locked` - output writer.
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+pub fn write_array_footer(locked: &mut impl Write, array_format: &str) -> io::Result<()> {
+    writeln!(
+        locked,
+        "{}",
+        match array_format {
+            "r" => "];",
+            "c" | "j" => "};",
+            "g" => "}",
+            "p" => "]",
+            "k" => ")",
+            "s" => "]",
+            "f" => "|]",
+            _ => "unknown array format",
+        }
+    )
+}
+
+/// Wrap concatenated `--fragment` arrays into one declaration, counting
+/// their elements for the formats that declare the length. The fragments
+/// are copied as they are; their trailing separator is valid in every
+/// array format.
+///
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `array_format` - array format, rust (r), C (c), golang (g).
+/// * `buf` - concatenated fragments.
+fn finalize_array<W: Write>(
+    out: &mut OutputWriter<W>,
+    array_format: &str,
+    buf: &mut dyn Read,
+) -> io::Result<u64> {
+    let mut text = String::new();
+    buf.read_to_string(&mut text)?;
+    let bytes = text.matches("0x").count() as u64;
+    write_array_header(out, array_format, bytes)?;
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        writeln!(out, "{line}")?;
+    }
+    write_array_footer(out, array_format)?;
+    out.flush()?;
+    Ok(bytes)
+}
+
+/// Values of the `--func` wave: a quarter sine rising from 0 towards 1.
+///
+/// # Arguments
+///
+/// * `len` - Wave length.
+pub fn function_wave(len: u64) -> impl Iterator<Item = f64> {
+    (0..len).map(move |y| (((y as f64 / len as f64) * f64::consts::PI) / 2.0).sin())
+}
+
+/// Function wave scaled to bytes, 0 to 255, for `--func-output bytes`.
+///
+/// # Arguments
+///
+/// * `len` - Wave length.
+pub fn function_bytes(len: u64) -> Vec<u8> {
+    function_wave(len)
+        .map(|x| (x * 255.0).round() as u8)
+        .collect()
+}
+
+/// Function wave out, comma separated with ten values per line, each in
+/// the color of the byte it scales to when colored.
+/// # Arguments
+///
+/// * `out` - output writer.
+/// * `len` - Wave length.
+/// * `places` - Number of decimal places for function wave floats.
+/// * `colorize` - color the values.
+pub fn output_function(
+    out: &mut dyn Write,
+    len: u64,
+    places: usize,
+    colorize: bool,
+) -> io::Result<()> {
+    for (y, x) in function_wave(len).enumerate() {
+        let formatted_number = format!("{:.*}", places, x);
+        match colorize {
+            true => {
+                let color = byte_to_color((x * 255.0).round() as u8);
+                write!(out, "{}", color.paint(formatted_number))?;
+            }
+            false => write!(out, "{formatted_number}")?,
+        }
+        write!(out, ",")?;
+        if (y % 10) == 9 {
+            writeln!(out)?;
+        }
+    }
+    writeln!(out)
+}
+
+/// Read up to `len` bytes into the hex body of `line`, returning the number
+/// of bytes read. A short count means the input is exhausted.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `line` - Line to fill.
+/// * `len` - maximum number of bytes for the line.
+pub fn read_line(buf: &mut dyn Read, line: &mut Line, len: u64) -> io::Result<u64> {
+    let start = line.hex_body.len();
+    buf.take(len).read_to_end(&mut line.hex_body)?;
+    let read = (line.hex_body.len() - start) as u64;
+    line.bytes += read;
+    Ok(read)
 }
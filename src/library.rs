@@ -0,0 +1,370 @@
+//! programmatic surface for embedding hx output in other tools: a
+//! `HexConfig` built in code instead of parsed from a command line, and
+//! dumps and arrays written to any reader and writer. The `hx` command
+//! doesn't go through it, `run_config` dumps a whole `Config`.
+
+use crate::args::{Config, ARRAY_FORMATS};
+use crate::bank::Banks;
+use crate::charset::{ByteClass, Charset};
+use crate::dwarf::LineTable;
+use crate::format::{Format, Group};
+use crate::formats::{emit, ArrayFormat, DumpFormat, OutputFormat};
+use crate::observe::{ObservedReader, Observer};
+use crate::panel::{Border, Panel, DEFAULT_PANELS};
+use crate::style::Span;
+use crate::{discard, line_spans, DumpOptions, FlushPolicy, Line, OutputWriter};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Line table of a dump without source lines
+static NO_LINES: LineTable = LineTable {
+    files: Vec::new(),
+    rows: Vec::new(),
+};
+
+/// Dump layout and styling for `dump` and `dump_array`, with the defaults
+/// of the `hx` command line and chained setters for the rest
+#[derive(Clone, Debug)]
+pub struct HexConfig {
+    /// bytes per line, 0 for everything on one line
+    pub columns: u64,
+    /// how every byte cell is written
+    pub format: Format,
+    /// color the cells and characters with terminal escape codes
+    pub colorize: bool,
+    /// include the format prefix with every byte
+    pub prefix: bool,
+    /// bytes dumped, 0 for all of them
+    pub truncate_len: u64,
+    /// bytes skipped before the dump, offsets count from the start
+    pub skip: u64,
+    /// text after every byte cell
+    pub separator: String,
+    /// bytes shown as one integer word per cell
+    pub group: Group,
+    /// dim every other group of this many byte columns, 0 for no stripes
+    pub stripe: u64,
+    /// cells whose value lies within these bounds stand out, when colored
+    pub highlight: Option<(f64, f64)>,
+    /// character set of the character panel
+    pub charset: Charset,
+    /// bytes shown, the others blank or dimmed when colored, None for all
+    pub only: Option<ByteClass>,
+    /// panels of every line, in order
+    pub panels: Vec<Panel>,
+    /// lines drawn around the panels
+    pub border: Border,
+}
+
+impl Default for HexConfig {
+    fn default() -> HexConfig {
+        HexConfig {
+            columns: 10,
+            format: Format::LowerHex,
+            colorize: false,
+            prefix: true,
+            truncate_len: 0,
+            skip: 0,
+            separator: " ".to_string(),
+            group: Group::default(),
+            stripe: 0,
+            highlight: None,
+            charset: Charset::Ascii,
+            only: None,
+            panels: DEFAULT_PANELS.to_vec(),
+            border: Border::None,
+        }
+    }
+}
+
+/// HexConfig implementation
+impl HexConfig {
+    /// HexConfig constructor, the defaults of `hx` without options.
+    pub fn new() -> HexConfig {
+        HexConfig::default()
+    }
+
+    /// Set the bytes per line.
+    pub fn columns(mut self, columns: u64) -> HexConfig {
+        self.columns = columns;
+        self
+    }
+
+    /// Set how every byte cell is written.
+    pub fn format(mut self, format: Format) -> HexConfig {
+        self.format = format;
+        self
+    }
+
+    /// Set whether terminal colors are written.
+    pub fn colorize(mut self, colorize: bool) -> HexConfig {
+        self.colorize = colorize;
+        self
+    }
+
+    /// Set whether every byte has the format prefix.
+    pub fn prefix(mut self, prefix: bool) -> HexConfig {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set the number of bytes dumped, 0 for all.
+    pub fn truncate_len(mut self, len: u64) -> HexConfig {
+        self.truncate_len = len;
+        self
+    }
+
+    /// Set the number of bytes skipped first.
+    pub fn skip(mut self, skip: u64) -> HexConfig {
+        self.skip = skip;
+        self
+    }
+
+    /// Set the text after every byte cell.
+    pub fn separator(mut self, separator: &str) -> HexConfig {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Set the bytes shown as one integer word per cell.
+    pub fn group(mut self, group: Group) -> HexConfig {
+        self.group = group;
+        self
+    }
+
+    /// Set the width of the dimmed column groups, 0 for none.
+    pub fn stripe(mut self, stripe: u64) -> HexConfig {
+        self.stripe = stripe;
+        self
+    }
+
+    /// Set the bounds of the values whose cells stand out, None for none.
+    pub fn highlight(mut self, highlight: Option<(f64, f64)>) -> HexConfig {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Set the character set of the character panel.
+    pub fn charset(mut self, charset: Charset) -> HexConfig {
+        self.charset = charset;
+        self
+    }
+
+    /// Set the bytes shown, None for all.
+    pub fn only(mut self, only: Option<ByteClass>) -> HexConfig {
+        self.only = only;
+        self
+    }
+
+    /// Set the panels of every line, in order.
+    pub fn panels(mut self, panels: &[Panel]) -> HexConfig {
+        self.panels = panels.to_vec();
+        self
+    }
+
+    /// Set the lines drawn around the panels.
+    pub fn border(mut self, border: Border) -> HexConfig {
+        self.border = border;
+        self
+    }
+
+    /// Dump options for the layout, without notes or bank translation.
+    fn options(&self) -> DumpOptions<'_> {
+        DumpOptions {
+            format: self.format,
+            colorize: self.colorize,
+            prefix: self.prefix,
+            truncate_len: self.truncate_len,
+            column_width: self.columns,
+            start_offset: self.skip,
+            align: false,
+            stripe: self.stripe,
+            highlight: self.highlight,
+            charset: self.charset,
+            only: self.only,
+            boundary: 0,
+            bookmarks: &[],
+            found: &[],
+            symbols: &[],
+            lines: &NO_LINES,
+            banks: Banks::default(),
+            source: None,
+            separator: &self.separator,
+            group: self.group,
+            timestamps: None,
+            panels: &self.panels,
+            border: self.border,
+        }
+    }
+}
+
+impl From<&Config> for HexConfig {
+    /// The dump layout of a parsed command line, colored only when it asks
+    /// for color. Bookmarks, symbols, searches and the other options only
+    /// `run_config` knows are left out.
+    fn from(config: &Config) -> HexConfig {
+        HexConfig {
+            columns: config.columns,
+            format: config.format,
+            colorize: config.color.unwrap_or(false),
+            prefix: config.prefix,
+            truncate_len: config.truncate_len,
+            skip: config.skip,
+            separator: config.separator.clone(),
+            group: config.group,
+            stripe: config.stripe,
+            highlight: config.highlight,
+            charset: config.charset,
+            only: config.only,
+            panels: config.panels.clone(),
+            border: config.border,
+        }
+    }
+}
+
+/// Write a hex dump of a reader, without the summary line.
+///
+/// # Arguments
+///
+/// * `reader` - input to be dumped.
+/// * `writer` - where the dump goes.
+/// * `config` - dump layout and styling.
+///
+/// Returns the number of bytes dumped.
+pub fn dump(reader: &mut dyn Read, writer: &mut dyn Write, config: &HexConfig) -> io::Result<u64> {
+    discard(reader, config.skip)?;
+    let opts = config.options();
+    let mut out = OutputWriter::new(writer, FlushPolicy::Block);
+    emit(&mut out, &mut DumpFormat::new(opts), reader, &opts)
+}
+
+/// Write a hex dump of a reader like `dump`, feeding the bytes dumped to
+/// observers as they are read, so hashes and statistics of a large input
+/// come out of the same pass. Skipped bytes and those past the length
+/// aren't observed.
+///
+/// # Arguments
+///
+/// * `reader` - input to be dumped.
+/// * `writer` - where the dump goes.
+/// * `config` - dump layout and styling.
+/// * `observers` - fed every byte dumped, in order.
+///
+/// Returns the number of bytes dumped.
+pub fn dump_observed(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    config: &HexConfig,
+    observers: Vec<&mut dyn Observer>,
+) -> io::Result<u64> {
+    discard(reader, config.skip)?;
+    let len = match config.truncate_len {
+        0 => u64::MAX,
+        len => len,
+    };
+    let mut observed = ObservedReader::new(reader.take(len), observers);
+    let opts = config.options();
+    let mut out = OutputWriter::new(writer, FlushPolicy::Block);
+    emit(&mut out, &mut DumpFormat::new(opts), &mut observed, &opts)
+}
+
+/// Write the bytes of a reader as a source code array.
+///
+/// # Arguments
+///
+/// * `reader` - input to be written.
+/// * `writer` - where the array goes.
+/// * `lang` - array language as `--array` takes it, e.g. `rust` or `r`.
+/// * `config` - bytes per row, skip and length, the rest is ignored.
+///
+/// Returns the number of bytes written.
+pub fn dump_array(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    lang: &str,
+    config: &HexConfig,
+) -> io::Result<u64> {
+    let Some(lang) = ARRAY_FORMATS.resolve(lang) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown array language {lang:?}"),
+        ));
+    };
+    discard(reader, config.skip)?;
+    let opts = DumpOptions {
+        colorize: false,
+        prefix: true,
+        start_offset: 0,
+        ..config.options()
+    };
+    let mut out = OutputWriter::new(writer, FlushPolicy::Block);
+    emit(
+        &mut out,
+        &mut ArrayFormat::new(lang, config.columns, None),
+        reader,
+        &opts,
+    )
+}
+
+/// One dump line of `render_range`, split into styled spans
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderedLine {
+    /// offset of the first byte of the line
+    pub offset: u64,
+    /// bytes on the line
+    pub bytes: u64,
+    /// the text of the line in order, without the line ending
+    pub spans: Vec<Span>,
+}
+
+/// Output format collecting every dump line as spans instead of writing
+/// it
+struct SpanFormat<'a> {
+    opts: DumpOptions<'a>,
+    lines: Vec<RenderedLine>,
+}
+
+impl OutputFormat for SpanFormat<'_> {
+    fn line(&mut self, _out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        self.lines.push(RenderedLine {
+            offset: line.offset,
+            bytes: line.bytes,
+            spans: line_spans(line, 0, &self.opts),
+        });
+        Ok(())
+    }
+}
+
+/// Render a window of a seekable source as dump lines of styled spans,
+/// for front ends that draw the text themselves and only the part of a
+/// large file on screen. Lines start at `start` and offsets count from
+/// the start of the source; the styles are the colors `dump` writes when
+/// `config.colorize` is set, otherwise every span has the default style.
+/// Border bars are kept inside the lines, the rules between them are left
+/// out.
+///
+/// # Arguments
+///
+/// * `source` - input to be rendered, e.g. an open file.
+/// * `start` - offset of the first byte rendered, `config.skip` is ignored.
+/// * `len` - bytes rendered, 0 for the rest of the source.
+/// * `config` - dump layout and styling.
+pub fn render_range<R: Read + Seek>(
+    source: &mut R,
+    start: u64,
+    len: u64,
+    config: &HexConfig,
+) -> io::Result<Vec<RenderedLine>> {
+    source.seek(SeekFrom::Start(start))?;
+    let opts = DumpOptions {
+        start_offset: start,
+        truncate_len: len,
+        ..config.options()
+    };
+    let mut format = SpanFormat {
+        opts,
+        lines: Vec::new(),
+    };
+    let mut out = OutputWriter::new(io::sink(), FlushPolicy::Block);
+    emit(&mut out, &mut format, source, &opts)?;
+    Ok(format.lines)
+}
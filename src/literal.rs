@@ -0,0 +1,138 @@
+//! byte sequence literals for `hx echo`: `de ad 0x10 #65 'A' "hi\n" 00*16`
+
+use crate::conv;
+
+/// Most bytes a literal may stand for, 1 GiB, so a mistyped repeat count
+/// fails instead of taking all memory
+const MAX_LEN: usize = 1 << 30;
+
+/// Parse a byte sequence literal. Items are separated by spaces or commas:
+///
+/// * bare hex digits, two per byte: `de ad`, `deadbeef`
+/// * a single byte with a `0x` or `0o` prefix, or `#` for decimal: `0x10`, `#65`
+/// * a negative byte in two's complement: `-1`, `-0x80`
+/// * a quoted string, single or double quotes, with `\n \r \t \0 \\ \' \" \xHH` escapes
+///
+/// Any item can be repeated with `*<count>`: `00*16`, `'ab'*3`, up to 1 GiB
+/// for the whole literal.
+///
+/// # Arguments
+///
+/// * `text` - literal, e.g. `de ad be ef 0x10 'A'`.
+pub fn parse(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(bytes);
+        };
+        let item = match first {
+            '\'' | '"' => {
+                chars.next();
+                string(&mut chars, first)?
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !",*'\"".contains(*c)) {
+                    word.push(c);
+                }
+                if word.is_empty() {
+                    return Err(format!("unexpected {first:?}"));
+                }
+                number(&word)?
+            }
+        };
+        let count = match chars.next_if_eq(&'*') {
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    word.push(c);
+                }
+                conv::parse(&word).map_err(|_| format!("{word:?} is not a repeat count"))?
+            }
+            None => 1,
+        };
+        if item.is_empty() {
+            continue;
+        }
+        let len = usize::try_from(count)
+            .ok()
+            .and_then(|count| item.len().checked_mul(count))
+            .and_then(|len| len.checked_add(bytes.len()))
+            .filter(|len| *len <= MAX_LEN);
+        if len.is_none() {
+            let shown: String = item.iter().take(8).map(|b| format!("{b:02x}")).collect();
+            let more = if item.len() > 8 { "…" } else { "" };
+            return Err(format!(
+                "{shown}{more}*{count} is too long, a literal is at most 1 GiB"
+            ));
+        }
+        for _ in 0..count {
+            bytes.extend_from_slice(&item);
+        }
+    }
+}
+
+/// Bytes of a word: a prefixed or negative number for one byte, else hex
+/// digits two per byte.
+fn number(word: &str) -> Result<Vec<u8>, String> {
+    let prefixed = word.starts_with("0x") || word.starts_with("0X") || word.starts_with("0o");
+    let value = if let Some(decimal) = word.strip_prefix('#') {
+        decimal.parse::<i64>().ok()
+    } else if prefixed || word.starts_with('-') {
+        conv::parse(word).ok().map(|value| value as i64)
+    } else {
+        if !word.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("{word:?} is not hex bytes"));
+        }
+        if !word.len().is_multiple_of(2) {
+            return Err(format!("{word:?} has an odd number of hex digits"));
+        }
+        return (0..word.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&word[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string());
+    };
+    match value {
+        Some(value @ -128..=255) => Ok(vec![value as u8]),
+        Some(_) => Err(format!("{word:?} doesn't fit in a byte")),
+        None => Err(format!("{word:?} is not a number")),
+    }
+}
+
+/// Bytes of a quoted string, up to its closing quote.
+fn string(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    quote: char,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    loop {
+        let c = chars.next().ok_or(format!("missing closing {quote}"))?;
+        if c == quote {
+            return Ok(bytes);
+        }
+        if c != '\\' {
+            let mut utf8 = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => 0,
+            Some(c @ ('\\' | '\'' | '"')) => c as u8,
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .ok_or(format!("\\x{hex} is not a hex byte"))?
+            }
+            Some(c) => return Err(format!("unknown escape \\{c}")),
+            None => return Err(format!("missing closing {quote}")),
+        });
+    }
+}
@@ -0,0 +1,219 @@
+//! leveled diagnostics on stderr: errors always, warnings and reports
+//! unless `-q`, option resolution and timing with `-v`, I/O decisions with
+//! `-vv`; as text, or JSON lines with `--log-format json`
+
+use crate::i18n::{tr, Msg};
+use crate::patch::json_string;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// How much hx reports on stderr, each level including the ones before it
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// errors only, `-q`
+    Error,
+    /// and warnings
+    Warn,
+    /// and the reports and summaries of verify modes, the default
+    Report,
+    /// and how options resolved and the timing report, `-v`
+    Info,
+    /// and every I/O decision, `-vv`
+    Trace,
+}
+
+/// Level implementation
+impl Level {
+    /// Level for the number of `-q` and `-v` flags given.
+    ///
+    /// # Arguments
+    ///
+    /// * `quiet` - times `-q` was given.
+    /// * `verbose` - times `-v` was given.
+    pub fn from_counts(quiet: u8, verbose: u8) -> Level {
+        match (quiet, verbose) {
+            (1.., _) => Level::Error,
+            (_, 0) => Level::Report,
+            (_, 1) => Level::Info,
+            _ => Level::Trace,
+        }
+    }
+
+    /// Name in JSON records.
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Report => "report",
+            Level::Info => "info",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+/// How records are written to stderr
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// plain lines, the default
+    Text,
+    /// one JSON object per line with the level and a message or event
+    Json,
+}
+
+/// LogFormat implementation
+impl LogFormat {
+    /// Format for a `--log-format` name, text for anything but `json`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - format name.
+    pub fn from_name(name: &str) -> LogFormat {
+        match name {
+            "json" => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+/// A value of a JSON event record
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Field<'a> {
+    /// string
+    Text(&'a str),
+    /// integer
+    Number(u64),
+    /// fractional number
+    Float(f64),
+    /// true or false
+    Flag(bool),
+}
+
+/// Level messages are reported up to, shared by every module
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Report as u8);
+
+/// Whether records are written as JSON lines
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Set the level messages are reported up to.
+///
+/// # Arguments
+///
+/// * `level` - most detailed level reported.
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Set how records are written.
+///
+/// # Arguments
+///
+/// * `format` - text or JSON lines.
+pub fn set_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+/// Whether records are written as JSON lines, so verify modes report
+/// through events instead of stdout.
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Whether messages of a level are reported.
+///
+/// # Arguments
+///
+/// * `level` - level of a message.
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Report a message at a level, if it's enabled.
+///
+/// # Arguments
+///
+/// * `level` - level of the message.
+/// * `message` - text, without a trailing newline.
+pub fn log(level: Level, message: impl Display) {
+    match (enabled(level), json()) {
+        (false, _) => {}
+        (true, true) => eprintln!(
+            "{{\"level\": \"{}\", \"message\": {}}}",
+            level.name(),
+            json_string(&message.to_string())
+        ),
+        (true, false) => eprintln!("{message}"),
+    }
+}
+
+/// Report an event as a JSON record, if JSON records are written and its
+/// level is enabled. Text output reports events its own way.
+///
+/// # Arguments
+///
+/// * `level` - level of the event.
+/// * `event` - kind of event, e.g. `check`.
+/// * `fields` - names and values of its details.
+pub fn event(level: Level, event: &str, fields: &[(&str, Field)]) {
+    if !json() || !enabled(level) {
+        return;
+    }
+    let mut record = format!(
+        "{{\"level\": \"{}\", \"event\": {}",
+        level.name(),
+        json_string(event)
+    );
+    for (name, value) in fields {
+        let value = match value {
+            Field::Text(text) => json_string(text),
+            Field::Number(number) => number.to_string(),
+            Field::Float(number) => number.to_string(),
+            Field::Flag(flag) => flag.to_string(),
+        };
+        record.push_str(&format!(", {}: {value}", json_string(name)));
+    }
+    eprintln!("{record}}}");
+}
+
+/// Report an error, prefixed with `error:` as text, e.g. an unreadable
+/// input file or what stopped hx.
+///
+/// # Arguments
+///
+/// * `message` - text, without a trailing newline.
+pub fn error(message: impl Display) {
+    match json() {
+        true => log(Level::Error, message),
+        false => log(Level::Error, format_args!("{}: {message}", tr(Msg::Error))),
+    }
+}
+
+/// Report a warning, prefixed with `warning:` as text and silenced by `-q`,
+/// e.g. a skipped record or an option a pipe can't honor.
+///
+/// # Arguments
+///
+/// * `message` - text, without a trailing newline.
+pub fn warn(message: impl Display) {
+    match json() {
+        true => log(Level::Warn, message),
+        false => log(Level::Warn, format_args!("{}: {message}", tr(Msg::Warning))),
+    }
+}
+
+/// Report how an option resolved, shown with `-v`.
+///
+/// # Arguments
+///
+/// * `message` - text, without a trailing newline.
+pub fn info(message: impl Display) {
+    log(Level::Info, message);
+}
+
+/// Report an I/O decision, shown with `-vv`.
+///
+/// # Arguments
+///
+/// * `message` - text, without a trailing newline.
+pub fn trace(message: impl Display) {
+    log(Level::Trace, message);
+}
@@ -0,0 +1,67 @@
+//! file type detection from leading magic bytes
+
+/// Signatures: offset of the magic bytes, the bytes, and the type name
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x7fELF", "ELF executable or object"),
+    (0, b"MZ", "DOS/Windows executable"),
+    (0, b"\xcf\xfa\xed\xfe", "Mach-O 64-bit executable"),
+    (0, b"\xce\xfa\xed\xfe", "Mach-O executable"),
+    (
+        0,
+        b"\xca\xfe\xba\xbe",
+        "Mach-O universal binary or Java class",
+    ),
+    (0, b"\0asm", "WebAssembly module"),
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (0, b"\xff\xd8\xff", "JPEG image"),
+    (0, b"GIF87a", "GIF image"),
+    (0, b"GIF89a", "GIF image"),
+    (0, b"BM", "BMP image"),
+    (0, b"%PDF-", "PDF document"),
+    (0, b"PK\x03\x04", "ZIP archive"),
+    (0, b"PK\x05\x06", "ZIP archive (empty)"),
+    (0, b"\x1f\x8b", "gzip compressed data"),
+    (0, b"BZh", "bzip2 compressed data"),
+    (0, b"\xfd7zXZ\0", "xz compressed data"),
+    (0, b"\x28\xb5\x2f\xfd", "zstd compressed data"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7-zip archive"),
+    (0, b"Rar!\x1a\x07", "RAR archive"),
+    (257, b"ustar", "tar archive"),
+    (0, b"SQLite format 3\0", "SQLite 3 database"),
+    (0, b"\xd4\xc3\xb2\xa1", "pcap capture"),
+    (0, b"\xa1\xb2\xc3\xd4", "pcap capture"),
+    (0, b"\x4d\x3c\xb2\xa1", "pcap capture"),
+    (0, b"\xa1\xb2\x3c\x4d", "pcap capture"),
+    (0, b"\x0a\x0d\x0d\x0a", "pcapng capture"),
+    (0, b"OggS", "Ogg media"),
+    (0, b"fLaC", "FLAC audio"),
+    (0, b"ID3", "MP3 audio"),
+    (0, b"\x1aE\xdf\xa3", "Matroska or WebM media"),
+    (0, b"regf", "Windows registry hive"),
+    (
+        0,
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        "OLE2 compound document",
+    ),
+];
+
+/// Name of the file type the leading bytes of an input belong to, None
+/// when no signature matches.
+///
+/// # Arguments
+///
+/// * `head` - first bytes of the input, a few hundred are enough.
+pub fn detect(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"RIFF") && head.len() >= 12 {
+        return Some(match &head[8..12] {
+            b"WAVE" => "WAVE audio",
+            b"AVI " => "AVI video",
+            b"WEBP" => "WebP image",
+            _ => "RIFF data",
+        });
+    }
+    SIGNATURES
+        .iter()
+        .find(|(at, magic, _)| head.get(*at..).is_some_and(|rest| rest.starts_with(magic)))
+        .map(|(_, _, name)| *name)
+}
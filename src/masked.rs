@@ -0,0 +1,18 @@
+//! masked comparison of a file against a reference pattern
+
+/// Offset of the first byte where `data` differs from `pattern` in a bit
+/// set in `mask`, or where `data` ends before the pattern does. Bytes past
+/// the end of the mask are compared in full, bytes past the end of the
+/// pattern aren't compared.
+///
+/// # Arguments
+///
+/// * `data` - bytes to check.
+/// * `pattern` - expected bytes.
+/// * `mask` - bits to compare of every pattern byte.
+pub fn first_mismatch(data: &[u8], pattern: &[u8], mask: &[u8]) -> Option<usize> {
+    pattern.iter().enumerate().position(|(i, expected)| {
+        let bits = mask.get(i).copied().unwrap_or(0xff);
+        data.get(i).is_none_or(|b| (b ^ expected) & bits != 0)
+    })
+}
@@ -0,0 +1,113 @@
+//! observers fed every byte a dump reads, so one pass over a large input
+//! gives the dump, digests and statistics together
+
+use crate::hash::{Crc32, Sha256};
+use std::io::{self, Read};
+
+/// Something fed the bytes of an input in order, each byte once, e.g. a
+/// hasher or a statistics collector
+pub trait Observer {
+    /// Take the next bytes of the input.
+    fn update(&mut self, data: &[u8]);
+}
+
+impl Observer for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Sha256::update(self, data)
+    }
+}
+
+impl Observer for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        Crc32::update(self, data)
+    }
+}
+
+impl<F: FnMut(&[u8])> Observer for F {
+    fn update(&mut self, data: &[u8]) {
+        self(data)
+    }
+}
+
+/// How often every byte value occurs, and the entropy that gives
+#[derive(Clone, Debug)]
+pub struct ByteStats {
+    /// occurrences of every byte value
+    pub counts: [u64; 256],
+    /// bytes counted
+    pub len: u64,
+}
+
+impl Default for ByteStats {
+    fn default() -> ByteStats {
+        ByteStats::new()
+    }
+}
+
+/// ByteStats implementation
+impl ByteStats {
+    /// ByteStats constructor
+    pub fn new() -> ByteStats {
+        ByteStats {
+            counts: [0; 256],
+            len: 0,
+        }
+    }
+
+    /// Shannon entropy in bits per byte, 0 for a single repeated value up
+    /// to 8 for uniformly spread ones.
+    pub fn entropy(&self) -> f64 {
+        let len = self.len as f64;
+        self.counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+}
+
+impl Observer for ByteStats {
+    fn update(&mut self, data: &[u8]) {
+        for b in data {
+            self.counts[usize::from(*b)] += 1;
+        }
+        self.len += data.len() as u64;
+    }
+}
+
+/// Reader handing every byte it reads to its observers on the way through
+pub struct ObservedReader<'a, R: Read> {
+    inner: R,
+    observers: Vec<&'a mut dyn Observer>,
+}
+
+/// ObservedReader implementation
+impl<'a, R: Read> ObservedReader<'a, R> {
+    /// ObservedReader constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input to read.
+    /// * `observers` - fed every byte read, in order.
+    pub fn new(inner: R, observers: Vec<&'a mut dyn Observer>) -> ObservedReader<'a, R> {
+        ObservedReader { inner, observers }
+    }
+
+    /// The input, once reading is done.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for ObservedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for observer in self.observers.iter_mut() {
+            observer.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
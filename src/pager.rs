@@ -0,0 +1,80 @@
+//! piping dump output through a pager
+
+use std::env;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// When to page output
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Paging {
+    /// page when stdout is a terminal
+    Auto,
+    /// page even when stdout isn't a terminal
+    Always,
+    /// never page, the default
+    #[default]
+    Never,
+}
+
+/// Paging implementation
+impl Paging {
+    /// Paging for a name such as `auto`.
+    pub fn parse(name: &str) -> Option<Paging> {
+        match name {
+            "auto" => Some(Paging::Auto),
+            "always" => Some(Paging::Always),
+            "never" => Some(Paging::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Pager program and arguments: `$PAGER`, else `less`. Plain `less` gets
+/// `-RFX` so colors come through and output that fits on one screen is
+/// printed without waiting for a keypress. None when `$PAGER` is empty or
+/// `cat`, which turns paging off.
+pub fn pager_command() -> Option<Vec<String>> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut words: Vec<String> = pager.split_whitespace().map(str::to_string).collect();
+    let program = Path::new(words.first()?).file_name()?.to_str()?;
+    match program {
+        "cat" => None,
+        "less" if words.len() == 1 => {
+            words.push("-RFX".to_string());
+            Some(words)
+        }
+        _ => Some(words),
+    }
+}
+
+/// Start the pager with its stdin piped, None when output shouldn't be
+/// paged. A pager that fails to start is an error only with `Always`; with
+/// `Auto` output goes straight to stdout.
+///
+/// # Arguments
+///
+/// * `paging` - when to page.
+pub fn spawn(paging: Paging) -> io::Result<Option<Child>> {
+    let wanted = match paging {
+        Paging::Auto => io::stdout().is_terminal(),
+        Paging::Always => true,
+        Paging::Never => false,
+    };
+    let words = match pager_command() {
+        Some(words) if wanted => words,
+        _ => return Ok(None),
+    };
+    match Command::new(&words[0])
+        .args(&words[1..])
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => Ok(Some(child)),
+        Err(e) if paging == Paging::Always => Err(io::Error::new(
+            e.kind(),
+            format!("can't start pager {}: {e}", words[0]),
+        )),
+        Err(_) => Ok(None),
+    }
+}
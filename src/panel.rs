@@ -0,0 +1,119 @@
+//! panels of a dump line for `--panels`: the offset, byte cells, character
+//! panel and annotations, in any order and any number of times
+
+/// A column group of a dump line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Panel {
+    /// line offset, highlighted on a marked boundary
+    Offset,
+    /// byte cells in the `--format`
+    Hex,
+    /// byte cells in decimal
+    Decimal,
+    /// characters in the `--charset`
+    Ascii,
+    /// bookmark, symbol and source line labels
+    Annotations,
+}
+
+/// Panels of a dump line without `--panels`
+pub const DEFAULT_PANELS: &[Panel] = &[Panel::Offset, Panel::Hex, Panel::Ascii, Panel::Annotations];
+
+/// Panel implementation
+impl Panel {
+    /// Panel for a name such as `ascii`.
+    pub fn parse(name: &str) -> Option<Panel> {
+        match name {
+            "offset" => Some(Panel::Offset),
+            "hex" => Some(Panel::Hex),
+            "decimal" => Some(Panel::Decimal),
+            "ascii" => Some(Panel::Ascii),
+            "annotations" => Some(Panel::Annotations),
+            _ => None,
+        }
+    }
+
+    /// Whether the panel is a row of byte cells.
+    pub fn is_cells(&self) -> bool {
+        matches!(self, Panel::Hex | Panel::Decimal)
+    }
+}
+
+/// Parse a comma separated panel list, e.g. `offset,ascii,hex`.
+///
+/// # Arguments
+///
+/// * `list` - panel names in the order they're printed.
+pub fn parse_panels(list: &str) -> Result<Vec<Panel>, String> {
+    list.split(',')
+        .map(|name| {
+            Panel::parse(name.trim()).ok_or(format!(
+                "unknown panel {name:?}, not offset, hex, decimal, ascii or annotations"
+            ))
+        })
+        .collect()
+}
+
+/// Lines drawn around the panels of a dump line, `--border`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Border {
+    /// panels spaced apart, the plain layout
+    #[default]
+    None,
+    /// `+`, `-` and `|`
+    Ascii,
+    /// box-drawing characters
+    Unicode,
+}
+
+/// Which rule of a bordered dump is drawn
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// above the first line
+    Top,
+    /// between lines, on a marked boundary
+    Middle,
+    /// below the last line
+    Bottom,
+}
+
+/// Border implementation
+impl Border {
+    /// Border for a name such as `unicode`.
+    pub fn parse(name: &str) -> Option<Border> {
+        match name {
+            "none" => Some(Border::None),
+            "ascii" => Some(Border::Ascii),
+            "unicode" => Some(Border::Unicode),
+            _ => None,
+        }
+    }
+
+    /// Line between panels, empty without a border.
+    pub fn bar(&self) -> &'static str {
+        match self {
+            Border::None => "",
+            Border::Ascii => "|",
+            Border::Unicode => "│",
+        }
+    }
+
+    /// Rule across panels of the given widths, with corners at the ends
+    /// and joints where the panels meet.
+    ///
+    /// # Arguments
+    ///
+    /// * `edge` - which rule.
+    /// * `widths` - characters each panel takes between the bars.
+    pub fn rule(&self, edge: Edge, widths: &[usize]) -> String {
+        let (left, joint, right, line) = match (self, edge) {
+            (Border::None, _) => return String::new(),
+            (Border::Ascii, _) => ("+", "+", "+", "-"),
+            (Border::Unicode, Edge::Top) => ("┌", "┬", "┐", "─"),
+            (Border::Unicode, Edge::Middle) => ("├", "┼", "┤", "─"),
+            (Border::Unicode, Edge::Bottom) => ("└", "┴", "┘", "─"),
+        };
+        let panels: Vec<String> = widths.iter().map(|width| line.repeat(*width)).collect();
+        format!("{left}{}{right}", panels.join(joint))
+    }
+}
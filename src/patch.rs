@@ -0,0 +1,583 @@
+//! in-place file patching, with optional backups, atomic replacement, an
+//! audit journal and reverse patches
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a mutating mode protects the file it edits
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteGuard {
+    /// copy the original to its path plus this suffix before changing it
+    pub backup: Option<String>,
+    /// fail rather than overwrite an existing backup
+    pub no_clobber: bool,
+    /// write a patched copy next to the file and rename it over the original
+    pub atomic: bool,
+    /// append a record of every edit to this file
+    pub journal: Option<PathBuf>,
+    /// write a patch file that undoes the edit
+    pub reverse_patch: Option<PathBuf>,
+    /// change the file without asking, `--yes`
+    pub yes: bool,
+}
+
+/// Edits shown in the question of `confirm`, the rest counted
+const DESCRIBED: usize = 8;
+
+/// What a list of edits changes, for `confirm`: the bytes written and
+/// their ranges, the first few of them.
+///
+/// # Arguments
+///
+/// * `path` - file to change.
+/// * `edits` - offsets and replacement bytes.
+pub fn describe(path: &Path, edits: &[(u64, Vec<u8>)]) -> String {
+    let bytes: usize = edits.iter().map(|(_, bytes)| bytes.len()).sum();
+    let mut ranges: Vec<String> = edits
+        .iter()
+        .take(DESCRIBED)
+        .map(|(at, bytes)| {
+            let end = at + bytes.len() as u64;
+            format!("{}:{}", crate::offset(*at), crate::offset(end))
+        })
+        .collect();
+    if edits.len() > DESCRIBED {
+        ranges.push(format!("{} more", edits.len() - DESCRIBED));
+    }
+    format!(
+        "write {bytes} bytes to {} at {}",
+        path.display(),
+        ranges.join(", ")
+    )
+}
+
+/// Ask before changing a file, unless `--yes` was given. On a terminal the
+/// question goes to stderr and the answer comes from stdin; anywhere else
+/// the change is refused, so a script never changes a file by accident.
+///
+/// # Arguments
+///
+/// * `change` - what would change, e.g. from `describe`.
+/// * `guard` - whether `--yes` was given.
+pub fn confirm(change: &str, guard: &WriteGuard) -> io::Result<()> {
+    if guard.yes {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to {change} without --yes when not run from a terminal"),
+        ));
+    }
+    let mut stderr = io::stderr().lock();
+    write!(stderr, "{change}? [y/N] ")?;
+    stderr.flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "cancelled, nothing was changed",
+        )),
+    }
+}
+
+/// Read up to `len` bytes at `at`, fewer at the end of the file.
+///
+/// # Arguments
+///
+/// * `file` - file to read.
+/// * `at` - offset of the first byte.
+/// * `len` - number of bytes.
+pub fn read_at(file: &mut File, at: u64, len: usize) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(at))?;
+    let mut bytes = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Copy `path` to `path` plus `suffix`.
+///
+/// # Arguments
+///
+/// * `path` - file about to be changed.
+/// * `suffix` - appended to the file name.
+/// * `no_clobber` - fail if the backup already exists.
+pub fn backup(path: &Path, suffix: &str, no_clobber: bool) -> io::Result<PathBuf> {
+    let mut target = path.as_os_str().to_owned();
+    target.push(suffix);
+    let target = PathBuf::from(target);
+    let mut copy = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .create_new(no_clobber)
+        .open(&target)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => io::Error::new(
+                e.kind(),
+                format!("backup {} already exists", target.display()),
+            ),
+            _ => e,
+        })?;
+    io::copy(&mut File::open(path)?, &mut copy)?;
+    copy.sync_all()?;
+    Ok(target)
+}
+
+/// A replacement of bytes at an offset. Where the old and new bytes differ
+/// in length, one side is empty and the hunk changes the end of the file:
+/// the bytes from `at` on are dropped or added.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    /// offset of the first byte
+    pub at: u64,
+    /// bytes expected there
+    pub old: Vec<u8>,
+    /// bytes to write instead
+    pub new: Vec<u8>,
+}
+
+/// Hunk implementation
+impl Hunk {
+    /// Whether the hunk truncates or extends the file.
+    pub fn resizes(&self) -> bool {
+        self.old.len() != self.new.len()
+    }
+
+    /// The hunk that undoes this one.
+    pub fn reversed(&self) -> Hunk {
+        Hunk {
+            at: self.at,
+            old: self.new.clone(),
+            new: self.old.clone(),
+        }
+    }
+}
+
+/// Reader showing the input with hunks applied, without touching the file
+pub struct Overlay<'a, R: Read> {
+    inner: R,
+    hunks: &'a [Hunk],
+    /// input offset of the next byte read
+    position: u64,
+}
+
+/// Overlay implementation
+impl<'a, R: Read> Overlay<'a, R> {
+    /// Overlay constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input, read from the start.
+    /// * `hunks` - replacements, later ones win where they overlap.
+    pub fn new(inner: R, hunks: &'a [Hunk]) -> Overlay<'a, R> {
+        Overlay {
+            inner,
+            hunks,
+            position: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for Overlay<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let end = self.position + n as u64;
+        for hunk in self.hunks {
+            let from = hunk.at.max(self.position);
+            let to = (hunk.at + hunk.new.len() as u64).min(end);
+            for at in from..to {
+                buf[(at - self.position) as usize] = hunk.new[(at - hunk.at) as usize];
+            }
+        }
+        self.position = end;
+        Ok(n)
+    }
+}
+
+/// Check that every edit lies within the file, as patches never grow it.
+///
+/// # Arguments
+///
+/// * `path` - file to patch.
+/// * `edits` - offsets and replacement bytes.
+pub fn check_fits(path: &Path, edits: &[(u64, Vec<u8>)]) -> io::Result<()> {
+    let len = fs::metadata(path)?.len();
+    for (at, bytes) in edits {
+        if at
+            .checked_add(bytes.len() as u64)
+            .is_none_or(|end| end > len)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "patch of {} bytes at {} runs past the end of the file ({len} bytes)",
+                    bytes.len(),
+                    crate::offset(*at)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite bytes at several offsets, guarded as asked, and return the
+/// hunks that were applied. Patches never grow the file, so writing past
+/// the end is an error and leaves everything untouched, as does saying no
+/// when asked to confirm.
+///
+/// # Arguments
+///
+/// * `path` - file to patch.
+/// * `edits` - offsets and replacement bytes.
+/// * `guard` - backup, replacement and journal behavior.
+pub fn apply(path: &Path, edits: &[(u64, Vec<u8>)], guard: &WriteGuard) -> io::Result<Vec<Hunk>> {
+    check_fits(path, edits)?;
+    let mut file = File::open(path)?;
+    let mut hunks = Vec::with_capacity(edits.len());
+    for (at, bytes) in edits {
+        hunks.push(Hunk {
+            at: *at,
+            old: read_at(&mut file, *at, bytes.len())?,
+            new: bytes.clone(),
+        });
+    }
+    confirm(&describe(path, edits), guard)?;
+    let hash_before = match guard.journal {
+        Some(_) => crate::hash::sha256_file(path)?,
+        None => String::new(),
+    };
+    if let Some(suffix) = &guard.backup {
+        backup(path, suffix, guard.no_clobber)?;
+    }
+    if guard.atomic {
+        replace_atomically(path, &hunks)?;
+    } else {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        for hunk in &hunks {
+            file.seek(SeekFrom::Start(hunk.at))?;
+            file.write_all(&hunk.new)?;
+        }
+        file.sync_all()?;
+    }
+    record(path, &hunks, &hash_before, guard)?;
+    Ok(hunks)
+}
+
+/// Truncate or extend a file, guarded as asked, filling what it grows by
+/// with a pattern repeated from offset 0. Saying no when asked to confirm
+/// leaves it untouched.
+///
+/// # Arguments
+///
+/// * `path` - file to resize.
+/// * `size` - new length.
+/// * `fill` - pattern, at least one byte when the file grows.
+/// * `guard` - backup, replacement and journal behavior.
+pub fn resize(path: &Path, size: u64, fill: &[u8], guard: &WriteGuard) -> io::Result<()> {
+    let len = fs::metadata(path)?.len();
+    if size == len {
+        return Ok(());
+    }
+    let change = match size < len {
+        true => format!(
+            "truncate {} from {len} to {size} bytes, dropping {}:{}",
+            path.display(),
+            crate::offset(size),
+            crate::offset(len)
+        ),
+        false => format!(
+            "extend {} from {len} to {size} bytes, writing {}:{}",
+            path.display(),
+            crate::offset(len),
+            crate::offset(size)
+        ),
+    };
+    confirm(&change, guard)?;
+    let at = len.min(size);
+    // the bytes dropped or added are only read to record them
+    let hunks = match guard.journal.is_some() || guard.reverse_patch.is_some() {
+        true => vec![Hunk {
+            at,
+            old: read_at(&mut File::open(path)?, at, (len - at) as usize)?,
+            new: filled(fill, at..size).flatten().collect(),
+        }],
+        false => Vec::new(),
+    };
+    let hash_before = match guard.journal {
+        Some(_) => crate::hash::sha256_file(path)?,
+        None => String::new(),
+    };
+    if let Some(suffix) = &guard.backup {
+        backup(path, suffix, guard.no_clobber)?;
+    }
+    match guard.atomic {
+        true => {
+            let (temp, mut file) = crate::temp::TempFile::beside(path)?;
+            io::copy(&mut File::open(path)?.take(at), &mut file)?;
+            for block in filled(fill, len..size) {
+                file.write_all(&block)?;
+            }
+            file.set_permissions(fs::metadata(path)?.permissions())?;
+            file.sync_all()?;
+            drop(file);
+            temp.persist(path)?;
+        }
+        false => {
+            let mut file = OpenOptions::new().write(true).open(path)?;
+            file.set_len(at)?;
+            file.seek(SeekFrom::Start(at))?;
+            for block in filled(fill, len..size) {
+                file.write_all(&block)?;
+            }
+            file.sync_all()?;
+        }
+    }
+    record(path, &hunks, &hash_before, guard)
+}
+
+/// Blocks of a pattern repeated from offset 0, covering a range of offsets.
+fn filled(fill: &[u8], range: std::ops::Range<u64>) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let pattern = fill.len() as u64;
+    let end = range.end;
+    range.step_by(0x10000).map(move |at| {
+        (at..end.min(at + 0x10000))
+            .map(|at| fill[(at % pattern) as usize])
+            .collect()
+    })
+}
+
+/// Append applied hunks to the journal and write the patch undoing them,
+/// when asked to.
+///
+/// # Arguments
+///
+/// * `path` - changed file.
+/// * `hunks` - replacements, as applied.
+/// * `hash_before` - hex sha-256 of the file before, for the journal.
+/// * `guard` - journal and reverse patch files.
+fn record(path: &Path, hunks: &[Hunk], hash_before: &str, guard: &WriteGuard) -> io::Result<()> {
+    if let Some(journal) = &guard.journal {
+        let hash_after = crate::hash::sha256_file(path)?;
+        for hunk in hunks {
+            let entry = JournalEntry {
+                file: &path.to_string_lossy(),
+                at: hunk.at,
+                old: &hunk.old,
+                new: &hunk.new,
+                hash_before,
+                hash_after: &hash_after,
+            };
+            append_journal(journal, &entry)?;
+        }
+    }
+    if let Some(reverse) = &guard.reverse_patch {
+        let undo: Vec<Hunk> = hunks.iter().rev().map(Hunk::reversed).collect();
+        fs::write(reverse, format_patch(path, &undo))?;
+    }
+    Ok(())
+}
+
+/// Text of a patch file: a `file` line naming the target, then one line per
+/// hunk with its offset, the bytes expected there and their replacement.
+///
+/// ```text
+/// # hx patch
+/// file fw.bin
+/// 0x000040 -deadbeef +00000000
+/// ```
+///
+/// # Arguments
+///
+/// * `path` - file the patch applies to.
+/// * `hunks` - replacements, applied in order.
+pub fn format_patch(path: &Path, hunks: &[Hunk]) -> String {
+    let mut text = format!("# hx patch\nfile {}\n", path.display());
+    for hunk in hunks {
+        text.push_str(&format!(
+            "{} -{} +{}\n",
+            crate::offset(hunk.at),
+            crate::hash::to_hex(&hunk.old),
+            crate::hash::to_hex(&hunk.new)
+        ));
+    }
+    text
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parse a patch file into its target path and hunks.
+///
+/// # Arguments
+///
+/// * `text` - patch file contents.
+pub fn parse_patch(text: &str) -> io::Result<(PathBuf, Vec<Hunk>)> {
+    let invalid = |line: usize, what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {} of the patch {what}", line + 1),
+        )
+    };
+    let mut path = None;
+    let mut hunks = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(file) = line.strip_prefix("file ") {
+            path = Some(PathBuf::from(file));
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(at), Some(old), Some(new), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid(i, "is not `offset -old +new`"));
+        };
+        let at = at
+            .strip_prefix("0x")
+            .and_then(|at| u64::from_str_radix(at, 16).ok())
+            .ok_or_else(|| invalid(i, "has a bad offset"))?;
+        let old = old.strip_prefix('-').and_then(from_hex);
+        let new = new.strip_prefix('+').and_then(from_hex);
+        match (old, new) {
+            (Some(old), Some(new))
+                if old.len() == new.len() || old.is_empty() || new.is_empty() =>
+            {
+                hunks.push(Hunk { at, old, new })
+            }
+            _ => return Err(invalid(i, "has bad or mismatched bytes")),
+        }
+    }
+    match path {
+        Some(path) => Ok((path, hunks)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the patch names no file",
+        )),
+    }
+}
+
+/// Check that the file still holds the bytes each hunk expects.
+///
+/// # Arguments
+///
+/// * `path` - file the patch applies to.
+/// * `hunks` - replacements about to be applied.
+pub fn verify(path: &Path, hunks: &[Hunk]) -> io::Result<()> {
+    let len = fs::metadata(path)?.len();
+    let mut file = File::open(path)?;
+    for hunk in hunks {
+        let end = hunk.at + hunk.old.len() as u64;
+        if hunk.resizes() && end != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} changed since the patch was written: it's {len} bytes instead of {end}",
+                    path.display()
+                ),
+            ));
+        }
+        let found = read_at(&mut file, hunk.at, hunk.old.len())?;
+        if found != hunk.old {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} changed since the patch was written: {} holds {} instead of {}",
+                    path.display(),
+                    crate::offset(hunk.at),
+                    crate::hash::to_hex(&found),
+                    crate::hash::to_hex(&hunk.old)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// One edit, as recorded in the journal
+#[derive(Clone, Debug)]
+pub struct JournalEntry<'a> {
+    /// edited file
+    pub file: &'a str,
+    /// offset of the first changed byte
+    pub at: u64,
+    /// bytes before the edit
+    pub old: &'a [u8],
+    /// bytes after the edit
+    pub new: &'a [u8],
+    /// hex sha-256 of the file before the edit
+    pub hash_before: &'a str,
+    /// hex sha-256 of the file after the edit
+    pub hash_after: &'a str,
+}
+
+/// JSON string literal, escaping quotes, backslashes and control characters.
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The journal line for an edit, one JSON object per line.
+///
+/// # Arguments
+///
+/// * `entry` - the edit.
+/// * `seconds` - time of the edit, seconds since the unix epoch.
+pub fn journal_line(entry: &JournalEntry, seconds: u64) -> String {
+    let time = crate::pcap::format_timestamp(seconds, 0, 0).replace(' ', "T") + "Z";
+    format!(
+        "{{\"time\":\"{time}\",\"file\":{},\"offset\":{},\"old\":\"{}\",\"new\":\"{}\",\"sha256_before\":\"{}\",\"sha256_after\":\"{}\"}}",
+        json_string(entry.file),
+        entry.at,
+        crate::hash::to_hex(entry.old),
+        crate::hash::to_hex(entry.new),
+        entry.hash_before,
+        entry.hash_after
+    )
+}
+
+/// Append an edit to the journal, creating it if needed.
+fn append_journal(journal: &Path, entry: &JournalEntry) -> io::Result<()> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut file = OpenOptions::new().create(true).append(true).open(journal)?;
+    writeln!(file, "{}", journal_line(entry, seconds))?;
+    file.sync_all()
+}
+
+/// Write a patched copy beside the original and rename it into place, so a
+/// crash leaves either the old or the new file, never a mix.
+fn replace_atomically(path: &Path, hunks: &[Hunk]) -> io::Result<()> {
+    let mut data = fs::read(path)?;
+    for hunk in hunks {
+        let at = hunk.at as usize;
+        data[at..at + hunk.new.len()].copy_from_slice(&hunk.new);
+    }
+
+    crate::temp::write_atomically(path, &data)
+}
@@ -0,0 +1,339 @@
+//! packet iteration over pcap and pcapng captures
+
+use std::io::{self, Read};
+
+/// A captured packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    /// 1 based position in the capture
+    pub index: u64,
+    /// capture time, seconds since the unix epoch
+    pub seconds: u64,
+    /// capture time, nanoseconds within the second
+    pub nanos: u32,
+    /// fractional digits worth printing for the capture's timestamp
+    /// resolution, 0 when the capture records no time for the packet
+    pub digits: usize,
+    /// length of the packet on the wire, may exceed the captured bytes
+    pub orig_len: u32,
+    /// captured bytes
+    pub data: Vec<u8>,
+}
+
+/// Capture file layout and byte order
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Layout {
+    /// classic pcap, with nanosecond instead of microsecond timestamps
+    Pcap { big_endian: bool, nanos: bool },
+    /// pcapng, byte order set by the current section header
+    PcapNg { big_endian: bool },
+}
+
+/// pcapng interface description: snap length and timestamp units per second
+#[derive(Copy, Clone, Debug)]
+struct Interface {
+    snaplen: u32,
+    units: u64,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Streaming pcap and pcapng packet reader
+pub struct PcapReader<R: Read> {
+    inner: R,
+    layout: Layout,
+    interfaces: Vec<Interface>,
+    index: u64,
+}
+
+/// PcapReader implementation
+impl<R: Read> PcapReader<R> {
+    /// Read the capture file header and detect the layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - capture, read from the start.
+    pub fn new(mut inner: R) -> io::Result<PcapReader<R>> {
+        let mut magic = [0u8; 4];
+        inner
+            .read_exact(&mut magic)
+            .map_err(|_| invalid("input is not a pcap or pcapng capture"))?;
+        let layout = match magic {
+            [0xd4, 0xc3, 0xb2, 0xa1] => Layout::Pcap {
+                big_endian: false,
+                nanos: false,
+            },
+            [0xa1, 0xb2, 0xc3, 0xd4] => Layout::Pcap {
+                big_endian: true,
+                nanos: false,
+            },
+            [0x4d, 0x3c, 0xb2, 0xa1] => Layout::Pcap {
+                big_endian: false,
+                nanos: true,
+            },
+            [0xa1, 0xb2, 0x3c, 0x4d] => Layout::Pcap {
+                big_endian: true,
+                nanos: true,
+            },
+            [0x0a, 0x0d, 0x0d, 0x0a] => Layout::PcapNg { big_endian: false },
+            _ => return Err(invalid("input is not a pcap or pcapng capture")),
+        };
+        let mut reader = PcapReader {
+            inner,
+            layout,
+            interfaces: Vec::new(),
+            index: 0,
+        };
+        match layout {
+            // rest of the global header: version, zone, sigfigs, snaplen, link type
+            Layout::Pcap { .. } => reader.skip(20)?,
+            Layout::PcapNg { .. } => reader.section_header()?,
+        }
+        Ok(reader)
+    }
+
+    fn big_endian(&self) -> bool {
+        match self.layout {
+            Layout::Pcap { big_endian, .. } | Layout::PcapNg { big_endian } => big_endian,
+        }
+    }
+
+    fn u16_at(&self, data: &[u8], at: usize) -> u16 {
+        let bytes = [data[at], data[at + 1]];
+        match self.big_endian() {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        }
+    }
+
+    fn u32_at(&self, data: &[u8], at: usize) -> u32 {
+        let bytes = [data[at], data[at + 1], data[at + 2], data[at + 3]];
+        match self.big_endian() {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        }
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        match crate::discard(&mut self.inner, len)? == len {
+            true => Ok(()),
+            false => Err(invalid("truncated capture header")),
+        }
+    }
+
+    /// Read exactly `len` bytes, None on a clean end of file.
+    fn read_block(&mut self, len: usize) -> io::Result<Option<Vec<u8>>> {
+        let mut data = Vec::with_capacity(len);
+        (&mut self.inner).take(len as u64).read_to_end(&mut data)?;
+        match data.len() {
+            0 if len > 0 => Ok(None),
+            n if n < len => Err(invalid("truncated capture record")),
+            _ => Ok(Some(data)),
+        }
+    }
+
+    /// Remainder of a pcapng section header block, after its block type.
+    fn section_header(&mut self) -> io::Result<()> {
+        let head = self
+            .read_block(8)?
+            .ok_or_else(|| invalid("truncated pcapng section header"))?;
+        let big_endian = match head[4..8] {
+            [0x1a, 0x2b, 0x3c, 0x4d] => true,
+            [0x4d, 0x3c, 0x2b, 0x1a] => false,
+            _ => return Err(invalid("bad pcapng byte order magic")),
+        };
+        self.layout = Layout::PcapNg { big_endian };
+        self.interfaces.clear();
+        let total = self.u32_at(&head, 0) as u64;
+        if total < 12 {
+            return Err(invalid("bad pcapng block length"));
+        }
+        self.skip(total - 12)
+    }
+
+    /// Next packet in the capture, None at the end.
+    pub fn next_packet(&mut self) -> io::Result<Option<Packet>> {
+        match self.layout {
+            Layout::Pcap { nanos, .. } => self.next_pcap(nanos),
+            Layout::PcapNg { .. } => self.next_pcapng(),
+        }
+    }
+
+    fn next_pcap(&mut self, nanos: bool) -> io::Result<Option<Packet>> {
+        let header = match self.read_block(16)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let seconds = self.u32_at(&header, 0) as u64;
+        let fraction = self.u32_at(&header, 4);
+        let captured = self.u32_at(&header, 8) as usize;
+        let orig_len = self.u32_at(&header, 12);
+        let data = self
+            .read_block(captured)?
+            .ok_or_else(|| invalid("truncated capture record"))?;
+        self.index += 1;
+        Ok(Some(Packet {
+            index: self.index,
+            seconds,
+            nanos: if nanos {
+                fraction
+            } else {
+                fraction.saturating_mul(1000)
+            },
+            digits: if nanos { 9 } else { 6 },
+            orig_len,
+            data,
+        }))
+    }
+
+    fn next_pcapng(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            let head = match self.read_block(4)? {
+                Some(head) => head,
+                None => return Ok(None),
+            };
+            if head == [0x0a, 0x0d, 0x0d, 0x0a] {
+                self.section_header()?;
+                continue;
+            }
+            let kind = self.u32_at(&head, 0);
+            let len = self
+                .read_block(4)?
+                .ok_or_else(|| invalid("truncated pcapng block"))?;
+            let total = self.u32_at(&len, 0) as usize;
+            if total < 12 || !total.is_multiple_of(4) {
+                return Err(invalid("bad pcapng block length"));
+            }
+            let body = self
+                .read_block(total - 8)?
+                .ok_or_else(|| invalid("truncated pcapng block"))?;
+            let body = &body[..body.len() - 4];
+            match kind {
+                1 => self.interface_description(body)?,
+                3 => return self.simple_packet(body).map(Some),
+                6 => return self.enhanced_packet(body).map(Some),
+                // statistics, name resolution, custom blocks, ...
+                _ => {}
+            }
+        }
+    }
+
+    fn interface_description(&mut self, body: &[u8]) -> io::Result<()> {
+        if body.len() < 8 {
+            return Err(invalid("truncated pcapng interface description"));
+        }
+        let mut interface = Interface {
+            snaplen: self.u32_at(body, 4),
+            units: 1_000_000,
+        };
+        let mut at = 8;
+        while at + 4 <= body.len() {
+            let code = self.u16_at(body, at);
+            let len = self.u16_at(body, at + 2) as usize;
+            // if_tsresol: power of ten, or power of two with the high bit set
+            if code == 9 && len == 1 && at + 4 < body.len() {
+                let resolution = body[at + 4];
+                interface.units = match resolution & 0x80 {
+                    0 => 10u64.saturating_pow(u32::from(resolution)),
+                    _ => 1u64 << (resolution & 0x7f).min(63),
+                };
+            }
+            if code == 0 {
+                break;
+            }
+            at += 4 + len.div_ceil(4) * 4;
+        }
+        self.interfaces.push(interface);
+        Ok(())
+    }
+
+    fn simple_packet(&mut self, body: &[u8]) -> io::Result<Packet> {
+        if body.len() < 4 {
+            return Err(invalid("truncated pcapng simple packet"));
+        }
+        let orig_len = self.u32_at(body, 0);
+        let snaplen = self.interfaces.first().map_or(0, |i| i.snaplen);
+        let mut captured = (body.len() - 4).min(orig_len as usize);
+        if snaplen > 0 {
+            captured = captured.min(snaplen as usize);
+        }
+        self.index += 1;
+        Ok(Packet {
+            index: self.index,
+            seconds: 0,
+            nanos: 0,
+            digits: 0,
+            orig_len,
+            data: body[4..4 + captured].to_vec(),
+        })
+    }
+
+    fn enhanced_packet(&mut self, body: &[u8]) -> io::Result<Packet> {
+        if body.len() < 20 {
+            return Err(invalid("truncated pcapng enhanced packet"));
+        }
+        let interface = self.u32_at(body, 0) as usize;
+        let units = self
+            .interfaces
+            .get(interface)
+            .map_or(1_000_000, |i| i.units)
+            .max(1);
+        let timestamp = (self.u32_at(body, 4) as u64) << 32 | self.u32_at(body, 8) as u64;
+        let captured = self.u32_at(body, 12) as usize;
+        let orig_len = self.u32_at(body, 16);
+        let data = body
+            .get(20..20 + captured)
+            .ok_or_else(|| invalid("truncated pcapng enhanced packet"))?;
+        self.index += 1;
+        Ok(Packet {
+            index: self.index,
+            seconds: timestamp / units,
+            nanos: ((timestamp % units) as u128 * 1_000_000_000 / units as u128) as u32,
+            digits: match units {
+                1..=1_000 => 3,
+                1_001..=1_000_000 => 6,
+                _ => 9,
+            },
+            orig_len,
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// UTC date and time of a capture timestamp, e.g.
+/// `2023-11-14 22:13:20.123456`.
+///
+/// # Arguments
+///
+/// * `seconds` - seconds since the unix epoch.
+/// * `nanos` - nanoseconds within the second.
+/// * `digits` - fractional digits to print.
+pub fn format_timestamp(seconds: u64, nanos: u32, digits: usize) -> String {
+    // days to civil date, after Howard Hinnant's algorithm
+    let days = (seconds / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let time = seconds % 86400;
+    let mut text = format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    if digits > 0 {
+        let fraction = format!("{nanos:09}");
+        text.push('.');
+        text.push_str(&fraction[..digits.min(9)]);
+    }
+    text
+}
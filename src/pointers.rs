@@ -0,0 +1,68 @@
+//! pointer scan for `--pointer-scan`: aligned words of a memory dump whose
+//! values fall within a region, likely pointers into it
+
+use crate::interrupt;
+use crate::scalar::{self, Endian};
+
+/// Region and word layout of a pointer scan
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PointerScan {
+    /// first address of the region
+    pub base: u64,
+    /// bytes the region spans
+    pub size: u64,
+    /// bytes of a pointer, 4 or 8
+    pub width: usize,
+    /// byte order of the pointers
+    pub endian: Endian,
+}
+
+/// PointerScan implementation
+impl PointerScan {
+    /// Parse a pointer type: `u32` or `u64` with a `le` or `be` suffix,
+    /// little endian when left out, into its width and byte order.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - type, e.g. `u64` or `u32be`.
+    pub fn parse_type(text: &str) -> Result<(usize, Endian), String> {
+        let (name, endian) = match (text.strip_suffix("le"), text.strip_suffix("be")) {
+            (Some(name), _) => (name, Endian::Little),
+            (_, Some(name)) => (name, Endian::Big),
+            _ => (text, Endian::Little),
+        };
+        match name {
+            "u32" => Ok((4, endian)),
+            "u64" => Ok((8, endian)),
+            _ => Err(format!("{text:?} is not u32 or u64 with le or be")),
+        }
+    }
+
+    /// Whether a value points into the region.
+    fn contains(&self, value: u64) -> bool {
+        value >= self.base && value - self.base < self.size
+    }
+
+    /// Find the words that point into the region, at offsets aligned to
+    /// the pointer width. Returns the index of each in the data with its
+    /// value.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to scan.
+    /// * `start_offset` - offset of the first byte, for alignment.
+    pub fn scan(&self, data: &[u8], start_offset: u64) -> Vec<(usize, u64)> {
+        let width = self.width as u64;
+        let first = (width - start_offset % width) % width;
+        (first as usize..data.len())
+            .step_by(self.width)
+            // Ctrl-C stops the scan, with what it found so far
+            .take_while(|_| !interrupt::interrupted())
+            .filter_map(|at| {
+                let word = data.get(at..at + self.width)?;
+                let value = scalar::decode(word, self.endian);
+                self.contains(value).then_some((at, value))
+            })
+            .collect()
+    }
+}
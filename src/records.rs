@@ -0,0 +1,402 @@
+//! address records decoded from Intel HEX, Motorola S-record and hex text
+//! input, and the image they describe
+//!
+//! Every input line is one record. In strict mode the first malformed line
+//! is an error naming its line and input offset; otherwise it is skipped with
+//! a warning and decoding goes on with the next line.
+
+use crate::offset;
+use std::fmt;
+use std::io;
+
+/// Text formats describing bytes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Intel HEX, `:10010000...`
+    Ihex,
+    /// Motorola S-records, `S1130100...`
+    Srec,
+    /// hex digit pairs as `xxd -p` writes them, spaces, commas and `0x`
+    /// prefixes between bytes are ignored
+    HexText,
+}
+
+/// RecordFormat implementation
+impl RecordFormat {
+    /// Format for a `--from` value: ihex, srec or hex.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - format name.
+    pub fn parse(name: &str) -> Option<RecordFormat> {
+        match name {
+            "ihex" => Some(RecordFormat::Ihex),
+            "srec" => Some(RecordFormat::Srec),
+            "hex" => Some(RecordFormat::HexText),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes at an address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// address of the first byte
+    pub address: u64,
+    /// record bytes
+    pub data: Vec<u8>,
+}
+
+/// A malformed record or character
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Malformed {
+    /// line number, counting from 1
+    pub line: usize,
+    /// input offset of the offending character
+    pub offset: u64,
+    /// what is wrong with it
+    pub reason: String,
+}
+
+impl fmt::Display for Malformed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, offset {}: {}",
+            self.line,
+            offset(self.offset),
+            self.reason
+        )
+    }
+}
+
+impl From<Malformed> for io::Error {
+    fn from(e: Malformed) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+    }
+}
+
+/// What is wrong at which column of a line
+type LineError = (usize, String);
+
+/// Decoder state carried from one line to the next
+#[derive(Default)]
+struct State {
+    /// address the next record's 16-bit address is relative to (ihex), or
+    /// of the next byte (hex text)
+    base: u64,
+    /// an end of file record was read (ihex)
+    done: bool,
+}
+
+/// Decode records, in input order.
+///
+/// # Arguments
+///
+/// * `text` - input.
+/// * `format` - record format.
+/// * `strict` - fail on the first malformed line instead of skipping it.
+/// * `warn` - called with every skipped line when not strict.
+pub fn parse(
+    text: &[u8],
+    format: RecordFormat,
+    strict: bool,
+    warn: &mut dyn FnMut(&Malformed),
+) -> Result<Vec<Record>, Malformed> {
+    let mut records = Vec::new();
+    let mut state = State::default();
+    let mut start = 0;
+    for (i, line) in text.split(|&b| b == b'\n').enumerate() {
+        let line_start = start;
+        start += line.len() + 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if state.done {
+            break;
+        }
+        let parsed = match format {
+            RecordFormat::Ihex => ihex_line(line, &mut state),
+            RecordFormat::Srec => srec_line(line),
+            RecordFormat::HexText => hex_text_line(line, &mut state),
+        };
+        match parsed {
+            Ok(Some(record)) => records.push(record),
+            Ok(None) => {}
+            Err((column, reason)) => {
+                let malformed = Malformed {
+                    line: i + 1,
+                    offset: (line_start + column) as u64,
+                    reason,
+                };
+                if strict {
+                    return Err(malformed);
+                }
+                warn(&malformed);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// What fills the gaps between records
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fill {
+    /// gaps become this byte in the image
+    Byte(u8),
+    /// gaps are left out of the image and marked in the dump
+    Skip,
+}
+
+/// Gaps are zero filled by default, as objcopy does
+impl Default for Fill {
+    fn default() -> Fill {
+        Fill::Byte(0)
+    }
+}
+
+/// Which record wins where records overlap
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Conflict {
+    /// overlapping records are an error
+    #[default]
+    Error,
+    /// the record read first is kept
+    First,
+    /// the record read last is kept
+    Last,
+}
+
+/// Conflict implementation
+impl Conflict {
+    /// Policy for an `--on-conflict` value: error, first or last.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - policy name.
+    pub fn parse(name: &str) -> Option<Conflict> {
+        match name {
+            "error" => Some(Conflict::Error),
+            "first" => Some(Conflict::First),
+            "last" => Some(Conflict::Last),
+            _ => None,
+        }
+    }
+}
+
+/// Address ranges written by more than one record, as start and end,
+/// sorted and merged.
+///
+/// # Arguments
+///
+/// * `records` - decoded records.
+pub fn overlaps(records: &[Record]) -> Vec<(u64, u64)> {
+    let mut spans: Vec<(u64, u64)> = records
+        .iter()
+        .map(|record| (record.address, record.address + record.data.len() as u64))
+        .filter(|(start, end)| start < end)
+        .collect();
+    spans.sort_unstable();
+    let mut overlaps: Vec<(u64, u64)> = Vec::new();
+    let mut covered = 0;
+    for (start, end) in spans {
+        if start < covered {
+            let overlap = (start, end.min(covered));
+            match overlaps.last_mut() {
+                Some(last) if overlap.0 <= last.1 => last.1 = last.1.max(overlap.1),
+                _ => overlaps.push(overlap),
+            }
+        }
+        covered = covered.max(end);
+    }
+    overlaps
+}
+
+/// Runs of contiguous bytes described by records, by address. Returns the
+/// address and bytes of every run.
+///
+/// # Arguments
+///
+/// * `records` - decoded records.
+/// * `conflict` - which record wins where records overlap, the last one
+///   unless `First`.
+pub fn segments(records: &[Record], conflict: Conflict) -> Vec<(u64, Vec<u8>)> {
+    let mut records: Vec<&Record> = records.iter().filter(|r| !r.data.is_empty()).collect();
+    let mut spans: Vec<(u64, u64)> = records
+        .iter()
+        .map(|record| (record.address, record.address + record.data.len() as u64))
+        .collect();
+    spans.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    let mut segments: Vec<(u64, Vec<u8>)> = merged
+        .iter()
+        .map(|(start, end)| (*start, vec![0; (end - start) as usize]))
+        .collect();
+    // written in order, so the record written last wins
+    if conflict == Conflict::First {
+        records.reverse();
+    }
+    for record in records {
+        let i = merged.partition_point(|(_, end)| *end <= record.address);
+        let (start, data) = &mut segments[i];
+        let at = (record.address - *start) as usize;
+        data[at..at + record.data.len()].copy_from_slice(&record.data);
+    }
+    segments
+}
+
+/// The image described by records: bytes from the lowest address to the
+/// end of the highest record, gaps filled with `fill`. Returns the lowest
+/// address and the image.
+///
+/// # Arguments
+///
+/// * `records` - decoded records.
+/// * `fill` - byte filling the gaps between records.
+/// * `conflict` - which record wins where records overlap.
+pub fn assemble(records: &[Record], fill: u8, conflict: Conflict) -> (u64, Vec<u8>) {
+    let segments = segments(records, conflict);
+    let (Some((base, _)), Some((last, data))) = (segments.first(), segments.last()) else {
+        return (0, Vec::new());
+    };
+    let mut image = vec![fill; (last + data.len() as u64 - base) as usize];
+    for (start, data) in &segments {
+        let at = (start - base) as usize;
+        image[at..at + data.len()].copy_from_slice(data);
+    }
+    (*base, image)
+}
+
+/// Bytes of hex digit pairs.
+///
+/// # Arguments
+///
+/// * `digits` - hex digits.
+/// * `column` - column of the first digit, for errors.
+fn decode_hex(digits: &[u8], column: usize) -> Result<Vec<u8>, LineError> {
+    if let Some(bad) = digits.iter().position(|b| !b.is_ascii_hexdigit()) {
+        return Err((
+            column + bad,
+            format!("invalid hex digit '{}'", digits[bad].escape_ascii()),
+        ));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err((column + digits.len(), "odd number of hex digits".into()));
+    }
+    let nibble = |b: u8| (b as char).to_digit(16).unwrap_or_default() as u8;
+    Ok(digits
+        .chunks(2)
+        .map(|pair| nibble(pair[0]) << 4 | nibble(pair[1]))
+        .collect())
+}
+
+/// One Intel HEX line: data, end of file, or an extended address.
+fn ihex_line(line: &[u8], state: &mut State) -> Result<Option<Record>, LineError> {
+    if line.trim_ascii().is_empty() {
+        return Ok(None);
+    }
+    if line[0] != b':' {
+        return Err((0, "record doesn't start with ':'".into()));
+    }
+    let bytes = decode_hex(line[1..].trim_ascii_end(), 1)?;
+    if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+        return Err((1, "record length doesn't match its byte count".into()));
+    }
+    let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    if sum != 0 {
+        let stated = bytes[bytes.len() - 1];
+        return Err((
+            1 + 2 * (bytes.len() - 1),
+            format!(
+                "checksum {stated:#04x}, expected {:#04x}",
+                stated.wrapping_sub(sum)
+            ),
+        ));
+    }
+    let address = u16::from_be_bytes([bytes[1], bytes[2]]) as u64;
+    let data = &bytes[4..bytes.len() - 1];
+    let word = || match data {
+        [hi, lo] => Ok(u16::from_be_bytes([*hi, *lo]) as u64),
+        _ => Err((9, "address record needs 2 data bytes".to_string())),
+    };
+    match bytes[3] {
+        0x00 => {
+            return Ok(Some(Record {
+                address: state.base + address,
+                data: data.to_vec(),
+            }))
+        }
+        0x01 => state.done = true,
+        0x02 => state.base = word()? << 4,
+        0x04 => state.base = word()? << 16,
+        // start addresses don't place bytes
+        0x03 | 0x05 => {}
+        kind => return Err((7, format!("unknown record type {kind:#04x}"))),
+    }
+    Ok(None)
+}
+
+/// One S-record line: data records place bytes, headers, counts and start
+/// addresses don't.
+fn srec_line(line: &[u8]) -> Result<Option<Record>, LineError> {
+    if line.trim_ascii().is_empty() {
+        return Ok(None);
+    }
+    if line[0] != b'S' {
+        return Err((0, "record doesn't start with 'S'".into()));
+    }
+    let address_len = match line.get(1) {
+        Some(b'0' | b'1' | b'5' | b'9') => 2,
+        Some(b'2' | b'6' | b'8') => 3,
+        Some(b'3' | b'7') => 4,
+        _ => return Err((1, "unknown record type".into())),
+    };
+    let bytes = decode_hex(line[2..].trim_ascii_end(), 2)?;
+    if bytes.len() < address_len + 2 || bytes.len() != bytes[0] as usize + 1 {
+        return Err((2, "record length doesn't match its byte count".into()));
+    }
+    let last = bytes.len() - 1;
+    let sum = bytes[..last]
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_add(*b));
+    if !sum != bytes[last] {
+        return Err((
+            2 + 2 * last,
+            format!("checksum {:#04x}, expected {:#04x}", bytes[last], !sum),
+        ));
+    }
+    if !matches!(line[1], b'1'..=b'3') {
+        return Ok(None);
+    }
+    let address = bytes[1..=address_len]
+        .iter()
+        .fold(0u64, |address, b| address << 8 | *b as u64);
+    Ok(Some(Record {
+        address,
+        data: bytes[address_len + 1..last].to_vec(),
+    }))
+}
+
+/// One line of hex text, placed right after the bytes of the line before.
+fn hex_text_line(line: &[u8], state: &mut State) -> Result<Option<Record>, LineError> {
+    let mut data = Vec::new();
+    let mut column = 0;
+    for token in line.split(|b| b.is_ascii_whitespace() || *b == b',') {
+        let digits = match token {
+            [b'0', b'x' | b'X', rest @ ..] => rest,
+            _ => token,
+        };
+        data.extend(decode_hex(digits, column + token.len() - digits.len())?);
+        column += token.len() + 1;
+    }
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let address = state.base;
+    state.base += data.len() as u64;
+    Ok(Some(Record { address, data }))
+}
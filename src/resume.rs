@@ -0,0 +1,120 @@
+//! resuming interrupted dump and array output files
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Where an interrupted output file left off
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResumePoint {
+    /// bytes of the existing output to keep, up to its last complete line
+    pub keep: u64,
+    /// the array declaration is already written
+    pub header: bool,
+    /// complete dump lines or array rows already written
+    pub rows: u64,
+    /// the output already ends with its summary or closing line
+    pub complete: bool,
+}
+
+fn mismatch(line: usize, what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "can't resume: line {} of the existing output {what}",
+            line + 1
+        ),
+    )
+}
+
+/// Find where a partial dump or array output left off. Only complete lines
+/// count; a line cut short by the interruption is dropped and written again.
+///
+/// # Arguments
+///
+/// * `partial` - existing output.
+/// * `array` - the output is a source code array rather than a dump.
+/// * `columns` - bytes per line the output was written with.
+/// * `first_offset` - offset of the first dump line, the `--skip` it was written with.
+pub fn resume_point(
+    partial: &[u8],
+    array: bool,
+    columns: u64,
+    first_offset: u64,
+) -> io::Result<ResumePoint> {
+    let mut point = ResumePoint::default();
+    let complete_len = partial
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |end| end + 1);
+    // elements on the previous array row, only the last row may be short
+    let mut previous: Option<u64> = None;
+
+    for (i, line) in partial[..complete_len].split(|b| *b == b'\n').enumerate() {
+        if point.keep == complete_len as u64 {
+            break;
+        }
+        point.keep += line.len() as u64 + 1;
+        let text = String::from_utf8_lossy(line);
+        if text.contains('\x1b') {
+            return Err(mismatch(i, "is colored, write it with --color 0"));
+        }
+        if array {
+            if !point.header {
+                point.header = true;
+            } else if let Some(row) = text.strip_prefix("    ") {
+                if previous.is_some_and(|elements| elements != columns) {
+                    return Err(mismatch(i - 1, "doesn't match --cols"));
+                }
+                previous = Some(row.matches("0x").count() as u64);
+                point.rows += 1;
+            } else {
+                point.complete = true;
+            }
+        } else if let Some(dump) = text.strip_prefix("0x") {
+            let offset = dump
+                .split_once(':')
+                .and_then(|(offset, _)| u64::from_str_radix(offset, 16).ok());
+            if offset != Some(first_offset + point.rows * columns) {
+                return Err(mismatch(i, "doesn't match --cols"));
+            }
+            point.rows += 1;
+        } else {
+            point.complete = true;
+        }
+        if point.complete {
+            break;
+        }
+    }
+    Ok(point)
+}
+
+/// Open an interrupted output file for appending, cut back to its last
+/// complete line, and return where it left off. A missing file starts
+/// from scratch.
+///
+/// # Arguments
+///
+/// * `path` - output file.
+/// * `array` - the output is a source code array rather than a dump.
+/// * `columns` - bytes per line the output was written with.
+/// * `first_offset` - offset of the first dump line.
+pub fn reopen(
+    path: &Path,
+    array: bool,
+    columns: u64,
+    first_offset: u64,
+) -> io::Result<(File, ResumePoint)> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    let mut partial = Vec::new();
+    file.read_to_end(&mut partial)?;
+    let point = resume_point(&partial, array, columns, first_offset)?;
+    file.set_len(point.keep)?;
+    file.seek(SeekFrom::End(0))?;
+    Ok((file, point))
+}
@@ -0,0 +1,209 @@
+//! `--profile safe`: output for chats, IRC and terminals with little
+//! support, colored with the 16 basic colors alone and written in ascii,
+//! so a dump pasted anywhere keeps its colors legible and its columns
+//! aligned
+
+use crate::style::{self, BASIC};
+use ansi_term::Color;
+use std::io::{self, Write};
+
+/// Escape character starting a color code
+const ESC: u8 = 0x1b;
+
+/// Writer passing output on with every 256 color or RGB code turned into
+/// the nearest basic color, box-drawing characters into `|`, `-` and `+`,
+/// and any other character past ascii into a dot
+pub struct SafeWriter<W: Write> {
+    inner: W,
+    /// start of an escape code or a character cut off by the last write
+    pending: Vec<u8>,
+}
+
+/// SafeWriter implementation
+impl<W: Write> SafeWriter<W> {
+    /// SafeWriter constructor
+    pub fn new(inner: W) -> SafeWriter<W> {
+        SafeWriter {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+}
+
+/// Index of the basic color nearest to a color, black and the whites left
+/// out for text so it shows on dark and light backgrounds alike.
+///
+/// # Arguments
+///
+/// * `color` - any terminal color.
+/// * `text` - whether it's a text color rather than a background.
+fn nearest(color: Color, text: bool) -> usize {
+    let rgb = style::rgb(color);
+    let channel = |rgb: u32, shift: u32| i64::from((rgb >> shift) & 0xff);
+    let distance = |basic: u32| {
+        [16, 8, 0]
+            .iter()
+            .map(|shift| (channel(rgb, *shift) - channel(basic, *shift)).pow(2))
+            .sum::<i64>()
+    };
+    (0..BASIC.len())
+        .filter(|i| !text || ![0, 7, 15].contains(i))
+        .min_by_key(|i| distance(BASIC[*i]))
+        .unwrap_or(0)
+}
+
+/// Parameters of a select graphic rendition code with the 256 color and
+/// RGB ones replaced by basic colors, `30`-`37` and `90`-`97` for text and
+/// `40`-`47` and `100`-`107` for backgrounds.
+fn basic_params(params: &str) -> String {
+    let values: Vec<&str> = params.split(';').collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        let number = |at: usize| values.get(at).and_then(|value| value.parse::<u8>().ok());
+        let color = match (values[i], values.get(i + 1).copied()) {
+            ("38" | "48", Some("5")) => number(i + 2).map(|n| (Color::Fixed(n), 3)),
+            ("38" | "48", Some("2")) => match (number(i + 2), number(i + 3), number(i + 4)) {
+                (Some(r), Some(g), Some(b)) => Some((Color::RGB(r, g, b), 5)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match color {
+            Some((color, used)) => {
+                let text = values[i] == "38";
+                let index = nearest(color, text);
+                let base = match (text, index < 8) {
+                    (true, true) => 30,
+                    (true, false) => 90 - 8,
+                    (false, true) => 40,
+                    (false, false) => 100 - 8,
+                };
+                out.push((base + index).to_string());
+                i += used;
+            }
+            None => {
+                out.push(values[i].to_string());
+                i += 1;
+            }
+        }
+    }
+    out.join(";")
+}
+
+/// Ascii stand-in for a character past ascii.
+fn ascii(c: char) -> char {
+    match c {
+        '│' | '┃' | '║' => '|',
+        '─' | '━' | '═' => '-',
+        '┌' | '┬' | '┐' | '├' | '┼' | '┤' | '└' | '┴' | '┘' => '+',
+        _ => '.',
+    }
+}
+
+/// Bytes of an incomplete escape code or character at the end of a
+/// buffer, to wait for the rest of.
+fn incomplete(buf: &[u8]) -> usize {
+    if let Some(esc) = buf.iter().rposition(|b| *b == ESC) {
+        let code = &buf[esc + 1..];
+        let finished = match code.first() {
+            None => false,
+            Some(b'[') => code[1..].iter().any(|b| (0x40..=0x7e).contains(b)),
+            Some(_) => true,
+        };
+        if !finished {
+            return buf.len() - esc;
+        }
+    }
+    // the lead byte of a utf-8 sequence missing some of its bytes
+    let tail = buf.len().saturating_sub(3);
+    for at in (tail..buf.len()).rev() {
+        let needs = match buf[at] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            0x80..=0xbf => continue,
+            _ => break,
+        };
+        if buf.len() - at < needs {
+            return buf.len() - at;
+        }
+        break;
+    }
+    0
+}
+
+/// Safe text of complete output: escape codes with basic colors, ascii
+/// characters, and a dot for every byte that isn't utf-8.
+fn convert(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut at = 0;
+    while at < buf.len() {
+        if buf[at] == ESC && buf.get(at + 1) == Some(&b'[') {
+            let end = buf[at + 2..]
+                .iter()
+                .position(|b| (0x40..=0x7e).contains(b))
+                .map_or(buf.len(), |end| at + 2 + end);
+            let params = String::from_utf8_lossy(&buf[at + 2..end.min(buf.len())]);
+            match buf.get(end) {
+                Some(b'm') => {
+                    out.extend_from_slice(format!("\x1b[{}m", basic_params(&params)).as_bytes())
+                }
+                _ => out.extend_from_slice(&buf[at..(end + 1).min(buf.len())]),
+            }
+            at = end + 1;
+            continue;
+        }
+        if buf[at].is_ascii() {
+            out.push(buf[at]);
+            at += 1;
+            continue;
+        }
+        let len = match buf[at] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let c = buf
+            .get(at..at + len)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|text| text.chars().next());
+        match c {
+            Some(c) => {
+                out.push(ascii(c) as u8);
+                at += len;
+            }
+            None => {
+                out.push(b'.');
+                at += 1;
+            }
+        }
+    }
+    out
+}
+
+impl<W: Write> Write for SafeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let keep = incomplete(&self.pending);
+        let ready = self.pending.len() - keep;
+        let text = convert(&self.pending[..ready]);
+        self.pending.drain(..ready);
+        self.inner.write_all(&text)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for SafeWriter<W> {
+    fn drop(&mut self) {
+        // whatever was cut off for good, so nothing is lost
+        let text = convert(&self.pending);
+        let _ = self.inner.write_all(&text);
+        let _ = self.inner.flush();
+    }
+}
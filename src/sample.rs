@@ -0,0 +1,111 @@
+//! seeded random sampling of fixed size windows
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// splitmix64 generator, small and reproducible across platforms
+#[derive(Clone, Debug)]
+pub struct SplitMix64(u64);
+
+/// SplitMix64 implementation
+impl SplitMix64 {
+    /// SplitMix64 constructor
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64(seed)
+    }
+
+    /// Next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`, bound must not be 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+/// A sampled window: its index and bytes
+pub type Window = (u64, Vec<u8>);
+
+/// Pick `count` distinct window indexes out of `windows`, sorted, with
+/// Floyd's algorithm.
+///
+/// # Arguments
+///
+/// * `windows` - number of windows to choose from.
+/// * `count` - number of windows to pick.
+/// * `seed` - random seed.
+pub fn pick(windows: u64, count: u64, seed: u64) -> Vec<u64> {
+    let mut rng = SplitMix64::new(seed);
+    let mut picked: Vec<u64> = Vec::new();
+    for j in windows.saturating_sub(count)..windows {
+        let t = rng.below(j + 1);
+        picked.push(match picked.contains(&t) {
+            true => j,
+            false => t,
+        });
+    }
+    picked.sort_unstable();
+    picked
+}
+
+/// Sample `count` windows of a file, seeking straight to each one.
+///
+/// # Arguments
+///
+/// * `file` - input file.
+/// * `window` - window length in bytes.
+/// * `count` - number of windows.
+/// * `seed` - random seed.
+pub fn sample_file(file: &mut File, window: u64, count: u64, seed: u64) -> io::Result<Vec<Window>> {
+    let len = file.metadata()?.len();
+    let mut windows = Vec::new();
+    for index in pick(len.div_ceil(window), count, seed) {
+        file.seek(SeekFrom::Start(index * window))?;
+        let mut data = Vec::new();
+        (&mut *file).take(window).read_to_end(&mut data)?;
+        windows.push((index, data));
+    }
+    Ok(windows)
+}
+
+/// Sample `count` windows of a stream of unknown length with reservoir
+/// sampling, keeping at most `count` windows in memory.
+///
+/// # Arguments
+///
+/// * `buf` - input to be read.
+/// * `window` - window length in bytes.
+/// * `count` - number of windows.
+/// * `seed` - random seed.
+pub fn sample_stream(
+    buf: &mut dyn Read,
+    window: u64,
+    count: u64,
+    seed: u64,
+) -> io::Result<Vec<Window>> {
+    let mut rng = SplitMix64::new(seed);
+    let mut reservoir: Vec<Window> = Vec::new();
+    for index in 0.. {
+        let mut data = Vec::new();
+        buf.take(window).read_to_end(&mut data)?;
+        if data.is_empty() {
+            break;
+        }
+        if index < count {
+            reservoir.push((index, data));
+        } else {
+            let slot = rng.below(index + 1);
+            if slot < count {
+                reservoir[slot as usize] = (index, data);
+            }
+        }
+    }
+    reservoir.sort_unstable_by_key(|(index, _)| *index);
+    Ok(reservoir)
+}
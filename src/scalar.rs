@@ -0,0 +1,642 @@
+//! typed scalar reading and bit field extraction
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Scalar types understood by `extract`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+    /// unsigned 8 bit
+    U8,
+    /// unsigned 16 bit
+    U16,
+    /// unsigned 32 bit
+    U32,
+    /// unsigned 64 bit
+    U64,
+    /// signed 8 bit
+    I8,
+    /// signed 16 bit
+    I16,
+    /// signed 32 bit
+    I32,
+    /// signed 64 bit
+    I64,
+    /// 32 bit float
+    F32,
+    /// 64 bit float
+    F64,
+}
+
+/// Every scalar type name, as accepted on the command line
+pub const SCALAR_TYPES: [&str; 10] = [
+    "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64",
+];
+
+/// ScalarType implementation
+impl ScalarType {
+    /// Scalar type for a name such as `u32`.
+    pub fn parse(name: &str) -> Option<ScalarType> {
+        Some(match name {
+            "u8" => ScalarType::U8,
+            "u16" => ScalarType::U16,
+            "u32" => ScalarType::U32,
+            "u64" => ScalarType::U64,
+            "i8" => ScalarType::I8,
+            "i16" => ScalarType::I16,
+            "i32" => ScalarType::I32,
+            "i64" => ScalarType::I64,
+            "f32" => ScalarType::F32,
+            "f64" => ScalarType::F64,
+            _ => return None,
+        })
+    }
+
+    /// Scalar type for a name with an optional byte order suffix, such as
+    /// `u16le` or `f32be`, and the byte order when it has one.
+    pub fn parse_ordered(name: &str) -> Option<(ScalarType, Option<Endian>)> {
+        match (name.strip_suffix("le"), name.strip_suffix("be")) {
+            (Some(kind), _) => Some((ScalarType::parse(kind)?, Some(Endian::Little))),
+            (_, Some(kind)) => Some((ScalarType::parse(kind)?, Some(Endian::Big))),
+            _ => Some((ScalarType::parse(name)?, None)),
+        }
+    }
+
+    /// Width in bytes.
+    pub fn width(&self) -> usize {
+        match self {
+            ScalarType::U8 | ScalarType::I8 => 1,
+            ScalarType::U16 | ScalarType::I16 => 2,
+            ScalarType::U32 | ScalarType::I32 | ScalarType::F32 => 4,
+            ScalarType::U64 | ScalarType::I64 | ScalarType::F64 => 8,
+        }
+    }
+
+    /// Decimal text of raw bits interpreted as this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - value bits, zero extended.
+    pub fn display(&self, raw: u64) -> String {
+        match self {
+            ScalarType::I8 => (raw as i8).to_string(),
+            ScalarType::I16 => (raw as i16).to_string(),
+            ScalarType::I32 => (raw as i32).to_string(),
+            ScalarType::I64 => (raw as i64).to_string(),
+            ScalarType::F32 => f32::from_bits(raw as u32).to_string(),
+            ScalarType::F64 => f64::from_bits(raw).to_string(),
+            _ => raw.to_string(),
+        }
+    }
+
+    /// Order of two raw values read as this type, None when a float is NaN.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - value bits, zero extended.
+    /// * `b` - value bits, zero extended.
+    pub fn order(&self, a: u64, b: u64) -> Option<Ordering> {
+        let signed = |raw: u64| {
+            let shift = 64 - self.width() as u32 * 8;
+            ((raw << shift) as i64) >> shift
+        };
+        match self {
+            ScalarType::I8 | ScalarType::I16 | ScalarType::I32 | ScalarType::I64 => {
+                Some(signed(a).cmp(&signed(b)))
+            }
+            ScalarType::F32 => f32::from_bits(a as u32).partial_cmp(&f32::from_bits(b as u32)),
+            ScalarType::F64 => f64::from_bits(a).partial_cmp(&f64::from_bits(b)),
+            _ => Some(a.cmp(&b)),
+        }
+    }
+}
+
+impl fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ScalarType::U8 => "u8",
+            ScalarType::U16 => "u16",
+            ScalarType::U32 => "u32",
+            ScalarType::U64 => "u64",
+            ScalarType::I8 => "i8",
+            ScalarType::I16 => "i16",
+            ScalarType::I32 => "i32",
+            ScalarType::I64 => "i64",
+            ScalarType::F32 => "f32",
+            ScalarType::F64 => "f64",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Byte order
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endian {
+    /// least significant byte first
+    Little,
+    /// most significant byte first
+    Big,
+}
+
+impl fmt::Display for Endian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endian::Little => write!(f, "little"),
+            Endian::Big => write!(f, "big"),
+        }
+    }
+}
+
+/// A typed scalar at a file offset
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScalarSpec {
+    /// offset of the first byte
+    pub at: u64,
+    /// scalar type
+    pub kind: ScalarType,
+    /// byte order
+    pub endian: Endian,
+}
+
+/// Combine bytes into a value in the given byte order.
+///
+/// # Arguments
+///
+/// * `bytes` - up to 8 bytes.
+/// * `endian` - byte order.
+pub fn decode(bytes: &[u8], endian: Endian) -> u64 {
+    let fold = |raw: u64, b: &u8| (raw << 8) | u64::from(*b);
+    match endian {
+        Endian::Big => bytes.iter().fold(0, fold),
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+    }
+}
+
+/// Bytes of a value in the given byte order.
+///
+/// # Arguments
+///
+/// * `raw` - value bits, zero extended.
+/// * `width` - number of bytes, up to 8.
+/// * `endian` - byte order.
+pub fn encode(raw: u64, width: usize, endian: Endian) -> Vec<u8> {
+    let bytes = raw.to_le_bytes()[..width].to_vec();
+    match endian {
+        Endian::Little => bytes,
+        Endian::Big => bytes.into_iter().rev().collect(),
+    }
+}
+
+/// Parse a value of the given type into its raw bits: decimal, hex with a
+/// `0x` prefix, negative for signed types, or a float literal.
+///
+/// # Arguments
+///
+/// * `text` - value to parse.
+/// * `kind` - scalar type the value must fit.
+pub fn parse_scalar(text: &str, kind: ScalarType) -> Result<u64, String> {
+    let bits = kind.width() as u32 * 8;
+    let mask = match bits {
+        64 => u64::MAX,
+        _ => (1u64 << bits) - 1,
+    };
+    let out_of_range = || format!("{text} doesn't fit in {kind}");
+    match kind {
+        ScalarType::F32 => text
+            .parse::<f32>()
+            .map(|f| u64::from(f.to_bits()))
+            .map_err(|e| e.to_string()),
+        ScalarType::F64 => text
+            .parse::<f64>()
+            .map(f64::to_bits)
+            .map_err(|e| e.to_string()),
+        _ => {
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, text),
+            };
+            let hex = digits
+                .strip_prefix("0x")
+                .or_else(|| digits.strip_prefix("0X"));
+            let magnitude = match hex {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => digits.parse::<u64>(),
+            }
+            .map_err(|e| e.to_string())?;
+            let signed = matches!(
+                kind,
+                ScalarType::I8 | ScalarType::I16 | ScalarType::I32 | ScalarType::I64
+            );
+            match (negative, signed) {
+                // hex gives the raw bits, so 0xff is a valid i8
+                (false, false) if magnitude <= mask => Ok(magnitude),
+                (false, true) if hex.is_some() && magnitude <= mask => Ok(magnitude),
+                (false, true) if magnitude < 1u64 << (bits - 1) => Ok(magnitude),
+                // two's complement, down to the most negative value of the type
+                (true, true) if magnitude <= 1u64 << (bits - 1) => {
+                    Ok(magnitude.wrapping_neg() & mask)
+                }
+                _ => Err(out_of_range()),
+            }
+        }
+    }
+}
+
+/// Bits `low` through `high` inclusive of `raw`, shifted down to bit 0.
+///
+/// # Arguments
+///
+/// * `raw` - value bits.
+/// * `low` - lowest bit, 0 is the least significant.
+/// * `high` - highest bit.
+pub fn bit_field(raw: u64, low: u32, high: u32) -> u64 {
+    let width = high - low + 1;
+    let mask = match width {
+        64 => u64::MAX,
+        _ => (1u64 << width) - 1,
+    };
+    (raw >> low) & mask
+}
+
+/// Read the raw bits of a scalar, reading forward to its offset.
+///
+/// # Arguments
+///
+/// * `buf` - input, read from the start.
+/// * `spec` - scalar to read.
+pub fn read_scalar(buf: &mut dyn Read, spec: &ScalarSpec) -> io::Result<u64> {
+    let skipped = crate::discard(buf, spec.at)?;
+    let mut bytes = vec![0; spec.kind.width()];
+    if skipped < spec.at || buf.read_exact(&mut bytes).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "{} at {} is beyond the end of the input",
+                spec.kind,
+                crate::offset(spec.at)
+            ),
+        ));
+    }
+    Ok(decode(&bytes, spec.endian))
+}
+
+/// Print a scalar, or a bit field of it, in decimal, hex, octal and binary.
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `spec` - scalar that was read.
+/// * `raw` - its raw bits.
+/// * `bits` - inclusive bit range to extract, the whole value when None.
+pub fn describe(
+    w: &mut impl Write,
+    spec: &ScalarSpec,
+    raw: u64,
+    bits: Option<(u32, u32)>,
+) -> io::Result<()> {
+    let digits = spec.kind.width() * 2;
+    writeln!(w, "{:>8}: {}", "at", crate::offset(spec.at))?;
+    writeln!(w, "{:>8}: {} {}", "type", spec.kind, spec.endian)?;
+    writeln!(w, "{:>8}: {:#02$x}", "raw", raw, digits + 2)?;
+    let (value, decimal) = match bits {
+        Some((low, high)) => {
+            writeln!(w, "{:>8}: {low}:{high}", "bits")?;
+            let field = bit_field(raw, low, high);
+            (field, field.to_string())
+        }
+        None => (raw, spec.kind.display(raw)),
+    };
+    writeln!(w, "{:>8}: {decimal}", "dec")?;
+    writeln!(w, "{:>8}: {value:#x}", "hex")?;
+    writeln!(w, "{:>8}: {value:#o}", "oct")?;
+    writeln!(w, "{:>8}: {value:#b}", "bin")?;
+    w.flush()
+}
+
+/// A named bit range of a register
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitField {
+    /// highest bit
+    pub high: u32,
+    /// lowest bit, 0 is the least significant
+    pub low: u32,
+    /// field name, empty for bits no field covers
+    pub name: String,
+}
+
+/// Parse a register layout such as `31:24 flags,23:12 addr,11:0 len`: comma
+/// separated fields, each a bit range written `high:low` or a single bit,
+/// and a name. Fields are returned highest first, with the bits no field
+/// covers filled in as unnamed fields.
+///
+/// # Arguments
+///
+/// * `text` - layout.
+/// * `kind` - scalar type the fields must fit.
+pub fn parse_layout(text: &str, kind: ScalarType) -> Result<Vec<BitField>, String> {
+    let width = kind.width() as u32 * 8;
+    let mut fields = Vec::new();
+    for item in text.split(',').map(str::trim) {
+        let (bits, name) = item
+            .split_once(char::is_whitespace)
+            .ok_or(format!("{item:?} is not a bit range and a name"))?;
+        let bit = |text: &str| {
+            text.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("{text:?} is not a bit number"))
+        };
+        let (high, low) = match bits.split_once(':') {
+            Some((high, low)) => (bit(high)?, bit(low)?),
+            None => (bit(bits)?, bit(bits)?),
+        };
+        let (high, low) = (high.max(low), high.min(low));
+        if high >= width {
+            return Err(format!("bits {bits} don't fit in a {width} bit {kind}"));
+        }
+        fields.push(BitField {
+            high,
+            low,
+            name: name.trim().to_string(),
+        });
+    }
+    fields.sort_by_key(|field| std::cmp::Reverse(field.high));
+    let mut next = width;
+    let mut layout = Vec::new();
+    for field in fields {
+        if field.high >= next {
+            return Err(format!("field {} overlaps the one before it", field.name));
+        }
+        if field.high + 1 < next {
+            layout.push(BitField {
+                high: next - 1,
+                low: field.high + 1,
+                name: String::new(),
+            });
+        }
+        next = field.low;
+        layout.push(field);
+    }
+    if next > 0 {
+        layout.push(BitField {
+            high: next - 1,
+            low: 0,
+            name: String::new(),
+        });
+    }
+    Ok(layout)
+}
+
+/// Draw a register diagram of a scalar: a box per field holding its bit
+/// range, name, and value in hex and decimal, highest bits on the left.
+/// Bits no field covers are drawn with a `-` name.
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `spec` - scalar that was read.
+/// * `raw` - its raw bits.
+/// * `layout` - fields from [parse_layout], highest first.
+pub fn diagram(
+    w: &mut impl Write,
+    spec: &ScalarSpec,
+    raw: u64,
+    layout: &[BitField],
+) -> io::Result<()> {
+    let digits = spec.kind.width() * 2;
+    writeln!(w, "{:>8}: {}", "at", crate::offset(spec.at))?;
+    writeln!(w, "{:>8}: {} {}", "type", spec.kind, spec.endian)?;
+    writeln!(w, "{:>8}: {:#02$x}", "raw", raw, digits + 2)?;
+    let cells: Vec<[String; 4]> = layout
+        .iter()
+        .map(|field| {
+            let value = bit_field(raw, field.low, field.high);
+            [
+                match field.high == field.low {
+                    true => field.high.to_string(),
+                    false => format!("{}:{}", field.high, field.low),
+                },
+                match field.name.is_empty() {
+                    true => "-".to_string(),
+                    false => field.name.clone(),
+                },
+                format!("{value:#x}"),
+                value.to_string(),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = cells
+        .iter()
+        .map(|cell| cell.iter().map(String::len).max().unwrap_or(0) + 2)
+        .collect();
+    let rule = widths
+        .iter()
+        .map(|width| "-".repeat(*width))
+        .collect::<Vec<_>>()
+        .join("+");
+    writeln!(w, "+{rule}+")?;
+    for row in 0..4 {
+        let text = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:^width$}", cell[row]))
+            .collect::<Vec<_>>()
+            .join("|");
+        writeln!(w, "|{text}|")?;
+        if row == 1 {
+            writeln!(w, "+{rule}+")?;
+        }
+    }
+    writeln!(w, "+{rule}+")?;
+    w.flush()
+}
+
+/// Sum, extremes and count of the typed values in byte ranges, for `hx sum`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Totals {
+    /// type of the values
+    pub kind: ScalarType,
+    /// byte order of the values
+    pub endian: Endian,
+    /// values read
+    pub count: u64,
+    /// sum of the integer values, exact
+    pub sum: i128,
+    /// sum of the float values
+    pub float_sum: f64,
+    /// raw bits and offset of the smallest value, the first of equals
+    pub min: Option<(u64, u64)>,
+    /// raw bits and offset of the largest value, the first of equals
+    pub max: Option<(u64, u64)>,
+    /// bytes at the end of ranges too short for a value
+    pub trailing: u64,
+}
+
+/// Totals implementation
+impl Totals {
+    /// Totals of no values yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - type of the values.
+    /// * `endian` - byte order of the values.
+    pub fn new(kind: ScalarType, endian: Endian) -> Totals {
+        Totals {
+            kind,
+            endian,
+            count: 0,
+            sum: 0,
+            float_sum: 0.0,
+            min: None,
+            max: None,
+            trailing: 0,
+        }
+    }
+
+    /// Whether the values are floats.
+    fn is_float(&self) -> bool {
+        matches!(self.kind, ScalarType::F32 | ScalarType::F64)
+    }
+
+    /// Count one value.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - its bits, zero extended.
+    /// * `at` - its offset.
+    pub fn add(&mut self, raw: u64, at: u64) {
+        let shift = 64 - self.kind.width() as u32 * 8;
+        match self.kind {
+            ScalarType::F32 => self.float_sum += f64::from(f32::from_bits(raw as u32)),
+            ScalarType::F64 => self.float_sum += f64::from_bits(raw),
+            ScalarType::I8 | ScalarType::I16 | ScalarType::I32 | ScalarType::I64 => {
+                self.sum += i128::from(((raw << shift) as i64) >> shift)
+            }
+            _ => self.sum += i128::from(raw),
+        }
+        self.count += 1;
+        let kind = self.kind;
+        // NaN floats are summed but have no place in the order
+        let beats = |best: Option<(u64, u64)>, wanted: Ordering| {
+            kind.order(raw, raw).is_some()
+                && best.is_none_or(|(best, _)| kind.order(raw, best) == Some(wanted))
+        };
+        if beats(self.min, Ordering::Less) {
+            self.min = Some((raw, at));
+        }
+        if beats(self.max, Ordering::Greater) {
+            self.max = Some((raw, at));
+        }
+    }
+
+    /// Mean of the values, None without any.
+    pub fn mean(&self) -> Option<f64> {
+        let sum = match self.is_float() {
+            true => self.float_sum,
+            false => self.sum as f64,
+        };
+        (self.count > 0).then(|| sum / self.count as f64)
+    }
+}
+
+/// Add up the values of a type in byte ranges of an input, or in all of
+/// it without ranges. Values are read from the start of every range, and
+/// ranges past the end of the input stop there.
+///
+/// # Arguments
+///
+/// * `buf` - input, read from the start.
+/// * `ranges` - sorted, disjoint `start`, `end` byte ranges, empty for all.
+/// * `kind` - type of the values.
+/// * `endian` - byte order of the values.
+pub fn totals(
+    buf: &mut dyn Read,
+    ranges: &[(u64, u64)],
+    kind: ScalarType,
+    endian: Endian,
+) -> io::Result<Totals> {
+    let mut totals = Totals::new(kind, endian);
+    let whole = [(0, u64::MAX)];
+    let ranges = match ranges.is_empty() {
+        true => &whole[..],
+        false => ranges,
+    };
+    let width = kind.width();
+    let mut position = 0;
+    let mut block = vec![0; 64 * 1024 / width * width];
+    for (start, end) in ranges {
+        position += crate::discard(buf, start - position)?;
+        if position < *start {
+            break;
+        }
+        let mut range = buf.take(end - start);
+        // bytes of the block filled, a value cut by the read kept at its start
+        let mut filled = 0;
+        loop {
+            let read = range.read(&mut block[filled..])?;
+            filled += read;
+            let complete = filled / width * width;
+            for (i, value) in block[..complete].chunks_exact(width).enumerate() {
+                totals.add(decode(value, endian), position + (i * width) as u64);
+            }
+            position += complete as u64;
+            block.copy_within(complete..filled, 0);
+            filled -= complete;
+            if read == 0 {
+                break;
+            }
+        }
+        totals.trailing += filled as u64;
+        position += filled as u64;
+    }
+    Ok(totals)
+}
+
+/// Print the totals of `hx sum`: the count, the sum, in hex as well and
+/// cut to the type width for integers, the smallest and largest values
+/// with their offsets and the mean.
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `totals` - values added up.
+pub fn report(w: &mut impl Write, totals: &Totals) -> io::Result<()> {
+    writeln!(w, "{:>8}: {} {}", "type", totals.kind, totals.endian)?;
+    writeln!(w, "{:>8}: {}", "values", totals.count)?;
+    if totals.is_float() {
+        writeln!(w, "{:>8}: {}", "sum", totals.float_sum)?;
+    } else {
+        let bits = totals.kind.width() as u32 * 8;
+        let wrapped = (totals.sum as u128) & (u128::MAX >> (128 - bits));
+        let sign = match totals.sum < 0 {
+            true => "-",
+            false => "",
+        };
+        writeln!(
+            w,
+            "{:>8}: {} ({sign}{:#x}, {wrapped:#0width$x} as {})",
+            "sum",
+            totals.sum,
+            totals.sum.unsigned_abs(),
+            totals.kind,
+            width = bits as usize / 4 + 2
+        )?;
+    }
+    for (name, extreme) in [("min", totals.min), ("max", totals.max)] {
+        if let Some((raw, at)) = extreme {
+            writeln!(
+                w,
+                "{name:>8}: {} at {}",
+                totals.kind.display(raw),
+                crate::offset(at)
+            )?;
+        }
+    }
+    if let Some(mean) = totals.mean() {
+        writeln!(w, "{:>8}: {mean}", "mean")?;
+    }
+    if totals.trailing > 0 {
+        writeln!(w, "{:>8}: {} left over", "bytes", totals.trailing)?;
+    }
+    w.flush()
+}
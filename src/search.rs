@@ -0,0 +1,94 @@
+//! exact byte pattern search, skipping ahead by the bad character rule
+//! instead of trying every offset
+
+use std::io::{self, Read};
+
+/// Bytes read at a time when counting matches
+const BLOCK: usize = 64 * 1024;
+
+/// Finds an exact byte pattern, Horspool style: a window that ends in a
+/// byte the pattern doesn't hold is skipped whole
+pub struct Finder<'a> {
+    needle: &'a [u8],
+    /// how far to move the window for its last byte
+    shift: [usize; 256],
+}
+
+/// Finder implementation
+impl<'a> Finder<'a> {
+    /// Finder constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `needle` - bytes to find, at least one.
+    pub fn new(needle: &'a [u8]) -> Finder<'a> {
+        let mut shift = [needle.len(); 256];
+        if let Some((_, head)) = needle.split_last() {
+            for (i, b) in head.iter().enumerate() {
+                shift[*b as usize] = needle.len() - 1 - i;
+            }
+        }
+        Finder { needle, shift }
+    }
+
+    /// Offset of the first match at or after `from`, None when there's no
+    /// match.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - bytes to search.
+    /// * `from` - offset to start at.
+    pub fn find(&self, haystack: &[u8], from: usize) -> Option<usize> {
+        let n = self.needle.len();
+        let (&last, head) = match self.needle.split_last() {
+            Some(split) => split,
+            None => return (from <= haystack.len()).then_some(from),
+        };
+        if n == 1 {
+            let rest = haystack.get(from..)?;
+            return rest.iter().position(|b| *b == last).map(|at| from + at);
+        }
+        let mut at = from;
+        while at + n <= haystack.len() {
+            let end = haystack[at + n - 1];
+            if end == last && &haystack[at..at + n - 1] == head {
+                return Some(at);
+            }
+            at += self.shift[end as usize];
+        }
+        None
+    }
+
+    /// Count the matches in a reader, overlapping ones included, a block at
+    /// a time so the input is never held whole. Returns the number of
+    /// matches and of bytes read.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - bytes to search.
+    pub fn count(&self, reader: &mut dyn Read) -> io::Result<(u64, u64)> {
+        // the end of the last block, where a match may start
+        let keep = self.needle.len().saturating_sub(1);
+        let mut window = Vec::with_capacity(keep + BLOCK);
+        let mut block = vec![0; BLOCK];
+        let (mut matches, mut bytes) = (0, 0);
+        loop {
+            let n = match reader.read(&mut block) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            bytes += n as u64;
+            window.extend_from_slice(&block[..n]);
+            // a match can't fit in what was kept, so none is counted twice
+            let mut from = 0;
+            while let Some(at) = self.find(&window, from) {
+                matches += 1;
+                from = at + 1;
+            }
+            window.drain(..window.len().saturating_sub(keep));
+        }
+        Ok((matches, bytes))
+    }
+}
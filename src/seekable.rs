@@ -0,0 +1,224 @@
+//! seekable zstd archives: the dumped bytes compressed in independent
+//! frames, followed by the seek table of the zstd seekable format, so a
+//! window of a large archive is read by decoding only the frames it covers
+
+use crate::formats::OutputFormat;
+use crate::Line;
+use ruzstd::decoding::FrameDecoder;
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Uncompressed bytes per frame
+pub const FRAME: usize = 1024 * 1024;
+/// Magic number of the skippable frame holding the seek table
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a5e;
+/// Magic number ending the seek table
+const SEEKABLE_MAGIC: u32 = 0x8f92_eab1;
+/// Bytes of the seek table footer: frame count, descriptor and magic
+const FOOTER: u64 = 9;
+
+/// Output format writing the input as a seekable zstd archive, `--output zst`
+#[derive(Default)]
+pub struct ArchiveFormat {
+    /// bytes of the frame being filled
+    pending: Vec<u8>,
+    /// compressed and uncompressed size of every frame written
+    frames: Vec<(u32, u32)>,
+}
+
+/// ArchiveFormat implementation
+impl ArchiveFormat {
+    /// Compress the pending bytes into a frame.
+    fn flush_frame(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let frame = compress_to_vec(self.pending.as_slice(), CompressionLevel::Fastest);
+        out.write_all(&frame)?;
+        self.frames
+            .push((frame.len() as u32, self.pending.len() as u32));
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+impl OutputFormat for ArchiveFormat {
+    fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+        let mut bytes = line.hex_body.as_slice();
+        while !bytes.is_empty() {
+            let take = (FRAME - self.pending.len()).min(bytes.len());
+            self.pending.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            if self.pending.len() == FRAME {
+                self.flush_frame(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, out: &mut dyn Write, _bytes: u64) -> io::Result<()> {
+        self.flush_frame(out)?;
+        let mut table = Vec::with_capacity(8 + self.frames.len() * 8 + FOOTER as usize);
+        table.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+        let size = self.frames.len() * 8 + FOOTER as usize;
+        table.extend_from_slice(&(size as u32).to_le_bytes());
+        for (compressed, uncompressed) in &self.frames {
+            table.extend_from_slice(&compressed.to_le_bytes());
+            table.extend_from_slice(&uncompressed.to_le_bytes());
+        }
+        table.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        // no checksums
+        table.push(0);
+        table.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        out.write_all(&table)
+    }
+}
+
+/// Error for a file that isn't a seekable zstd archive.
+fn not_archive(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("not a seekable zstd archive, {reason}"),
+    )
+}
+
+/// Little endian u32 at `at` of a buffer.
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+/// Reader of the bytes a seekable zstd archive holds, decoding a frame only
+/// when a read reaches it
+pub struct ArchiveReader<R: Read + Seek> {
+    inner: R,
+    /// archive offset and uncompressed offset of every frame, and one past
+    /// the last
+    frames: Vec<(u64, u64)>,
+    /// index and bytes of the frame decoded last
+    current: Option<(usize, Vec<u8>)>,
+    /// uncompressed offset of the next read
+    pos: u64,
+}
+
+/// ArchiveReader implementation
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// Open an archive by its seek table.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - archive written by `--output zst`, or any zstd file in
+    ///   the seekable format without checksums or with them.
+    pub fn new(mut inner: R) -> io::Result<ArchiveReader<R>> {
+        let end = inner.seek(SeekFrom::End(0))?;
+        if end < FOOTER + 8 {
+            return Err(not_archive("too short for a seek table"));
+        }
+        let mut footer = [0; FOOTER as usize];
+        inner.seek(SeekFrom::Start(end - FOOTER))?;
+        inner.read_exact(&mut footer)?;
+        if u32_at(&footer, 5) != SEEKABLE_MAGIC {
+            return Err(not_archive("no seek table at the end"));
+        }
+        let count = u64::from(u32_at(&footer, 0));
+        let entry = match footer[4] & 0x80 {
+            0 => 8,
+            _ => 12,
+        };
+        let size = count * entry + FOOTER;
+        let start = end
+            .checked_sub(size + 8)
+            .ok_or_else(|| not_archive("the seek table runs past the start"))?;
+        let mut table = vec![0; (size + 8 - FOOTER) as usize];
+        inner.seek(SeekFrom::Start(start))?;
+        inner.read_exact(&mut table)?;
+        if u32_at(&table, 0) != SKIPPABLE_MAGIC || u64::from(u32_at(&table, 4)) != size {
+            return Err(not_archive("the seek table frame is damaged"));
+        }
+        let mut frames = Vec::with_capacity(count as usize + 1);
+        let (mut at, mut pos) = (0, 0);
+        for entry in table[8..].chunks_exact(entry as usize) {
+            frames.push((at, pos));
+            at += u64::from(u32_at(entry, 0));
+            pos += u64::from(u32_at(entry, 4));
+        }
+        if at != start {
+            return Err(not_archive("the frames don't end at the seek table"));
+        }
+        frames.push((at, pos));
+        Ok(ArchiveReader {
+            inner,
+            frames,
+            current: None,
+            pos: 0,
+        })
+    }
+
+    /// Bytes the archive holds, uncompressed.
+    pub fn len(&self) -> u64 {
+        self.frames.last().map_or(0, |(_, pos)| *pos)
+    }
+
+    /// Uncompressed offset and archive offset of every frame, in order.
+    pub fn frames(&self) -> Vec<(u64, u64)> {
+        let frames = &self.frames[..self.frames.len() - 1];
+        frames.iter().map(|(at, pos)| (*pos, *at)).collect()
+    }
+
+    /// Whether the archive holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decode a frame, unless it's the one decoded last.
+    fn frame(&mut self, index: usize) -> io::Result<&[u8]> {
+        if self.current.as_ref().is_none_or(|(at, _)| *at != index) {
+            let (start, pos) = self.frames[index];
+            let (end, next) = self.frames[index + 1];
+            let mut compressed = vec![0; (end - start) as usize];
+            self.inner.seek(SeekFrom::Start(start))?;
+            self.inner.read_exact(&mut compressed)?;
+            let mut data = Vec::with_capacity((next - pos) as usize);
+            FrameDecoder::new()
+                .decode_all_to_vec(&compressed, &mut data)
+                .map_err(|e| not_archive(&format!("frame {index} doesn't decode: {e}")))?;
+            self.current = Some((index, data));
+        }
+        Ok(self
+            .current
+            .as_ref()
+            .map_or(&[], |(_, data)| data.as_slice()))
+    }
+}
+
+impl<R: Read + Seek> Read for ArchiveReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len() || out.is_empty() {
+            return Ok(0);
+        }
+        // the last frame starting at or before the position
+        let index = self.frames.partition_point(|(_, pos)| *pos <= self.pos) - 1;
+        let at = (self.pos - self.frames[index].1) as usize;
+        let data = self.frame(index)?;
+        let n = data.len().saturating_sub(at).min(out.len());
+        out[..n].copy_from_slice(&data[at..at + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for ArchiveReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(to) => (to, 0),
+            SeekFrom::Current(delta) => (self.pos, delta),
+            SeekFrom::End(delta) => (self.len(), delta),
+        };
+        self.pos = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to before the start of the archive",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,231 @@
+//! built-in golden output checks, run with `hx selftest`
+
+use crate::bank::Banks;
+use crate::charset::Charset;
+use crate::dwarf::LineTable;
+use crate::format::{Format, Group};
+use crate::panel::{Border, DEFAULT_PANELS};
+use crate::{output_dump, print_summary, write_array, DumpOptions, FlushPolicy, OutputWriter};
+use std::io::{self, Write};
+
+/// bytes every check renders: printable, nul, del, high bit and newline
+pub const CORPUS: &[u8] = b"hx\x00\x7f\x80\xff\n";
+
+/// dump checks: name, format, prefix, colorize, columns, expected output
+const DUMP_GOLDEN: [(&str, Format, bool, bool, u64, &str); 10] = [
+    (
+        "dump/octal/prefix",
+        Format::Octal,
+        true,
+        false,
+        4,
+        "0x000000: 0o0150 0o0170 0o0000 0o0177 hx..\n\
+         0x000004: 0o0200 0o0377 0o0012        ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/octal",
+        Format::Octal,
+        false,
+        false,
+        4,
+        "0x000000: 0150 0170 0000 0177 hx..\n\
+         0x000004: 0200 0377 0012      ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/lowerhex/prefix",
+        Format::LowerHex,
+        true,
+        false,
+        4,
+        "0x000000: 0x68 0x78 0x00 0x7f hx..\n\
+         0x000004: 0x80 0xff 0x0a      ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/lowerhex",
+        Format::LowerHex,
+        false,
+        false,
+        4,
+        "0x000000: 68 78 00 7f hx..\n\
+         0x000004: 80 ff 0a    ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/upperhex/prefix",
+        Format::UpperHex,
+        true,
+        false,
+        4,
+        "0x000000: 0x68 0x78 0x00 0x7F hx..\n\
+         0x000004: 0x80 0xFF 0x0A      ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/upperhex",
+        Format::UpperHex,
+        false,
+        false,
+        4,
+        "0x000000: 68 78 00 7F hx..\n\
+         0x000004: 80 FF 0A    ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/binary/prefix",
+        Format::Binary,
+        true,
+        false,
+        4,
+        "0x000000: 0b01101000 0b01111000 0b00000000 0b01111111 hx..\n\
+         0x000004: 0b10000000 0b11111111 0b00001010            ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/binary",
+        Format::Binary,
+        false,
+        false,
+        4,
+        "0x000000: 01101000 01111000 00000000 01111111 hx..\n\
+         0x000004: 10000000 11111111 00001010          ...\n   bytes: 7\n",
+    ),
+    (
+        "dump/lowerhex/cols10",
+        Format::LowerHex,
+        true,
+        false,
+        10,
+        "0x000000: 0x68 0x78 0x00 0x7f 0x80 0xff 0x0a                hx.....\n   bytes: 7\n",
+    ),
+    (
+        "dump/lowerhex/color",
+        Format::LowerHex,
+        true,
+        true,
+        8,
+        "0x000000: \x1b[38;5;104m0x68\x1b[0m \x1b[38;5;120m0x78\x1b[0m \
+         \x1b[38;5;22m0x00\x1b[0m \x1b[38;5;127m0x7f\x1b[0m \
+         \x1b[38;5;128m0x80\x1b[0m \x1b[38;5;255m0xff\x1b[0m \
+         \x1b[38;5;10m0x0a\x1b[0m      \
+         \x1b[38;5;104mh\x1b[0m\x1b[38;5;120mx\x1b[0m\x1b[38;5;22m.\x1b[0m\
+         \x1b[38;5;127m.\x1b[0m\x1b[38;5;128m.\x1b[0m\x1b[38;5;255m.\x1b[0m\
+         \x1b[38;5;10m.\x1b[0m\n   bytes: 7\n",
+    ),
+];
+
+/// array checks: name, array format, expected output
+const ARRAY_GOLDEN: [(&str, &str, &str); 8] = [
+    (
+        "array/rust",
+        "r",
+        "let ARRAY: [u8; 7] = [\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n];\n",
+    ),
+    (
+        "array/c",
+        "c",
+        "unsigned char ARRAY[7] = {\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n};\n",
+    ),
+    (
+        "array/golang",
+        "g",
+        "a := [7]byte{\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a, \n}\n",
+    ),
+    (
+        "array/python",
+        "p",
+        "a = [\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n]\n",
+    ),
+    (
+        "array/kotlin",
+        "k",
+        "val a = byteArrayOf(\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n)\n",
+    ),
+    (
+        "array/java",
+        "j",
+        "byte[] a = new byte[]{\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n};\n",
+    ),
+    (
+        "array/swift",
+        "s",
+        "let a: [UInt8] = [\n    0x68, 0x78, 0x00, 0x7f, \n    0x80, 0xff, 0x0a\n]\n",
+    ),
+    (
+        "array/fsharp",
+        "f",
+        "let a = [|\n    0x68uy; 0x78uy; 0x00uy; 0x7fuy; \n    0x80uy; 0xffuy; 0x0auy\n|]\n",
+    ),
+];
+
+/// Render the corpus as a dump into a string.
+fn render_dump(format: Format, prefix: bool, colorize: bool, columns: u64) -> io::Result<String> {
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Block);
+    let opts = DumpOptions {
+        format,
+        colorize,
+        prefix,
+        truncate_len: 0,
+        column_width: columns,
+        start_offset: 0,
+        align: false,
+        stripe: 0,
+        highlight: None,
+        charset: Charset::Ascii,
+        only: None,
+        boundary: 0,
+        bookmarks: &[],
+        found: &[],
+        symbols: &[],
+        lines: &LineTable::default(),
+        banks: Banks::default(),
+        source: None,
+        separator: " ",
+        group: Group::default(),
+        timestamps: None,
+        panels: DEFAULT_PANELS,
+        border: Border::None,
+    };
+    let bytes = output_dump(&mut out, &mut &CORPUS[..], &opts)?;
+    print_summary(&mut out, bytes, true)?;
+    let bytes = out.into_inner()?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Render the corpus as a source code array into a string.
+fn render_array(array_format: &str) -> io::Result<String> {
+    let mut out = Vec::new();
+    write_array(&mut out, array_format, &mut &CORPUS[..], 0, 4)?;
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Run every golden check, reporting each result to `w`.
+///
+/// Returns the number of mismatches.
+pub fn selftest(w: &mut impl Write) -> io::Result<usize> {
+    let mut results: Vec<(&str, String, &str)> = Vec::new();
+    for (name, format, prefix, colorize, columns, expected) in DUMP_GOLDEN {
+        results.push((
+            name,
+            render_dump(format, prefix, colorize, columns)?,
+            expected,
+        ));
+    }
+    for (name, array_format, expected) in ARRAY_GOLDEN {
+        results.push((name, render_array(array_format)?, expected));
+    }
+
+    let mut mismatches = 0;
+    for (name, actual, expected) in results.iter() {
+        if actual == expected {
+            writeln!(w, "ok       {name}")?;
+        } else {
+            mismatches += 1;
+            writeln!(w, "MISMATCH {name}")?;
+            writeln!(w, "expected:\n{}", expected.escape_debug())?;
+            writeln!(w, "actual:\n{}", actual.escape_debug())?;
+        }
+    }
+    writeln!(
+        w,
+        "selftest: {} checks, {} mismatches",
+        results.len(),
+        mismatches
+    )?;
+    Ok(mismatches)
+}
@@ -0,0 +1,99 @@
+//! `--session` files: the input and dump options of a long-running look at
+//! one binary, restored on the next run and saved back after it
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// first line of a session file
+const HEADER: &str = "# hx session, one command line argument per line";
+
+/// A session file and the arguments to save into it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// session file
+    pub path: PathBuf,
+    /// command line arguments of the session, options first, input last
+    pub args: Vec<String>,
+}
+
+/// Arguments given on the command line, grouped by argument id, as the
+/// command line tokens that give them again. Options come first, in the
+/// order they're defined, and the input last.
+///
+/// # Arguments
+///
+/// * `command` - command line definition.
+/// * `matches` - parsed command line.
+/// * `skip` - ids of arguments to leave out.
+pub fn given(command: &Command, matches: &ArgMatches, skip: &[&str]) -> Vec<(String, Vec<String>)> {
+    let mut options = Vec::new();
+    let mut positionals = Vec::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if skip.contains(&id) || matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let values: Vec<String> = matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect();
+        let Some(long) = arg.get_long() else {
+            positionals.push((id.to_string(), values));
+            continue;
+        };
+        let takes_values = arg.get_action().takes_values();
+        let many = arg
+            .get_num_args()
+            .is_some_and(|range| range.max_values() > 1);
+        let tokens = match (takes_values, many) {
+            (false, _) => vec![format!("--{long}")],
+            (true, false) => values
+                .iter()
+                .map(|value| format!("--{long}={value}"))
+                .collect(),
+            (true, true) => std::iter::once(format!("--{long}")).chain(values).collect(),
+        };
+        options.push((id.to_string(), tokens));
+    }
+    options.extend(positionals);
+    options
+}
+
+/// Arguments saved in a session file, none when it doesn't exist yet.
+///
+/// # Arguments
+///
+/// * `path` - session file.
+pub fn load(path: &PathBuf) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write a session's arguments to its file, creating its directory.
+///
+/// # Arguments
+///
+/// * `session` - session to save.
+pub fn save(session: &Session) -> io::Result<()> {
+    if let Some(dir) = session.path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = format!("{HEADER}\n");
+    for arg in &session.args {
+        text.push_str(arg);
+        text.push('\n');
+    }
+    crate::temp::write_atomically(&session.path, text.as_bytes())
+}
@@ -0,0 +1,81 @@
+//! `--source-offsets`: where the bytes of a transformed dump come from in
+//! the input file, shown next to the offset of every line
+//!
+//! Transforms keyed by offset, such as `--xor`, keep every byte where it
+//! was. `--deinterleave` keeps every `lanes`th byte, and an archive member
+//! or a zstd frame is a stream read out of the file: a stored one at an
+//! offset of its own, a compressed one at the start of its compressed data.
+
+/// Map from offsets of the dumped bytes to offsets of the input file, the
+/// identity by default
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceMap {
+    /// lanes of `--deinterleave` and the one kept, 1 and 0 without it
+    pub lanes: (u64, u64),
+    /// stream offset and file offset of every block of the stream the
+    /// lanes are kept from, in order, the first at stream offset 0
+    pub blocks: Vec<(u64, u64)>,
+    /// the blocks are compressed: a byte maps to the start of its block,
+    /// not to an offset of its own
+    pub compressed: bool,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap {
+            lanes: (1, 0),
+            blocks: vec![(0, 0)],
+            compressed: false,
+        }
+    }
+}
+
+/// SourceMap implementation
+impl SourceMap {
+    /// Map of a dump keeping one lane of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `lanes` - lanes of `--deinterleave` and the one kept, None without it.
+    pub fn new(lanes: Option<(usize, usize)>) -> SourceMap {
+        SourceMap {
+            lanes: lanes.map_or((1, 0), |(lanes, lane)| (lanes as u64, lane as u64)),
+            ..SourceMap::default()
+        }
+    }
+
+    /// The same map for a stream read out of the input file.
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - stream offset and file offset of every block, in order.
+    /// * `compressed` - whether the blocks are compressed.
+    pub fn within(&self, blocks: Vec<(u64, u64)>, compressed: bool) -> SourceMap {
+        SourceMap {
+            lanes: self.lanes,
+            blocks,
+            compressed,
+        }
+    }
+
+    /// Input file offset of a dumped byte, and whether it's the start of
+    /// the compressed block holding it rather than its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the byte in the dump.
+    pub fn translate(&self, offset: u64) -> (u64, bool) {
+        let (lanes, lane) = self.lanes;
+        let at = offset * lanes + lane;
+        // the last block starting at or before the byte
+        let index = self
+            .blocks
+            .partition_point(|(start, _)| *start <= at)
+            .saturating_sub(1);
+        let (start, file) = self.blocks.get(index).copied().unwrap_or_default();
+        match self.compressed {
+            true => (file, true),
+            false => (file + (at - start), false),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! layout specs for `hx check`: the magic bytes, field values and checksums
+//! a binary format expects, written in a small subset of TOML
+//!
+//! ```toml
+//! [[field]]
+//! name = "magic"
+//! at = 0
+//! bytes = "7f 'ELF'"
+//!
+//! [[field]]
+//! name = "version"
+//! at = 0x14
+//! type = "u32"
+//! min = 1
+//! max = 2
+//!
+//! [[checksum]]
+//! name = "header crc"
+//! algorithm = "crc32"
+//! range = "0:0x40"
+//! at = 0x40
+//! ```
+
+use crate::expr;
+use crate::hash::{self, Sha256};
+use crate::literal;
+use crate::scalar::{self, Endian, ScalarType};
+use std::cmp::Ordering;
+
+/// What a check expects at its offset
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    /// exactly these bytes
+    Bytes(Vec<u8>),
+    /// a scalar within bounds, raw bits zero extended
+    Value {
+        /// scalar type
+        kind: ScalarType,
+        /// byte order
+        endian: Endian,
+        /// smallest allowed value
+        min: Option<u64>,
+        /// largest allowed value
+        max: Option<u64>,
+    },
+    /// the checksum of a range of the file
+    Checksum {
+        /// how the checksum is computed
+        algorithm: Algorithm,
+        /// start and end of the bytes summed
+        range: (u64, u64),
+        /// byte order of a stored sum or crc
+        endian: Endian,
+    },
+}
+
+/// Checksum algorithms a spec can name
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// byte sum modulo 256
+    Sum8,
+    /// byte sum modulo 65536
+    Sum16,
+    /// CRC-32 as zip and PNG use it
+    Crc32,
+    /// sha-256 digest
+    Sha256,
+}
+
+/// Algorithm implementation
+impl Algorithm {
+    /// Algorithm for a spec name such as `crc32`.
+    fn parse(name: &str) -> Option<Algorithm> {
+        Some(match name {
+            "sum8" => Algorithm::Sum8,
+            "sum16" => Algorithm::Sum16,
+            "crc32" => Algorithm::Crc32,
+            "sha256" => Algorithm::Sha256,
+            _ => return None,
+        })
+    }
+
+    /// Bytes of the stored checksum.
+    fn width(&self) -> usize {
+        match self {
+            Algorithm::Sum8 => 1,
+            Algorithm::Sum16 => 2,
+            Algorithm::Crc32 => 4,
+            Algorithm::Sha256 => 32,
+        }
+    }
+
+    /// Checksum of the bytes, as it's stored.
+    fn compute(&self, data: &[u8], endian: Endian) -> Vec<u8> {
+        let sum = || data.iter().fold(0u64, |sum, b| sum + u64::from(*b));
+        match self {
+            Algorithm::Sum8 => vec![sum() as u8],
+            Algorithm::Sum16 => scalar::encode(sum() & 0xffff, 2, endian),
+            Algorithm::Crc32 => scalar::encode(u64::from(hash::crc32(data)), 4, endian),
+            Algorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(data);
+                hasher.finish().to_vec()
+            }
+        }
+    }
+}
+
+/// A named expectation about the bytes at an offset
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    /// name in the report
+    pub name: String,
+    /// offset of the checked bytes
+    pub at: u64,
+    /// what's expected there
+    pub rule: Rule,
+}
+
+/// Result of a check against a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// whether the file meets it
+    pub pass: bool,
+    /// what the file holds
+    pub found: String,
+    /// what was expected
+    pub expected: String,
+    /// bytes looked at, from the check's offset
+    pub len: usize,
+}
+
+/// Line number, kind and keys of a `[[field]]` or `[[checksum]]` table
+type Table<'a> = (usize, &'a str, Vec<(&'a str, String)>);
+
+/// Parse a spec: `[[field]]` and `[[checksum]]` tables of `key = value`
+/// lines, values quoted strings or bare numbers, `#` comments.
+///
+/// A field has a `name`, an offset `at` and either `bytes` as for `hx
+/// echo`, or a `type` (u8 when left out) and `endian` (little) with
+/// `equals`, or `min` and `max`. A checksum has a `name`, an `algorithm`
+/// (sum8, sum16, crc32 or sha256), the `range` it covers as `start:end` or
+/// `start+len`, the offset `at` it's stored at and an `endian`. Offsets are
+/// expressions, with `end` the length of the file.
+///
+/// # Arguments
+///
+/// * `text` - spec file contents.
+/// * `end` - length of the checked file.
+pub fn parse(text: &str, end: u64) -> Result<Vec<Check>, String> {
+    let mut tables: Vec<Table> = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |reason: String| format!("line {number}: {reason}");
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+            match name.trim() {
+                kind @ ("field" | "checksum") => tables.push((number, kind, Vec::new())),
+                other => return Err(error(format!("unknown table [[{other}]]"))),
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error(format!("{line:?} is not key = value")));
+        };
+        let Some((_, _, keys)) = tables.last_mut() else {
+            return Err(error(
+                "keys must follow [[field]] or [[checksum]]".to_string(),
+            ));
+        };
+        keys.push((key.trim(), unquote(value.trim()).map_err(error)?));
+    }
+    tables
+        .into_iter()
+        .map(|(number, kind, keys)| {
+            check(kind, &keys, end).map_err(|reason| format!("line {number}: {reason}"))
+        })
+        .collect()
+}
+
+/// Line without a `#` comment outside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Value of a quoted string with `\"` and `\\` escapes, or a bare value.
+fn unquote(value: &str) -> Result<String, String> {
+    let Some(inner) = value.strip_prefix('"') else {
+        return Ok(value.to_string());
+    };
+    let Some(inner) = inner.strip_suffix('"') else {
+        return Err(format!("{value} has no closing quote"));
+    };
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        text.push(match c {
+            '\\' => chars.next().ok_or("a string ends with \\")?,
+            c => c,
+        });
+    }
+    Ok(text)
+}
+
+/// A check from the keys of its table.
+fn check(kind: &str, keys: &[(&str, String)], end: u64) -> Result<Check, String> {
+    let allowed: &[&str] = match kind {
+        "field" => &[
+            "name", "at", "bytes", "type", "endian", "equals", "min", "max",
+        ],
+        _ => &["name", "at", "algorithm", "range", "endian"],
+    };
+    if let Some((key, _)) = keys.iter().find(|(key, _)| !allowed.contains(key)) {
+        return Err(format!("unknown key {key:?} in [[{kind}]]"));
+    }
+    let get = |key: &str| keys.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v);
+    let require = |key: &str| get(key).ok_or(format!("[[{kind}]] needs {key}"));
+    let offset = |text: &str| expr::eval(text, Some(end));
+    let name = require("name")?.clone();
+    let at = offset(require("at")?)?;
+    let endian = match get("endian").map(String::as_str) {
+        None | Some("little") => Endian::Little,
+        Some("big") => Endian::Big,
+        Some(other) => return Err(format!("{name}: endian {other:?} is not little or big")),
+    };
+    let rule = match (kind, get("bytes")) {
+        ("field", Some(bytes)) => Rule::Bytes(literal::parse(bytes)?),
+        ("field", None) => {
+            let kind = match get("type") {
+                Some(name) => ScalarType::parse(name).ok_or(format!("unknown type {name:?}"))?,
+                None => ScalarType::U8,
+            };
+            let value = |key: &str| match get(key) {
+                Some(text) => scalar::parse_scalar(text, kind).map(Some),
+                None => Ok(None),
+            };
+            let (min, max) = match value("equals")? {
+                Some(value) => (Some(value), Some(value)),
+                None => (value("min")?, value("max")?),
+            };
+            if min.is_none() && max.is_none() {
+                return Err(format!("{name}: give bytes, equals, min or max"));
+            }
+            Rule::Value {
+                kind,
+                endian,
+                min,
+                max,
+            }
+        }
+        _ => {
+            let name = require("algorithm")?;
+            let algorithm = Algorithm::parse(name).ok_or(format!(
+                "unknown algorithm {name:?}, not sum8, sum16, crc32 or sha256"
+            ))?;
+            let range = require("range")?;
+            let range = match (range.split_once(':'), range.rsplit_once('+')) {
+                (Some((start, stop)), _) => (offset(start)?, offset(stop)?),
+                (None, Some((start, len))) => {
+                    let start = offset(start)?;
+                    (start, start.saturating_add(offset(len)?))
+                }
+                (None, None) => {
+                    return Err(format!("range {range:?} is not start:end or start+len"))
+                }
+            };
+            Rule::Checksum {
+                algorithm,
+                range,
+                endian,
+            }
+        }
+    };
+    Ok(Check { name, at, rule })
+}
+
+/// Space separated hex bytes.
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Run a check against the contents of a file.
+///
+/// # Arguments
+///
+/// * `check` - what to check.
+/// * `data` - file contents.
+pub fn evaluate(check: &Check, data: &[u8]) -> Outcome {
+    let at = check.at as usize;
+    let len = match &check.rule {
+        Rule::Bytes(bytes) => bytes.len(),
+        Rule::Value { kind, .. } => kind.width(),
+        Rule::Checksum { algorithm, .. } => algorithm.width(),
+    };
+    let expected = match &check.rule {
+        Rule::Bytes(bytes) => hex(bytes),
+        Rule::Value { kind, min, max, .. } => match (min, max) {
+            (Some(min), Some(max)) if min == max => kind.display(*min),
+            (Some(min), Some(max)) => format!("{}..={}", kind.display(*min), kind.display(*max)),
+            (Some(min), None) => format!(">= {}", kind.display(*min)),
+            (None, Some(max)) => format!("<= {}", kind.display(*max)),
+            (None, None) => String::new(),
+        },
+        Rule::Checksum {
+            algorithm,
+            range: (start, stop),
+            endian,
+        } => match data.get(*start as usize..*stop as usize) {
+            Some(covered) if start < stop => hex(&algorithm.compute(covered, *endian)),
+            _ => {
+                return Outcome {
+                    pass: false,
+                    found: format!(
+                        "range {}:{} is outside the file",
+                        crate::offset(*start),
+                        crate::offset(*stop)
+                    ),
+                    expected: "a range within the file".to_string(),
+                    len: 0,
+                }
+            }
+        },
+    };
+    let Some(bytes) = data.get(at..at.saturating_add(len)) else {
+        return Outcome {
+            pass: false,
+            found: "past the end of the file".to_string(),
+            expected,
+            len: data.len().saturating_sub(at),
+        };
+    };
+    let (pass, found) = match &check.rule {
+        Rule::Value {
+            kind,
+            endian,
+            min,
+            max,
+        } => {
+            let raw = scalar::decode(bytes, *endian);
+            let order = |bound: &u64| kind.order(raw, *bound);
+            let pass = min
+                .as_ref()
+                .is_none_or(|min| order(min).is_some_and(Ordering::is_ge))
+                && max
+                    .as_ref()
+                    .is_none_or(|max| order(max).is_some_and(Ordering::is_le));
+            (pass, kind.display(raw))
+        }
+        _ => (hex(bytes) == expected, hex(bytes)),
+    };
+    Outcome {
+        pass,
+        found,
+        expected,
+        len,
+    }
+}
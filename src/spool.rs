@@ -0,0 +1,35 @@
+//! `--two-pass`: a streamed input copied to a temporary file before the
+//! dump, so its length is known up front and it can be seeked
+
+use crate::input::InputSource;
+use crate::temp::TempFile;
+use std::io;
+use std::path::Path;
+
+/// Temporary copy of a streamed input, removed when dropped
+#[derive(Debug, PartialEq, Eq)]
+pub struct Spool {
+    /// the temporary file
+    pub temp: TempFile,
+    /// the input it copies
+    pub of: InputSource,
+}
+
+/// Spool implementation
+impl Spool {
+    /// Copy an input to the end into a new temporary file, readable by the
+    /// user alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - streamed input, e.g. stdin.
+    /// * `dir` - directory of temporary files.
+    pub fn create(source: InputSource, dir: &Path) -> io::Result<Spool> {
+        let (temp, mut file) = TempFile::create(dir, "hx-spool")
+            .map_err(|e| io::Error::new(e.kind(), format!("--two-pass {e}")))?;
+        // made first, so a failed copy is removed too
+        let spool = Spool { temp, of: source };
+        io::copy(&mut spool.of.open()?, &mut file)?;
+        Ok(spool)
+    }
+}
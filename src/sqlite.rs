@@ -0,0 +1,177 @@
+//! sqlite database page lookup and b-tree page header annotation
+//!
+//! Pages are found by reading forward, never seeking, so databases piped
+//! through stdin work as well as files.
+
+use std::io::{self, Read, Write};
+
+/// every sqlite database starts with this magic string
+const MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// size of the database header at the start of page 1
+pub const HEADER_LEN: usize = 100;
+
+/// B-tree page header fields
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PageHeader {
+    /// page type byte: 2, 5, 10 or 13
+    pub kind: u8,
+    /// offset of the first freeblock, 0 when there are none
+    pub first_freeblock: u16,
+    /// number of cells on the page
+    pub cells: u16,
+    /// start of the cell content area
+    pub content_start: u32,
+    /// fragmented free bytes in the cell content area
+    pub fragmented: u8,
+    /// right-most child page, interior pages only
+    pub right_most: Option<u32>,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn u16_be(data: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([data[at], data[at + 1]])
+}
+
+fn u32_be(data: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Page size and page count from the database header. The page count is 0
+/// when the header predates sqlite 3.7.0 and doesn't record it.
+///
+/// # Arguments
+///
+/// * `header` - the first 100 bytes of the database.
+pub fn database_geometry(header: &[u8]) -> io::Result<(u32, u32)> {
+    if header.len() < HEADER_LEN || !header.starts_with(MAGIC) {
+        return Err(invalid("input is not a sqlite 3 database"));
+    }
+    let page_size = match u16_be(header, 16) {
+        1 => 65536,
+        size if size >= 512 && size.is_power_of_two() => u32::from(size),
+        size => return Err(invalid(format!("invalid sqlite page size {size}"))),
+    };
+    Ok((page_size, u32_be(header, 28)))
+}
+
+/// Name of a b-tree page type.
+pub fn page_kind_name(kind: u8) -> Option<&'static str> {
+    match kind {
+        2 => Some("interior index b-tree"),
+        5 => Some("interior table b-tree"),
+        10 => Some("leaf index b-tree"),
+        13 => Some("leaf table b-tree"),
+        _ => None,
+    }
+}
+
+/// Parse the b-tree page header, None for overflow, freelist and pointer
+/// map pages which don't carry one.
+///
+/// # Arguments
+///
+/// * `page` - whole page contents.
+/// * `page_no` - 1 based page number, page 1 has the header after the database header.
+pub fn page_header(page: &[u8], page_no: u32) -> Option<PageHeader> {
+    let at = if page_no == 1 { HEADER_LEN } else { 0 };
+    let kind = *page.get(at)?;
+    page_kind_name(kind)?;
+    let interior = kind == 2 || kind == 5;
+    if page.len() < at + if interior { 12 } else { 8 } {
+        return None;
+    }
+    Some(PageHeader {
+        kind,
+        first_freeblock: u16_be(page, at + 1),
+        cells: u16_be(page, at + 3),
+        content_start: match u16_be(page, at + 5) {
+            0 => 65536,
+            start => u32::from(start),
+        },
+        fragmented: page[at + 7],
+        right_most: interior.then(|| u32_be(page, at + 8)),
+    })
+}
+
+/// Read page `page_no` from a database stream, returning the page size and
+/// page contents.
+///
+/// # Arguments
+///
+/// * `buf` - database, read from the start.
+/// * `page_no` - 1 based page number.
+pub fn read_page(buf: &mut dyn Read, page_no: u32) -> io::Result<(u32, Vec<u8>)> {
+    if page_no == 0 {
+        return Err(invalid("sqlite pages are numbered from 1"));
+    }
+    let mut header = vec![0; HEADER_LEN];
+    buf.read_exact(&mut header)
+        .map_err(|_| invalid("input is not a sqlite 3 database"))?;
+    let (page_size, page_count) = database_geometry(&header)?;
+    if page_count > 0 && page_no > page_count {
+        return Err(invalid(format!(
+            "page {page_no} is beyond the last page {page_count}"
+        )));
+    }
+
+    let page_start = u64::from(page_no - 1) * u64::from(page_size);
+    let mut page = match page_no {
+        1 => header,
+        _ => {
+            let skip = page_start - HEADER_LEN as u64;
+            if crate::discard(buf, skip)? < skip {
+                return Err(invalid(format!(
+                    "page {page_no} is beyond the end of the database"
+                )));
+            }
+            Vec::with_capacity(page_size as usize)
+        }
+    };
+    let want = page_size as u64 - page.len() as u64;
+    buf.take(want).read_to_end(&mut page)?;
+    if page.len() < page_size as usize {
+        return Err(invalid(format!("page {page_no} is truncated")));
+    }
+    Ok((page_size, page))
+}
+
+/// Write the page position and its b-tree header fields.
+///
+/// # Arguments
+///
+/// * `w` - output writer.
+/// * `page_no` - 1 based page number.
+/// * `page_size` - database page size.
+/// * `page` - whole page contents.
+pub fn annotate(w: &mut impl Write, page_no: u32, page_size: u32, page: &[u8]) -> io::Result<()> {
+    let start = u64::from(page_no - 1) * u64::from(page_size);
+    writeln!(
+        w,
+        "{:>15}: {page_no} at {} ({page_size} bytes)",
+        "page",
+        crate::offset(start)
+    )?;
+    match page_header(page, page_no) {
+        Some(header) => {
+            let kind = page_kind_name(header.kind).unwrap_or_default();
+            writeln!(w, "{:>15}: {} {kind}", "type", header.kind)?;
+            writeln!(w, "{:>15}: {}", "first freeblock", header.first_freeblock)?;
+            writeln!(w, "{:>15}: {}", "cells", header.cells)?;
+            writeln!(w, "{:>15}: {}", "content start", header.content_start)?;
+            writeln!(w, "{:>15}: {}", "fragmented", header.fragmented)?;
+            if let Some(right_most) = header.right_most {
+                writeln!(w, "{:>15}: {right_most}", "right-most page")?;
+            }
+        }
+        None => writeln!(
+            w,
+            "{:>15}: not a b-tree page (overflow, freelist or pointer map)",
+            "type"
+        )?,
+    }
+    Ok(())
+}
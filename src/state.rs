@@ -0,0 +1,62 @@
+//! where `--continue` left off in each input, kept in the state directory
+
+use crate::hash::{to_hex, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// bytes from the start of an input that go into its fingerprint
+const FINGERPRINT_LEN: u64 = 64 * 1024;
+
+/// Hex sha-256 of an input's length and first 64 KiB, which identifies it
+/// across renames without reading a huge file end to end.
+///
+/// # Arguments
+///
+/// * `path` - input file.
+pub fn fingerprint(path: &Path) -> io::Result<String> {
+    let file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&file.metadata()?.len().to_le_bytes());
+    let mut head = Vec::new();
+    file.take(FINGERPRINT_LEN).read_to_end(&mut head)?;
+    hasher.update(&head);
+    Ok(to_hex(&hasher.finish()))
+}
+
+/// Offset recorded in a state file, None when there isn't one yet.
+///
+/// # Arguments
+///
+/// * `state` - state file of the input.
+pub fn load(state: &Path) -> io::Result<Option<u64>> {
+    let text = match fs::read_to_string(state) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let text = text.trim();
+    text.strip_prefix("0x")
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has a bad offset: {text}", state.display()),
+            )
+        })
+}
+
+/// Record an offset in a state file, creating the state directory.
+///
+/// # Arguments
+///
+/// * `state` - state file of the input.
+/// * `at` - offset the next `--continue` starts from.
+pub fn save(state: &Path, at: u64) -> io::Result<()> {
+    if let Some(dir) = state.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // a run stopped halfway leaves the last position, not half of one
+    crate::temp::write_atomically(state, format!("{}\n", crate::offset(at)).as_bytes())
+}
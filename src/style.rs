@@ -0,0 +1,238 @@
+//! styled spans of a dump line, and the backends writing them as ANSI
+//! color codes, HTML or plain text
+
+use ansi_term::{Color, Style};
+use std::io::{self, Write};
+
+/// Part of a dump line a span belongs to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// the line offset
+    Offset,
+    /// a byte, word or decimal cell
+    Cell,
+    /// a glyph of the character panel
+    Char,
+    /// margin notes
+    Note,
+    /// a border bar
+    Border,
+    /// separators and padding
+    Space,
+}
+
+/// Role implementation
+impl Role {
+    /// Class of the role in HTML output, e.g. `hx-cell`.
+    pub fn class(&self) -> &'static str {
+        match self {
+            Role::Offset => "hx-offset",
+            Role::Cell => "hx-cell",
+            Role::Char => "hx-char",
+            Role::Note => "hx-note",
+            Role::Border => "hx-border",
+            Role::Space => "hx-space",
+        }
+    }
+}
+
+/// A run of text of a dump line in one style
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    /// the text, without color codes
+    pub text: String,
+    /// its colors and attributes, the default style for plain text
+    pub style: Style,
+    /// the part of the line it belongs to
+    pub role: Role,
+}
+
+/// Span implementation
+impl Span {
+    /// Span constructor
+    pub fn new(text: impl Into<String>, style: Style, role: Role) -> Span {
+        Span {
+            text: text.into(),
+            style,
+            role,
+        }
+    }
+
+    /// Unstyled separator or padding.
+    pub fn space(text: impl Into<String>) -> Span {
+        Span::new(text, Style::new(), Role::Space)
+    }
+}
+
+/// Where the spans of a dump line go as they are made
+pub trait SpanSink {
+    /// Add a run of text in a style.
+    fn push(&mut self, text: &str, style: Style, role: Role);
+
+    /// Add blanks, if any.
+    fn pad(&mut self, width: usize);
+}
+
+/// Every span kept for a backend to write
+impl SpanSink for Vec<Span> {
+    fn push(&mut self, text: &str, style: Style, role: Role) {
+        Vec::push(self, Span::new(text, style, role));
+    }
+
+    fn pad(&mut self, width: usize) {
+        if width > 0 {
+            Vec::push(self, Span::space(" ".repeat(width)));
+        }
+    }
+}
+
+/// The text of the spans alone, for plain output that has no use for
+/// their styles
+impl SpanSink for String {
+    fn push(&mut self, text: &str, _style: Style, _role: Role) {
+        self.push_str(text);
+    }
+
+    fn pad(&mut self, width: usize) {
+        self.extend(std::iter::repeat_n(' ', width));
+    }
+}
+
+/// How spans are written out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// the text alone
+    Plain,
+    /// the text with ANSI color codes for terminals
+    Ansi,
+    /// `<span>` elements with a class for the role and inline colors
+    Html,
+}
+
+/// Backend implementation
+impl Backend {
+    /// Backend of terminal output, colored or not.
+    pub fn terminal(colorize: bool) -> Backend {
+        match colorize {
+            true => Backend::Ansi,
+            false => Backend::Plain,
+        }
+    }
+
+    /// Write spans in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - output writer.
+    /// * `spans` - spans to write.
+    pub fn write(&self, out: &mut dyn Write, spans: &[Span]) -> io::Result<()> {
+        for span in spans {
+            match self {
+                Backend::Plain => out.write_all(span.text.as_bytes())?,
+                Backend::Ansi => write!(out, "{}", span.style.paint(span.text.as_str()))?,
+                Backend::Html => write_html(out, span)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text with the characters HTML gives a meaning escaped.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The 16 basic terminal colors as xterm shows them, the 8 normal ones
+/// then the 8 bright ones
+pub const BASIC: [u32; 16] = [
+    0x000000, 0xcd0000, 0x00cd00, 0xcdcd00, 0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5, 0x7f7f7f,
+    0xff0000, 0x00ff00, 0xffff00, 0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+];
+
+/// 24 bit RGB of a terminal color, the xterm palette for the 256 fixed
+/// ones.
+pub fn rgb(color: Color) -> u32 {
+    const LEVELS: [u32; 6] = [0, 95, 135, 175, 215, 255];
+    match color {
+        Color::Black => BASIC[0],
+        Color::Red => BASIC[1],
+        Color::Green => BASIC[2],
+        Color::Yellow => BASIC[3],
+        Color::Blue => BASIC[4],
+        Color::Purple => BASIC[5],
+        Color::Cyan => BASIC[6],
+        Color::White => BASIC[7],
+        Color::Fixed(n @ 0..=15) => BASIC[n as usize],
+        Color::Fixed(n @ 16..=231) => {
+            let n = u32::from(n) - 16;
+            LEVELS[(n / 36) as usize] << 16
+                | LEVELS[(n / 6 % 6) as usize] << 8
+                | LEVELS[(n % 6) as usize]
+        }
+        Color::Fixed(n) => {
+            let gray = 8 + 10 * (u32::from(n) - 232);
+            gray << 16 | gray << 8 | gray
+        }
+        Color::RGB(r, g, b) => u32::from(r) << 16 | u32::from(g) << 8 | u32::from(b),
+    }
+}
+
+/// CSS color of a terminal color.
+fn css_color(color: Color) -> String {
+    format!("#{:06x}", rgb(color))
+}
+
+/// Inline CSS of a style, empty for the default style. Reversed text
+/// swaps the colors, the page colors standing in for missing ones.
+fn css(style: &Style) -> String {
+    let (fg, bg) = match style.is_reverse {
+        true => (
+            Some(style.background.map_or("Canvas".to_string(), css_color)),
+            Some(style.foreground.map_or("CanvasText".to_string(), css_color)),
+        ),
+        false => (
+            style.foreground.map(css_color),
+            style.background.map(css_color),
+        ),
+    };
+    let mut css = Vec::new();
+    if let Some(fg) = fg {
+        css.push(format!("color:{fg}"));
+    }
+    if let Some(bg) = bg {
+        css.push(format!("background-color:{bg}"));
+    }
+    if style.is_bold {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.is_dimmed {
+        css.push("opacity:0.5".to_string());
+    }
+    css.join(";")
+}
+
+/// Write a span as an HTML element, separators and padding as bare text.
+fn write_html(out: &mut dyn Write, span: &Span) -> io::Result<()> {
+    let text = escape_html(&span.text);
+    if span.role == Role::Space {
+        return write!(out, "{text}");
+    }
+    match css(&span.style) {
+        css if css.is_empty() => write!(out, "<span class=\"{}\">{text}</span>", span.role.class()),
+        css => write!(
+            out,
+            "<span class=\"{}\" style=\"{css}\">{text}</span>",
+            span.role.class()
+        ),
+    }
+}
@@ -0,0 +1,235 @@
+//! symbol tables from linker map files, `nm` listings and ELF files, for
+//! labeling offsets as `name+0x1c`
+
+use std::io;
+
+/// A named address range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// start address, taken as an offset into the input
+    pub addr: u64,
+    /// size in bytes, None when the table doesn't say
+    pub size: Option<u64>,
+    /// symbol name
+    pub name: String,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Parse a symbol file, an ELF file or a text listing, sorted by address.
+///
+/// # Arguments
+///
+/// * `data` - file contents.
+pub fn parse(data: &[u8]) -> io::Result<Vec<Symbol>> {
+    let mut symbols = match data.starts_with(b"\x7fELF") {
+        true => parse_elf(data)?,
+        false => parse_listing(&String::from_utf8_lossy(data)),
+    };
+    symbols.sort_by_key(|symbol| symbol.addr);
+    Ok(symbols)
+}
+
+/// Symbols of a text listing: GNU ld map lines `0x00001000 main` and `nm`
+/// lines `00001000 T main`. Anything else, like section headers and
+/// assignments, is skipped.
+///
+/// # Arguments
+///
+/// * `text` - listing.
+pub fn parse_listing(text: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (addr, name) = match fields[..] {
+            [addr, name] if addr.starts_with("0x") => (&addr[2..], name),
+            [addr, kind, name] if kind.len() == 1 && !addr.starts_with("0x") => (addr, name),
+            _ => continue,
+        };
+        let identifier = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.$@".contains(c));
+        if let (Ok(addr), true) = (u64::from_str_radix(addr, 16), identifier) {
+            symbols.push(Symbol {
+                addr,
+                size: None,
+                name: name.to_string(),
+            });
+        }
+    }
+    symbols
+}
+
+/// Reads ELF fields in the file's word size and byte order
+pub(crate) struct Elf<'a> {
+    data: &'a [u8],
+    /// 64 bit file
+    pub wide: bool,
+    /// big endian file
+    pub big_endian: bool,
+}
+
+impl<'a> Elf<'a> {
+    /// Check the ELF class and byte order.
+    pub fn new(data: &'a [u8]) -> io::Result<Elf<'a>> {
+        Ok(Elf {
+            data,
+            wide: match data.get(4) {
+                Some(1) => false,
+                Some(2) => true,
+                _ => return Err(invalid("unknown ELF class")),
+            },
+            big_endian: data.get(5) == Some(&2),
+        })
+    }
+
+    /// Contents of the section called `name`, None when there is none.
+    #[cfg(feature = "dwarf")]
+    pub fn section_named(&self, name: &str) -> io::Result<Option<&'a [u8]>> {
+        let count = self.uint(if self.wide { 0x3c } else { 0x30 }, 2)?;
+        let names = self.uint(if self.wide { 0x3e } else { 0x32 }, 2)?;
+        let names_at = self.section(names)?.1;
+        for index in 0..count {
+            let (_, offset, size, ..) = self.section(index)?;
+            let header = self.header_at(index)?;
+            if self.name(names_at, self.uint(header, 4)?) == name {
+                let range = offset as usize..(offset + size) as usize;
+                return Ok(Some(
+                    self.data
+                        .get(range)
+                        .ok_or_else(|| invalid("truncated ELF file"))?,
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    fn header_at(&self, index: u64) -> io::Result<usize> {
+        let shoff = self.word(if self.wide { 0x28 } else { 0x20 })? as usize;
+        let entsize = self.uint(if self.wide { 0x3a } else { 0x2e }, 2)? as usize;
+        Ok(shoff + index as usize * entsize)
+    }
+
+    fn uint(&self, at: usize, len: usize) -> io::Result<u64> {
+        let bytes = self
+            .data
+            .get(at..at + len)
+            .ok_or_else(|| invalid("truncated ELF file"))?;
+        Ok(crate::scalar::decode(
+            bytes,
+            match self.big_endian {
+                true => crate::scalar::Endian::Big,
+                false => crate::scalar::Endian::Little,
+            },
+        ))
+    }
+
+    /// An address or offset sized field
+    fn word(&self, at: usize) -> io::Result<u64> {
+        self.uint(at, if self.wide { 8 } else { 4 })
+    }
+
+    /// type, offset, size, link and entry size of section `index`
+    fn section(&self, index: u64) -> io::Result<(u32, u64, u64, u32, u64)> {
+        let at = self.header_at(index)?;
+        let kind = self.uint(at + 4, 4)? as u32;
+        match self.wide {
+            true => Ok((
+                kind,
+                self.uint(at + 0x18, 8)?,
+                self.uint(at + 0x20, 8)?,
+                self.uint(at + 0x28, 4)? as u32,
+                self.uint(at + 0x38, 8)?,
+            )),
+            false => Ok((
+                kind,
+                self.uint(at + 0x10, 4)?,
+                self.uint(at + 0x14, 4)?,
+                self.uint(at + 0x18, 4)? as u32,
+                self.uint(at + 0x24, 4)?,
+            )),
+        }
+    }
+
+    fn name(&self, strtab: u64, offset: u64) -> String {
+        let start = (strtab + offset) as usize;
+        let rest = self.data.get(start..).unwrap_or_default();
+        let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+        String::from_utf8_lossy(&rest[..end]).to_string()
+    }
+}
+
+/// Function and object symbols of an ELF file's symbol table, or of its
+/// dynamic symbol table when it is stripped.
+///
+/// # Arguments
+///
+/// * `data` - ELF file contents.
+pub fn parse_elf(data: &[u8]) -> io::Result<Vec<Symbol>> {
+    let elf = Elf::new(data)?;
+    let count = elf.uint(if elf.wide { 0x3c } else { 0x30 }, 2)?;
+    let tables: Vec<_> = (0..count)
+        .map(|index| elf.section(index))
+        .collect::<io::Result<_>>()?;
+    // SHT_SYMTAB, else SHT_DYNSYM
+    let Some(&(_, offset, size, link, entsize)) = tables
+        .iter()
+        .find(|section| section.0 == 2)
+        .or_else(|| tables.iter().find(|section| section.0 == 11))
+    else {
+        return Err(invalid("the ELF file has no symbol table"));
+    };
+    let strtab = elf.section(u64::from(link))?.1;
+
+    let mut symbols = Vec::new();
+    for at in (offset..offset + size).step_by(entsize.max(1) as usize) {
+        let at = at as usize;
+        let (name, info, shndx, addr, len) = match elf.wide {
+            true => (
+                elf.uint(at, 4)?,
+                elf.uint(at + 4, 1)?,
+                elf.uint(at + 6, 2)?,
+                elf.uint(at + 8, 8)?,
+                elf.uint(at + 16, 8)?,
+            ),
+            false => (
+                elf.uint(at, 4)?,
+                elf.uint(at + 12, 1)?,
+                elf.uint(at + 14, 2)?,
+                elf.uint(at + 4, 4)?,
+                elf.uint(at + 8, 4)?,
+            ),
+        };
+        // STT_OBJECT and STT_FUNC, defined in some section
+        if matches!(info & 0xf, 1 | 2) && shndx != 0 {
+            symbols.push(Symbol {
+                addr,
+                size: (len > 0).then_some(len),
+                name: elf.name(strtab, name),
+            });
+        }
+    }
+    Ok(symbols)
+}
+
+/// `name+0x1c` for the symbol holding `offset`: the closest symbol at or
+/// below it, within its size when the table records one.
+///
+/// # Arguments
+///
+/// * `symbols` - symbols sorted by address.
+/// * `offset` - offset to look up.
+pub fn lookup(symbols: &[Symbol], offset: u64) -> Option<String> {
+    let index = symbols.partition_point(|symbol| symbol.addr <= offset);
+    let symbol = symbols.get(index.checked_sub(1)?)?;
+    let delta = offset - symbol.addr;
+    if symbol.size.is_some_and(|size| delta >= size) {
+        return None;
+    }
+    Some(match delta {
+        0 => symbol.name.clone(),
+        delta => format!("{}+{delta:#x}", symbol.name),
+    })
+}
@@ -0,0 +1,121 @@
+//! temporary files: the `--two-pass` copy of a stream and the new copy of a
+//! file written before it's renamed into place, uniquely named, removed when
+//! dropped and, on unix, when hx is killed by a signal, so a stopped run
+//! never leaves half a file behind for a later one to read
+
+use crate::interrupt::{unwatch, watch};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// environment variable choosing the directory of temporary files
+pub const TEMP_DIR_ENV: &str = "HX_TEMP_DIR";
+
+/// Directory temporary files go in: `--temp-dir`, else `$HX_TEMP_DIR`, else
+/// the system's.
+///
+/// # Arguments
+///
+/// * `given` - directory given with `--temp-dir`.
+pub fn dir(given: Option<&Path>) -> PathBuf {
+    match given {
+        Some(dir) => dir.to_path_buf(),
+        None => env::var_os(TEMP_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map_or_else(env::temp_dir, PathBuf::from),
+    }
+}
+
+/// File that's removed when dropped unless it's renamed into place first
+#[derive(Debug, PartialEq, Eq)]
+pub struct TempFile {
+    /// where the file is
+    pub path: PathBuf,
+}
+
+/// TempFile implementation
+impl TempFile {
+    /// Create a new file of a unique name, readable by the user alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - directory to create it in.
+    /// * `prefix` - start of its name, e.g. `hx-spool`.
+    pub fn create(dir: &Path, prefix: &str) -> io::Result<(TempFile, File)> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut n = 0;
+        loop {
+            let path = dir.join(format!("{prefix}-{}-{n}", std::process::id()));
+            match options.open(&path) {
+                Ok(file) => {
+                    watch(&path);
+                    return Ok((TempFile { path }, file));
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+                Err(e) => {
+                    return Err(io::Error::new(
+                        e.kind(),
+                        format!("can't create {}: {e}", path.display()),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Create a new file beside another, hidden on unix, to be renamed
+    /// over it.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - file it's going to replace.
+    pub fn beside(target: &Path) -> io::Result<(TempFile, File)> {
+        let dir = match target.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        TempFile::create(dir, &format!(".{name}.hx"))
+    }
+
+    /// Rename the file over another, which then holds either its old bytes
+    /// or all the new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - file to replace.
+    pub fn persist(self, target: &Path) -> io::Result<()> {
+        fs::rename(&self.path, target)?;
+        unwatch(&self.path);
+        // renamed, there's nothing left to remove
+        std::mem::forget(self);
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        unwatch(&self.path);
+    }
+}
+
+/// Write a file whole or not at all, through a temporary file beside it.
+///
+/// # Arguments
+///
+/// * `path` - file to write.
+/// * `data` - its new bytes.
+pub fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let (temp, mut file) = TempFile::beside(path)?;
+    file.write_all(data)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()?;
+    drop(file);
+    temp.persist(path)
+}
@@ -71,6 +71,122 @@ fn test_line_struct() {
     assert_eq!(ascii_line.offset, 0x0);
 }
 
+#[test]
+fn test_page_adapters() {
+    let page = Page::from_lines(
+        b"0123456789"
+            .chunks(4)
+            .zip((0..).step_by(4))
+            .map(|(bytes, offset)| Line {
+                offset,
+                hex_body: bytes.to_vec(),
+                bytes: bytes.len() as u64,
+                ..Line::new()
+            })
+            .collect(),
+    );
+    assert_eq!(page.body[1].as_slice(), b"4567");
+    assert_eq!(page.body[1].end(), 8);
+    assert_eq!(page.iter_bytes().collect::<Vec<u8>>(), b"0123456789");
+    let chunks: Vec<Page> = page.chunks(2).collect();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!((chunks[0].offset, chunks[0].bytes), (0, 8));
+    assert_eq!((chunks[1].offset, chunks[1].bytes), (8, 2));
+    assert_eq!(chunks[1].iter_bytes().collect::<Vec<u8>>(), b"89");
+    let slice = page.slice(2..9);
+    assert_eq!((slice.offset, slice.bytes), (2, 7));
+    let lines: Vec<(u64, &[u8])> = slice
+        .body
+        .iter()
+        .map(|line| (line.offset, line.as_slice()))
+        .collect();
+    assert_eq!(lines, [(2, &b"23"[..]), (4, b"4567"), (8, b"8")]);
+    assert_eq!(page.slice(4..8).body.len(), 1);
+    assert_eq!(page.slice(5..5).bytes, 0);
+    assert_eq!(page.slice(8..100).iter_bytes().collect::<Vec<u8>>(), b"89");
+}
+
+#[test]
+fn test_observed_reader() {
+    use crate::hash::{crc32, to_hex};
+    let data: Vec<u8> = (0..=255).collect();
+    let (mut sha, mut crc, mut stats) = (Sha256::new(), Crc32::new(), ByteStats::new());
+    let mut chunks = 0;
+    let mut count = |_: &[u8]| chunks += 1;
+    let mut reader =
+        ObservedReader::new(&data[..], vec![&mut sha, &mut crc, &mut stats, &mut count]);
+    let mut buf = [0; 100];
+    while reader.read(&mut buf).unwrap() > 0 {}
+    assert_eq!(chunks, 4);
+    assert_eq!(crc.finish(), crc32(&data));
+    assert_eq!(stats.len, 256);
+    assert_eq!(stats.entropy(), 8.0);
+    let mut whole = Sha256::new();
+    whole.update(&data);
+    assert_eq!(to_hex(&sha.finish()), to_hex(&whole.finish()));
+
+    let mut same = ByteStats::new();
+    same.update(b"aaaa");
+    assert_eq!(same.entropy(), 0.0);
+}
+
+#[test]
+fn test_dump_observed() {
+    let config = HexConfig::new().skip(2).truncate_len(4);
+    let mut seen = Vec::new();
+    let mut collect = |data: &[u8]| seen.extend_from_slice(data);
+    let mut out = Vec::new();
+    let dumped = dump_observed(
+        &mut &b"0123456789"[..],
+        &mut out,
+        &config,
+        vec![&mut collect],
+    )
+    .unwrap();
+    assert_eq!(dumped, 4);
+    assert_eq!(seen, b"2345");
+    let mut plain = Vec::new();
+    dump(&mut &b"0123456789"[..], &mut plain, &config).unwrap();
+    assert_eq!(out, plain);
+}
+
+#[test]
+fn test_block_cache() {
+    use std::io::{Cursor, Seek, SeekFrom};
+    let data: Vec<u8> = (0..100).collect();
+    let mut cache = BlockCache::with_block(Cursor::new(data.clone()), 16, 2);
+    let mut buf = [0; 8];
+    cache.seek(SeekFrom::Start(12)).unwrap();
+    cache.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, data[12..20]);
+    assert_eq!(cache.stats(), (0, 2));
+    // scrolling back reads from memory
+    cache.seek(SeekFrom::Start(0)).unwrap();
+    cache.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, data[..8]);
+    assert_eq!(cache.stats(), (1, 2));
+    // a third block drops the least recently used, the one at 16
+    cache.seek(SeekFrom::End(-4)).unwrap();
+    let mut tail = Vec::new();
+    cache.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, data[96..]);
+    cache.seek(SeekFrom::Start(0)).unwrap();
+    cache.read_exact(&mut buf).unwrap();
+    let (_, misses) = cache.stats();
+    cache.seek(SeekFrom::Start(16)).unwrap();
+    cache.read_exact(&mut buf).unwrap();
+    assert_eq!(cache.stats().1, misses + 1);
+    assert!(cache.seek(SeekFrom::Current(-100)).is_err());
+
+    let config = HexConfig::new().columns(8);
+    let mut cache = BlockCache::new(Cursor::new(data.clone()), 4);
+    let lines = render_range(&mut cache, 8, 16, &config).unwrap();
+    let again = render_range(&mut cache, 8, 16, &config).unwrap();
+    assert_eq!(lines, again);
+    assert_eq!(lines[0].offset, 8);
+    assert_eq!(cache.stats().1, 1);
+}
+
 use assert_cmd::Command;
 
 /// target/debug/hx -ar tests/files/tiny.txt
@@ -93,6 +209,92 @@ fn test_cli_arg_order_2() {
     assert.success().code(0);
 }
 
+#[test]
+fn test_cli_fragment() {
+    let fragment = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c2", "--fragment", "-l2"])
+            .args(args)
+            .arg("tests/files/lorem.md")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    let fragments = fragment(&["-ar"]) + &fragment(&["-ar", "-s2"]);
+    assert_eq!(fragments, "    0x23, 0x20, \n    0x4c, 0x6f, \n");
+    assert_eq!(fragment(&["-s2"]), "0x000002: 0x4c 0x6f Lo\n");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-ar", "--finalize"])
+        .write_stdin(fragments)
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "let ARRAY: [u8; 4] = [\n    0x23, 0x20, \n    0x4c, 0x6f, \n];\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--finalize").write_stdin("").assert().failure();
+}
+
+#[test]
+fn test_cli_array_streams() {
+    let array = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-ac"])
+            .args(args)
+            .arg("tests/files/lorem.md")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    // lorem.md is 139 bytes, the rows are written as they're read
+    let stdout = array(&[]);
+    assert!(
+        stdout.starts_with("unsigned char ARRAY[139] = {\n"),
+        "{stdout}"
+    );
+    assert_eq!(array(&["--memory-limit", "100"]), stdout);
+    assert_eq!(array(&["--memory-limit", "100", "-c4"]), array(&["-c4"]));
+}
+
+#[test]
+fn test_cli_mark_truncated() {
+    let run = |args: &[&str], stdin: Option<&str>| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["-t0", "-c4", "--mark-truncated"]).args(args);
+        if let Some(stdin) = stdin {
+            cmd.write_stdin(stdin);
+        }
+        let assert = cmd.assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        run(&["-l2", "tests/files/tiny.txt"], None),
+        "0x000000: 0x69 0x6c           il\n... truncated, 1 more bytes ...\n   bytes: 2\n"
+    );
+    assert_eq!(
+        run(&["-l3", "tests/files/tiny.txt"], None),
+        "0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n"
+    );
+    assert_eq!(
+        run(&["-l2"], Some("abc")),
+        "0x000000: 0x61 0x62           ab\n... truncated, more bytes follow ...\n   bytes: 2\n"
+    );
+    assert_eq!(
+        run(&["-l2", "-ar", "tests/files/tiny.txt"], None),
+        "let ARRAY: [u8; 2] = [\n    0x69, 0x6c\n];\n// truncated, 1 more bytes\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--mark-truncated")
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
 /// target/debug/hx --len tests/files/tiny.txt
 ///     error: invalid digit found in string
 #[test]
@@ -125,3 +327,5982 @@ fn test_cli_input_stdin() {
         .code(0)
         .stdout("0x000000: 0x30 0x31 0x32                                    012\n   bytes: 3\n");
 }
+
+#[test]
+fn test_read_line() {
+    let mut buf: &[u8] = b"0123456789";
+    let mut line: Line = Line::new();
+    assert_eq!(read_line(&mut buf, &mut line, 4).unwrap(), 4);
+    assert_eq!(line.hex_body, b"0123");
+    assert_eq!(line.bytes, 4);
+    assert_eq!(read_line(&mut buf, &mut line, 8).unwrap(), 6);
+    assert_eq!(line.bytes, 10);
+    assert_eq!(read_line(&mut buf, &mut line, 8).unwrap(), 0);
+}
+
+/// a line which is still sitting in the buffer shows up once the line ends
+#[test]
+fn test_output_writer_line_policy() {
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Line);
+    write!(out, "0x000000:").unwrap();
+    out.end_line().unwrap();
+    assert_eq!(out.inner.get_ref().as_slice(), b"0x000000:\n");
+
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Block);
+    write!(out, "0x000000:").unwrap();
+    out.end_line().unwrap();
+    assert!(out.inner.get_ref().is_empty());
+}
+
+#[test]
+fn test_cli_line_buffered() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--line-buffered")
+        .arg("-t0")
+        .arg("-c2")
+        .write_stdin("0123")
+        .assert();
+    assert
+        .success()
+        .code(0)
+        .stdout("0x000000: 0x30 0x31 01\n0x000002: 0x32 0x33 23\n   bytes: 4\n");
+}
+
+#[test]
+fn test_cli_timestamps() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c2", "--timestamps=delta"])
+        .write_stdin("0123")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for (line, dump) in lines
+        .iter()
+        .zip(["0x000000: 0x30 0x31 01", "0x000002: 0x32 0x33 23"])
+    {
+        let (stamp, rest) = line.trim_start().split_once(' ').unwrap();
+        assert!(stamp.starts_with('+'));
+        assert!(stamp[1..].parse::<f64>().is_ok());
+        assert_eq!(rest, dump);
+    }
+    assert_eq!(lines[2], "   bytes: 4");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--timestamps"])
+        .write_stdin("0123")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let (stamp, rest) = stdout.lines().next().unwrap().split_once(' ').unwrap();
+    // hh:mm:ss.micros
+    assert_eq!(stamp.len(), 15);
+    assert_eq!(&stamp[2..3], ":");
+    assert_eq!(&stamp[8..9], ".");
+    assert_eq!(rest, "0x000000: 0x30 0x31 0x32 0x33 0123");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--timestamps", "--raw"]).assert().failure();
+}
+
+#[test]
+fn test_timing_report() {
+    let report = timing_report(Duration::from_millis(500), 1_000_000, true);
+    assert_eq!(report, "  timing: 0.500s, 1000000 bytes, 2.00 MB/s");
+    assert_eq!(
+        timing_report(Duration::ZERO, 0, true),
+        "  timing: 0.000s, 0 bytes, 0.00 MB/s"
+    );
+}
+
+/// timing goes to stderr so stdout stays unchanged
+#[test]
+fn test_cli_timing() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--timing").arg("-t0").write_stdin("012").assert();
+    let output = assert.success().code(0).get_output().clone();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "0x000000: 0x30 0x31 0x32                                    012\n   bytes: 3\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("  timing: "));
+    assert!(stderr.contains(", 3 bytes, "));
+}
+
+#[test]
+fn test_selftest_golden() {
+    let mut report = Vec::new();
+    assert_eq!(selftest::selftest(&mut report).unwrap(), 0);
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.ends_with("selftest: 18 checks, 0 mismatches\n"));
+}
+
+#[test]
+fn test_cli_selftest() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("selftest").assert();
+    assert.success().code(0);
+}
+
+#[test]
+fn test_parse_args_defaults() {
+    let config = parse_args(&[]).unwrap();
+    assert_eq!(config.mode, Mode::Dump);
+    assert_eq!(config.input, InputSource::Stdin);
+    assert_eq!(config.columns, 10);
+    assert_eq!(config.truncate_len, 0);
+    assert_eq!(config.color, None);
+    assert!(config.prefix);
+}
+
+#[test]
+fn test_parse_args_values() {
+    let config = parse_args(&["-c4", "-l", "16", "-t0", "-r0", "-ar", "file.bin"]).unwrap();
+    assert_eq!(config.mode, Mode::Array("r".to_string()));
+    assert_eq!(config.input, InputSource::File("file.bin".into()));
+    assert_eq!(config.columns, 4);
+    assert_eq!(config.truncate_len, 16);
+    assert_eq!(config.color, Some(false));
+    assert!(!config.prefix);
+
+    let config = parse_args(&["--func", "8", "--places", "2"]).unwrap();
+    assert_eq!(config.mode, Mode::Func { len: 8, places: 2 });
+}
+
+/// malformed values are errors, never panics
+#[test]
+fn test_parse_args_errors() {
+    for args in [
+        &["--func", "x"][..],
+        &["--cols", "-1"],
+        &["--len", "ten"],
+        &["--places", "1.5", "--func", "3"],
+        &["--color", "2"],
+        &["--format", "q"],
+    ] {
+        assert!(parse_args(args).is_err(), "{args:?}");
+    }
+    let err = parse_args(&["--cols", "x"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "-c, --cols <integer> expected. invalid digit found in string"
+    );
+}
+
+/// options another option would ignore are rejected, naming both
+#[test]
+fn test_parse_args_incompatible() {
+    let err = parse_args(&["-ar", "--stripe", "2"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "--stripe can't be combined with --array: arrays aren't colored, drop one of them"
+    );
+    assert!(matches!(
+        parse_args(&["-ac", "-f", "o"]),
+        Err(HexError::Incompatible {
+            option: "format",
+            with: "array",
+            ..
+        })
+    ));
+    assert!(parse_args(&["--func", "8", "-ar"]).is_err());
+    let config = parse_args(&["--func", "4", "--func-output", "bytes", "-ar"]).unwrap();
+    assert_eq!(config.mode, Mode::Array("r".to_string()));
+    assert_eq!(
+        config.input,
+        InputSource::Literal(vec![0x00, 0x62, 0xb4, 0xec])
+    );
+    assert!(parse_args(&["--func", "4", "--func-output", "bytes", "-p2"]).is_err());
+    assert!(parse_args(&["--func", "4", "--func-output", "bytes", "file.bin"]).is_err());
+    assert!(parse_args(&["--func-output", "bytes"]).is_err());
+    let mut wave = Vec::new();
+    output_function(&mut wave, 11, 2, false).unwrap();
+    assert_eq!(
+        String::from_utf8(wave).unwrap(),
+        "0.00,0.14,0.28,0.42,0.54,0.65,0.76,0.84,0.91,0.96,\n0.99,\n"
+    );
+    let mut wave = Vec::new();
+    output_function(&mut wave, 2, 1, true).unwrap();
+    assert_eq!(
+        String::from_utf8(wave).unwrap(),
+        "\x1b[38;5;22m0.0\x1b[0m,\x1b[38;5;180m0.7\x1b[0m,\n"
+    );
+    assert!(parse_args(&["--places", "2"]).is_err());
+    let err = parse_args(&["-c0", "--unique-lines"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "--unique-lines can't be combined with --cols 0: one line has nothing to compare, drop one of them"
+    );
+    for args in [
+        &["-ar", "--only", "printable"][..],
+        &["--raw", "--only", "printable"],
+        &["-ar", "--profile", "safe"],
+        &["--glob", "*.bin", "fw.bin"],
+        &["-ar", "--source-offsets"],
+        &["--endian", "big", "fw.bin"],
+        &["--unaligned", "--find", "00", "fw.bin"],
+        &["--output", "json", "--stripe", "2"],
+        &["--output", "plain", "--highlight", "0:9"],
+    ] {
+        assert!(parse_args(args).is_err(), "{args:?}");
+    }
+    // options arrays do use are fine
+    assert!(parse_args(&["-ar", "-c4", "-l8", "-t0", "-s2"]).is_ok());
+}
+
+#[test]
+fn test_cli_func_invalid_length() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--func").arg("abc").assert();
+    assert
+        .failure()
+        .code(1)
+        .stderr("error: -u, --func <integer> expected. invalid digit found in string\n");
+}
+
+#[test]
+fn test_i18n_fallback() {
+    assert_eq!(i18n::translate(Msg::Bytes, "en"), "bytes");
+    assert_eq!(i18n::translate(Msg::Error, "xx"), "error");
+    assert_eq!(i18n::translate(Msg::Timing, ""), "timing");
+}
+
+#[cfg(feature = "i18n")]
+#[test]
+fn test_i18n_catalog() {
+    assert_eq!(i18n::translate(Msg::Bytes, "de"), "Bytes");
+    assert_eq!(i18n::translate(Msg::Error, "fr"), "erreur");
+    assert_eq!(i18n::translate(Msg::Timing, "es"), "tiempo");
+    assert_eq!(i18n::translate(Msg::Warning, "de"), "Warnung");
+}
+
+#[test]
+fn test_human_size() {
+    assert_eq!(i18n::group_digits(0, "en"), "0");
+    assert_eq!(i18n::group_digits(999, "en"), "999");
+    assert_eq!(i18n::group_digits(1_000, "en"), "1,000");
+    assert_eq!(i18n::group_digits(12_345_678, "en"), "12,345,678");
+    assert_eq!(i18n::human_size(68, "en"), "68");
+    assert_eq!(i18n::human_size(1024, "en"), "1,024 (1.0 KiB)");
+    assert_eq!(i18n::human_size(12_345_678, "en"), "12,345,678 (11.8 MiB)");
+    assert_eq!(format_size(12_345_678, true), "12345678");
+}
+
+#[cfg(feature = "i18n")]
+#[test]
+fn test_human_size_locale() {
+    assert_eq!(i18n::human_size(12_345_678, "de"), "12.345.678 (11,8 MiB)");
+    assert_eq!(i18n::human_size(12_345_678, "fr"), "12 345 678 (11,8 MiB)");
+}
+
+#[test]
+fn test_cli_raw_sizes() {
+    let input = "0".repeat(2048);
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let output = cmd.arg("-t0").write_stdin(input.clone()).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("   bytes: 2,048 (2.0 KiB)\n"));
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let output = cmd
+        .arg("-t0")
+        .arg("--raw-sizes")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("   bytes: 2048\n"));
+}
+
+#[test]
+fn test_cli_reproducible() {
+    let dump = |columns: &str| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .env("COLUMNS", columns)
+            .env("LC_ALL", "de_DE.UTF-8")
+            .args(["--reproducible", "-t1", "-c", "auto", "--header"])
+            .arg("tests/files/lorem.md")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    let stdout = dump("200");
+    assert_eq!(stdout, dump("40"));
+    assert!(!stdout.contains('\x1b'), "{stdout}");
+    assert!(!stdout.contains("modified"), "{stdout}");
+    assert!(stdout.contains(" options: -c 11 -f x\n"), "{stdout}");
+    let size = std::fs::metadata("tests/files/lorem.md").unwrap().len();
+    assert!(stdout.ends_with(&format!("   bytes: {size}\n")), "{stdout}");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--reproducible", "--timestamps", "tests/files/lorem.md"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_memory_limit() {
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["-t0", "--memory-limit", "100"])
+            .args(args)
+            .arg("tests/files/lorem.md")
+            .assert()
+    };
+    // lorem.md is 139 bytes, more than --rle may hold
+    let assert = run(&["--rle", "4"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(
+        stderr.contains("the input needs more than the --memory-limit of 100"),
+        "{stderr}"
+    );
+    run(&["--rle", "4", "-l", "100"]).success();
+    // the read-ahead blocks shrink to fit, unless given
+    let assert = run(&[]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.ends_with("bytes: 139\n"), "{stdout}");
+    run(&["--io-readahead", "64"]).failure();
+    run(&["-c", "0"]).failure();
+    // distinct lines, while arrays of a stream are spooled to a file
+    let assert = run(&["--unique-lines", "-c4"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("--unique-lines needs more"), "{stderr}");
+    run(&["--unique-lines", "-c4", "-l", "40"]).success();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-ac", "--memory-limit", "100"])
+        .write_stdin(std::fs::read("tests/files/lorem.md").unwrap())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let whole = cmd.args(["-ac", "tests/files/lorem.md"]).assert().success();
+    assert_eq!(assert.get_output().stdout, whole.get_output().stdout);
+
+    assert_eq!(textdiff::table_bytes("a\nb\nc\n", "a\nx\nc\n"), 16);
+    assert_eq!(textdiff::table_bytes("a\n", "a\n"), 4);
+    assert!(parse_args(&["--memory-limit", "0"]).is_err());
+    assert_eq!(
+        parse_args(&["--memory-limit", "1MiB"])
+            .unwrap()
+            .memory_limit,
+        Some(1 << 20)
+    );
+}
+
+#[test]
+fn test_cli_fd() {
+    // stdin is the descriptor hx inherits from any parent
+    let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("hx"))
+        .args(["-t0", "--fd", "0", "--skip", "end-4"])
+        .stdin(std::fs::File::open("tests/files/lorem.md").unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        stdout.starts_with("0x000087: 0x75 0x61 0x2e 0x0a"),
+        "{stdout}"
+    );
+    let assert = Command::cargo_bin("hx")
+        .unwrap()
+        .args(["--fd", "999"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(
+        stderr.contains("fd 999 isn't an open descriptor"),
+        "{stderr}"
+    );
+    assert_eq!(
+        parse_args(&["--fd", "3"]).unwrap().input,
+        InputSource::Fd(3)
+    );
+    assert_eq!(InputSource::Fd(3).name(), "fd 3");
+    assert!(parse_args(&["--fd", "x"]).is_err());
+    assert!(parse_args(&["--fd", "3", "file.bin"]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_examples() {
+    let dir = env::temp_dir().join(format!("hx-examples-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut file = b"\x7fELF".to_vec();
+    file.extend(0..252);
+    std::fs::write(dir.join("file.bin"), &file).unwrap();
+    std::fs::write(dir.join("old.bin"), b"\x7fELF\x01\x01\x00\x00").unwrap();
+    std::fs::write(dir.join("new.bin"), b"\x7fELF\x01\x01\x03\x00").unwrap();
+    std::fs::write(dir.join("mask.bin"), b"\xff\xff\xff\xff\xff\xff\x00\xff").unwrap();
+    // every example runs as printed, with this hx first on the PATH
+    let hx = assert_cmd::cargo::cargo_bin("hx");
+    let path = format!(
+        "{}:{}",
+        hx.parent().unwrap().display(),
+        env::var("PATH").unwrap_or_default()
+    );
+    for example in examples::EXAMPLES {
+        assert!(
+            examples::TOPICS
+                .iter()
+                .any(|(name, _)| *name == example.topic),
+            "{}",
+            example.topic
+        );
+        let output = std::process::Command::new("sh")
+            .args(["-c", example.command])
+            .current_dir(&dir)
+            .env("PATH", &path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            example.command,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["examples", "arrays"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with(
+        "arrays: source code arrays\n\n  # Write a file as a rust array\n  hx -ar file.bin\n"
+    ));
+    assert!(!stdout.contains("piping"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("examples").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(stdout.matches("\n  # ").count(), examples::EXAMPLES.len());
+    Command::cargo_bin("hx")
+        .unwrap()
+        .args(["examples", "nope"])
+        .assert()
+        .failure();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--help").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("Examples:\n  hx -c16 file.bin\n"));
+    assert!(stdout.ends_with("topics: basics, piping, arrays, diffs, searches\n"));
+}
+
+#[test]
+fn test_cli_two_pass() {
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["-t0"])
+            .args(args)
+            .pipe_stdin("tests/files/lorem.md")
+            .unwrap()
+            .assert()
+    };
+    let assert = run(&["--skip", "end-4"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("read a stream with --two-pass"), "{stderr}");
+    let assert = run(&["--two-pass", "--skip", "end-4"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(
+        stdout.starts_with("0x000087: 0x75 0x61 0x2e 0x0a"),
+        "{stdout}"
+    );
+    let assert = run(&["--two-pass", "-l4", "--mark-truncated"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("135 more bytes"), "{stdout}");
+
+    // parsing reads neither stdin nor the files the options name, the run does
+    let dir = env::temp_dir().join(format!("hx-two-pass-parse-{}", std::process::id()));
+    let dir_arg = dir.to_str().unwrap();
+    let config = parse_args(&["--two-pass", "--temp-dir", dir_arg, "--skip", "end-4"]).unwrap();
+    assert_eq!(config.pending.spool_dir.as_deref(), Some(dir.as_path()));
+    assert_eq!(config.skip, 0);
+    assert!(!dir.exists());
+    let config = parse_args(&["--symbols", "missing.map", "tests/files/lorem.md"]).unwrap();
+    assert!(config.symbols.is_empty());
+    assert!(config.load().is_err());
+
+    let spool =
+        spool::Spool::create(InputSource::Literal(b"abc".to_vec()), &env::temp_dir()).unwrap();
+    let path = spool.temp.path.clone();
+    let source = InputSource::Spooled(std::sync::Arc::new(spool));
+    assert_eq!(source.capabilities().len, Some(3));
+    assert_eq!(source.name(), "stdin");
+    let mut bytes = Vec::new();
+    source.open().unwrap().read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes, b"abc");
+    drop(source);
+    assert!(!path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_live_input() {
+    let zero = InputSource::new(Some(Path::new("/dev/zero")));
+    assert!(zero.capabilities().live);
+    assert_eq!(zero.capabilities().len, None);
+    // an endless device can't be sampled, it's dumped as it streams
+    let assert = Command::cargo_bin("hx")
+        .unwrap()
+        .args(["-t0", "-l4", "--sample", "2", "--header", "/dev/zero"])
+        .assert()
+        .success();
+    let output = assert.get_output();
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(
+        stdout.starts_with("    file: /dev/zero\n    type: data\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("0x000000: 0x00 0x00 0x00 0x00"), "{stdout}");
+    assert!(
+        stderr.contains("warning: --sample needs the end of /dev/zero"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("warning: /dev/zero is a pipe or device, --header leaves out its size"),
+        "{stderr}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_live_stdin() {
+    use std::io::BufRead;
+    use std::process::Stdio;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("hx"))
+        .args(["-t0", "-c16"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // a piped stdin is live, its first line shows while the pipe is open
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"0123456789abcdef").unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (sent, line) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut first = String::new();
+        io::BufReader::new(stdout).read_line(&mut first).unwrap();
+        sent.send(first).unwrap();
+    });
+    let first = line.recv_timeout(std::time::Duration::from_secs(10));
+    drop(stdin);
+    child.wait().unwrap();
+    assert!(first.unwrap().starts_with("0x000000: 0x30 0x31"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_non_utf8_paths() {
+    use std::os::unix::ffi::OsStrExt;
+    let dir = env::temp_dir().join(format!("hx-non-utf8-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join(std::ffi::OsStr::from_bytes(b"in-\xff.bin"));
+    let output = dir.join(std::ffi::OsStr::from_bytes(b"out-\xfe.txt"));
+    std::fs::write(&input, "hx").unwrap();
+    let assert = Command::cargo_bin("hx")
+        .unwrap()
+        .args(["-t0", "-l2"])
+        .arg(&input)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000000: 0x68 0x78"), "{stdout}");
+    Command::cargo_bin("hx")
+        .unwrap()
+        .args(["-t0", "--concat"])
+        .args([&input, &input])
+        .arg("--outfile")
+        .arg(&output)
+        .assert()
+        .success();
+    let written = std::fs::read_to_string(&output).unwrap();
+    assert!(
+        written.starts_with("0x000000: 0x68 0x78 0x68 0x78"),
+        "{written}"
+    );
+    // file lists, the modes and --carve keep the name's bytes too
+    let mut list = input.as_os_str().as_bytes().to_vec();
+    list.push(0);
+    Command::cargo_bin("hx")
+        .unwrap()
+        .args(["-t0", "--files-from", "-"])
+        .write_stdin(list)
+        .assert()
+        .success();
+    Command::cargo_bin("hx")
+        .unwrap()
+        .args(["poke", "--yes"])
+        .arg(&input)
+        .args(["--at", "1", "--type", "u8", "--value", "0x21"])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&input).unwrap(), b"h!");
+    let mut carve = b"0:1=".to_vec();
+    carve.extend_from_slice(output.as_os_str().as_bytes());
+    Command::cargo_bin("hx")
+        .unwrap()
+        .arg("--carve")
+        .arg(std::ffi::OsStr::from_bytes(&carve))
+        .arg(&input)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&output).unwrap(), b"h");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_dedupe_report() {
+    let dir = env::temp_dir().join(format!("hx-dedupe-report-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    for (name, data) in [("a", "abc"), ("b", "abd"), ("c", "xyzw"), ("sub/d", "abc")] {
+        std::fs::write(dir.join(name), data).unwrap();
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--recursive")
+        .arg(&dir)
+        .arg("--dedupe-report")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    assert_eq!(
+        stdout,
+        format!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  size 3, 2 files\n  {}\n  {}\n\n   files: 4\n  groups: 1\n  copies: 1\n   bytes: 3\n",
+            path("a"),
+            path("sub/d")
+        )
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--dedupe-report", "tests/files/tiny.txt"])
+        .assert()
+        .failure();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_file_list() {
+    assert_eq!(
+        split_file_list(b"a.bin\nb c.bin\n\n"),
+        vec![PathBuf::from("a.bin"), PathBuf::from("b c.bin")]
+    );
+    assert_eq!(
+        split_file_list(b"a\nb.bin\0c.bin\0"),
+        vec![PathBuf::from("a\nb.bin"), PathBuf::from("c.bin")]
+    );
+    assert!(split_file_list(b"").is_empty());
+}
+
+#[test]
+fn test_cli_files_from_stdin() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--files-from")
+        .arg("-")
+        .arg("-t0")
+        .arg("-c4")
+        .write_stdin("tests/files/tiny.txt\0tests/files/tiny.txt\0")
+        .assert();
+    assert.success().code(0).stdout(
+        "==> tests/files/tiny.txt <==\n0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n\n\
+         ==> tests/files/tiny.txt <==\n0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n",
+    );
+}
+
+#[test]
+fn test_cli_continuous_offsets() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "--files-from",
+            "-",
+            "-t0",
+            "-c4",
+            "--continuous-offsets",
+            "-s1",
+        ])
+        .write_stdin("tests/files/tiny.txt\0tests/files/tiny.txt\0")
+        .assert();
+    assert.success().code(0).stdout(
+        "==> tests/files/tiny.txt at 0x000000 <==\n0x000001: 0x6c 0x0a           l.\n   bytes: 2\n\n\
+         ==> tests/files/tiny.txt at 0x000003 <==\n0x000004: 0x6c 0x0a           l.\n   bytes: 2\n",
+    );
+}
+
+#[test]
+fn test_cli_len_scope() {
+    let files = "tests/files/tiny.txt\0tests/files/tiny.txt\0tests/files/tiny.txt\0";
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "--files-from",
+            "-",
+            "-t0",
+            "-c4",
+            "-l4",
+            "--len-scope",
+            "total",
+        ])
+        .write_stdin(files)
+        .assert();
+    assert.success().code(0).stdout(
+        "==> tests/files/tiny.txt <==\n0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n\n\
+         ==> tests/files/tiny.txt <==\n0x000000: 0x69                i\n   bytes: 1\n",
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "--files-from",
+            "-",
+            "-t0",
+            "-c4",
+            "-l2",
+            "--len-scope",
+            "file",
+        ])
+        .write_stdin(files)
+        .assert();
+    let stdout = String::from_utf8_lossy(&assert.success().get_output().stdout).into_owned();
+    assert_eq!(stdout.matches("   bytes: 2\n").count(), 3);
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--len-scope", "total", "tests/files/tiny.txt"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_files_from_missing_file() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--files-from")
+        .arg("-")
+        .arg("-t0")
+        .write_stdin("missing-file\ntests/files/tiny.txt\n")
+        .assert();
+    assert.failure().code(1);
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", "anything.bin"));
+    assert!(glob_match("*.bin", "fw.bin"));
+    assert!(!glob_match("*.bin", "fw.bin.bak"));
+    assert!(glob_match("fw-??.img", "fw-01.img"));
+    assert!(!glob_match("fw-??.img", "fw-1.img"));
+    assert!(glob_match("*.[ch]", "main.c"));
+    assert!(!glob_match("*.[!ch]", "main.h"));
+    assert!(glob_match("[a-c]*", "boot"));
+    assert!(glob_match("*a*b*", "xxaxxbxx"));
+    assert!(glob_match("files/*.txt", "files/tiny.txt"));
+}
+
+#[test]
+fn test_cli_recursive_glob() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--recursive")
+        .arg("tests")
+        .arg("--glob")
+        .arg("t*.txt")
+        .arg("-t0")
+        .arg("-c4")
+        .arg("-l2")
+        .assert();
+    assert
+        .success()
+        .code(0)
+        .stdout("==> tests/files/tiny.txt <==\n0x000000: 0x69 0x6c           il\n   bytes: 2\n");
+}
+
+/// Single-member ustar archive.
+#[cfg(feature = "archives")]
+fn tar_with(name: &str, data: &[u8]) -> Vec<u8> {
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+    header[156] = b'0';
+    header[257..262].copy_from_slice(b"ustar");
+    let mut tar = header.to_vec();
+    tar.extend_from_slice(data);
+    tar.resize(512 + data.len().div_ceil(512) * 512 + 1024, 0);
+    tar
+}
+
+/// Single-member zip archive with a stored (uncompressed) member.
+#[cfg(feature = "archives")]
+fn zip_with(name: &str, data: &[u8]) -> Vec<u8> {
+    let (name_len, size) = (name.len() as u16, data.len() as u32);
+    let mut zip = b"PK\x03\x04".to_vec();
+    zip.extend_from_slice(&[0; 14]);
+    zip.extend_from_slice(&size.to_le_bytes());
+    zip.extend_from_slice(&size.to_le_bytes());
+    zip.extend_from_slice(&name_len.to_le_bytes());
+    zip.extend_from_slice(&[0; 2]);
+    zip.extend_from_slice(name.as_bytes());
+    zip.extend_from_slice(data);
+    let central = zip.len() as u32;
+    zip.extend_from_slice(b"PK\x01\x02");
+    zip.extend_from_slice(&[0; 16]);
+    zip.extend_from_slice(&size.to_le_bytes());
+    zip.extend_from_slice(&size.to_le_bytes());
+    zip.extend_from_slice(&name_len.to_le_bytes());
+    zip.extend_from_slice(&[0; 16]);
+    zip.extend_from_slice(name.as_bytes());
+    let central_len = zip.len() as u32 - central;
+    zip.extend_from_slice(b"PK\x05\x06");
+    zip.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+    zip.extend_from_slice(&central_len.to_le_bytes());
+    zip.extend_from_slice(&central.to_le_bytes());
+    zip.extend_from_slice(&[0; 2]);
+    zip
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn test_seekable_zstd() {
+    use crate::formats::OutputFormat;
+    use crate::seekable::{ArchiveFormat, FRAME};
+    use std::io::{Cursor, Seek, SeekFrom};
+    let data: Vec<u8> = (0..FRAME * 2 + 100).map(|i| (i * 7 % 251) as u8).collect();
+    let mut archive = Vec::new();
+    let mut format = ArchiveFormat::default();
+    for chunk in data.chunks(4096) {
+        let mut line = Line::new();
+        line.hex_body = chunk.to_vec();
+        format.line(&mut archive, &line).unwrap();
+    }
+    format.end(&mut archive, data.len() as u64).unwrap();
+    assert!(archive.len() < data.len());
+    let mut reader = ArchiveReader::new(Cursor::new(archive)).unwrap();
+    assert_eq!(reader.len(), data.len() as u64);
+    let mut buf = [0; 16];
+    reader.seek(SeekFrom::Start(FRAME as u64 * 2 - 8)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, data[FRAME * 2 - 8..FRAME * 2 + 8]);
+    reader.seek(SeekFrom::Start(0)).unwrap();
+    let mut all = Vec::new();
+    reader.read_to_end(&mut all).unwrap();
+    assert_eq!(all, data);
+    assert!(ArchiveReader::new(Cursor::new(data)).is_err());
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn test_cli_zst() {
+    let dir = env::temp_dir().join(format!("hx-zst-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let archive = dir.join("count.zst");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--output", "zst", "--generate", "count:0..255"])
+        .assert()
+        .success();
+    fs::write(&archive, &assert.get_output().stdout).unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--zst", "-t0", "-s", "0x41", "-l", "3"])
+        .arg(&archive)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000041: 0x41 0x42 0x43"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--zst", "--raw"])
+        .arg(&archive)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, (0..=255).collect::<Vec<u8>>());
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--zst").arg("Cargo.toml").assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("not a seekable zstd archive"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "archives")]
+fn test_cli_archive_member_tar() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--member")
+        .arg("fw/boot.bin")
+        .arg("-t0")
+        .arg("-c4")
+        .write_stdin(tar_with("fw/boot.bin", b"il\n"))
+        .assert();
+    assert
+        .success()
+        .code(0)
+        .stdout("0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n");
+}
+
+#[test]
+#[cfg(feature = "archives")]
+fn test_cli_archive_list_zip() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--member")
+        .write_stdin(zip_with("fw/boot.bin", b"il\n"))
+        .assert();
+    assert
+        .success()
+        .code(0)
+        .stdout("           3  fw/boot.bin\n");
+}
+
+#[test]
+#[cfg(feature = "archives")]
+fn test_cli_archive_missing_member() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--member")
+        .arg("missing.bin")
+        .write_stdin(zip_with("fw/boot.bin", b"il\n"))
+        .assert();
+    assert.failure().code(1);
+}
+
+/// Two page sqlite database with 512 byte pages, page 2 a leaf table page.
+fn sqlite_db() -> Vec<u8> {
+    let mut db = vec![0u8; 1024];
+    db[..16].copy_from_slice(b"SQLite format 3\0");
+    db[16..18].copy_from_slice(&512u16.to_be_bytes());
+    db[28..32].copy_from_slice(&2u32.to_be_bytes());
+    db[100] = 13;
+    db[512..520].copy_from_slice(&[13, 0, 0, 0, 1, 0x01, 0xf4, 0]);
+    db
+}
+
+#[test]
+fn test_sqlite_page_header() {
+    let db = sqlite_db();
+    let (page_size, page) = crate::sqlite::read_page(&mut &db[..], 2).unwrap();
+    assert_eq!(page_size, 512);
+    assert_eq!(page.len(), 512);
+    let header = crate::sqlite::page_header(&page, 2).unwrap();
+    assert_eq!(header.cells, 1);
+    assert_eq!(header.content_start, 500);
+    assert_eq!(header.right_most, None);
+    assert!(crate::sqlite::read_page(&mut &db[..], 3).is_err());
+    assert!(crate::sqlite::read_page(&mut &b"not a database"[..], 1).is_err());
+}
+
+#[test]
+fn test_cli_sqlite_page() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--sqlite-page")
+        .arg("2")
+        .arg("-t0")
+        .arg("-c8")
+        .arg("-l8")
+        .write_stdin(sqlite_db())
+        .assert();
+    assert.success().code(0).stdout(
+        "           page: 2 at 0x000200 (512 bytes)\n\
+         \x20          type: 13 leaf table b-tree\n\
+         first freeblock: 0\n\
+         \x20         cells: 1\n\
+         \x20 content start: 500\n\
+         \x20    fragmented: 0\n\
+         0x000000: 0x0d 0x00 0x00 0x00 0x01 0x01 0xf4 0x00 ........\n   bytes: 8\n",
+    );
+}
+
+/// Little endian microsecond pcap with one packet per payload, one second apart.
+fn pcap_with(payloads: &[&[u8]]) -> Vec<u8> {
+    let mut pcap = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0];
+    pcap.extend_from_slice(&[0; 8]);
+    pcap.extend_from_slice(&65535u32.to_le_bytes());
+    pcap.extend_from_slice(&1u32.to_le_bytes());
+    for (i, payload) in payloads.iter().enumerate() {
+        pcap.extend_from_slice(&(1_700_000_000 + i as u32).to_le_bytes());
+        pcap.extend_from_slice(&250u32.to_le_bytes());
+        pcap.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        pcap.extend_from_slice(&(payload.len() as u32 + 10).to_le_bytes());
+        pcap.extend_from_slice(payload);
+    }
+    pcap
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(
+        crate::pcap::format_timestamp(0, 0, 0),
+        "1970-01-01 00:00:00"
+    );
+    assert_eq!(
+        crate::pcap::format_timestamp(1_700_000_000, 123_456_000, 6),
+        "2023-11-14 22:13:20.123456"
+    );
+    assert_eq!(
+        crate::pcap::format_timestamp(951_782_400, 5, 9),
+        "2000-02-29 00:00:00.000000005"
+    );
+}
+
+#[test]
+fn test_pcapng_enhanced_packet() {
+    // section header, interface with nanosecond resolution, one packet
+    let mut ng = vec![0x0a, 0x0d, 0x0d, 0x0a, 28, 0, 0, 0, 0x4d, 0x3c, 0x2b, 0x1a];
+    ng.extend_from_slice(&[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    ng.extend_from_slice(&28u32.to_le_bytes());
+    ng.extend_from_slice(&[1, 0, 0, 0, 28, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+    ng.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 28, 0, 0, 0]);
+    ng.extend_from_slice(&[6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0]);
+    ng.extend_from_slice(&0u32.to_le_bytes());
+    ng.extend_from_slice(&1_000_000_007u32.to_le_bytes());
+    ng.extend_from_slice(&[3, 0, 0, 0, 3, 0, 0, 0]);
+    ng.extend_from_slice(b"il\n\0");
+    ng.extend_from_slice(&36u32.to_le_bytes());
+
+    let mut reader = crate::pcap::PcapReader::new(&ng[..]).unwrap();
+    let packet = reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.index, 1);
+    assert_eq!((packet.seconds, packet.nanos, packet.digits), (1, 7, 9));
+    assert_eq!(packet.data, b"il\n");
+    assert!(reader.next_packet().unwrap().is_none());
+}
+
+#[test]
+fn test_parse_packets_range() {
+    let config = parse_args(&["--pcap", "--packets", "2-"]).unwrap();
+    let range = config.packets.unwrap();
+    assert!(!range.contains(1) && range.contains(2) && range.contains(99));
+    assert!(parse_args(&["--pcap", "--packets", "0"]).is_err());
+    assert!(parse_args(&["--pcap", "--packets", "5-3"]).is_err());
+    assert!(parse_args(&["--packets", "3"]).is_err());
+}
+
+#[test]
+fn test_cli_pcap_packets() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--pcap")
+        .arg("--packets")
+        .arg("2-3")
+        .arg("-t0")
+        .arg("-c4")
+        .write_stdin(pcap_with(&[b"one", b"il\n", b"xy"]))
+        .assert();
+    assert.success().code(0).stdout(
+        "==> packet 2, 2023-11-14 22:13:21.000250, 3 of 13 bytes <==\n\
+         0x000000: 0x69 0x6c 0x0a      il.\n   bytes: 3\n\n\
+         ==> packet 3, 2023-11-14 22:13:22.000250, 2 of 12 bytes <==\n\
+         0x000000: 0x78 0x79           xy\n   bytes: 2\n",
+    );
+}
+
+/// Reader that hands out at most three bytes per read, like a slow pipe.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.len().min(buf.len()).min(3);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_discard_pipe() {
+    let mut pipe = Trickle(b"0123456789");
+    assert_eq!(discard(&mut pipe, 7).unwrap(), 7);
+    assert_eq!(pipe.0, b"789");
+    assert_eq!(discard(&mut pipe, 7).unwrap(), 3);
+}
+
+#[test]
+fn test_sqlite_pcap_pipe() {
+    let db = sqlite_db();
+    let (_, page) = crate::sqlite::read_page(&mut Trickle(&db), 2).unwrap();
+    assert_eq!(page, &db[512..]);
+    let capture = pcap_with(&[b"one", b"xy"]);
+    let mut reader = crate::pcap::PcapReader::new(Trickle(&capture)).unwrap();
+    assert_eq!(reader.next_packet().unwrap().unwrap().data, b"one");
+    assert_eq!(reader.next_packet().unwrap().unwrap().data, b"xy");
+}
+
+#[test]
+fn test_resume_point() {
+    let dump = b"0x000000: 68 78 00 7f hx..\n0x000004: 80 ff 0a      ...\n   by";
+    let point = crate::resume::resume_point(dump, false, 4, 0).unwrap();
+    assert_eq!((point.keep, point.rows, point.complete), (55, 2, false));
+    let point = crate::resume::resume_point(b"0x000000: 68 78", false, 4, 0).unwrap();
+    assert_eq!((point.keep, point.rows), (0, 0));
+    assert!(crate::resume::resume_point(dump, false, 8, 0).is_err());
+
+    let array = b"a = [\n    0x68, 0x78, \n    0x00, 0x7f, \n    0x80";
+    let point = crate::resume::resume_point(array, true, 2, 0).unwrap();
+    assert_eq!((point.header, point.rows, point.complete), (true, 2, false));
+    let point = crate::resume::resume_point(b"a = [\n    0x68\n]\n", true, 2, 0).unwrap();
+    assert!(point.complete);
+}
+
+#[test]
+fn test_cli_also() {
+    let dir = env::temp_dir().join(format!("hx-also-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (json, raw) = (dir.join("out.json"), dir.join("out.bin"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-ac", "-c4", "-l6", "--also", "dump=/dev/stderr"])
+        .arg(format!("--also=json={}", json.display()))
+        .arg(format!("--also=raw={}", raw.display()))
+        .write_stdin("hello, world")
+        .assert()
+        .success();
+    let output = assert.get_output();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("unsigned char ARRAY[6] = {\n"));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "0x000000: 0x68 0x65 0x6c 0x6c hell\n0x000004: 0x6f 0x2c           o,\n   bytes: 6\n"
+    );
+    let json = fs::read_to_string(&json).unwrap();
+    assert_eq!(json.lines().count(), 2);
+    assert!(json.starts_with("{\"offset\": 0, \"bytes\": 4, \"hex\": \"68656c6c\""));
+    assert_eq!(fs::read(&raw).unwrap(), b"hello,");
+    assert!(parse_args(&["--also", "dump"]).is_err());
+    assert!(parse_args(&["--also", "pdf=out.pdf"]).is_err());
+    assert_eq!(
+        parse_args(&["--also", "array=a.rs"]).unwrap().also,
+        [("array".to_string(), "a.rs".to_string())]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_outfile_resume() {
+    for (name, mode) in [("dump", "-t0"), ("array", "-ap")] {
+        let path = env::temp_dir().join(format!("hx-resume-{}-{name}", std::process::id()));
+        let path_arg = path.to_str().unwrap();
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args([mode, "-c4", "--outfile", path_arg, "tests/files/lorem.md"])
+            .assert()
+            .success()
+            .stdout("");
+        let full = std::fs::read(&path).unwrap();
+
+        // cut the output mid-line, as an interrupted run would leave it
+        std::fs::write(&path, &full[..full.len() / 2]).unwrap();
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args([mode, "-c4", "--outfile", path_arg, "--resume"])
+            .arg("tests/files/lorem.md")
+            .assert()
+            .success();
+        assert_eq!(std::fs::read(&path).unwrap(), full, "{name}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn test_sample_pick() {
+    let picked = crate::sample::pick(100, 10, 42);
+    assert_eq!(picked.len(), 10);
+    assert!(picked.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(picked.iter().all(|index| *index < 100));
+    assert_eq!(picked, crate::sample::pick(100, 10, 42));
+    assert_ne!(picked, crate::sample::pick(100, 10, 43));
+    assert_eq!(crate::sample::pick(3, 10, 42), vec![0, 1, 2]);
+
+    let data: Vec<u8> = (0..=255).collect();
+    let windows = crate::sample::sample_stream(&mut &data[..], 16, 4, 42).unwrap();
+    assert_eq!(windows.len(), 4);
+    for (index, window) in windows.iter() {
+        assert_eq!(window[0] as u64, index * 16);
+    }
+}
+
+#[test]
+fn test_cli_sample_seed() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--sample", "2", "--seed", "7", "-l8", "-c8", "-t0"])
+        .arg("tests/files/lorem.md")
+        .assert();
+    assert.success().code(0).stdout(
+        "==> sample 1 of 2, 0x000000 <==\n\
+         0x000000: 0x23 0x20 0x4c 0x6f 0x72 0x65 0x6d 0x20 # Lorem \n   bytes: 8\n\n\
+         ==> sample 2 of 2, 0x000030 <==\n\
+         0x000030: 0x63 0x74 0x65 0x74 0x75 0x72 0x20 0x61 ctetur a\n   bytes: 8\n",
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["--sample", "0", "tests/files/lorem.md"]).assert();
+    assert.failure().code(2).stdout("");
+}
+
+#[test]
+fn test_parse_skip() {
+    assert_eq!(parse_args(&["-s", "0x1f3"]).unwrap().skip, 0x1f3);
+    assert_eq!(parse_args(&["--skip", "499"]).unwrap().skip, 499);
+    assert!(parse_args(&["--skip", "0xzz"]).is_err());
+    assert!(parse_args(&["--skip", "-1"]).is_err());
+}
+
+#[test]
+fn test_cli_skip_align() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c8", "-s0x5", "-l12", "tests/files/lorem.md"])
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000005: 0x65 0x6d 0x20 0x49 0x70 0x73 0x75 0x6d em Ipsum\n\
+         0x00000d: 0x0a 0x0a 0x4c 0x6f                     ..Lo\n   bytes: 12\n",
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "-c8",
+            "-s0x5",
+            "-l12",
+            "--align",
+            "tests/files/lorem.md",
+        ])
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000000:                          0x65 0x6d 0x20      em \n\
+         0x000008: 0x49 0x70 0x73 0x75 0x6d 0x0a 0x0a 0x4c Ipsum..L\n\
+         0x000010: 0x6f                                    o\n   bytes: 12\n",
+    );
+}
+
+#[test]
+fn test_cli_unique_lines() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--unique-lines", "-c4", "-t0"])
+        .write_stdin("AAAABBBBAAAACCCCAAAABBBBxy")
+        .assert();
+    assert.success().code(0).stdout(
+        "      3x 0x000000: 0x41 0x41 0x41 0x41 AAAA\n\
+         \x20     2x 0x000004: 0x42 0x42 0x42 0x42 BBBB\n\
+         \x20     1x 0x00000c: 0x43 0x43 0x43 0x43 CCCC\n\
+         \x20     1x 0x000018: 0x78 0x79           xy\n\
+         \x20  bytes: 26\n  unique: 4 of 7 lines\n",
+    );
+}
+
+#[test]
+fn test_dedupe_scan() {
+    let mut data = b"ABCDEFGH12345678xxABCDEFGH12345678".to_vec();
+    data.extend_from_slice(&[0; 32]);
+    let found = crate::dedupe::dedupe_scan(&data, 8);
+    assert_eq!(
+        found,
+        vec![
+            crate::dedupe::Duplicate {
+                offset: 0x12,
+                len: 16,
+                source: 0
+            },
+            crate::dedupe::Duplicate {
+                offset: 0x2a,
+                len: 24,
+                source: 0x22
+            },
+        ]
+    );
+    assert!(crate::dedupe::dedupe_scan(b"no repeats here", 4).is_empty());
+}
+
+#[test]
+fn test_cli_dedupe_scan() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--dedupe-scan", "8"])
+        .write_stdin("ABCDEFGH12345678xxABCDEFGH12345678")
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000012-0x000021: 16 bytes, same as 0x000000-0x00000f\n\
+         \x20  bytes: 34\n repeats: 1 ranges, 16 bytes\n",
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--dedupe-scan", "0"]).assert().failure().code(1);
+}
+
+#[test]
+fn test_scalar_decode_bits() {
+    assert_eq!(
+        crate::scalar::decode(&[0xef, 0xbe, 0xad, 0xde], Endian::Little),
+        0xdeadbeef
+    );
+    assert_eq!(crate::scalar::decode(&[0xde, 0xad], Endian::Big), 0xdead);
+    assert_eq!(crate::scalar::bit_field(0xdeadbeef, 4, 9), 0x2e);
+    assert_eq!(crate::scalar::bit_field(u64::MAX, 0, 63), u64::MAX);
+    assert_eq!(ScalarType::I8.display(0xff), "-1");
+    assert_eq!(ScalarType::F32.display(0x3fc0_0000), "1.5");
+}
+
+#[test]
+fn test_parse_extract() {
+    let config = parse_args(&[
+        "extract", "fw.bin", "--at", "0x40", "--type", "u32", "--bits", "4:9",
+    ])
+    .unwrap();
+    assert_eq!(
+        config.mode,
+        Mode::Extract {
+            path: PathBuf::from("fw.bin"),
+            spec: ScalarSpec {
+                at: 0x40,
+                kind: ScalarType::U32,
+                endian: Endian::Little
+            },
+            bits: Some((4, 9)),
+        }
+    );
+    assert!(
+        parse_args(&["extract", "fw.bin", "--at", "0", "--type", "u8", "--bits", "8"]).is_err()
+    );
+    assert!(parse_args(&["extract", "fw.bin", "--at", "0", "--bits", "5:4"]).is_err());
+    assert!(parse_args(&["extract", "fw.bin"]).is_err());
+}
+
+#[test]
+fn test_bits() {
+    use crate::scalar::{parse_layout, BitField};
+    let field = |high, low, name: &str| BitField {
+        high,
+        low,
+        name: name.to_string(),
+    };
+    assert_eq!(
+        parse_layout("7:4 hi, 1 en", ScalarType::U8).unwrap(),
+        vec![
+            field(7, 4, "hi"),
+            field(3, 2, ""),
+            field(1, 1, "en"),
+            field(0, 0, "")
+        ]
+    );
+    assert_eq!(
+        parse_layout("0:3 lo", ScalarType::U8).unwrap()[1],
+        field(3, 0, "lo")
+    );
+    assert!(parse_layout("8 flag", ScalarType::U8).is_err());
+    assert!(parse_layout("7:4 a,5 b", ScalarType::U8).is_err());
+    assert!(parse_layout("7:4", ScalarType::U8).is_err());
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["bits", "tests/files/lorem.md", "--at", "2", "--type", "u16"])
+        .args(["--endian", "big", "--layout", "15:8 hi,3:0 lo"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "\x20     at: 0x000002\n\
+         \x20   type: u16 big\n\
+         \x20    raw: 0x4c6f\n\
+         +------+-----+-----+\n\
+         | 15:8 | 7:4 | 3:0 |\n\
+         |  hi  |  -  | lo  |\n\
+         +------+-----+-----+\n\
+         | 0x4c | 0x6 | 0xf |\n\
+         |  76  |  6  | 15  |\n\
+         +------+-----+-----+\n"
+    );
+}
+
+#[test]
+fn test_sum() {
+    let data = b"\x01\x00\x02\x00\xff\xff\x10";
+    let totals =
+        crate::scalar::totals(&mut &data[..], &[], ScalarType::U16, Endian::Little).unwrap();
+    assert_eq!(totals.count, 3);
+    assert_eq!(totals.sum, 65538);
+    assert_eq!(totals.min, Some((1, 0)));
+    assert_eq!(totals.max, Some((0xffff, 4)));
+    assert_eq!(totals.trailing, 1);
+    let totals = crate::scalar::totals(
+        &mut &data[..],
+        &[(0, 3), (4, 6)],
+        ScalarType::I16,
+        Endian::Little,
+    )
+    .unwrap();
+    assert_eq!((totals.count, totals.sum, totals.trailing), (2, -1 + 1, 1));
+    assert_eq!(totals.mean(), Some(0.0));
+    assert_eq!(
+        ScalarType::parse_ordered("u16be"),
+        Some((ScalarType::U16, Some(Endian::Big)))
+    );
+    assert_eq!(
+        ScalarType::parse_ordered("f64"),
+        Some((ScalarType::F64, None))
+    );
+    assert_eq!(ScalarType::parse_ordered("u17le"), None);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "sum",
+            "tests/files/lorem.md",
+            "--type",
+            "u16be",
+            "--range",
+            "0:4",
+        ])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "    type: u16 big\n  values: 2\n     sum: 28559 (0x6f8f, 0x6f8f as u16)\n     min: 8992 at 0x000000\n     max: 19567 at 0x000002\n    mean: 14279.5\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["sum", "tests/files/lorem.md", "--type", "u24"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_extract() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "extract",
+            "tests/files/lorem.md",
+            "--at",
+            "2",
+            "--type",
+            "u16",
+        ])
+        .args(["--endian", "big"])
+        .assert();
+    assert.success().code(0).stdout(
+        "      at: 0x000002\n    type: u16 big\n     raw: 0x4c6f\n     dec: 19567\n\
+         \x20    hex: 0x4c6f\n     oct: 0o46157\n     bin: 0b100110001101111\n",
+    );
+}
+
+#[test]
+fn test_parse_scalar_value() {
+    use crate::scalar::{encode, parse_scalar};
+    assert_eq!(parse_scalar("0xdeadbeef", ScalarType::U32), Ok(0xdeadbeef));
+    assert_eq!(parse_scalar("-2", ScalarType::I16), Ok(0xfffe));
+    assert_eq!(parse_scalar("-128", ScalarType::I8), Ok(0x80));
+    assert_eq!(parse_scalar("0xff", ScalarType::I8), Ok(0xff));
+    assert_eq!(parse_scalar("1.5", ScalarType::F32), Ok(0x3fc0_0000));
+    assert!(parse_scalar("128", ScalarType::I8).is_err());
+    assert!(parse_scalar("-129", ScalarType::I8).is_err());
+    assert!(parse_scalar("256", ScalarType::U8).is_err());
+    assert!(parse_scalar("-1", ScalarType::U32).is_err());
+    assert_eq!(encode(0xdeadbeef, 4, Endian::Big), [0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(
+        encode(0xdeadbeef, 4, Endian::Little),
+        [0xef, 0xbe, 0xad, 0xde]
+    );
+}
+
+#[test]
+fn test_parse_poke() {
+    let config = parse_args(&[
+        "poke", "fw.bin", "--at", "0x40", "--type", "i16", "--value", "-2",
+    ])
+    .unwrap();
+    assert_eq!(
+        config.mode,
+        Mode::Poke {
+            path: PathBuf::from("fw.bin"),
+            spec: ScalarSpec {
+                at: 0x40,
+                kind: ScalarType::I16,
+                endian: Endian::Little
+            },
+            value: 0xfffe,
+            guard: WriteGuard::default(),
+        }
+    );
+    assert!(parse_args(&["poke", "fw.bin", "--at", "0", "--value", "300"]).is_err());
+    assert!(parse_args(&["poke", "fw.bin", "--at", "0"]).is_err());
+}
+
+#[test]
+fn test_cli_poke() {
+    let path = env::temp_dir().join(format!("hx-poke-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    std::fs::write(&path, [0u8; 8]).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-c4", "-t0", "poke", "--yes", path_arg, "--at", "2", "--type", "u32",
+        ])
+        .args(["--endian", "big", "--value", "0xdeadbeef"])
+        .assert();
+    assert.success().code(0).stdout(
+        "  before 0x000000: 0x00 0x00 0x00 0x00 ....\n\
+         \x20 before 0x000004: 0x00 0x00 0x00 0x00 ....\n\
+         \x20  after 0x000000: 0x00 0x00 0xde 0xad ....\n\
+         \x20  after 0x000004: 0xbe 0xef 0x00 0x00 ....\n",
+    );
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        [0, 0, 0xde, 0xad, 0xbe, 0xef, 0, 0]
+    );
+
+    // patches never grow the file
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args([
+        "poke", "--yes", path_arg, "--at", "6", "--type", "u32", "--value", "1",
+    ])
+    .assert()
+    .failure();
+    // an offset whose end doesn't fit in 64 bits is past the end too
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["poke", "--yes", path_arg, "--at", "18446744073709551615"])
+        .args(["--type", "u64", "--value", "1"])
+        .assert()
+        .failure()
+        .code(1);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("runs past the end of the file"), "{stderr}");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_parse_write_guard() {
+    let guard = |args: &[&str]| match parse_args(args).map(|config| config.mode) {
+        Ok(Mode::Poke { guard, .. }) => Some(guard),
+        _ => None,
+    };
+    let poke = ["poke", "fw.bin", "--at", "0", "--value", "1"];
+    assert_eq!(guard(&poke), Some(WriteGuard::default()));
+    assert_eq!(
+        guard(&[&poke[..], &["--backup", "--atomic"]].concat()),
+        Some(WriteGuard {
+            backup: Some(".bak".to_string()),
+            no_clobber: false,
+            atomic: true,
+            journal: None,
+            reverse_patch: None,
+            yes: false,
+        })
+    );
+    assert_eq!(
+        guard(&[&poke[..], &["--backup=.orig", "--no-clobber"]].concat()),
+        Some(WriteGuard {
+            backup: Some(".orig".to_string()),
+            no_clobber: true,
+            atomic: false,
+            journal: None,
+            reverse_patch: None,
+            yes: false,
+        })
+    );
+    assert_eq!(
+        guard(&[&poke[..], &["--yes"]].concat()),
+        Some(WriteGuard {
+            yes: true,
+            ..WriteGuard::default()
+        })
+    );
+    // nothing to clobber without a backup
+    assert_eq!(guard(&[&poke[..], &["--no-clobber"]].concat()), None);
+}
+
+#[test]
+fn test_cli_confirm() {
+    assert_eq!(
+        patch::describe(
+            Path::new("fw.bin"),
+            &[(0x10, vec![0; 4]), (0x40, vec![0; 2])]
+        ),
+        "write 6 bytes to fw.bin at 0x000010:0x000014, 0x000040:0x000042"
+    );
+    let edits: Vec<(u64, Vec<u8>)> = (0..10).map(|at| (at * 2, vec![0])).collect();
+    assert!(patch::describe(Path::new("fw.bin"), &edits).ends_with(", 0x00000e:0x00000f, 2 more"));
+    let path = env::temp_dir().join(format!("hx-confirm-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    std::fs::write(&path, [0u8; 8]).unwrap();
+    // not run from a terminal, every change needs --yes
+    for args in [
+        &["poke", path_arg, "--at", "2", "--value", "1"][..],
+        &["move", path_arg, "--from", "0", "--len", "2", "--to", "4"],
+        &["resize", path_arg, "--to", "4"],
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).assert().failure().code(1);
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+        assert!(stderr.contains("without --yes"), "{stderr}");
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["resize", path_arg, "--to", "4"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains(&format!(
+        "refusing to truncate {path_arg} from 8 to 4 bytes, dropping 0x000004:0x000008"
+    )));
+    assert_eq!(std::fs::read(&path).unwrap(), [0u8; 8]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cli_poke_backup() {
+    let path = env::temp_dir().join(format!("hx-guard-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let backup = env::temp_dir().join(format!("hx-guard-{}.orig", std::process::id()));
+    std::fs::write(&path, [1u8, 2, 3, 4]).unwrap();
+    let _ = std::fs::remove_file(&backup);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["poke", "--yes", path_arg, "--at", "1", "--value", "0xff"])
+        .args(["--backup=.orig", "--no-clobber", "--atomic"])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 0xff, 3, 4]);
+    assert_eq!(std::fs::read(&backup).unwrap(), [1, 2, 3, 4]);
+
+    // the first backup is kept and the file left alone
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["poke", "--yes", path_arg, "--at", "2", "--value", "0xff"])
+        .args(["--backup=.orig", "--no-clobber"])
+        .assert()
+        .failure();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 0xff, 3, 4]);
+    assert_eq!(std::fs::read(&backup).unwrap(), [1, 2, 3, 4]);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&backup).unwrap();
+}
+
+#[test]
+fn test_cli_move() {
+    let path = env::temp_dir().join(format!("hx-move-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["-c8", "-t0", "move", "--yes", path_arg])
+            .args(args)
+            .assert()
+    };
+
+    // an overlapping copy moves the original bytes
+    std::fs::write(&path, b"ABCDEFGHIJKLMNOP").unwrap();
+    run(&["--from", "2", "--len", "6", "--to", "4"])
+        .success()
+        .stdout(
+            "  before 0x000000: 0x41 0x42 0x43 0x44 0x45 0x46 0x47 0x48 ABCDEFGH\n\
+             \x20 before 0x000008: 0x49 0x4a 0x4b 0x4c 0x4d 0x4e 0x4f 0x50 IJKLMNOP\n\
+             \x20  after 0x000000: 0x41 0x42 0x43 0x44 0x43 0x44 0x45 0x46 ABCDCDEF\n\
+             \x20  after 0x000008: 0x47 0x48 0x4b 0x4c 0x4d 0x4e 0x4f 0x50 GHKLMNOP\n",
+        );
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCDCDEFGHKLMNOP");
+
+    // --clear fills what the copy leaves behind, overlapping or not
+    std::fs::write(&path, b"ABCDEFGHIJKLMNOP").unwrap();
+    run(&["--from", "4", "--len", "6", "--to", "1", "--clear", "0x2e"]).success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"AEFGHIJ...KLMNOP");
+    std::fs::write(&path, b"ABCDEFGHIJKLMNOP").unwrap();
+    run(&[
+        "--from", "0", "--len", "4", "--to", "end-4", "--clear", "0x2e",
+    ])
+    .success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"....EFGHIJKLABCD");
+
+    // neither end may run past the file
+    run(&["--from", "12", "--len", "6", "--to", "0"]).failure();
+    run(&["--from", "0", "--len", "6", "--to", "12"]).failure();
+    run(&["--from", "0", "--len", "0", "--to", "4"]).failure();
+    run(&["--from", "0", "--len", "18446744073709551615", "--to", "1"])
+        .failure()
+        .code(1);
+    run(&["--from", "1", "--len", "2", "--to", "18446744073709551615"])
+        .failure()
+        .code(1);
+    assert_eq!(std::fs::read(&path).unwrap(), b"....EFGHIJKLABCD");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cli_resize() {
+    let path = env::temp_dir().join(format!("hx-resize-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["resize", "--yes", path_arg]).args(args).assert()
+    };
+    std::fs::write(&path, b"ABCDEF").unwrap();
+
+    // the pattern lines up with offset 0, not the old end
+    run(&["--to", "12", "--fill", "de ad be ef"])
+        .success()
+        .stdout(format!("{path_arg}: 6 -> 12 bytes\n"));
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"ABCDEF\xbe\xef\xde\xad\xbe\xef"
+    );
+    run(&["--to", "end-8"]).success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCD");
+    run(&["--to", "end+2"]).success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCD\0\0");
+    run(&["--to", "2", "--fill", ""]).failure();
+    // the report goes where the other subcommands' go
+    run(&["--to", "5", "--quiet"]).success().stdout("");
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCD\0");
+    let assert = run(&["--to", "4", "--log-format", "json"])
+        .success()
+        .stdout("");
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("\"event\": \"resize\""), "{stderr}");
+
+    // truncating and extending are journaled and undone like any edit
+    let reverse = env::temp_dir().join(format!("hx-resize-{}.patch", std::process::id()));
+    let reverse_arg = reverse.to_str().unwrap();
+    let journal = env::temp_dir().join(format!("hx-resize-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&journal);
+    std::fs::write(&path, b"ABCDEF").unwrap();
+    let journal_arg = journal.to_str().unwrap();
+    run(&[
+        "--to",
+        "3",
+        "--atomic",
+        "--reverse-patch",
+        reverse_arg,
+        "--journal",
+        journal_arg,
+    ])
+    .success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABC");
+    assert_eq!(
+        std::fs::read_to_string(&reverse).unwrap(),
+        format!("# hx patch\nfile {path_arg}\n0x000003 - +444546\n")
+    );
+    let log = std::fs::read_to_string(&journal).unwrap();
+    assert!(
+        log.contains("\"offset\":3,\"old\":\"444546\",\"new\":\"\""),
+        "{log}"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["undo", "--yes", reverse_arg]).assert().success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCDEF");
+    run(&[
+        "--to",
+        "8",
+        "--fill",
+        "0x2e",
+        "--reverse-patch",
+        reverse_arg,
+    ])
+    .success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCDEF..");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["undo", "--yes", reverse_arg]).assert().success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCDEF");
+    // the file is no longer as long as the patch expects
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["undo", "--yes", reverse_arg]).assert().failure();
+    assert_eq!(std::fs::read(&path).unwrap(), b"ABCDEF");
+    std::fs::remove_file(&reverse).unwrap();
+    std::fs::remove_file(&journal).unwrap();
+    assert!(matches!(
+        parse_args(&["resize", "fw.bin", "--to", "1MiB", "--fill", "0xff"]).map(|c| c.mode),
+        Ok(Mode::Resize {
+            size: 0x10_0000,
+            ..
+        })
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_parse_read_only() {
+    assert!(matches!(
+        parse_args(&["--read-only", "poke", "fw.bin", "--at", "0", "--value", "1"]),
+        Err(HexError::ReadOnly(CMD_POKE))
+    ));
+    assert!(matches!(
+        parse_args(&["poke", "fw.bin", "--at", "0", "--value", "1", "--read-only"]),
+        Err(HexError::ReadOnly(CMD_POKE))
+    ));
+    assert!(matches!(
+        parse_args(&[
+            "--read-only",
+            "move",
+            "fw.bin",
+            "--from",
+            "0",
+            "--len",
+            "1",
+            "--to",
+            "1"
+        ]),
+        Err(HexError::ReadOnly(CMD_MOVE))
+    ));
+    // reading is always allowed
+    assert_eq!(
+        parse_args(&["--read-only", "fw.bin"]).unwrap().mode,
+        Mode::Dump
+    );
+    assert!(parse_args(&["--read-only", "extract", "fw.bin", "--at", "0"]).is_ok());
+}
+
+#[test]
+fn test_cli_read_only_env() {
+    let path = env::temp_dir().join(format!("hx-read-only-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    std::fs::write(&path, [0u8; 4]).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .env(READ_ONLY_ENV, "1")
+        .args(["poke", "--yes", path_arg, "--at", "0", "--value", "1"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("read-only mode"), "{stderr}");
+    assert_eq!(std::fs::read(&path).unwrap(), [0, 0, 0, 0]);
+
+    // neither overwriting an existing file nor patching it in place
+    let patch = env::temp_dir().join(format!("hx-read-only-{}.txt", std::process::id()));
+    std::fs::write(&patch, "0x000001: 0xff\n").unwrap();
+    for args in [
+        &["--reverse", "--absolute", "--outfile", path_arg][..],
+        &["--outfile", path_arg],
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .env(READ_ONLY_ENV, "1")
+            .args(args)
+            .arg(&patch)
+            .assert()
+            .failure();
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+        assert!(stderr.contains("read-only mode"), "{stderr}");
+        assert_eq!(std::fs::read(&path).unwrap(), [0, 0, 0, 0]);
+    }
+    // a new file is fine
+    let out = env::temp_dir().join(format!("hx-read-only-{}.out", std::process::id()));
+    let _ = std::fs::remove_file(&out);
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.env(READ_ONLY_ENV, "1")
+        .arg("--outfile")
+        .arg(&out)
+        .arg(&patch)
+        .assert()
+        .success();
+    std::fs::remove_file(&out).unwrap();
+    std::fs::remove_file(&patch).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.env(READ_ONLY_ENV, "0")
+        .args(["poke", "--yes", path_arg, "--at", "0", "--value", "1"])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 0, 0, 0]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_sha256() {
+    let digest = |data: &[u8]| {
+        let mut hasher = crate::hash::Sha256::new();
+        hasher.update(data);
+        crate::hash::to_hex(&hasher.finish())
+    };
+    assert_eq!(
+        digest(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        digest(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // two blocks once padded
+    assert_eq!(
+        digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+#[test]
+fn test_journal_line() {
+    let entry = crate::patch::JournalEntry {
+        file: "fw \"v2\".bin",
+        at: 64,
+        old: &[0, 0],
+        new: &[0xde, 0xad],
+        hash_before: "aa",
+        hash_after: "bb",
+    };
+    assert_eq!(
+        crate::patch::journal_line(&entry, 1_700_000_000),
+        "{\"time\":\"2023-11-14T22:13:20Z\",\"file\":\"fw \\\"v2\\\".bin\",\"offset\":64,\
+         \"old\":\"0000\",\"new\":\"dead\",\"sha256_before\":\"aa\",\"sha256_after\":\"bb\"}"
+    );
+}
+
+#[test]
+fn test_cli_poke_journal() {
+    let path = env::temp_dir().join(format!("hx-journal-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let journal = env::temp_dir().join(format!("hx-journal-{}.log", std::process::id()));
+    std::fs::write(&path, b"abc").unwrap();
+    let _ = std::fs::remove_file(&journal);
+
+    for value in ["0x62", "0x42"] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["poke", "--yes", path_arg, "--at", "1", "--value", value])
+            .args(["--journal", journal.to_str().unwrap()])
+            .assert()
+            .success();
+    }
+    let log = std::fs::read_to_string(&journal).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2);
+    // sha-256 of "abc" before the second edit and "aBc" after it
+    assert!(lines[0].contains("\"old\":\"62\",\"new\":\"62\""));
+    assert!(lines[1].contains(
+        "\"old\":\"62\",\"new\":\"42\",\
+         \"sha256_before\":\"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\""
+    ));
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&journal).unwrap();
+}
+
+#[test]
+fn test_patch_format() {
+    use crate::patch::{format_patch, parse_patch, Hunk};
+    let hunks = vec![
+        Hunk {
+            at: 0x40,
+            old: vec![0xde, 0xad],
+            new: vec![0, 0],
+        },
+        Hunk {
+            at: 2,
+            old: vec![1],
+            new: vec![0xff],
+        },
+    ];
+    let text = format_patch(Path::new("fw.bin"), &hunks);
+    assert_eq!(
+        text,
+        "# hx patch\nfile fw.bin\n0x000040 -dead +0000\n0x000002 -01 +ff\n"
+    );
+    assert_eq!(
+        parse_patch(&text).unwrap(),
+        (PathBuf::from("fw.bin"), hunks)
+    );
+    assert!(parse_patch("0x000002 -01 +ff\n").is_err());
+    assert!(parse_patch("file fw.bin\n0x000002 -01 +ffff\n").is_err());
+    // a side left empty truncates or extends the file
+    let (_, tail) = parse_patch("file fw.bin\n0x000002 - +ffff\n").unwrap();
+    assert!(tail[0].resizes() && tail[0].old.is_empty());
+    assert!(parse_patch("file fw.bin\n2 -01 +ff\n").is_err());
+}
+
+#[test]
+fn test_overlay() {
+    use crate::patch::{Hunk, Overlay};
+    let hunks = vec![
+        Hunk {
+            at: 1,
+            old: b"bc".to_vec(),
+            new: b"BC".to_vec(),
+        },
+        Hunk {
+            at: 5,
+            old: b"fg".to_vec(),
+            new: b"FG".to_vec(),
+        },
+    ];
+    // reads that split a hunk still show all of it
+    let mut overlay = Overlay::new(&b"abcdefg"[..], &hunks);
+    let mut shown = Vec::new();
+    let mut chunk = [0; 2];
+    loop {
+        let n = overlay.read(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        shown.extend_from_slice(&chunk[..n]);
+    }
+    assert_eq!(shown, b"aBCdeFG");
+
+    let path = env::temp_dir().join(format!("hx-overlay-{}", std::process::id()));
+    std::fs::write(&path, "file lorem.md\n0x000002 -4c6f +4c4f\n").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "-l8", "--overlay"])
+        .arg(&path)
+        .arg("tests/files/lorem.md")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "0x000000: 0x23 0x20 0x4c 0x4f # LO  # 0x000002 overlay -4c6f +4c4f\n\
+         0x000004: 0x72 0x65 0x6d 0x20 rem \n\
+         \x20  bytes: 8\n"
+    );
+    // bytes the patch doesn't expect there
+    std::fs::write(&path, "file lorem.md\n0x000002 -0000 +4c4f\n").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--overlay")
+        .arg(&path)
+        .arg("tests/files/lorem.md")
+        .assert()
+        .failure();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cli_undo() {
+    let path = env::temp_dir().join(format!("hx-undo-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let reverse = env::temp_dir().join(format!("hx-undo-{}.patch", std::process::id()));
+    let reverse_arg = reverse.to_str().unwrap();
+    std::fs::write(&path, [1u8, 2, 3, 4]).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args([
+        "poke", "--yes", path_arg, "--at", "1", "--type", "u16", "--value", "0xffff",
+    ])
+    .args(["--reverse-patch", reverse_arg])
+    .assert()
+    .success();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 0xff, 0xff, 4]);
+    assert_eq!(
+        std::fs::read_to_string(&reverse).unwrap(),
+        format!("# hx patch\nfile {path_arg}\n0x000001 -ffff +0203\n")
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["undo", "--yes", reverse_arg]).assert().success();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 2, 3, 4]);
+
+    // the bytes no longer match what the patch expects
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["undo", "--yes", reverse_arg]).assert().failure();
+    assert_eq!(std::fs::read(&path).unwrap(), [1, 2, 3, 4]);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&reverse).unwrap();
+}
+
+#[test]
+fn test_render_to_string() {
+    let expected = "let ARRAY: [u8; 3] = [\n    0x69, 0x6c, 0x0a\n];\n";
+    for args in [
+        ["-ar", "tests/files/tiny.txt"],
+        ["tests/files/tiny.txt", "-ar"],
+    ] {
+        let config = parse_args(&args).unwrap();
+        assert_eq!(render_to_string(&config, b"il\n").unwrap(), expected);
+    }
+    let config = parse_args(&["-c4"]).unwrap();
+    assert_eq!(
+        render_to_string(&config, b"hello").unwrap(),
+        "0x000000: 0x68 0x65 0x6c 0x6c hell\n\
+         0x000004: 0x6f                o\n   \
+         bytes: 5\n"
+    );
+}
+
+#[test]
+fn test_library_dump() {
+    let mut out = Vec::new();
+    let config = HexConfig::new().columns(4).skip(1);
+    assert_eq!(dump(&mut &b"hello"[..], &mut out, &config).unwrap(), 4);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "0x000001: 0x65 0x6c 0x6c 0x6f ello\n"
+    );
+    // the layout of a parsed command line, as render_to_string dumps it
+    let parsed = parse_args(&["-c4", "-f", "o"]).unwrap();
+    let mut out = Vec::new();
+    dump(&mut &b"hello"[..], &mut out, &HexConfig::from(&parsed)).unwrap();
+    let rendered = render_to_string(&parsed, b"hello").unwrap();
+    assert!(rendered.starts_with(&String::from_utf8(out).unwrap()));
+
+    let mut out = Vec::new();
+    let config = HexConfig::new().columns(2).truncate_len(3);
+    dump_array(&mut &b"hello"[..], &mut out, "rust", &config).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "let ARRAY: [u8; 3] = [\n    0x68, 0x65, \n    0x6c\n];\n"
+    );
+    assert!(dump_array(&mut &b""[..], &mut Vec::new(), "cobol", &config).is_err());
+}
+
+#[test]
+fn test_render_range() {
+    let mut source = std::io::Cursor::new(b"hello world".to_vec());
+    let config = HexConfig::new().columns(4);
+    let lines = render_range(&mut source, 2, 5, &config).unwrap();
+    assert_eq!(
+        lines
+            .iter()
+            .map(|l| (l.offset, l.bytes))
+            .collect::<Vec<_>>(),
+        [(2, 4), (6, 1)]
+    );
+    let text = |line: &RenderedLine| {
+        let spans = line.spans.iter().map(|span| span.text.as_str());
+        spans.collect::<String>()
+    };
+    assert_eq!(text(&lines[0]), "0x000002: 0x6c 0x6c 0x6f 0x20 llo ");
+    assert_eq!(
+        lines[0].spans[..3],
+        [
+            Span::new("0x000002", ansi_term::Style::new(), Role::Offset),
+            Span::space(": "),
+            Span::new("0x6c", ansi_term::Style::new(), Role::Cell),
+        ]
+    );
+    // colored, the same text in the dump colors
+    let colored = render_range(&mut source, 2, 5, &config.clone().colorize(true)).unwrap();
+    for (plain, colored) in lines.iter().zip(&colored) {
+        assert_eq!(text(colored), text(plain));
+    }
+    let spans = &colored[0].spans;
+    assert_eq!(spans[2].style, Color::Fixed(0x6c).normal());
+    let glyph = spans.iter().find(|span| span.role == Role::Char).unwrap();
+    assert_eq!(
+        (glyph.text.as_str(), glyph.style),
+        ("l", Color::Fixed(0x6c).normal())
+    );
+    assert!(spans.iter().all(|span| !span.text.contains('\x1b')));
+    let mut html = Vec::new();
+    Backend::Html.write(&mut html, &spans[..3]).unwrap();
+    assert_eq!(
+        String::from_utf8(html).unwrap(),
+        "<span class=\"hx-offset\">0x000002</span>: \
+         <span class=\"hx-cell\" style=\"color:#87af87\">0x6c</span>"
+    );
+    // the rest of the source
+    assert_eq!(render_range(&mut source, 8, 0, &config).unwrap().len(), 1);
+}
+
+#[test]
+fn test_stripe() {
+    let render = |color: bool, stripe: &[&str]| {
+        let mut config = parse_args(&[&["-c4"], stripe].concat()).unwrap();
+        config.color = Some(color);
+        render_to_string(&config, b"abcd").unwrap()
+    };
+    // columns 2 and 3 form the second group of two
+    let striped = render(true, &["--stripe", "2"]);
+    assert!(!striped.contains("\x1b[2;38;5;97m"));
+    assert!(!striped.contains("\x1b[2;38;5;98m"));
+    assert!(striped.contains("\x1b[2;38;5;99m"));
+    assert!(striped.contains("\x1b[2;38;5;100m"));
+    assert_eq!(render(true, &[]).matches("\x1b[2;").count(), 0);
+    // stripes are styling, plain output is unchanged
+    assert_eq!(render(false, &["--stripe", "2"]), render(false, &[]));
+    assert!(parse_args(&["--stripe", "0"]).is_err());
+}
+
+#[test]
+fn test_word_colors() {
+    use crate::args::parse_bounds;
+    assert_eq!(parse_bounds("0x20:0x7e"), Ok((32.0, 126.0)));
+    assert_eq!(parse_bounds("-1.5:1e3"), Ok((-1.5, 1000.0)));
+    assert!(parse_bounds("3:1").is_err());
+    assert!(parse_bounds("nan:1").is_err());
+    assert!(parse_bounds("1").is_err());
+
+    assert_eq!(word_to_color(0.0), byte_to_color(0));
+    assert_eq!(word_to_color(f64::NAN), Color::Red);
+    assert_ne!(word_to_color(1e-9), word_to_color(1.5));
+    assert_eq!(word_to_color(-1.5), word_to_color(1.5));
+
+    let render = |args: &[&str], bytes: &[u8]| {
+        let mut config = parse_args(args).unwrap();
+        config.color = Some(true);
+        render_to_string(&config, bytes).unwrap()
+    };
+    // 1.5 and 3e7 as f32, the second one highlighted
+    let floats = [0x00, 0x00, 0xc0, 0x3f, 0xc0, 0xe1, 0xe4, 0x4b];
+    let words = render(&["-fe", "-c8", "--highlight", "1e6:1e8"], &floats);
+    assert!(words.contains("\x1b[38;5;40m   1.500000e0\x1b[0m"));
+    assert!(words.contains("\x1b[1;7;38;5;178m   3.000000e7\x1b[0m"));
+    // bytes within the bounds, here the capital letter
+    let bytes = render(&["-c4", "--highlight", "0x41:0x5a"], b"aB");
+    assert_eq!(bytes.matches("\x1b[1;7;").count(), 1);
+    assert!(bytes.contains("\x1b[1;7;38;5;66m0x42"));
+    // highlighting is styling, plain output is unchanged
+    let plain = |args: &[&str]| render_to_string(&parse_args(args).unwrap(), b"aB").unwrap();
+    assert_eq!(plain(&["--highlight", "0:255"]), plain(&[]));
+}
+
+#[test]
+fn test_cli_boundary() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-c8", "-l24", "-t0", "-s4", "--boundary", "16"])
+        .arg("tests/files/lorem.md")
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000004: 0x72 0x65 0x6d 0x20 0x49 0x70 0x73 0x75 rem Ipsu\n\
+         -------- 0x000010 --------\n\
+         0x00000c: 0x6d 0x0a 0x0a 0x4c 0x6f 0x72 0x65 0x6d m..Lorem\n\
+         0x000014: 0x20 0x69 0x70 0x73 0x75 0x6d 0x20 0x64  ipsum d\n   bytes: 24\n",
+    );
+    assert_eq!(parse_args(&["--boundary", "page"]).unwrap().boundary, 4096);
+    assert_eq!(parse_args(&["--boundary", "0x200"]).unwrap().boundary, 512);
+    assert!(parse_args(&["--boundary", "0"]).is_err());
+}
+
+#[test]
+fn test_bookmarks_parse() {
+    let marks = crate::bookmarks::parse("# disk\n0x400 fs superblock\n\n16 mbr end\n").unwrap();
+    assert_eq!(
+        marks,
+        [
+            Bookmark {
+                offset: 16,
+                label: "mbr end".to_string()
+            },
+            Bookmark {
+                offset: 0x400,
+                label: "fs superblock".to_string()
+            },
+        ]
+    );
+    assert_eq!(
+        crate::bookmarks::format(&marks),
+        "0x000010 mbr end\n0x000400 fs superblock\n"
+    );
+    assert_eq!(crate::bookmarks::within(&marks, 0x3f8, 8), []);
+    assert_eq!(crate::bookmarks::within(&marks, 0x3f8, 9), &marks[1..]);
+    assert!(crate::bookmarks::parse("0xzz oops\n").is_err());
+}
+
+#[test]
+fn test_cli_bookmarks() {
+    let marks = env::temp_dir().join(format!("hx-bookmarks-{}", std::process::id()));
+    let marks_arg = marks.to_str().unwrap();
+    let _ = std::fs::remove_file(&marks);
+
+    for (at, label) in [
+        ("0x10", "second line"),
+        ("3", "title"),
+        ("0x10", "line two"),
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["bookmark", "add", marks_arg, at, label])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["bookmark", "list", marks_arg])
+        .assert()
+        .success()
+        .stdout("0x000003 title\n0x000010 line two\n");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-c8", "-l24", "-t0", "--bookmarks", marks_arg])
+        .arg("tests/files/lorem.md")
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000000: 0x23 0x20 0x4c 0x6f 0x72 0x65 0x6d 0x20 # Lorem   # 0x000003 title\n\
+         0x000008: 0x49 0x70 0x73 0x75 0x6d 0x0a 0x0a 0x4c Ipsum..L\n\
+         0x000010: 0x6f 0x72 0x65 0x6d 0x20 0x69 0x70 0x73 orem ips  # 0x000010 line two\n   bytes: 24\n",
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["bookmark", "remove", marks_arg, "3"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["bookmark", "remove", marks_arg, "3"])
+        .assert()
+        .failure();
+    assert_eq!(
+        std::fs::read_to_string(&marks).unwrap(),
+        "0x000010 line two\n"
+    );
+    std::fs::remove_file(&marks).unwrap();
+}
+
+#[test]
+fn test_offset_expressions() {
+    use crate::expr::eval;
+    assert_eq!(eval("0x400+3*512", None), Ok(0x400 + 3 * 512));
+    assert_eq!(eval("(0x10 + 2) * 4", None), Ok(72));
+    assert_eq!(eval("end-256", Some(4096)), Ok(3840));
+    assert_eq!(eval("end/2-1", Some(10)), Ok(4));
+    assert_eq!(eval("1_000", None), Ok(1000));
+    assert_eq!(eval("1MiB+4KiB", None), Ok(0x10_1000));
+    assert_eq!(eval("0x10KiB", None), Ok(0x4000));
+    assert!(eval("KiB", None).is_err());
+    assert!(eval("16777216TiB", None).is_err());
+    assert!(eval("end-1", None).is_err());
+    assert!(eval("1-2", None).is_err());
+    assert!(eval("4/0", None).is_err());
+    assert!(eval("(1+2", None).is_err());
+    assert!(eval("1+", None).is_err());
+    assert!(eval("0xfg", None).is_err());
+    assert!(eval("2 3", None).is_err());
+}
+
+#[test]
+fn test_cli_skip_expression() {
+    // end-8 is the last 8 bytes of the input
+    let len = std::fs::metadata("tests/files/lorem.md").unwrap().len();
+    let config = parse_args(&["--skip", "end-8", "tests/files/lorem.md"]).unwrap();
+    assert_eq!(config.skip, len - 8);
+    let config = parse_args(&["--skip", "2*0x10+1", "tests/files/lorem.md"]).unwrap();
+    assert_eq!(config.skip, 33);
+    assert!(parse_args(&["--skip", "end-8"]).is_err());
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["extract", "tests/files/lorem.md", "--at", "end-end+1*2"])
+        .args(["--type", "u16", "--endian", "big"])
+        .assert();
+    assert.success().code(0).stdout(
+        "      at: 0x000002\n    type: u16 big\n     raw: 0x4c6f\n     dec: 19567\n\
+         \x20    hex: 0x4c6f\n     oct: 0o46157\n     bin: 0b100110001101111\n",
+    );
+}
+
+#[test]
+fn test_parse_byte_ranges() {
+    let ranges = |value: &str| parse_args(&["--range", value]).map(|config| config.ranges);
+    assert_eq!(ranges("0x200+0x40").unwrap(), [(0x200, 0x240)]);
+    assert_eq!(
+        ranges("0x10:0x20,0x200+0x40").unwrap(),
+        [(0x10, 0x20), (0x200, 0x240)]
+    );
+    // the start may be an expression when it is parenthesized
+    assert_eq!(ranges("(0x400+3*512)+0x10").unwrap(), [(0xa00, 0xa10)]);
+    assert!(ranges("0x20:0x10").is_err());
+    assert!(ranges("0x10+0x10,0x18:0x30").is_err());
+    assert!(ranges("0x10").is_err());
+    assert!(parse_args(&["--range", "0+4", "--skip", "2"]).is_err());
+}
+
+#[test]
+fn test_cli_range() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-c8", "-t0", "--range", "2+6,0x10:0x14,(end-4)+2"])
+        .arg("tests/files/lorem.md")
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000002: 0x4c 0x6f 0x72 0x65 0x6d 0x20           Lorem \n\
+         0x000010: 0x6f 0x72 0x65 0x6d                     orem\n\
+         0x000087: 0x75 0x61                               ua\n   bytes: 12\n",
+    );
+}
+
+#[test]
+fn test_cli_carve() {
+    let dir = env::temp_dir().join(format!("hx-carve-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let header = dir.join("header.bin");
+    let tail = dir.join("tail.bin");
+    let carve = format!("2+4={},(end-2)+8={}", header.display(), tail.display());
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-c8", "-t0", "--carve", &carve])
+        .arg("tests/files/lorem.md")
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        format!(
+            "carved {}: 0x000002, 4 bytes\n\
+             0x000002: 0x4c 0x6f 0x72 0x65                     Lore\n\
+             carved {}: 0x000089, 2 bytes\n\
+             0x000089: 0x2e 0x0a                               ..\n\
+             \x20  bytes: 6\n\
+             \x20 carved: 2\n",
+            header.display(),
+            tail.display()
+        )
+    );
+    assert_eq!(std::fs::read(&header).unwrap(), b"Lore");
+    assert_eq!(std::fs::read(&tail).unwrap(), b".\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(parse_args(&["--carve", "0:4"]).is_err());
+    assert!(parse_args(&["--carve", "0:4=a", "--range", "0:4"]).is_err());
+}
+
+/// Minimal little endian ELF64 file with a symbol table of
+/// `(name, value, size, type)` entries.
+fn elf_with(symbols: &[(&str, u64, u64, u8)]) -> Vec<u8> {
+    let mut strtab = vec![0u8];
+    let mut symtab = vec![0u8; 24];
+    for (name, value, size, kind) in symbols {
+        let name_at = strtab.len() as u32;
+        strtab.extend_from_slice(name.as_bytes());
+        strtab.push(0);
+        symtab.extend_from_slice(&name_at.to_le_bytes());
+        symtab.push(0x10 | kind);
+        symtab.push(0);
+        symtab.extend_from_slice(&1u16.to_le_bytes());
+        symtab.extend_from_slice(&value.to_le_bytes());
+        symtab.extend_from_slice(&size.to_le_bytes());
+    }
+    let strtab_at = 64u64;
+    let symtab_at = strtab_at + strtab.len() as u64;
+    let shoff = symtab_at + symtab.len() as u64;
+
+    let mut elf = vec![0u8; 64];
+    elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    elf[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+    elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+    elf.extend_from_slice(&strtab);
+    elf.extend_from_slice(&symtab);
+    let section = |kind: u32, offset: u64, size: u64, link: u32, entsize: u64| {
+        let mut header = vec![0u8; 64];
+        header[4..8].copy_from_slice(&kind.to_le_bytes());
+        header[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+        header[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+        header[0x28..0x2c].copy_from_slice(&link.to_le_bytes());
+        header[0x38..0x40].copy_from_slice(&entsize.to_le_bytes());
+        header
+    };
+    elf.extend(section(0, 0, 0, 0, 0));
+    elf.extend(section(2, symtab_at, symtab.len() as u64, 2, 24));
+    elf.extend(section(3, strtab_at, strtab.len() as u64, 0, 0));
+    elf
+}
+
+#[test]
+fn test_symbols() {
+    use crate::symbols::{lookup, parse};
+    let listing = b".text           0x00000000     0x200 main.o\n\
+                    \x20               0x00000010                reset_handler\n\
+                    \x20               0x00000100                main\n\
+                    00000180 T idle\n";
+    let symbols = parse(listing).unwrap();
+    assert_eq!(
+        symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+        ["reset_handler", "main", "idle"]
+    );
+    assert_eq!(lookup(&symbols, 0x8), None);
+    assert_eq!(lookup(&symbols, 0x10).as_deref(), Some("reset_handler"));
+    assert_eq!(lookup(&symbols, 0x11c).as_deref(), Some("main+0x1c"));
+    assert_eq!(lookup(&symbols, 0x1000).as_deref(), Some("idle+0xe80"));
+
+    let elf = elf_with(&[("vectors", 0x0, 0x40, 1), ("main", 0x100, 0x20, 2)]);
+    let symbols = parse(&elf).unwrap();
+    assert_eq!(
+        symbols,
+        [
+            Symbol {
+                addr: 0,
+                size: Some(0x40),
+                name: "vectors".to_string()
+            },
+            Symbol {
+                addr: 0x100,
+                size: Some(0x20),
+                name: "main".to_string()
+            },
+        ]
+    );
+    assert_eq!(lookup(&symbols, 0x3f).as_deref(), Some("vectors+0x3f"));
+    // past the end of a sized symbol
+    assert_eq!(lookup(&symbols, 0x40), None);
+    assert_eq!(lookup(&symbols, 0x120), None);
+}
+
+#[test]
+fn test_cli_symbols() {
+    let path = env::temp_dir().join(format!("hx-symbols-{}.map", std::process::id()));
+    std::fs::write(&path, "0x00000004 header\n0x00000010 body\n").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-c8", "-l24", "-t0", "--symbols", path.to_str().unwrap()])
+        .arg("tests/files/lorem.md")
+        .assert();
+    assert.success().code(0).stdout(
+        "0x000000: 0x23 0x20 0x4c 0x6f 0x72 0x65 0x6d 0x20 # Lorem \n\
+         0x000008: 0x49 0x70 0x73 0x75 0x6d 0x0a 0x0a 0x4c Ipsum..L  # header+0x4\n\
+         0x000010: 0x6f 0x72 0x65 0x6d 0x20 0x69 0x70 0x73 orem ips  # body\n   bytes: 24\n",
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_line_table_lookup() {
+    let table = LineTable {
+        files: vec!["src/main.c".to_string(), "src/util.c".to_string()],
+        rows: vec![
+            LineRow {
+                addr: 0x100,
+                file: 0,
+                line: 10,
+                end: false,
+            },
+            LineRow {
+                addr: 0x108,
+                file: 1,
+                line: 3,
+                end: false,
+            },
+            LineRow {
+                addr: 0x110,
+                file: 1,
+                line: 3,
+                end: true,
+            },
+        ],
+    };
+    assert_eq!(table.lookup(0xff), None);
+    assert_eq!(table.lookup(0x104).as_deref(), Some("src/main.c:10"));
+    assert_eq!(table.lookup(0x10f).as_deref(), Some("src/util.c:3"));
+    assert_eq!(table.lookup(0x110), None);
+}
+
+#[cfg(feature = "dwarf")]
+#[test]
+fn test_dwarf_line_table() {
+    // the test binary itself is built with debug info
+    let exe = std::fs::read(env::current_exe().unwrap()).unwrap();
+    let symbols = crate::symbols::parse(&exe).unwrap();
+    let table = crate::dwarf::line_table(&exe).unwrap();
+    let test = symbols
+        .iter()
+        .find(|symbol| symbol.name.contains("test_dwarf_line_table"))
+        .unwrap();
+    let line = table.lookup(test.addr).unwrap();
+    assert!(line.contains("src/tests.rs:"), "{line}");
+}
+
+#[test]
+fn test_utf16_panel() {
+    let panel = |bytes: &[u8], charset: Charset| {
+        let mut text = String::new();
+        crate::charset::panel_spans(&mut text, bytes, charset, false);
+        text
+    };
+    assert_eq!(panel(b"H\0i\0\n\0", Charset::Utf16Le), "Hi.");
+    assert_eq!(panel(b"\0H\0i", Charset::Utf16Be), "Hi");
+    assert_eq!(panel(b"H\0i\0", Charset::Ascii), "H.i.");
+    // a surrogate pair is one glyph, a lone surrogate and an odd byte are dots
+    assert_eq!(
+        panel(b"\x3d\xd8\x00\xde!\0", Charset::Utf16Le),
+        "\u{1f600}!"
+    );
+    assert_eq!(panel(b"\x3d\xd8!\0", Charset::Utf16Le), ".!");
+    assert_eq!(panel(b"!\0!", Charset::Utf16Le), "!.");
+}
+
+#[test]
+fn test_cli_charset() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--charset", "utf16le", "-t", "0", "-c", "4"])
+        .write_stdin("h\0x\0")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(
+        stdout.starts_with("0x000000: 0x68 0x00 0x78 0x00 hx\n"),
+        "{stdout}"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--charset", "utf8"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_only_byte_class() {
+    assert!(ByteClass::Printable.contains(b' '));
+    assert!(!ByteClass::Printable.contains(0x7f));
+    assert!(ByteClass::Nonzero.contains(0x01));
+    assert!(!ByteClass::High.contains(0x7f));
+    assert_eq!(ByteClass::High.blank(b"a\xff\0", Charset::Ascii), b" \xff ");
+    // a code unit stays when either of its bytes is in the class
+    assert_eq!(
+        ByteClass::Printable.blank(b"h\0\x01\0", Charset::Utf16Le),
+        b"h\0 \0"
+    );
+    assert_eq!(
+        ByteClass::Printable.blank(b"\x01\0", Charset::Utf16Be),
+        b"\0 "
+    );
+
+    let dump = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4"])
+            .args(args)
+            .write_stdin(&b"hi\0\xffok"[..])
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        dump(&["--only", "printable"]),
+        "0x000000: 0x68 0x69           hi  \n0x000004: 0x6f 0x6b           ok\n   bytes: 6\n"
+    );
+    assert_eq!(
+        dump(&["--only", "nonzero", "--panels", "offset,decimal"]),
+        "0x000000: 104 105     255 \n0x000004: 111 107         \n   bytes: 6\n"
+    );
+    assert_eq!(
+        dump(&["--only", "high", "--group", "2"]),
+        "0x000000:        0xff00    .\n0x000004:                 \n   bytes: 6\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--only", "text"])
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_panels() {
+    use crate::panel::{parse_panels, Panel};
+    assert_eq!(
+        parse_panels("offset, ascii,hex,ascii").unwrap(),
+        [Panel::Offset, Panel::Ascii, Panel::Hex, Panel::Ascii]
+    );
+    assert!(parse_panels("offset,").is_err());
+    assert!(parse_panels("text").is_err());
+
+    let dump = |panels: &str| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4", "--panels", panels])
+            .write_stdin("hello")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        dump("offset,ascii,hex"),
+        "0x000000: hell 0x68 0x65 0x6c 0x6c \n0x000004: o    0x6f                \n   bytes: 5\n"
+    );
+    assert_eq!(
+        dump("hex,decimal,ascii"),
+        "0x68 0x65 0x6c 0x6c 104 101 108 108 hell\n0x6f                111             o\n   bytes: 5\n"
+    );
+    // the default order
+    assert_eq!(
+        dump("offset,hex,ascii,annotations"),
+        "0x000000: 0x68 0x65 0x6c 0x6c hell\n0x000004: 0x6f                o\n   bytes: 5\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--panels", "offset,bytes"])
+        .write_stdin("")
+        .assert()
+        .failure();
+    // refused where there are no panels to order
+    for args in [
+        &["-ar"][..],
+        &["--raw"],
+        &["--output", "plain"],
+        &["--output", "json"],
+        &["--explain"],
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["--panels", "hex"])
+            .args(args)
+            .write_stdin("hello")
+            .assert()
+            .code(2);
+    }
+}
+
+#[test]
+fn test_cli_border() {
+    use crate::panel::{Border, Edge};
+    assert_eq!(Border::Unicode.rule(Edge::Middle, &[2, 1]), "├──┼─┤");
+    assert_eq!(Border::Ascii.rule(Edge::Top, &[1]), "+-+");
+    assert_eq!(Border::None.rule(Edge::Bottom, &[3]), "");
+
+    let dump = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4"])
+            .args(args)
+            .write_stdin("hello")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        dump(&["--border", "unicode"]),
+        "┌────────┬─────────────────────┬────┐\n\
+         │0x000000│ 0x68 0x65 0x6c 0x6c │hell│\n\
+         │0x000004│ 0x6f                │o   │\n\
+         └────────┴─────────────────────┴────┘\n   bytes: 5\n"
+    );
+    assert_eq!(
+        dump(&["--border", "ascii", "--panels", "ascii,offset", "--boundary", "4"]),
+        "+----+--------+\n|hell|0x000000|\n+----+--------+\n|o   |0x000004|\n+----+--------+\n   bytes: 5\n"
+    );
+    // the plain layout by default
+    assert_eq!(dump(&["--border", "none"]), dump(&[]));
+
+    // refused where the frame would be broken or left out
+    for (args, with) in [
+        (&["-c", "0"][..], "--cols 0"),
+        (&["--no-wrap"], "--no-wrap"),
+        (&["--stacked"], "--stacked"),
+        (&["--output", "json"], "--output json"),
+        (&["--output", "plain"], "--output plain"),
+        (&["--explain"], "--explain"),
+        (&["--find", "'ll'"], "--find"),
+        (&["--find-int", "7"], "--find-int"),
+        (&["-ar"], "--array"),
+        (&["--raw"], "--raw"),
+        (&["--text-report"], "--text-report"),
+        (&["--dedupe-scan", "4"], "--dedupe-scan"),
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["--border", "ascii"])
+            .args(args)
+            .write_stdin("hello")
+            .assert()
+            .code(2);
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+        assert!(
+            stderr.contains(&format!("--border can't be combined with {with}")),
+            "{stderr}"
+        );
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--border", "none", "--stacked"])
+        .write_stdin("hello")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_find() {
+    use crate::args::parse_pattern;
+    assert_eq!(parse_pattern("deadbeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(parse_pattern("'ELF'").unwrap(), b"ELF");
+    assert_eq!(parse_pattern("hello").unwrap(), b"hello");
+    assert!(parse_pattern("''").is_err());
+
+    let find = |pattern: &str| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4", "--find", pattern])
+            .write_stdin("abcdefghijabcdzz")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    // "cde" straddles the first line break, and lines apart are separated
+    assert_eq!(
+        find("cde"),
+        "0x000000: 0x61 0x62 0x63 0x64 abcd  # 0x000002 match\n\
+         0x000004: 0x65 0x66 0x67 0x68 efgh\n   bytes: 16\n matches: 1\n"
+    );
+    assert_eq!(
+        find("6263"),
+        "0x000000: 0x61 0x62 0x63 0x64 abcd  # 0x000001 match\n--\n\
+         0x000008: 0x69 0x6a 0x61 0x62 ijab  # 0x00000b match\n\
+         0x00000c: 0x63 0x64 0x7a 0x7a cdzz\n   bytes: 16\n matches: 2\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--find", "nope"])
+        .write_stdin("abcdefghijabcdzz")
+        .assert()
+        .code(1);
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "   bytes: 16\n matches: 0\n"
+    );
+
+    // the input is searched in blocks: a match over a block boundary is
+    // found, and a stream larger than --memory-limit is searched whole
+    let mut data = vec![0; 200_000];
+    data[65_534..65_538].copy_from_slice(b"ELF!");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c16", "--memory-limit", "1000", "--find", "'ELF!'"])
+        .write_stdin(data)
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "0x00fff0: 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 \
+         0x00 0x00 0x45 0x4c ..............EL  # 0x00fffe match\n\
+         0x010000: 0x46 0x21 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 0x00 \
+         0x00 0x00 0x00 0x00 F!..............\n   bytes: 200,000 (195.3 KiB)\n matches: 1\n"
+    );
+
+    // matched bytes stand out when colored
+    let mut config = parse_args(&["-c4", "--find", "'zz'"]).unwrap();
+    config.color = Some(true);
+    let colored = render_to_string(&config, b"abzzz").unwrap();
+    assert_eq!(colored.matches("\x1b[1;43;30m").count(), 3);
+}
+
+#[test]
+fn test_pointer_scan() {
+    assert_eq!(PointerScan::parse_type("u64"), Ok((8, Endian::Little)));
+    assert_eq!(PointerScan::parse_type("u32be"), Ok((4, Endian::Big)));
+    assert!(PointerScan::parse_type("u16").is_err());
+
+    let scan = PointerScan {
+        base: 0x1000,
+        size: 0x100,
+        width: 4,
+        endian: Endian::Little,
+    };
+    // the region's end is outside, and only aligned words count
+    let data = [
+        0x00, 0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0xff, 0x10, 0x00, 0x00, 0x00, 0x00,
+    ];
+    assert_eq!(scan.scan(&data, 0), [(0, 0x1000), (8, 0x10ff)]);
+    // offsets count from the start, so words start 1 and 2 bytes in
+    assert_eq!(scan.scan(&data[1..], 3), []);
+    assert_eq!(scan.scan(&data[2..], 2), [(6, 0x10ff)]);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "-c4",
+            "--pointer-scan",
+            "--base",
+            "0x1000",
+            "--size",
+            "0x100",
+        ])
+        .args(["--pointer-type", "u32"])
+        .write_stdin(&data[..])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "0x000000: 0x00 0x10 0x00 0x00 ....  # 0x000000 -> 0x001000\n--\n\
+         0x000008: 0xff 0x10 0x00 0x00 ....  # 0x000008 -> 0x0010ff\n   bytes: 14\npointers: 2\n"
+    );
+}
+
+#[test]
+fn test_find_int() {
+    let search = IntSearch {
+        kind: ScalarType::U32,
+        value: 0x1234_5678,
+        near: 0,
+        endian: None,
+    };
+    // both byte orders, at any offset, a palindrome once
+    let data = [0x00, 0x78, 0x56, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78];
+    assert_eq!(
+        search.scan(&data),
+        [
+            (1, 0x1234_5678, Endian::Little),
+            (5, 0x1234_5678, Endian::Big)
+        ]
+    );
+    let big = IntSearch {
+        endian: Some(Endian::Big),
+        ..search
+    };
+    assert_eq!(big.scan(&data), [(5, 0x1234_5678, Endian::Big)]);
+    assert_eq!(big.label(0x1234_5678, Endian::Big), "u32be 305419896");
+
+    // values around it, signed ones across zero
+    let near = IntSearch {
+        kind: ScalarType::I16,
+        value: 0xfffe,
+        near: 2,
+        endian: Some(Endian::Little),
+    };
+    let data = [0xfc, 0xff, 0x00, 0x00, 0x01, 0x00, 0x00];
+    assert_eq!(
+        near.scan(&data),
+        [
+            (0, 0xfffc, Endian::Little),
+            (2, 0, Endian::Little),
+            (5, 0, Endian::Little)
+        ]
+    );
+    assert_eq!(near.label(0xfffc, Endian::Little), "i16le -4");
+    let byte = IntSearch {
+        kind: ScalarType::U8,
+        value: 7,
+        near: 0,
+        endian: None,
+    };
+    assert_eq!(byte.scan(&[7, 8]), [(0, 7, Endian::Little)]);
+    assert_eq!(byte.label(7, Endian::Little), "u8 7");
+}
+
+#[test]
+fn test_cli_find_int() {
+    let find = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4"])
+            .args(args)
+            .write_stdin(&[0x00, 0x78, 0x56, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78][..])
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    // hex or decimal, u32 in either byte order unless told otherwise
+    let both = "0x000000: 0x00 0x78 0x56 0x34 .xV4  # 0x000001 u32le 305419896\n\
+                0x000004: 0x12 0x12 0x34 0x56 ..4V  # 0x000005 u32be 305419896\n\
+                0x000008: 0x78                x\n   bytes: 9\n matches: 2\n";
+    assert_eq!(find(&["--find-int", "305419896"]), both);
+    assert_eq!(
+        find(&[
+            "--find-int",
+            "0x12345678",
+            "--type",
+            "u32",
+            "--endian",
+            "any"
+        ]),
+        both
+    );
+    assert_eq!(
+        find(&["--find-int", "0x12345670", "--near", "8", "--endian", "big"]),
+        "0x000004: 0x12 0x12 0x34 0x56 ..4V  # 0x000005 u32be 305419896\n\
+         0x000008: 0x78                x\n   bytes: 9\n matches: 1\n"
+    );
+    assert_eq!(
+        find(&["--find-int", "0x3412", "--type", "u16", "--matches-json"]),
+        "{\"offset\": 3, \"length\": 2, \"hex\": \"3412\", \"value\": 13330}\n\
+         {\"offset\": 5, \"length\": 2, \"hex\": \"1234\", \"value\": 13330}\n"
+    );
+
+    // negative values of signed types
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--find-int", "-2", "--type", "i16", "--only-offsets"])
+        .write_stdin(&[0x01, 0xfe, 0xff][..])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "0x000001\n"
+    );
+
+    let fails = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).write_stdin("x").assert().failure();
+        String::from_utf8_lossy(&assert.get_output().stderr).into_owned()
+    };
+    assert!(fails(&["--find-int", "256", "--type", "u8"]).contains("doesn't fit in u8"));
+    assert!(fails(&["--group", "2", "--endian", "any"]).contains("any only applies to --find-int"));
+}
+
+#[test]
+fn test_find_float() {
+    let search = FloatSearch {
+        kind: ScalarType::F32,
+        value: 1.2345,
+        tolerance: 0.0,
+        endian: None,
+        unaligned: false,
+    };
+    // 1.2345, 1.2346 a byte off alignment, and 1.2344 big endian
+    let data = [
+        0x19, 0x04, 0x9e, 0x3f, 0x00, 0x5f, 0x07, 0x9e, 0x3f, 0x00, 0x00, 0x00, 0x3f, 0x9e, 0x00,
+        0xd2,
+    ];
+    // the value rounded to f32 is found exactly
+    assert_eq!(search.scan(&data, 0), [(0, 0x3f9e_0419, Endian::Little)]);
+    let near = FloatSearch {
+        tolerance: 0.001,
+        ..search
+    };
+    assert_eq!(
+        near.scan(&data, 0),
+        [
+            (0, 0x3f9e_0419, Endian::Little),
+            (12, 0x3f9e_00d2, Endian::Big)
+        ]
+    );
+    // alignment counts from the start of the input
+    assert_eq!(near.scan(&data[1..], 1), [(11, 0x3f9e_00d2, Endian::Big)]);
+    let unaligned = FloatSearch {
+        unaligned: true,
+        endian: Some(Endian::Little),
+        ..near
+    };
+    assert_eq!(
+        unaligned.scan(&data, 0),
+        [
+            (0, 0x3f9e_0419, Endian::Little),
+            (5, 0x3f9e_075f, Endian::Little)
+        ]
+    );
+    assert_eq!(unaligned.label(0x3f9e_075f, Endian::Little), "f32le 1.2346");
+
+    // NaN is never near anything
+    let wide = FloatSearch {
+        kind: ScalarType::F64,
+        value: 0.0,
+        tolerance: f64::INFINITY,
+        endian: Some(Endian::Little),
+        unaligned: false,
+    };
+    let mut data = f64::NAN.to_le_bytes().to_vec();
+    data.extend(2.5f64.to_le_bytes());
+    assert_eq!(wide.scan(&data, 0), [(8, 2.5f64.to_bits(), Endian::Little)]);
+}
+
+#[test]
+fn test_cli_find_float() {
+    let mut data = vec![
+        0x19, 0x04, 0x9e, 0x3f, 0x00, 0x5f, 0x07, 0x9e, 0x3f, 0x00, 0x00, 0x00, 0x3f, 0x9e, 0x00,
+        0xd2,
+    ];
+    data.extend((-2.5f64).to_le_bytes());
+    let find = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4"])
+            .args(args)
+            .write_stdin(data.as_slice())
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        find(&[
+            "--find-float",
+            "1.2345",
+            "--tolerance",
+            "0.001",
+            "--unaligned"
+        ]),
+        "0x000000: 0x19 0x04 0x9e 0x3f ...?  # 0x000000 f32le 1.2345\n\
+         0x000004: 0x00 0x5f 0x07 0x9e ._..  # 0x000005 f32le 1.2346\n\
+         0x000008: 0x3f 0x00 0x00 0x00 ?...\n\
+         0x00000c: 0x3f 0x9e 0x00 0xd2 ?...  # 0x00000c f32be 1.2344\n   bytes: 24\n matches: 3\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "--find-float",
+            "1.2345",
+            "--type",
+            "f32",
+            "--endian",
+            "big",
+        ])
+        .write_stdin(data.as_slice())
+        .assert()
+        .code(1);
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "   bytes: 24\n matches: 0\n"
+    );
+    assert_eq!(
+        find(&["--find-float", "-2.5", "--type", "f64", "--matches-json"]),
+        "{\"offset\": 16, \"length\": 8, \"hex\": \"00000000000004c0\", \"value\": -2.5}\n"
+    );
+
+    let fails = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).write_stdin("x").assert().failure();
+        String::from_utf8_lossy(&assert.get_output().stderr).into_owned()
+    };
+    assert!(fails(&["--find-float", "1", "--type", "u32"]).contains("not a float type"));
+    assert!(fails(&["--find-int", "1", "--type", "f32"]).contains("not an integer type"));
+    assert!(fails(&["--type", "u32"]).contains("give one of them"));
+}
+
+#[test]
+fn test_safe_writer() {
+    use crate::safe::SafeWriter;
+    let safe = |writes: &[&[u8]]| {
+        let mut out = Vec::new();
+        let mut writer = SafeWriter::new(&mut out);
+        for bytes in writes {
+            writer.write_all(bytes).unwrap();
+        }
+        drop(writer);
+        String::from_utf8(out).unwrap()
+    };
+    // 256 colors and RGB to the nearest basic one, bright ones as 90-97,
+    // and never black text
+    assert_eq!(
+        safe(&[b"\x1b[38;5;104mh\x1b[0m \x1b[1;38;5;22;48;5;196m.\x1b[0m"]),
+        "\x1b[94mh\x1b[0m \x1b[1;32;101m.\x1b[0m"
+    );
+    assert_eq!(safe(&[b"\x1b[38;2;250;250;0mx"]), "\x1b[93mx");
+    // other codes pass as they are
+    assert_eq!(safe(&[b"\x1b[2;33mx\x1b[K"]), "\x1b[2;33mx\x1b[K");
+    // codes and characters cut between writes
+    assert_eq!(
+        safe(&[b"a\x1b[38;5", b";104mb\xe2\x94", b"\x82c"]),
+        "a\x1b[94mb|c"
+    );
+    assert_eq!(safe(&["┌─┬─┐ │ └─┴─┘ 中é".as_bytes()]), "+-+-+ | +-+-+ ..");
+    // bytes that aren't utf-8, and what was left cut off at the end
+    assert_eq!(safe(&[b"\xff(\xc3", b"(", b"\xe2\x94"]), ".(.(..");
+}
+
+#[test]
+fn test_cli_profile_safe() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t1", "-c4", "--border", "unicode", "--profile", "safe"])
+        .write_stdin("h\0")
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "+--------+---------------------+----+\n\
+         |0x000000| \x1b[94m0x68\x1b[0m \x1b[32m0x00\x1b[0m           |\x1b[94mh\x1b[0m\x1b[32m.\x1b[0m  |\n\
+         +--------+---------------------+----+\n   bytes: 2\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "--charset", "utf16le", "--profile", "safe"])
+        .write_stdin("A\0\x2dN")
+        .assert()
+        .success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stdout).contains(" A."));
+}
+
+#[test]
+fn test_cli_hit_lists() {
+    let list = |args: &[&str], input: &[u8]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).write_stdin(input).assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        list(&["--find", "'bc'", "--only-offsets", "-s1"], b"abcabcab"),
+        "0x000001\n0x000004\n"
+    );
+    assert_eq!(
+        list(&["--find", "'bc'", "--matches-json"], b"abcabcab"),
+        "{\"offset\": 1, \"length\": 2, \"hex\": \"6263\"}\n\
+         {\"offset\": 4, \"length\": 2, \"hex\": \"6263\"}\n"
+    );
+    let scan = [
+        "--pointer-scan",
+        "--base",
+        "0x10",
+        "--size",
+        "4",
+        "--pointer-type",
+        "u32",
+    ];
+    assert_eq!(
+        list(
+            &[&scan[..], &["--matches-json"]].concat(),
+            b"\x10\0\0\0\x99\0\0\0"
+        ),
+        "{\"offset\": 0, \"length\": 4, \"hex\": \"10000000\", \"value\": 16}\n"
+    );
+    // the lists need something to list
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--only-offsets")
+        .write_stdin("x")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_search_no_match() {
+    let pointers = ["--pointer-scan", "--base", "0x1000", "--size", "16"];
+    // every search mode, and its hit lists, fails finding nothing
+    for args in [
+        &["--find", "'zz'"][..],
+        &["--find", "'zz'", "--only-offsets"],
+        &["--find", "'zz'", "--matches-json"],
+        &["--find", "'zz'", "--count-matches"],
+        &["--find-int", "0x7a7a7a7a"],
+        &["--find-float", "2.5"],
+        &pointers,
+        &[&pointers[..], &["--matches-json"]].concat(),
+    ] {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).arg("tests/files/lorem.md").assert().code(1);
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+        assert!(stderr.contains("no matches found"), "{args:?}: {stderr}");
+        // quietly with -q, for scripts testing the status alone
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .arg("-q")
+            .args(args)
+            .arg("tests/files/lorem.md")
+            .assert()
+            .code(1);
+        assert!(assert.get_output().stderr.is_empty(), "{args:?}");
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--find", "'Lorem'", "tests/files/lorem.md"])
+        .assert()
+        .success();
+    // every run counts its own hits, a library caller's later runs too
+    let out = env::temp_dir().join(format!("hx-no-match-{}", std::process::id()));
+    let out_arg = out.to_str().unwrap();
+    let run = |args: &[&str]| {
+        let config = parse_args(&[args, &["--outfile", out_arg, "tests/files/lorem.md"]].concat());
+        run_config(&config.unwrap())
+    };
+    assert!(run(&["--find", "'zz'"]).is_err());
+    assert!(run(&[]).is_ok());
+    assert!(run(&["--find", "'Lorem'"]).is_ok());
+    std::fs::remove_file(&out).unwrap();
+}
+
+#[cfg(feature = "cores")]
+#[test]
+fn test_core_regions() {
+    use crate::coredump::{regions, select};
+    // ELF64 core: a header, one PT_NOTE and two PT_LOAD program headers
+    let mut core = vec![0u8; 0xe8 + 12];
+    core[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    core[0x10] = 4;
+    core[0x20] = 0x40;
+    core[0x36] = 56;
+    core[0x38] = 3;
+    let segment = |core: &mut Vec<u8>, at: usize, kind: u8, flags: u8, fields: [u64; 3]| {
+        core[at] = kind;
+        core[at + 4] = flags;
+        for (field, value) in [8, 16, 32].iter().zip(fields) {
+            core[at + field..at + field + 8].copy_from_slice(&value.to_le_bytes());
+        }
+    };
+    segment(&mut core, 0x40, 4, 0, [0, 0, 0]);
+    segment(&mut core, 0x78, 1, 5, [0xe8, 0x401000, 8]);
+    segment(&mut core, 0xb0, 1, 6, [0xf0, 0x7ffd0000, 4]);
+    core[0xe8..].copy_from_slice(b"codedataSTCK");
+    let found = regions(&core).unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(
+        (
+            found[1].name.as_str(),
+            found[1].address,
+            found[1].offset,
+            found[1].size
+        ),
+        ("load1", 0x7ffd0000, 0xf0, 4)
+    );
+    assert_eq!(
+        (found[0].flags.as_str(), found[1].flags.as_str()),
+        ("r-x", "rw-")
+    );
+    assert_eq!(select(&found, "load1").unwrap().name, "load1");
+    assert_eq!(select(&found, "0").unwrap().name, "load0");
+    assert_eq!(select(&found, "0x401004").unwrap().name, "load0");
+    assert!(select(&found, "0x401008").is_err());
+
+    // minidump with a Memory64ListStream of two ranges from rva 0x40
+    let mut dump = vec![0u8; 0x40];
+    dump[..4].copy_from_slice(b"MDMP");
+    dump[8] = 1;
+    dump[12] = 0x20;
+    dump[0x20] = 9;
+    dump[0x28] = 0x40;
+    for value in [2u64, 0x70, 0x1000, 4, 0x2000, 2] {
+        dump.extend(value.to_le_bytes());
+    }
+    dump.extend(b"abcdef");
+    let found = regions(&dump).unwrap();
+    assert_eq!(
+        found
+            .iter()
+            .map(|r| (r.address, r.offset, r.size))
+            .collect::<Vec<_>>(),
+        [(0x1000, 0x70, 4), (0x2000, 0x74, 2)]
+    );
+    assert!(regions(b"plain").is_err());
+
+    let path = env::temp_dir().join(format!("hx-core-regions-{}.core", std::process::id()));
+    fs::write(&path, &core).unwrap();
+    // both runs before any assert, so the file is removed whatever they print
+    let dump = Command::cargo_bin("hx")
+        .unwrap()
+        .args(["-t0", "-c4", "--region", "load1"])
+        .arg(&path)
+        .output();
+    let listing = Command::cargo_bin("hx")
+        .unwrap()
+        .arg(&path)
+        .arg("--region")
+        .output();
+    fs::remove_file(&path).unwrap();
+    let (dump, listing) = (dump.unwrap(), listing.unwrap());
+    assert!(dump.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&dump.stdout),
+        "0x7ffd0000: 0x53 0x54 0x43 0x4b STCK\n   bytes: 4\n"
+    );
+    assert!(listing.status.success());
+    let listing = String::from_utf8_lossy(&listing.stdout).into_owned();
+    assert_eq!(listing.lines().count(), 2);
+    assert!(listing.starts_with("  0  load0         0x401000    0x0000e8           8  r-x\n"));
+}
+
+#[test]
+fn test_reverse_dump() {
+    let reverse = |dump: &str| {
+        let mut bytes = Vec::new();
+        reverse_dump(&mut dump.as_bytes(), &mut bytes).map(|_| bytes)
+    };
+    assert_eq!(
+        reverse(
+            "0x000000: 0x68 0x65 0x6c 0x6c hell\n0x000004: 0x6f                o\n   bytes: 5\n"
+        )
+        .unwrap(),
+        b"hello"
+    );
+    // bare cells, whose character panel could pass for a cell
+    assert_eq!(reverse("0x000000: 61 62 ab\n").unwrap(), b"ab");
+    // no character panel, varying widths and a gap
+    assert_eq!(
+        reverse("0x000010: 0x01 0x02\n0x000012: 0X0A\n0x000016: 0xff\n").unwrap(),
+        b"\x01\x02\x0a\0\0\0\xff"
+    );
+    assert_eq!(
+        reverse("0x000000: \x1b[38;5;104m0x68\x1b[0m 0x69 hi\n").unwrap(),
+        b"hi"
+    );
+    assert!(reverse("0x000004: 0x01\n0x000000: 0x02\n").is_err());
+    // bare cells reading 0b, or ending the line without a panel
+    assert_eq!(
+        reverse("0x000000: d3 0b 58 70\n").unwrap(),
+        b"\xd3\x0b\x58\x70"
+    );
+    assert_eq!(
+        reverse("0x000000: ab cd ef 01\n").unwrap(),
+        b"\xab\xcd\xef\x01"
+    );
+    // the first line tells a short last line has no panel
+    assert_eq!(
+        reverse("0x000000: 01 02 03 04\n0x000004: 61 62 ab\n").unwrap(),
+        b"\x01\x02\x03\x04\x61\x62\xab"
+    );
+
+    // every byte value, colored or not and with the 0x prefix or not
+    let path = env::temp_dir().join(format!("hx-reverse-all-{}.bin", std::process::id()));
+    let data: Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+    std::fs::write(&path, &data).unwrap();
+    for color in ["-t0", "-t1"] {
+        for prefix in ["-r0", "-r1"] {
+            for cols in ["-c16", "-c7"] {
+                let mut cmd = Command::cargo_bin("hx").unwrap();
+                let dump = cmd
+                    .args([color, prefix, cols])
+                    .arg(&path)
+                    .assert()
+                    .success()
+                    .get_output()
+                    .stdout
+                    .clone();
+                let mut cmd = Command::cargo_bin("hx").unwrap();
+                let assert = cmd.arg("--reverse").write_stdin(dump).assert().success();
+                assert_eq!(assert.get_output().stdout, data, "{color} {prefix} {cols}");
+            }
+        }
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let dump = cmd
+        .args(["-t1", "-c7", "tests/files/tiny.txt"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--reverse").write_stdin(dump).assert().success();
+    assert_eq!(
+        assert.get_output().stdout,
+        std::fs::read("tests/files/tiny.txt").unwrap()
+    );
+}
+
+#[test]
+fn test_reverse_layouts() {
+    let path = env::temp_dir().join(format!("hx-reverse-{}.bin", std::process::id()));
+    let data = b"hello\0\xff world, binary \x01\x02\x03 safe  \n\x00";
+    std::fs::write(&path, data).unwrap();
+    let layouts: &[&[&str]] = &[
+        &[],
+        &["-r0"],
+        &["-fX"],
+        &["-fo"],
+        &["-fb"],
+        &["-c1"],
+        &["-c3"],
+        &["-c16"],
+        &["--separator", ","],
+        &["--separator="],
+        &["--group", "2"],
+        &["--group", "4"],
+        &["--group", "8", "-c16"],
+        &["--border", "unicode"],
+        &["--border", "ascii"],
+        &["--panels", "hex,offset"],
+        &["--panels", "offset,decimal,hex,ascii"],
+        &["--panels", "offset,ascii,decimal,hex", "-c7"],
+        &["--panels", "decimal,hex,ascii,offset"],
+        &["--charset", "utf16le"],
+    ];
+    for args in layouts {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let dump = cmd
+            .args(*args)
+            .arg(&path)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.arg("--reverse").write_stdin(dump).assert().success();
+        assert_eq!(assert.get_output().stdout, data, "{args:?}");
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let reverse = |dump: &str, words: Option<Endian>| {
+        let mut bytes = Vec::new();
+        reverse_dump_words(&mut dump.as_bytes(), &mut bytes, words).map(|_| bytes)
+    };
+    // --group 2 --endian big
+    assert_eq!(
+        reverse(
+            "0x000000: 0x6865 0x6c6c he ll\n0x000004: 6f          o\n",
+            Some(Endian::Big)
+        )
+        .unwrap(),
+        b"hello"
+    );
+    // xxd and xxd -g4 -u
+    assert_eq!(
+        reverse(
+            "00000000: 6865 6c6c 6f00 ff                        hello..\n",
+            None
+        )
+        .unwrap(),
+        b"hello\0\xff"
+    );
+    assert_eq!(
+        reverse(
+            "00000000: 68656C6C 6F00FF                      hello..\n",
+            None
+        )
+        .unwrap(),
+        b"hello\0\xff"
+    );
+    // hexdump -C, a repeated line left out and the end offset
+    let canonical = "00000000  61 62 63 00 00 00 00 00  00 00 00 00 00 00 00 00  |abc.............|
+00000010  41 41 41 41 41 41 41 41  41 41 41 41 41 41 41 41  |AAAAAAAAAAAAAAAA|
+*
+00000030  71                                                |q|
+00000031
+";
+    let mut expected = b"abc".to_vec();
+    expected.extend([0; 13]);
+    expected.extend([b'A'; 32]);
+    expected.push(b'q');
+    assert_eq!(reverse(canonical, None).unwrap(), expected);
+    // hexdump, little endian words with the last one padded
+    let words = "0000000 6261 0063 0000 0000 0000 0000 0000 0000
+0000010 4141 4141 4141 4141 4141 4141 4141 4141
+*
+0000030 0071
+0000031
+";
+    assert_eq!(reverse(words, Some(Endian::Little)).unwrap(), expected);
+}
+
+#[test]
+fn test_reverse_plain_and_0b() {
+    let path = env::temp_dir().join(format!("hx-reverse-0b-{}.bin", std::process::id()));
+    // bare cells and words starting with 0x0b read as hex, not binary
+    let data = b"\x0b\x01\x0b\x0b\x00\x11\x0b\x10\x01\x01hello\n";
+    std::fs::write(&path, data).unwrap();
+    let layouts: &[&[&str]] = &[
+        &["-r0"],
+        &["-fb"],
+        &["--output", "plain"],
+        &["--output", "plain", "-c4"],
+    ];
+    for args in layouts {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let dump = cmd
+            .args(*args)
+            .arg(&path)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.arg("--reverse").write_stdin(dump).assert().success();
+        assert_eq!(assert.get_output().stdout, data, "{args:?}");
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let reverse = |dump: &str| {
+        let mut bytes = Vec::new();
+        reverse_dump(&mut dump.as_bytes(), &mut bytes).map(|_| bytes)
+    };
+    // xxd words
+    assert_eq!(
+        reverse("00000000: 0b01 0b0b 0011  .....\n").unwrap(),
+        b"\x0b\x01\x0b\x0b\x00\x11"
+    );
+    // binary cells, unless the rest of the run reads as hex alone
+    assert_eq!(
+        reverse("00000000: 0b00001011 0b00000001 ..\n").unwrap(),
+        b"\x0b\x01"
+    );
+    assert_eq!(
+        reverse("00000000: 0b01010101 ab01020304\n").unwrap(),
+        b"\x0b\x01\x01\x01\x01\xab\x01\x02\x03\x04"
+    );
+    // xxd -p, then a line that isn't plain hex
+    assert_eq!(reverse("0b0102\n\nff\n").unwrap(), b"\x0b\x01\x02\xff");
+    assert!(reverse("0b0102\n0x000003: 0x01\n").is_err());
+}
+
+#[test]
+fn test_reverse_absolute() {
+    let path = env::temp_dir().join(format!("hx-absolute-{}.bin", std::process::id()));
+    std::fs::write(&path, [b'A'; 8]).unwrap();
+    let mut image = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    let dump = "0x000002: 0x62 0x63 bc\n0x000006: 0x64 d\n   bytes: 3\n";
+    assert_eq!(
+        reverse_dump_into(&mut dump.as_bytes(), &mut image, None).unwrap(),
+        5
+    );
+    assert_eq!(std::fs::read(&path).unwrap(), b"AAbcAAdA");
+    // past the end of the image
+    let dump = "0x00000c: 0x7a\n";
+    reverse_dump_into(&mut dump.as_bytes(), &mut image, None).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"AAbcAAdA\0\0\0\0z");
+    drop(image);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--reverse", "--absolute", "--outfile"])
+        .arg(&path)
+        .write_stdin("00000004: 7879\n")
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"AAbcxydA\0\0\0\0z");
+    // without --absolute the bytes start the file
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--reverse", "--outfile"])
+        .arg(&path)
+        .write_stdin("00000004: 7879\n")
+        .assert()
+        .success();
+    assert_eq!(std::fs::read(&path).unwrap(), b"xy");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cli_continue() {
+    let dir = env::temp_dir().join(format!("hx-continue-{}", std::process::id()));
+    let page = |extra: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .env("HX_STATE_DIR", &dir)
+            .args(["-t0", "-c4", "-l8", "--continue"])
+            .args(extra)
+            .arg("tests/files/lorem.md")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert!(page(&[]).starts_with("0x000000: "));
+    assert!(page(&[]).starts_with("0x000008: "));
+    assert!(page(&[]).starts_with("0x000010: "));
+    // --skip starts over from a given offset
+    assert!(page(&["--skip", "0x4"]).starts_with("0x000004: "));
+    assert!(page(&[]).starts_with("0x00000c: "));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--continue"])
+        .write_stdin("abc")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_session() {
+    let path = env::temp_dir().join(format!("hx-session-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .arg("--session")
+            .arg(&path)
+            .args(args)
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        run(&["-t0", "-c4", "-l6", "tests/files/lorem.md"]),
+        "0x000000: 0x23 0x20 0x4c 0x6f # Lo\n0x000004: 0x72 0x65           re\n   bytes: 6\n"
+    );
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("# hx session"));
+    assert!(saved.contains("\n--cols=4\n"));
+    assert!(saved.ends_with("\ntests/files/lorem.md\n"));
+    // the input and options come back, the command line overrides them
+    assert_eq!(
+        run(&["-c2"]),
+        "0x000000: 0x23 0x20 # \n0x000002: 0x4c 0x6f Lo\n0x000004: 0x72 0x65 re\n   bytes: 6\n"
+    );
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .contains("\n--cols=2\n"));
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--session").arg(&path).args(["echo", "00"]);
+    cmd.assert().failure();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cli_paging() {
+    let paged = |paging: &str| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .env("PAGER", "sed s/^/paged:/")
+            .args(["-t0", "--paging", paging])
+            .write_stdin("hx")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert!(paged("always").starts_with("paged:0x000000: 0x68 0x78"));
+    assert!(paged("always").contains("paged:   bytes: 2"));
+    // stdout isn't a terminal here, so auto doesn't page
+    assert!(paged("auto").starts_with("0x000000: "));
+    assert!(paged("never").starts_with("0x000000: "));
+}
+
+#[test]
+fn test_magic_detect() {
+    assert_eq!(
+        crate::magic::detect(b"\x7fELF\x02\x01"),
+        Some("ELF executable or object")
+    );
+    assert_eq!(
+        crate::magic::detect(b"RIFF\0\0\0\0WAVEfmt "),
+        Some("WAVE audio")
+    );
+    let mut tar = vec![0; 512];
+    tar[257..262].copy_from_slice(b"ustar");
+    assert_eq!(crate::magic::detect(&tar), Some("tar archive"));
+    assert_eq!(crate::magic::detect(b"# Lorem"), None);
+    assert_eq!(crate::magic::detect(b""), None);
+}
+
+/// Input with a PNG, a JPEG, a ZIP archive and a gzip stream embedded
+/// between other bytes.
+fn embedded_files() -> Vec<u8> {
+    let mut data = b"junk".to_vec();
+    data.extend_from_slice(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR");
+    data.extend_from_slice(&[0; 17]);
+    data.extend_from_slice(b"\0\0\0\0IEND\xaeB`\x82xx");
+    data.extend_from_slice(b"\xff\xd8\xff\xe0\0\x04ab\xff\xda\0\x02\x12\xff\0\x34\xff\xd9");
+    data.extend_from_slice(b"PK\x03\x04");
+    data.extend_from_slice(&[0; 10]);
+    data.extend_from_slice(b"PK\x05\x06");
+    data.extend_from_slice(&[0; 16]);
+    data.extend_from_slice(b"\x02\0hiPK\x03\x1f\x8b\x08\0\0\0\0\0\0\0");
+    data
+}
+
+#[test]
+fn test_carve_scan() {
+    use crate::carve::{scan, End};
+    let found = scan(&embedded_files());
+    let extents: Vec<_> = found
+        .iter()
+        .map(|file| (file.offset, file.len, file.ext, file.end))
+        .collect();
+    assert_eq!(
+        extents,
+        [
+            (0x04, 45, "png", End::Found),
+            (0x33, 18, "jpg", End::Found),
+            (0x45, 38, "zip", End::Found),
+            (0x6e, 10, "gz", End::Unknown),
+        ]
+    );
+    assert_eq!(found[0].kind, "PNG image");
+    // a cut off PNG runs to the end, a bare signature is no file
+    let png = &embedded_files()[4..30];
+    assert_eq!(scan(png)[0].end, End::CutOff);
+    assert!(scan(b"\x89PNG\r\n\x1a\n\0\0\0\0junk").is_empty());
+    assert!(scan(b"\xff\xd8\xff\0").is_empty());
+}
+
+#[test]
+fn test_cli_carve_auto() {
+    let dir = env::temp_dir().join(format!("hx-carve-auto-{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--carve-auto")
+        .arg(&dir)
+        .write_stdin(embedded_files())
+        .assert()
+        .success();
+    let file = |name: &str| dir.join(name).display().to_string();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        format!(
+            "0x000004: PNG image, 45 bytes -> {}\n\
+             0x000033: JPEG image, 18 bytes -> {}\n\
+             0x000045: ZIP archive, 38 bytes -> {}\n\
+             0x00006e: gzip compressed data, 10 bytes, end unknown -> {}\n\
+             \x20  bytes: 120\n\
+             \x20 carved: 4\n",
+            file("00000004.png"),
+            file("00000033.jpg"),
+            file("00000045.zip"),
+            file("0000006e.gz")
+        )
+    );
+    assert_eq!(
+        std::fs::read(dir.join("00000033.jpg")).unwrap(),
+        &embedded_files()[0x33..0x45]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_header() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "-l4",
+            "--header",
+            "--header-hash",
+            "tests/files/lorem.md",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("    file: tests/files/lorem.md\n    size: 139\n"));
+    assert!(stdout.contains("modified: "));
+    assert!(stdout.contains("    type: data\n options: -c 10 -f x -l 4\n"));
+    let sha256 = crate::hash::sha256_file("tests/files/lorem.md").unwrap();
+    assert!(stdout.contains(&format!("  sha256: {sha256}\n\n0x000000: ")));
+
+    // stdin has no size, time or hash, only what its first bytes tell
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "--header"])
+        .write_stdin("%PDF-1.7")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("    file: -\n    type: PDF document\n options: "));
+}
+
+#[test]
+fn test_output_format_registry() {
+    struct Offsets;
+    impl OutputFormat for Offsets {
+        fn line(&mut self, out: &mut dyn Write, line: &Line) -> io::Result<()> {
+            writeln!(out, "{}", offset(line.offset))
+        }
+        fn end(&mut self, out: &mut dyn Write, bytes: u64) -> io::Result<()> {
+            writeln!(out, "{bytes} bytes")
+        }
+    }
+    let mut config = parse_args(&["-c4", "-t0"]).unwrap();
+    let mut builtin = vec![
+        "dump",
+        "stacked",
+        "explain",
+        "transposed",
+        "raw",
+        "json",
+        "plain",
+        "html",
+        "array",
+    ];
+    if cfg!(feature = "zstd") {
+        builtin.push("zst");
+    }
+    assert_eq!(config.formats.names(), builtin);
+    config.formats.register("offsets", |_, _| Box::new(Offsets));
+    builtin.push("offsets");
+    assert_eq!(config.formats.names(), builtin);
+    assert!(config.formats.get("yaml").is_none());
+
+    let opts = dump_options(&config);
+    let constructor = config.formats.get("offsets").unwrap();
+    let mut out = OutputWriter::new(Vec::new(), FlushPolicy::Block);
+    let bytes = emit(
+        &mut out,
+        constructor(&config, opts).as_mut(),
+        &mut &b"abcdef"[..],
+        &opts,
+    );
+    assert_eq!(bytes.unwrap(), 6);
+    let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+    assert_eq!(text, "0x000000\n0x000004\n6 bytes\n");
+}
+
+#[test]
+fn test_transform_reader() {
+    // reads of one byte at a time still hand out whole groups
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(out.len()).min(1);
+            out[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+    let steps = [Step::ReverseBytes(4), Step::Xor(vec![0x01, 0x02])];
+    let mut reader = TransformReader::new(Trickle(b"\x00\x01\x02\x03\x04\x05"), &steps);
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).unwrap();
+    // 03 02 01 00, then the short group 04 05 as is, xored by offset
+    assert_eq!(data, [0x02, 0x00, 0x00, 0x02, 0x05, 0x07]);
+
+    let mut data = Vec::new();
+    TransformReader::new(&b"Hello, World"[..], &[Step::Rot(13)])
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"Uryyb, Jbeyq");
+}
+
+#[test]
+fn test_redact() {
+    let config = parse_args(&["-c8", "--redact", "2:4,9+2", "--xor", "0x01"]).unwrap();
+    assert_eq!(
+        config.transforms.last(),
+        Some(&Step::Redact {
+            ranges: vec![(2, 4), (9, 11)],
+            fill: 0
+        })
+    );
+    assert_eq!(
+        render_to_string(&config, b"`abcdefghijkl").unwrap(),
+        "0x000000: 0x61 0x60 0x00 0x00 0x65 0x64 0x67 0x66 a`..edgf  # 0x000002 redacted 2 bytes\n\
+         0x000008: 0x69 0x00 0x00 0x6a 0x6d                i..jm  # 0x000009 redacted 2 bytes\n\
+         \x20  bytes: 13\n"
+    );
+    let config = parse_args(&["-ar", "--redact", "1+1", "--redact-fill", "0x2a"]).unwrap();
+    assert_eq!(
+        render_to_string(&config, b"abc").unwrap(),
+        "let ARRAY: [u8; 3] = [\n    0x61, 0x2a, 0x63\n];\n"
+    );
+    assert!(parse_args(&["--redact", "4:2"]).is_err());
+    assert!(parse_args(&["--redact", "1+1", "--redact-fill", "256"]).is_err());
+    assert!(parse_args(&["--redact-fill", "1"]).is_err());
+}
+
+#[test]
+fn test_cli_transform_order() {
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0"])
+            .args(args)
+            .write_stdin("a")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert!(run(&["--xor", "1", "--rot", "1"]).starts_with("0x000000: 0x60 "));
+    assert!(run(&["--rot", "1", "--xor", "0x01"]).starts_with("0x000000: 0x63 "));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--xor", "0x5"])
+        .write_stdin("a")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_input_source() {
+    assert_eq!(InputSource::new(None), InputSource::Stdin);
+    assert_eq!(InputSource::new(Some(Path::new("-"))), InputSource::Stdin);
+    // whether stdin is live depends on what the test runner gave it
+    let stdin = InputSource::Stdin.capabilities();
+    assert!(!stdin.seekable);
+    assert_eq!(stdin.len, None);
+    let lorem = InputSource::new(Some(Path::new("tests/files/lorem.md")));
+    assert_eq!(lorem.path(), Some(Path::new("tests/files/lorem.md")));
+    assert_eq!(
+        lorem.capabilities(),
+        Capabilities {
+            seekable: true,
+            len: Some(139),
+            live: false,
+        }
+    );
+    assert!(lorem.open().unwrap().file().is_some());
+    // directories and missing files only fail once opened
+    assert_eq!(
+        InputSource::new(Some(Path::new("tests/files"))).capabilities(),
+        Capabilities::default()
+    );
+    assert!(InputSource::new(Some(Path::new("tests/files/missing")))
+        .open()
+        .is_err());
+}
+
+#[test]
+fn test_cli_input_dash_is_stdin() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t0", "-"]).write_stdin("hx").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000000: 0x68 0x78 "));
+}
+
+#[test]
+fn test_records() {
+    let mut warnings = Vec::new();
+    let mut warn = |e: &Malformed| warnings.push(e.clone());
+    // an extended linear address moves the following data records
+    let ihex =
+        b":0400100068656C6C47\r\n:020000040800F2\n:0200000068692D\n:00000001FF\n:0200000068692D\n";
+    let records = records::parse(ihex, RecordFormat::Ihex, true, &mut warn).unwrap();
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                address: 0x10,
+                data: b"hell".to_vec(),
+            },
+            Record {
+                address: 0x0800_0000,
+                data: b"hi".to_vec(),
+            },
+        ]
+    );
+    let srec = b"S00600004844521B\nS107001068656C6C43\nS20800001468656C6C3E\nS9030000FC\n";
+    let records = records::parse(srec, RecordFormat::Srec, true, &mut warn).unwrap();
+    assert_eq!(
+        records::assemble(&records, 0, Conflict::Last),
+        (0x10, b"hellhell".to_vec())
+    );
+    let text = b"68 65 0x6c,6C\n\n6f\n";
+    let records = records::parse(text, RecordFormat::HexText, true, &mut warn).unwrap();
+    assert_eq!(
+        records::assemble(&records, 0, Conflict::Last),
+        (0, b"hello".to_vec())
+    );
+    assert!(warnings.is_empty());
+
+    // strict mode fails on the first malformed line, naming its offset
+    let bad = b":0400100068656C6C47\n:0400140068656C6C00\n";
+    let e = records::parse(bad, RecordFormat::Ihex, true, &mut |_| {}).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "line 2, offset 0x000025: checksum 0x00, expected 0x43"
+    );
+    let e = records::parse(b"68 65\n6fzz\n", RecordFormat::HexText, true, &mut |_| {}).unwrap_err();
+    assert_eq!((e.line, e.offset), (2, 8));
+    assert_eq!(e.reason, "invalid hex digit 'z'");
+
+    // otherwise the line is skipped with a warning
+    let mut warnings = Vec::new();
+    let records = records::parse(bad, RecordFormat::Ihex, false, &mut |e| {
+        warnings.push(e.clone())
+    })
+    .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line, 2);
+}
+
+#[test]
+fn test_cli_from() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--from", "ihex"])
+        .write_stdin(":0400100068656C6C47\n:02001400FFFFxx\n:00000001FF\n")
+        .assert()
+        .success();
+    let output = assert.get_output();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "0x000010: 0x68 0x65 0x6c 0x6c hell\n   bytes: 4\n"
+    );
+    // a warning, as hx goes on and exits 0
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "warning: line 2, offset 0x000021: invalid hex digit 'x', skipped\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--from", "ihex", "--strict"])
+        .write_stdin(":0400100068656C6C47\n:02001400FFFFxx\n")
+        .assert()
+        .failure()
+        .code(1);
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("--strict").write_stdin("").assert().failure();
+}
+
+#[test]
+fn test_records_fill() {
+    let records = [
+        Record {
+            address: 0x14,
+            data: b"ab".to_vec(),
+        },
+        Record {
+            address: 0x10,
+            data: b"hell".to_vec(),
+        },
+        Record {
+            address: 0x20,
+            data: b"hi".to_vec(),
+        },
+    ];
+    assert_eq!(
+        records::segments(&records, Conflict::Last),
+        vec![(0x10, b"hellab".to_vec()), (0x20, b"hi".to_vec())]
+    );
+    let (base, image) = records::assemble(&records, 0xff, Conflict::Last);
+    assert_eq!(base, 0x10);
+    assert_eq!(image, b"hellab\xff\xff\xff\xff\xff\xff\xff\xff\xff\xffhi");
+    assert_eq!(
+        records::assemble(&[], 0xff, Conflict::Last),
+        (0, Vec::new())
+    );
+    assert_eq!(
+        parse_args(&["--from", "ihex", "--fill", "0xff"])
+            .unwrap()
+            .fill,
+        Fill::Byte(0xff)
+    );
+    assert_eq!(parse_args(&["--from", "ihex"]).unwrap().fill, Fill::Byte(0));
+    assert!(parse_args(&["--from", "ihex", "--fill", "256"]).is_err());
+    assert!(parse_args(&["--from", "ihex", "--fill", "skip", "-ar"]).is_err());
+    assert!(parse_args(&["--fill", "0"]).is_err());
+}
+
+#[test]
+fn test_render_gaps() {
+    let mut config = parse_args(&["-c8", "--from", "ihex", "--fill", "skip"]).unwrap();
+    let ihex = b":0400100068656C6C47\n:0200200068690D\n:00000001FF\n";
+    assert_eq!(
+        render_to_string(&config, ihex).unwrap(),
+        "0x000010: 0x68 0x65 0x6c 0x6c                     hell\n\
+         <gap> 0x000014..0x000020\n\
+         0x000020: 0x68 0x69                               hi\n   \
+         bytes: 6\n"
+    );
+    config.fill = Fill::Byte(0xff);
+    assert!(render_to_string(&config, ihex)
+        .unwrap()
+        .contains("0x000018: 0xff 0xff 0xff 0xff 0xff 0xff 0xff 0xff ........\n"));
+}
+
+#[test]
+fn test_records_conflict() {
+    let record = |address: u64, data: &[u8]| Record {
+        address,
+        data: data.to_vec(),
+    };
+    let records = [
+        record(0x10, b"hello"),
+        record(0x12, b"LL"),
+        record(0x20, b"ab"),
+        record(0x13, b"O!"),
+    ];
+    assert_eq!(records::overlaps(&records), vec![(0x12, 0x15)]);
+    assert_eq!(records::overlaps(&records[..3]), vec![(0x12, 0x14)]);
+    assert!(records::overlaps(&[record(0, b"ab"), record(2, b"cd")]).is_empty());
+    assert_eq!(
+        records::segments(&records, Conflict::Last),
+        vec![(0x10, b"heLO!".to_vec()), (0x20, b"ab".to_vec())]
+    );
+    assert_eq!(
+        records::segments(&records, Conflict::First),
+        vec![(0x10, b"hello".to_vec()), (0x20, b"ab".to_vec())]
+    );
+
+    let ihex = ":0400100068656C6C47\n:02001200414C5F\n";
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--from", "ihex"])
+        .write_stdin(ihex)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("records overlap at 0x000012..0x000014"));
+    let config = parse_args(&["-c4", "--from", "ihex", "--on-conflict", "last"]).unwrap();
+    assert!(render_to_string(&config, ihex.as_bytes())
+        .unwrap()
+        .starts_with("0x000010: 0x68 0x65 0x41 0x4c heAL\n"));
+}
+
+#[test]
+fn test_banks() {
+    let banks = Banks {
+        size: 0x4000,
+        stride: 0x10000,
+    };
+    assert_eq!(banks.translate(0x3fff), 0x3fff);
+    assert_eq!(banks.translate(0xc010), 0x3_0010);
+    assert_eq!(Banks::default().translate(0xc010), 0xc010);
+    let config = parse_args(&["--bank-size", "4", "--bank-stride", "0x100", "-c4"]).unwrap();
+    assert_eq!(
+        config.banks,
+        Banks {
+            size: 4,
+            stride: 0x100
+        }
+    );
+    let dump = render_to_string(&config, b"abcdefgh").unwrap();
+    assert!(dump.starts_with("0x000000: 0x61 0x62 0x63 0x64 abcd\n0x000100: 0x65 "));
+    assert!(parse_args(&["--bank-size", "4"]).is_err());
+    assert!(parse_args(&["--bank-size", "0", "--bank-stride", "4"]).is_err());
+}
+
+#[test]
+fn test_source_map() {
+    assert_eq!(SourceMap::default().translate(0x10), (0x10, false));
+    let lanes = SourceMap::new(Some((4, 1)));
+    assert_eq!(lanes.translate(3), (13, false));
+    let stored = lanes.within(vec![(0, 0x200)], false);
+    assert_eq!(stored.translate(3), (0x20d, false));
+    let frames = SourceMap::default().within(vec![(0, 0), (0x100, 0x40)], true);
+    assert_eq!(frames.translate(0xff), (0, true));
+    assert_eq!(frames.translate(0x180), (0x40, true));
+    let config = parse_args(&["--source-offsets", "--xor", "0x20", "-c4"]).unwrap();
+    let dump = render_to_string(&config, b"ABCDEFGH").unwrap();
+    assert!(dump.starts_with("0x000000 @0x000000: 0x61 0x62 0x63 0x64 abcd\n0x000004 @0x000004: "));
+    let config = parse_args(&[
+        "--source-offsets",
+        "--deinterleave",
+        "2",
+        "--lane",
+        "1",
+        "-c2",
+    ])
+    .unwrap();
+    let dump = render_to_string(&config, b"aAbBcCdD").unwrap();
+    assert!(
+        dump.starts_with("0x000000 @0x000001: 0x41 0x42 AB\n0x000002 @0x000005: 0x43 0x44 CD\n")
+    );
+    assert!(parse_args(&["--source-offsets", "--concat", "a", "b"]).is_err());
+}
+
+#[test]
+#[cfg(feature = "archives")]
+fn test_cli_source_offsets_tar() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--member", "fw/boot.bin", "--source-offsets", "-t0", "-c4"])
+        .write_stdin(tar_with("fw/boot.bin", b"il\n"))
+        .assert();
+    assert
+        .success()
+        .stdout("0x000000 @0x000200: 0x69 0x6c 0x0a      il.\n   bytes: 3\n");
+}
+
+#[test]
+fn test_cli_colors() {
+    assert_eq!(colors::class(0x0a), "whitespace");
+    assert_eq!(colors::class(0x7f), "control");
+    assert_eq!(colors::ranges("control"), "0x01-0x08, 0x0e-0x1f, 0x7f");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t0", "colors"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(stdout.lines().count(), 16 + 1 + colors::CLASSES.len());
+    assert!(stdout.starts_with("0x000000: 0x00 0x01 "));
+    assert!(stdout.contains("0x0000f0: 0xf0 "));
+    assert!(stdout.ends_with("high       0x80-0xff\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t1", "colors"]).assert().success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stdout).contains("\x1b[38;5;255m0xff"));
+}
+
+#[test]
+fn test_no_wrap() {
+    let input = b"hello, world";
+    let config = parse_args(&["-c0"]).unwrap();
+    assert_eq!(
+        render_to_string(&config, input).unwrap(),
+        "0x000000: 0x68 0x65 0x6c 0x6c 0x6f 0x2c 0x20 0x77 0x6f 0x72 0x6c 0x64 hello, world\n   \
+         bytes: 12\n"
+    );
+    let config = parse_args(&["--no-wrap", "--separator", "", "-r0"]).unwrap();
+    assert_eq!(config.columns, 0);
+    assert!(render_to_string(&config, input)
+        .unwrap()
+        .starts_with("0x000000: 68656c6c6f2c20776f726c64 hello, world\n"));
+    let config = parse_args(&["-c4", "--separator", ","]).unwrap();
+    assert_eq!(
+        render_to_string(&config, b"hello").unwrap(),
+        "0x000000: 0x68,0x65,0x6c,0x6c, hell\n0x000004: 0x6f,                o\n   bytes: 5\n"
+    );
+    assert!(parse_args(&["--no-wrap", "-c4"]).is_err());
+    assert!(parse_args(&["-c0", "--align"]).is_err());
+    assert!(parse_args(&["-ar", "--separator", ","]).is_err());
+}
+
+#[test]
+fn test_cols_auto() {
+    let config = parse_args(&["-fb", "-c3"]).unwrap();
+    assert_eq!(
+        render_to_string(&config, b"hello").unwrap(),
+        "0x000000: 0b01101000 0b01100101 0b01101100 hel\n\
+         0x000003: 0b01101100 0b01101111            lo\n   bytes: 5\n"
+    );
+    let config = parse_args(&["-fb", "-cauto"]).unwrap();
+    assert_eq!(config.fit_columns(80), 5);
+    assert_eq!(config.fit_columns(20), 1);
+    let config = parse_args(&["-fb", "-r0", "-cauto"]).unwrap();
+    assert_eq!(config.fit_columns(80), 7);
+    let config = parse_args(&["-cauto"]).unwrap();
+    assert_eq!(config.fit_columns(80), 11);
+    assert_eq!(config.fit_columns(60), 8);
+}
+
+#[test]
+fn test_exp_format() {
+    let mut input = Vec::new();
+    for value in [1.5f32, -2.25e-3, 3.4e38] {
+        input.extend_from_slice(&value.to_le_bytes());
+    }
+    input.extend_from_slice(b"ab");
+    let config = parse_args(&["-fe", "-c8"]).unwrap();
+    assert_eq!(config.columns, 8);
+    assert_eq!(
+        render_to_string(&config, &input).unwrap(),
+        "0x000000:    1.500000e0  -2.250000e-3 ...?.t..\n\
+         0x000008:   3.400000e38          6162 ....ab\n   bytes: 14\n"
+    );
+    let config = parse_args(&["-fE", "--float", "f64be"]).unwrap();
+    assert_eq!(config.columns, 16);
+    assert!(render_to_string(&config, &1e-300f64.to_be_bytes())
+        .unwrap()
+        .starts_with("0x000000:  1.000000E-300                "));
+    assert_eq!(parse_args(&["-fe", "-cauto"]).unwrap().fit_columns(80), 12);
+    assert_eq!(
+        Format::LowerExp(FloatWord::default()).format(200, false),
+        "2e2"
+    );
+    assert!(parse_args(&["-fe", "-c6"]).is_err());
+    assert!(parse_args(&["-fe", "--align"]).is_err());
+    assert!(parse_args(&["-fe", "--float", "f16"]).is_err());
+    assert!(parse_args(&["--float", "f64"]).is_err());
+}
+
+#[test]
+fn test_cli_unknown_format() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-a", "ruts", "tests/files/tiny.txt"]).assert();
+    let stderr =
+        String::from_utf8_lossy(&assert.failure().code(2).get_output().stderr).into_owned();
+    assert!(stderr.contains("r (rust), c, g (golang), p (python)"));
+    assert!(stderr.contains("did you mean 'r' (rust)?"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-f", "q", "tests/files/tiny.txt"]).assert();
+    let stderr =
+        String::from_utf8_lossy(&assert.failure().code(2).get_output().stderr).into_owned();
+    assert!(stderr.contains("o (octal), x (hex), X (upperhex), b (binary)"));
+    assert!(!stderr.contains("did you mean"));
+    let config = parse_args(&["-a", "golang"]).unwrap();
+    assert_eq!(config.mode, Mode::Array("g".to_string()));
+    let config = parse_args(&["-f", "Binary"]).unwrap();
+    assert_eq!(config.format.format(1, false), "00000001");
+}
+
+#[test]
+fn test_named_values() {
+    assert_eq!(
+        parse_args(&["--color", "always"]).unwrap().color,
+        Some(true)
+    );
+    assert_eq!(parse_args(&["-t", "off"]).unwrap().color, Some(false));
+    assert_eq!(parse_args(&["--no-color"]).unwrap().color, Some(false));
+    assert_eq!(parse_args(&["--color", "auto"]).unwrap().color, None);
+    assert_eq!(parse_args(&["-t1"]).unwrap().color, Some(true));
+    assert!(!parse_args(&["--prefix", "off"]).unwrap().prefix);
+    assert!(parse_args(&["-r1"]).unwrap().prefix);
+    assert_eq!(
+        parse_args(&["--array", "go"]).unwrap().mode,
+        Mode::Array("g".to_string())
+    );
+    assert_eq!(
+        parse_args(&["-f", "oct"]).unwrap().format.format(8, true),
+        "0o0010"
+    );
+    assert!(parse_args(&["--no-color", "-t1"]).is_err());
+    assert!(parse_args(&["--color", "yes"]).is_err());
+    assert!(parse_args(&["--prefix", "2"]).is_err());
+}
+
+#[test]
+fn test_cli_capabilities() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--capabilities").assert();
+    let stdout = String::from_utf8_lossy(&assert.success().get_output().stdout).into_owned();
+    assert!(stdout.starts_with("{\n  \"name\": \"hx\",\n"));
+    assert!(stdout.contains("{\"value\": \"r\", \"name\": \"rust\"}"));
+    assert!(stdout.contains("{\"value\": \"b\", \"name\": \"binary\"}"));
+    assert!(stdout.contains("\"outputs\": [\"dump\", \"stacked\", \"explain\""));
+    assert!(stdout.contains("\"selftest\""));
+    assert!(stdout.contains("\"capabilities\""));
+    let archives = format!("\"archives\": {}", cfg!(feature = "archives"));
+    assert!(stdout.contains(&archives));
+    assert!(stdout.ends_with("}\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--capabilities", "-c4"])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn test_search_finder() {
+    let naive = |haystack: &[u8], needle: &[u8], from: usize| {
+        (from..=haystack.len().saturating_sub(needle.len()))
+            .find(|at| haystack[*at..].starts_with(needle))
+    };
+    let haystack: Vec<u8> = (0..4096u32).map(|i| (i * 7 % 13) as u8).collect();
+    for needle in [
+        &[3u8][..],
+        &[3, 10],
+        &[12, 6, 0, 7],
+        &[0, 7, 1, 8, 2, 9],
+        &[5, 5],
+    ] {
+        let finder = search::Finder::new(needle);
+        for from in [0, 1, 17, 4000, 4096] {
+            assert_eq!(finder.find(&haystack, from), naive(&haystack, needle, from));
+        }
+    }
+    assert_eq!(search::Finder::new(b"ab").find(b"xxab", 5), None);
+    assert_eq!(search::Finder::new(b"aab").find(b"aaaab", 0), Some(2));
+}
+
+#[test]
+fn test_cli_count_matches() {
+    // a match across two reads is counted once
+    let finder = search::Finder::new(b"aa");
+    let mut split = (&b"xa"[..]).chain(&b"aab"[..]);
+    assert_eq!(finder.count(&mut split).unwrap(), (2, 5));
+    let long = [vec![b'a'; 70_000], b"b".to_vec()].concat();
+    assert_eq!(finder.count(&mut &long[..]).unwrap(), (69_999, 70_001));
+
+    let count = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.args(args).write_stdin("aaaa").assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(count(&["--find", "'aa'", "--count-matches"]), "3\n");
+    assert_eq!(
+        count(&["--find", "'aa'", "--count-matches", "-s1", "-l2"]),
+        "1\n"
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--files-from", "-", "--find", "'i'", "--count-matches"])
+        .write_stdin("tests/files/tiny.txt\ntests/files/tiny.txt\n")
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "tests/files/tiny.txt:1\ntests/files/tiny.txt:1\n"
+    );
+}
+
+#[test]
+fn test_stacked() {
+    let config = parse_args(&["--stacked", "-c4"]).unwrap();
+    assert_eq!(config.output, "stacked");
+    assert_eq!(
+        render_to_string(&config, b"hi\t\x00!").unwrap(),
+        "0x000000: 0x68 0x69 0x09 0x00 \n             h    i   \\t   \\0 \n\
+         0x000004: 0x21 \n             ! \n   bytes: 5\n"
+    );
+    assert!(parse_args(&["--stacked", "-ar"]).is_err());
+}
+
+#[test]
+fn test_explain() {
+    assert_eq!(ascii::describe(b'\n'), "LF (line feed)");
+    assert_eq!(ascii::describe(0x7f), "DEL (delete)");
+    assert_eq!(ascii::describe(b'A'), "A");
+    assert_eq!(ascii::describe(0x80), "not ascii");
+    let config = parse_args(&["--explain", "-c4", "-s1", "--align"]).unwrap();
+    assert_eq!(
+        render_to_string(&config, b"hi\n").unwrap(),
+        "0x000001: 0b01101001 0o151 105 0x69 i\n\
+         0x000002: 0b00001010 0o012  10 0x0a LF (line feed)\n   \
+         bytes: 2\n"
+    );
+    assert!(parse_args(&["--explain", "--stacked"]).is_err());
+}
+
+#[test]
+fn test_cli_table() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t0", "table"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x00 NUL   0x20 SP    0x40 @     0x60 `\n"));
+    assert!(stdout.contains("0x1f US    0x3f ?     0x5f _     0x7f DEL\n"));
+    assert!(stdout.contains("\nLF  line feed\n"));
+    assert!(stdout.ends_with("DEL delete\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-f", "X", "-r0", "table", "--extended"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("41 A     61 a     81 .     A1 ¡     C1 Á     E1 á\n"));
+}
+
+#[test]
+fn test_conv() {
+    use crate::conv::{describe, parse};
+    assert_eq!(parse("0xDEAD_beef"), Ok(0xdead_beef));
+    assert_eq!(parse("0o17"), Ok(0o17));
+    assert_eq!(parse("0b1010"), Ok(10));
+    assert_eq!(parse("42"), Ok(42));
+    assert_eq!(parse("-1"), Ok(u64::MAX));
+    assert_eq!(parse("-9223372036854775808"), Ok(1 << 63));
+    assert!(parse("-9223372036854775809").is_err());
+    assert!(parse("0xg").is_err());
+    assert!(parse("").is_err());
+
+    let mut out = Vec::new();
+    describe(&mut out, 0x1ff, false).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("      le: ff 01\n      be: 01 ff\n"));
+    assert!(text.contains("      i8: -1 (truncated)\n     i16: 511\n"));
+
+    let mut out = Vec::new();
+    describe(&mut out, parse("-17").unwrap(), true).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("      le: ef\n"));
+    assert!(text.ends_with("      i8: -17\n     i16: -17\n     i32: -17\n     i64: -17\n"));
+
+    // a literal without a sign takes as many bytes as its magnitude
+    let bytes = |value, negative| {
+        let mut out = Vec::new();
+        describe(&mut out, value, negative).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let le = text.lines().find(|line| line.starts_with("      le: "));
+        le.unwrap()[10..].to_string()
+    };
+    assert_eq!(bytes(0, false), "00");
+    assert_eq!(bytes(0xff, false), "ff");
+    assert_eq!(bytes(0xffff, false), "ff ff");
+    assert_eq!(bytes(0x10000, false), "00 00 01");
+    assert_eq!(bytes(u64::MAX, false), "ff ff ff ff ff ff ff ff");
+    assert_eq!(bytes(parse("-1").unwrap(), true), "ff");
+    assert_eq!(bytes(parse("-129").unwrap(), true), "7f ff");
+}
+
+#[test]
+fn test_cli_conv() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["conv", "0xdeadbeef"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("     hex: 0xdeadbeef\n     dec: 3735928559\n"));
+    assert!(stdout.contains("      be: de ad be ef\n"));
+    assert!(stdout.contains("     i32: -559038737\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["conv", "0xff"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("      le: ff\n      be: ff\n      i8: -1\n     i16: 255\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["conv", "0x"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("conv <number> expected"));
+}
+
+#[test]
+fn test_literal() {
+    use crate::literal::parse;
+    assert_eq!(parse("de ad,beef"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(
+        parse("0x10 0o17 #65 -1 -0x80"),
+        Ok(vec![0x10, 0o17, 65, 0xff, 0x80])
+    );
+    assert_eq!(parse("'A' \"hi\\n\" '\\x21\\''"), Ok(b"Ahi\n!'".to_vec()));
+    assert_eq!(parse("00*3 'ab'*2"), Ok(b"\0\0\0abab".to_vec()));
+    assert_eq!(parse(" "), Ok(vec![]));
+    assert!(parse("abc").is_err());
+    assert!(parse("+1").is_err());
+    assert!(parse("0x100").is_err());
+    assert!(parse("#-129").is_err());
+    assert!(parse("'open").is_err());
+    assert!(parse("'\\q'").is_err());
+    assert!(parse("00*").is_err());
+    // repeats past 1 GiB fail up front, nothing repeated takes no time
+    assert_eq!(
+        parse("00*99999999999"),
+        Err("00*99999999999 is too long, a literal is at most 1 GiB".to_string())
+    );
+    assert!(parse("'0123456789'*0x8000000").is_err());
+    assert_eq!(parse("''*0xffffffffffffffff"), Ok(vec![]));
+}
+
+#[test]
+fn test_generate() {
+    use crate::generate::parse;
+    use std::io::Read;
+    assert_eq!(
+        parse("count:0..3"),
+        Ok(InputSource::Literal(vec![0, 1, 2, 3]))
+    );
+    assert_eq!(
+        parse("count:0x12..0x10"),
+        Ok(InputSource::Literal(vec![0x12, 0x11, 0x10]))
+    );
+    assert_eq!(
+        parse("repeat:dead*2"),
+        Ok(InputSource::Literal(vec![0xde, 0xad, 0xde, 0xad]))
+    );
+    let Ok(InputSource::Lfsr(lfsr)) = parse("lfsr:poly=0x1d,seed=0xff,len=510") else {
+        panic!("lfsr expected");
+    };
+    assert_eq!(
+        (lfsr.poly, lfsr.seed, lfsr.width, lfsr.len),
+        (0x1d, 0xff, 8, 510)
+    );
+    let mut bytes = Vec::new();
+    lfsr.reader().read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 510);
+    assert_eq!(bytes[..4], [0xf4, 0xcd, 0x46, 0x0e]);
+    // a primitive polynomial cycles through all 255 states, so 8 * 255 bits repeat after 255 bytes
+    assert_eq!(bytes[..255], bytes[255..]);
+    assert!(bytes[..255].windows(2).any(|w| w[0] != w[1]));
+    assert!(parse("count:0..256").is_err());
+    assert!(parse("count:5").is_err());
+    assert!(parse("lfsr:poly=0x1d").is_err());
+    assert!(parse("lfsr:poly=0x1d,len=8,seed=0").is_err());
+    assert!(parse("lfsr:poly=0x100,len=8").is_err());
+    assert!(parse("lfsr:poly=0x3,len=8,width=33").is_err());
+    assert!(parse("lfsr:poly=0x1d,len=8,taps=3").is_err());
+    assert!(parse("random:8").is_err());
+    assert!(parse("count").is_err());
+}
+
+#[test]
+fn test_cli_generate() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--raw", "--generate", "count:0x41..0x44"])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"ABCD");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "--generate", "lfsr:poly=0x1d,seed=0xff,len=4"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000000: 0xf4 0xcd 0x46 0x0e"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-ar", "--generate", "repeat:dead*2"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("0xde, 0xad, 0xde, 0xad"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--generate", "count:0..1", "echo", "00"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("--generate <expression> expected"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--generate", "count:0..1", "file.bin"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_echo() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--raw", "echo", "7f 'ELF' 00*2"])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"\x7fELF\0\0");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t0", "echo", "de ad"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000000: 0xde 0xad"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-ar", "echo", "de ad"]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("0xde, 0xad"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["echo", "0x100"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("\"0x100\" doesn't fit in a byte"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["echo", "00*99999999999"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("00*99999999999 is too long"), "{stderr}");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--raw", "echo", "''*0xffffffffffffffff"])
+        .assert()
+        .success();
+    assert!(assert.get_output().stdout.is_empty());
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["file.bin", "echo", "de ad"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(
+        stderr.contains("the literal is the input, drop \"file.bin\""),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_fill() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "fill", "--len", "10", "--pattern", "de ad be ef"])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "0x000000: 0xde 0xad 0xbe 0xef 0xde 0xad 0xbe 0xef 0xde 0xad ..........\n\
+         \x20  bytes: 10\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--raw", "fill", "--len", "64KiB", "--pattern", "0xff"])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, vec![0xff; 0x10000]);
+    let config = parse_args(&["fill", "--len", "0x10"]).unwrap();
+    assert_eq!(
+        config.input,
+        InputSource::Pattern {
+            bytes: vec![0],
+            len: 0x10
+        }
+    );
+    assert!(parse_args(&["fill", "--len", "4", "--pattern", ""]).is_err());
+    assert!(parse_args(&["fill", "--len", "4", "--pattern", "0x100"]).is_err());
+}
+
+#[test]
+fn test_cli_concat_interleave() {
+    let dir = std::env::temp_dir().join(format!("hx-interleave-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let lo = dir.join("lo.bin");
+    let hi = dir.join("hi.bin");
+    std::fs::write(&lo, b"ACEG").unwrap();
+    std::fs::write(&hi, b"BDFHIJ").unwrap();
+    let (lo, hi) = (lo.to_str().unwrap(), hi.to_str().unwrap());
+    let raw = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd.arg("--raw").args(args).assert().success();
+        assert.get_output().stdout.clone()
+    };
+    assert_eq!(raw(&["--concat", lo, hi]), b"ACEGBDFHIJ");
+    assert_eq!(raw(&["--interleave", lo, hi]), b"ABCDEFGH");
+    assert_eq!(raw(&["--interleave", lo, hi, "--word", "2"]), b"ACBDEGFH");
+    assert_eq!(raw(&["--interleave", hi, lo, "--word", "3"]), b"BDFACEHIJG");
+    assert_eq!(
+        raw(&["--interleave", lo, hi, "--skip", "2", "--len", "4"]),
+        b"CDEF"
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.args(["-t0", "--interleave", lo, hi]).assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("0x000000: 0x41 0x42 0x43 0x44 0x45 0x46 0x47 0x48"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--word", "2", lo]).assert().failure();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--raw", "-ar", lo]).assert().failure();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_read_ahead() {
+    let data: Vec<u8> = (0..=255).collect();
+    let mut ahead = ReadAhead::new(std::io::Cursor::new(data.clone()), 7);
+    assert_eq!(ahead.fill_buf().unwrap(), &data[..7]);
+    ahead.consume(3);
+    let mut read = Vec::new();
+    ahead.read_to_end(&mut read).unwrap();
+    assert_eq!(read, &data[3..]);
+    assert!(ahead.fill_buf().unwrap().is_empty());
+
+    let dump = |readahead: &str| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "--io-readahead", readahead, "tests/files/lorem.md"])
+            .assert();
+        assert.success().get_output().stdout.clone()
+    };
+    assert_eq!(dump("5"), dump("0"));
+    assert_eq!(dump("0x100000"), dump("0"));
+}
+
+#[test]
+fn test_cli_direct_io() {
+    let dump = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(["-t0", "tests/files/lorem.md"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let direct = dump(&["--direct-io"]);
+    match direct.status.success() {
+        true => assert_eq!(direct.stdout, dump(&[]).stdout),
+        // not Linux, or a filesystem without O_DIRECT
+        false => assert!(String::from_utf8_lossy(&direct.stderr).contains("direct I/O")),
+    }
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("--direct-io").write_stdin("ab").assert();
+    let stderr = String::from_utf8_lossy(&assert.failure().get_output().stderr).into_owned();
+    assert!(stderr.contains("direct I/O needs a file input"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--direct-io", "--io-readahead", "0", "tests/files/lorem.md"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_deinterleave() {
+    let read = |lanes, lane| {
+        let mut kept = Vec::new();
+        Deinterleave::new(&b"ABCDEFGHIJ"[..], lanes, lane)
+            .read_to_end(&mut kept)
+            .unwrap();
+        kept
+    };
+    assert_eq!(read(2, 0), b"ACEGI");
+    assert_eq!(read(2, 1), b"BDFHJ");
+    assert_eq!(read(3, 2), b"CFI");
+    assert_eq!(read(1, 0), b"ABCDEFGHIJ");
+    assert_eq!(read(16, 12), b"");
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "--raw",
+            "--deinterleave",
+            "2",
+            "--lane",
+            "1",
+            "echo",
+            "'ABCDEFGH'",
+        ])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"BDFH");
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--deinterleave", "2", "--lane", "2", "echo", "00"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("count from 0 to 1"));
+}
+
+#[test]
+fn test_transpose() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "-c2",
+            "--transpose",
+            "3",
+            "echo",
+            "'ab1' 'cd2' 'ef3'",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "0x000000: 0x61 0x63 ac\n\
+         0x000001: 0x62 0x64 bd\n\
+         0x000002: 0x31 0x32 12\n\
+         \n\
+         0x000006: 0x65      e\n\
+         0x000007: 0x66      f\n\
+         0x000008: 0x33      3\n\
+         \x20  bytes: 9\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--transpose", "0", "echo", "00"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("records are at least a byte"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--transpose", "4", "--stacked", "echo", "00"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_rle() {
+    use crate::dedupe::{runs, Run};
+    let run = |offset, len, byte| Run { offset, len, byte };
+    assert_eq!(
+        runs(b"ab\xff\xff\xff\xffc\0\0\0", 3),
+        vec![run(2, 4, 0xff), run(7, 3, 0)]
+    );
+    assert_eq!(runs(b"aabbb", 3), vec![run(2, 3, b'b')]);
+    assert_eq!(runs(b"", 2), vec![]);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--rle", "4", "echo", "01 ff*6 02 03"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "0x000001-0x000006: 6 bytes of 0xff\n\
+         \n\
+         0x000000: 0x01                .\n\
+         0x000001: 0xff x 6\n\
+         0x000007: 0x02 0x03           ..\n\
+         \x20  bytes: 9\n\
+         \x20   runs: 1 runs, 6 bytes\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--rle", "1", "echo", "00"]).assert().failure();
+}
+
+#[test]
+fn test_match() {
+    use crate::masked::first_mismatch;
+    assert_eq!(first_mismatch(b"abcd", b"abcd", b""), None);
+    assert_eq!(first_mismatch(b"abXd", b"abcd", b""), Some(2));
+    assert_eq!(first_mismatch(b"abXd", b"abcd", b"\xff\xff\x00"), None);
+    // only the low nibble is compared
+    assert_eq!(first_mismatch(b"\x12", b"\xf2", b"\x0f"), None);
+    assert_eq!(first_mismatch(b"\x13", b"\xf2", b"\x0f"), Some(0));
+    assert_eq!(first_mismatch(b"ab", b"abc", b""), Some(2));
+    assert_eq!(first_mismatch(b"abcdef", b"abc", b""), None);
+
+    let dir = std::env::temp_dir().join(format!("hx-match-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    std::fs::write(path("image"), b"HDR\x01serial").unwrap();
+    std::fs::write(path("pattern"), b"HDR\x01xxxxxx").unwrap();
+    std::fs::write(path("mask"), b"\xff\xff\xff\xff").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "match",
+            &path("image"),
+            "--pattern",
+            &path("pattern"),
+        ])
+        .args(["--mask", &path("mask")])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("mismatch at 0x000004: 0x73 where "));
+    assert!(stdout.contains("    mask 0x000000: 0xff 0xff 0xff 0xff"));
+    std::fs::write(path("mask"), b"\xff\xff\xff\xff\0\0\0\0\0\0").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["match", &path("image"), "--pattern", &path("pattern")])
+        .args(["--mask", &path("mask")])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.ends_with(": 10 bytes compared, 6 masked out\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_bindiff() {
+    use crate::bindiff::{diff_aligned, diff_naive as diff, Change};
+    assert_eq!(diff(b"abcdef", b"abcdef", 4), []);
+    assert_eq!(
+        diff(b"abcdef", b"abXdefgh", 4),
+        [
+            Change::Changed { at: 2, len: 1 },
+            Change::Added { at: 6, len: 2 }
+        ]
+    );
+    // bytes equal by chance don't split a changed region
+    assert_eq!(
+        diff(b"abcdefgh", b"XbXdXfXh", 0),
+        [Change::Changed { at: 0, len: 7 }]
+    );
+    assert_eq!(
+        diff(b"abcdef", b"abc", 4),
+        [Change::Removed { at: 3, len: 3 }]
+    );
+    let old: Vec<u8> = (0..64).collect();
+    let mut new = vec![0xff; 16];
+    new.extend_from_slice(&old[..48]);
+    assert_eq!(
+        diff(&old, &new, 8),
+        [
+            Change::Changed { at: 0, len: 16 },
+            Change::Moved {
+                from: 0,
+                to: 16,
+                len: 48
+            },
+        ]
+    );
+    let changes = diff(&old, &new, 0);
+    assert!(!changes
+        .iter()
+        .any(|change| matches!(change, Change::Moved { .. })));
+    // an inserted byte shifts nothing after it
+    let mut state = 0x2545_f491u32;
+    let old: Vec<u8> = (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    let mut new = old.clone();
+    new.insert(2000, 0xaa);
+    assert_eq!(
+        diff_aligned(&old, &new, 64),
+        [Change::Inserted {
+            at: 2000,
+            old_at: 2000,
+            len: 1
+        }]
+    );
+    new.drain(100..110);
+    new[3000] ^= 1;
+    assert_eq!(
+        diff_aligned(&old, &new, 64),
+        [
+            Change::Deleted {
+                at: 100,
+                new_at: 100,
+                len: 10
+            },
+            Change::Inserted {
+                at: 1990,
+                old_at: 2000,
+                len: 1
+            },
+            // in step with the old input again, 9 bytes behind it
+            Change::Replaced {
+                from: 3009,
+                old_len: 1,
+                to: 3000,
+                len: 1
+            },
+        ]
+    );
+    assert_eq!(
+        diff_aligned(b"abcdef", b"abXYZf", 0),
+        [Change::Changed { at: 2, len: 3 }]
+    );
+    assert_eq!(
+        diff_aligned(b"abcdef", b"abXYZWf", 0),
+        [Change::Replaced {
+            from: 2,
+            old_len: 3,
+            to: 2,
+            len: 4
+        }]
+    );
+    // a deleted block inserted elsewhere moved
+    let mut new = old[1024..].to_vec();
+    new.extend_from_slice(&old[..1024]);
+    assert_eq!(
+        diff_aligned(&old, &new, 64),
+        [Change::Moved {
+            from: 0,
+            to: 3072,
+            len: 1024
+        }]
+    );
+    assert_eq!(diff_aligned(&old, &old, 64), []);
+}
+
+#[test]
+fn test_cli_diff() {
+    let dir = env::temp_dir().join(format!("hx-diff-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (old, new) = (dir.join("old.bin"), dir.join("new.bin"));
+    let data: Vec<u8> = (0..=255).collect();
+    let mut moved = data.clone();
+    moved.rotate_left(128);
+    fs::write(&old, &data).unwrap();
+    fs::write(&new, &moved).unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["diff", "--checksums", "--diff-algo", "naive"])
+        .args([&old, &new])
+        .assert()
+        .failure()
+        .code(1);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    let crc = crate::hash::crc32(&data[128..]);
+    assert!(stdout.starts_with(&format!(
+        "moved 128 bytes from 0x000080 to 0x000000, crc32 {crc:08x}\n"
+    )));
+    assert!(stdout.contains("moved 128 bytes from 0x000000 to 0x000080"));
+    assert!(stdout.ends_with(" changes: 2, 0 bytes changed, 256 moved, 0 added, 0 removed\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["diff", "--block", "0", "--diff-algo", "naive"])
+        .args([&old, &new])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("changed 256 bytes at 0x000000\n"));
+    let mut inserted = data.clone();
+    inserted.insert(16, 0xaa);
+    fs::write(&new, &inserted).unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd.arg("diff").args([&old, &new]).assert().failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "inserted 1 bytes at 0x000010 (old 0x000010)\n changes: 1, 0 bytes changed, 0 moved, 1 added, 0 removed\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.arg("diff").args([&old, &old]).assert().success();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_spec() {
+    use crate::spec::{evaluate, parse, Algorithm, Rule};
+    assert_eq!(crate::hash::crc32(b"123456789"), 0xcbf43926);
+    let spec = r#"
+        # header of a made up format
+        [[field]]
+        name = "magic"
+        at = 0
+        bytes = "'HX'"
+
+        [[field]]
+        name = "version"
+        at = 2
+        type = "u16"
+        min = 1
+        max = 2
+
+        [[checksum]]
+        name = "payload crc"
+        algorithm = "crc32"
+        range = "4+9"
+        at = "end-4"  # stored last
+    "#;
+    let checks = parse(spec, 17).unwrap();
+    assert_eq!(checks.len(), 3);
+    assert_eq!(checks[0].rule, Rule::Bytes(b"HX".to_vec()));
+    assert_eq!(checks[2].at, 13);
+    assert!(matches!(
+        checks[2].rule,
+        Rule::Checksum {
+            algorithm: Algorithm::Crc32,
+            range: (4, 13),
+            ..
+        }
+    ));
+    let mut data = b"HX\x02\x00123456789\x26\x39\xf4\xcb".to_vec();
+    assert!(checks.iter().all(|check| evaluate(check, &data).pass));
+    data[2] = 3;
+    data[5] = b'0';
+    let outcomes: Vec<_> = checks.iter().map(|check| evaluate(check, &data)).collect();
+    assert!(outcomes[0].pass);
+    assert_eq!(outcomes[1].found, "3");
+    assert_eq!(outcomes[1].expected, "1..=2");
+    assert!(!outcomes[2].pass);
+    assert_eq!(outcomes[2].found, "26 39 f4 cb");
+    // signed fields compare as signed
+    let checks = parse(
+        "[[field]]\nname = \"t\"\nat = 0\ntype = \"i8\"\nmin = -2",
+        1,
+    )
+    .unwrap();
+    assert!(evaluate(&checks[0], b"\xff").pass);
+    assert!(!evaluate(&checks[0], b"\xfd").pass);
+    assert!(parse("[[field]]\nname = \"x\"\nat = 0\nsize = 4", 1)
+        .unwrap_err()
+        .contains("unknown key \"size\""));
+    assert!(parse("name = \"x\"", 1).is_err());
+
+    let dir = std::env::temp_dir().join(format!("hx-check-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    std::fs::write(path("layout.toml"), spec).unwrap();
+    std::fs::write(path("good"), b"HX\x01\x00123456789\x26\x39\xf4\xcb").unwrap();
+    std::fs::write(path("bad"), &data).unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "check",
+            &path("good"),
+            "--spec",
+            &path("layout.toml"),
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.starts_with("pass magic: 0x000000 48 58\npass version: 0x000002 1\n"));
+    assert!(stdout.ends_with("  checks: 3, 0 failed\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "check", &path("bad"), "--spec", &path("layout.toml")])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("FAIL version: 0x000002 3, expected 1..=2\n"));
+    assert!(stdout.contains("FAIL payload crc: 0x00000d 26 39 f4 cb, expected "));
+    assert!(stdout.contains("\n         0x00000a: 0x37 0x38 0x39 0x26 0x39 0xf4 0xcb "));
+    assert!(stdout.ends_with("  checks: 3, 2 failed\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_quiet() {
+    let dir = std::env::temp_dir().join(format!("hx-quiet-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    std::fs::write(path("image"), b"HDR\x01").unwrap();
+    std::fs::write(path("pattern"), b"HDR\x02").unwrap();
+    std::fs::write(
+        path("layout.toml"),
+        "[[field]]\nname = \"magic\"\nat = 0\nbytes = \"'HDR'\"\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-q", "match", &path("image"), "--pattern", &path("pattern")])
+        .assert()
+        .failure()
+        .code(1);
+    assert!(assert.get_output().stdout.is_empty());
+    assert!(assert.get_output().stderr.is_empty());
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "check",
+            &path("image"),
+            "--spec",
+            &path("layout.toml"),
+            "--quiet",
+        ])
+        .assert()
+        .success();
+    assert!(assert.get_output().stdout.is_empty());
+    // errors other than a failed check are still reported
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-q",
+            "check",
+            &path("missing"),
+            "--spec",
+            &path("layout.toml"),
+        ])
+        .assert()
+        .failure()
+        .code(1);
+    assert!(!assert.get_output().stderr.is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_log_levels() {
+    use crate::log::Level;
+    assert_eq!(Level::from_counts(0, 0), Level::Report);
+    assert_eq!(Level::from_counts(2, 0), Level::Error);
+    assert_eq!(Level::from_counts(0, 1), Level::Info);
+    assert_eq!(Level::from_counts(0, 3), Level::Trace);
+    assert!(Level::Error < Level::Trace);
+
+    let records = ":0400100068656C6C47\n:02001400FFFFxx\n:00000001FF\n";
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-q", "--from", "ihex"])
+        .write_stdin(records)
+        .assert()
+        .success();
+    assert!(assert.get_output().stderr.is_empty());
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-v", "-l", "4", "--from", "ihex"])
+        .write_stdin(records)
+        .assert()
+        .success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.starts_with("mode: Dump\ninput: stdin\nlayout: 10 columns, skip 0, length 4\n"));
+    assert!(stderr.contains("line 2, offset 0x000021"));
+    assert!(stderr.contains("  timing: "));
+    assert!(!stderr.contains("read-ahead"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-vv", "-l", "4", "Cargo.toml"])
+        .assert()
+        .success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("reading Cargo.toml with 1048576 byte read-ahead blocks\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["-q", "-v", "Cargo.toml"]).assert().failure();
+}
+
+#[test]
+fn test_cli_log_json() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--log-format", "json", "--from", "ihex"])
+        .write_stdin(":0400100068656C6C47\n:02001400FFFFxx\n")
+        .assert()
+        .success();
+    let output = assert.get_output();
+    // stdout is the dump alone
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "0x000010: 0x68 0x65 0x6c 0x6c hell\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "{\"level\": \"warn\", \"message\": \"line 2, offset 0x000021: invalid hex digit 'x', skipped\"}\n\
+         {\"level\": \"report\", \"event\": \"summary\", \"bytes\": 4}\n"
+    );
+
+    let dir = std::env::temp_dir().join(format!("hx-log-json-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    std::fs::write(path("image"), b"HDR\x01").unwrap();
+    std::fs::write(path("pattern"), b"HDR\x02").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["--log-format", "json", "match", &path("image")])
+        .args(["--pattern", &path("pattern")])
+        .assert()
+        .failure();
+    let output = assert.get_output();
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("{\"level\": \"report\", \"event\": \"mismatch\", \"file\": "));
+    assert!(lines[0].ends_with(", \"at\": 3, \"expected\": 2, \"mask\": 255, \"found\": 1}"));
+    assert!(lines[1].starts_with("{\"level\": \"error\", \"message\": \""));
+    assert!(lines[1].ends_with(&format!("doesn't match {}\"}}", path("pattern"))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_frame() {
+    use crate::frame::{next, parse, Framing};
+    use crate::scalar::Endian;
+    assert_eq!(
+        parse("len-prefixed:u32le"),
+        Ok(Framing::LengthPrefixed {
+            width: 4,
+            endian: Endian::Little
+        })
+    );
+    assert_eq!(
+        parse("len-prefixed:u16"),
+        Ok(Framing::LengthPrefixed {
+            width: 2,
+            endian: Endian::Big
+        })
+    );
+    assert_eq!(
+        parse("delim:0d 0a"),
+        Ok(Framing::Delimited(vec![0x0d, 0x0a]))
+    );
+    assert!(parse("len-prefixed:u24").is_err());
+    assert!(parse("delim:").is_err());
+    assert!(parse("lines").is_err());
+
+    let framing = parse("delim:'||'").unwrap();
+    let mut input = &b"a|b||||c"[..];
+    let frame = next(&mut input, &framing).unwrap().unwrap();
+    assert_eq!((frame.body, frame.tail), (b"a|b".to_vec(), 2));
+    let frame = next(&mut input, &framing).unwrap().unwrap();
+    assert_eq!(frame.body, b"");
+    let frame = next(&mut input, &framing).unwrap().unwrap();
+    assert_eq!(frame.body, b"c");
+    assert_eq!(
+        frame.problem.as_deref(),
+        Some("no delimiter before the end")
+    );
+    assert_eq!(next(&mut input, &framing).unwrap(), None);
+    let framing = parse("len-prefixed:u16be").unwrap();
+    let frame = next(&mut &b"\x00"[..], &framing).unwrap().unwrap();
+    assert_eq!(
+        frame.problem.as_deref(),
+        Some("length cut off after 1 of 2 bytes")
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c8", "--frame", "len-prefixed:u8"])
+        .write_stdin("\x03abc\x00\x04de")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "frame 0: 0x000000, length 3\n\
+         0x000001: 0x61 0x62 0x63                          abc\n\
+         frame 1: 0x000004, length 0\n\
+         frame 2: 0x000005, length 4  ! cut off after 2 bytes\n\
+         0x000006: 0x64 0x65                               de\n\
+         \x20  bytes: 8\n\
+         \x20 frames: 3\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--frame", "len-prefixed:u8", "--split-on", "00"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_split_on() {
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0",
+            "-c4",
+            "--split-on",
+            "0x00",
+            "echo",
+            "'ab' 00 00 'cdefg' 00",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "field 0: 0x000000, 2 bytes\n\
+         0x000000: 0x61 0x62           ab\n\
+         field 1: 0x000003, 0 bytes\n\
+         field 2: 0x000004, 5 bytes\n\
+         0x000004: 0x63 0x64 0x65 0x66 cdef\n\
+         0x000008: 0x67                g\n\
+         \x20  bytes: 10\n\
+         \x20 fields: 3\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "--split-on", "'--'", "echo", "'a--b--'"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(stdout.contains("field 1: 0x000003, 1 bytes\n"));
+    assert!(stdout.ends_with(" fields: 2\n"));
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--split-on", "", "echo", "00"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_decode_tlv() {
+    use crate::decode::tlv;
+    use crate::scalar::Endian;
+    let entries = tlv(b"\x01\x00\x02ab\x02\x00\x00", 1, 2, Endian::Big);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].header, Some((1, 2)));
+    assert_eq!(entries[0].value, 3..5);
+    assert_eq!(entries[1].header, Some((2, 0)));
+    assert_eq!(entries[1].problem, None);
+    let entries = tlv(b"\x01\x00\x02\x00ab", 2, 2, Endian::Little);
+    assert_eq!(entries[0].header, Some((1, 2)));
+    let entries = tlv(b"\x07\x09ab", 1, 1, Endian::Big);
+    assert_eq!(entries[0].value, 2..4);
+    assert_eq!(
+        entries[0].problem.as_deref(),
+        Some("length runs 7 bytes past the end")
+    );
+    let entries = tlv(b"\x01\x01a\x02", 1, 1, Endian::Big);
+    assert_eq!(entries[1].header, None);
+    assert_eq!(
+        entries[1].problem.as_deref(),
+        Some("header cut off after 1 of 2 bytes")
+    );
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "-c4", "--decode", "tlv", "--len-size", "2"])
+        .args(["echo", "01 00 02 'ab' 02 00 09 'c'"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "0x000000: type 0x01, length 2\n\
+         0x000003: 0x61 0x62           ab\n\
+         0x000005: type 0x02, length 9  ! length runs 8 bytes past the end\n\
+         0x000008: 0x63                c\n\
+         \x20  bytes: 9\n\
+         \x20entries: 2\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--decode", "tlv", "--type-size", "9", "echo", "00"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_decode_leb128() {
+    use crate::decode::leb128;
+    let varints = leb128(b"\xe5\x8e\x26\x7f\x80\x7f", 0, 3);
+    assert_eq!(varints.len(), 3);
+    assert_eq!((varints[0].offset, varints[0].len), (0, 3));
+    assert_eq!(varints[0].unsigned, 624485);
+    assert_eq!(varints[0].signed, 624485);
+    assert_eq!((varints[1].unsigned, varints[1].signed), (127, -1));
+    assert_eq!((varints[2].unsigned, varints[2].signed), (16256, -128));
+    let varints = leb128(b"\x00\x80\x80", 1, 5);
+    assert_eq!(varints.len(), 1);
+    assert_eq!(varints[0].len, 2);
+    assert_eq!(
+        varints[0].problem.as_deref(),
+        Some("cut off by the end of the input")
+    );
+    let varints = leb128(&[0xff; 12], 0, 1);
+    assert_eq!(varints[0].len, 10);
+    assert!(varints[0].problem.is_some());
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args([
+            "-t0", "-c8", "--decode", "leb128", "--at", "2", "--count", "2",
+        ])
+        .args(["echo", "00 00 e5 8e 26 7f 02 aa"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(
+        stdout,
+        "0x000002-0x000004: 0xe5 0x8e 0x26 unsigned 624485, signed 624485\n\
+         0x000005-0x000005: 0x7f unsigned 127, signed -1\n\
+         \n\
+         0x000000: 0x00 0x00 0xe5 0x8e 0x26 0x7f 0x02 0xaa ....&...  # 0x000002 624485, 0x000005 127\n\
+         \x20  bytes: 8\n\
+         \x20varints: 2\n"
+    );
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--decode", "leb128", "--at", "9", "echo", "00"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_group() {
+    let group = Group {
+        bytes: 4,
+        endian: Endian::Big,
+    };
+    assert_eq!(
+        group.format_word(Format::LowerHex, b"\xde\xad\xbe\xef", true),
+        "0xdeadbeef"
+    );
+    assert_eq!(
+        group.format_word(Format::LowerHex, b"\xde\xad", false),
+        "    dead"
+    );
+    assert_eq!(group.cell_width(Format::Octal, true), 13);
+
+    let dump = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t0", "-c4"])
+            .args(args)
+            .write_stdin("hello")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        dump(&["--group", "2"]),
+        "0x000000: 0x6568 0x6c6c hell\n0x000004:     6f        o\n   bytes: 5\n"
+    );
+    assert_eq!(
+        dump(&["--group", "4", "--endian", "big"]),
+        "0x000000: 0x68656c6c hell\n0x000004:         6f o\n   bytes: 5\n"
+    );
+    // --seek is --skip
+    assert_eq!(dump(&["--seek", "2"]), dump(&["-s", "2"]));
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["-c3", "--group", "2"])
+        .write_stdin("hello")
+        .assert()
+        .failure();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["-fe", "--group", "2"])
+        .write_stdin("hello")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_detect_encoding() {
+    use crate::encoding::{detect, Encoding};
+    let best = |data: &[u8]| {
+        detect(data)
+            .first()
+            .map(|guess| (guess.encoding, guess.bom))
+    };
+    assert_eq!(best(b"\xef\xbb\xbfplain"), Some((Encoding::Utf8, true)));
+    assert_eq!(best(b"\xfe\xff\0h"), Some((Encoding::Utf16Be, true)));
+    assert_eq!(best(b"caf\xc3\xa9"), Some((Encoding::Utf8, false)));
+    assert_eq!(best(b"\0h\0i\0!"), Some((Encoding::Utf16Be, false)));
+    assert_eq!(best(b"\x82\xa0\x82\xa2"), Some((Encoding::ShiftJis, false)));
+    assert_eq!(best(b"caf\xe9 cr\xe8me"), Some((Encoding::Latin1, false)));
+    assert_eq!(best(b""), None);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["-t0", "--detect-encoding"])
+        .write_stdin("h\0i\0")
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "encoding: utf-16le, 100%\nencoding: utf-8, 40%\nencoding: latin-1, 25%\n\n\
+         0x000000: 0x68 0x00 0x69 0x00                               hi\n   bytes: 4\n"
+    );
+}
+
+#[test]
+fn test_cli_output_formats() {
+    let output = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        let assert = cmd
+            .args(["-t1"])
+            .args(args)
+            .write_stdin("hello, world\n\"")
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert_eq!(
+        output(&["-c8", "--output", "json"]),
+        "{\"offset\": 0, \"bytes\": 8, \"hex\": \"68656c6c6f2c2077\", \"ascii\": \"hello, w\", \"notes\": []}\n\
+         {\"offset\": 8, \"bytes\": 6, \"hex\": \"6f726c640a22\", \"ascii\": \"orld.\\\"\", \"notes\": []}\n"
+    );
+    assert_eq!(
+        output(&["--output", "plain"]),
+        "68656c6c6f2c20776f726c640a22\n"
+    );
+    assert_eq!(output(&["-c4", "-s10", "--output", "plain"]), "6c640a22\n");
+    // html spans in the dump colors, escaped, without a summary
+    assert_eq!(
+        output(&["-c2", "-s12", "--output", "html"]),
+        "<pre class=\"hx\">\n<span class=\"hx-offset\">0x00000c</span>: \
+         <span class=\"hx-cell\" style=\"color:#00ff00\">0x0a</span> \
+         <span class=\"hx-cell\" style=\"color:#00af00\">0x22</span> \
+         <span class=\"hx-char\" style=\"color:#00ff00\">.</span>\
+         <span class=\"hx-char\" style=\"color:#00af00\">&quot;</span>\n</pre>\n"
+    );
+    // the human dump is still the default
+    assert_eq!(output(&["--output", "dump"]), output(&[]));
+}
+
+#[test]
+fn test_cli_output_machine_text() {
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.args(args).arg("tests/files/lorem.md").assert()
+    };
+    // refused before anything is written, so every line stays json or plain hex
+    for format in ["json", "plain"] {
+        for text in [
+            "--timestamps",
+            "--timestamps=delta",
+            "--header",
+            "--mark-truncated",
+        ] {
+            let assert = run(&["-l16", "--output", format, text]).code(2);
+            assert!(assert.get_output().stdout.is_empty());
+            let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+            assert!(
+                stderr.contains("can't be combined with --output"),
+                "{stderr}"
+            );
+        }
+    }
+    let assert = run(&["--output", "json", "-c8"]).success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert_eq!(stdout.lines().count(), 18);
+    for line in stdout.lines() {
+        assert!(
+            line.starts_with("{\"offset\": ") && line.ends_with("\"notes\": []}"),
+            "{line}"
+        );
+    }
+}
+
+#[test]
+fn test_text_report() {
+    use crate::encoding::{Encoding, TextReport};
+    let mut report = TextReport::default();
+    // a CRLF split across chunks is still one
+    report.feed(b"\xff\xfeab\r");
+    report.feed(b"\n\r\rlonger\n\0");
+    report.finish();
+    assert_eq!(report.bom, Some(Encoding::Utf16Le));
+    assert_eq!((report.lf, report.crlf, report.cr), (1, 1, 2));
+    assert_eq!(report.lines, 5);
+    assert_eq!((report.longest, report.longest_line), (6, 4));
+    assert_eq!((report.nuls, report.first_nul), (1, Some(15)));
+    assert_eq!(report.bytes, 16);
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .arg("--text-report")
+        .write_stdin("one\r\ntwo is longer\nthree\r\0x")
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "     bom: none\n endings: lf 1, crlf 1, cr 1, mixed\n   lines: 4\n \
+         longest: 13 bytes, line 2\n    nuls: 1, first at 0x000019\n   bytes: 27\n"
+    );
+}
+
+#[test]
+fn test_cli_expect() {
+    use crate::textdiff::unified;
+    assert_eq!(unified("a\nb\n", "a\nb\n", "old", "new"), None);
+    assert_eq!(
+        unified("a\nb\nc\n", "a\nx\nc\nd", "old", "new").unwrap(),
+        "--- old\n+++ new\n@@ -1,3 +1,4 @@\n a\n-b\n+x\n c\n+d\n\\ No newline at end of file\n"
+    );
+    assert_eq!(
+        unified(
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n",
+            "1\n2\n3\n4\n5\n6\n7\n8\n",
+            "old",
+            "new"
+        )
+        .unwrap(),
+        "--- old\n+++ new\n@@ -6,4 +6,3 @@\n 6\n 7\n 8\n-9\n"
+    );
+
+    let golden = env::temp_dir().join(format!("hx-expect-{}", std::process::id()));
+    std::fs::write(&golden, "0x000000: 0x69 0x6c 0x0a il.\n   bytes: 3\n").unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["tests/files/tiny.txt", "-c3", "--expect"])
+        .arg(&golden)
+        .assert()
+        .success();
+    assert!(assert.get_output().stdout.is_empty());
+
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    let assert = cmd
+        .args(["tests/files/tiny.txt", "-c2", "--expect"])
+        .arg(&golden)
+        .assert()
+        .failure();
+    let name = golden.display();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        format!(
+            "--- {name}\n+++ hx output\n@@ -1,2 +1,3 @@\n-0x000000: 0x69 0x6c 0x0a il.\n\
+             +0x000000: 0x69 0x6c il\n+0x000002: 0x0a      .\n    bytes: 3\n"
+        )
+    );
+    std::fs::remove_file(&golden).unwrap();
+}
+
+#[test]
+fn test_dirs() {
+    use crate::dirs::{named_file, Dirs, Platform};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+    let env = |vars: &'static [(&str, &str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    };
+    let home = Path::new("/home/me");
+    let unix = Dirs::resolve(Platform::Unix, env(&[("HOME", "/home/me")]));
+    assert_eq!(unix.config, Some(home.join(".config/hx")));
+    assert_eq!(unix.data, Some(home.join(".local/share/hx")));
+    assert_eq!(unix.state, Some(home.join(".local/state/hx")));
+    assert_eq!(unix.sessions(), Some(home.join(".local/state/hx/sessions")));
+    assert_eq!(
+        unix.bookmarks(),
+        Some(home.join(".local/share/hx/bookmarks"))
+    );
+    let xdg = Dirs::resolve(
+        Platform::Unix,
+        env(&[
+            ("HOME", "/home/me"),
+            ("XDG_CONFIG_HOME", "/cfg"),
+            ("HX_STATE_DIR", "/st"),
+        ]),
+    );
+    assert_eq!(xdg.config, Some(Path::new("/cfg").join("hx")));
+    assert_eq!(xdg.data, Some(home.join(".local/share/hx")));
+    assert_eq!(xdg.state, Some(PathBuf::from("/st")));
+
+    let mac = Dirs::resolve(Platform::MacOs, env(&[("HOME", "/Users/me")]));
+    let support = Path::new("/Users/me").join("Library/Application Support/hx");
+    assert_eq!(mac.config, Some(support.clone()));
+    assert_eq!(mac.data, Some(support.clone()));
+    assert_eq!(mac.state, Some(support.join("state")));
+
+    let windows = Dirs::resolve(
+        Platform::Windows,
+        env(&[("APPDATA", "C:\\Roaming"), ("USERPROFILE", "C:\\Users\\me")]),
+    );
+    assert_eq!(windows.config, Some(Path::new("C:\\Roaming").join("hx")));
+    assert_eq!(windows.data, windows.config);
+    assert_eq!(
+        windows.state,
+        Some(Path::new("C:\\Users\\me").join("AppData\\Local\\hx"))
+    );
+
+    let nowhere = Dirs::resolve(Platform::Unix, env(&[]));
+    assert_eq!(nowhere.state, None);
+    assert_eq!(nowhere.sessions(), None);
+
+    let dir = Some(PathBuf::from("/s"));
+    assert_eq!(
+        named_file("@fw", dir.clone(), "hxs", "HX_STATE_DIR"),
+        Ok(Path::new("/s").join("fw.hxs"))
+    );
+    assert_eq!(
+        named_file("fw", dir.clone(), "hxs", "HX_STATE_DIR"),
+        Ok(PathBuf::from("fw"))
+    );
+    for bad in ["@", "@a/b", "@..", "@a\\b"] {
+        assert!(named_file(bad, dir.clone(), "hxs", "HX_STATE_DIR").is_err());
+    }
+    assert!(named_file("@fw", None, "hxs", "HX_STATE_DIR")
+        .unwrap_err()
+        .contains("HX_STATE_DIR"));
+}
+
+#[test]
+fn test_cli_paths() {
+    let root = env::temp_dir().join(format!("hx-paths-{}", std::process::id()));
+    let (config, data, state) = (root.join("config"), root.join("data"), root.join("state"));
+    let hx = || {
+        let mut cmd = Command::cargo_bin("hx").unwrap();
+        cmd.env("HX_CONFIG_DIR", &config)
+            .env("HX_DATA_DIR", &data)
+            .env("HX_STATE_DIR", &state)
+            .env("HX_TEMP_DIR", &root);
+        cmd
+    };
+    let assert = hx().arg("paths").assert().success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        format!(
+            "   config: {}  ($HX_CONFIG_DIR)\n     data: {}  ($HX_DATA_DIR)\n    \
+             state: {}  ($HX_STATE_DIR)\n     temp: {}  ($HX_TEMP_DIR, --temp-dir)\nbookmarks: {}  (<name>.marks for @name)\n \
+             sessions: {}  (<name>.hxs for @name)\n continue: {}  (a file per input)\n",
+            config.display(),
+            data.display(),
+            state.display(),
+            root.display(),
+            data.join("bookmarks").display(),
+            state.join("sessions").display(),
+            state.display(),
+        )
+    );
+
+    // @name files land in the data and state directories
+    hx().args(["bookmark", "add", "@fw", "3", "title"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(data.join("bookmarks").join("fw.marks")).unwrap(),
+        "0x000003 title\n"
+    );
+    let assert = hx()
+        .args(["-c8", "-l8", "-t0", "--bookmarks", "@fw", "--session", "@s"])
+        .arg("tests/files/lorem.md")
+        .assert()
+        .success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stdout).contains("# 0x000003 title"));
+    assert!(state.join("sessions").join("s.hxs").exists());
+    hx().args(["--bookmarks", "@../fw", "tests/files/lorem.md"])
+        .assert()
+        .failure();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_temp_files() {
+    use crate::temp::{write_atomically, TempFile};
+    let dir = env::temp_dir().join(format!("hx-temp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let listing = || std::fs::read_dir(&dir).unwrap().count();
+
+    let (first, _) = TempFile::create(&dir, "hx-test").unwrap();
+    let (second, _) = TempFile::create(&dir, "hx-test").unwrap();
+    assert_ne!(first.path, second.path);
+    assert_eq!(listing(), 2);
+    drop((first, second));
+    assert_eq!(listing(), 0);
+
+    let target = dir.join("marks");
+    write_atomically(&target, b"old\n").unwrap();
+    let (temp, mut file) = TempFile::beside(&target).unwrap();
+    assert!(temp
+        .path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with(".marks.hx-"));
+    file.write_all(b"new\n").unwrap();
+    // dropped before it's renamed, the old file stays as it was
+    drop(temp);
+    assert_eq!(std::fs::read(&target).unwrap(), b"old\n");
+    write_atomically(&target, b"new\n").unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), b"new\n");
+    assert_eq!(listing(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_temp_dir() {
+    let dir = env::temp_dir().join(format!("hx-temp-dir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--two-pass", "-t0", "-c3", "--temp-dir"])
+        .arg(&dir)
+        .write_stdin("abc")
+        .assert()
+        .success()
+        .stdout("0x000000: 0x61 0x62 0x63 abc\n   bytes: 3\n");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    // only --two-pass makes a temporary file
+    let mut cmd = Command::cargo_bin("hx").unwrap();
+    cmd.args(["--temp-dir"])
+        .arg(&dir)
+        .write_stdin("abc")
+        .assert()
+        .failure();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_temp_dir_interrupted() {
+    use std::process::Stdio;
+    let dir = env::temp_dir().join(format!("hx-temp-signal-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("hx"))
+        .args(["--two-pass", "--temp-dir"])
+        .arg(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    // the stream stays open, so hx waits in the copy with its file made
+    child.stdin.as_mut().unwrap().write_all(b"abc").unwrap();
+    let made = (0..200).any(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::read_dir(&dir).unwrap().count() == 1
+    });
+    assert!(made);
+    std::process::Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(!child.wait().unwrap().success());
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cli_interrupted() {
+    use std::process::Stdio;
+    let interrupt = |args: &[&str]| {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("hx"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // the pipe stays open, hx reads the bytes and waits for more
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(b"abcd").unwrap();
+        let proc = Path::new("/proc").join(child.id().to_string());
+        let waiting = (0..500).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let status = std::fs::read_to_string(proc.join("status")).unwrap_or_default();
+            let caught = status
+                .lines()
+                .find_map(|line| line.strip_prefix("SigCgt:"))
+                .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+                .is_some_and(|mask| mask & 0x2 != 0);
+            let wchan = std::fs::read_to_string(proc.join("wchan")).unwrap_or_default();
+            caught && wchan.contains("pipe_read")
+        });
+        assert!(waiting);
+        std::process::Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(EXIT_INTERRUPTED), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("interrupted after"));
+        drop(stdin);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(
+        interrupt(&["-t0", "-c4"]),
+        "0x000000: 0x61 0x62 0x63 0x64 abcd\n   bytes: 4\n"
+    );
+    // modes holding the input stop their scans too, with a partial summary
+    for (args, count) in [
+        (&["--find-int", "0"][..], " matches: "),
+        (&["--find", "'b'"], " matches: "),
+        (&["--rle", "4"], "    runs: "),
+        (&["--dedupe-scan", "2"], " repeats: "),
+        (&["--split-on", "'b'"], "  fields: "),
+    ] {
+        let stdout = interrupt(&[&["-t0"][..], args].concat());
+        assert!(stdout.contains("   bytes: 4\n"), "{args:?}: {stdout}");
+        assert!(stdout.contains(count), "{args:?}: {stdout}");
+    }
+}
@@ -0,0 +1,162 @@
+//! unified text diff of a rendered dump against the expected one, for
+//! `--expect`
+
+/// Lines of context around each change
+const CONTEXT: usize = 3;
+
+/// One line of the diff: kept, removed from the expected text or added by
+/// the output
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Op<'a> {
+    Keep(&'a str),
+    Remove(&'a str),
+    Add(&'a str),
+}
+
+/// Lines the two texts have in common at the start and, after those, at
+/// the end.
+fn common_ends(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, suffix)
+}
+
+/// Bytes of the table `unified` builds to diff two texts, for checking
+/// them against a memory budget first.
+///
+/// # Arguments
+///
+/// * `old` - expected text.
+/// * `new` - actual text.
+pub fn table_bytes(old: &str, new: &str) -> u64 {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let (prefix, suffix) = common_ends(&old, &new);
+    let rows = (old.len() - prefix - suffix + 1) as u64;
+    let columns = (new.len() - prefix - suffix + 1) as u64;
+    rows * columns * std::mem::size_of::<u32>() as u64
+}
+
+/// Line by line edit script from `old` to `new`, the longest common
+/// subsequence of the lines kept. Common leading and trailing lines are
+/// matched first, so a few changes in a long dump stay cheap.
+fn edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let (prefix, suffix) = common_ends(old, new);
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    // common[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = match a[i] == b[j] {
+                true => common[(i + 1) * width + j + 1] + 1,
+                false => common[(i + 1) * width + j].max(common[i * width + j + 1]),
+            };
+        }
+    }
+    let mut ops: Vec<Op> = old[..prefix].iter().map(|line| Op::Keep(line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push(Op::Keep(a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len()
+            || (i < a.len() && common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            ops.push(Op::Remove(a[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Add(b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| Op::Keep(line)));
+    ops
+}
+
+/// Write one diff line, marking a last line without a newline the way
+/// `diff` does.
+fn push_line(diff: &mut String, marker: char, line: &str) {
+    diff.push(marker);
+    diff.push_str(line);
+    if !line.ends_with('\n') {
+        diff.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// Unified diff of two texts with three lines of context, None when they
+/// are the same.
+///
+/// # Arguments
+///
+/// * `old` - expected text.
+/// * `new` - actual text.
+/// * `old_name` - name of the expected text in the `---` header.
+/// * `new_name` - name of the actual text in the `+++` header.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = edits(&old_lines, &new_lines);
+    let changed: Vec<usize> = (0..ops.len())
+        .filter(|i| !matches!(ops[*i], Op::Keep(_)))
+        .collect();
+    // runs of ops around changes, merged when their context would touch
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changed {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut diff = format!("--- {old_name}\n+++ {new_name}\n");
+    // line numbers, from 0, of the op each hunk starts at
+    let (mut old_at, mut new_at, mut done) = (0, 0, 0);
+    for (start, end) in hunks {
+        for op in &ops[done..start] {
+            match op {
+                Op::Keep(_) => (old_at, new_at) = (old_at + 1, new_at + 1),
+                Op::Remove(_) => old_at += 1,
+                Op::Add(_) => new_at += 1,
+            }
+        }
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| !matches!(op, Op::Add(_))).count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Remove(_)))
+            .count();
+        // an empty side is numbered by the line before it
+        let first = |at: usize, count: usize| match count {
+            0 => at,
+            _ => at + 1,
+        };
+        diff.push_str(&format!(
+            "@@ -{},{old_count} +{},{new_count} @@\n",
+            first(old_at, old_count),
+            first(new_at, new_count)
+        ));
+        for op in hunk {
+            match op {
+                Op::Keep(line) => push_line(&mut diff, ' ', line),
+                Op::Remove(line) => push_line(&mut diff, '-', line),
+                Op::Add(line) => push_line(&mut diff, '+', line),
+            }
+        }
+        old_at += old_count;
+        new_at += new_count;
+        done = end;
+    }
+    Some(diff)
+}
@@ -0,0 +1,211 @@
+//! byte transforms applied to the input before it is dumped
+//!
+//! Transforms run in command line order on the input as it is read, keyed
+//! by input offset, so `--skip` and `--range` see transformed bytes at the
+//! same offsets as the original.
+
+use std::io::{self, Read};
+
+/// A transform of input bytes
+pub trait Transform {
+    /// Transform bytes in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - bytes to transform, a whole number of blocks unless the input ended.
+    /// * `offset` - input offset of the first byte.
+    fn apply(&mut self, data: &mut [u8], offset: u64);
+
+    /// Bytes the transform works on at a time; `apply` gets data starting
+    /// on a multiple of this.
+    fn block(&self) -> usize {
+        1
+    }
+}
+
+/// XOR with a repeating key, the key byte picked by input offset
+#[derive(Clone, Debug)]
+pub struct Xor {
+    key: Vec<u8>,
+}
+
+impl Transform for Xor {
+    fn apply(&mut self, data: &mut [u8], offset: u64) {
+        let len = self.key.len() as u64;
+        for (i, b) in data.iter_mut().enumerate() {
+            *b ^= self.key[((offset + i as u64) % len) as usize];
+        }
+    }
+}
+
+/// Rotate ascii letters through the alphabet, e.g. rot13
+#[derive(Clone, Debug)]
+pub struct Rot {
+    by: u8,
+}
+
+impl Transform for Rot {
+    fn apply(&mut self, data: &mut [u8], _offset: u64) {
+        for b in data.iter_mut() {
+            let base = match b {
+                b'a'..=b'z' => b'a',
+                b'A'..=b'Z' => b'A',
+                _ => continue,
+            };
+            *b = base + (*b - base + self.by) % 26;
+        }
+    }
+}
+
+/// Reverse the order of bytes in every group, swapping the endianness of
+/// 2, 4 or 8 byte words. A short group at the end of the input is left as is.
+#[derive(Clone, Debug)]
+pub struct ReverseBytes {
+    width: usize,
+}
+
+impl Transform for ReverseBytes {
+    fn apply(&mut self, data: &mut [u8], _offset: u64) {
+        for group in data.chunks_exact_mut(self.width) {
+            group.reverse();
+        }
+    }
+
+    fn block(&self) -> usize {
+        self.width
+    }
+}
+
+/// Overwrite byte ranges with a fill byte, so dumps can be shared without
+/// what's in them
+#[derive(Clone, Debug)]
+pub struct Redact {
+    /// input offset ranges, start inclusive, end exclusive
+    ranges: Vec<(u64, u64)>,
+    fill: u8,
+}
+
+impl Transform for Redact {
+    fn apply(&mut self, data: &mut [u8], offset: u64) {
+        let end = offset + data.len() as u64;
+        for (start, stop) in &self.ranges {
+            let from = (*start).max(offset);
+            let to = (*stop).min(end);
+            if from < to {
+                data[(from - offset) as usize..(to - offset) as usize].fill(self.fill);
+            }
+        }
+    }
+}
+
+/// A transform as given on the command line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `--xor <key>`
+    Xor(Vec<u8>),
+    /// `--rot <n>`
+    Rot(u8),
+    /// `--reverse-bytes <width>`
+    ReverseBytes(usize),
+    /// `--redact <ranges>`, always last
+    Redact {
+        /// input offset ranges, start inclusive, end exclusive
+        ranges: Vec<(u64, u64)>,
+        /// byte written over them
+        fill: u8,
+    },
+}
+
+/// Step implementation
+impl Step {
+    /// The transform this step stands for.
+    pub fn build(&self) -> Box<dyn Transform> {
+        match self {
+            Step::Xor(key) => Box::new(Xor { key: key.clone() }),
+            Step::Rot(by) => Box::new(Rot { by: by % 26 }),
+            Step::ReverseBytes(width) => Box::new(ReverseBytes { width: *width }),
+            Step::Redact { ranges, fill } => Box::new(Redact {
+                ranges: ranges.clone(),
+                fill: *fill,
+            }),
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/// Reader applying a chain of transforms to everything read through it
+pub struct TransformReader<R: Read> {
+    inner: R,
+    transforms: Vec<Box<dyn Transform>>,
+    /// transformed bytes not yet handed out
+    buf: Vec<u8>,
+    pos: usize,
+    /// input offset of the first byte in `buf`
+    offset: u64,
+    /// every transform's block divides this
+    block: usize,
+}
+
+/// TransformReader implementation
+impl<R: Read> TransformReader<R> {
+    /// TransformReader constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - input, read from the start.
+    /// * `steps` - transforms to apply, in order.
+    pub fn new(inner: R, steps: &[Step]) -> TransformReader<R> {
+        let transforms: Vec<Box<dyn Transform>> = steps.iter().map(Step::build).collect();
+        let block = transforms
+            .iter()
+            .fold(1, |lcm, t| lcm / gcd(lcm, t.block()) * t.block());
+        TransformReader {
+            inner,
+            transforms,
+            buf: Vec::new(),
+            pos: 0,
+            offset: 0,
+            block,
+        }
+    }
+
+    /// Read and transform the next whole blocks, as many as arrive in one
+    /// read so live streams aren't held back; a partial block is only
+    /// left at the end of the input.
+    fn fill(&mut self) -> io::Result<()> {
+        self.offset += self.buf.len() as u64;
+        self.pos = 0;
+        self.buf.resize((8192 / self.block).max(1) * self.block, 0);
+        let mut filled = 0;
+        loop {
+            let n = self.inner.read(&mut self.buf[filled..])?;
+            filled += n;
+            if n == 0 || filled.is_multiple_of(self.block) {
+                break;
+            }
+        }
+        self.buf.truncate(filled);
+        for transform in self.transforms.iter_mut() {
+            transform.apply(&mut self.buf, self.offset);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for TransformReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            self.fill()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
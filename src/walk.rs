@@ -0,0 +1,138 @@
+//! recursive directory walking with glob filtering
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Match `name` against a glob `pattern` supporting `*`, `?`, `[abc]`,
+/// `[a-z]` and `[!abc]`.
+///
+/// # Arguments
+///
+/// * `pattern` - glob pattern.
+/// * `name` - text to match.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    star = Some((p, n));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, next)) = match_class(&pattern, p, name[n]) {
+                        if matched {
+                            p = next;
+                            n += 1;
+                            continue;
+                        }
+                    } else if name[n] == '[' {
+                        // unterminated class matches a literal bracket
+                        p += 1;
+                        n += 1;
+                        continue;
+                    }
+                }
+                c if c == name[n] => {
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        // mismatch, let the last `*` swallow one more character
+        match star {
+            Some((star_p, star_n)) => {
+                p = star_p + 1;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Match a character against the bracket class starting at `pattern[start]`,
+/// returning whether it matched and the index after the class, or None when
+/// the class is unterminated.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() && (first || pattern[i] != ']') {
+        first = false;
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            matched |= pattern[i] <= c && c <= pattern[i + 2];
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+    }
+    match i < pattern.len() {
+        true => Some((matched != negate, i + 1)),
+        false => None,
+    }
+}
+
+/// Walk `dir` recursively, returning the files whose name matches `glob`,
+/// in sorted order. A glob containing `/` is matched against the path
+/// relative to `dir` instead of the file name. Symlinked directories are
+/// not followed; unreadable directories are reported to `warn` and skipped.
+///
+/// # Arguments
+///
+/// * `dir` - directory to walk.
+/// * `glob` - glob pattern selecting files.
+/// * `warn` - called for every directory which could not be read.
+pub fn walk(dir: &Path, glob: &str, warn: &mut dyn FnMut(&Path, io::Error)) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn(&current, e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+                let candidate = match glob.contains('/') {
+                    true => path.strip_prefix(dir).unwrap_or(&path).to_string_lossy(),
+                    false => entry.file_name().to_string_lossy().into_owned().into(),
+                };
+                if glob_match(glob, &candidate) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}